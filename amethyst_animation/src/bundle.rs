@@ -1,6 +1,6 @@
 use crate::{
     resources::AnimationSampling,
-    skinning::VertexSkinningSystemDesc,
+    skinning::{SkeletonDebugSystem, VertexSkinningSystemDesc},
     systems::{
         AnimationControlSystemDesc, AnimationProcessor, SamplerInterpolationSystem,
         SamplerProcessor,
@@ -15,8 +15,8 @@ use std::{hash::Hash, marker};
 
 /// Bundle for vertex skinning
 ///
-/// This registers `VertexSkinningSystem`.
-/// Note that the user must make sure this system runs after `TransformSystem`
+/// This registers `VertexSkinningSystem` and `SkeletonDebugSystem`.
+/// Note that the user must make sure these systems run after `TransformSystem`
 #[derive(Default, Debug)]
 pub struct VertexSkinningBundle<'a> {
     dep: &'a [&'a str],
@@ -28,7 +28,7 @@ impl<'a> VertexSkinningBundle<'a> {
         Default::default()
     }
 
-    /// Set dependencies for the `VertexSkinningSystem`
+    /// Set dependencies for the `VertexSkinningSystem` and `SkeletonDebugSystem`
     pub fn with_dep(mut self, dep: &'a [&'a str]) -> Self {
         self.dep = dep;
         self
@@ -46,6 +46,11 @@ impl<'a, 'b, 'c> SystemBundle<'a, 'b> for VertexSkinningBundle<'c> {
             "vertex_skinning_system",
             self.dep,
         );
+        builder.add(
+            SkeletonDebugSystem::new(),
+            "skeleton_debug_system",
+            self.dep,
+        );
         Ok(())
     }
 }
@@ -59,13 +59,17 @@ pub use minterpolate::{InterpolationFunction, InterpolationPrimitive};
 pub use self::{
     bundle::{AnimationBundle, SamplingBundle, VertexSkinningBundle},
     material::{MaterialChannel, MaterialPrimitive},
+    morph::MorphWeightsChannel,
     prefab::{AnimatablePrefab, AnimationHierarchyPrefab, AnimationPrefab, AnimationSetPrefab},
     resources::{
         Animation, AnimationCommand, AnimationControl, AnimationControlSet, AnimationHierarchy,
         AnimationSampling, AnimationSet, ApplyData, BlendMethod, ControlState, DeferStartRelation,
         EndControl, RestState, Sampler, SamplerControl, SamplerControlSet, StepDirection,
     },
-    skinning::{Joint, JointPrefab, Skin, SkinPrefab, SkinnablePrefab, VertexSkinningSystem},
+    skinning::{
+        BoneShape, Joint, JointPrefab, SkeletonDebug, SkeletonDebugSystem, Skin, SkinPrefab,
+        SkinnablePrefab, VertexSkinningSystem,
+    },
     sprite::{SpriteRenderChannel, SpriteRenderPrimitive},
     systems::{
         AnimationControlSystem, AnimationProcessor, SamplerInterpolationSystem, SamplerProcessor,
@@ -77,6 +81,7 @@ pub use self::{
 
 mod bundle;
 mod material;
+mod morph;
 mod prefab;
 mod resources;
 mod skinning;
@@ -0,0 +1,46 @@
+use amethyst_rendy::morph::MorphWeights;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    resources::{AnimationSampling, ApplyData, BlendMethod},
+    util::SamplerPrimitive,
+};
+
+/// Channels that can be animated on `MorphWeights`
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub enum MorphWeightsChannel {
+    /// The weights of all morph targets
+    Weights,
+}
+
+impl<'a> ApplyData<'a> for MorphWeights {
+    type ApplyData = ();
+}
+
+impl AnimationSampling for MorphWeights {
+    type Primitive = SamplerPrimitive<f32>;
+    type Channel = MorphWeightsChannel;
+
+    fn apply_sample(&mut self, channel: &Self::Channel, data: &SamplerPrimitive<f32>, _: &()) {
+        match (channel, *data) {
+            (MorphWeightsChannel::Weights, SamplerPrimitive::Vec4(d)) => self.0 = d,
+            _ => panic!("Attempt to apply invalid sample to MorphWeights"),
+        }
+    }
+
+    fn current_sample(&self, channel: &Self::Channel, _: &()) -> SamplerPrimitive<f32> {
+        match channel {
+            MorphWeightsChannel::Weights => SamplerPrimitive::Vec4(self.0),
+        }
+    }
+
+    fn default_primitive(channel: &Self::Channel) -> Self::Primitive {
+        match channel {
+            MorphWeightsChannel::Weights => SamplerPrimitive::Vec4([0.0; 4]),
+        }
+    }
+
+    fn blend_method(&self, _: &Self::Channel) -> Option<BlendMethod> {
+        Some(BlendMethod::Linear)
+    }
+}
@@ -5,13 +5,13 @@ use amethyst_assets::{PrefabData, ProgressCounter};
 use amethyst_core::{
     ecs::{
         hibitset::BitSet,
-        prelude::{Component, DenseVecStorage, Entity, WriteStorage},
+        prelude::{Component, DenseVecStorage, Entity, HashMapStorage, WriteStorage},
     },
     math::Matrix4,
 };
 use amethyst_derive::PrefabData;
 use amethyst_error::Error;
-use amethyst_rendy::skinning::JointTransformsPrefab;
+use amethyst_rendy::{palette::Srgba, skinning::JointTransformsPrefab};
 
 /// Joint, attach to an entity with a `Transform`
 #[derive(Debug, Clone)]
@@ -61,6 +61,61 @@ impl Component for Skin {
     type Storage = DenseVecStorage<Self>;
 }
 
+/// Shape of the bones drawn by `SkeletonDebugSystem`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BoneShape {
+    /// A line from the parent joint to the joint.
+    Line,
+    /// An octahedron from the parent joint to the joint, widest near the parent joint, so
+    /// the direction of the bone can be told apart.
+    Octahedron,
+}
+
+/// Draw the skeleton of the skin used by the mesh of this entity with `DebugLines`, for
+/// diagnosing skinning and retargeting issues.
+///
+/// Attach it to an entity with `JointTransforms`. A bone is drawn from every joint of the skin
+/// whose parent is also a joint of the skin to that parent, at the global transforms of the
+/// joints. The lines are depth tested unless `DebugLines` are drawn always on top, so
+/// skeletons hidden inside their mesh need that or the mesh hidden.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SkeletonDebug {
+    /// Draw the skeleton, so it can be toggled without removing the component.
+    pub enabled: bool,
+    /// Shape of the bones.
+    pub shape: BoneShape,
+    /// Color of the bones.
+    pub color: Srgba,
+}
+
+impl Default for SkeletonDebug {
+    fn default() -> Self {
+        SkeletonDebug {
+            enabled: true,
+            shape: BoneShape::Octahedron,
+            color: Srgba::new(1.0, 0.8, 0.2, 1.0),
+        }
+    }
+}
+
+impl SkeletonDebug {
+    /// Draw the skeleton with bones of the given shape.
+    pub fn with_shape(mut self, shape: BoneShape) -> Self {
+        self.shape = shape;
+        self
+    }
+
+    /// Draw the skeleton in the given color.
+    pub fn with_color(mut self, color: Srgba) -> Self {
+        self.color = color;
+        self
+    }
+}
+
+impl Component for SkeletonDebug {
+    type Storage = HashMapStorage<Self>;
+}
+
 /// `PrefabData` for loading `Joint`s
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct JointPrefab {
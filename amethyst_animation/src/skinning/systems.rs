@@ -1,12 +1,13 @@
 use amethyst_core::{
     ecs::prelude::{
-        BitSet, ComponentEvent, Join, ReadStorage, ReaderId, System, SystemData, WriteStorage,
+        BitSet, ComponentEvent, Join, ReadStorage, ReaderId, System, SystemData, Write,
+        WriteStorage,
     },
-    math::{convert, Matrix4},
-    Transform,
+    math::{convert, Matrix4, Point3, Vector3},
+    Parent, Transform,
 };
 use amethyst_derive::SystemDesc;
-use amethyst_rendy::skinning::JointTransforms;
+use amethyst_rendy::{debug_drawing::DebugLines, palette::Srgba, skinning::JointTransforms};
 
 use log::error;
 
@@ -134,3 +135,94 @@ impl<'a> System<'a> for VertexSkinningSystem {
         }
     }
 }
+
+/// System drawing the skeletons of entities with an enabled `SkeletonDebug` with `DebugLines`.
+///
+/// Needs to run after global transforms have been updated for the current frame.
+#[derive(Debug, Default)]
+pub struct SkeletonDebugSystem;
+
+impl SkeletonDebugSystem {
+    /// Creates a new `SkeletonDebugSystem`
+    pub fn new() -> Self {
+        SkeletonDebugSystem
+    }
+}
+
+impl<'a> System<'a> for SkeletonDebugSystem {
+    type SystemData = (
+        ReadStorage<'a, SkeletonDebug>,
+        ReadStorage<'a, JointTransforms>,
+        ReadStorage<'a, Skin>,
+        ReadStorage<'a, Parent>,
+        ReadStorage<'a, Transform>,
+        Write<'a, DebugLines>,
+    );
+
+    fn run(
+        &mut self,
+        (debugs, joint_transforms, skins, parents, global_transforms, mut debug_lines): Self::SystemData,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("skeleton_debug_system");
+
+        let position = |entity| {
+            global_transforms
+                .get(entity)
+                .map(|global| global.global_matrix().transform_point(&Point3::origin()))
+        };
+
+        for (debug, joint_transform) in (&debugs, &joint_transforms).join() {
+            if !debug.enabled {
+                continue;
+            }
+            let skin = match skins.get(joint_transform.skin) {
+                Some(skin) => skin,
+                None => continue,
+            };
+            for joint in &skin.joints {
+                // Bones lead from a joint to its parent, unless the parent is outside the skin.
+                let parent = match parents.get(*joint) {
+                    Some(parent) if skin.joints.contains(&parent.entity) => parent.entity,
+                    _ => continue,
+                };
+                if let (Some(head), Some(tail)) = (position(parent), position(*joint)) {
+                    draw_bone(&mut debug_lines, head, tail, debug.shape, debug.color);
+                }
+            }
+        }
+    }
+}
+
+/// Draw a bone from the joint at `head` to its child joint at `tail`.
+fn draw_bone(
+    debug_lines: &mut DebugLines,
+    head: Point3<f32>,
+    tail: Point3<f32>,
+    shape: BoneShape,
+    color: Srgba,
+) {
+    let bone = tail - head;
+    let length = bone.norm();
+    if shape == BoneShape::Line || length <= std::f32::EPSILON {
+        debug_lines.draw_line(head, tail, color);
+        return;
+    }
+
+    // Square of the octahedron, a tenth of the way along the bone and a fifth as wide.
+    let direction = bone / length;
+    let other = if direction.x.abs() < 0.9 {
+        Vector3::x()
+    } else {
+        Vector3::y()
+    };
+    let side = direction.cross(&other).normalize() * (0.1 * length);
+    let up = direction.cross(&side);
+    let center = head + bone * 0.1;
+    let corners = [center + side, center + up, center - side, center - up];
+    for (i, corner) in corners.iter().enumerate() {
+        debug_lines.draw_line(head, *corner, color);
+        debug_lines.draw_line(*corner, tail, color);
+        debug_lines.draw_line(*corner, corners[(i + 1) % corners.len()], color);
+    }
+}
@@ -13,7 +13,7 @@ use rayon::ThreadPool;
 
 use amethyst_core::{
     ecs::{
-        hibitset::BitSet,
+        hibitset::{BitSet, BitSetLike},
         prelude::{Component, Read, ReadExpect, System, SystemData, VecStorage, World, Write},
         storage::UnprotectedStorage,
     },
@@ -186,6 +186,16 @@ impl<A: Asset> AssetStorage<A> {
         handle
     }
 
+    /// Returns the number of loaded assets in the storage.
+    pub fn len(&self) -> usize {
+        (&self.bitset).iter().count()
+    }
+
+    /// Check if there are no loaded assets in the storage.
+    pub fn is_empty(&self) -> bool {
+        self.bitset.is_empty()
+    }
+
     /// Check if given handle points to a valid asset in the storage.
     pub fn contains(&self, handle: &Handle<A>) -> bool {
         self.bitset.contains(handle.id())
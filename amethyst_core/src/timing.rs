@@ -29,6 +29,8 @@ pub struct Time {
     fixed_time_accumulator: f32,
     /// Fixed update interpolation alpha
     interpolation_alpha: f32,
+    /// Frame time used instead of the measured one, if any.
+    forced_delta_time: Option<Duration>,
 }
 
 impl Time {
@@ -119,9 +121,12 @@ impl Time {
 
     /// Sets both `delta_time` and `delta_seconds` based on the duration given.
     ///
+    /// The duration is ignored while a forced delta time is set.
+    ///
     /// This should only be called by the engine.  Bad things might happen if you call this in
     /// your game.
     pub fn set_delta_time(&mut self, time: Duration) {
+        let time = self.forced_delta_time.unwrap_or(time);
         self.delta_seconds = duration_to_secs(time) * self.time_scale;
         self.delta_time = secs_to_duration(duration_to_secs(time) * self.time_scale);
         self.delta_real_seconds = duration_to_secs(time);
@@ -143,6 +148,20 @@ impl Time {
         self.fixed_time = time;
     }
 
+    /// Gets the frame time used instead of the measured one, if any.
+    pub fn forced_delta_time(&self) -> Option<Duration> {
+        self.forced_delta_time
+    }
+
+    /// Makes every following frame advance the time by exactly `time` instead of the measured
+    /// frame time, or measures frame times again with `None`.
+    ///
+    /// Real and fixed update times advance by the forced delta as well, so the game runs as if
+    /// every frame took exactly that long, e.g. to record a video at a fixed framerate.
+    pub fn set_forced_delta_time(&mut self, time: Option<Duration>) {
+        self.forced_delta_time = time;
+    }
+
     /// Increments the current frame number by 1.
     ///
     /// This should only be called by the engine.  Bad things might happen if you call this in
@@ -209,6 +228,7 @@ impl Default for Time {
             absolute_real_time: Duration::default(),
             absolute_time: Duration::default(),
             time_scale: 1.0,
+            forced_delta_time: None,
         }
     }
 }
@@ -432,6 +452,23 @@ mod tests {
         }
         assert_eq!(fixed_count, 2);
     }
+
+    #[test]
+    fn forced_delta_time() {
+        use super::Time;
+
+        let mut time = Time::default();
+        time.set_time_scale(0.5);
+        time.set_forced_delta_time(Some(Duration::from_millis(40)));
+        time.set_delta_time(Duration::from_millis(100));
+        assert_eq!(time.delta_real_time(), Duration::from_millis(40));
+        assert!((time.delta_seconds() - 0.02).abs() < 1e-6);
+
+        time.set_forced_delta_time(None);
+        time.set_delta_time(Duration::from_millis(100));
+        assert_eq!(time.delta_real_time(), Duration::from_millis(100));
+        assert_eq!(time.absolute_real_time(), Duration::from_millis(140));
+    }
 }
 
 /// Converts a Duration to the time in seconds.
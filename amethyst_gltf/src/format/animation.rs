@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 
 use amethyst_error::Error;
+use log::warn;
 
 use amethyst_animation::{
     AnimationPrefab, AnimationSetPrefab, InterpolationFunction, InterpolationPrimitive, Sampler,
@@ -40,6 +41,17 @@ fn load_animation(
     let mut a = AnimationPrefab::default();
     a.samplers = animation
         .channels()
+        .filter(|channel| {
+            use gltf::animation::Property;
+            let weights = channel.target().property() == Property::MorphTargetWeights;
+            if weights {
+                warn!(
+                    "Skipping morph target weights of animation {}, they can't be loaded",
+                    animation.index()
+                );
+            }
+            !weights
+        })
         .map(|ref channel| load_channel(channel, buffers))
         .collect::<Result<Vec<_>, Error>>()?;
     Ok(a)
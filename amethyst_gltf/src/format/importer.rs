@@ -3,6 +3,7 @@ use std::{path::Path, sync::Arc};
 use amethyst_assets::Source as AssetSource;
 use amethyst_error::Error;
 use gltf::{self, json, Gltf};
+use serde::Deserialize;
 
 use crate::error;
 
@@ -48,8 +49,168 @@ impl Buffers {
     }
 }
 
+/// Material extensions which the `gltf` crate doesn't read, indexed by material.
+#[derive(Clone, Debug, Default)]
+pub struct MaterialExtensions {
+    emissive_strengths: Vec<f32>,
+    texture_transforms: Vec<TextureTransforms>,
+}
+
+/// The `KHR_texture_transform` of a texture.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct TextureTransform {
+    /// Offset of the texture coordinates
+    pub offset: [f32; 2],
+    /// Rotation of the texture coordinates in radians
+    pub rotation: f32,
+    /// Scale of the texture coordinates
+    pub scale: [f32; 2],
+    /// Texture coordinates overriding the ones of the texture
+    #[serde(rename = "texCoord")]
+    pub tex_coord: Option<u32>,
+}
+
+impl Default for TextureTransform {
+    fn default() -> Self {
+        TextureTransform {
+            offset: [0.0, 0.0],
+            rotation: 0.0,
+            scale: [1.0, 1.0],
+            tex_coord: None,
+        }
+    }
+}
+
+/// The `KHR_texture_transform` of each texture of a material.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TextureTransforms {
+    /// Transform of the base color texture
+    pub base_color: Option<TextureTransform>,
+    /// Transform of the metallic-roughness texture
+    pub metallic_roughness: Option<TextureTransform>,
+    /// Transform of the normal texture
+    pub normal: Option<TextureTransform>,
+    /// Transform of the occlusion texture
+    pub occlusion: Option<TextureTransform>,
+    /// Transform of the emissive texture
+    pub emissive: Option<TextureTransform>,
+}
+
+impl MaterialExtensions {
+    fn from_json(json: &[u8]) -> Result<Self, Error> {
+        #[derive(Deserialize)]
+        struct Root {
+            #[serde(default)]
+            materials: Vec<Material>,
+        }
+
+        #[derive(Deserialize)]
+        struct Material {
+            #[serde(default)]
+            extensions: Extensions,
+            #[serde(default, rename = "pbrMetallicRoughness")]
+            pbr: Pbr,
+            #[serde(rename = "normalTexture")]
+            normal_texture: Option<TextureInfo>,
+            #[serde(rename = "occlusionTexture")]
+            occlusion_texture: Option<TextureInfo>,
+            #[serde(rename = "emissiveTexture")]
+            emissive_texture: Option<TextureInfo>,
+        }
+
+        #[derive(Default, Deserialize)]
+        struct Pbr {
+            #[serde(rename = "baseColorTexture")]
+            base_color_texture: Option<TextureInfo>,
+            #[serde(rename = "metallicRoughnessTexture")]
+            metallic_roughness_texture: Option<TextureInfo>,
+        }
+
+        #[derive(Deserialize)]
+        struct TextureInfo {
+            #[serde(default)]
+            extensions: TextureExtensions,
+        }
+
+        impl TextureInfo {
+            fn transform(info: Option<TextureInfo>) -> Option<TextureTransform> {
+                info.and_then(|info| info.extensions.texture_transform)
+            }
+        }
+
+        #[derive(Default, Deserialize)]
+        struct TextureExtensions {
+            #[serde(rename = "KHR_texture_transform")]
+            texture_transform: Option<TextureTransform>,
+        }
+
+        #[derive(Default, Deserialize)]
+        struct Extensions {
+            #[serde(rename = "KHR_materials_emissive_strength")]
+            emissive_strength: Option<EmissiveStrength>,
+        }
+
+        #[derive(Deserialize)]
+        struct EmissiveStrength {
+            #[serde(rename = "emissiveStrength")]
+            emissive_strength: Option<f32>,
+        }
+
+        let root: Root = serde_json::from_slice(json)?;
+        Ok(MaterialExtensions {
+            emissive_strengths: root
+                .materials
+                .iter()
+                .map(|material| {
+                    material
+                        .extensions
+                        .emissive_strength
+                        .as_ref()
+                        .and_then(|extension| extension.emissive_strength)
+                        .unwrap_or(1.0)
+                })
+                .collect(),
+            texture_transforms: root
+                .materials
+                .into_iter()
+                .map(|material| TextureTransforms {
+                    base_color: TextureInfo::transform(material.pbr.base_color_texture),
+                    metallic_roughness: TextureInfo::transform(
+                        material.pbr.metallic_roughness_texture,
+                    ),
+                    normal: TextureInfo::transform(material.normal_texture),
+                    occlusion: TextureInfo::transform(material.occlusion_texture),
+                    emissive: TextureInfo::transform(material.emissive_texture),
+                })
+                .collect(),
+        })
+    }
+
+    /// Multiplier of the emissive factor, from `KHR_materials_emissive_strength`.
+    pub fn emissive_strength(&self, material: &gltf::Material<'_>) -> f32 {
+        material
+            .index()
+            .and_then(|index| self.emissive_strengths.get(index))
+            .cloned()
+            .unwrap_or(1.0)
+    }
+
+    /// The `KHR_texture_transform` of each texture of the material.
+    pub fn texture_transforms(&self, material: &gltf::Material<'_>) -> TextureTransforms {
+        material
+            .index()
+            .and_then(|index| self.texture_transforms.get(index))
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
 /// Imports glTF 2.0
-pub fn import<P>(source: Arc<dyn AssetSource>, path: P) -> Result<(Gltf, Buffers), Error>
+pub fn import<P>(
+    source: Arc<dyn AssetSource>,
+    path: P,
+) -> Result<(Gltf, Buffers, MaterialExtensions), Error>
 where
     P: AsRef<Path>,
 {
@@ -115,22 +276,24 @@ fn import_standard(
     data: &[u8],
     source: Arc<dyn AssetSource>,
     base_path: &Path,
-) -> Result<(Gltf, Buffers), Error> {
+) -> Result<(Gltf, Buffers, MaterialExtensions), Error> {
     let gltf = Gltf::from_slice(data)?;
+    let extensions = MaterialExtensions::from_json(data)?;
     let buffers = Buffers(load_external_buffers(source, base_path, &gltf, None)?);
-    Ok((gltf, buffers))
+    Ok((gltf, buffers, extensions))
 }
 
 fn import_binary(
     data: &[u8],
     source: Arc<dyn AssetSource>,
     base_path: &Path,
-) -> Result<(Gltf, Buffers), Error> {
+) -> Result<(Gltf, Buffers, MaterialExtensions), Error> {
     let gltf::binary::Glb { json, bin, .. } = gltf::binary::Glb::from_slice(data)?;
     let gltf = Gltf::from_slice(&json)?;
+    let extensions = MaterialExtensions::from_json(&json)?;
     let bin = bin.map(|x| x.to_vec());
     let buffers = Buffers(load_external_buffers(source, base_path, &gltf, bin)?);
-    Ok((gltf, buffers))
+    Ok((gltf, buffers, extensions))
 }
 
 pub fn get_image_data(
@@ -193,3 +356,78 @@ pub fn get_image_data(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{MaterialExtensions, TextureTransform, TextureTransforms};
+
+    #[test]
+    fn emissive_strength_is_read_per_material() {
+        let json = br#"{
+            "asset": { "version": "2.0" },
+            "materials": [
+                { "name": "plain" },
+                {
+                    "emissiveFactor": [1.0, 0.5, 0.0],
+                    "extensions": {
+                        "KHR_materials_emissive_strength": { "emissiveStrength": 4.0 }
+                    }
+                }
+            ]
+        }"#;
+
+        let extensions = MaterialExtensions::from_json(json).unwrap();
+
+        assert_eq!(extensions.emissive_strengths, vec![1.0, 4.0]);
+    }
+
+    #[test]
+    fn texture_transforms_are_read_per_texture() {
+        let json = br#"{
+            "asset": { "version": "2.0" },
+            "materials": [
+                { "pbrMetallicRoughness": { "baseColorTexture": { "index": 0 } } },
+                {
+                    "pbrMetallicRoughness": {
+                        "baseColorTexture": {
+                            "index": 0,
+                            "extensions": {
+                                "KHR_texture_transform": { "offset": [0.5, 0.0], "scale": [2.0, 2.0] }
+                            }
+                        }
+                    },
+                    "normalTexture": {
+                        "index": 1,
+                        "extensions": {
+                            "KHR_texture_transform": { "rotation": 1.5, "texCoord": 1 }
+                        }
+                    }
+                }
+            ]
+        }"#;
+
+        let extensions = MaterialExtensions::from_json(json).unwrap();
+
+        assert_eq!(
+            extensions.texture_transforms,
+            vec![
+                TextureTransforms::default(),
+                TextureTransforms {
+                    base_color: Some(TextureTransform {
+                        offset: [0.5, 0.0],
+                        rotation: 0.0,
+                        scale: [2.0, 2.0],
+                        tex_coord: None,
+                    }),
+                    normal: Some(TextureTransform {
+                        offset: [0.0, 0.0],
+                        rotation: 1.5,
+                        scale: [1.0, 1.0],
+                        tex_coord: Some(1),
+                    }),
+                    ..TextureTransforms::default()
+                },
+            ]
+        );
+    }
+}
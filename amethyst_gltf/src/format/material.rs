@@ -1,8 +1,13 @@
-use super::{get_image_data, Buffers, ImageFormat as ImportDataFormat};
+use super::{
+    get_image_data, importer::TextureTransform, Buffers, ImageFormat as ImportDataFormat,
+    MaterialExtensions,
+};
+use crate::GltfSceneOptions;
 use amethyst_assets::Source;
 use amethyst_error::Error;
 use amethyst_rendy::{
     formats::{mtl::MaterialPrefab, texture::TexturePrefab},
+    mtl,
     palette::{LinSrgba, Srgba},
     rendy::{
         hal,
@@ -15,12 +20,15 @@ use amethyst_rendy::{
 };
 
 use gltf::{self, material::AlphaMode};
+use log::warn;
 use std::sync::Arc;
 
 // Load a single material, and transform into a format usable by the engine
 pub fn load_material(
     material: &gltf::Material<'_>,
     buffers: &Buffers,
+    extensions: &MaterialExtensions,
+    options: &GltfSceneOptions,
     source: Arc<dyn Source>,
     name: &str,
 ) -> Result<MaterialPrefab, Error> {
@@ -28,6 +36,37 @@ pub fn load_material(
 
     let pbr = material.pbr_metallic_roughness();
 
+    let transforms = extensions.texture_transforms(material);
+    let transform =
+        |tex_coord, transform| texture_transform(material, tex_coord, transform, options);
+    let tex_coord = |info: Option<gltf::texture::Info<'_>>| info.map_or(0, |info| info.tex_coord());
+    prefab.texture_transforms = mtl::TextureTransforms {
+        albedo: transform(
+            tex_coord(pbr.base_color_texture()),
+            transforms.base_color.as_ref(),
+        ),
+        metallic_roughness: transform(
+            tex_coord(pbr.metallic_roughness_texture()),
+            transforms.metallic_roughness.as_ref(),
+        ),
+        normal: transform(
+            material.normal_texture().map_or(0, |info| info.tex_coord()),
+            transforms.normal.as_ref(),
+        ),
+        ambient_occlusion: transform(
+            material
+                .occlusion_texture()
+                .map_or(0, |info| info.tex_coord()),
+            transforms.occlusion.as_ref(),
+        ),
+        emission: transform(
+            tex_coord(material.emissive_texture()),
+            transforms.emissive.as_ref(),
+        ),
+    };
+    prefab.unlit = material.unlit();
+    prefab.emission_strength = extensions.emissive_strength(material);
+
     prefab.albedo = Some(
         load_texture_with_factor(
             pbr.base_color_texture(),
@@ -114,6 +153,42 @@ pub fn load_material(
     Ok(prefab)
 }
 
+/// Converts the texture coordinates and `KHR_texture_transform` of a texture to a
+/// `TextureTransform`.
+fn texture_transform(
+    material: &gltf::Material<'_>,
+    tex_coord: u32,
+    transform: Option<&TextureTransform>,
+    options: &GltfSceneOptions,
+) -> mtl::TextureTransform {
+    let mut result = match transform {
+        Some(transform) => mtl::TextureTransform {
+            offset: transform.offset,
+            rotation: transform.rotation,
+            scale: transform.scale,
+            tex_coord: transform.tex_coord.unwrap_or(tex_coord),
+        },
+        None => mtl::TextureTransform {
+            tex_coord,
+            ..Default::default()
+        },
+    };
+    // Only TEXCOORD_0 and TEXCOORD_1, loaded as `LightmapTexCoord`, are available to shaders.
+    if result.tex_coord > 1 {
+        warn!(
+            "Material {:?} samples unsupported texture coordinates {}, using 0",
+            material.index(),
+            result.tex_coord
+        );
+        result.tex_coord = 0;
+    }
+    if options.flip_v_coord {
+        result.flip_v()
+    } else {
+        result
+    }
+}
+
 fn load_texture_with_factor(
     texture: Option<gltf::texture::Info<'_>>,
     factor: [f32; 4],
@@ -3,6 +3,9 @@ use crate::{error, GltfSceneOptions};
 use amethyst_core::math::{zero, Vector3};
 use amethyst_error::Error;
 use amethyst_rendy::{
+    custom_attribute::CustomAttribute,
+    lightmap::LightmapTexCoord,
+    morph::{MorphPositions, MAX_MORPH_TARGETS},
     rendy::mesh::{Color, MeshBuilder, Normal, Position, Tangent, TexCoord},
     skinning::JointCombined,
 };
@@ -108,6 +111,18 @@ pub fn load_mesh(
             }
         });
 
+        let lightmap_tex_coords = try_compute_if(options.load_texcoords, || {
+            trace!("Loading lightmap texture coordinates");
+            let tex_coords = reader.read_tex_coords(1)?.into_f32();
+            Some(if options.flip_v_coord {
+                tex_coords
+                    .map(|[u, v]| LightmapTexCoord([u, 1. - v]))
+                    .collect::<Vec<_>>()
+            } else {
+                tex_coords.map(LightmapTexCoord).collect::<Vec<_>>()
+            })
+        });
+
         let tangents = compute_if(options.load_tangents, || {
             trace!("Loading tangents");
             let tangents = reader.read_tangents();
@@ -148,6 +163,37 @@ pub fn load_mesh(
             }
         });
 
+        let morph_positions = try_compute_if(options.load_morph_targets, || {
+            trace!("Loading morph targets");
+            let mut morph_positions = vec![MorphPositions::default(); positions.len()];
+            let mut targets = 0;
+            for (target, (target_positions, _, _)) in reader.read_morph_targets().enumerate() {
+                if target >= MAX_MORPH_TARGETS {
+                    warn!(
+                        "Mesh primitive has more than {} morph targets, the others are ignored",
+                        MAX_MORPH_TARGETS
+                    );
+                    break;
+                }
+                targets += 1;
+                if let Some(target_positions) = target_positions {
+                    for (morph, offset) in morph_positions.iter_mut().zip(target_positions) {
+                        morph.0[target] = offset;
+                    }
+                }
+            }
+            if targets > 0 {
+                Some(morph_positions)
+            } else {
+                None
+            }
+        });
+
+        let custom_attributes = compute_if(options.load_custom_attributes, || {
+            trace!("Loading custom attributes");
+            read_custom_attributes(&primitive, buffers)
+        });
+
         match indices {
             Indices::U16(vec) => {
                 builder.set_indices(vec);
@@ -162,8 +208,13 @@ pub fn load_mesh(
         normals.map(|v| builder.add_vertices(v));
         tangents.map(|v| builder.add_vertices(v));
         tex_coords.map(|v| builder.add_vertices(v));
+        lightmap_tex_coords.map(|v| builder.add_vertices(v));
         colors.map(|v| builder.add_vertices(v));
         joints.map(|v| builder.add_vertices(v));
+        morph_positions.map(|v| builder.add_vertices(v));
+        for (attribute, bytes) in custom_attributes.iter().flatten() {
+            attribute.add_vertices(&mut builder, bytes);
+        }
 
         trace!("Loading bounding box");
         let bounds = primitive.bounding_box();
@@ -176,6 +227,45 @@ pub fn load_mesh(
     Ok(primitives)
 }
 
+/// Read the application specific attributes of `primitive` registered as `CustomAttribute`s.
+fn read_custom_attributes(
+    primitive: &gltf::Primitive<'_>,
+    buffers: &Buffers,
+) -> Vec<(&'static CustomAttribute, Vec<u8>)> {
+    primitive
+        .attributes()
+        .filter_map(|(semantic, accessor)| {
+            let name = match semantic {
+                gltf::mesh::Semantic::Extras(name) => name,
+                _ => return None,
+            };
+            let attribute = CustomAttribute::find(&name)?;
+            let size = accessor.size();
+            if size != attribute.size() {
+                warn!(
+                    "Attribute {} of {} bytes doesn't match the {} bytes of the registered attribute, it is ignored",
+                    name,
+                    size,
+                    attribute.size(),
+                );
+                return None;
+            }
+            // Sparse accessors without a buffer view aren't supported.
+            let view = accessor.view()?;
+            let data = buffers.view(&view)?;
+            let stride = view.stride().unwrap_or(size);
+            let bytes = (0..accessor.count())
+                .map(|vertex| {
+                    let offset = accessor.offset() + vertex * stride;
+                    data.get(offset..offset + size)
+                })
+                .collect::<Option<Vec<_>>>()?
+                .concat();
+            Some((attribute, bytes))
+        })
+        .collect()
+}
+
 fn calculate_normals(positions: &[Position], indices: &Indices) -> Vec<Normal> {
     let mut normals = vec![zero::<Vector3<f32>>(); positions.len()];
     let num_faces = indices.len().unwrap_or_else(|| positions.len()) / 3;
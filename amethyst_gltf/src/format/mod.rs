@@ -13,13 +13,13 @@ use amethyst_core::{
     transform::Transform,
 };
 use amethyst_error::{format_err, Error, ResultExt};
-use amethyst_rendy::camera::CameraPrefab;
+use amethyst_rendy::{camera::CameraPrefab, morph::MorphWeights};
 
 use crate::{error, GltfMaterialSet, GltfNodeExtent, GltfPrefab, GltfSceneOptions, Named};
 
 use self::{
     animation::load_animations,
-    importer::{get_image_data, import, Buffers, ImageFormat},
+    importer::{get_image_data, import, Buffers, ImageFormat, MaterialExtensions},
     material::load_material,
     mesh::load_mesh,
     skin::load_skin,
@@ -68,14 +68,15 @@ fn load_gltf(
     debug!("Loading GLTF scene '{}'", name);
     import(source.clone(), name)
         .with_context(|_| error::Error::GltfImporterError)
-        .and_then(|(gltf, buffers)| {
-            load_data(&gltf, &buffers, options, source, name).map_err(Into::into)
+        .and_then(|(gltf, buffers, extensions)| {
+            load_data(&gltf, &buffers, &extensions, options, source, name).map_err(Into::into)
         })
 }
 
 fn load_data(
     gltf: &Gltf,
     buffers: &Buffers,
+    extensions: &MaterialExtensions,
     options: &GltfSceneOptions,
     source: Arc<dyn Source>,
     name: &str,
@@ -86,6 +87,7 @@ fn load_data(
         gltf,
         scene_index,
         buffers,
+        extensions,
         options,
         source,
         name,
@@ -111,6 +113,7 @@ fn load_scene(
     gltf: &Gltf,
     scene_index: usize,
     buffers: &Buffers,
+    extensions: &MaterialExtensions,
     options: &GltfSceneOptions,
     source: Arc<dyn Source>,
     name: &str,
@@ -131,6 +134,7 @@ fn load_scene(
             &node,
             index,
             buffers,
+            extensions,
             options,
             source.clone(),
             name,
@@ -196,6 +200,7 @@ fn load_node(
     node: &gltf::Node<'_>,
     entity_index: usize,
     buffers: &Buffers,
+    extensions: &MaterialExtensions,
     options: &GltfSceneOptions,
     source: Arc<dyn Source>,
     name: &str,
@@ -256,6 +261,17 @@ fn load_node(
 
     // load graphics
     if let Some(mesh) = node.mesh() {
+        let morph_weights = if options.load_morph_targets
+            && mesh
+                .primitives()
+                .any(|primitive| primitive.morph_targets().next().is_some())
+        {
+            Some(MorphWeights::from_slice(
+                node.weights().or_else(|| mesh.weights()).unwrap_or(&[]),
+            ))
+        } else {
+            None
+        };
         let mut graphics = load_mesh(&mesh, buffers, options)?;
         match graphics.len().cmp(&1) {
             Ordering::Equal => {
@@ -264,13 +280,21 @@ fn load_node(
                 bounding_box.extend_range(&bounds);
                 let prefab_data = prefab.data_or_default(entity_index);
                 prefab_data.mesh = Some(mesh);
+                prefab_data.morph_weights = morph_weights;
                 if let Some((material_id, material)) =
                     material_index.and_then(|index| gltf.materials().nth(index).map(|m| (index, m)))
                 {
                     material_set
                         .materials
                         .entry(material_id)
-                        .or_insert(load_material(&material, buffers, source.clone(), name)?);
+                        .or_insert(load_material(
+                            &material,
+                            buffers,
+                            extensions,
+                            options,
+                            source.clone(),
+                            name,
+                        )?);
                     prefab_data.material_id = Some(material_id);
                 }
                 // if we have a skin we need to track the mesh entities
@@ -286,13 +310,21 @@ fn load_node(
                     let prefab_data = prefab.data_or_default(mesh_entity);
                     prefab_data.transform = Some(Transform::default());
                     prefab_data.mesh = Some(mesh);
+                    prefab_data.morph_weights = morph_weights;
                     if let Some((material_id, material)) = material_index
                         .and_then(|index| gltf.materials().nth(index).map(|m| (index, m)))
                     {
                         material_set
                             .materials
                             .entry(material_id)
-                            .or_insert(load_material(&material, buffers, source.clone(), name)?);
+                            .or_insert(load_material(
+                                &material,
+                                buffers,
+                                extensions,
+                                options,
+                                source.clone(),
+                                name,
+                            )?);
                         prefab_data.material_id = Some(material_id);
                     }
 
@@ -318,6 +350,7 @@ fn load_node(
             &child,
             index,
             buffers,
+            extensions,
             options,
             source.clone(),
             name,
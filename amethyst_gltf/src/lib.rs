@@ -22,8 +22,12 @@ use amethyst_core::{
 };
 use amethyst_error::Error;
 use amethyst_rendy::{
-    camera::CameraPrefab, formats::mtl::MaterialPrefab, rendy::mesh::MeshBuilder, types::Mesh,
-    visibility::BoundingSphere,
+    camera::CameraPrefab,
+    formats::mtl::MaterialPrefab,
+    morph::MorphWeights,
+    rendy::mesh::MeshBuilder,
+    types::Mesh,
+    visibility::{BoundingBox, BoundingSphere},
 };
 use derivative::Derivative;
 use serde::{Deserialize, Serialize};
@@ -62,6 +66,9 @@ pub struct GltfPrefab {
     /// Skin data is placed on `Entity`s involved in the skin, skeleton or graphical primitives
     /// using the skin
     pub skinnable: Option<SkinnablePrefab>,
+    /// Initial morph target weights, placed on `Entity`s with graphics primitives using morph
+    /// targets
+    pub morph_weights: Option<MorphWeights>,
     /// Node extent
     pub extent: Option<GltfNodeExtent>,
     /// Node name
@@ -168,6 +175,12 @@ impl Into<BoundingSphere> for GltfNodeExtent {
     }
 }
 
+impl Into<BoundingBox> for GltfNodeExtent {
+    fn into(self) -> BoundingBox {
+        BoundingBox::new(self.start, self.end)
+    }
+}
+
 impl From<Range<[f32; 3]>> for GltfNodeExtent {
     fn from(range: Range<[f32; 3]>) -> Self {
         GltfNodeExtent {
@@ -198,7 +211,8 @@ pub struct GltfSceneOptions {
     /// Load vertex color data from the Gltf file
     pub load_colors: bool,
     #[derivative(Default(value = "true"))]
-    /// Load texture coordinates data from the Gltf file
+    /// Load texture coordinates data from the Gltf file, with the second set of coordinates
+    /// as `LightmapTexCoord`
     pub load_texcoords: bool,
     #[derivative(Default(value = "true"))]
     /// Load vertex tangent data from the Gltf file
@@ -206,6 +220,13 @@ pub struct GltfSceneOptions {
     #[derivative(Default(value = "true"))]
     /// Load animation data from the Gltf file
     pub load_animations: bool,
+    #[derivative(Default(value = "true"))]
+    /// Load the position offsets of the first `MAX_MORPH_TARGETS` morph targets from the Gltf file
+    pub load_morph_targets: bool,
+    #[derivative(Default(value = "true"))]
+    /// Load the application specific vertex attributes registered as `CustomAttribute`s from
+    /// the Gltf file, e.g. `_WIND_WEIGHT` attributes registered with the `WIND_WEIGHT` semantic
+    pub load_custom_attributes: bool,
     /// Flip the v coordinate for all texture coordinates
     pub flip_v_coord: bool,
     /// Load the given scene index, if not supplied will either load the default scene (if set),
@@ -221,7 +242,9 @@ impl<'a> PrefabData<'a> for GltfPrefab {
         <MaterialPrefab as PrefabData<'a>>::SystemData,
         <AnimatablePrefab<usize, Transform> as PrefabData<'a>>::SystemData,
         <SkinnablePrefab as PrefabData<'a>>::SystemData,
+        <MorphWeights as PrefabData<'a>>::SystemData,
         WriteStorage<'a, BoundingSphere>,
+        WriteStorage<'a, BoundingBox>,
         WriteStorage<'a, Handle<Mesh>>,
         Read<'a, AssetStorage<Mesh>>,
         ReadExpect<'a, Loader>,
@@ -243,7 +266,9 @@ impl<'a> PrefabData<'a> for GltfPrefab {
             materials,
             animatables,
             skinnables,
+            morph_weights,
             bound,
+            aabb,
             meshes,
             _,
             _,
@@ -270,8 +295,12 @@ impl<'a> PrefabData<'a> for GltfPrefab {
         if let Some(skinnable) = &self.skinnable {
             skinnable.add_to_entity(entity, skinnables, entities, children)?;
         }
+        if let Some(weights) = &self.morph_weights {
+            weights.add_to_entity(entity, morph_weights, entities, children)?;
+        }
         if let Some(extent) = &self.extent {
             bound.insert(entity, extent.clone().into())?;
+            aabb.insert(entity, extent.clone().into())?;
         }
         Ok(())
     }
@@ -281,7 +310,7 @@ impl<'a> PrefabData<'a> for GltfPrefab {
         progress: &mut ProgressCounter,
         system_data: &mut Self::SystemData,
     ) -> Result<bool, Error> {
-        let (_, _, _, materials, animatables, _, _, _, meshes_storage, loader, mat_set) =
+        let (_, _, _, materials, animatables, _, _, _, _, _, meshes_storage, loader, mat_set) =
             system_data;
 
         let mut ret = false;
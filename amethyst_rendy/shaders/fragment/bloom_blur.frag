@@ -0,0 +1,26 @@
+#version 450
+
+layout(location = 0) in vec2 tex_coord;
+
+layout(location = 0) out vec4 out_color;
+
+layout(set = 0, binding = 0) uniform sampler2D source;
+
+layout(push_constant) uniform BloomBlur {
+    // Distance between texels of the output along the blurred axis.
+    vec2 texel_step;
+};
+
+// 9-tap gaussian kernel folded into 5 bilinear fetches.
+const float offsets[3] = float[](0.0, 1.3846153846, 3.2307692308);
+const float weights[3] = float[](0.2270270270, 0.3162162162, 0.0702702703);
+
+void main() {
+    vec3 color = texture(source, tex_coord).rgb * weights[0];
+    for (int i = 1; i < 3; i++) {
+        vec2 offset = texel_step * offsets[i];
+        color += texture(source, tex_coord + offset).rgb * weights[i];
+        color += texture(source, tex_coord - offset).rgb * weights[i];
+    }
+    out_color = vec4(color, 1.0);
+}
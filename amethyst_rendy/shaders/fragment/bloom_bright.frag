@@ -0,0 +1,25 @@
+#version 450
+
+layout(location = 0) in vec2 tex_coord;
+
+layout(location = 0) out vec4 out_color;
+
+layout(set = 0, binding = 0) uniform sampler2D source;
+
+layout(push_constant) uniform BloomBright {
+    float threshold;
+    float soft_knee;
+};
+
+void main() {
+    vec3 color = texture(source, tex_coord).rgb;
+    float brightness = max(color.r, max(color.g, color.b));
+
+    // Quadratic falloff below the threshold, linear above it.
+    float knee = threshold * soft_knee + 0.00001;
+    float soft = clamp(brightness - threshold + knee, 0.0, 2.0 * knee);
+    soft = soft * soft / (4.0 * knee);
+    float contribution = max(soft, brightness - threshold) / max(brightness, 0.00001);
+
+    out_color = vec4(color * contribution, 1.0);
+}
@@ -0,0 +1,21 @@
+#version 450
+
+layout(location = 0) in vec2 tex_coord;
+
+layout(location = 0) out vec4 out_color;
+
+layout(set = 0, binding = 0) uniform sampler2D base;
+layout(set = 0, binding = 1) uniform sampler2D bloom;
+
+layout(push_constant) uniform BloomCombine {
+    float base_weight;
+    float bloom_weight;
+};
+
+void main() {
+    vec4 color = texture(base, tex_coord);
+    out_color = vec4(
+        color.rgb * base_weight + texture(bloom, tex_coord).rgb * bloom_weight,
+        color.a
+    );
+}
@@ -0,0 +1,26 @@
+#version 450
+
+layout(location = 0) in vec2 tex_coord;
+
+layout(location = 0) out vec4 out_color;
+
+layout(set = 0, binding = 0) uniform sampler2D source;
+
+layout(push_constant) uniform ChromaticAberrationArgs {
+    float enabled;
+    float intensity;
+};
+
+void main() {
+    vec4 color = texture(source, tex_coord);
+    if (enabled < 0.5) {
+        out_color = color;
+        return;
+    }
+
+    // Offset grows with the distance from the center, reaching `intensity` at the corners.
+    vec2 offset = (tex_coord - 0.5) * 2.0 * length(tex_coord - 0.5) * sqrt(2.0) * intensity;
+    float red = textureLod(source, tex_coord + offset, 0.0).r;
+    float blue = textureLod(source, tex_coord - offset, 0.0).b;
+    out_color = vec4(red, color.g, blue, color.a);
+}
@@ -0,0 +1,53 @@
+#version 450
+
+layout(location = 0) in vec2 tex_coord;
+
+layout(location = 0) out vec4 out_color;
+
+layout(set = 0, binding = 0) uniform sampler2D source;
+// Horizontal strip of lookup table slices, one per blue level.
+layout(set = 1, binding = 0) uniform sampler2D lut;
+
+layout(push_constant) uniform ColorGradingArgs {
+    float enabled;
+    float intensity;
+};
+
+vec3 linear_to_srgb(vec3 color) {
+    vec3 low = color * 12.92;
+    vec3 high = 1.055 * pow(color, vec3(1.0 / 2.4)) - 0.055;
+    return mix(low, high, step(vec3(0.0031308), color));
+}
+
+vec3 srgb_to_linear(vec3 color) {
+    vec3 low = color / 12.92;
+    vec3 high = pow((color + 0.055) / 1.055, vec3(2.4));
+    return mix(low, high, step(vec3(0.04045), color));
+}
+
+// Trilinear lookup of `color`, filtering red and green in hardware and blending
+// between the two closest blue slices.
+vec3 lookup(vec3 color, float size) {
+    vec3 scaled = clamp(color, 0.0, 1.0) * (size - 1.0);
+    float slice = floor(scaled.b);
+    float next_slice = min(slice + 1.0, size - 1.0);
+    vec2 texel = scaled.rg + 0.5;
+    vec2 strip_size = vec2(size * size, size);
+    vec3 lower = textureLod(lut, (texel + vec2(slice * size, 0.0)) / strip_size, 0.0).rgb;
+    vec3 upper = textureLod(lut, (texel + vec2(next_slice * size, 0.0)) / strip_size, 0.0).rgb;
+    return mix(lower, upper, scaled.b - slice);
+}
+
+void main() {
+    vec4 color = texture(source, tex_coord);
+    float size = float(textureSize(lut, 0).y);
+    // Nothing to grade with while the lookup table is missing.
+    if (enabled < 0.5 || size < 2.0) {
+        out_color = color;
+        return;
+    }
+
+    // Lookup tables map display colors, the image holds linear colors.
+    vec3 graded = srgb_to_linear(lookup(linear_to_srgb(color.rgb), size));
+    out_color = vec4(mix(color.rgb, graded, intensity), color.a);
+}
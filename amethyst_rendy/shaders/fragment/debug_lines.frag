@@ -4,8 +4,18 @@ layout(location = 0) in VertexData {
     vec4 color;
 } vertex;
 
+layout(location = 1) noperspective in float line_distance;
+layout(location = 2) flat in vec2 pattern;
+layout(location = 3) noperspective in float edge_distance;
+layout(location = 4) flat in float half_width;
+
 layout(location = 0) out vec4 out_color;
 
 void main() {
-    out_color = vertex.color;
+    if (pattern.x > 0.0 && mod(line_distance, pattern.x + pattern.y) > pattern.x) {
+        discard;
+    }
+    // Coverage of the pixel by the line, fading out over the pixel straddling its edge.
+    float coverage = clamp(half_width + 0.5 - abs(edge_distance), 0.0, 1.0);
+    out_color = vec4(vertex.color.rgb, vertex.color.a * coverage);
 }
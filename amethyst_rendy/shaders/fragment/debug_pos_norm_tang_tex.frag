@@ -0,0 +1,5 @@
+#version 450
+
+#define VERTEX_NORMAL
+#define VERTEX_TANGENT
+#include "header/debug_view.frag"
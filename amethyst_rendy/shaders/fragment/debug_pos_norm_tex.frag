@@ -0,0 +1,4 @@
+#version 450
+
+#define VERTEX_NORMAL
+#include "header/debug_view.frag"
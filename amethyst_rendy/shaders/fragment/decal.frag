@@ -0,0 +1,38 @@
+#version 450
+
+#include "header/deferred.frag"
+
+layout(set = 2, binding = 0) uniform sampler2D albedo;
+layout(set = 3, binding = 0) uniform sampler2D normal;
+
+layout(location = 0) flat in mat4 world_to_decal;
+layout(location = 4) flat in mat3 decal_basis;
+layout(location = 7) flat in vec4 decal_color;
+layout(location = 8) flat in float decal_normal_strength;
+
+layout(location = 0) out vec4 out_albedo;
+layout(location = 1) out vec4 out_normal;
+
+// Projects the decal onto the G-buffer surface behind the fragment, along the negative z axis
+// of the box. Outputs have premultiplied alpha, see `header/deferred.frag`.
+void main() {
+    vec2 uv = gl_FragCoord.xy / vec2(textureSize(gbuffer_depth, 0));
+    Surface surface;
+    if (!read_surface(uv, surface)) discard;
+
+    vec3 local = (world_to_decal * vec4(surface.position, 1.0)).xyz;
+    if (any(greaterThan(abs(local), vec3(0.5)))) discard;
+
+    // Fade out on surfaces turning away from the projection, instead of stretching over them.
+    float facing = smoothstep(0.0, 0.5, dot(surface.normal, decal_basis[2]));
+    if (facing <= 0.0) discard;
+
+    vec2 tex_uv = vec2(local.x + 0.5, 0.5 - local.y);
+    vec4 color = texture(albedo, tex_uv) * decal_color;
+    float alpha = color.a * facing;
+    out_albedo = vec4(color.rgb * alpha, alpha);
+
+    vec3 tangent_normal = texture(normal, tex_uv).rgb * 2 - 1;
+    float weight = alpha * decal_normal_strength;
+    out_normal = vec4(normalize(decal_basis * tangent_normal) * weight, weight);
+}
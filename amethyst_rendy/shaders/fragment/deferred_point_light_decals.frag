@@ -0,0 +1,4 @@
+#version 450
+
+#define DECALS
+#include "header/deferred_point_light.frag"
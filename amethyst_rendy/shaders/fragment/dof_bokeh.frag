@@ -0,0 +1,49 @@
+#version 450
+
+layout(location = 0) in vec2 tex_coord;
+
+layout(location = 0) out vec4 out_color;
+
+// Colors with the signed circle of confusion in alpha.
+layout(set = 0, binding = 0) uniform sampler2D source;
+
+layout(push_constant) uniform DofBokehArgs {
+    float enabled;
+    float max_blur_radius;
+    float sample_spacing;
+};
+
+const float GOLDEN_ANGLE = 2.39996323;
+
+void main() {
+    vec4 center = textureLod(source, tex_coord, 0.0);
+    if (enabled < 0.5 || max_blur_radius <= 0.0) {
+        out_color = vec4(center.rgb, 1.0);
+        return;
+    }
+
+    vec2 pixel = 1.0 / vec2(textureSize(source, 0));
+    float center_size = abs(center.a) * max_blur_radius;
+    vec3 color = center.rgb;
+    float total = 1.0;
+
+    // Gather samples along a golden angle spiral. Each sample contributes where its own
+    // blur covers this pixel, and samples behind the center don't blur over it further
+    // than the center itself is blurred.
+    float spacing = max(sample_spacing, 0.1);
+    float radius = spacing;
+    for (float angle = 0.0; radius < max_blur_radius; angle += GOLDEN_ANGLE) {
+        vec2 uv = tex_coord + vec2(cos(angle), sin(angle)) * pixel * radius;
+        vec4 sample_color = textureLod(source, uv, 0.0);
+        float sample_size = abs(sample_color.a) * max_blur_radius;
+        if (sample_color.a > center.a) {
+            sample_size = clamp(sample_size, 0.0, center_size * 2.0);
+        }
+        float weight = smoothstep(radius - 0.5, radius + 0.5, sample_size);
+        color += mix(color / total, sample_color.rgb, weight);
+        total += 1.0;
+        radius += spacing / radius;
+    }
+
+    out_color = vec4(color / total, 1.0);
+}
@@ -0,0 +1,33 @@
+#version 450
+
+layout(location = 0) in vec2 tex_coord;
+
+layout(location = 0) out vec4 out_color;
+
+layout(set = 0, binding = 0) uniform sampler2D source;
+layout(set = 0, binding = 1) uniform sampler2D depth;
+
+layout(push_constant) uniform DofCocArgs {
+    // Elements of the projection matrix mapping view space depth to the depth buffer.
+    float proj_22;
+    float proj_23;
+    float proj_32;
+    float proj_33;
+    float enabled;
+    float focus_distance;
+    float aperture;
+};
+
+void main() {
+    vec3 color = texture(source, tex_coord).rgb;
+    float coc = 0.0;
+    if (enabled > 0.5) {
+        // Distance of the surface from the camera, infinite where nothing was drawn.
+        float d = textureLod(depth, tex_coord, 0.0).r;
+        float z = (proj_32 - d * proj_33) / (d * proj_23 - proj_22);
+        float distance = d > 0.0 ? max(-z, 0.0001) : 1.0 / 0.0;
+        // Signed circle of confusion, negative in front of the focus and growing with distance.
+        coc = clamp(aperture * (1.0 - focus_distance / distance), -1.0, 1.0);
+    }
+    out_color = vec4(color, coc);
+}
@@ -0,0 +1,4 @@
+#version 450
+
+#define OIT
+#include "header/flat.frag"
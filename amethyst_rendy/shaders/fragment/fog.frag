@@ -0,0 +1,96 @@
+#version 450
+
+layout(location = 0) in vec2 tex_coord;
+
+layout(location = 0) out vec4 out_color;
+
+layout(set = 0, binding = 0) uniform sampler2D source;
+layout(set = 0, binding = 1) uniform sampler2D depth;
+
+// Environment bound to set 1 by the post-process pass.
+// Keep in sync with amethyst_rendy/src/submodules/environment.rs
+layout(std140, set = 1, binding = 0) uniform Projview {
+    mat4 proj;
+    mat4 view;
+    mat4 proj_view;
+};
+
+layout(std140, set = 1, binding = 1) uniform Environment {
+    vec3 ambient_color;
+    vec3 camera_position;
+    int point_light_count;
+    int directional_light_count;
+    int spot_light_count;
+    float environment_map_intensity;
+};
+
+layout(push_constant) uniform FogArgs {
+    float enabled;
+    float mode;
+    float density;
+    float start;
+    vec3 color;
+    float end;
+    float height_falloff;
+    float base_height;
+    float sky;
+};
+
+// Distance at which pixels where nothing was drawn are fogged.
+const float SKY_DISTANCE = 100000.0;
+
+// World space position of the surface drawn at `uv` with depth buffer value `depth`.
+vec3 world_position(vec2 uv, float depth) {
+    vec2 ndc = uv * 2.0 - 1.0;
+    float z = (proj[3][2] - depth * proj[3][3]) / (depth * proj[2][3] - proj[2][2]);
+    float w = proj[2][3] * z + proj[3][3];
+    float x = (ndc.x * w - proj[2][0] * z - proj[3][0]) / proj[0][0];
+    float y = (ndc.y * w - proj[2][1] * z - proj[3][1]) / proj[1][1];
+    // The view matrix is a rigid transform, its inverse is the transposed rotation.
+    return transpose(mat3(view)) * (vec3(x, y, z) - view[3].xyz);
+}
+
+// Density of height fog relative to its density at `base_height`.
+float relative_density(float height) {
+    return exp(min(-height_falloff * (height - base_height), 80.0));
+}
+
+void main() {
+    vec4 source_color = texture(source, tex_coord);
+    float d = texture(depth, tex_coord).r;
+    if (enabled < 0.5 || (d <= 0.0 && sky < 0.5)) {
+        out_color = source_color;
+        return;
+    }
+
+    vec3 to_surface;
+    if (d > 0.0) {
+        to_surface = world_position(tex_coord, d) - camera_position;
+    } else {
+        // Depth 1 is on the near plane, giving the direction of the view ray.
+        to_surface = normalize(world_position(tex_coord, 1.0) - camera_position) * SKY_DISTANCE;
+    }
+
+    // Scale the distance by the average density of height fog along the view ray.
+    float distance = length(to_surface);
+    if (height_falloff > 0.0) {
+        float rise = height_falloff * to_surface.y;
+        float camera_density = relative_density(camera_position.y);
+        if (abs(rise) > 0.0001) {
+            distance *= (camera_density - relative_density(camera_position.y + to_surface.y)) / rise;
+        } else {
+            distance *= camera_density;
+        }
+    }
+
+    float amount;
+    if (mode < 0.5) {
+        amount = clamp((distance - start) / max(end - start, 0.0001), 0.0, 1.0);
+    } else if (mode < 1.5) {
+        amount = 1.0 - exp(-density * distance);
+    } else {
+        float x = density * distance;
+        amount = 1.0 - exp(-x * x);
+    }
+    out_color = vec4(mix(source_color.rgb, color, amount), source_color.a);
+}
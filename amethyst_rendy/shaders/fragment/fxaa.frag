@@ -0,0 +1,142 @@
+#version 450
+
+layout(location = 0) in vec2 tex_coord;
+
+layout(location = 0) out vec4 out_color;
+
+layout(set = 0, binding = 0) uniform sampler2D source;
+
+layout(push_constant) uniform FxaaArgs {
+    float enabled;
+    float edge_threshold;
+    float edge_threshold_min;
+    float subpixel;
+};
+
+const int SEARCH_STEPS = 10;
+const float SEARCH_STEP_SCALE[SEARCH_STEPS] = float[](
+    1.0, 1.5, 2.0, 2.0, 2.0, 2.0, 4.0, 8.0, 8.0, 8.0
+);
+
+// Perceptual luma of a linear color.
+float luma(vec3 color) {
+    return sqrt(dot(color, vec3(0.299, 0.587, 0.114)));
+}
+
+float luma_at(vec2 uv) {
+    return luma(textureLod(source, uv, 0.0).rgb);
+}
+
+void main() {
+    vec4 center = textureLod(source, tex_coord, 0.0);
+    if (enabled < 0.5) {
+        out_color = center;
+        return;
+    }
+
+    vec2 texel = 1.0 / vec2(textureSize(source, 0));
+
+    float luma_m = luma(center.rgb);
+    float luma_n = luma(textureLodOffset(source, tex_coord, 0.0, ivec2(0, -1)).rgb);
+    float luma_s = luma(textureLodOffset(source, tex_coord, 0.0, ivec2(0, 1)).rgb);
+    float luma_e = luma(textureLodOffset(source, tex_coord, 0.0, ivec2(1, 0)).rgb);
+    float luma_w = luma(textureLodOffset(source, tex_coord, 0.0, ivec2(-1, 0)).rgb);
+
+    float luma_max = max(luma_m, max(max(luma_n, luma_s), max(luma_e, luma_w)));
+    float luma_min = min(luma_m, min(min(luma_n, luma_s), min(luma_e, luma_w)));
+    float contrast = luma_max - luma_min;
+    if (contrast < max(edge_threshold_min, luma_max * edge_threshold)) {
+        out_color = center;
+        return;
+    }
+
+    float luma_nw = luma(textureLodOffset(source, tex_coord, 0.0, ivec2(-1, -1)).rgb);
+    float luma_ne = luma(textureLodOffset(source, tex_coord, 0.0, ivec2(1, -1)).rgb);
+    float luma_sw = luma(textureLodOffset(source, tex_coord, 0.0, ivec2(-1, 1)).rgb);
+    float luma_se = luma(textureLodOffset(source, tex_coord, 0.0, ivec2(1, 1)).rgb);
+
+    // Sub-pixel blend factor from the contrast of the 3x3 neighbourhood average.
+    float average = (2.0 * (luma_n + luma_s + luma_e + luma_w) + luma_nw + luma_ne + luma_sw + luma_se) / 12.0;
+    float subpixel_blend = clamp(abs(average - luma_m) / contrast, 0.0, 1.0);
+    subpixel_blend = smoothstep(0.0, 1.0, subpixel_blend);
+    subpixel_blend = subpixel_blend * subpixel_blend * subpixel;
+
+    // Edge orientation.
+    float horizontal = abs(luma_nw + luma_ne - 2.0 * luma_n)
+        + 2.0 * abs(luma_w + luma_e - 2.0 * luma_m)
+        + abs(luma_sw + luma_se - 2.0 * luma_s);
+    float vertical = abs(luma_nw + luma_sw - 2.0 * luma_w)
+        + 2.0 * abs(luma_n + luma_s - 2.0 * luma_m)
+        + abs(luma_ne + luma_se - 2.0 * luma_e);
+    bool is_horizontal = horizontal >= vertical;
+
+    // Pick the side of the edge with the steeper gradient.
+    float luma_pos = is_horizontal ? luma_s : luma_e;
+    float luma_neg = is_horizontal ? luma_n : luma_w;
+    float gradient_pos = abs(luma_pos - luma_m);
+    float gradient_neg = abs(luma_neg - luma_m);
+    float step_length = is_horizontal ? texel.y : texel.x;
+    float luma_opposite;
+    float gradient;
+    if (gradient_pos >= gradient_neg) {
+        luma_opposite = luma_pos;
+        gradient = gradient_pos;
+    } else {
+        luma_opposite = luma_neg;
+        gradient = gradient_neg;
+        step_length = -step_length;
+    }
+
+    // Walk along the edge in both directions until its ends.
+    vec2 edge_uv = tex_coord;
+    if (is_horizontal) {
+        edge_uv.y += step_length * 0.5;
+    } else {
+        edge_uv.x += step_length * 0.5;
+    }
+    vec2 edge_step = is_horizontal ? vec2(texel.x, 0.0) : vec2(0.0, texel.y);
+    float edge_luma = (luma_m + luma_opposite) * 0.5;
+    float gradient_threshold = gradient * 0.25;
+
+    vec2 uv_pos = edge_uv + edge_step;
+    vec2 uv_neg = edge_uv - edge_step;
+    float delta_pos = luma_at(uv_pos) - edge_luma;
+    float delta_neg = luma_at(uv_neg) - edge_luma;
+    bool done_pos = abs(delta_pos) >= gradient_threshold;
+    bool done_neg = abs(delta_neg) >= gradient_threshold;
+    for (int i = 1; i < SEARCH_STEPS && !(done_pos && done_neg); i++) {
+        if (!done_pos) {
+            uv_pos += edge_step * SEARCH_STEP_SCALE[i];
+            delta_pos = luma_at(uv_pos) - edge_luma;
+            done_pos = abs(delta_pos) >= gradient_threshold;
+        }
+        if (!done_neg) {
+            uv_neg -= edge_step * SEARCH_STEP_SCALE[i];
+            delta_neg = luma_at(uv_neg) - edge_luma;
+            done_neg = abs(delta_neg) >= gradient_threshold;
+        }
+    }
+
+    float distance_pos = is_horizontal ? uv_pos.x - tex_coord.x : uv_pos.y - tex_coord.y;
+    float distance_neg = is_horizontal ? tex_coord.x - uv_neg.x : tex_coord.y - uv_neg.y;
+    bool closer_pos = distance_pos <= distance_neg;
+    float distance = min(distance_pos, distance_neg);
+    float edge_length = distance_pos + distance_neg;
+
+    // Only blend when the center is on the darker or brighter side the closest end agrees with.
+    bool center_smaller = luma_m - edge_luma < 0.0;
+    float end_delta = closer_pos ? delta_pos : delta_neg;
+    float edge_blend = (end_delta < 0.0) != center_smaller
+        ? 0.5 - distance / edge_length
+        : 0.0;
+
+    float blend = max(edge_blend, subpixel_blend);
+    vec2 uv = tex_coord;
+    if (is_horizontal) {
+        uv.y += blend * step_length;
+    } else {
+        uv.x += blend * step_length;
+    }
+
+    out_color = vec4(textureLod(source, uv, 0.0).rgb, center.a);
+}
@@ -0,0 +1,72 @@
+#version 450
+
+#include "header/math.frag"
+#include "header/view_args.frag"
+
+layout(std140, set = 1, binding = 0) uniform Material {
+    UvOffset uv_offset;
+    float alpha_cutoff;
+    float emission_strength;
+    uint unlit;
+    float lightmap_intensity;
+    UvTransform albedo_transform;
+    UvTransform emission_transform;
+    UvTransform normal_transform;
+    UvTransform metallic_roughness_transform;
+    UvTransform ambient_occlusion_transform;
+};
+
+layout(set = 1, binding = 1) uniform sampler2D albedo;
+layout(set = 1, binding = 2) uniform sampler2D emission;
+layout(set = 1, binding = 3) uniform sampler2D normal;
+layout(set = 1, binding = 4) uniform sampler2D metallic_roughness;
+layout(set = 1, binding = 5) uniform sampler2D ambient_occlusion;
+
+layout(location = 0) in VertexData {
+    vec3 position;
+    vec3 normal;
+    vec3 tangent;
+    float tang_handedness;
+    vec2 tex_coord;
+    vec4 color;
+} vertex;
+layout(location = 7) in vec2 vertex_lightmap_tex_coord;
+
+// Keep in sync with shaders/fragment/header/deferred.frag
+layout(location = 0) out vec4 out_albedo;
+layout(location = 1) out vec4 out_normal;
+layout(location = 2) out vec4 out_emission;
+
+void main() {
+    clip_fragment(vertex.position);
+    vec2 final_tex_coords   = tex_coords(transform_tex_coords(vertex.tex_coord, vertex_lightmap_tex_coord, albedo_transform), uv_offset);
+    vec4 albedo_alpha       = texture(albedo, final_tex_coords);
+    if(albedo_alpha.a < alpha_cutoff) discard;
+
+    vec2 normal_coords      = tex_coords(transform_tex_coords(vertex.tex_coord, vertex_lightmap_tex_coord, normal_transform), uv_offset);
+    vec2 mr_coords          = tex_coords(transform_tex_coords(vertex.tex_coord, vertex_lightmap_tex_coord, metallic_roughness_transform), uv_offset);
+    vec2 ao_coords          = tex_coords(transform_tex_coords(vertex.tex_coord, vertex_lightmap_tex_coord, ambient_occlusion_transform), uv_offset);
+    vec3 normal             = texture(normal, normal_coords).rgb * 2 - 1;
+    vec2 metallic_roughness = texture(metallic_roughness, mr_coords).bg;
+    float ambient_occlusion = texture(ambient_occlusion, ao_coords).r;
+
+    vec3 vertex_normal = normalize(vertex.normal);
+    vec3 vertex_tangent = normalize(vertex.tangent - vertex_normal * dot(vertex_normal, vertex.tangent));
+    vec3 vertex_bitangent = normalize(cross(vertex_normal, vertex_tangent) * vertex.tang_handedness);
+    mat3 vertex_basis = mat3(vertex_tangent, vertex_bitangent, vertex_normal);
+    normal = normalize(vertex_basis * normal);
+
+    vec2 emission_coords = tex_coords(transform_tex_coords(vertex.tex_coord, vertex_lightmap_tex_coord, emission_transform), uv_offset);
+    vec3 emission = texture(emission, emission_coords).rgb * emission_strength;
+    if (unlit != 0u) {
+        // Unlit surfaces only emit, which leaves just a faint specular highlight when lit.
+        out_albedo = vec4(vec3(0.0), 0.0);
+        out_normal = vec4(normal, 1.0);
+        out_emission = vec4(albedo_alpha.rgb * vertex.color.rgb + emission, 0.0);
+        return;
+    }
+
+    out_albedo = vec4(albedo_alpha.rgb * vertex.color.rgb, ambient_occlusion);
+    out_normal = vec4(normal, metallic_roughness.g);
+    out_emission = vec4(emission, metallic_roughness.r);
+}
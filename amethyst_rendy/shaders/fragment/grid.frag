@@ -0,0 +1,50 @@
+#version 450
+
+layout(std140, set = 0, binding = 0) uniform ViewArgs {
+    uniform mat4 proj;
+    uniform mat4 view;
+    uniform mat4 proj_view;
+};
+
+layout(std140, set = 1, binding = 0) uniform GridArgs {
+    uniform vec4 minor_color;
+    uniform vec4 major_color;
+    uniform float spacing;
+    uniform float major_spacing;
+    uniform float fade_distance;
+};
+
+layout(location = 0) in vec3 near_point;
+layout(location = 1) in vec3 far_point;
+
+layout(location = 0) out vec4 out_color;
+
+// Coverage of grid lines with given spacing, antialiased over one pixel.
+float grid(vec2 position, float cell) {
+    vec2 coord = position / cell;
+    vec2 derivative = fwidth(coord);
+    vec2 lines = abs(fract(coord - 0.5) - 0.5) / derivative;
+    return 1.0 - min(min(lines.x, lines.y), 1.0);
+}
+
+void main() {
+    float t = -near_point.y / (far_point.y - near_point.y);
+    if (t <= 0.0) {
+        discard;
+    }
+    vec3 position = near_point + t * (far_point - near_point);
+
+    vec4 clip = proj_view * vec4(position, 1.0);
+    gl_FragDepth = clip.z / clip.w;
+
+    vec3 camera = inverse(view)[3].xyz;
+    float fade = 1.0 - smoothstep(0.0, fade_distance, distance(camera.xz, position.xz));
+
+    float minor = grid(position.xz, spacing);
+    float major = grid(position.xz, major_spacing);
+    vec4 color = mix(vec4(minor_color.rgb, minor_color.a * minor), major_color, major);
+    if (color.a * fade <= 0.0) {
+        discard;
+    }
+    out_color = vec4(color.rgb, color.a * fade);
+}
@@ -0,0 +1,60 @@
+// Debug view fragment shader body, shared by the variants for each vertex layout.
+// Define VERTEX_NORMAL before including when the vertex data has a normal, and
+// VERTEX_TANGENT when it also has a tangent.
+
+#include "math.frag"
+#include "view_args.frag"
+
+layout(std140, set = 1, binding = 0) uniform Material {
+    UvOffset uv_offset;
+    float alpha_cutoff;
+};
+
+layout(set = 1, binding = 1) uniform sampler2D albedo;
+
+// Keep in sync with RenderMode in amethyst_rendy/src/render_mode.rs
+layout(push_constant) uniform DebugViewArgs {
+    uint mode;
+};
+
+const uint MODE_NORMALS = 3;
+const uint MODE_OVERDRAW = 4;
+
+// Added to the image for every fragment in overdraw mode.
+const vec3 OVERDRAW_COLOR = vec3(0.08, 0.04, 0.02);
+
+layout(location = 0) in VertexData {
+    vec3 position;
+#ifdef VERTEX_NORMAL
+    vec3 normal;
+#endif
+#ifdef VERTEX_TANGENT
+    vec3 tangent;
+    float tang_handedness;
+#endif
+    vec2 tex_coord;
+    vec4 color;
+} vertex;
+
+layout(location = 0) out vec4 out_color;
+
+void main() {
+    clip_fragment(vertex.position);
+    vec4 albedo = texture(albedo, tex_coords(vertex.tex_coord, uv_offset));
+    if(albedo.w < alpha_cutoff) discard;
+
+    if (mode == MODE_OVERDRAW) {
+        out_color = vec4(OVERDRAW_COLOR, 0.0);
+    } else if (mode == MODE_NORMALS) {
+#ifdef VERTEX_NORMAL
+        vec3 normal = normalize(vertex.normal);
+#else
+        vec3 normal = normalize(cross(dFdy(vertex.position), dFdx(vertex.position)));
+#endif
+        out_color = vec4(normal * 0.5 + 0.5, 1.0);
+    } else {
+        // Premultiplied, as blended by the transparent passes.
+        vec4 color = albedo * vertex.color;
+        out_color = vec4(color.rgb * color.a, color.a);
+    }
+}
@@ -0,0 +1,76 @@
+#ifndef DEFERRED_FRAG
+#define DEFERRED_FRAG
+
+// G-buffer of the deferred shading path, set 1.
+// Keep in sync with amethyst_rendy/src/pass/deferred.rs
+
+layout(std140, set = 0, binding = 0) uniform Projview {
+    mat4 proj;
+    mat4 view;
+    mat4 proj_view;
+};
+
+// Albedo in `rgb` and material ambient occlusion in `a`.
+layout(set = 1, binding = 0) uniform sampler2D gbuffer_albedo;
+// World space normal in `xyz` and roughness in `a`, a zero normal where nothing was drawn.
+layout(set = 1, binding = 1) uniform sampler2D gbuffer_normal;
+// Emission in `rgb` and metallic in `a`.
+layout(set = 1, binding = 2) uniform sampler2D gbuffer_emission;
+layout(set = 1, binding = 3) uniform sampler2D gbuffer_depth;
+
+#ifdef DECALS
+// Decals drawn over the G-buffer, with premultiplied alpha.
+// Albedo in `rgb` and coverage in `a`.
+layout(set = 1, binding = 4) uniform sampler2D decal_albedo;
+// World space normal in `xyz` and its weight in `a`.
+layout(set = 1, binding = 5) uniform sampler2D decal_normal;
+#endif
+
+struct Surface {
+    vec3 position;
+    float depth;
+    vec3 albedo;
+    float ambient_occlusion;
+    vec3 normal;
+    float roughness;
+    vec3 emission;
+    float metallic;
+};
+
+// World space position of the surface drawn at `uv` with depth buffer value `depth`.
+vec3 world_position(vec2 uv, float depth) {
+    vec2 ndc = uv * 2.0 - 1.0;
+    float z = (proj[3][2] - depth * proj[3][3]) / (depth * proj[2][3] - proj[2][2]);
+    float w = proj[2][3] * z + proj[3][3];
+    float x = (ndc.x * w - proj[2][0] * z - proj[3][0]) / proj[0][0];
+    float y = (ndc.y * w - proj[2][1] * z - proj[3][1]) / proj[1][1];
+    // The view matrix is a rigid transform, its inverse is the transposed rotation.
+    return transpose(mat3(view)) * (vec3(x, y, z) - view[3].xyz);
+}
+
+// Returns `false` where no surface was drawn.
+bool read_surface(vec2 uv, out Surface surface) {
+    vec4 normal_roughness = texture(gbuffer_normal, uv);
+    if (dot(normal_roughness.xyz, normal_roughness.xyz) < 0.01) {
+        return false;
+    }
+    vec4 albedo_occlusion = texture(gbuffer_albedo, uv);
+    vec4 emission_metallic = texture(gbuffer_emission, uv);
+    surface.depth = texture(gbuffer_depth, uv).r;
+    surface.position = world_position(uv, surface.depth);
+    surface.albedo = albedo_occlusion.rgb;
+    surface.ambient_occlusion = albedo_occlusion.a;
+    surface.normal = normalize(normal_roughness.xyz);
+    surface.roughness = normal_roughness.a;
+    surface.emission = emission_metallic.rgb;
+    surface.metallic = emission_metallic.a;
+#ifdef DECALS
+    vec4 decal = texture(decal_albedo, uv);
+    surface.albedo = surface.albedo * (1.0 - decal.a) + decal.rgb;
+    vec4 decal_normal_weight = texture(decal_normal, uv);
+    surface.normal = normalize(surface.normal * (1.0 - decal_normal_weight.a) + decal_normal_weight.xyz);
+#endif
+    return true;
+}
+
+#endif
@@ -0,0 +1,62 @@
+// Deferred lighting shader body, shared by the plain and decal variants.
+// Define DECALS before including to blend the decal images over the G-buffer.
+
+#include "math.frag"
+#include "environment.frag"
+#include "deferred.frag"
+
+#define ENVIRONMENT_MAP_SET 2
+#include "environment_map.frag"
+
+#include "pbr_light.frag"
+
+layout(location = 0) in vec2 tex_coord;
+
+layout(location = 0) out vec4 out_color;
+
+// Ambient, environment, directional and spot lighting of the G-buffer.
+// Point lights are accumulated on top by `deferred_point_light.frag`.
+void main() {
+    Surface surface;
+    if (!read_surface(tex_coord, surface)) discard;
+
+    float roughness2 = surface.roughness * surface.roughness;
+    vec3 fresnel_base = mix(vec3(0.04), surface.albedo, surface.metallic);
+    vec3 view_direction = normalize(camera_position - surface.position);
+
+    vec3 lighted = vec3(0.0);
+    for (int i = 0; i < directional_light_count; i++) {
+        lighted += compute_light(vec3(dlight[i].intensity),
+                                 dlight[i].color,
+                                 view_direction,
+                                 -normalize(dlight[i].direction),
+                                 surface.albedo,
+                                 surface.normal,
+                                 roughness2,
+                                 surface.metallic,
+                                 fresnel_base);
+    }
+
+    for (int i = 0; i < spot_light_count; i++) {
+        lighted += compute_light(vec3(spot_attenuation(slight[i], surface.position)),
+                                 slight[i].color,
+                                 view_direction,
+                                 normalize(slight[i].position - surface.position),
+                                 surface.albedo,
+                                 surface.normal,
+                                 roughness2,
+                                 surface.metallic,
+                                 fresnel_base);
+    }
+
+    vec3 ambient;
+    if (environment_map_intensity > 0.0) {
+        ambient = environment_light(view_direction, surface.albedo, surface.normal, surface.roughness, surface.metallic, fresnel_base)
+            * surface.ambient_occlusion * environment_map_intensity;
+    } else {
+        ambient = ambient_color * surface.albedo * surface.ambient_occlusion;
+    }
+
+    out_color = vec4(ambient + lighted + surface.emission, 1.0);
+    gl_FragDepth = surface.depth;
+}
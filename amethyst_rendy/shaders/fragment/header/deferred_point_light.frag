@@ -0,0 +1,45 @@
+// Deferred point light shader body, shared by the plain and decal variants.
+// Define DECALS before including to blend the decal images over the G-buffer.
+
+#include "math.frag"
+#include "environment.frag"
+#include "deferred.frag"
+
+#define ENVIRONMENT_MAP_SET 2
+#include "environment_map.frag"
+
+#include "pbr_light.frag"
+
+layout(location = 0) flat in vec3 light_position;
+layout(location = 1) flat in float light_radius;
+layout(location = 2) flat in vec3 light_color;
+layout(location = 3) flat in float light_intensity;
+layout(location = 4) flat in float light_smoothness;
+
+layout(location = 0) out vec4 out_color;
+
+void main() {
+    vec2 uv = gl_FragCoord.xy / vec2(textureSize(gbuffer_depth, 0));
+    Surface surface;
+    if (!read_surface(uv, surface)) discard;
+
+    vec3 light_vec = light_position - surface.position;
+    float distance = length(light_vec);
+    if (distance >= light_radius) discard;
+
+    float attenuation = point_attenuation(light_intensity, distance, light_radius, light_smoothness);
+
+    float roughness2 = surface.roughness * surface.roughness;
+    vec3 fresnel_base = mix(vec3(0.04), surface.albedo, surface.metallic);
+
+    vec3 light = compute_light(vec3(attenuation),
+                               light_color,
+                               normalize(camera_position - surface.position),
+                               light_vec / distance,
+                               surface.albedo,
+                               surface.normal,
+                               roughness2,
+                               surface.metallic,
+                               fresnel_base);
+    out_color = vec4(light, 0.0);
+}
@@ -4,8 +4,10 @@
 
 struct PointLight {
     vec3 position;
+    float radius;
     vec3 color;
     float intensity;
+    float smoothness;
 };
 
 struct DirectionalLight {
@@ -22,6 +24,9 @@ struct SpotLight {
     float intensity;
     float range;
     float smoothness;
+    int shadow_index;
+    float shadow_bias;
+    float shadow_softness;
 };
 
 layout(std140, set = 0, binding = 1) uniform Environment {
@@ -30,10 +35,11 @@ layout(std140, set = 0, binding = 1) uniform Environment {
     int point_light_count;
     int directional_light_count;
     int spot_light_count;
+    float environment_map_intensity;
 };
 
 layout(std140, set = 0, binding = 2) uniform PointLights {
-    PointLight plight[128];
+    PointLight plight[256];
 };
 
 layout(std140, set = 0, binding = 3) uniform DirectionalLights {
@@ -42,4 +48,10 @@ layout(std140, set = 0, binding = 3) uniform DirectionalLights {
 
 layout(std140, set = 0, binding = 4) uniform SpotLights {
     SpotLight slight[128];
-};
\ No newline at end of file
+};
+
+layout(std140, set = 0, binding = 5) uniform SpotShadows {
+    mat4 spot_shadow_proj_view[4];
+    vec2 spot_shadow_texel_size;
+    int spot_shadow_columns;
+};
@@ -0,0 +1,32 @@
+#ifndef ENVIRONMENT_MAP_FRAG
+#define ENVIRONMENT_MAP_FRAG
+
+// Environment cube maps, black without an environment map.
+// Define ENVIRONMENT_MAP_SET before including.
+// Keep in sync with amethyst_rendy/src/submodules/environment_map.rs
+
+#define SPECULAR_LEVELS 5
+layout(set = ENVIRONMENT_MAP_SET, binding = 0) uniform samplerCube irradiance_map;
+layout(set = ENVIRONMENT_MAP_SET, binding = 1) uniform samplerCubeArray specular_map;
+
+// Radiance prefiltered for `roughness`, blended between the two closest specular levels.
+vec3 environment_specular(vec3 direction, float roughness) {
+    float level = roughness * float(SPECULAR_LEVELS - 1);
+    float lower = floor(level);
+    float upper = min(lower + 1.0, float(SPECULAR_LEVELS - 1));
+    vec3 lower_radiance = texture(specular_map, vec4(direction, lower)).rgb;
+    vec3 upper_radiance = texture(specular_map, vec4(direction, upper)).rgb;
+    return mix(lower_radiance, upper_radiance, level - lower);
+}
+
+// Analytical approximation of the split-sum environment BRDF, returning scale and bias
+// of the fresnel base.
+vec2 environment_brdf(float NdotV, float roughness) {
+    const vec4 c0 = vec4(-1.0, -0.0275, -0.572, 0.022);
+    const vec4 c1 = vec4(1.0, 0.0425, 1.04, -0.04);
+    vec4 r = roughness * c0 + c1;
+    float a004 = min(r.x * r.x, exp2(-9.28 * NdotV)) * r.x + r.y;
+    return vec2(-1.04, 1.04) * a004 + r.zw;
+}
+
+#endif
@@ -0,0 +1,27 @@
+// Flat fragment shader body, shared by the plain and order-independent transparency variants.
+// Define OIT before including to accumulate weighted blended transparency.
+
+#include "math.frag"
+#include "view_args.frag"
+
+layout(std140, set = 1, binding = 0) uniform Material {
+    UvOffset uv_offset;
+    float alpha_cutoff;
+};
+
+layout(set = 1, binding = 1) uniform sampler2D albedo;
+
+layout(location = 0) in VertexData {
+    vec3 position;
+    vec2 tex_coord;
+    vec4 color;
+} vertex;
+
+#include "transparency.frag"
+
+void main() {
+    clip_fragment(vertex.position);
+    vec4 albedo = texture(albedo, tex_coords(vertex.tex_coord, uv_offset));
+    if(albedo.w < alpha_cutoff) discard;
+    write_color(albedo * vertex.color);
+}
@@ -0,0 +1,37 @@
+#ifndef LIGHT_CLUSTER_FRAG
+#define LIGHT_CLUSTER_FRAG
+
+// Clustered light lists of the environment, set 0.
+// Include "environment.frag" before including.
+// Keep in sync with amethyst_rendy/src/light_cluster.rs
+
+#define CLUSTER_COLUMNS 16
+#define CLUSTER_ROWS 9
+#define CLUSTER_SLICES 24
+#define CLUSTER_NEAR 0.1
+#define CLUSTER_FAR 1000.0
+
+#include "view_args.frag"
+
+// Offset into `light_indices` and count of the lights reaching each cluster.
+// Indices from `point_light_count` on refer to spot lights.
+layout(std430, set = 0, binding = 6) readonly buffer LightClusters {
+    uvec2 cluster_lights[CLUSTER_COLUMNS * CLUSTER_ROWS * CLUSTER_SLICES];
+    uint light_indices[];
+};
+
+// Lights of the cluster containing the world space `position`.
+uvec2 light_cluster(vec3 position) {
+    vec4 clip = proj_view * vec4(position, 1.0);
+    vec2 cell = clamp((clip.xy / clip.w) * 0.5 + 0.5, 0.0, 1.0) * vec2(CLUSTER_COLUMNS, CLUSTER_ROWS);
+    int column = min(int(cell.x), CLUSTER_COLUMNS - 1);
+    int row = min(int(cell.y), CLUSTER_ROWS - 1);
+
+    float distance = -(view * vec4(position, 1.0)).z;
+    float slice = log(max(distance, CLUSTER_NEAR) / CLUSTER_NEAR) / log(CLUSTER_FAR / CLUSTER_NEAR);
+    int depth_slice = min(int(slice * CLUSTER_SLICES), CLUSTER_SLICES - 1);
+
+    return cluster_lights[(depth_slice * CLUSTER_ROWS + row) * CLUSTER_COLUMNS + column];
+}
+
+#endif
@@ -0,0 +1,56 @@
+#ifndef LIGHT_PROBE_FRAG
+#define LIGHT_PROBE_FRAG
+
+// Baked light probes, bound along with the environment cube maps.
+// Define ENVIRONMENT_MAP_SET before including.
+// Keep in sync with amethyst_rendy/src/submodules/environment_map.rs
+
+layout(std430, set = ENVIRONMENT_MAP_SET, binding = 5) readonly buffer LightProbes {
+    vec3 light_probe_origin;
+    float light_probe_intensity;
+    vec3 light_probe_spacing;
+    // Zero without light probes.
+    int light_probe_count;
+    ivec4 light_probe_counts;
+    // Nine spherical harmonics coefficients of every probe of the grid,
+    // with x varying fastest, then y and z.
+    vec4 light_probe_coefficients[];
+};
+
+// Irradiance reflected by a white Lambertian surface facing `normal`, from the spherical
+// harmonics of the radiance at `origin` interpolated between the eight closest probes.
+vec3 light_probe_irradiance(vec3 origin, vec3 normal) {
+    ivec3 last_probe = light_probe_counts.xyz - 1;
+    vec3 cell = clamp((origin - light_probe_origin) / light_probe_spacing, vec3(0.0), vec3(last_probe));
+    ivec3 lower = min(ivec3(cell), last_probe);
+    ivec3 upper = min(lower + 1, last_probe);
+    vec3 fraction = cell - vec3(lower);
+
+    // Basis functions convolved with the cosine lobe and divided by pi.
+    float basis[9] = float[](
+        0.282095,
+        0.325735 * normal.y,
+        0.325735 * normal.z,
+        0.325735 * normal.x,
+        0.273137 * normal.x * normal.y,
+        0.273137 * normal.y * normal.z,
+        0.078848 * (3.0 * normal.z * normal.z - 1.0),
+        0.273137 * normal.x * normal.z,
+        0.136569 * (normal.x * normal.x - normal.y * normal.y)
+    );
+
+    vec3 irradiance = vec3(0.0);
+    for (int corner = 0; corner < 8; corner++) {
+        bvec3 is_upper = bvec3(corner & 1, corner & 2, corner & 4);
+        ivec3 probe = mix(lower, upper, is_upper);
+        vec3 weights = mix(1.0 - fraction, fraction, is_upper);
+        float weight = weights.x * weights.y * weights.z;
+        int first = ((probe.z * light_probe_counts.y + probe.y) * light_probe_counts.x + probe.x) * 9;
+        for (int i = 0; i < 9; i++) {
+            irradiance += light_probe_coefficients[first + i].rgb * basis[i] * weight;
+        }
+    }
+    return max(irradiance, vec3(0.0)) * light_probe_intensity;
+}
+
+#endif
@@ -15,6 +15,17 @@ float tex_coord(float coord, vec2 offset) {
 vec2 tex_coords(vec2 coord, UvOffset offset) {
     return vec2(tex_coord(coord.x, offset.u_offset), tex_coord(coord.y, offset.v_offset));
 }
+
+// Affine transform of texture coordinates. The w component of `u` selects the coordinates.
+struct UvTransform {
+    vec4 u;
+    vec4 v;
+};
+
+vec2 transform_tex_coords(vec2 coord, vec2 coord1, UvTransform transform) {
+    vec3 source = vec3(transform.u.w > 0.5 ? coord1 : coord, 1.0);
+    return vec2(dot(transform.u.xyz, source), dot(transform.v.xyz, source));
+}
  
 vec3 schlick_fresnel(float HdotV, vec3 fresnel_base) {
     return fresnel_base + (1.0 - fresnel_base) * pow(1.0 - HdotV, 5.0);
@@ -0,0 +1,202 @@
+// PBR fragment shader body, shared by the plain, shadowed and order-independent transparency
+// variants.
+// Define SHADOWS before including to sample the directional light shadow map.
+// Define OIT before including to accumulate weighted blended transparency.
+
+#include "math.frag"
+
+#include "environment.frag"
+#include "light_cluster.frag"
+#include "view_args.frag"
+
+#define ENVIRONMENT_MAP_SET 3
+#include "environment_map.frag"
+
+#ifdef SHADOWS
+#include "shadow.frag"
+#endif
+
+layout(std140, set = 1, binding = 0) uniform Material {
+    UvOffset uv_offset;
+    float alpha_cutoff;
+    float emission_strength;
+    uint unlit;
+    float lightmap_intensity;
+    UvTransform albedo_transform;
+    UvTransform emission_transform;
+    UvTransform normal_transform;
+    UvTransform metallic_roughness_transform;
+    UvTransform ambient_occlusion_transform;
+};
+
+layout(set = 1, binding = 1) uniform sampler2D albedo;
+layout(set = 1, binding = 2) uniform sampler2D emission;
+layout(set = 1, binding = 3) uniform sampler2D normal;
+layout(set = 1, binding = 4) uniform sampler2D metallic_roughness;
+layout(set = 1, binding = 5) uniform sampler2D ambient_occlusion;
+layout(set = 1, binding = 6) uniform sampler2D cavity;
+layout(set = 1, binding = 7) uniform sampler2D lightmap;
+
+
+// Screen-space ambient occlusion, white without `RenderSsao`.
+layout(set = 4, binding = 0) uniform sampler2D screen_ambient_occlusion;
+
+layout(location = 0) in VertexData {
+    vec3 position;
+    vec3 normal;
+    vec3 tangent;
+    float tang_handedness;
+    vec2 tex_coord;
+    vec4 color;
+} vertex;
+layout(location = 6) flat in vec3 vertex_origin;
+layout(location = 7) in vec2 vertex_lightmap_tex_coord;
+
+#include "transparency.frag"
+
+
+#include "pbr_light.frag"
+#include "reflection_probe.frag"
+#include "light_probe.frag"
+
+void main() {
+    clip_fragment(vertex.position);
+    vec2 final_tex_coords   = tex_coords(transform_tex_coords(vertex.tex_coord, vertex_lightmap_tex_coord, albedo_transform), uv_offset);
+    vec4 albedo_alpha       = texture(albedo, final_tex_coords);
+    float alpha             = albedo_alpha.a;
+    if(alpha < alpha_cutoff) discard;
+
+    vec3 albedo             = albedo_alpha.rgb;
+    vec2 emission_coords    = tex_coords(transform_tex_coords(vertex.tex_coord, vertex_lightmap_tex_coord, emission_transform), uv_offset);
+    vec3 emission           = texture(emission, emission_coords).rgb * emission_strength;
+    if (unlit != 0u) {
+        write_color(vec4(albedo + emission, alpha) * vertex.color);
+        return;
+    }
+
+    vec2 normal_coords      = tex_coords(transform_tex_coords(vertex.tex_coord, vertex_lightmap_tex_coord, normal_transform), uv_offset);
+    vec2 mr_coords          = tex_coords(transform_tex_coords(vertex.tex_coord, vertex_lightmap_tex_coord, metallic_roughness_transform), uv_offset);
+    vec2 ao_coords          = tex_coords(transform_tex_coords(vertex.tex_coord, vertex_lightmap_tex_coord, ambient_occlusion_transform), uv_offset);
+    vec3 normal             = texture(normal, normal_coords).rgb;
+    vec2 metallic_roughness = texture(metallic_roughness, mr_coords).bg;
+    float ambient_occlusion = texture(ambient_occlusion, ao_coords).r;
+    vec2 screen_coords      = gl_FragCoord.xy / vec2(textureSize(screen_ambient_occlusion, 0));
+    ambient_occlusion      *= texture(screen_ambient_occlusion, screen_coords).r;
+    // TODO: Use cavity
+    // float cavity            = texture(cavity, tex_coords(vertex.tex_coord, final_tex_coords).r;
+    float metallic          = metallic_roughness.r;
+    float roughness         = metallic_roughness.g;
+
+    // normal conversion
+    normal = normal * 2 - 1;
+
+    float roughness2 = roughness * roughness;
+    vec3 fresnel_base = mix(vec3(0.04), albedo, metallic);
+
+    vec3 vertex_normal = normalize(vertex.normal);
+    vec3 vertex_tangent = normalize(vertex.tangent - vertex_normal * dot(vertex_normal, vertex.tangent));
+    vec3 vertex_bitangent = normalize(cross(vertex_normal, vertex_tangent) * vertex.tang_handedness);
+    mat3 vertex_basis = mat3(vertex_tangent, vertex_bitangent, vertex_normal);
+    normal = normalize(vertex_basis * normal);
+
+    vec3 view_direction = normalize(camera_position - vertex.position);
+    vec3 lighted = vec3(0.0);
+    uvec2 cluster = light_cluster(vertex.position);
+    for (uint c = cluster.x; c < cluster.x + cluster.y; c++) {
+        int i = int(light_indices[c]);
+        vec3 light_vec;
+        vec3 light_color;
+        float attenuation;
+        if (i < point_light_count) {
+            light_vec = plight[i].position - vertex.position;
+            light_color = plight[i].color;
+            attenuation = point_attenuation(plight[i].intensity,
+                                            length(light_vec),
+                                            plight[i].radius,
+                                            plight[i].smoothness);
+#ifdef SHADOWS
+            // Shadow casting point lights come first.
+            if (i < point_shadow_count) {
+                attenuation *= point_shadow(i, plight[i].position, vertex.position);
+            }
+#endif
+        } else {
+            i -= point_light_count;
+            light_vec = slight[i].position - vertex.position;
+            light_color = slight[i].color;
+            attenuation = spot_attenuation(slight[i], vertex.position);
+#ifdef SHADOWS
+            if (slight[i].shadow_index >= 0) {
+                attenuation *= spot_shadow(slight[i].shadow_index,
+                                           vertex.position,
+                                           slight[i].shadow_bias,
+                                           slight[i].shadow_softness);
+            }
+#endif
+        }
+
+        vec3 light = compute_light(vec3(attenuation),
+                                   light_color,
+                                   view_direction,
+                                   normalize(light_vec),
+                                   albedo,
+                                   normal,
+                                   roughness2,
+                                   metallic,
+                                   fresnel_base);
+        lighted += light;
+    }
+
+    for (int i = 0; i < directional_light_count; i++) {
+        vec3 light_direction = -normalize(dlight[i].direction);
+        float attenuation = dlight[i].intensity;
+#ifdef SHADOWS
+        // Only the first directional light casts shadows.
+        if (i == 0) {
+            attenuation *= directional_shadow(vertex.position, dot(vertex_normal, light_direction));
+        }
+#endif
+
+        vec3 light = compute_light(vec3(attenuation),
+                                   dlight[i].color,
+                                   view_direction,
+                                   light_direction,
+                                   albedo,
+                                   normal,
+                                   roughness2,
+                                   metallic,
+                                   fresnel_base);
+
+        lighted += light;
+    }
+
+    // Diffuse ambient light comes from the lightmap or the light probes when there are some,
+    // specular ambient light from the reflection probe around the mesh or the environment map.
+    vec3 ambient;
+    int probe = reflection_probe(vertex_origin);
+    if (probe >= 0 || environment_map_intensity > 0.0 || light_probe_count > 0 || lightmap_intensity > 0.0) {
+        vec3 reflected = reflect(-view_direction, normal);
+        vec3 irradiance = vec3(0.0);
+        vec3 radiance = vec3(0.0);
+        if (probe >= 0) {
+            irradiance = probe_irradiance(probe, normal);
+            radiance = probe_radiance(probe, vertex.position, reflected, roughness);
+        } else if (environment_map_intensity > 0.0) {
+            irradiance = texture(irradiance_map, normal).rgb * environment_map_intensity;
+            radiance = environment_specular(reflected, roughness) * environment_map_intensity;
+        }
+        if (lightmap_intensity > 0.0) {
+            irradiance = texture(lightmap, vertex_lightmap_tex_coord).rgb * lightmap_intensity;
+        } else if (light_probe_count > 0) {
+            irradiance = light_probe_irradiance(vertex_origin, normal);
+        }
+        float NdotV = max(dot(normal, view_direction), 0.0);
+        ambient = image_based_light(irradiance, radiance, NdotV, albedo, roughness, metallic, fresnel_base)
+            * ambient_occlusion;
+    } else {
+        ambient = ambient_color * albedo * ambient_occlusion;
+    }
+    vec3 color = ambient + lighted + emission;
+
+    write_color(vec4(color, alpha) * vertex.color);
+}
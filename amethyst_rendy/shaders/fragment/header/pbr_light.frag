@@ -0,0 +1,127 @@
+#ifndef PBR_LIGHT_FRAG
+#define PBR_LIGHT_FRAG
+
+// PBR lighting of a surface, shared by forward and deferred shading.
+// Include "environment.frag" and "environment_map.frag" before including.
+
+#include "math.frag"
+
+vec3 fresnel(float HdotV, vec3 fresnel_base) {
+    return fresnel_base + (1.0 - fresnel_base) * pow(1.0 - HdotV, 5.0);
+}
+
+vec3 compute_light(vec3 attenuation,
+                   vec3 light_color,
+                   vec3 view_direction,
+                   vec3 light_direction,
+                   vec3 albedo,
+                   vec3 normal,
+                   float roughness2,
+                   float metallic,
+                   vec3 fresnel_base) {
+
+    vec3 halfway = normalize(view_direction + light_direction);
+    float normal_distribution = ggx_normal_distribution(normal, halfway, roughness2);
+
+    float NdotV = max(dot(normal, view_direction), 0.0);
+    float NdotL = max(dot(normal, light_direction), 0.0);
+    float HdotV = max(dot(halfway, view_direction), 0.0);
+    float geometry = ggx_geometry(NdotV, NdotL, roughness2);
+
+
+    vec3 fresnel = fresnel(HdotV, fresnel_base);
+    vec3 diffuse = vec3(1.0) - fresnel;
+    diffuse *= 1.0 - metallic;
+
+    vec3 nominator = normal_distribution * geometry * fresnel;
+    float denominator = 4 * NdotV * NdotL + 0.0001;
+    vec3 specular = nominator / denominator;
+
+    vec3 resulting_light = (diffuse * albedo / PI + specular) * light_color * attenuation * NdotL;
+    return resulting_light;
+}
+
+// Light reflected from the environment, given the `irradiance` around the surface normal and
+// the `radiance` prefiltered for the roughness around the reflected direction.
+vec3 image_based_light(vec3 irradiance,
+                       vec3 radiance,
+                       float NdotV,
+                       vec3 albedo,
+                       float roughness,
+                       float metallic,
+                       vec3 fresnel_base) {
+    vec3 fresnel = fresnel_base + (max(vec3(1.0 - roughness), fresnel_base) - fresnel_base) * pow(1.0 - NdotV, 5.0);
+    vec3 diffuse = (vec3(1.0) - fresnel) * (1.0 - metallic) * albedo * irradiance;
+
+    vec2 brdf = environment_brdf(NdotV, roughness);
+    vec3 specular = radiance * (fresnel_base * brdf.x + brdf.y);
+    return diffuse + specular;
+}
+
+vec3 environment_light(vec3 view_direction,
+                       vec3 albedo,
+                       vec3 normal,
+                       float roughness,
+                       float metallic,
+                       vec3 fresnel_base) {
+    float NdotV = max(dot(normal, view_direction), 0.0);
+    vec3 reflected = reflect(-view_direction, normal);
+    return image_based_light(texture(irradiance_map, normal).rgb,
+                             environment_specular(reflected, roughness),
+                             NdotV,
+                             albedo,
+                             roughness,
+                             metallic,
+                             fresnel_base);
+}
+
+// Intensity of a point light at `distance`, falling off with the inverse square of the
+// distance and windowed to reach zero at `radius`.
+float point_attenuation(float intensity, float distance, float radius, float smoothness) {
+    float window = clamp(1.0 - pow(distance / max(radius, 0.00001), smoothness), 0.0, 1.0);
+    return intensity * window * window / (distance * distance + 1.0);
+}
+
+// Intensity of spot `light` reaching `position`, without shadows.
+float spot_attenuation(SpotLight light, vec3 position) {
+    vec3 light_vec = light.position - position;
+    vec3 normalized_light_vec = normalize(light_vec);
+
+    // The distance between the current fragment and the "core" of the light
+    float light_length = length(light_vec);
+
+    // The allowed "length", everything after this won't be lit.
+    // Later on we are dividing by this range, so it can't be 0
+    float range = max(light.range, 0.00001);
+
+    // get normalized range, so everything 0..1 could be lit, everything else can't.
+    float normalized_range = light_length / max(0.00001, range);
+
+    // The attenuation for the "range". If we would only consider this, we'd have a
+    // point light instead, so we need to also check for the spot angle and direction.
+    float range_attenuation = max(0.0, 1.0 - normalized_range);
+
+    // this is actually the cosine of the angle, so it can be compared with the
+    // "dotted" frag_angle below a lot cheaper.
+    float spot_angle = max(light.angle, 0.00001);
+    vec3 spot_direction = normalize(light.direction);
+    float smoothness = 1.0 - light.smoothness;
+
+    // Here we check if the current fragment is within the "ring" of the spotlight.
+    float frag_angle = dot(spot_direction, -normalized_light_vec);
+
+    // so that the ring_attenuation won't be > 1
+    frag_angle = max(frag_angle, spot_angle);
+
+    // How much is this outside of the ring? (let's call it "rim")
+    // Also smooth this out.
+    float rim_attenuation = pow(max((1.0 - frag_angle) / (1.0 - spot_angle), 0.00001), smoothness);
+
+    // How much is this inside the "ring"?
+    float ring_attenuation = 1.0 - rim_attenuation;
+
+    // combine the attenuations and intensity
+    return range_attenuation * ring_attenuation * light.intensity;
+}
+
+#endif
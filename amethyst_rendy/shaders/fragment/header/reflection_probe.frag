@@ -0,0 +1,80 @@
+#ifndef REFLECTION_PROBE_FRAG
+#define REFLECTION_PROBE_FRAG
+
+// Reflection probes, bound along with the environment cube maps.
+// Define ENVIRONMENT_MAP_SET and include environment_map.frag before including.
+// Keep in sync with amethyst_rendy/src/submodules/environment_map.rs
+
+#define MAX_REFLECTION_PROBES 16
+
+struct ReflectionProbe {
+    vec3 position;
+    float intensity;
+    vec3 box_min;
+    vec3 box_max;
+};
+
+// Irradiance of every probe, and its SPECULAR_LEVELS prefiltered specular cube maps.
+layout(set = ENVIRONMENT_MAP_SET, binding = 2) uniform samplerCubeArray probe_irradiance_map;
+layout(set = ENVIRONMENT_MAP_SET, binding = 3) uniform samplerCubeArray probe_specular_map;
+
+layout(std140, set = ENVIRONMENT_MAP_SET, binding = 4) uniform ReflectionProbes {
+    ReflectionProbe reflection_probes[MAX_REFLECTION_PROBES];
+    int reflection_probe_count;
+};
+
+// Index of the nearest probe whose box contains `origin`, or -1 if there is none.
+int reflection_probe(vec3 origin) {
+    int nearest = -1;
+    float nearest_distance = 0.0;
+    for (int i = 0; i < reflection_probe_count; i++) {
+        ReflectionProbe probe = reflection_probes[i];
+        if (any(lessThan(origin, probe.box_min)) || any(greaterThan(origin, probe.box_max))) {
+            continue;
+        }
+        float probe_distance = distance(origin, probe.position);
+        if (nearest < 0 || probe_distance < nearest_distance) {
+            nearest = i;
+            nearest_distance = probe_distance;
+        }
+    }
+    return nearest;
+}
+
+// Direction from the probe to where `direction` leaving `position` hits the box of the probe,
+// correcting the parallax of reflections seen away from the probe position.
+vec3 box_projected(ReflectionProbe probe, vec3 position, vec3 direction) {
+    vec3 to_max = (probe.box_max - position) / direction;
+    vec3 to_min = (probe.box_min - position) / direction;
+    vec3 furthest = max(to_max, to_min);
+    float hit = min(min(furthest.x, furthest.y), furthest.z);
+    if (hit <= 0.0) {
+        // Outside of the box, there is nothing to correct.
+        return direction;
+    }
+    return position + direction * hit - probe.position;
+}
+
+// Radiance of `probe` prefiltered for `roughness`, blended between the two closest levels.
+vec3 probe_specular(int probe, vec3 direction, float roughness) {
+    float level = roughness * float(SPECULAR_LEVELS - 1);
+    float lower = floor(level);
+    float upper = min(lower + 1.0, float(SPECULAR_LEVELS - 1));
+    float first = float(probe * SPECULAR_LEVELS);
+    vec3 lower_radiance = texture(probe_specular_map, vec4(direction, first + lower)).rgb;
+    vec3 upper_radiance = texture(probe_specular_map, vec4(direction, first + upper)).rgb;
+    return mix(lower_radiance, upper_radiance, level - lower);
+}
+
+// Irradiance captured by `probe` around `normal`.
+vec3 probe_irradiance(int probe, vec3 normal) {
+    return texture(probe_irradiance_map, vec4(normal, float(probe))).rgb * reflection_probes[probe].intensity;
+}
+
+// Radiance captured by `probe` for `roughness` in the box-projected `direction` from `position`.
+vec3 probe_radiance(int probe, vec3 position, vec3 direction, float roughness) {
+    ReflectionProbe box = reflection_probes[probe];
+    return probe_specular(probe, box_projected(box, position, direction), roughness) * box.intensity;
+}
+
+#endif
@@ -0,0 +1,113 @@
+#ifndef SHADOW_FRAG
+#define SHADOW_FRAG
+
+// Shadow map definition.
+// Sets 5 and 6.
+// Keep in sync with amethyst_rendy/src/submodules/shadow.rs
+
+layout(std140, set = 5, binding = 0) uniform ShadowArgs {
+    mat4 cascade_proj_view[4];
+    mat4 point_proj_view[24];
+    int cascade_count;
+    int pcf_radius;
+    float depth_bias;
+    vec2 texel_size;
+    int point_shadow_count;
+    int point_atlas_rows;
+    float point_depth_bias;
+    vec2 point_texel_size;
+};
+
+layout(set = 6, binding = 0) uniform sampler2D shadow_map;
+layout(set = 6, binding = 1) uniform sampler2D point_shadow_map;
+layout(set = 6, binding = 2) uniform sampler2D spot_shadow_map;
+
+// Percentage-closer filtering of `depth` around `uv`, limited to the atlas tile `bounds`.
+// Depth is reversed, so a fragment is lit when it is not below the stored depth.
+float filter_shadow(sampler2D map, vec2 uv, vec4 bounds, vec2 texel, float depth) {
+    float lit = 0.0;
+    for (int x = -pcf_radius; x <= pcf_radius; x++) {
+        for (int y = -pcf_radius; y <= pcf_radius; y++) {
+            vec2 offset_uv = clamp(uv + vec2(x, y) * texel, bounds.xy, bounds.zw);
+            lit += depth >= texture(map, offset_uv).r ? 1.0 : 0.0;
+        }
+    }
+    float kernel = float(2 * pcf_radius + 1);
+    return lit / (kernel * kernel);
+}
+
+// Returns how much of the first directional light reaches `position`, from 0.0 to 1.0.
+// Cascades are stored side by side in `shadow_map`.
+float directional_shadow(vec3 position, float NdotL) {
+    for (int i = 0; i < cascade_count; i++) {
+        vec4 light_space = cascade_proj_view[i] * vec4(position, 1.0);
+        vec3 ndc = light_space.xyz / light_space.w;
+        if (any(greaterThan(abs(ndc.xy), vec2(1.0)))) {
+            continue;
+        }
+
+        float tile = 1.0 / float(cascade_count);
+        vec2 uv = ndc.xy * 0.5 + 0.5;
+        uv.x = (float(i) + uv.x) * tile;
+        vec4 bounds = vec4(float(i) * tile, 0.0, float(i + 1) * tile, 1.0);
+        bounds += vec4(texel_size, -texel_size) * 0.5;
+        // Surfaces at grazing angles need a larger bias.
+        float bias = depth_bias * (1.0 + 2.0 * (1.0 - clamp(NdotL, 0.0, 1.0)));
+        return filter_shadow(shadow_map, uv, bounds, texel_size, ndc.z + bias);
+    }
+    return 1.0;
+}
+
+// Returns how much of the point light `light` reaches `position`, from 0.0 to 1.0.
+// Each shadow casting light occupies one row of six cube map faces in `point_shadow_map`.
+float point_shadow(int light, vec3 light_position, vec3 position) {
+    vec3 direction = position - light_position;
+    vec3 distance = abs(direction);
+    int face;
+    if (distance.x >= distance.y && distance.x >= distance.z) {
+        face = direction.x > 0.0 ? 0 : 1;
+    } else if (distance.y >= distance.z) {
+        face = direction.y > 0.0 ? 2 : 3;
+    } else {
+        face = direction.z > 0.0 ? 4 : 5;
+    }
+
+    vec4 light_space = point_proj_view[light * 6 + face] * vec4(position, 1.0);
+    vec3 ndc = light_space.xyz / light_space.w;
+
+    vec2 tile = vec2(1.0 / 6.0, 1.0 / float(point_atlas_rows));
+    vec2 origin = vec2(float(face), float(light)) * tile;
+    vec2 uv = origin + clamp(ndc.xy * 0.5 + 0.5, 0.0, 1.0) * tile;
+    vec4 bounds = vec4(origin, origin + tile) + vec4(point_texel_size, -point_texel_size) * 0.5;
+    return filter_shadow(point_shadow_map, uv, bounds, point_texel_size, ndc.z * (1.0 + point_depth_bias));
+}
+
+// Returns how much of the spot light with shadow map tile `index` reaches `position`,
+// from 0.0 to 1.0. Light-space matrices come from the environment set.
+float spot_shadow(int index, vec3 position, float bias, float softness) {
+    vec4 light_space = spot_shadow_proj_view[index] * vec4(position, 1.0);
+    vec3 ndc = light_space.xyz / light_space.w;
+    if (light_space.w <= 0.0 || any(greaterThan(abs(ndc.xy), vec2(1.0)))) {
+        return 1.0;
+    }
+
+    float tile = 1.0 / float(spot_shadow_columns);
+    vec2 uv = ndc.xy * 0.5 + 0.5;
+    uv.x = (float(index) + uv.x) * tile;
+    vec4 bounds = vec4(float(index) * tile, 0.0, float(index + 1) * tile, 1.0);
+    bounds += vec4(spot_shadow_texel_size, -spot_shadow_texel_size) * 0.5;
+
+    // 3x3 kernel spread over `softness` texels.
+    vec2 spread = spot_shadow_texel_size * softness;
+    float depth = ndc.z * (1.0 + bias);
+    float lit = 0.0;
+    for (int x = -1; x <= 1; x++) {
+        for (int y = -1; y <= 1; y++) {
+            vec2 offset_uv = clamp(uv + vec2(x, y) * spread, bounds.xy, bounds.zw);
+            lit += depth >= texture(spot_shadow_map, offset_uv).r ? 1.0 : 0.0;
+        }
+    }
+    return lit / 9.0;
+}
+
+#endif
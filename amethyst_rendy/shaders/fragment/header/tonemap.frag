@@ -0,0 +1,16 @@
+// Filmic curve fitted to the ACES reference rendering transform by Krzysztof Narkowicz.
+vec3 tonemap_aces(vec3 color) {
+    return clamp((color * (2.51 * color + 0.03)) / (color * (2.43 * color + 0.59) + 0.14), 0.0, 1.0);
+}
+
+vec3 tonemap_reinhard(vec3 color) {
+    return color / (1.0 + color);
+}
+
+// Operator indices match `ToneMapOperator::index`.
+vec3 tonemap(vec3 color, float operator_index) {
+    if (operator_index < 0.5) {
+        return tonemap_aces(color);
+    }
+    return tonemap_reinhard(color);
+}
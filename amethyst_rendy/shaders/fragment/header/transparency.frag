@@ -0,0 +1,30 @@
+// Color output of the 3d mesh fragment shaders, written with `write_color`.
+// Define OIT before including to accumulate weighted blended order-independent transparency
+// into an accumulation and a revealage output instead, resolved by `oit_resolve.frag`.
+
+#ifdef OIT
+layout(location = 0) out vec4 out_accumulation;
+layout(location = 1) out float out_revealage;
+
+// Weight favoring near and opaque fragments, from "Weighted Blended Order-Independent
+// Transparency" by McGuire and Bavoil, equation 7.
+float oit_weight(float alpha) {
+    // The w of the clip position, which is the view space depth with a perspective projection.
+    float depth = 1.0 / gl_FragCoord.w;
+    float falloff = 10.0 / (1e-5 + pow(depth / 5.0, 2.0) + pow(depth / 200.0, 6.0));
+    return alpha * clamp(falloff, 1e-2, 3e3);
+}
+
+// Colors are premultiplied by alpha, as with the sorted blending.
+void write_color(vec4 color) {
+    out_accumulation = vec4(color.rgb, color.a) * oit_weight(color.a);
+    // Multiplied into the destination as `1 - alpha` by the blend state.
+    out_revealage = color.a;
+}
+#else
+layout(location = 0) out vec4 out_color;
+
+void write_color(vec4 color) {
+    out_color = color;
+}
+#endif
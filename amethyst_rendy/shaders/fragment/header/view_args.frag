@@ -0,0 +1,20 @@
+#ifndef VIEW_ARGS_FRAG
+#define VIEW_ARGS_FRAG
+
+// Camera of the environment, set 0, binding 0.
+// Keep in sync with ViewArgs in amethyst_rendy/src/pod.rs
+
+layout(std140, set = 0, binding = 0) uniform Projview {
+    mat4 proj;
+    mat4 view;
+    mat4 proj_view;
+    // World space clip plane of the camera, zero without one.
+    vec4 clip_plane;
+};
+
+// Discard fragments at the world space `position` behind the clip plane of the camera.
+void clip_fragment(vec3 position) {
+    if (dot(clip_plane, vec4(position, 1.0)) < 0.0) discard;
+}
+
+#endif
@@ -0,0 +1,40 @@
+#version 450
+
+layout(location = 0) in vec2 tex_coord;
+
+layout(location = 0) out vec4 out_color;
+
+layout(set = 0, binding = 0) uniform sampler2D source;
+// Motion since the previous frame in texture coordinates.
+layout(set = 0, binding = 1) uniform sampler2D velocity;
+
+layout(push_constant) uniform MotionBlurArgs {
+    float enabled;
+    float sample_count;
+    float shutter_scale;
+};
+
+// Per-pixel offset of the samples, hiding steps between them.
+float sample_offset(vec2 pixel) {
+    return fract(52.9829189 * fract(dot(pixel, vec2(0.06711056, 0.00583715))));
+}
+
+void main() {
+    vec4 color = texture(source, tex_coord);
+    vec2 motion = texture(velocity, tex_coord).xy * shutter_scale;
+    int samples = int(sample_count);
+    vec2 pixel = 1.0 / vec2(textureSize(source, 0));
+    if (enabled < 0.5 || samples < 2 || all(lessThan(abs(motion), pixel * 0.5))) {
+        out_color = color;
+        return;
+    }
+
+    // Average samples along the motion, centered on the pixel.
+    float offset = sample_offset(gl_FragCoord.xy);
+    vec3 sum = vec3(0.0);
+    for (int i = 0; i < samples; i++) {
+        float t = (float(i) + offset) / float(samples) - 0.5;
+        sum += textureLod(source, tex_coord - motion * t, 0.0).rgb;
+    }
+    out_color = vec4(sum / float(samples), color.a);
+}
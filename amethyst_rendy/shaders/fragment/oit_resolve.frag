@@ -0,0 +1,27 @@
+#version 450
+
+layout(location = 0) in vec2 tex_coord;
+
+layout(location = 0) out vec4 out_color;
+
+layout(set = 0, binding = 0) uniform sampler2D scene;
+layout(set = 0, binding = 1) uniform sampler2D accumulation;
+layout(set = 0, binding = 2) uniform sampler2D revealage;
+
+void main() {
+    vec4 color = texture(scene, tex_coord);
+    // Product of `1 - alpha` of all transparent fragments drawn to the pixel.
+    float revealed = texture(revealage, tex_coord).r;
+    if (revealed >= 1.0) {
+        out_color = color;
+        return;
+    }
+
+    vec4 accumulated = texture(accumulation, tex_coord);
+    // Very heavy weights overflow half floats.
+    if (any(isinf(accumulated.rgb))) {
+        accumulated.rgb = vec3(accumulated.a);
+    }
+    vec3 average = accumulated.rgb / clamp(accumulated.a, 1e-4, 5e4);
+    out_color = vec4(color.rgb * revealed + average * (1.0 - revealed), color.a);
+}
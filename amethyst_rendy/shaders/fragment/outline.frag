@@ -0,0 +1,47 @@
+#version 450
+
+layout(location = 0) in vec2 tex_coord;
+
+layout(location = 0) out vec4 out_color;
+
+layout(set = 0, binding = 0) uniform sampler2D source;
+// Outline color of the silhouettes.
+layout(set = 0, binding = 1) uniform sampler2D mask_color;
+// Outline width of the silhouettes in pixels, zero where nothing was drawn.
+layout(set = 0, binding = 2) uniform sampler2D mask_width;
+
+layout(push_constant) uniform OutlineArgs {
+    float max_width;
+};
+
+void main() {
+    vec4 color = texture(source, tex_coord);
+    out_color = color;
+
+    // Outlines are only drawn around silhouettes, never over them.
+    if (texture(mask_width, tex_coord).r > 0.0) {
+        return;
+    }
+
+    vec2 texel = 1.0 / vec2(textureSize(mask_width, 0));
+    int radius = int(ceil(max_width));
+    float nearest = max_width + 1.0;
+    vec4 outline = vec4(0.0);
+    for (int y = -radius; y <= radius; y++) {
+        for (int x = -radius; x <= radius; x++) {
+            vec2 offset = vec2(x, y);
+            float dist = length(offset);
+            if (dist >= nearest) {
+                continue;
+            }
+            vec2 coord = tex_coord + offset * texel;
+            float width = texture(mask_width, coord).r;
+            if (width > 0.0 && dist <= width) {
+                nearest = dist;
+                outline = texture(mask_color, coord);
+            }
+        }
+    }
+
+    out_color = vec4(mix(color.rgb, outline.rgb, outline.a), color.a);
+}
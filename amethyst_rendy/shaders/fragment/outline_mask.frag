@@ -0,0 +1,12 @@
+#version 450
+
+layout(location = 0) flat in vec4 color;
+layout(location = 1) flat in float width;
+
+layout(location = 0) out vec4 out_color;
+layout(location = 1) out float out_width;
+
+void main() {
+    out_color = color;
+    out_width = width;
+}
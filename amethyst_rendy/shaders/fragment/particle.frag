@@ -0,0 +1,15 @@
+#version 450
+
+layout(set = 1, binding = 0) uniform sampler2D albedo;
+
+layout(location = 0) in VertexData {
+    vec2 tex_uv;
+    vec4 color;
+    float view_depth;
+} vertex;
+layout(location = 0) out vec4 out_color;
+
+void main() {
+    vec4 color = texture(albedo, vertex.tex_uv) * vertex.color;
+    out_color = vec4(color.rgb * color.a, color.a);
+}
@@ -0,0 +1,37 @@
+#version 450
+
+layout(std140, set = 0, binding = 0) uniform ViewArgs {
+    uniform mat4 proj;
+    uniform mat4 view;
+    uniform mat4 proj_view;
+};
+
+layout(set = 1, binding = 0) uniform sampler2D albedo;
+layout(set = 2, binding = 0) uniform sampler2D depth;
+
+layout(push_constant) uniform SoftParticleArgs {
+    // Distance in front of opaque surfaces over which particles fade out.
+    float fade_distance;
+};
+
+layout(location = 0) in VertexData {
+    vec2 tex_uv;
+    vec4 color;
+    float view_depth;
+} vertex;
+layout(location = 0) out vec4 out_color;
+
+void main() {
+    // Distance of the opaque surface behind the particle, infinite where nothing was drawn.
+    float d = texelFetch(depth, ivec2(gl_FragCoord.xy), 0).r;
+    float z = (proj[3][2] - d * proj[3][3]) / (d * proj[2][3] - proj[2][2]);
+    float surface_depth = d > 0.0 ? -z : 1.0 / 0.0;
+    float fade = clamp((surface_depth - vertex.view_depth) / max(fade_distance, 0.0001), 0.0, 1.0);
+    if (fade <= 0.0) {
+        discard;
+    }
+
+    vec4 color = texture(albedo, vertex.tex_uv) * vertex.color;
+    color.a *= fade;
+    out_color = vec4(color.rgb * color.a, color.a);
+}
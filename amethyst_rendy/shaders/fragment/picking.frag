@@ -0,0 +1,9 @@
+#version 450
+
+layout(location = 0) flat in uint entity;
+
+layout(location = 0) out uint out_entity;
+
+void main() {
+    out_entity = entity;
+}
@@ -0,0 +1,16 @@
+#version 450
+
+layout(early_fragment_tests) in;
+
+layout(location = 0) in VertexData {
+    vec3 position;
+    vec2 tex_coord;
+} vertex;
+
+layout(location = 0) out vec4 out_color;
+
+layout(set = 1, binding = 0) uniform samplerCube cubemap;
+
+void main() {
+    out_color = vec4(texture(cubemap, normalize(vertex.position)).rgb, 1.0);
+}
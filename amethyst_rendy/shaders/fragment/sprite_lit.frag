@@ -0,0 +1,50 @@
+#version 450
+
+#include "header/environment.frag"
+
+layout(set = 1, binding = 0) uniform sampler2D albedo;
+layout(set = 2, binding = 0) uniform sampler2D normal_map;
+
+layout(location = 0) in VertexData {
+    vec3 position;
+    vec2 tangent;
+    vec2 bitangent;
+    vec2 tex_uv;
+    vec4 color;
+} vertex;
+layout(location = 0) out vec4 out_color;
+
+// Windowed falloff reaching zero at `radius`. Unlike 3D lights there is no inverse square term,
+// as 2D scenes are usually measured in pixels.
+float falloff(float distance, float radius, float smoothness) {
+    float window = clamp(1.0 - pow(distance / max(radius, 0.00001), smoothness), 0.0, 1.0);
+    return window * window;
+}
+
+void main() {
+    vec4 color = texture(albedo, vertex.tex_uv) * vertex.color;
+    if (color.a == 0.0) {
+        discard;
+    }
+
+    vec3 tangent_normal = texture(normal_map, vertex.tex_uv).rgb * 2.0 - 1.0;
+    vec3 normal = normalize(
+        tangent_normal.x * vec3(normalize(vertex.tangent), 0.0) +
+        tangent_normal.y * vec3(normalize(vertex.bitangent), 0.0) +
+        tangent_normal.z * vec3(0.0, 0.0, 1.0)
+    );
+
+    vec3 lighting = ambient_color;
+    for (int i = 0; i < point_light_count; i++) {
+        vec3 light_vec = plight[i].position - vertex.position;
+        float diffuse = max(dot(normalize(light_vec), normal), 0.0);
+        float attenuation = falloff(length(light_vec), plight[i].radius, plight[i].smoothness);
+        lighting += plight[i].color * plight[i].intensity * diffuse * attenuation;
+    }
+    for (int i = 0; i < directional_light_count; i++) {
+        float diffuse = max(dot(-normalize(dlight[i].direction), normal), 0.0);
+        lighting += dlight[i].color * dlight[i].intensity * diffuse;
+    }
+
+    out_color = vec4(color.rgb * lighting, color.a);
+}
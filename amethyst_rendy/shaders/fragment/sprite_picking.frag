@@ -0,0 +1,19 @@
+#version 450
+
+layout(set = 1, binding = 0) uniform sampler2D albedo;
+
+layout(location = 0) in VertexData {
+    vec2 tex_uv;
+    float alpha;
+} vertex;
+layout(location = 2) flat in uint entity;
+
+layout(location = 0) out uint out_entity;
+
+void main() {
+    // Fully transparent texels aren't drawn by sprite passes, so they can't be picked.
+    if (texture(albedo, vertex.tex_uv).a * vertex.alpha == 0.0) {
+        discard;
+    }
+    out_entity = entity;
+}
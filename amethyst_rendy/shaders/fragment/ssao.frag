@@ -0,0 +1,90 @@
+#version 450
+
+layout(location = 0) in vec2 tex_coord;
+
+layout(location = 0) out vec4 out_occlusion;
+
+// View space normal in `xyz` and depth in `w`, a zero normal where nothing was drawn.
+layout(set = 0, binding = 0) uniform sampler2D normal_depth;
+
+layout(push_constant) uniform SsaoArgs {
+    mat4 proj;
+    float enabled;
+    float radius;
+    float bias;
+    float intensity;
+    float kernel_size;
+};
+
+const float GOLDEN_ANGLE = 2.39996323;
+
+// Per-pixel rotation of the sampling kernel, repeating every 4x4 pixels
+// so the blur pass can average it out.
+float kernel_rotation(vec2 pixel) {
+    ivec2 cell = ivec2(pixel) & 3;
+    const float pattern[16] = float[](
+        0.0, 8.0, 2.0, 10.0,
+        12.0, 4.0, 14.0, 6.0,
+        3.0, 11.0, 1.0, 9.0,
+        15.0, 7.0, 13.0, 5.0
+    );
+    return pattern[cell.y * 4 + cell.x] / 16.0 * 6.28318531;
+}
+
+// Point `i` of `count` in the unit hemisphere around +z, denser close to the center.
+vec3 kernel_sample(int i, int count, float rotation) {
+    float t = (float(i) + 0.5) / float(count);
+    float cos_theta = sqrt(1.0 - t);
+    float sin_theta = sqrt(t);
+    float phi = float(i) * GOLDEN_ANGLE + rotation;
+    vec3 direction = vec3(cos(phi) * sin_theta, sin(phi) * sin_theta, cos_theta);
+    float scale = mix(0.1, 1.0, t * t);
+    return direction * scale;
+}
+
+// View space position of the surface drawn at `uv` with view space depth `depth`.
+vec3 view_position(vec2 uv, float depth) {
+    vec2 ndc = uv * 2.0 - 1.0;
+    float w = proj[2][3] * depth + proj[3][3];
+    float x = (ndc.x * w - proj[2][0] * depth - proj[3][0]) / proj[0][0];
+    float y = (ndc.y * w - proj[2][1] * depth - proj[3][1]) / proj[1][1];
+    return vec3(x, y, depth);
+}
+
+void main() {
+    vec4 center = texture(normal_depth, tex_coord);
+    if (enabled < 0.5 || dot(center.xyz, center.xyz) < 0.5) {
+        out_occlusion = vec4(1.0);
+        return;
+    }
+
+    vec3 position = view_position(tex_coord, center.w);
+    vec3 normal = normalize(center.xyz);
+
+    // Orient the hemisphere along the normal, rotated around it per pixel.
+    vec3 helper = abs(normal.z) < 0.999 ? vec3(0.0, 0.0, 1.0) : vec3(1.0, 0.0, 0.0);
+    vec3 tangent = normalize(cross(helper, normal));
+    vec3 bitangent = cross(normal, tangent);
+    mat3 basis = mat3(tangent, bitangent, normal);
+    float rotation = kernel_rotation(gl_FragCoord.xy);
+
+    int count = clamp(int(kernel_size), 1, 64);
+    float occlusion = 0.0;
+    for (int i = 0; i < count; i++) {
+        vec3 sample_position = position + basis * kernel_sample(i, count, rotation) * radius;
+
+        vec4 clip = proj * vec4(sample_position, 1.0);
+        vec2 sample_uv = clip.xy / clip.w * 0.5 + 0.5;
+        vec4 surface = texture(normal_depth, sample_uv);
+        if (dot(surface.xyz, surface.xyz) < 0.5) {
+            continue;
+        }
+
+        // Surfaces far in front of the sampled point don't occlude it.
+        float range = smoothstep(0.0, 1.0, radius / abs(position.z - surface.w));
+        occlusion += (surface.w >= sample_position.z + bias ? 1.0 : 0.0) * range;
+    }
+
+    float ambient = 1.0 - occlusion / float(count);
+    out_occlusion = vec4(pow(clamp(ambient, 0.0, 1.0), max(intensity, 0.0)));
+}
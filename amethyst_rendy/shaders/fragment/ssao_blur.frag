@@ -0,0 +1,31 @@
+#version 450
+
+layout(location = 0) in vec2 tex_coord;
+
+layout(location = 0) out vec4 out_occlusion;
+
+layout(set = 0, binding = 0) uniform sampler2D occlusion;
+layout(set = 0, binding = 1) uniform sampler2D normal_depth;
+
+layout(push_constant) uniform SsaoBlurArgs {
+    float radius;
+};
+
+// Averages the 4x4 kernel rotation pattern, ignoring empty pixels and pixels
+// at a different depth so occlusion doesn't bleed across edges.
+void main() {
+    float depth = texture(normal_depth, tex_coord).w;
+    float total = 0.0;
+    float weight = 0.0;
+    for (int y = -2; y < 2; y++) {
+        for (int x = -2; x < 2; x++) {
+            ivec2 offset = ivec2(x, y);
+            vec4 surface = textureOffset(normal_depth, tex_coord, offset);
+            bool drawn = dot(surface.xyz, surface.xyz) > 0.5;
+            float sample_weight = drawn && abs(surface.w - depth) < radius ? 1.0 : 0.0;
+            total += textureOffset(occlusion, tex_coord, offset).r * sample_weight;
+            weight += sample_weight;
+        }
+    }
+    out_occlusion = vec4(weight > 0.0 ? total / weight : 1.0);
+}
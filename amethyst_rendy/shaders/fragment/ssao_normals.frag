@@ -0,0 +1,13 @@
+#version 450
+
+layout(location = 0) in VertexData {
+    vec3 normal;
+    float depth;
+} vertex;
+
+// View space normal and depth, the depth being negative in front of the camera.
+layout(location = 0) out vec4 out_normal_depth;
+
+void main() {
+    out_normal_depth = vec4(normalize(vertex.normal), vertex.depth);
+}
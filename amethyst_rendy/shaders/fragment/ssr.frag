@@ -0,0 +1,140 @@
+#version 450
+
+#define ENVIRONMENT_MAP_SET 2
+#include "header/environment_map.frag"
+
+layout(location = 0) in vec2 tex_coord;
+
+layout(location = 0) out vec4 out_color;
+
+layout(set = 0, binding = 0) uniform sampler2D source;
+// View space normal in `xyz` and depth in `w`, a zero normal where nothing was drawn.
+layout(set = 0, binding = 1) uniform sampler2D normal_depth;
+// Fresnel reflectance at normal incidence in `rgb` and roughness in `a`.
+layout(set = 0, binding = 2) uniform sampler2D reflectance;
+
+// Environment bound to set 1 by the post-process pass.
+// Keep in sync with amethyst_rendy/src/submodules/environment.rs
+layout(std140, set = 1, binding = 0) uniform Projview {
+    mat4 proj;
+    mat4 view;
+    mat4 proj_view;
+};
+
+layout(std140, set = 1, binding = 1) uniform Environment {
+    vec3 ambient_color;
+    vec3 camera_position;
+    int point_light_count;
+    int directional_light_count;
+    int spot_light_count;
+    float environment_map_intensity;
+};
+
+layout(push_constant) uniform SsrArgs {
+    float enabled;
+    float max_steps;
+    float refinement_steps;
+    float max_distance;
+    float thickness;
+    float max_roughness;
+    float intensity;
+};
+
+// View space position of the surface drawn at `uv` with view space depth `depth`.
+vec3 view_position(vec2 uv, float depth) {
+    vec2 ndc = uv * 2.0 - 1.0;
+    float w = proj[2][3] * depth + proj[3][3];
+    float x = (ndc.x * w - proj[2][0] * depth - proj[3][0]) / proj[0][0];
+    float y = (ndc.y * w - proj[2][1] * depth - proj[3][1]) / proj[1][1];
+    return vec3(x, y, depth);
+}
+
+vec2 project(vec3 position) {
+    vec4 clip = proj * vec4(position, 1.0);
+    return clip.xy / clip.w * 0.5 + 0.5;
+}
+
+bool on_screen(vec2 uv) {
+    return all(greaterThanEqual(uv, vec2(0.0))) && all(lessThanEqual(uv, vec2(1.0)));
+}
+
+// Depth of the surface drawn at `uv` minus the depth of `position`, positive when the
+// position is behind the surface. Empty pixels are infinitely far.
+float depth_difference(vec3 position, out vec2 uv) {
+    uv = project(position);
+    vec4 surface = textureLod(normal_depth, uv, 0.0);
+    if (dot(surface.xyz, surface.xyz) < 0.5) {
+        return -1.0 / 0.0;
+    }
+    return surface.w - position.z;
+}
+
+// Per-pixel offset of the first ray step, hiding banding between steps.
+float step_offset(vec2 pixel) {
+    return fract(52.9829189 * fract(dot(pixel, vec2(0.06711056, 0.00583715))));
+}
+
+void main() {
+    vec4 color = texture(source, tex_coord);
+    vec4 center = texture(normal_depth, tex_coord);
+    vec4 surface = texture(reflectance, tex_coord);
+    float roughness = surface.a;
+    if (enabled < 0.5 || dot(center.xyz, center.xyz) < 0.5 || roughness > max_roughness) {
+        out_color = color;
+        return;
+    }
+
+    vec3 position = view_position(tex_coord, center.w);
+    vec3 normal = normalize(center.xyz);
+    vec3 view_direction = normalize(-position);
+    vec3 reflected = reflect(-view_direction, normal);
+
+    // March along the reflected ray until it passes behind a surface.
+    int steps = max(int(max_steps), 1);
+    float step_length = max_distance / float(steps);
+    float travelled = step_length * step_offset(gl_FragCoord.xy);
+    vec2 hit_uv = vec2(0.0);
+    float hit = 0.0;
+    for (int i = 0; i < steps; i++) {
+        travelled += step_length;
+        vec2 uv;
+        float difference = depth_difference(position + reflected * travelled, uv);
+        if (!on_screen(uv)) {
+            break;
+        }
+        if (difference > 0.0 && difference < thickness) {
+            // Binary search between the last two steps for the intersection.
+            float near = travelled - step_length;
+            float far = travelled;
+            hit_uv = uv;
+            for (int j = 0; j < int(refinement_steps); j++) {
+                float middle = (near + far) * 0.5;
+                difference = depth_difference(position + reflected * middle, uv);
+                if (difference > 0.0) {
+                    far = middle;
+                    hit_uv = uv;
+                } else {
+                    near = middle;
+                }
+            }
+            hit = 1.0 - float(i) / float(steps);
+            break;
+        }
+    }
+
+    // Fade out hits near the edges of the screen and on rough surfaces.
+    vec2 edge = smoothstep(0.0, 0.1, hit_uv) * smoothstep(0.0, 0.1, 1.0 - hit_uv);
+    float confidence = hit * edge.x * edge.y * (1.0 - roughness / max(max_roughness, 0.0001));
+
+    // Where a reflection is found, it replaces the environment map reflection lit surfaces
+    // already include. Elsewhere the environment map reflection is kept.
+    float NdotV = max(dot(normal, view_direction), 0.0);
+    vec2 brdf = environment_brdf(NdotV, roughness);
+    vec3 weight = surface.rgb * brdf.x + brdf.y;
+    vec3 world_reflected = transpose(mat3(view)) * reflected;
+    vec3 environment = environment_specular(world_reflected, roughness) * environment_map_intensity;
+    vec3 reflection = textureLod(source, hit_uv, 0.0).rgb;
+
+    vec3 result = color.rgb + (reflection - environment) * weight * confidence * intensity;
+    out_color = vec4(max(result, vec3(0.0)), color.a);
+}
@@ -0,0 +1,43 @@
+#version 450
+
+#include "header/math.frag"
+
+layout(std140, set = 0, binding = 0) uniform Projview {
+    mat4 proj;
+    mat4 view;
+    mat4 proj_view;
+};
+
+layout(std140, set = 1, binding = 0) uniform Material {
+    UvOffset uv_offset;
+    float alpha_cutoff;
+};
+
+layout(set = 1, binding = 1) uniform sampler2D albedo;
+layout(set = 1, binding = 4) uniform sampler2D metallic_roughness;
+
+layout(location = 0) in VertexData {
+    vec3 position;
+    vec3 normal;
+    vec2 tex_coord;
+    vec4 color;
+} vertex;
+
+// View space normal and depth, the depth being negative in front of the camera.
+layout(location = 0) out vec4 out_normal_depth;
+// Fresnel reflectance at normal incidence and roughness.
+layout(location = 1) out vec4 out_reflectance;
+
+void main() {
+    vec2 final_tex_coords = tex_coords(vertex.tex_coord, uv_offset);
+    vec4 albedo_alpha = texture(albedo, final_tex_coords);
+    if (albedo_alpha.a < alpha_cutoff) discard;
+
+    vec2 metallic_roughness = texture(metallic_roughness, final_tex_coords).bg;
+    vec3 fresnel_base = mix(vec3(0.04), albedo_alpha.rgb * vertex.color.rgb, metallic_roughness.r);
+
+    vec3 normal = normalize(mat3(view) * vertex.normal);
+    float depth = (view * vec4(vertex.position, 1.0)).z;
+    out_normal_depth = vec4(normal, depth);
+    out_reflectance = vec4(fresnel_base, metallic_roughness.g);
+}
@@ -0,0 +1,64 @@
+#version 450
+
+layout(location = 0) in vec2 tex_coord;
+
+layout(location = 0) out vec4 out_color;
+
+layout(set = 0, binding = 0) uniform sampler2D source;
+layout(set = 0, binding = 1) uniform sampler2D velocity;
+layout(set = 0, binding = 2) uniform sampler2D history;
+
+layout(push_constant) uniform TaaArgs {
+    float history_weight;
+};
+
+// Compress HDR colors so a few very bright samples don't dominate the blend.
+vec3 compress(vec3 color) {
+    return color / (1.0 + max(color.r, max(color.g, color.b)));
+}
+
+vec3 uncompress(vec3 color) {
+    return color / max(1.0 - max(color.r, max(color.g, color.b)), 0.0001);
+}
+
+void main() {
+    vec4 current = texture(source, tex_coord);
+    if (history_weight <= 0.0) {
+        out_color = current;
+        return;
+    }
+
+    // Use the largest motion around the pixel, so edges of moving objects
+    // are reprojected with them.
+    vec2 motion = vec2(0.0);
+    float motion_length = -1.0;
+    vec3 neighbourhood_min = vec3(1.0);
+    vec3 neighbourhood_max = vec3(0.0);
+    for (int y = -1; y <= 1; y++) {
+        for (int x = -1; x <= 1; x++) {
+            ivec2 offset = ivec2(x, y);
+            vec2 sample_motion = textureOffset(velocity, tex_coord, offset).xy;
+            float sample_length = dot(sample_motion, sample_motion);
+            if (sample_length > motion_length) {
+                motion = sample_motion;
+                motion_length = sample_length;
+            }
+            vec3 color = compress(textureOffset(source, tex_coord, offset).rgb);
+            neighbourhood_min = min(neighbourhood_min, color);
+            neighbourhood_max = max(neighbourhood_max, color);
+        }
+    }
+
+    vec2 history_coord = tex_coord - motion;
+    if (any(lessThan(history_coord, vec2(0.0))) || any(greaterThan(history_coord, vec2(1.0)))) {
+        out_color = current;
+        return;
+    }
+
+    // Clamp the history to the colors around the pixel to reject stale samples.
+    vec3 previous = compress(texture(history, history_coord).rgb);
+    previous = clamp(previous, neighbourhood_min, neighbourhood_max);
+
+    vec3 blended = mix(compress(current.rgb), previous, clamp(history_weight, 0.0, 1.0));
+    out_color = vec4(uncompress(blended), current.a);
+}
@@ -0,0 +1,19 @@
+#version 450
+
+#include "header/tonemap.frag"
+
+layout(location = 0) in vec2 tex_coord;
+
+layout(location = 0) out vec4 out_color;
+
+layout(set = 0, binding = 0) uniform sampler2D source;
+
+layout(push_constant) uniform ToneMapArgs {
+    float operator_index;
+    float exposure;
+};
+
+void main() {
+    vec4 color = texture(source, tex_coord);
+    out_color = vec4(tonemap(color.rgb * exposure, operator_index), color.a);
+}
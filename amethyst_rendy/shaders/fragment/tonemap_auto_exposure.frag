@@ -0,0 +1,27 @@
+#version 450
+
+#include "header/tonemap.frag"
+
+layout(location = 0) in vec2 tex_coord;
+
+layout(location = 0) out vec4 out_color;
+
+layout(set = 0, binding = 0) uniform sampler2D source;
+
+layout(std430, set = 0, binding = 1) readonly buffer Exposure {
+    float average_luminance;
+};
+
+layout(push_constant) uniform ToneMapArgs {
+    float operator_index;
+    float exposure;
+};
+
+// Average luminance is mapped to middle gray.
+const float KEY_VALUE = 0.18;
+
+void main() {
+    vec4 color = texture(source, tex_coord);
+    float auto_exposure = KEY_VALUE / max(average_luminance, 0.0001);
+    out_color = vec4(tonemap(color.rgb * exposure * auto_exposure, operator_index), color.a);
+}
@@ -0,0 +1,15 @@
+#version 450
+
+layout(location = 0) in VertexData {
+    vec4 position;
+    vec4 prev_position;
+} vertex;
+
+layout(location = 0) out vec2 out_velocity;
+
+void main() {
+    vec2 current = vertex.position.xy / vertex.position.w;
+    vec2 previous = vertex.prev_position.xy / vertex.prev_position.w;
+    // Normalized device coordinates to texture coordinates.
+    out_velocity = (current - previous) * 0.5;
+}
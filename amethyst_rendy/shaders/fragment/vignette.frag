@@ -0,0 +1,30 @@
+#version 450
+
+layout(location = 0) in vec2 tex_coord;
+
+layout(location = 0) out vec4 out_color;
+
+layout(set = 0, binding = 0) uniform sampler2D source;
+
+layout(push_constant) uniform VignetteArgs {
+    float enabled;
+    float intensity;
+    float radius;
+    float smoothness;
+    vec3 color;
+};
+
+void main() {
+    vec4 source_color = texture(source, tex_coord);
+    if (enabled < 0.5) {
+        out_color = source_color;
+        return;
+    }
+
+    // Distance from the center, 1 at the corners, keeping the vignette round on wide images.
+    vec2 size = vec2(textureSize(source, 0));
+    vec2 offset = (tex_coord - 0.5) * size / length(size) * 2.0;
+    float distance = length(offset);
+    float amount = smoothstep(radius, radius + max(smoothness, 0.0001), distance) * intensity;
+    out_color = vec4(mix(source_color.rgb, color, amount), source_color.a);
+}
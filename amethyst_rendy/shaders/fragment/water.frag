@@ -0,0 +1,78 @@
+#version 450
+
+layout(std140, set = 0, binding = 0) uniform Projview {
+    mat4 proj;
+    mat4 view;
+    mat4 proj_view;
+};
+
+// Image and depth of the scene behind the water.
+layout(set = 1, binding = 0) uniform sampler2D scene_color;
+layout(set = 1, binding = 1) uniform sampler2D scene_depth;
+layout(set = 2, binding = 0) uniform sampler2D normal_map;
+layout(set = 3, binding = 0) uniform sampler2D reflection_map;
+
+layout(location = 0) in vec3 world_position;
+layout(location = 1) in vec2 surface_position;
+layout(location = 2) in vec4 reflection_position;
+layout(location = 3) flat in mat3 surface_basis;
+layout(location = 6) flat in vec4 water_waves;
+layout(location = 7) flat in vec4 water_surface;
+layout(location = 8) flat in vec4 water_deep_color;
+layout(location = 9) flat in vec4 water_sky_color;
+layout(location = 10) flat in float water_has_reflection;
+
+layout(location = 0) out vec4 out_color;
+
+// Distance from the camera of the reversed depth `d`, infinite where nothing was drawn.
+float view_depth(float d) {
+    float z = (proj[3][2] - d * proj[3][3]) / (d * proj[2][3] - proj[2][2]);
+    return d > 0.0 ? -z : 1.0 / 0.0;
+}
+
+void main() {
+    // The target has no depth attachment, so the water is hidden behind opaque surfaces here.
+    vec2 screen_size = vec2(textureSize(scene_depth, 0));
+    vec2 screen_uv = gl_FragCoord.xy / screen_size;
+    float depth = texture(scene_depth, screen_uv).r;
+    if (depth > gl_FragCoord.z) discard;
+
+    // Two layers of the normal map scrolling in different directions.
+    vec2 uv = surface_position * water_surface.x;
+    vec2 slope = texture(normal_map, uv + water_waves.xy).xy * 2.0 - 1.0;
+    slope += texture(normal_map, uv + water_waves.zw).xy * 2.0 - 1.0;
+    slope *= water_surface.y;
+    vec3 normal = normalize(surface_basis * vec3(slope, 1.0));
+
+    mat4 inverse_view = inverse(view);
+    vec3 to_camera = normalize(inverse_view[3].xyz - world_position);
+    // Seen from below, the surface faces down.
+    if (dot(to_camera, surface_basis[2]) < 0.0) {
+        normal = -normal;
+    }
+
+    // Refraction, skipping offsets which would sample surfaces in front of the water.
+    vec2 refraction_uv = screen_uv + slope * water_surface.z;
+    float refraction_depth = texture(scene_depth, refraction_uv).r;
+    if (refraction_depth > gl_FragCoord.z) {
+        refraction_uv = screen_uv;
+        refraction_depth = depth;
+    }
+    vec3 refraction = texture(scene_color, refraction_uv).rgb;
+    float travelled = view_depth(refraction_depth) - view_depth(gl_FragCoord.z);
+    float transmittance = exp(-max(travelled, 0.0) / max(water_deep_color.a, 0.0001));
+    refraction = mix(water_deep_color.rgb, refraction, transmittance);
+
+    vec3 reflection = water_sky_color.rgb;
+    if (water_has_reflection > 0.5) {
+        vec2 reflection_uv = reflection_position.xy / reflection_position.w * 0.5 + 0.5;
+        reflection = texture(reflection_map, reflection_uv + slope * water_surface.w).rgb;
+    }
+
+    // Schlick's approximation of the Fresnel term.
+    float reflectance = water_sky_color.a;
+    float cos_theta = clamp(dot(normal, to_camera), 0.0, 1.0);
+    float fresnel = reflectance + (1.0 - reflectance) * pow(1.0 - cos_theta, 5.0);
+
+    out_color = vec4(mix(refraction, reflection, fresnel), 1.0);
+}
@@ -0,0 +1,47 @@
+#version 450
+
+layout(std140, set = 0, binding = 0) uniform ViewArgs {
+    uniform mat4 proj;
+    uniform mat4 view;
+    uniform mat4 proj_view;
+};
+
+// Billboard instance.
+layout(location = 0) in vec3 position;
+layout(location = 1) in uint cylindrical;
+layout(location = 2) in vec2 size;
+layout(location = 3) in vec2 offset;
+layout(location = 4) in vec2 u_offset;
+layout(location = 5) in vec2 v_offset;
+layout(location = 6) in vec4 color;
+
+layout(location = 0) out VertexData {
+    vec2 tex_uv;
+    vec4 color;
+} vertex;
+
+const vec2 positions[4] = vec2[](
+    vec2(0.5, -0.5), // Right bottom
+    vec2(-0.5, -0.5), // Left bottom
+    vec2(0.5, 0.5), // Right top
+    vec2(-0.5, 0.5) // Left top
+);
+
+void main() {
+    vec2 corner = positions[gl_VertexIndex];
+    // Camera right and up axes, so the quad always faces the camera.
+    vec3 right = vec3(view[0][0], view[1][0], view[2][0]);
+    vec3 up = vec3(view[0][1], view[1][1], view[2][1]);
+    if (cylindrical != 0) {
+        // Stay upright, turning only around the world y axis.
+        right = normalize(vec3(right.x, 0.0, right.z));
+        up = vec3(0.0, 1.0, 0.0);
+    }
+    vec2 local = corner * size - offset;
+    vec3 world_position = position + local.x * right + local.y * up;
+
+    vertex.tex_uv = vec2(mix(u_offset.x, u_offset.y, corner.x + 0.5),
+                         mix(v_offset.x, v_offset.y, 0.5 - corner.y));
+    vertex.color = color;
+    gl_Position = proj_view * vec4(world_position, 1.0);
+}
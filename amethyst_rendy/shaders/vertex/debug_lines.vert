@@ -8,20 +8,36 @@ layout(std140, set = 0, binding = 0) uniform ViewArgs {
 
 layout(std140, set = 1, binding = 0) uniform DebugLinesArgs {
     uniform vec2 screen_space_thickness;
+    uniform vec2 pixel_size;
 };
 
 layout(location = 0) in vec3 position_a;
 layout(location = 1) in vec4 color_a;
 layout(location = 2) in vec3 position_b;
 layout(location = 3) in vec4 color_b;
+layout(location = 4) in float line_width;
+layout(location = 5) in vec2 dash_pattern;
 
 layout(location = 0) out VertexData {
     vec4 color;
 } vertex;
 
+layout(location = 1) noperspective out float line_distance;
+layout(location = 2) flat out vec2 pattern;
+// Distance from the center line across the line and half the width of the line, in pixels.
+layout(location = 3) noperspective out float edge_distance;
+layout(location = 4) flat out float half_width;
+
+// Pixels the line is widened by on each side to fade its edges out.
+const float FEATHER = 1.0;
+
 void main() {
     float factor = float(gl_VertexIndex >> 1);
     vertex.color = mix(color_a, color_b, factor);
+    pattern = dash_pattern;
+    line_distance = 0.0;
+    edge_distance = 0.0;
+    half_width = FEATHER;
 
     vec4 projected_a = proj_view * vec4(position_a, 1.0);
     vec4 projected_b = proj_view * vec4(position_b, 1.0);
@@ -38,15 +54,23 @@ void main() {
         vec2 screen_a = projected_a.xy / projected_a.w;
         vec2 screen_b = projected_b.xy / projected_b.w;
         vec2 dir = normalize(screen_b - screen_a);
+        line_distance = factor * length((screen_b - screen_a) / pixel_size);
 
         vec2 normal;
+        float side;
         if (mod(gl_VertexIndex, 2) == 0) {
             normal = vec2(-dir.y, dir.x);
+            side = 1.0;
         } else {
             normal = vec2(dir.y, -dir.x);
+            side = -1.0;
         }
         
-        normal *= proj_current.w * screen_space_thickness;
+        // per-line width overrides the global thickness
+        vec2 thickness = line_width > 0.0 ? line_width * pixel_size : screen_space_thickness;
+        half_width = thickness.x / pixel_size.x;
+        edge_distance = side * (half_width + FEATHER);
+        normal *= proj_current.w * (thickness + FEATHER * pixel_size);
         gl_Position = proj_current + vec4(normal, 0.0, 0.0);
     }
 }
@@ -0,0 +1,39 @@
+#version 450
+
+layout(std140, set = 0, binding = 0) uniform Projview {
+    mat4 proj;
+    mat4 view;
+    mat4 proj_view;
+};
+
+// Decal instance.
+layout(location = 0) in mat4 model;
+layout(location = 4) in vec4 color;
+layout(location = 5) in float normal_strength;
+
+layout(location = 0) flat out mat4 world_to_decal;
+layout(location = 4) flat out mat3 decal_basis;
+layout(location = 7) flat out vec4 decal_color;
+layout(location = 8) flat out float decal_normal_strength;
+
+// Corners of the unit box, indexed by their x, y and z bits.
+const int indices[36] = int[](
+    0, 4, 6, 0, 6, 2, // -x
+    1, 3, 7, 1, 7, 5, // +x
+    0, 1, 5, 0, 5, 4, // -y
+    2, 6, 7, 2, 7, 3, // +y
+    0, 2, 3, 0, 3, 1, // -z
+    4, 5, 7, 4, 7, 6  // +z
+);
+
+// The box of the decal, with triangles facing outwards.
+void main() {
+    int corner = indices[gl_VertexIndex];
+    vec3 position = vec3(corner & 1, (corner >> 1) & 1, (corner >> 2) & 1) - 0.5;
+
+    world_to_decal = inverse(model);
+    decal_basis = mat3(normalize(model[0].xyz), normalize(model[1].xyz), normalize(model[2].xyz));
+    decal_color = color;
+    decal_normal_strength = normal_strength;
+    gl_Position = proj_view * model * vec4(position, 1.0);
+}
@@ -0,0 +1,54 @@
+#version 450
+
+layout(std140, set = 0, binding = 0) uniform Projview {
+    mat4 proj;
+    mat4 view;
+    mat4 proj_view;
+};
+
+layout(location = 0) in vec3 position; // instance rate
+layout(location = 1) in float radius; // instance rate
+layout(location = 2) in vec3 color; // instance rate
+layout(location = 3) in float intensity; // instance rate
+layout(location = 4) in float smoothness; // instance rate
+
+layout(location = 0) flat out vec3 light_position;
+layout(location = 1) flat out float light_radius;
+layout(location = 2) flat out vec3 light_color;
+layout(location = 3) flat out float light_intensity;
+layout(location = 4) flat out float light_smoothness;
+
+// A screen space rectangle of two triangles bounding the light's sphere of influence,
+// covering the whole framebuffer when the camera is close enough to clip it.
+void main() {
+    vec2 min_ndc = vec2(1.0);
+    vec2 max_ndc = vec2(-1.0);
+    bool clipped = false;
+    for (int i = 0; i < 8; i++) {
+        vec3 corner = vec3(i & 1, (i >> 1) & 1, (i >> 2) & 1) * 2.0 - 1.0;
+        vec4 clip = proj_view * vec4(position + corner * radius, 1.0);
+        if (clip.w <= 0.0001) {
+            clipped = true;
+            break;
+        }
+        min_ndc = min(min_ndc, clip.xy / clip.w);
+        max_ndc = max(max_ndc, clip.xy / clip.w);
+    }
+    if (clipped) {
+        min_ndc = vec2(-1.0);
+        max_ndc = vec2(1.0);
+    }
+    min_ndc = clamp(min_ndc, -1.0, 1.0);
+    max_ndc = clamp(max_ndc, -1.0, 1.0);
+
+    // Vertices 0, 1, 2 and 3, 4, 5 make up the two triangles.
+    int corner = gl_VertexIndex < 3 ? gl_VertexIndex : gl_VertexIndex - 2;
+    vec2 select = vec2(corner & 1, (corner >> 1) & 1);
+    gl_Position = vec4(mix(min_ndc, max_ndc, select), 0.0, 1.0);
+
+    light_position = position;
+    light_radius = radius;
+    light_color = color;
+    light_intensity = intensity;
+    light_smoothness = smoothness;
+}
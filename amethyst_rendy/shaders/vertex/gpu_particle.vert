@@ -0,0 +1,52 @@
+#version 450
+
+layout(std140, set = 0, binding = 0) uniform ViewArgs {
+    uniform mat4 proj;
+    uniform mat4 view;
+    uniform mat4 proj_view;
+};
+
+struct Particle {
+    vec4 position;
+    vec4 velocity;
+    vec4 acceleration;
+    vec4 size;
+    vec4 color_start;
+    vec4 color_end;
+};
+
+// Indexed by instance, the indirect draw starts at the half written this frame.
+layout(std430, set = 2, binding = 0) readonly buffer Particles {
+    Particle particles[];
+};
+
+layout(location = 0) out VertexData {
+    vec2 tex_uv;
+    vec4 color;
+    float view_depth;
+} vertex;
+
+const vec2 positions[4] = vec2[](
+    vec2(0.5, -0.5), // Right bottom
+    vec2(-0.5, -0.5), // Left bottom
+    vec2(0.5, 0.5), // Right top
+    vec2(-0.5, 0.5) // Left top
+);
+
+void main() {
+    Particle particle = particles[gl_InstanceIndex];
+    float t = clamp(particle.position.w / particle.velocity.w, 0.0, 1.0);
+    float size = mix(particle.size.x, particle.size.y, t);
+
+    vec2 corner = positions[gl_VertexIndex];
+    // Camera right and up axes, so the quad always faces the camera.
+    vec3 right = vec3(view[0][0], view[1][0], view[2][0]);
+    vec3 up = vec3(view[0][1], view[1][1], view[2][1]);
+    vec3 world_position = particle.position.xyz + (corner.x * right + corner.y * up) * size;
+
+    vec4 view_position = view * vec4(world_position, 1.0);
+    vertex.tex_uv = vec2(corner.x + 0.5, 0.5 - corner.y);
+    vertex.color = mix(particle.color_start, particle.color_end, t);
+    vertex.view_depth = -view_position.z;
+    gl_Position = proj * view_position;
+}
@@ -0,0 +1,31 @@
+#version 450
+
+layout(std140, set = 0, binding = 0) uniform ViewArgs {
+    uniform mat4 proj;
+    uniform mat4 view;
+    uniform mat4 proj_view;
+};
+
+layout(location = 0) out vec3 near_point;
+layout(location = 1) out vec3 far_point;
+
+const vec2 corners[4] = vec2[](
+    vec2(-1.0, -1.0),
+    vec2(1.0, -1.0),
+    vec2(-1.0, 1.0),
+    vec2(1.0, 1.0)
+);
+
+vec3 unproject(vec2 xy, float depth) {
+    vec4 point = inverse(proj_view) * vec4(xy, depth, 1.0);
+    return point.xyz / point.w;
+}
+
+void main() {
+    vec2 corner = corners[gl_VertexIndex];
+    // Reverse-Z: 1.0 is the near plane. The far plane is at infinity,
+    // so a point halfway into the depth range is used to get the view ray.
+    near_point = unproject(corner, 1.0);
+    far_point = unproject(corner, 0.5);
+    gl_Position = vec4(corner, 0.0, 1.0);
+}
@@ -0,0 +1,21 @@
+#version 450
+
+layout(std140, set = 0, binding = 0) uniform Projview {
+    mat4 proj;
+    mat4 view;
+    mat4 proj_view;
+};
+
+layout(location = 0) in vec3 position;
+layout(location = 1) in mat4 model; // instance rate
+layout(location = 5) in vec4 color; // instance rate
+layout(location = 6) in float width; // instance rate
+
+layout(location = 0) flat out vec4 out_color;
+layout(location = 1) flat out float out_width;
+
+void main() {
+    out_color = color;
+    out_width = width;
+    gl_Position = proj_view * model * vec4(position, 1.0);
+}
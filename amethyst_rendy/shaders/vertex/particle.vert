@@ -0,0 +1,39 @@
+#version 450
+
+layout(std140, set = 0, binding = 0) uniform ViewArgs {
+    uniform mat4 proj;
+    uniform mat4 view;
+    uniform mat4 proj_view;
+};
+
+// Particle instance.
+layout(location = 0) in vec3 position;
+layout(location = 1) in float size;
+layout(location = 2) in vec4 color;
+
+layout(location = 0) out VertexData {
+    vec2 tex_uv;
+    vec4 color;
+    float view_depth;
+} vertex;
+
+const vec2 positions[4] = vec2[](
+    vec2(0.5, -0.5), // Right bottom
+    vec2(-0.5, -0.5), // Left bottom
+    vec2(0.5, 0.5), // Right top
+    vec2(-0.5, 0.5) // Left top
+);
+
+void main() {
+    vec2 corner = positions[gl_VertexIndex];
+    // Camera right and up axes, so the quad always faces the camera.
+    vec3 right = vec3(view[0][0], view[1][0], view[2][0]);
+    vec3 up = vec3(view[0][1], view[1][1], view[2][1]);
+    vec3 world_position = position + (corner.x * right + corner.y * up) * size;
+
+    vec4 view_position = view * vec4(world_position, 1.0);
+    vertex.tex_uv = vec2(corner.x + 0.5, 0.5 - corner.y);
+    vertex.color = color;
+    vertex.view_depth = -view_position.z;
+    gl_Position = proj * view_position;
+}
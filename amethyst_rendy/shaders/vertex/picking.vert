@@ -0,0 +1,16 @@
+#version 450
+
+layout(std140, set = 0, binding = 0) uniform PickingArgs {
+    mat4 proj_view;
+};
+
+layout(location = 0) in vec3 position;
+layout(location = 1) in mat4 model; // instance rate
+layout(location = 5) in uint entity; // instance rate
+
+layout(location = 0) flat out uint vertex_entity;
+
+void main() {
+    vertex_entity = entity;
+    gl_Position = proj_view * model * vec4(position, 1.0);
+}
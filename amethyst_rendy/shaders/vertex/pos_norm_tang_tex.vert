@@ -12,6 +12,8 @@ layout(location = 2) in vec4 tangent;
 layout(location = 3) in vec2 tex_coord;
 layout(location = 4) in mat4 model; // instance rate
 layout(location = 8) in vec4 tint; // instance rate
+layout(location = 9) in vec2 lightmap_tex_coord;
+layout(location = 10) in vec4 color; // white at instance rate for meshes without colors
 
 layout(location = 0) out VertexData {
     vec3 position;
@@ -21,6 +23,9 @@ layout(location = 0) out VertexData {
     vec2 tex_coord;
     vec4 color;
 } vertex;
+// Origin of the model in world space, for per-object selection of reflection probes.
+layout(location = 6) flat out vec3 vertex_origin;
+layout(location = 7) out vec2 vertex_lightmap_tex_coord;
 
 void main() {
     vec4 vertex_position = model * vec4(position, 1.0);
@@ -29,6 +34,8 @@ void main() {
     vertex.tangent = mat3(model) * tangent.xyz;
     vertex.tang_handedness = tangent.w;
     vertex.tex_coord = tex_coord;
-    vertex.color = tint;
+    vertex.color = tint * color;
+    vertex_origin = model[3].xyz;
+    vertex_lightmap_tex_coord = lightmap_tex_coord;
     gl_Position = proj_view * vertex_position;
 }
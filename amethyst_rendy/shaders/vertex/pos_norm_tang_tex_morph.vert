@@ -0,0 +1,49 @@
+#version 450
+
+layout(std140, set = 0, binding = 0) uniform Projview {
+    mat4 proj;
+    mat4 view;
+    mat4 proj_view;
+};
+
+layout(location = 0) in vec3 position;
+layout(location = 1) in vec3 normal;
+layout(location = 2) in vec4 tangent;
+layout(location = 3) in vec2 tex_coord;
+layout(location = 4) in vec3 morph_position[4];
+layout(location = 8) in mat4 model; // instance rate
+layout(location = 12) in vec4 tint; // instance rate
+layout(location = 13) in vec4 morph_weights; // instance rate
+layout(location = 14) in vec2 lightmap_tex_coord;
+layout(location = 15) in vec4 color; // white at instance rate for meshes without colors
+
+layout(location = 0) out VertexData {
+    vec3 position;
+    vec3 normal;
+    vec3 tangent;
+    float tang_handedness;
+    vec2 tex_coord;
+    vec4 color;
+} vertex;
+// Origin of the model in world space, for per-object selection of reflection probes.
+layout(location = 6) flat out vec3 vertex_origin;
+layout(location = 7) out vec2 vertex_lightmap_tex_coord;
+
+void main() {
+    vec3 morphed_position = position +
+        morph_weights.x * morph_position[0] +
+        morph_weights.y * morph_position[1] +
+        morph_weights.z * morph_position[2] +
+        morph_weights.w * morph_position[3];
+
+    vec4 vertex_position = model * vec4(morphed_position, 1.0);
+    vertex.position = vertex_position.xyz;
+    vertex.normal = mat3(model) * normal;
+    vertex.tangent = mat3(model) * tangent.xyz;
+    vertex.tang_handedness = tangent.w;
+    vertex.tex_coord = tex_coord;
+    vertex.color = tint * color;
+    vertex_origin = model[3].xyz;
+    vertex_lightmap_tex_coord = lightmap_tex_coord;
+    gl_Position = proj_view * vertex_position;
+}
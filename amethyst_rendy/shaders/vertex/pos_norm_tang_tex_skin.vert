@@ -19,6 +19,8 @@ layout(location = 5) in vec4 joint_weights;
 layout(location = 6) in mat4 model; // instance rate
 layout(location = 10) in vec4 tint; // instance rate
 layout(location = 11) in uint joints_offset; // instance rate
+layout(location = 12) in vec2 lightmap_tex_coord;
+layout(location = 13) in vec4 color; // white at instance rate for meshes without colors
 
 layout(location = 0) out VertexData {
     vec3 position;
@@ -28,6 +30,9 @@ layout(location = 0) out VertexData {
     vec2 tex_coord;
     vec4 color;
 } vertex;
+// Origin of the model in world space, for per-object selection of reflection probes.
+layout(location = 6) flat out vec3 vertex_origin;
+layout(location = 7) out vec2 vertex_lightmap_tex_coord;
 
 void main() {
     mat4 joint_transform =
@@ -43,6 +48,8 @@ void main() {
     vertex.tangent = mat3_transform * tangent.xyz;
     vertex.tang_handedness = tangent.w;
     vertex.tex_coord = tex_coord;
-    vertex.color = tint;
+    vertex.color = tint * color;
+    vertex_origin = model[3].xyz;
+    vertex_lightmap_tex_coord = lightmap_tex_coord;
     gl_Position = proj_view * vertex_position;
 }
@@ -11,6 +11,7 @@ layout(location = 1) in vec3 normal;
 layout(location = 2) in vec2 tex_coord;
 layout(location = 3) in mat4 model; // instance rate
 layout(location = 7) in vec4 tint; // instance rate
+layout(location = 8) in vec4 color; // white at instance rate for meshes without colors
 
 layout(location = 0) out VertexData {
     vec3 position;
@@ -24,6 +25,6 @@ void main() {
     vertex.position = vertex_position.xyz;
     vertex.normal = mat3(model) * normal;
     vertex.tex_coord = tex_coord;
-    vertex.color = tint;
+    vertex.color = tint * color;
     gl_Position = proj_view * vertex_position;
 }
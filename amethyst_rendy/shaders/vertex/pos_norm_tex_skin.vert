@@ -18,6 +18,7 @@ layout(location = 4) in vec4 joint_weights;
 layout(location = 5) in mat4 model; // instance rate
 layout(location = 9) in vec4 tint; // instance rate
 layout(location = 10) in uint joints_offset; // instance rate
+layout(location = 11) in vec4 color; // white at instance rate for meshes without colors
 
 layout(location = 0) out VertexData {
     vec3 position;
@@ -38,7 +39,7 @@ void main() {
     vertex.position = vertex_position.xyz;
     vertex.normal = mat3_transform * normal;
     vertex.tex_coord = tex_coord;
-    vertex.color = tint;
+    vertex.color = tint * color;
     gl_Position = proj_view * vertex_position;
 
 }
@@ -10,6 +10,7 @@ layout(location = 0) in vec3 position;
 layout(location = 1) in vec2 tex_coord;
 layout(location = 2) in mat4 model; // instance rate
 layout(location = 6) in vec4 tint; // instance rate
+layout(location = 7) in vec4 color; // white at instance rate for meshes without colors
 
 layout(location = 0) out VertexData {
     vec3 position;
@@ -21,6 +22,6 @@ void main() {
     vec4 vertex_position = model * vec4(position, 1.0);
     vertex.position = vertex_position.xyz;
     vertex.tex_coord = tex_coord;
-    vertex.color = tint;
+    vertex.color = tint * color;
     gl_Position = proj_view * vertex_position;
 }
@@ -17,6 +17,7 @@ layout(location = 3) in vec4 joint_weights;
 layout(location = 4) in mat4 model; // instance rate
 layout(location = 8) in vec4 tint; // instance rate
 layout(location = 9) in uint joints_offset; // instance rate
+layout(location = 10) in vec4 color; // white at instance rate for meshes without colors
 
 layout(location = 0) out VertexData {
     vec3 position;
@@ -34,6 +35,6 @@ void main() {
     vec4 vertex_position = model * joint_transform * vec4(position, 1.0);
     vertex.position = vertex_position.xyz;
     vertex.tex_coord = tex_coord;
-    vertex.color = tint;
+    vertex.color = tint * color;
     gl_Position = proj_view * vertex_position;
 }
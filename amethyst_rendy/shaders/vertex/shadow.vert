@@ -0,0 +1,19 @@
+#version 450
+
+layout(push_constant) uniform Tile {
+    // Light-space projection-view matrix of the cascade or cube map face.
+    mat4 proj_view;
+    // Offset and size of the tile in the shadow atlas, in texture coordinates.
+    vec4 tile;
+};
+
+layout(location = 0) in vec3 position;
+layout(location = 1) in mat4 model; // instance rate
+layout(location = 5) in vec4 tint; // instance rate
+
+void main() {
+    vec4 pos = proj_view * model * vec4(position, 1.0);
+    // Move the rendered area into its tile of the shadow atlas.
+    pos.xy = (tile.xy * 2.0 - 1.0) * pos.w + (pos.xy + pos.w) * tile.zw;
+    gl_Position = pos;
+}
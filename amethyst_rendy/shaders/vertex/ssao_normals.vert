@@ -0,0 +1,25 @@
+#version 450
+
+layout(std140, set = 0, binding = 0) uniform Projview {
+    mat4 proj;
+    mat4 view;
+    mat4 proj_view;
+};
+
+layout(location = 0) in vec3 position;
+layout(location = 1) in vec3 normal;
+layout(location = 2) in mat4 model; // instance rate
+layout(location = 6) in vec4 tint; // instance rate
+
+layout(location = 0) out VertexData {
+    vec3 normal;
+    float depth;
+} vertex;
+
+void main() {
+    mat4 model_view = view * model;
+    vec4 view_position = model_view * vec4(position, 1.0);
+    vertex.normal = mat3(model_view) * normal;
+    vertex.depth = view_position.z;
+    gl_Position = proj * view_position;
+}
@@ -0,0 +1,27 @@
+#version 450
+
+layout(std140, set = 0, binding = 0) uniform VelocityArgs {
+    mat4 proj_view;
+    mat4 prev_proj_view;
+    vec2 jitter;
+    vec2 prev_jitter;
+};
+
+layout(location = 0) in vec3 position;
+layout(location = 1) in mat4 model; // instance rate
+layout(location = 5) in mat4 prev_model; // instance rate
+
+layout(location = 0) out VertexData {
+    vec4 position;
+    vec4 prev_position;
+} vertex;
+
+void main() {
+    vec4 pos = proj_view * model * vec4(position, 1.0);
+    gl_Position = pos;
+
+    // Motion is measured without jitter, so the image doesn't move while the camera is still.
+    vertex.position = vec4(pos.xy - jitter * pos.w, pos.zw);
+    vec4 prev_pos = prev_proj_view * prev_model * vec4(position, 1.0);
+    vertex.prev_position = vec4(prev_pos.xy - prev_jitter * prev_pos.w, prev_pos.zw);
+}
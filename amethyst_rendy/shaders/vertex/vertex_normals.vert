@@ -0,0 +1,27 @@
+#version 450
+
+layout(std140, set = 0, binding = 0) uniform Projview {
+    mat4 proj;
+    mat4 view;
+    mat4 proj_view;
+};
+
+layout(push_constant) uniform VertexNormalsArgs {
+    mat4 model;
+    // Length of the lines, in world units.
+    float line_length;
+};
+
+// Every instance is a vertex of the mesh, drawn as a line along its normal.
+layout(location = 0) in vec3 position; // instance rate
+layout(location = 1) in vec3 normal; // instance rate
+
+layout(location = 0) flat out vec4 color;
+
+void main() {
+    vec3 start = (model * vec4(position, 1.0)).xyz;
+    // Normals are transformed the way the shading passes transform them.
+    vec3 end = start + normalize(mat3(model) * normal) * line_length;
+    color = vec4(0.2, 0.4, 1.0, 1.0);
+    gl_Position = proj_view * vec4(gl_VertexIndex == 0 ? start : end, 1.0);
+}
@@ -0,0 +1,44 @@
+#version 450
+
+layout(std140, set = 0, binding = 0) uniform Projview {
+    mat4 proj;
+    mat4 view;
+    mat4 proj_view;
+};
+
+layout(push_constant) uniform VertexNormalsArgs {
+    mat4 model;
+    // Length of the lines, in world units.
+    float line_length;
+};
+
+// Every instance is a vertex of the mesh, drawn as three lines along its normal, tangent and
+// bitangent.
+layout(location = 0) in vec3 position; // instance rate
+layout(location = 1) in vec3 normal; // instance rate
+layout(location = 2) in vec4 tangent; // instance rate
+
+layout(location = 0) flat out vec4 color;
+
+void main() {
+    vec3 start = (model * vec4(position, 1.0)).xyz;
+    // Vectors are transformed the way the shading passes transform them.
+    vec3 vertex_normal = normalize(mat3(model) * normal);
+    vec3 vertex_tangent = normalize(mat3(model) * tangent.xyz);
+    vec3 vertex_bitangent = normalize(cross(vertex_normal, vertex_tangent) * tangent.w);
+
+    int line = gl_VertexIndex >> 1;
+    vec3 direction;
+    if (line == 0) {
+        direction = vertex_normal;
+        color = vec4(0.2, 0.4, 1.0, 1.0);
+    } else if (line == 1) {
+        direction = vertex_tangent;
+        color = vec4(1.0, 0.2, 0.2, 1.0);
+    } else {
+        direction = vertex_bitangent;
+        color = vec4(0.2, 1.0, 0.2, 1.0);
+    }
+    vec3 end = start + direction * line_length;
+    gl_Position = proj_view * vec4((gl_VertexIndex & 1) == 0 ? start : end, 1.0);
+}
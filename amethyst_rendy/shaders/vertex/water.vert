@@ -0,0 +1,51 @@
+#version 450
+
+layout(std140, set = 0, binding = 0) uniform Projview {
+    mat4 proj;
+    mat4 view;
+    mat4 proj_view;
+};
+
+// Water surface instance.
+layout(location = 0) in mat4 model;
+layout(location = 4) in mat4 reflection;
+layout(location = 8) in vec4 waves;
+layout(location = 9) in vec4 surface;
+layout(location = 10) in vec4 deep_color;
+layout(location = 11) in vec4 sky_color;
+layout(location = 12) in float has_reflection;
+
+layout(location = 0) out vec3 world_position;
+layout(location = 1) out vec2 surface_position;
+layout(location = 2) out vec4 reflection_position;
+layout(location = 3) flat out mat3 surface_basis;
+layout(location = 6) flat out vec4 water_waves;
+layout(location = 7) flat out vec4 water_surface;
+layout(location = 8) flat out vec4 water_deep_color;
+layout(location = 9) flat out vec4 water_sky_color;
+layout(location = 10) flat out float water_has_reflection;
+
+const vec2 positions[4] = vec2[](
+    vec2(0.5, -0.5),
+    vec2(-0.5, -0.5),
+    vec2(0.5, 0.5),
+    vec2(-0.5, 0.5)
+);
+
+// The unit square of the surface on the xz plane, drawn as a triangle strip.
+void main() {
+    vec2 corner = positions[gl_VertexIndex];
+    vec4 position = model * vec4(corner.x, 0.0, corner.y, 1.0);
+
+    world_position = position.xyz;
+    // Position on the surface in world units, so the waves keep their size on any surface.
+    surface_position = corner * vec2(length(model[0].xyz), length(model[2].xyz));
+    reflection_position = reflection * position;
+    surface_basis = mat3(normalize(model[0].xyz), normalize(model[2].xyz), normalize(model[1].xyz));
+    water_waves = waves;
+    water_surface = surface;
+    water_deep_color = deep_color;
+    water_sky_color = sky_color;
+    water_has_reflection = has_reflection;
+    gl_Position = proj_view * position;
+}
@@ -0,0 +1,377 @@
+//! Runtime packing of images into shared atlas textures.
+//!
+//! Many small textures cost a draw call each. Packing them into one texture lets UI, particles,
+//! decals and sprites sample sub-rectangles of it instead.
+use image::{Pixel, RgbaImage};
+use rendy::{
+    hal::{
+        format::Format,
+        image::{Filter, Kind, SamplerInfo, ViewKind, WrapMode},
+    },
+    texture::TextureBuilder,
+};
+
+use crate::types::{Texture, TextureData};
+use amethyst_assets::{AssetStorage, Handle, Loader};
+use amethyst_error::{format_err, Error};
+
+/// Identifies an image added to an `AtlasBuilder`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct AtlasRegionId(usize);
+
+/// Packs a set of images into a single atlas texture.
+///
+/// Images are taken decoded, as the pixels of a `TextureData` can't be read back once built.
+///
+/// # Example
+///
+/// ```ignore
+/// let mut atlas = AtlasBuilder::new();
+/// let spark = atlas.add(image::open("spark.png")?.to_rgba());
+/// let smoke = atlas.add(image::open("smoke.png")?.to_rgba());
+/// let atlas = atlas.build(&loader, &texture_storage)?;
+///
+/// let spark_region = atlas.region(spark).clone();
+/// ```
+#[derive(Debug)]
+pub struct AtlasBuilder {
+    max_size: u32,
+    padding: u32,
+    sampler_info: SamplerInfo,
+    images: Vec<RgbaImage>,
+}
+
+impl Default for AtlasBuilder {
+    fn default() -> Self {
+        AtlasBuilder {
+            max_size: 4096,
+            padding: 1,
+            sampler_info: SamplerInfo::new(Filter::Linear, WrapMode::Clamp),
+            images: Vec::new(),
+        }
+    }
+}
+
+impl AtlasBuilder {
+    /// Creates an empty atlas builder for a texture of up to 4096x4096 pixels.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Sets the maximum width and height of the atlas texture.
+    pub fn with_max_size(mut self, max_size: u32) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Sets the number of transparent pixels left between packed images, which keeps filtering
+    /// from bleeding neighbouring images into each other.
+    pub fn with_padding(mut self, padding: u32) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Sets the sampler of the atlas texture.
+    pub fn with_sampler_info(mut self, sampler_info: SamplerInfo) -> Self {
+        self.sampler_info = sampler_info;
+        self
+    }
+
+    /// Adds an image in sRGB with straight alpha.
+    pub fn add(&mut self, image: RgbaImage) -> AtlasRegionId {
+        self.images.push(image);
+        AtlasRegionId(self.images.len() - 1)
+    }
+
+    /// Packs all added images and loads the atlas texture.
+    ///
+    /// Fails when the images don't fit a single texture of the maximum size.
+    pub fn build(
+        self,
+        loader: &Loader,
+        texture_storage: &AssetStorage<Texture>,
+    ) -> Result<TextureAtlas, Error> {
+        let sizes = self
+            .images
+            .iter()
+            .map(|image| image.dimensions())
+            .collect::<Vec<_>>();
+        let (page_sizes, placements) = pack(&sizes, self.max_size, self.padding);
+        if page_sizes.len() > 1 || page_sizes.iter().any(|&(w, h)| w.max(h) > self.max_size) {
+            return Err(format_err!(
+                "Images do not fit an atlas of {0}x{0} pixels",
+                self.max_size
+            ));
+        }
+
+        let page_size = page_sizes.first().copied().unwrap_or((1, 1));
+        let page = compose_pages(self.images.iter(), &placements, &[page_size]).remove(0);
+        let texture = loader.load_from_data(
+            page_texture_data(page, self.sampler_info),
+            (),
+            texture_storage,
+        );
+
+        let regions = sizes
+            .iter()
+            .zip(&placements)
+            .map(|(&(width, height), placement)| {
+                let (left, top) =
+                    region_coordinates(placement, (0.0, 0.0), (width, height), page_size);
+                let (right, bottom) =
+                    region_coordinates(placement, (1.0, 1.0), (width, height), page_size);
+                TextureRegion {
+                    texture: texture.clone(),
+                    left,
+                    right,
+                    bottom,
+                    top,
+                }
+            })
+            .collect();
+
+        Ok(TextureAtlas { texture, regions })
+    }
+}
+
+/// An atlas texture along with the regions of the images packed into it.
+#[derive(Debug, Clone)]
+pub struct TextureAtlas {
+    texture: Handle<Texture>,
+    regions: Vec<TextureRegion>,
+}
+
+impl TextureAtlas {
+    /// Returns the atlas texture.
+    pub fn texture(&self) -> &Handle<Texture> {
+        &self.texture
+    }
+
+    /// Returns the region of an image added to the builder.
+    pub fn region(&self, id: AtlasRegionId) -> &TextureRegion {
+        &self.regions[id.0]
+    }
+}
+
+/// Sub-rectangle of a texture holding one packed image.
+///
+/// Texture coordinates are normalized, with 0.0 at the left and top edges of the texture.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextureRegion {
+    /// Texture the region is part of
+    pub texture: Handle<Texture>,
+    /// Normalized left x coordinate
+    pub left: f32,
+    /// Normalized right x coordinate
+    pub right: f32,
+    /// Normalized bottom y coordinate
+    pub bottom: f32,
+    /// Normalized top y coordinate
+    pub top: f32,
+}
+
+/// Location of a packed image, in pixels from the top left corner of its page.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Placement {
+    pub(crate) page: usize,
+    pub(crate) x: u32,
+    pub(crate) y: u32,
+}
+
+/// A row of images on an atlas page, as tall as its first and tallest image.
+#[derive(Debug)]
+struct Shelf {
+    y: u32,
+    height: u32,
+    width: u32,
+}
+
+#[derive(Debug)]
+struct Page {
+    limit: (u32, u32),
+    width: u32,
+    height: u32,
+    shelves: Vec<Shelf>,
+}
+
+impl Page {
+    fn new(limit: (u32, u32)) -> Self {
+        Page {
+            limit,
+            width: 0,
+            height: 0,
+            shelves: Vec::new(),
+        }
+    }
+
+    /// Finds room for a rectangle on the first shelf that fits it, or on a new shelf.
+    fn insert(&mut self, (width, height): (u32, u32)) -> Option<(u32, u32)> {
+        let limit = self.limit;
+        let (x, y) = match self
+            .shelves
+            .iter_mut()
+            .find(|shelf| height <= shelf.height && shelf.width + width <= limit.0)
+        {
+            Some(shelf) => {
+                let position = (shelf.width, shelf.y);
+                shelf.width += width;
+                position
+            }
+            None if self.height + height <= limit.1 && width <= limit.0 => {
+                self.shelves.push(Shelf {
+                    y: self.height,
+                    height,
+                    width,
+                });
+                self.height += height;
+                (0, self.height - height)
+            }
+            None => return None,
+        };
+        self.width = self.width.max(x + width);
+        Some((x, y))
+    }
+}
+
+/// Packs rectangles into as few pages of at most `max_size` pixels per side as this shelf packer
+/// manages, tallest first. Returns the size of each page and the placement of each rectangle.
+pub(crate) fn pack(
+    sizes: &[(u32, u32)],
+    max_size: u32,
+    padding: u32,
+) -> (Vec<(u32, u32)>, Vec<Placement>) {
+    let mut order = (0..sizes.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&i| std::cmp::Reverse((sizes[i].1, sizes[i].0)));
+
+    let mut pages: Vec<Page> = Vec::new();
+    let mut placements = vec![
+        Placement {
+            page: 0,
+            x: 0,
+            y: 0
+        };
+        sizes.len()
+    ];
+    for i in order {
+        let padded = (sizes[i].0 + padding, sizes[i].1 + padding);
+        let found = pages
+            .iter_mut()
+            .enumerate()
+            .find_map(|(page, p)| p.insert(padded).map(|(x, y)| Placement { page, x, y }));
+        placements[i] = match found {
+            Some(placement) => placement,
+            None => {
+                // Oversized images get a page sized to fit them.
+                let mut page = Page::new((max_size.max(padded.0), max_size.max(padded.1)));
+                let (x, y) = page
+                    .insert(padded)
+                    .expect("Image does not fit an empty page");
+                pages.push(page);
+                Placement {
+                    page: pages.len() - 1,
+                    x,
+                    y,
+                }
+            }
+        };
+    }
+
+    let page_sizes = pages
+        .iter()
+        .map(|page| (page.width.max(1), page.height.max(1)))
+        .collect();
+    (page_sizes, placements)
+}
+
+fn premultiply_alpha(pixel: &mut image::Rgba<u8>) {
+    let alpha = u16::from(pixel[3]);
+    pixel.apply_without_alpha(|channel| ((u16::from(channel) * alpha + 127) / 255) as u8);
+}
+
+/// Copies packed images into their pages, premultiplying their alpha.
+pub(crate) fn compose_pages<'a>(
+    images: impl Iterator<Item = &'a RgbaImage>,
+    placements: &[Placement],
+    page_sizes: &[(u32, u32)],
+) -> Vec<RgbaImage> {
+    let mut pages = page_sizes
+        .iter()
+        .map(|&(width, height)| RgbaImage::new(width, height))
+        .collect::<Vec<_>>();
+    for (image, placement) in images.zip(placements) {
+        let page = &mut pages[placement.page];
+        for (x, y, pixel) in image.enumerate_pixels() {
+            let mut pixel = *pixel;
+            premultiply_alpha(&mut pixel);
+            page.put_pixel(placement.x + x, placement.y + y, pixel);
+        }
+    }
+    pages
+}
+
+/// Creates the texture data of a composed page.
+pub(crate) fn page_texture_data(page: RgbaImage, sampler_info: SamplerInfo) -> TextureData {
+    let (width, height) = page.dimensions();
+    TextureBuilder::new()
+        .with_kind(Kind::D2(width, height, 1, 1))
+        .with_view_kind(ViewKind::D2)
+        .with_data_width(width)
+        .with_data_height(height)
+        .with_sampler_info(sampler_info)
+        .with_premultiplied_alpha(true)
+        .with_raw_data(page.into_raw(), Format::Rgba8Srgb)
+        .into()
+}
+
+/// Maps normalized coordinates within a packed image to coordinates within its page.
+pub(crate) fn region_coordinates(
+    placement: &Placement,
+    (u, v): (f32, f32),
+    (image_width, image_height): (u32, u32),
+    (page_width, page_height): (u32, u32),
+) -> (f32, f32) {
+    (
+        (placement.x as f32 + u * image_width as f32) / page_width as f32,
+        (placement.y as f32 + v * image_height as f32) / page_height as f32,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overlaps(a: (Placement, (u32, u32)), b: (Placement, (u32, u32))) -> bool {
+        a.0.page == b.0.page
+            && a.0.x < b.0.x + (b.1).0
+            && b.0.x < a.0.x + (a.1).0
+            && a.0.y < b.0.y + (b.1).1
+            && b.0.y < a.0.y + (a.1).1
+    }
+
+    #[test]
+    fn packs_images_without_overlap() {
+        let sizes = vec![(32, 32), (64, 16), (16, 64), (8, 8), (100, 20), (30, 30)];
+        let (pages, placements) = pack(&sizes, 128, 1);
+
+        assert_eq!(pages.len(), 1);
+        for (i, a) in placements.iter().enumerate() {
+            assert!(a.x + sizes[i].0 <= pages[a.page].0);
+            assert!(a.y + sizes[i].1 <= pages[a.page].1);
+            for (j, b) in placements.iter().enumerate().skip(i + 1) {
+                assert!(!overlaps((*a, sizes[i]), (*b, sizes[j])));
+            }
+        }
+    }
+
+    #[test]
+    fn overflowing_images_open_new_pages() {
+        let sizes = vec![(60, 60); 5];
+        let (pages, placements) = pack(&sizes, 128, 0);
+
+        assert_eq!(pages, vec![(120, 120), (60, 60)]);
+        assert_eq!(placements.iter().filter(|p| p.page == 1).count(), 1);
+
+        let (pages, _) = pack(&[(300, 10)], 128, 0);
+        assert_eq!(pages, vec![(300, 10)]);
+    }
+}
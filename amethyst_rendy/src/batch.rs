@@ -4,6 +4,7 @@ use crate::util::TapCountIter;
 use derivative::Derivative;
 use smallvec::{smallvec, SmallVec};
 use std::{
+    cmp::Ordering,
     collections::hash_map::Entry,
     iter::{Extend, FromIterator},
     ops::Range,
@@ -364,6 +365,16 @@ where
         }
     }
 
+    /// Inserts items given with their view space depth, farthest first so they blend back to
+    /// front. The camera looks along negative z, so items are sorted by increasing depth.
+    pub fn insert_back_to_front(&mut self, items: impl IntoIterator<Item = (f32, PK, D)>) {
+        let mut sorted = items.into_iter().collect::<Vec<_>>();
+        sorted.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+        for (_, pk, data) in sorted {
+            self.insert(pk, Some(data));
+        }
+    }
+
     /// Returns an iterator to raw data for this batch.
     pub fn data(&self) -> &Vec<D> {
         &self.data_list
@@ -422,4 +433,20 @@ mod tests {
         assert_eq!(batch.count(), 0);
         assert_eq!(batch.iter().collect::<Vec<_>>(), vec![]);
     }
+
+    #[test]
+    fn test_ordered_onelevel_batch_back_to_front() {
+        let mut batch = OrderedOneLevelBatch::<u32, char>::default();
+        batch.insert_back_to_front(vec![
+            (-1.0, 0, 'a'),
+            (-5.0, 1, 'b'),
+            (2.0, 0, 'c'),
+            (-3.0, 1, 'd'),
+        ]);
+        assert_eq!(batch.data(), &vec!['b', 'd', 'a', 'c']);
+        assert_eq!(
+            batch.iter().collect::<Vec<_>>(),
+            vec![(&1, 0..2), (&0, 2..4)]
+        );
+    }
 }
@@ -0,0 +1,48 @@
+//! Sprites drawn in 3D as quads facing the camera.
+use crate::sprite::SpriteRender;
+use amethyst_core::ecs::prelude::{Component, DenseVecStorage};
+
+/// How a [Billboard] turns towards the camera.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BillboardMode {
+    /// Face the camera on every axis, e.g. for impostors and health bars.
+    Spherical,
+    /// Turn only around the world y axis, staying upright, e.g. for foliage.
+    Cylindrical,
+}
+
+/// Component drawing a sprite as a quad facing the camera, with the `RenderBillboards` plugin.
+///
+/// The quad is centered on the world position of the `Transform` of the entity, offset by
+/// the sprite offsets, and sized by the sprite scaled by the global x and y scale of the
+/// transform. The rotation of the transform is ignored. Billboards are tinted by the `Tint`
+/// component.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Billboard {
+    /// Sprite drawn on the quad.
+    pub sprite: SpriteRender,
+    /// How the quad turns towards the camera.
+    pub mode: BillboardMode,
+}
+
+impl Billboard {
+    /// Billboard facing the camera on every axis.
+    pub fn spherical(sprite: SpriteRender) -> Self {
+        Billboard {
+            sprite,
+            mode: BillboardMode::Spherical,
+        }
+    }
+
+    /// Billboard turning only around the world y axis.
+    pub fn cylindrical(sprite: SpriteRender) -> Self {
+        Billboard {
+            sprite,
+            mode: BillboardMode::Cylindrical,
+        }
+    }
+}
+
+impl Component for Billboard {
+    type Storage = DenseVecStorage<Self>;
+}
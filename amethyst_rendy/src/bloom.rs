@@ -0,0 +1,48 @@
+//! Bloom post-processing configuration.
+
+use amethyst_core::ecs::{Read, SystemData, World};
+
+/// Maximum number of blurred levels of the bloom chain.
+pub const MAX_BLOOM_LEVELS: usize = 6;
+
+/// Resource configuring the bloom effect applied by `RenderBloom`.
+///
+/// Changing `levels` rebuilds the render graph.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BloomParams {
+    /// Brightness above which pixels contribute to bloom.
+    pub threshold: f32,
+    /// Width of the smooth transition below the threshold, relative to the threshold.
+    /// Zero gives a hard cutoff.
+    pub soft_knee: f32,
+    /// Strength of the bloom added to the image. Zero disables the effect.
+    pub intensity: f32,
+    /// Number of blurred levels, each at half the resolution of the previous one,
+    /// clamped between 1 and 6. More levels give a wider glow.
+    pub levels: usize,
+}
+
+impl Default for BloomParams {
+    fn default() -> Self {
+        Self {
+            threshold: 1.0,
+            soft_knee: 0.5,
+            intensity: 0.1,
+            levels: 5,
+        }
+    }
+}
+
+impl BloomParams {
+    /// Returns the `BloomParams` resource, or default parameters if there is none.
+    pub fn fetch(world: &World) -> Self {
+        <Option<Read<'_, BloomParams>>>::fetch(world)
+            .map(|p| *p)
+            .unwrap_or_default()
+    }
+
+    /// Number of blurred levels actually rendered.
+    pub fn level_count(&self) -> usize {
+        self.levels.max(1).min(MAX_BLOOM_LEVELS)
+    }
+}
@@ -1,16 +1,26 @@
 //! A home of [RenderingBundle] with it's rendering plugins system and all types directly related to it.
 
 use crate::{
+    debug_drawing::DebugLinesExpirySystem,
+    dynamic_mesh::MeshUpdateSystem,
+    graph_description::{
+        node_type_name, BufferDescription, ImageDescription, NodeDescription,
+        RenderGraphDescription, ResourceAccessDescription,
+    },
+    light_probe::LightProbeGrid,
     mtl::Material,
+    pass::{CountedGroupBuilder, PassTimerDesc},
     rendy::{
         factory::Factory,
         graph::{
+            present::PresentNode,
             render::{RenderGroupBuilder, RenderPassNodeBuilder, SubpassBuilder},
-            GraphBuilder, ImageId, NodeId,
+            BufferId, DescBuilder, GraphBuilder, ImageId, NodeBuilder, NodeDesc, NodeId,
         },
         hal,
         wsi::Surface,
     },
+    stats::RenderGroupId,
     system::{GraphCreator, MeshProcessorSystem, RenderingSystem, TextureProcessorSystem},
     types::Backend,
     SpriteSheet,
@@ -21,7 +31,11 @@ use amethyst_core::{
     SystemBundle,
 };
 use amethyst_error::{format_err, Error};
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    ops::{Deref, DerefMut},
+    path::PathBuf,
+};
 
 /// A bundle of systems used for rendering using `Rendy` render graph.
 ///
@@ -29,26 +43,40 @@ use std::collections::HashMap;
 /// By itself doesn't render anything, you must use `with_plugin` method
 /// to define a set of functionalities you want to use.
 ///
-/// If you need much more control, or you need to deal directly with the render pipeline,
-/// it's possible to define a `RenderGraphCreator` as show by the
+/// Custom render passes are added by implementing [RenderPlugin], as shown by the
+/// `custom_render_pass` example. If you need much more control, or you need to deal directly
+/// with the render graph, it's possible to define a [GraphCreator] as shown by the
 /// `renderable_custom` example.
 #[derive(Debug)]
 pub struct RenderingBundle<B: Backend> {
     plugins: Vec<Box<dyn RenderPlugin<B>>>,
+    post_processes: Vec<Box<dyn PostProcessPlugin<B>>>,
+    pipeline_cache: Option<PathBuf>,
 }
 
 impl<B: Backend> RenderingBundle<B> {
     /// Create empty `RenderingBundle`. You must register a plugin using
-    /// [`with_plugin`] in order to actually display anything.
+    /// [`with_plugin`](Self::with_plugin) in order to actually display anything.
     pub fn new() -> Self {
         Self {
             plugins: Vec::new(),
+            post_processes: Vec::new(),
+            pipeline_cache: None,
         }
     }
 
+    /// Persist the pipeline cache in the file at `path`, loaded on startup and written on
+    /// shutdown, so pipelines don't have to be compiled again on the next run.
+    ///
+    /// See [PipelineCache](crate::pipeline::PipelineCache).
+    pub fn with_pipeline_cache(mut self, path: impl Into<PathBuf>) -> Self {
+        self.pipeline_cache = Some(path.into());
+        self
+    }
+
     /// Register a [`RenderPlugin`].
     ///
-    /// If you want the non-consuming version of this method, see [`add_plugin`].
+    /// If you want the non-consuming version of this method, see [`add_plugin`](Self::add_plugin).
     pub fn with_plugin(mut self, plugin: impl RenderPlugin<B> + 'static) -> Self {
         self.add_plugin(plugin);
         self
@@ -59,9 +87,25 @@ impl<B: Backend> RenderingBundle<B> {
         self.plugins.push(Box::new(plugin));
     }
 
+    /// Register a [`PostProcessPlugin`].
+    ///
+    /// Effects are applied to the [`Target::Main`] output in registration order.
+    /// If you want the non-consuming version of this method, see [`add_post_process`](Self::add_post_process).
+    pub fn with_post_process(mut self, effect: impl PostProcessPlugin<B> + 'static) -> Self {
+        self.add_post_process(effect);
+        self
+    }
+
+    /// Register a [`PostProcessPlugin`].
+    pub fn add_post_process(&mut self, effect: impl PostProcessPlugin<B> + 'static) {
+        self.post_processes.push(Box::new(effect));
+    }
+
     fn into_graph_creator(self) -> PluggableRenderGraphCreator<B> {
         PluggableRenderGraphCreator {
             plugins: self.plugins,
+            post_processes: self.post_processes,
+            description: None,
         }
     }
 }
@@ -84,21 +128,38 @@ impl<'a, 'b, B: Backend> SystemBundle<'a, 'b> for RenderingBundle<B> {
             "sprite_sheet_processor",
             &[],
         );
+        builder.add(
+            Processor::<LightProbeGrid>::new(),
+            "light_probe_grid_processor",
+            &[],
+        );
 
         // make sure that all renderer-specific systems run after game code
         builder.add_barrier();
+        builder.add(MeshUpdateSystem::<B>::default(), "mesh_update", &[]);
+        builder.add(DebugLinesExpirySystem, "debug_lines_expiry", &[]);
 
         for plugin in &mut self.plugins {
             plugin.on_build(world, builder)?;
         }
+        for effect in &mut self.post_processes {
+            effect.on_build(world, builder)?;
+        }
 
-        builder.add_thread_local(RenderingSystem::<B, _>::new(self.into_graph_creator()));
+        let pipeline_cache = self.pipeline_cache.take();
+        let mut system = RenderingSystem::<B, _>::new(self.into_graph_creator());
+        if let Some(path) = pipeline_cache {
+            system = system.with_pipeline_cache(path);
+        }
+        builder.add_thread_local(system);
         Ok(())
     }
 }
 
 struct PluggableRenderGraphCreator<B: Backend> {
     plugins: Vec<Box<dyn RenderPlugin<B>>>,
+    post_processes: Vec<Box<dyn PostProcessPlugin<B>>>,
+    description: Option<RenderGraphDescription>,
 }
 
 impl<B: Backend> GraphCreator<B> for PluggableRenderGraphCreator<B> {
@@ -107,6 +168,9 @@ impl<B: Backend> GraphCreator<B> for PluggableRenderGraphCreator<B> {
         for plugin in self.plugins.iter_mut() {
             rebuild = plugin.should_rebuild(world) || rebuild;
         }
+        for effect in self.post_processes.iter_mut() {
+            rebuild = effect.should_rebuild(world) || rebuild;
+        }
         rebuild
     }
 
@@ -119,7 +183,15 @@ impl<B: Backend> GraphCreator<B> for PluggableRenderGraphCreator<B> {
         for plugin in self.plugins.iter_mut() {
             plugin.on_plan(&mut plan, factory, world).unwrap();
         }
-        plan.build(factory).unwrap()
+        plan.chain_post_processes(&mut self.post_processes, factory, world)
+            .unwrap();
+        let graph = plan.build(factory).unwrap();
+        self.description = Some(graph.description);
+        graph.builder
+    }
+
+    fn description(&mut self) -> Option<RenderGraphDescription> {
+        self.description.take()
     }
 }
 
@@ -128,6 +200,11 @@ impl<B: Backend> GraphCreator<B> for PluggableRenderGraphCreator<B> {
 /// Can be used to register rendering-related systems to the dispatcher,
 /// building render graph by registering render targets, adding [RenderableAction]s to them
 /// and signalling when the graph has to be rebuild.
+///
+/// A plugin declares the targets it renders to with [RenderPlan::define_pass] and adds its
+/// render groups with [RenderPlan::extend_target]. Targets can be extended by any number of
+/// plugins, e.g. `RenderToWindow` defines [Target::Main] which `RenderPbr3D` and
+/// `RenderDebugLines` both draw to, ordered by [RenderOrder].
 pub trait RenderPlugin<B: Backend>: std::fmt::Debug {
     /// Hook for adding systems and bundles to the dispatcher.
     fn on_build<'a, 'b>(
@@ -152,11 +229,82 @@ pub trait RenderPlugin<B: Backend>: std::fmt::Debug {
     ) -> Result<(), Error>;
 }
 
+/// A fullscreen effect applied to the rendered image by [RenderingBundle].
+///
+/// Effects are chained after [`Target::Main`]: each one renders into its own
+/// [`Target::PostProcess`] target, reading the color output of the previous step.
+/// The last effect in the chain renders to the original output of the main target,
+/// e.g. the window surface.
+pub trait PostProcessPlugin<B: Backend>: std::fmt::Debug {
+    /// Hook for adding systems and bundles to the dispatcher.
+    fn on_build<'a, 'b>(
+        &mut self,
+        _world: &mut World,
+        _builder: &mut DispatcherBuilder<'a, 'b>,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Hook for providing triggers to rebuild the render graph.
+    fn should_rebuild(&mut self, _world: &World) -> bool {
+        false
+    }
+
+    /// Hook for extending the rendering plan with this effect.
+    ///
+    /// The effect must fill the whole `stage.target`, which is already defined
+    /// with a single color output and no depth. It may define additional targets,
+    /// e.g. for downsampled intermediate images.
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        stage: PostProcessStage,
+        factory: &mut Factory<B>,
+        world: &World,
+    ) -> Result<(), Error>;
+}
+
+/// Format of the main target color output and intermediate images
+/// when post-processing is enabled.
+pub const POST_PROCESS_FORMAT: hal::format::Format = hal::format::Format::Rgba16Sfloat;
+
+/// Position of a [PostProcessPlugin] in the post-processing chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PostProcessStage {
+    /// Target the effect renders to.
+    pub target: Target,
+    /// Color output of the previous step in the chain.
+    pub input: TargetImage,
+    /// Width of the input and output images.
+    pub width: u32,
+    /// Height of the input and output images.
+    pub height: u32,
+}
+
+impl PostProcessStage {
+    /// Options for an intermediate image of this stage with the given downscaling factor.
+    pub fn image_options(&self, downscale: u32) -> ImageOptions {
+        let downscale = downscale.max(1);
+        ImageOptions {
+            kind: hal::image::Kind::D2(
+                (self.width / downscale).max(1),
+                (self.height / downscale).max(1),
+                1,
+                1,
+            ),
+            levels: 1,
+            format: POST_PROCESS_FORMAT,
+            clear: None,
+        }
+    }
+}
+
 /// Builder of a rendering plan for specified target.
 #[derive(Debug)]
 pub struct RenderPlan<B: Backend> {
     targets: HashMap<Target, TargetPlan<B>>,
     roots: Vec<Target>,
+    time_passes: bool,
 }
 
 impl<B: Backend> RenderPlan<B> {
@@ -164,9 +312,20 @@ impl<B: Backend> RenderPlan<B> {
         Self {
             targets: Default::default(),
             roots: vec![],
+            time_passes: false,
         }
     }
 
+    /// Write GPU timestamps between the render groups of every evaluated target and count
+    /// their draw calls, published in the [RenderStats](crate::stats::RenderStats) resource.
+    ///
+    /// Every render group is drawn in its own render pass for that, and targets rendering
+    /// directly to a window surface render to an image copied to the surface instead.
+    /// The resource must be inserted into the world.
+    pub fn time_passes(&mut self) {
+        self.time_passes = true;
+    }
+
     /// Mark render target as root. Root render targets are always
     /// evaluated, even if nothing depends on them.
     pub fn add_root(&mut self, target: Target) {
@@ -191,6 +350,29 @@ impl<B: Backend> RenderPlan<B> {
         Ok(())
     }
 
+    /// Render the pass of `target` with the depth output of `source` as its depth attachment,
+    /// keeping the depth `source` drew, e.g. to depth test more geometry against it.
+    ///
+    /// `target` must be defined without a depth output. Its pass runs after the one of
+    /// `source`, and its images can't be sampled by the groups of `source`.
+    pub fn share_depth(&mut self, target: Target, source: Target) {
+        let target_plan = self
+            .targets
+            .entry(target)
+            .or_insert_with(|| TargetPlan::new(target));
+        target_plan.depth_source = Some(source);
+    }
+
+    /// Retrieve metadata, e.g. size, of a render target defined with `define_pass`.
+    ///
+    /// Returns `None` when the target is not defined yet.
+    pub fn target_metadata(&self, target: Target, factory: &Factory<B>) -> Option<TargetMetadata> {
+        // safety: window surfaces of render targets are created by the same factory.
+        self.targets
+            .get(&target)
+            .and_then(|plan| unsafe { plan.metadata(factory.physical()) })
+    }
+
     /// Extend the rendering plan of a render target. Target can be defined in other plugins.
     /// The closure is evaluated only if the target contributes to the rendering result, e.g.
     /// is rendered to a window or is a dependency of other evaluated target.
@@ -206,7 +388,105 @@ impl<B: Backend> RenderPlan<B> {
         target_plan.add_extension(Box::new(closure));
     }
 
-    fn build(self, factory: &Factory<B>) -> Result<GraphBuilder<B, World>, Error> {
+    /// Extend the rendering plan with nodes running after the pass of a render target, e.g.
+    /// compute nodes reading its outputs added with
+    /// [TargetPlanContext::add_compute](crate::bundle::TargetPlanContext::add_compute).
+    ///
+    /// The closure is evaluated right after the pass is built, only if the target is
+    /// evaluated. Render groups can't be added to the target from it.
+    pub fn extend_target_after(
+        &mut self,
+        target: Target,
+        closure: impl FnOnce(&mut TargetPlanContext<'_, B>) -> Result<(), Error> + 'static,
+    ) {
+        let target_plan = self
+            .targets
+            .entry(target)
+            .or_insert_with(|| TargetPlan::new(target));
+        target_plan.add_follow_up(Box::new(closure));
+    }
+
+    /// Reroute the color output of [`Target::Main`] through a chain of post-processing effects.
+    fn chain_post_processes(
+        &mut self,
+        effects: &mut [Box<dyn PostProcessPlugin<B>>],
+        factory: &mut Factory<B>,
+        world: &World,
+    ) -> Result<(), Error> {
+        if effects.is_empty() {
+            return Ok(());
+        }
+
+        let main = self
+            .targets
+            .get_mut(&Target::Main)
+            .filter(|plan| plan.outputs.is_some())
+            .ok_or_else(|| {
+                format_err!(
+                    "Post-processing requires {:?} target to be defined.",
+                    Target::Main
+                )
+            })?;
+        // safety: the surface of the main target is created by the same factory.
+        let metadata = unsafe { main.metadata(factory.physical()) }.expect("Outputs are defined");
+        let outputs = main.outputs.as_mut().expect("Outputs are defined");
+        if outputs.colors.len() != 1 {
+            return Err(format_err!(
+                "Post-processing requires {:?} target with a single color output, found {}.",
+                Target::Main,
+                outputs.colors.len(),
+            ));
+        }
+
+        let mut stage = PostProcessStage {
+            target: Target::Main,
+            input: TargetImage::Color(Target::Main, 0),
+            width: metadata.width,
+            height: metadata.height,
+        };
+        let clear = match &outputs.colors[0] {
+            OutputColor::Surface(_, clear) => *clear,
+            OutputColor::Image(options) | OutputColor::Present(_, _, options) => options.clear,
+        };
+        let scene = OutputColor::Image(ImageOptions {
+            clear: clear.or(Some(hal::command::ClearValue::Color(
+                hal::command::ClearColor::Sfloat([0.0, 0.0, 0.0, 1.0]),
+            ))),
+            ..stage.image_options(1)
+        });
+        let mut output = Some(std::mem::replace(&mut outputs.colors[0], scene));
+
+        let last = effects.len() - 1;
+        for (i, effect) in effects.iter_mut().enumerate() {
+            stage.target = Target::PostProcess(i);
+            let color = if i == last {
+                output.take().expect("Final output is taken once")
+            } else {
+                OutputColor::Image(stage.image_options(1))
+            };
+            self.define_pass(
+                stage.target,
+                TargetPlanOutputs {
+                    colors: vec![color],
+                    depth: None,
+                },
+            )?;
+            effect.on_plan(self, stage, factory, world)?;
+            stage.input = TargetImage::Color(stage.target, 0);
+        }
+        self.add_root(Target::PostProcess(last));
+
+        Ok(())
+    }
+
+    fn build(mut self, factory: &Factory<B>) -> Result<PlanGraph<B>, Error> {
+        if self.time_passes {
+            for plan in self.targets.values_mut() {
+                // safety: the surfaces of the targets are created by the same factory.
+                unsafe { plan.present_surfaces(factory.physical()) };
+            }
+        }
+
         let mut ctx = PlanContext {
             target_metadata: self
                 .targets
@@ -216,13 +496,31 @@ impl<B: Backend> RenderPlan<B> {
             targets: self.targets,
             passes: Default::default(),
             outputs: Default::default(),
-            graph_builder: GraphBuilder::new(),
+            graph_builder: PlanGraph::new(),
+            time_passes: self.time_passes,
+            presents: vec![],
         };
 
         for target in self.roots {
             ctx.evaluate_target(target)?;
         }
 
+        for present in ctx.presents.drain(..) {
+            let mode = present.mode;
+            let builder = PresentNode::builder(factory, present.surface, present.image)
+                .with_dependency(present.node)
+                .with_present_modes_priority(|supported| {
+                    if supported == mode {
+                        Some(1)
+                    } else if supported == hal::window::PresentMode::Fifo {
+                        Some(0)
+                    } else {
+                        None
+                    }
+                });
+            ctx.graph_builder.add_node(builder);
+        }
+
         Ok(ctx.graph_builder)
     }
 }
@@ -255,13 +553,41 @@ pub struct TargetMetadata {
     layers: u16,
 }
 
+impl TargetMetadata {
+    /// Width of the target's framebuffer.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Height of the target's framebuffer.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Number of layers of the target's framebuffer.
+    pub fn layers(&self) -> u16 {
+        self.layers
+    }
+}
+
+/// Image of a pass to be presented to a window surface with a present mode.
+#[derive(Debug)]
+struct PendingPresent<B: Backend> {
+    surface: Surface<B>,
+    mode: hal::window::PresentMode,
+    image: ImageId,
+    node: NodeId,
+}
+
 #[derive(Debug)]
 struct PlanContext<B: Backend> {
     targets: HashMap<Target, TargetPlan<B>>,
     target_metadata: HashMap<Target, TargetMetadata>,
     passes: HashMap<Target, EvaluationState>,
     outputs: HashMap<TargetImage, ImageId>,
-    graph_builder: GraphBuilder<B, World>,
+    graph_builder: PlanGraph<B>,
+    time_passes: bool,
+    presents: Vec<PendingPresent<B>>,
 }
 
 impl<B: Backend> PlanContext<B> {
@@ -298,7 +624,9 @@ impl<B: Backend> PlanContext<B> {
                 target
             ),
         };
-        let node = self.graph_builder.add_node(pass);
+        let node = self
+            .graph_builder
+            .add_named_node(format!("{:?}", target), pass);
         self.passes.insert(target, EvaluationState::Built(node));
         Ok(())
     }
@@ -356,7 +684,7 @@ impl<B: Backend> PlanContext<B> {
         Ok(())
     }
 
-    pub fn graph(&mut self) -> &mut GraphBuilder<B, World> {
+    pub fn graph(&mut self) -> &mut PlanGraph<B> {
         &mut self.graph_builder
     }
 
@@ -366,6 +694,114 @@ impl<B: Backend> PlanContext<B> {
     }
 }
 
+/// Render graph builder of a [RenderPlan], which describes the nodes, images and buffers
+/// added to it in a [RenderGraphDescription].
+///
+/// Dereferences to rendy's `GraphBuilder`. Nodes and resources added to the `GraphBuilder`
+/// itself are missing from the description.
+#[derive(Debug)]
+pub struct PlanGraph<B: Backend> {
+    builder: GraphBuilder<B, World>,
+    description: RenderGraphDescription,
+    nodes: HashMap<NodeId, usize>,
+    images: HashMap<ImageId, usize>,
+    buffers: HashMap<BufferId, usize>,
+}
+
+impl<B: Backend> PlanGraph<B> {
+    fn new() -> Self {
+        Self {
+            builder: GraphBuilder::new(),
+            description: Default::default(),
+            nodes: Default::default(),
+            images: Default::default(),
+            buffers: Default::default(),
+        }
+    }
+
+    /// Add a node to the graph, described by the name of its type.
+    pub fn add_node<N: NodeBuilder<B, World> + 'static>(&mut self, builder: N) -> NodeId {
+        self.add_named_node(node_type_name::<N>(), builder)
+    }
+
+    /// Add a node to the graph, described by `name`.
+    pub fn add_named_node<N: NodeBuilder<B, World> + 'static>(
+        &mut self,
+        name: String,
+        builder: N,
+    ) -> NodeId {
+        let images = builder
+            .images()
+            .iter()
+            .filter_map(|(id, access)| {
+                let index = *self.images.get(id)?;
+                Some(ResourceAccessDescription::image(index, access))
+            })
+            .collect();
+        let buffers = builder
+            .buffers()
+            .iter()
+            .filter_map(|(id, access)| {
+                let index = *self.buffers.get(id)?;
+                Some(ResourceAccessDescription::buffer(index, access))
+            })
+            .collect();
+        let dependencies = builder
+            .dependencies()
+            .iter()
+            .filter_map(|id| self.nodes.get(id).copied())
+            .collect();
+
+        let node = self.builder.add_node(builder);
+        self.nodes.insert(node, self.description.nodes.len());
+        self.description.nodes.push(NodeDescription {
+            name,
+            images,
+            buffers,
+            dependencies,
+        });
+        node
+    }
+
+    /// Create an image managed by the graph.
+    pub fn create_image(
+        &mut self,
+        kind: hal::image::Kind,
+        levels: hal::image::Level,
+        format: hal::format::Format,
+        clear: Option<hal::command::ClearValue>,
+    ) -> ImageId {
+        let image = self.builder.create_image(kind, levels, format, clear);
+        self.images.insert(image, self.description.images.len());
+        self.description
+            .images
+            .push(ImageDescription::new(kind, levels, format, clear.is_some()));
+        image
+    }
+
+    /// Create a buffer managed by the graph.
+    pub fn create_buffer(&mut self, size: u64) -> BufferId {
+        let buffer = self.builder.create_buffer(size);
+        self.buffers.insert(buffer, self.description.buffers.len());
+        self.description.buffers.push(BufferDescription { size });
+        buffer
+    }
+}
+
+impl<B: Backend> Deref for PlanGraph<B> {
+    type Target = GraphBuilder<B, World>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.builder
+    }
+}
+
+impl<B: Backend> DerefMut for PlanGraph<B> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.builder
+    }
+}
+
 /// A planning context focused on specific render target.
 #[derive(Debug)]
 pub struct TargetPlanContext<'a, B: Backend> {
@@ -453,11 +889,30 @@ impl<'a, B: Backend> TargetPlanContext<'a, B> {
         }
     }
 
+    /// Add a node running before the pass of this target, like a compute node dispatching
+    /// [DispatchComputeDesc](crate::pass::DispatchComputeDesc).
+    ///
+    /// The node depends on the nodes producing the images retrieved with `get_image` so far,
+    /// so retrieve the images it reads before adding it.
+    ///
+    /// Nodes added from [RenderPlan::extend_target_after] run after the pass instead.
+    pub fn add_compute<N: NodeDesc<B, World>>(
+        &mut self,
+        mut builder: DescBuilder<B, World, N>,
+    ) -> NodeId {
+        for dep in &self.deps {
+            builder.add_dependency(*dep);
+        }
+        let node = self.graph().add_node(builder);
+        self.add_dep(node);
+        node
+    }
+
     /// Access underlying rendy's GraphBuilder directly.
     /// This is useful for adding custom rendering nodes
     /// that are not just standard graphics render passes,
     /// e.g. for compute dispatch.
-    pub fn graph(&mut self) -> &mut GraphBuilder<B, World> {
+    pub fn graph(&mut self) -> &mut PlanGraph<B> {
         self.plan_context.graph()
     }
 
@@ -511,6 +966,10 @@ pub enum OutputColor<B: Backend> {
     Image(ImageOptions),
     /// Render directly to a window surface.
     Surface(Surface<B>, Option<hal::command::ClearValue>),
+    /// Render to an image with specified options, which is then copied to a window surface
+    /// presenting with the given mode, falling back to `Fifo` if the surface doesn't support
+    /// it. Rendering directly to a surface always presents with `Fifo`.
+    Present(Surface<B>, hal::window::PresentMode, ImageOptions),
 }
 
 /// Definition for set of outputs for a given render target.
@@ -528,7 +987,10 @@ struct TargetPlan<B: Backend> {
     key: Target,
     #[derivative(Debug = "ignore")]
     extensions: Vec<Box<dyn FnOnce(&mut TargetPlanContext<'_, B>) -> Result<(), Error> + 'static>>,
+    #[derivative(Debug = "ignore")]
+    follow_ups: Vec<Box<dyn FnOnce(&mut TargetPlanContext<'_, B>) -> Result<(), Error> + 'static>>,
     outputs: Option<TargetPlanOutputs<B>>,
+    depth_source: Option<Target>,
 }
 
 impl<B: Backend> TargetPlan<B> {
@@ -536,7 +998,9 @@ impl<B: Backend> TargetPlan<B> {
         Self {
             key,
             extensions: vec![],
+            follow_ups: vec![],
             outputs: None,
+            depth_source: None,
         }
     }
 
@@ -567,7 +1031,7 @@ impl<B: Backend> TargetPlan<B> {
                             }
                             framebuffer_layers = min(framebuffer_layers, 1);
                         }
-                        OutputColor::Image(options) => {
+                        OutputColor::Image(options) | OutputColor::Present(_, _, options) => {
                             let extent = options.kind.extent();
                             framebuffer_width = min(framebuffer_width, extent.width);
                             framebuffer_height = min(framebuffer_height, extent.height);
@@ -589,6 +1053,36 @@ impl<B: Backend> TargetPlan<B> {
             })
     }
 
+    /// Render to images presented to the window surfaces the target renders to, as surfaces
+    /// can't be shared by several render passes.
+    ///
+    /// safety:
+    /// * `physical_device` must be created from same `Instance` as the `Surface` present in output
+    unsafe fn present_surfaces(&mut self, physical_device: &B::PhysicalDevice) {
+        let colors = match &mut self.outputs {
+            Some(outputs) => &mut outputs.colors,
+            None => return,
+        };
+        for color in colors.iter_mut() {
+            if let OutputColor::Surface(surface, clear) = color {
+                let (width, height) = surface
+                    .extent(physical_device)
+                    .map_or((1, 1), |extent| (extent.width, extent.height));
+                let options = ImageOptions {
+                    kind: hal::image::Kind::D2(width, height, 1, 1),
+                    levels: 1,
+                    format: surface.format(physical_device),
+                    clear: *clear,
+                };
+                // Only used to move the surface out, replaced right away.
+                let placeholder = OutputColor::Image(options.clone());
+                if let OutputColor::Surface(surface, _) = std::mem::replace(color, placeholder) {
+                    *color = OutputColor::Present(surface, hal::window::PresentMode::Fifo, options);
+                }
+            }
+        }
+    }
+
     fn set_outputs(&mut self, outputs: TargetPlanOutputs<B>) -> Result<(), Error> {
         if self.outputs.is_some() {
             return Err(format_err!("Target {:?} already defined.", self.key));
@@ -604,6 +1098,13 @@ impl<B: Backend> TargetPlan<B> {
         self.extensions.push(extension);
     }
 
+    fn add_follow_up(
+        &mut self,
+        follow_up: Box<dyn FnOnce(&mut TargetPlanContext<'_, B>) -> Result<(), Error> + 'static>,
+    ) {
+        self.follow_ups.push(follow_up);
+    }
+
     fn evaluate(self, ctx: &mut PlanContext<B>) -> Result<(), Error> {
         if self.outputs.is_none() {
             return Err(format_err!(
@@ -612,15 +1113,24 @@ impl<B: Backend> TargetPlan<B> {
             ));
         }
         let mut outputs = self.outputs.unwrap();
+        if let (Some(_), Some(source)) = (&outputs.depth, self.depth_source) {
+            return Err(format_err!(
+                "Target {:?} defines a depth output and shares the depth of {:?}.",
+                self.key,
+                source
+            ));
+        }
 
         ctx.mark_evaluating(self.key)?;
 
+        let colors = outputs.colors.len();
+        let depth = outputs.depth.is_some() || self.depth_source.is_some();
         let mut target_ctx = TargetPlanContext {
             plan_context: ctx,
             key: self.key,
             actions: vec![],
-            colors: outputs.colors.len(),
-            depth: outputs.depth.is_some(),
+            colors,
+            depth,
             deps: vec![],
         };
 
@@ -632,44 +1142,150 @@ impl<B: Backend> TargetPlan<B> {
             mut actions, deps, ..
         } = target_ctx;
 
-        let mut subpass = SubpassBuilder::new();
-        let mut pass = RenderPassNodeBuilder::new();
-
+        let key = self.key;
         actions.sort_by_key(|a| a.0);
-        for action in actions.drain(..).map(|a| a.1) {
-            match action {
-                RenderableAction::RenderGroup(group) => {
-                    subpass.add_dyn_group(group);
+        let groups: Vec<_> = actions
+            .drain(..)
+            .enumerate()
+            .map(|(index, (order, action))| match action {
+                RenderableAction::RenderGroup(group) if ctx.time_passes => {
+                    let id = RenderGroupId { target: key, index };
+                    Box::new(CountedGroupBuilder::new(id, order, group))
+                        as Box<dyn RenderGroupBuilder<B, World>>
                 }
-            }
-        }
+                RenderableAction::RenderGroup(group) => group,
+            })
+            .collect();
 
+        let mut presents = vec![];
+        let mut surfaces = vec![];
+        // Color attachments, `None` for a surface.
+        let mut color_nodes = vec![];
         for (i, color) in outputs.colors.drain(..).enumerate() {
             match color {
                 OutputColor::Surface(surface, clear) => {
-                    subpass.add_color_surface();
-                    pass.add_surface(surface, clear);
+                    surfaces.push((surface, clear));
+                    color_nodes.push(None);
                 }
                 OutputColor::Image(opts) => {
                     let node = ctx.create_image(opts);
                     ctx.register_output(TargetImage::Color(self.key, i), node)?;
-                    subpass.add_color(node);
+                    color_nodes.push(Some(node));
+                }
+                OutputColor::Present(surface, mode, opts) => {
+                    let node = ctx.create_image(opts);
+                    ctx.register_output(TargetImage::Color(self.key, i), node)?;
+                    color_nodes.push(Some(node));
+                    presents.push((surface, mode, node));
                 }
             }
         }
 
-        if let Some(opts) = outputs.depth {
+        let mut deps = deps;
+        let depth_node = if let Some(opts) = outputs.depth {
             let node = ctx.create_image(opts);
             ctx.register_output(TargetImage::Depth(self.key), node)?;
-            subpass.set_depth_stencil(node);
+            Some(node)
+        } else if let Some(source) = self.depth_source {
+            // Only the first pass using an image clears it, so the depth is loaded here.
+            let node = ctx.get_image(TargetImage::Depth(source))?;
+            deps.push(ctx.get_node(source)?);
+            Some(node)
+        } else {
+            None
+        };
+
+        let subpass = |groups: Vec<Box<dyn RenderGroupBuilder<B, World>>>| {
+            let mut subpass = SubpassBuilder::new();
+            for group in groups {
+                subpass.add_dyn_group(group);
+            }
+            for color in &color_nodes {
+                match color {
+                    Some(node) => subpass.add_color(*node),
+                    None => subpass.add_color_surface(),
+                };
+            }
+            if let Some(node) = depth_node {
+                subpass.set_depth_stencil(node);
+            }
+            for node in &deps {
+                subpass.add_dependency(*node);
+            }
+            subpass
+        };
+
+        if ctx.time_passes && surfaces.is_empty() {
+            // Each group gets its own pass, between the timestamps timing it. Only the first
+            // pass clears the images, the others load them.
+            let mut passes: Vec<_> = groups.into_iter().map(|group| vec![group]).collect();
+            if passes.is_empty() {
+                passes.push(vec![]);
+            }
+            let last = passes.len();
+            let mut previous = None;
+            for (index, groups) in passes.into_iter().enumerate() {
+                let mut timer = PassTimerDesc::new(self.key, index, last).builder();
+                for node in previous.iter().chain(&deps) {
+                    timer.add_dependency(*node);
+                }
+                let timer = ctx.graph_builder.add_node(timer);
+
+                let mut subpass = subpass(groups);
+                subpass.add_dependency(timer);
+                let pass = RenderPassNodeBuilder::new().with_subpass(subpass);
+                previous = Some(if index + 1 == last {
+                    ctx.submit_pass(self.key, pass)?;
+                    ctx.get_pass_node_raw(self.key).expect("Just submitted")
+                } else {
+                    ctx.graph_builder.add_node(pass)
+                });
+            }
+            ctx.graph_builder.add_node(
+                PassTimerDesc::new(self.key, last, last)
+                    .builder()
+                    .with_dependency(previous.expect("At least one pass")),
+            );
+        } else {
+            let mut pass = RenderPassNodeBuilder::new();
+            for (surface, clear) in surfaces {
+                pass.add_surface(surface, clear);
+            }
+            pass.add_subpass(subpass(groups));
+            ctx.submit_pass(self.key, pass)?;
         }
 
-        for node in deps {
-            subpass.add_dependency(node);
+        let node = ctx.get_pass_node_raw(self.key).expect("Just submitted");
+        for (surface, mode, image) in presents {
+            // The present node is built once the factory is available, see `RenderPlan::build`.
+            ctx.presents.push(PendingPresent {
+                surface,
+                mode,
+                image,
+                node,
+            });
         }
 
-        pass.add_subpass(subpass);
-        ctx.submit_pass(self.key, pass)?;
+        if !self.follow_ups.is_empty() {
+            // Nodes added with `add_compute` depend on the pass through the context deps.
+            let mut target_ctx = TargetPlanContext {
+                plan_context: ctx,
+                key: self.key,
+                actions: vec![],
+                colors,
+                depth,
+                deps: vec![node],
+            };
+            for follow_up in self.follow_ups {
+                follow_up(&mut target_ctx)?;
+            }
+            if !target_ctx.actions.is_empty() {
+                return Err(format_err!(
+                    "Trying to add render actions to target {:?} after its pass is built.",
+                    self.key,
+                ));
+            }
+        }
         Ok(())
     }
 }
@@ -761,6 +1377,26 @@ pub enum Target {
     /// Render target for shadow mapping.
     /// Builtin plugins use cascaded shadow maps.
     ShadowMap,
+    /// Render target for point light shadow mapping.
+    /// Builtin plugins store cube map faces of all shadow casting lights in a single atlas.
+    PointShadowMap,
+    /// Render target for spot light shadow mapping.
+    /// Builtin plugins store shadow maps of all shadow casting spot lights in a single atlas.
+    SpotShadowMap,
+    /// Render target for screen-space ambient occlusion sampled by 3d passes.
+    AmbientOcclusion,
+    /// Render target for screen-space motion of opaque meshes since the previous frame,
+    /// shared by effects reprojecting or blurring the image.
+    Velocity,
+    /// Render target for ids of the entities drawn at the picked pixel of the main target.
+    Picking,
+    /// Render target accumulating transparent meshes with weighted blended order-independent
+    /// transparency, sharing the depth of the main target.
+    OrderIndependentTransparency,
+    /// Render target of the cube map faces captured by reflection probes.
+    ReflectionProbe,
+    /// Render target of an effect in the post-processing chain, by position in the chain.
+    PostProcess(usize),
     /// Custom render target identifier.
     Custom(&'static str),
 }
@@ -963,4 +1599,112 @@ mod tests {
             format!("{:?}", manual_graph)
         );
     }
+
+    #[derive(Debug)]
+    struct TestPostProcess;
+
+    impl<B: Backend> PostProcessPlugin<B> for TestPostProcess {
+        fn on_plan(
+            &mut self,
+            plan: &mut RenderPlan<B>,
+            stage: PostProcessStage,
+            _factory: &mut Factory<B>,
+            _world: &World,
+        ) -> Result<(), Error> {
+            plan.extend_target(stage.target, move |ctx| {
+                let input = ctx.get_image(stage.input)?;
+                ctx.add(
+                    RenderOrder::LinearPostEffects,
+                    TestGroup1.builder().with_image(input),
+                )?;
+                Ok(())
+            });
+            Ok(())
+        }
+    }
+
+    #[test]
+    #[ignore] // CI can't run tests requiring actual backend
+    fn main_pass_post_process_plan() {
+        let config: rendy::factory::Config = Default::default();
+        let (mut factory, families): (Factory<DefaultBackend>, _) =
+            rendy::factory::init(config).unwrap();
+        let world = World::new();
+        let mut plan = RenderPlan::<DefaultBackend>::new();
+
+        plan.extend_target(Target::Main, |ctx| {
+            ctx.add(RenderOrder::Opaque, TestGroup2.builder())?;
+            Ok(())
+        });
+
+        let kind = crate::Kind::D2(1920, 1080, 1, 1);
+        let depth_clear = Some(ClearValue::DepthStencil(ClearDepthStencil(0.0, 0)));
+        plan.add_root(Target::Main);
+        plan.define_pass(
+            Target::Main,
+            TargetPlanOutputs {
+                colors: vec![OutputColor::Image(ImageOptions {
+                    kind,
+                    levels: 1,
+                    format: Format::Rgb8Unorm,
+                    clear: None,
+                })],
+                depth: Some(ImageOptions {
+                    kind,
+                    levels: 1,
+                    format: Format::D32Sfloat,
+                    clear: depth_clear,
+                }),
+            },
+        )
+        .unwrap();
+
+        let mut effects: Vec<Box<dyn PostProcessPlugin<DefaultBackend>>> =
+            vec![Box::new(TestPostProcess), Box::new(TestPostProcess)];
+        plan.chain_post_processes(&mut effects, &mut factory, &world)
+            .unwrap();
+        let planned_graph = plan.build(&factory).unwrap();
+
+        let mut manual_graph = GraphBuilder::<DefaultBackend, World>::new();
+        let scene = manual_graph.create_image(
+            kind,
+            1,
+            POST_PROCESS_FORMAT,
+            Some(ClearValue::Color(hal::command::ClearColor::Sfloat([
+                0.0, 0.0, 0.0, 1.0,
+            ]))),
+        );
+        let depth = manual_graph.create_image(kind, 1, Format::D32Sfloat, depth_clear);
+        let main = manual_graph.add_node(
+            RenderPassNodeBuilder::new().with_subpass(
+                SubpassBuilder::new()
+                    .with_group(TestGroup2.builder())
+                    .with_color(scene)
+                    .with_depth_stencil(depth),
+            ),
+        );
+        let intermediate = manual_graph.create_image(kind, 1, POST_PROCESS_FORMAT, None);
+        let first = manual_graph.add_node(
+            RenderPassNodeBuilder::new().with_subpass(
+                SubpassBuilder::new()
+                    .with_group(TestGroup1.builder().with_image(scene))
+                    .with_color(intermediate)
+                    .with_dependency(main),
+            ),
+        );
+        let color = manual_graph.create_image(kind, 1, Format::Rgb8Unorm, None);
+        manual_graph.add_node(
+            RenderPassNodeBuilder::new().with_subpass(
+                SubpassBuilder::new()
+                    .with_group(TestGroup1.builder().with_image(intermediate))
+                    .with_color(color)
+                    .with_dependency(first),
+            ),
+        );
+
+        assert_eq!(
+            format!("{:?}", planned_graph),
+            format!("{:?}", manual_graph)
+        );
+    }
 }
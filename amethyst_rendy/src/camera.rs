@@ -4,7 +4,7 @@ use amethyst_assets::PrefabData;
 use amethyst_core::{
     ecs::prelude::{Component, Entity, HashMapStorage, Write, WriteStorage},
     geometry::Ray,
-    math::{Matrix4, Point2, Point3, Vector2},
+    math::{Matrix4, Point2, Point3, Vector2, Vector3, Vector4},
     transform::components::Transform,
 };
 use amethyst_error::Error;
@@ -28,6 +28,13 @@ use amethyst_error::Error;
 /// |
 /// +y
 ///
+/// Depth is reversed: the near plane is at depth 1 and `perspective` projections put the far
+/// plane at infinity, at depth 0. Floating point depth buffers are most precise around 0, which
+/// cancels out the loss of precision with distance of perspective depth, so distant geometry
+/// doesn't z-fight even without a far plane. Depth buffers are accordingly cleared to 0 and
+/// pipelines draw fragments with a `Greater` depth. Custom projection matrices and passes
+/// have to follow the same convention.
+///
 /// The camera also stores the inverse transformation in order to avoid recomputing it.
 ///
 /// If you change `matrix` you must also change `inverse` so that they stay in sync.
@@ -153,6 +160,45 @@ impl Camera {
         Self::from_matrix(matrix)
     }
 
+    /// An off-axis perspective projection, with the frustum through the given edges of the
+    /// near plane, e.g. for head-tracked displays or the view through a portal.
+    ///
+    /// Like `perspective`, the projection matrix is right-handed, has no far plane and depth
+    /// goes from 1 at the near plane to 0.
+    ///
+    /// # Arguments
+    ///
+    /// * `left` - The x-coordinate of the left edge of the near plane.
+    /// * `right` - The x-coordinate of the right edge of the near plane.
+    /// * `bottom` - The y-coordinate of the bottom edge of the near plane.
+    /// * `top` - The y-coordinate of the top edge of the near plane.
+    /// * `z_near` - Near clip plane distance
+    ///
+    /// * panics if `left` equals `right` or `bottom` equals `top`
+    pub fn off_axis(left: f32, right: f32, bottom: f32, top: f32, z_near: f32) -> Self {
+        if cfg!(debug_assertions) {
+            assert!(
+                !approx::relative_eq!(left - right, 0.0),
+                "The left-plane and right-plane must not be superimposed."
+            );
+            assert!(
+                !approx::relative_eq!(top - bottom, 0.0),
+                "The top-plane and bottom-plane must not be superimposed."
+            );
+        }
+
+        let mut matrix = Matrix4::<f32>::zeros();
+
+        matrix[(0, 0)] = 2.0 * z_near / (right - left);
+        matrix[(1, 1)] = -2.0 * z_near / (top - bottom);
+        matrix[(0, 2)] = (right + left) / (right - left);
+        matrix[(1, 2)] = -(top + bottom) / (top - bottom);
+        matrix[(2, 3)] = z_near;
+        matrix[(3, 2)] = -1.0;
+
+        Self::from_matrix(matrix)
+    }
+
     /// Makes a camera with the matrix provided.
     ///
     /// * panics if the matrix is not invertible
@@ -165,6 +211,36 @@ impl Camera {
         }
     }
 
+    /// Returns a camera whose near plane is replaced by the world space `plane`, seen from a
+    /// camera with the given transform, so that nothing on the negative side of the plane
+    /// is drawn, e.g. behind the mirror for a camera rendering its reflection.
+    ///
+    /// Unlike a `ClipPlane` component, this clips in all passes at no cost, but moves the far
+    /// plane of the projection to a finite distance and spreads depth precision over a larger
+    /// range the more the plane is tilted against the view direction. The plane should be in
+    /// front of the camera, facing away from it.
+    ///
+    /// See Lengyel, "Oblique View Frustum Depth Projection and Clipping".
+    pub fn with_oblique_near_plane(&self, plane: &ClipPlane, camera_transform: &Transform) -> Self {
+        // Planes are transformed by the inverse transpose of the point transform, and the
+        // inverse of the view matrix is the global matrix of the camera.
+        let plane = camera_transform.global_matrix().transpose() * plane.to_vector();
+        let clip_plane = self.inverse.transpose() * plane;
+
+        // Frustum corner opposite to the plane on the far plane, at depth 0.
+        let corner =
+            self.inverse * Vector4::new(clip_plane.x.signum(), clip_plane.y.signum(), 0.0, 1.0);
+
+        // Depth is 1 where `w - z` vanishes, so setting the z row to `w - scale * plane` puts
+        // the near plane on the plane, and the scale puts the far plane through the corner.
+        let w = self.matrix.row(3).transpose();
+        let scale = w.dot(&corner) / plane.dot(&corner);
+
+        let mut matrix = self.matrix;
+        matrix.set_row(2, &(w - plane * scale).transpose());
+        Self::from_matrix(matrix)
+    }
+
     /// Returns a `Ray` going out form the camera through provided screen position. The ray origin lies on camera near plane.
     ///
     /// The screen coordinate (0, 0) is the top-left corner of the top-left pixel.
@@ -243,6 +319,46 @@ pub struct ActiveCamera {
     pub entity: Option<Entity>,
 }
 
+/// A world space plane clipping everything a camera draws on its negative side, e.g. below the
+/// water surface for a camera rendering planar reflections, or in front of the portal for a
+/// camera looking through one.
+///
+/// The standard 3D passes discard the fragments of meshes on the negative side of the plane
+/// of the camera they are drawn with.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct ClipPlane {
+    /// Unit normal of the plane, pointing at the side which is drawn
+    pub normal: Vector3<f32>,
+    /// Distance of the plane from the origin along its normal
+    pub distance: f32,
+}
+
+impl ClipPlane {
+    /// Create a plane with the given normal, normalized, through `point`.
+    pub fn new(normal: Vector3<f32>, point: Point3<f32>) -> Self {
+        let normal = normal.normalize();
+        ClipPlane {
+            normal,
+            distance: normal.dot(&point.coords),
+        }
+    }
+
+    /// Returns the signed distance of `point` from the plane, negative for clipped points.
+    pub fn signed_distance(&self, point: &Point3<f32>) -> f32 {
+        self.normal.dot(&point.coords) - self.distance
+    }
+
+    /// Returns the plane as the `vec4` of its normal and negated distance, which has a negative
+    /// dot product with the homogeneous coordinates of clipped points.
+    pub fn to_vector(&self) -> Vector4<f32> {
+        self.normal.push(-self.distance)
+    }
+}
+
+impl Component for ClipPlane {
+    type Storage = HashMapStorage<Self>;
+}
+
 /// Projection prefab
 #[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
 pub enum CameraPrefab {
@@ -270,6 +386,21 @@ pub enum CameraPrefab {
         /// Near clip plane distance
         znear: f32,
     },
+    /// Off-axis perspective prefab
+    OffAxis {
+        /// The x-coordinate of the left edge of the near plane.
+        left: f32,
+        /// The x-coordinate of the right edge of the near plane.
+        right: f32,
+        /// The y-coordinate of the bottom edge of the near plane.
+        bottom: f32,
+        /// The y-coordinate of the top edge of the near plane.
+        top: f32,
+        /// Near clip plane distance
+        znear: f32,
+    },
+    /// Prefab of a custom projection matrix, which must be invertible.
+    Matrix(Matrix4<f32>),
 }
 
 impl<'a> PrefabData<'a> for CameraPrefab {
@@ -299,6 +430,14 @@ impl<'a> PrefabData<'a> for CameraPrefab {
                     fovy,
                     znear,
                 } => Camera::perspective(aspect, fovy, znear),
+                CameraPrefab::OffAxis {
+                    left,
+                    right,
+                    bottom,
+                    top,
+                    znear,
+                } => Camera::off_axis(left, right, bottom, top, znear),
+                CameraPrefab::Matrix(matrix) => Camera::from_matrix(matrix),
             },
         )?;
         Ok(())
@@ -671,4 +810,68 @@ mod tests {
         let projected_point = mvp.transform_point(&far);
         assert_abs_diff_eq!(projected_point[2], 0.0);
     }
+
+    #[test]
+    fn clip_plane_keeps_normal_side() {
+        let plane = ClipPlane::new(Vector3::new(0.0, 2.0, 0.0), Point3::new(5.0, 1.0, 0.0));
+        assert_ulps_eq!(plane.normal, Vector3::y());
+        assert_ulps_eq!(plane.distance, 1.0);
+        assert_ulps_eq!(plane.signed_distance(&Point3::new(0.0, 3.0, 7.0)), 2.0);
+        assert_ulps_eq!(plane.signed_distance(&Point3::new(0.0, -1.0, 0.0)), -2.0);
+
+        let point = Point3::new(3.0, -1.0, 2.0);
+        assert_ulps_eq!(
+            plane.to_vector().dot(&point.to_homogeneous()),
+            plane.signed_distance(&point)
+        );
+    }
+
+    #[test]
+    fn off_axis_matches_symmetric_perspective() {
+        let fov = std::f32::consts::FRAC_PI_3;
+        let aspect = 1280.0 / 720.0;
+        let top = 0.5 * (fov / 2.0).tan();
+        let right = top * aspect;
+
+        let perspective = Camera::perspective(aspect, fov, 0.5);
+        let off_axis = Camera::off_axis(-right, right, -top, top, 0.5);
+        assert_ulps_eq!(off_axis.matrix, perspective.matrix);
+
+        // The edges of the near plane map to the edges of the screen.
+        let shifted = Camera::off_axis(1.0, 3.0, 2.0, 3.0, 0.5);
+        let corner = shifted.matrix.transform_point(&Point3::new(3.0, 3.0, -0.5));
+        assert_ulps_eq!(corner, Point3::new(1.0, -1.0, 1.0));
+        let corner = shifted.matrix.transform_point(&Point3::new(2.0, 4.0, -1.0));
+        assert_ulps_eq!(corner, Point3::new(-1.0, 1.0, 0.5));
+    }
+
+    #[test]
+    fn oblique_near_plane() {
+        let camera = Camera::perspective(1280.0 / 720.0, std::f32::consts::FRAC_PI_3, 0.1);
+        let mut transform = Transform::default();
+        transform.set_translation_xyz(0.0, 2.0, 0.0);
+        transform.copy_local_to_global();
+
+        // Mirror tilted towards the camera, 5 units away.
+        let plane = ClipPlane::new(Vector3::new(0.0, -0.6, -0.8), Point3::new(0.0, 2.0, -5.0));
+        let oblique = camera.with_oblique_near_plane(&plane, &transform);
+        let projection = oblique.matrix * transform.view_matrix();
+
+        for point in &[
+            Point3::new(0.0, 2.0, -5.0),
+            Point3::new(1.0, 2.8, -5.6),
+            Point3::new(-2.0, 0.4, -3.8),
+        ] {
+            assert_ulps_eq!(plane.signed_distance(point), 0.0, epsilon = 1e-5);
+            assert_abs_diff_eq!(projection.transform_point(point).z, 1.0, epsilon = 1e-4);
+        }
+
+        let behind = projection.transform_point(&Point3::new(0.0, 2.0, -8.0)).z;
+        assert_gt!(behind, 0.0);
+        assert_lt!(behind, 1.0);
+        assert_gt!(
+            projection.transform_point(&Point3::new(0.0, 2.0, -4.0)).z,
+            1.0
+        );
+    }
 }
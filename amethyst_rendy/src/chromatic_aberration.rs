@@ -0,0 +1,34 @@
+//! Chromatic aberration configuration.
+
+use amethyst_core::ecs::{Read, SystemData, World};
+
+/// Resource configuring the chromatic aberration applied by `RenderChromaticAberration`.
+///
+/// All fields are read every frame. While disabled, the pass copies its input unchanged.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChromaticAberrationParams {
+    /// Offset the red and blue channels of the image.
+    pub enabled: bool,
+    /// Offset of the red and blue channels at the corners of the image, as a fraction
+    /// of the image size. Red is shifted outwards and blue inwards, fading to no offset
+    /// at the center.
+    pub intensity: f32,
+}
+
+impl Default for ChromaticAberrationParams {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            intensity: 0.005,
+        }
+    }
+}
+
+impl ChromaticAberrationParams {
+    /// Returns the `ChromaticAberrationParams` resource, or default parameters if there is none.
+    pub fn fetch(world: &World) -> Self {
+        <Option<Read<'_, ChromaticAberrationParams>>>::fetch(world)
+            .map(|p| *p)
+            .unwrap_or_default()
+    }
+}
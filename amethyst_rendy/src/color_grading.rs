@@ -0,0 +1,239 @@
+//! Color grading with 3D lookup tables.
+
+use crate::types::{Texture, TextureData};
+use amethyst_assets::Handle;
+use amethyst_core::ecs::{Read, SystemData, World};
+use amethyst_error::{format_err, Error};
+use rendy::{
+    hal::image::{Filter, Kind, SamplerInfo, ViewKind, WrapMode},
+    texture::{pixel::Rgba32Sfloat, TextureBuilder},
+};
+
+/// Resource configuring the color grading applied by `RenderColorGrading`.
+///
+/// All fields are read every frame, so lookup tables can be swapped or blended in
+/// without rebuilding the render graph. The image is left unchanged while there is no
+/// lookup table or it is still loading.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorGrading {
+    /// Lookup table texture, loaded with `CubeLutFormat` or `LutStripFormat`.
+    pub lut: Option<Handle<Texture>>,
+    /// Blend between the original image at 0 and the fully graded image at 1.
+    pub intensity: f32,
+}
+
+impl Default for ColorGrading {
+    fn default() -> Self {
+        Self {
+            lut: None,
+            intensity: 1.0,
+        }
+    }
+}
+
+impl ColorGrading {
+    /// Returns the `ColorGrading` resource, or no grading if there is none.
+    pub fn fetch(world: &World) -> Self {
+        <Option<Read<'_, ColorGrading>>>::fetch(world)
+            .map(|g| g.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// 3D color lookup table, mapping display colors to graded colors.
+///
+/// Lookup tables are uploaded as a horizontal strip of `size` slices of `size`x`size` texels,
+/// one slice per blue level, with red increasing along the width and green along the height
+/// of each slice.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorLut {
+    size: u32,
+    /// Graded colors with red changing fastest, then green, then blue.
+    texels: Vec<[f32; 3]>,
+}
+
+impl ColorLut {
+    /// Create a lookup table of `size` levels per channel from graded colors, red changing
+    /// fastest, then green, then blue.
+    pub fn new(size: u32, texels: Vec<[f32; 3]>) -> Result<Self, Error> {
+        if size < 2 {
+            return Err(format_err!(
+                "Lookup table needs at least 2 levels, got {}",
+                size
+            ));
+        }
+        let expected = (size * size * size) as usize;
+        if texels.len() != expected {
+            return Err(format_err!(
+                "Lookup table of size {} needs {} colors, got {}",
+                size,
+                expected,
+                texels.len()
+            ));
+        }
+        Ok(Self { size, texels })
+    }
+
+    /// Lookup table of `size` levels per channel leaving colors unchanged.
+    pub fn identity(size: u32) -> Self {
+        let size = size.max(2);
+        let max = (size - 1) as f32;
+        let texels = (0..size * size * size)
+            .map(|i| {
+                [
+                    (i % size) as f32 / max,
+                    (i / size % size) as f32 / max,
+                    (i / (size * size)) as f32 / max,
+                ]
+            })
+            .collect();
+        Self { size, texels }
+    }
+
+    /// Parse a lookup table in the `.cube` format.
+    ///
+    /// Only 3D tables with the default input domain of 0 to 1 are supported.
+    pub fn from_cube(text: &str) -> Result<Self, Error> {
+        let mut size = None;
+        let mut texels = Vec::new();
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut words = line.split_whitespace();
+            let keyword = words.next().expect("Line is not empty");
+            match keyword {
+                "TITLE" => {}
+                "LUT_3D_SIZE" => {
+                    size = Some(
+                        words
+                            .next()
+                            .and_then(|s| s.parse::<u32>().ok())
+                            .ok_or_else(|| format_err!("Invalid LUT_3D_SIZE: {}", line))?,
+                    );
+                }
+                "DOMAIN_MIN" | "DOMAIN_MAX" => {
+                    let expected = if keyword == "DOMAIN_MIN" { 0.0 } else { 1.0 };
+                    let values = parse_floats(line, words)?;
+                    if values
+                        .iter()
+                        .any(|v| (v - expected).abs() > std::f32::EPSILON)
+                    {
+                        return Err(format_err!("Unsupported lookup table domain: {}", line));
+                    }
+                }
+                "LUT_1D_SIZE" => {
+                    return Err(format_err!("1D lookup tables are not supported"));
+                }
+                _ => texels.push(parse_floats(line, line.split_whitespace())?),
+            }
+        }
+
+        let size = size.ok_or_else(|| format_err!("Missing LUT_3D_SIZE"))?;
+        Self::new(size, texels)
+    }
+
+    /// Decode a lookup table from an image of a horizontal strip, e.g. 256x16 or 1024x32
+    /// texels. Colors are read as they are stored, without conversion from sRGB.
+    pub fn from_strip(bytes: &[u8]) -> Result<Self, Error> {
+        let image = image::load_from_memory(bytes)
+            .map_err(|e| format_err!("{}", e))?
+            .to_rgb();
+        let (width, size) = image.dimensions();
+        if width != size * size {
+            return Err(format_err!(
+                "Lookup table strip of height {} needs a width of {}, got {}",
+                size,
+                size * size,
+                width
+            ));
+        }
+
+        let texels = (0..size * size * size)
+            .map(|i| {
+                let (red, green, blue) = (i % size, i / size % size, i / (size * size));
+                let pixel = image.get_pixel(blue * size + red, green);
+                [
+                    pixel[0] as f32 / 255.0,
+                    pixel[1] as f32 / 255.0,
+                    pixel[2] as f32 / 255.0,
+                ]
+            })
+            .collect();
+        Self::new(size, texels)
+    }
+
+    /// Number of levels per color channel.
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// Graded colors with red changing fastest, then green, then blue.
+    pub fn texels(&self) -> &[[f32; 3]] {
+        &self.texels
+    }
+
+    /// Texture data of the lookup table strip.
+    pub fn texture_data(&self) -> TextureData {
+        let size = self.size;
+        // Strip rows hold the slices side by side, so blue changes before green.
+        let data = (0..size)
+            .flat_map(|green| (0..size * size).map(move |x| (green, x)))
+            .map(|(green, x)| {
+                let (red, blue) = (x % size, x / size);
+                let [r, g, b] = self.texels[(red + green * size + blue * size * size) as usize];
+                Rgba32Sfloat {
+                    repr: [r, g, b, 1.0],
+                }
+            })
+            .collect::<Vec<_>>();
+
+        TextureBuilder::new()
+            .with_kind(Kind::D2(size * size, size, 1, 1))
+            .with_view_kind(ViewKind::D2)
+            .with_data_width(size * size)
+            .with_data_height(size)
+            .with_sampler_info(SamplerInfo::new(Filter::Linear, WrapMode::Clamp))
+            .with_data(data)
+            .into()
+    }
+}
+
+fn parse_floats<'a>(line: &str, words: impl Iterator<Item = &'a str>) -> Result<[f32; 3], Error> {
+    let values = words
+        .map(|word| word.parse::<f32>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| format_err!("Invalid lookup table line: {}", line))?;
+    match values.as_slice() {
+        &[r, g, b] => Ok([r, g, b]),
+        _ => Err(format_err!("Invalid lookup table line: {}", line)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_cube() {
+        let text = "\
+            TITLE \"Identity\"\n\
+            # comment\n\
+            LUT_3D_SIZE 2\n\
+            DOMAIN_MIN 0.0 0.0 0.0\n\
+            DOMAIN_MAX 1.0 1.0 1.0\n\
+            \n\
+            0 0 0\n1 0 0\n0 1 0\n1 1 0\n\
+            0 0 1\n1 0 1\n0 1 1\n1 1 1\n";
+        assert_eq!(ColorLut::from_cube(text).unwrap(), ColorLut::identity(2));
+    }
+
+    #[test]
+    fn reject_invalid_cube() {
+        assert!(ColorLut::from_cube("LUT_3D_SIZE 2\n0 0 0\n").is_err());
+        assert!(ColorLut::from_cube("0 0 0\n").is_err());
+        assert!(ColorLut::from_cube("LUT_1D_SIZE 2\n0 0 0\n1 1 1\n").is_err());
+        assert!(ColorLut::from_cube("LUT_3D_SIZE 2\nDOMAIN_MAX 2 2 2\n").is_err());
+    }
+}
@@ -0,0 +1,154 @@
+//! Vertex attributes defined by user crates, kept on loaded meshes for custom pipelines.
+//!
+//! Register an attribute type with [register_vertex_attribute] and mesh loaders read the
+//! attributes of mesh files with its semantic into a vertex buffer of their own, which a
+//! [CustomMaterial] can list in its `vertex_format`.
+//!
+//! [CustomMaterial]: crate::pass::CustomMaterial
+use amethyst_assets::inventory;
+use rendy::{
+    hal::format::Format,
+    mesh::{AsAttribute, MeshBuilder},
+};
+
+/// A vertex attribute type registered with [register_vertex_attribute].
+#[derive(Debug)]
+pub struct CustomAttribute {
+    semantic: &'static str,
+    format: Format,
+    size: usize,
+    add_vertices: fn(&mut MeshBuilder<'static>, &[u8]),
+}
+
+inventory::collect!(CustomAttribute);
+
+impl CustomAttribute {
+    // This code is called by `register_vertex_attribute` macro. Considered a private api otherwise.
+    #[doc(hidden)]
+    pub fn new<A: AsAttribute>(semantic: &'static str) -> Self {
+        Self {
+            semantic,
+            format: A::FORMAT,
+            size: std::mem::size_of::<A>(),
+            add_vertices: add_vertices::<A>,
+        }
+    }
+
+    /// Returns the attribute registered for `semantic`, ignoring the leading underscores
+    /// marking application specific semantics in glTF files.
+    pub fn find(semantic: &str) -> Option<&'static Self> {
+        let semantic = semantic.trim_start_matches('_');
+        Self::registered().find(|attribute| attribute.semantic() == semantic)
+    }
+
+    /// Returns all registered attributes.
+    pub fn registered() -> impl Iterator<Item = &'static Self> {
+        inventory::iter::<Self>.into_iter()
+    }
+
+    /// Name of the attribute in mesh files, without leading underscores
+    pub fn semantic(&self) -> &'static str {
+        self.semantic.trim_start_matches('_')
+    }
+
+    /// Format of the attribute on meshes
+    pub fn format(&self) -> Format {
+        self.format
+    }
+
+    /// Size of the attribute of a vertex in bytes
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Add the attributes of tightly packed vertices in `bytes` to `builder` as a vertex buffer
+    /// of their own. Trailing bytes of an incomplete vertex are ignored.
+    pub fn add_vertices(&self, builder: &mut MeshBuilder<'static>, bytes: &[u8]) {
+        (self.add_vertices)(builder, bytes)
+    }
+}
+
+fn read_vertices<A: AsAttribute>(bytes: &[u8]) -> Vec<A> {
+    bytes
+        .chunks_exact(std::mem::size_of::<A>())
+        .map(|chunk| unsafe { std::ptr::read_unaligned(chunk.as_ptr() as *const A) })
+        .collect()
+}
+
+fn add_vertices<A: AsAttribute>(builder: &mut MeshBuilder<'static>, bytes: &[u8]) {
+    builder.add_vertices(read_vertices::<A>(bytes));
+}
+
+/// Register a vertex attribute type read by mesh loaders from attributes with the given
+/// semantic, like `_WIND_WEIGHT` attributes of glTF primitives for `"WIND_WEIGHT"`.
+///
+/// ```ignore
+/// #[repr(C)]
+/// #[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+/// pub struct WindWeight(pub f32);
+///
+/// impl AsAttribute for WindWeight {
+///     const NAME: &'static str = "wind_weight";
+///     const FORMAT: Format = Format::R32Sfloat;
+/// }
+///
+/// amethyst_rendy::register_vertex_attribute!("WIND_WEIGHT", WindWeight);
+/// ```
+///
+/// The attribute type is read from the bytes of mesh files, so any bytes of its size must be
+/// a valid value, like for the arrays of numbers of the rendy attributes.
+///
+/// The `amethyst_assets` crate must be in scope in order to use that macro.
+/// You can also specify name for the crate as additional first parameter.
+#[macro_export]
+macro_rules! register_vertex_attribute {
+    ($semantic:literal, $attribute:ty) => {
+        $crate::register_vertex_attribute!(amethyst_assets; $semantic, $attribute);
+    };
+    ($krate:ident; $semantic:literal, $attribute:ty) => {
+        $krate::inventory::submit! {
+            #![crate = $krate]
+            $crate::custom_attribute::CustomAttribute::new::<$attribute>($semantic)
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+    struct TestWeight(f32);
+
+    impl AsAttribute for TestWeight {
+        const NAME: &'static str = "test_weight";
+        const FORMAT: Format = Format::R32Sfloat;
+    }
+
+    register_vertex_attribute!("TEST_WEIGHT", TestWeight);
+
+    #[test]
+    fn finds_registered_attributes() {
+        let attribute = CustomAttribute::find("_TEST_WEIGHT").unwrap();
+        assert_eq!(attribute.semantic(), "TEST_WEIGHT");
+        assert_eq!(attribute.format(), Format::R32Sfloat);
+        assert_eq!(attribute.size(), 4);
+        assert!(CustomAttribute::find("_TEST_WEIGHTS").is_none());
+    }
+
+    #[test]
+    fn reads_packed_vertices() {
+        // Vertices of mesh files aren't necessarily aligned.
+        let bytes = Some(0)
+            .into_iter()
+            .chain(1.5f32.to_ne_bytes().iter().copied())
+            .chain((-2.0f32).to_ne_bytes().iter().copied())
+            .chain(Some(0))
+            .collect::<Vec<u8>>();
+        assert_eq!(
+            read_vertices::<TestWeight>(&bytes[1..]),
+            vec![TestWeight(1.5), TestWeight(-2.0)]
+        );
+    }
+}
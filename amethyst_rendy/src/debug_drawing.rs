@@ -1,36 +1,95 @@
 //! Debug Drawing library
 use crate::pod::IntoPod;
 use amethyst_core::{
-    ecs::{Component, DenseVecStorage},
+    ecs::{Component, DenseVecStorage, Read, System, Write},
     math::{Point2, Point3, UnitQuaternion, Vector2, Vector3},
+    timing::Time,
 };
 use palette::Srgba;
-use rendy::mesh::{AsVertex, Color, PosColor, VertexFormat};
+use rendy::{
+    hal::format::Format,
+    mesh::{AsVertex, Color, PosColor, VertexFormat},
+};
+use std::time::Duration;
 
-/// Debug lines are stored as a pair of position and color.
+/// Debug lines are stored as a pair of position and color, plus an optional width and dash pattern.
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
 #[repr(C)]
 pub struct DebugLine {
     start: PosColor,
     end: PosColor,
+    /// Width of the line in screen space pixels. Zero means `DebugLinesParams::line_width` is used.
+    width: f32,
+    /// Lengths of the visible and invisible parts of the dash pattern in screen space pixels.
+    /// Zero dash length means a solid line.
+    pattern: [f32; 2],
 }
 
 impl AsVertex for DebugLine {
     fn vertex() -> VertexFormat {
-        VertexFormat::new((PosColor::vertex(), PosColor::vertex()))
+        VertexFormat::new((
+            PosColor::vertex(),
+            PosColor::vertex(),
+            (Format::R32Sfloat, "line_width"),
+            (Format::Rg32Sfloat, "dash_pattern"),
+        ))
     }
 }
 
 impl DebugLine {
-    fn new(start: PosColor, end: PosColor) -> Self {
-        Self { start, end }
+    fn new(start: PosColor, end: PosColor, width: f32, style: DebugLineStyle) -> Self {
+        Self {
+            start,
+            end,
+            width,
+            pattern: style.pattern(),
+        }
+    }
+}
+
+/// Style in which a debug line is drawn.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum DebugLineStyle {
+    /// Continuous line.
+    Solid,
+    /// Line made of dashes separated by gaps of the same length.
+    Dashed {
+        /// Length of a single dash in screen space pixels.
+        dash_length: f32,
+    },
+    /// Line made of single pixel dots.
+    Dotted {
+        /// Distance between the dots in screen space pixels.
+        spacing: f32,
+    },
+}
+
+impl Default for DebugLineStyle {
+    fn default() -> Self {
+        DebugLineStyle::Solid
+    }
+}
+
+impl DebugLineStyle {
+    fn pattern(self) -> [f32; 2] {
+        match self {
+            DebugLineStyle::Solid => [0.0, 0.0],
+            DebugLineStyle::Dashed { dash_length } => {
+                let dash_length = dash_length.max(0.0);
+                [dash_length, dash_length]
+            }
+            DebugLineStyle::Dotted { spacing } => [1.0, spacing.max(0.0)],
+        }
     }
 }
 
 /// Parameters for renderer of debug lines. The params affect all lines.
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
 pub struct DebugLinesParams {
-    /// Width of lines in screen space pixels, default is 1.0 pixel
+    /// Width of lines in screen space pixels, default is 1.0 pixel.
+    /// Lines submitted with an explicit width ignore this value.
+    ///
+    /// The edges of lines are anti-aliased, fading out over an extra pixel on either side.
     pub line_width: f32,
 }
 
@@ -46,6 +105,8 @@ impl Default for DebugLinesParams {
 pub struct DebugLinesComponent {
     /// Lines to be rendered
     lines: Vec<DebugLine>,
+    /// Whether lines are drawn without depth testing
+    always_on_top: bool,
 }
 
 impl Component for DebugLinesComponent {
@@ -62,9 +123,26 @@ impl DebugLinesComponent {
     pub fn with_capacity(capacity: usize) -> Self {
         Self {
             lines: Vec::with_capacity(capacity),
+            always_on_top: false,
         }
     }
 
+    /// Builder method to draw the lines of this component on top of all geometry.
+    pub fn with_always_on_top(mut self) -> Self {
+        self.always_on_top = true;
+        self
+    }
+
+    /// Sets whether the lines are drawn without depth testing, staying visible through other geometry.
+    pub fn set_always_on_top(&mut self, always_on_top: bool) {
+        self.always_on_top = always_on_top;
+    }
+
+    /// Returns whether the lines are drawn without depth testing.
+    pub fn always_on_top(&self) -> bool {
+        self.always_on_top
+    }
+
     /// Adds a line to be rendered by giving a position and a direction.
     pub fn add_direction(&mut self, position: Point3<f32>, direction: Vector3<f32>, color: Srgba) {
         self.add_line(position, position + direction, color);
@@ -82,6 +160,71 @@ impl DebugLinesComponent {
         end: Point3<f32>,
         start_color: Srgba,
         end_color: Srgba,
+    ) {
+        self.push_line(
+            start,
+            end,
+            start_color,
+            end_color,
+            0.0,
+            DebugLineStyle::Solid,
+        );
+    }
+
+    /// Adds a line to be rendered by giving a start and an end position and a width in screen space pixels.
+    ///
+    /// The width overrides `DebugLinesParams::line_width` for this line only.
+    pub fn add_line_with_width(
+        &mut self,
+        start: Point3<f32>,
+        end: Point3<f32>,
+        color: Srgba,
+        width: f32,
+    ) {
+        self.add_gradient_line_with_width(start, end, color, color, width);
+    }
+
+    /// Adds a line to be rendered by giving a start and an end position with separate start and end colors
+    /// and a width in screen space pixels.
+    ///
+    /// The width overrides `DebugLinesParams::line_width` for this line only.
+    pub fn add_gradient_line_with_width(
+        &mut self,
+        start: Point3<f32>,
+        end: Point3<f32>,
+        start_color: Srgba,
+        end_color: Srgba,
+        width: f32,
+    ) {
+        self.push_line(
+            start,
+            end,
+            start_color,
+            end_color,
+            width.max(0.0),
+            DebugLineStyle::Solid,
+        );
+    }
+
+    /// Adds a line to be rendered by giving a start and an end position and a line style.
+    pub fn add_styled_line(
+        &mut self,
+        start: Point3<f32>,
+        end: Point3<f32>,
+        color: Srgba,
+        style: DebugLineStyle,
+    ) {
+        self.push_line(start, end, color, color, 0.0, style);
+    }
+
+    fn push_line(
+        &mut self,
+        start: Point3<f32>,
+        end: Point3<f32>,
+        start_color: Srgba,
+        end_color: Srgba,
+        width: f32,
+        style: DebugLineStyle,
     ) {
         let vertex = DebugLine::new(
             PosColor {
@@ -92,6 +235,8 @@ impl DebugLinesComponent {
                 position: end.to_homogeneous().xyz().into(),
                 color: Color(end_color.into_pod()),
             },
+            width,
+            style,
         );
         self.lines.push(vertex);
     }
@@ -364,6 +509,96 @@ impl DebugLinesComponent {
         }
     }
 
+    /// Adds multiple lines that form a circle to be rendered by giving a center, a normal, a radius and an amount of points.
+    ///
+    /// The circle lies in the plane perpendicular to `normal`.
+    pub fn add_circle(
+        &mut self,
+        center: Point3<f32>,
+        normal: Vector3<f32>,
+        radius: f32,
+        points: u32,
+        color: Srgba,
+    ) {
+        let (u, v) = orthonormal_basis(&normal);
+        self.add_arc(
+            center,
+            u,
+            v,
+            radius,
+            0.0,
+            std::f32::consts::PI * 2.0,
+            points,
+            color,
+        );
+    }
+
+    /// Adds multiple lines that form a capsule to be rendered by giving the centers of both caps, a radius and an amount of points.
+    ///
+    /// The capsule is drawn as two rings around the ends of its segment, four lines connecting them
+    /// and two half-circle arcs per cap. `points` controls the amount of segments of the full rings.
+    pub fn add_capsule(
+        &mut self,
+        start: Point3<f32>,
+        end: Point3<f32>,
+        radius: f32,
+        points: u32,
+        color: Srgba,
+    ) {
+        let axis = end - start;
+        let axis_dir = if axis.norm_squared() > std::f32::EPSILON {
+            axis.normalize()
+        } else {
+            Vector3::y()
+        };
+        let (u, v) = orthonormal_basis(&axis_dir);
+        let half_points = (points / 2).max(1);
+        let pi = std::f32::consts::PI;
+
+        self.add_arc(start, u, v, radius, 0.0, pi * 2.0, points, color);
+        self.add_arc(end, u, v, radius, 0.0, pi * 2.0, points, color);
+
+        for side in &[u, v, -u, -v] {
+            let offset = side * radius;
+            self.add_line(start + offset, end + offset, color);
+        }
+
+        // Caps are half circles bulging away from the capsule segment.
+        self.add_arc(end, u, axis_dir, radius, 0.0, pi, half_points, color);
+        self.add_arc(end, v, axis_dir, radius, 0.0, pi, half_points, color);
+        self.add_arc(start, u, -axis_dir, radius, 0.0, pi, half_points, color);
+        self.add_arc(start, v, -axis_dir, radius, 0.0, pi, half_points, color);
+    }
+
+    /// Adds lines approximating an arc spanned by the `u` and `v` axes, going from `u` towards `v`.
+    #[allow(clippy::too_many_arguments)]
+    fn add_arc(
+        &mut self,
+        center: Point3<f32>,
+        u: Vector3<f32>,
+        v: Vector3<f32>,
+        radius: f32,
+        start_angle: f32,
+        end_angle: f32,
+        points: u32,
+        color: Srgba,
+    ) {
+        let points = points.max(1);
+        let step = (end_angle - start_angle) / (points as f32);
+        let mut prev = None;
+
+        for i in 0..=points {
+            let a = start_angle + step * (i as f32);
+            let point = center + (u * a.cos() + v * a.sin()) * radius;
+
+            if let Some(prev) = prev {
+                self.add_line(prev, point, color);
+            }
+
+            prev = Some(point);
+        }
+    }
+
     /// Clears lines buffer.
     ///
     /// As lines are persistent, it's necessary to use this function for updating or deleting lines.
@@ -378,18 +613,34 @@ impl DebugLinesComponent {
 
 /// Resource that stores non-persistent debug lines to be rendered in DebugLinesPass draw pass.
 /// The vector is automatically cleared after being rendered.
+/// Lines submitted with a duration are kept until that much time has passed since they were
+/// first drawn, which the [DebugLinesExpirySystem] of the `RenderingBundle` keeps track of.
 #[derive(Debug, Default)]
 pub struct DebugLines {
     /// Lines to be rendered
     inner: DebugLinesComponent,
+    /// Lines to be rendered until they expire
+    timed: DebugLinesComponent,
+    /// Remaining lifetime of each timed line in seconds
+    remaining: Vec<f32>,
+    /// Number of timed lines, at the start of `timed`, which have been drawn
+    drawn: usize,
 }
 
 impl DebugLines {
     /// Creates a new debug lines component with an empty DebugLine vector.
     pub fn new() -> DebugLines {
-        Self {
-            inner: Default::default(),
-        }
+        Self::default()
+    }
+
+    /// Sets whether the submitted lines are drawn without depth testing, staying visible through other geometry.
+    pub fn set_always_on_top(&mut self, always_on_top: bool) {
+        self.inner.set_always_on_top(always_on_top);
+    }
+
+    /// Returns whether the submitted lines are drawn without depth testing.
+    pub fn always_on_top(&self) -> bool {
+        self.inner.always_on_top()
     }
 
     /// Submits a line to be rendered by giving a position and a direction.
@@ -414,6 +665,56 @@ impl DebugLines {
         self.inner.add_line(start, end, color);
     }
 
+    /// Submits a line to be rendered for the given duration by giving a start and an end position.
+    ///
+    /// The line is drawn at least once, even when the duration is zero.
+    pub fn draw_line_for(
+        &mut self,
+        start: Point3<f32>,
+        end: Point3<f32>,
+        color: Srgba,
+        duration: Duration,
+    ) {
+        self.timed.add_line(start, end, color);
+        self.remaining.push(duration.as_secs_f32());
+    }
+
+    /// Submits a line to be rendered by giving a start and an end position and a width in screen space pixels.
+    pub fn draw_line_with_width(
+        &mut self,
+        start: Point3<f32>,
+        end: Point3<f32>,
+        color: Srgba,
+        width: f32,
+    ) {
+        self.inner.add_line_with_width(start, end, color, width);
+    }
+
+    /// Submits a line to be rendered by giving a start and an end position with separate start and end colors
+    /// and a width in screen space pixels.
+    pub fn draw_gradient_line_with_width(
+        &mut self,
+        start: Point3<f32>,
+        end: Point3<f32>,
+        start_color: Srgba,
+        end_color: Srgba,
+        width: f32,
+    ) {
+        self.inner
+            .add_gradient_line_with_width(start, end, start_color, end_color, width);
+    }
+
+    /// Submits a line to be rendered by giving a start and an end position and a line style.
+    pub fn draw_styled_line(
+        &mut self,
+        start: Point3<f32>,
+        end: Point3<f32>,
+        color: Srgba,
+        style: DebugLineStyle,
+    ) {
+        self.inner.add_styled_line(start, end, color, style);
+    }
+
     /// Submits multiple lines that form a rectangle to be rendered by giving a Z coordinate, a min and a max position.
     ///
     /// This rectangle is aligned to the XY plane.
@@ -516,7 +817,403 @@ impl DebugLines {
             .add_rotated_cylinder(center, radius, height, points, rotation, color);
     }
 
+    /// Submits multiple lines that form a circle to be rendered by giving a center, a normal, a radius and an amount of points.
+    ///
+    /// The circle lies in the plane perpendicular to `normal`.
+    pub fn draw_circle_with_normal(
+        &mut self,
+        center: Point3<f32>,
+        normal: Vector3<f32>,
+        radius: f32,
+        points: u32,
+        color: Srgba,
+    ) {
+        self.inner.add_circle(center, normal, radius, points, color);
+    }
+
+    /// Submits multiple lines that form a capsule to be rendered by giving the centers of both caps, a radius and an amount of points.
+    pub fn draw_capsule(
+        &mut self,
+        start: Point3<f32>,
+        end: Point3<f32>,
+        radius: f32,
+        points: u32,
+        color: Srgba,
+    ) {
+        self.inner.add_capsule(start, end, radius, points, color);
+    }
+
     pub(crate) fn drain<'a>(&'a mut self) -> impl Iterator<Item = DebugLine> + 'a {
         self.inner.lines.drain(..)
     }
+
+    /// Returns the timed lines to draw, starting the lifetime of the ones not drawn yet.
+    pub(crate) fn draw_timed_lines(&mut self) -> &[DebugLine] {
+        self.drawn = self.timed.lines.len();
+        self.timed.lines()
+    }
+
+    /// Advances the lifetime of the timed lines drawn so far and removes the ones that expired.
+    pub fn expire(&mut self, delta_seconds: f32) {
+        let mut kept = 0;
+        for i in 0..self.remaining.len() {
+            if i < self.drawn {
+                self.remaining[i] -= delta_seconds;
+                if self.remaining[i] <= 0.0 {
+                    continue;
+                }
+            }
+            self.remaining.swap(kept, i);
+            self.timed.lines.swap(kept, i);
+            kept += 1;
+        }
+        self.drawn -= self.remaining.len() - kept;
+        self.remaining.truncate(kept);
+        self.timed.lines.truncate(kept);
+    }
+}
+
+/// Expires the lines of the [DebugLines] resource submitted with a duration, once per frame.
+///
+/// It's automatically registered with the `RenderingBundle`.
+#[derive(Debug, Default)]
+pub struct DebugLinesExpirySystem;
+
+impl<'a> System<'a> for DebugLinesExpirySystem {
+    type SystemData = (Read<'a, Time>, Option<Write<'a, DebugLines>>);
+
+    fn run(&mut self, (time, lines): Self::SystemData) {
+        if let Some(mut lines) = lines {
+            lines.expire(time.delta_seconds());
+        }
+    }
+}
+
+/// Resource that stores non-persistent debug lines given in screen space pixels.
+/// Lines are drawn on top of the scene without a camera, with the origin in the bottom left corner.
+/// The vector is automatically cleared after being rendered.
+#[derive(Debug, Default)]
+pub struct ScreenDebugLines {
+    /// Lines to be rendered
+    inner: DebugLinesComponent,
+}
+
+impl ScreenDebugLines {
+    /// Creates a new screen space debug lines resource with an empty DebugLine vector.
+    pub fn new() -> ScreenDebugLines {
+        Self::default()
+    }
+
+    /// Submits a line to be rendered by giving a start and an end position in pixels.
+    pub fn draw_line(&mut self, start: Point2<f32>, end: Point2<f32>, color: Srgba) {
+        self.inner.add_line(
+            Point3::new(start.x, start.y, 0.0),
+            Point3::new(end.x, end.y, 0.0),
+            color,
+        );
+    }
+
+    /// Submits a line to be rendered by giving a start and an end position in pixels
+    /// with separate start and end colors.
+    pub fn draw_gradient_line(
+        &mut self,
+        start: Point2<f32>,
+        end: Point2<f32>,
+        start_color: Srgba,
+        end_color: Srgba,
+    ) {
+        self.inner.add_gradient_line(
+            Point3::new(start.x, start.y, 0.0),
+            Point3::new(end.x, end.y, 0.0),
+            start_color,
+            end_color,
+        );
+    }
+
+    /// Submits a line to be rendered by giving a start and an end position and a width in pixels.
+    pub fn draw_line_with_width(
+        &mut self,
+        start: Point2<f32>,
+        end: Point2<f32>,
+        color: Srgba,
+        width: f32,
+    ) {
+        self.inner.add_line_with_width(
+            Point3::new(start.x, start.y, 0.0),
+            Point3::new(end.x, end.y, 0.0),
+            color,
+            width,
+        );
+    }
+
+    /// Submits multiple lines that form a rectangle to be rendered by giving a min and a max position in pixels.
+    pub fn draw_rectangle(&mut self, min: Point2<f32>, max: Point2<f32>, color: Srgba) {
+        self.inner.add_rectangle_2d(min, max, 0.0, color);
+    }
+
+    /// Submits multiple lines that form a circle to be rendered by giving a center and a radius in pixels.
+    pub fn draw_circle(&mut self, center: Point2<f32>, radius: f32, points: u32, color: Srgba) {
+        self.inner
+            .add_circle_2d(Point3::new(center.x, center.y, 0.0), radius, points, color);
+    }
+
+    /// Clears all debug lines.
+    pub fn clear(&mut self) {
+        self.inner.clear();
+    }
+
+    pub(crate) fn drain<'a>(&'a mut self) -> impl Iterator<Item = DebugLine> + 'a {
+        self.inner.lines.drain(..)
+    }
+}
+
+/// Component drawing the vertex normals of the mesh of its entity as lines, with
+/// `RenderVertexNormals`.
+///
+/// Normals are drawn blue, and when `tangents` is set and the mesh has tangents, tangents
+/// red and bitangents green, all starting at the vertex and `length` world units long.
+/// Bitangents follow the handedness stored in the `w` component of the tangents, so mirrored
+/// texture coordinates show up as bitangents pointing the wrong way.
+///
+/// Vertex data stays on the GPU, so the number of vertices is taken from the mesh. For a mesh
+/// with indices that is the number of indices instead, so `vertex_count` must be set for
+/// those.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VertexNormalsDebug {
+    /// Length of the lines, in world units.
+    pub length: f32,
+    /// Whether tangents and bitangents are drawn along with the normals.
+    pub tangents: bool,
+    /// Number of vertices of the mesh, or `None` for a mesh without indices.
+    pub vertex_count: Option<u32>,
+}
+
+impl Default for VertexNormalsDebug {
+    fn default() -> Self {
+        VertexNormalsDebug {
+            length: 0.1,
+            tangents: true,
+            vertex_count: None,
+        }
+    }
+}
+
+impl VertexNormalsDebug {
+    /// Draw normals, tangents and bitangents `length` world units long.
+    pub fn new(length: f32) -> Self {
+        VertexNormalsDebug {
+            length,
+            ..Default::default()
+        }
+    }
+
+    /// Draw the normals only.
+    pub fn without_tangents(mut self) -> Self {
+        self.tangents = false;
+        self
+    }
+
+    /// Set the number of vertices of a mesh with indices.
+    pub fn with_vertex_count(mut self, vertex_count: u32) -> Self {
+        self.vertex_count = Some(vertex_count);
+        self
+    }
+}
+
+impl Component for VertexNormalsDebug {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Returns two unit vectors perpendicular to `normal` and to each other.
+fn orthonormal_basis(normal: &Vector3<f32>) -> (Vector3<f32>, Vector3<f32>) {
+    let normal = if normal.norm_squared() > std::f32::EPSILON {
+        normal.normalize()
+    } else {
+        Vector3::z()
+    };
+    let reference = if normal.x.abs() < 0.9 {
+        Vector3::x()
+    } else {
+        Vector3::y()
+    };
+    let u = normal.cross(&reference).normalize();
+    let v = normal.cross(&u);
+    (u, v)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    fn draw_timed(lines: &mut DebugLines, x: f32, seconds: u64) {
+        lines.draw_line_for(
+            Point3::origin(),
+            Point3::new(x, 0.0, 0.0),
+            Srgba::default(),
+            Duration::from_secs(seconds),
+        );
+    }
+
+    fn timed_ends(lines: &mut DebugLines) -> Vec<f32> {
+        lines
+            .draw_timed_lines()
+            .iter()
+            .map(|line| line.end.position.0[0])
+            .collect()
+    }
+
+    #[test]
+    fn timed_lines_are_drawn_before_expiring() {
+        let mut lines = DebugLines::new();
+        draw_timed(&mut lines, 1.0, 0);
+        lines.expire(1.0);
+        draw_timed(&mut lines, 2.0, 0);
+
+        // Lines only age once drawn, however often they are drawn in a frame.
+        assert_eq!(timed_ends(&mut lines), vec![1.0, 2.0]);
+        assert_eq!(timed_ends(&mut lines), vec![1.0, 2.0]);
+        lines.expire(0.1);
+        assert!(timed_ends(&mut lines).is_empty());
+    }
+
+    #[test]
+    fn circle_lies_in_plane() {
+        let mut lines = DebugLinesComponent::new();
+        let normal = Vector3::new(1.0, 1.0, 0.0);
+        lines.add_circle(Point3::origin(), normal, 2.0, 16, Srgba::default());
+
+        assert_eq!(lines.lines().len(), 16);
+        for line in lines.lines() {
+            let start = Vector3::from(line.start.position.0);
+            assert_relative_eq!(start.dot(&normal.normalize()), 0.0, epsilon = 1e-5);
+            assert_relative_eq!(start.norm(), 2.0, epsilon = 1e-5);
+        }
+    }
+
+    #[test]
+    fn capsule_line_count() {
+        let mut lines = DebugLinesComponent::new();
+        lines.add_capsule(
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(0.0, 2.0, 0.0),
+            0.5,
+            16,
+            Srgba::default(),
+        );
+
+        // two rings, four side lines and four half-circle arcs
+        assert_eq!(lines.lines().len(), 16 * 2 + 4 + 8 * 4);
+    }
+
+    #[test]
+    fn capsule_caps_extend_past_segment() {
+        let mut lines = DebugLinesComponent::new();
+        lines.add_capsule(
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(0.0, 2.0, 0.0),
+            0.5,
+            8,
+            Srgba::default(),
+        );
+
+        let max_y = lines
+            .lines()
+            .iter()
+            .flat_map(|l| vec![l.start.position.0[1], l.end.position.0[1]])
+            .fold(std::f32::MIN, f32::max);
+        let min_y = lines
+            .lines()
+            .iter()
+            .flat_map(|l| vec![l.start.position.0[1], l.end.position.0[1]])
+            .fold(std::f32::MAX, f32::min);
+        assert_relative_eq!(max_y, 2.5, epsilon = 1e-5);
+        assert_relative_eq!(min_y, -0.5, epsilon = 1e-5);
+    }
+
+    #[test]
+    fn line_width_defaults_to_global() {
+        let mut lines = DebugLinesComponent::new();
+        lines.add_line(
+            Point3::origin(),
+            Point3::new(1.0, 0.0, 0.0),
+            Srgba::default(),
+        );
+        lines.add_line_with_width(
+            Point3::origin(),
+            Point3::new(0.0, 1.0, 0.0),
+            Srgba::default(),
+            3.0,
+        );
+        lines.add_line_with_width(
+            Point3::origin(),
+            Point3::new(0.0, 0.0, 1.0),
+            Srgba::default(),
+            -1.0,
+        );
+
+        let widths: Vec<f32> = lines.lines().iter().map(|l| l.width).collect();
+        assert_eq!(widths, vec![0.0, 3.0, 0.0]);
+    }
+
+    #[test]
+    fn line_style_patterns() {
+        let mut lines = DebugLinesComponent::new();
+        let (start, end) = (Point3::origin(), Point3::new(1.0, 0.0, 0.0));
+        lines.add_line(start, end, Srgba::default());
+        lines.add_styled_line(
+            start,
+            end,
+            Srgba::default(),
+            DebugLineStyle::Dashed { dash_length: 4.0 },
+        );
+        lines.add_styled_line(
+            start,
+            end,
+            Srgba::default(),
+            DebugLineStyle::Dotted { spacing: 3.0 },
+        );
+
+        let patterns: Vec<[f32; 2]> = lines.lines().iter().map(|l| l.pattern).collect();
+        assert_eq!(patterns, vec![[0.0, 0.0], [4.0, 4.0], [1.0, 3.0]]);
+    }
+
+    #[test]
+    fn timed_lines_expire() {
+        let mut lines = DebugLines::new();
+        let (start, end) = (Point3::origin(), Point3::new(1.0, 0.0, 0.0));
+        lines.draw_line_for(start, end, Srgba::default(), Duration::from_millis(0));
+        lines.draw_line_for(start, end, Srgba::default(), Duration::from_millis(1500));
+        assert_eq!(lines.draw_timed_lines().len(), 2);
+
+        lines.expire(1.0);
+        assert_eq!(lines.draw_timed_lines().len(), 1);
+
+        lines.expire(1.0);
+        assert!(lines.draw_timed_lines().is_empty());
+    }
+
+    #[test]
+    fn screen_lines_lie_on_zero_plane() {
+        let mut lines = ScreenDebugLines::new();
+        lines.draw_line(
+            Point2::new(10.0, 20.0),
+            Point2::new(30.0, 40.0),
+            Srgba::default(),
+        );
+        lines.draw_rectangle(
+            Point2::new(0.0, 0.0),
+            Point2::new(5.0, 5.0),
+            Srgba::default(),
+        );
+
+        let drained: Vec<DebugLine> = lines.drain().collect();
+        assert_eq!(drained.len(), 5);
+        assert_eq!(drained[0].start.position.0, [10.0, 20.0, 0.0]);
+        assert_eq!(drained[0].end.position.0, [30.0, 40.0, 0.0]);
+        assert!(drained
+            .iter()
+            .all(|l| l.start.position.0[2] == 0.0 && l.end.position.0[2] == 0.0));
+        assert_eq!(lines.drain().count(), 0);
+    }
 }
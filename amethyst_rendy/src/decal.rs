@@ -0,0 +1,62 @@
+//! Decals projected onto the opaque surfaces of the deferred shading path.
+use crate::types::Texture;
+use amethyst_assets::Handle;
+use amethyst_core::{
+    ecs::prelude::{Component, DenseVecStorage},
+    math::Vector3,
+};
+
+/// Component projecting a texture onto the opaque surfaces inside a box, for bullet holes,
+/// blood or road markings.
+///
+/// The box is centered on the `Transform` of the entity and spans `size` along its local axes,
+/// scaled by the transform. The texture is projected along the negative z axis with its top
+/// towards the positive y axis, and fades out on surfaces turning away from the projection.
+/// Decals are drawn by `RenderDeferredPbr3D` with decals enabled, blending their albedo and
+/// normal onto the G-buffer before it is shaded.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Decal {
+    /// Albedo blended over the surfaces by its alpha.
+    pub albedo: Handle<Texture>,
+    /// Tangent space normal map replacing the normal of the surfaces, blended by the alpha of
+    /// `albedo`. The x and y axes of the box are the tangent and bitangent.
+    pub normal: Option<Handle<Texture>>,
+    /// Linear RGBA color multiplying the albedo texture.
+    pub color: [f32; 4],
+    /// Size of the box along its local axes.
+    pub size: Vector3<f32>,
+}
+
+impl Decal {
+    /// Decal projecting `albedo` through a unit box.
+    pub fn new(albedo: Handle<Texture>) -> Self {
+        Decal {
+            albedo,
+            normal: None,
+            color: [1.0; 4],
+            size: Vector3::new(1.0, 1.0, 1.0),
+        }
+    }
+
+    /// Set the normal map of the decal.
+    pub fn with_normal(mut self, normal: Handle<Texture>) -> Self {
+        self.normal = Some(normal);
+        self
+    }
+
+    /// Set the color multiplying the albedo texture.
+    pub fn with_color(mut self, color: [f32; 4]) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Set the size of the box.
+    pub fn with_size(mut self, size: Vector3<f32>) -> Self {
+        self.size = size;
+        self
+    }
+}
+
+impl Component for Decal {
+    type Storage = DenseVecStorage<Self>;
+}
@@ -0,0 +1,75 @@
+//! Depth of field configuration.
+
+use crate::submodules::gather::CameraGatherer;
+use amethyst_core::ecs::{Component, DenseVecStorage, Read, ReadStorage, SystemData, World};
+
+/// Resource configuring the depth of field applied by `RenderDepthOfField`.
+///
+/// All fields are read every frame. Focus is set per camera with the [DepthOfField]
+/// component; nothing is blurred while the active camera has none.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DofParams {
+    /// Blur out of focus surfaces.
+    pub enabled: bool,
+    /// Largest radius of the bokeh blur, in pixels.
+    pub max_blur_radius: f32,
+    /// Distance, in pixels, between the first samples of the bokeh disk. Samples are spaced
+    /// further apart towards its edge. Smaller values take more samples at a higher cost.
+    pub sample_spacing: f32,
+}
+
+impl Default for DofParams {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_blur_radius: 12.0,
+            sample_spacing: 0.5,
+        }
+    }
+}
+
+impl DofParams {
+    /// Returns the `DofParams` resource, or default parameters if there is none.
+    pub fn fetch(world: &World) -> Self {
+        <Option<Read<'_, DofParams>>>::fetch(world)
+            .map(|p| *p)
+            .unwrap_or_default()
+    }
+}
+
+/// Focus of the camera entity it is attached to, applied by `RenderDepthOfField`
+/// while that camera is active.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct DepthOfField {
+    /// Distance from the camera of surfaces in focus, in world units.
+    pub focus_distance: f32,
+    /// Size of the lens aperture, scaling the blur of out of focus surfaces.
+    /// At 1, infinitely far surfaces are blurred by `DofParams::max_blur_radius`.
+    pub aperture: f32,
+}
+
+impl Default for DepthOfField {
+    fn default() -> Self {
+        Self {
+            focus_distance: 10.0,
+            aperture: 0.5,
+        }
+    }
+}
+
+impl Component for DepthOfField {
+    type Storage = DenseVecStorage<Self>;
+}
+
+impl DepthOfField {
+    /// Returns the `DepthOfField` of the active camera, if it has one.
+    ///
+    /// The component must be registered, which `RenderDepthOfField` does.
+    pub fn active(world: &World) -> Option<Self> {
+        let camera = CameraGatherer::gather_camera_entity(world)?;
+        <ReadStorage<'_, DepthOfField>>::fetch(world)
+            .get(camera)
+            .copied()
+    }
+}
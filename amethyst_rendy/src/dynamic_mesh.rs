@@ -0,0 +1,80 @@
+//! Updating the geometry of loaded meshes every frame, e.g. for trails, ropes or destructible
+//! terrain.
+
+use crate::types::{Backend, Mesh, MeshData};
+use amethyst_assets::{AssetStorage, Handle};
+use amethyst_core::ecs::{ReadExpect, System, Write};
+use rendy::{command::QueueId, factory::Factory};
+use std::marker::PhantomData;
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+/// Resource queueing new vertices and indices of `Mesh` assets, uploaded by the
+/// [MeshUpdateSystem] before the frame is drawn.
+///
+/// An update replaces the whole mesh under its handle, so every entity sharing the handle draws
+/// the new geometry from this frame on without loading a new asset. Updates of a mesh which isn't
+/// loaded yet wait until it is. Bounding volumes of the entities aren't updated.
+#[derive(Debug, Default)]
+pub struct MeshUpdates {
+    pending: Vec<(Handle<Mesh>, MeshData)>,
+}
+
+impl MeshUpdates {
+    /// Queue new geometry of the mesh under `handle`, replacing any update queued for it
+    /// earlier in the frame.
+    pub fn update(&mut self, handle: &Handle<Mesh>, data: impl Into<MeshData>) {
+        let data = data.into();
+        match self
+            .pending
+            .iter_mut()
+            .find(|(pending, _)| pending.id() == handle.id())
+        {
+            Some((_, pending)) => *pending = data,
+            None => self.pending.push((handle.clone(), data)),
+        }
+    }
+
+    /// Returns `true` if no update is queued.
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+/// Uploads the meshes queued in [MeshUpdates] and replaces the assets in the storage.
+///
+/// Each update is uploaded into new buffers through the transfer queue, so the frames in flight
+/// keep drawing the previous geometry without waiting for the GPU. The buffers of the previous
+/// mesh are released once those frames are done.
+#[derive(Debug, derivative::Derivative)]
+#[derivative(Default(bound = ""))]
+pub struct MeshUpdateSystem<B: Backend>(PhantomData<B>);
+
+impl<'a, B: Backend> System<'a> for MeshUpdateSystem<B> {
+    type SystemData = (
+        Write<'a, MeshUpdates>,
+        Write<'a, AssetStorage<Mesh>>,
+        ReadExpect<'a, QueueId>,
+        ReadExpect<'a, Factory<B>>,
+    );
+
+    fn run(&mut self, (mut updates, mut mesh_storage, queue_id, factory): Self::SystemData) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("mesh_update");
+
+        let pending = std::mem::replace(&mut updates.pending, Vec::new());
+        for (handle, data) in pending {
+            if !mesh_storage.contains(&handle) {
+                updates.pending.push((handle, data));
+                continue;
+            }
+            match data.0.build(*queue_id, &factory) {
+                Ok(mesh) => {
+                    mesh_storage.replace(&handle, B::wrap_mesh(mesh));
+                }
+                Err(e) => log::error!("Failed to update mesh {}: {}", handle.id(), e),
+            }
+        }
+    }
+}
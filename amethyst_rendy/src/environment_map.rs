@@ -0,0 +1,553 @@
+//! Image-based lighting from prefiltered environment cube maps.
+
+use crate::{
+    light_probe::{sh_basis, ShCoefficients},
+    types::{Texture, TextureData},
+};
+use amethyst_assets::{AssetStorage, Handle, Loader, ProgressCounter};
+use amethyst_core::math::Vector3;
+use amethyst_error::{format_err, Error};
+use rendy::{
+    hal::image::{Filter, Kind, SamplerInfo, ViewKind, WrapMode},
+    texture::{pixel::Rgba32Sfloat, TextureBuilder},
+};
+use std::f32::consts::PI;
+
+/// Number of prefiltered specular cube maps, for roughness evenly spread from 0.0 to 1.0.
+pub const SPECULAR_LEVELS: usize = 5;
+
+/// Width and height of the faces of the irradiance cube map, in texels.
+const IRRADIANCE_SIZE: u32 = 16;
+
+/// Maximum width and height of the faces of the specular cube maps, in texels.
+const MAX_SPECULAR_SIZE: u32 = 128;
+
+/// Number of GGX samples taken for each texel of the rough specular cube maps.
+const SPECULAR_SAMPLES: u32 = 64;
+
+/// Resource providing image-based lighting to the PBR pass.
+///
+/// When present, the constant `AmbientColor` of PBR materials is replaced
+/// by the diffuse and specular response to the environment.
+#[derive(Clone, Debug)]
+pub struct EnvironmentMap {
+    /// Cube map of cosine-convolved radiance, sampled with the surface normal.
+    pub irradiance: Handle<Texture>,
+    /// Cube map array of radiance prefiltered with increasing GGX roughness,
+    /// `SPECULAR_LEVELS` cube maps in total.
+    pub specular: Handle<Texture>,
+    /// Scale applied to the lighting from the environment.
+    pub intensity: f32,
+}
+
+impl EnvironmentMap {
+    /// Convolve the environment `cubemap` and load the irradiance and specular cube maps
+    /// as textures.
+    ///
+    /// Convolution happens on the calling thread, so this is best done while loading.
+    pub fn load(
+        cubemap: &HdrCubemap,
+        loader: &Loader,
+        storage: &AssetStorage<Texture>,
+        progress: &mut ProgressCounter,
+    ) -> Self {
+        let (irradiance, specular) = convolve(cubemap);
+        Self {
+            irradiance: loader.load_from_data(
+                cube_texture_builder(&[irradiance]).into(),
+                &mut *progress,
+                storage,
+            ),
+            specular: loader.load_from_data(
+                cube_texture_builder(&specular).into(),
+                &mut *progress,
+                storage,
+            ),
+            intensity: 1.0,
+        }
+    }
+
+    /// Returns this environment map with the given intensity.
+    pub fn with_intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+}
+
+/// Convolve `cubemap` into an irradiance cube map and `SPECULAR_LEVELS` prefiltered specular
+/// cube maps.
+pub(crate) fn convolve(cubemap: &HdrCubemap) -> (HdrCubemap, Vec<HdrCubemap>) {
+    let irradiance = cubemap.irradiance(IRRADIANCE_SIZE.min(cubemap.size()));
+    let specular_size = MAX_SPECULAR_SIZE.min(cubemap.size());
+    let specular = (0..SPECULAR_LEVELS)
+        .map(|level| {
+            let roughness = level as f32 / (SPECULAR_LEVELS - 1) as f32;
+            cubemap.prefiltered(specular_size, roughness)
+        })
+        .collect();
+    (irradiance, specular)
+}
+
+/// Cube map of linear HDR radiance.
+///
+/// Faces are stored in +X, -X, +Y, -Y, +Z, -Z order, each face row by row from the top,
+/// following the Vulkan cube map face orientation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HdrCubemap {
+    size: u32,
+    texels: Vec<[f32; 3]>,
+}
+
+impl HdrCubemap {
+    /// Create a cube map with faces of `size` by `size` texels from linear RGB `texels`.
+    pub fn new(size: u32, texels: Vec<[f32; 3]>) -> Result<Self, Error> {
+        let expected = 6 * size as usize * size as usize;
+        if size == 0 || texels.len() != expected {
+            return Err(format_err!(
+                "Cube map with {}x{} faces needs {} texels, got {}",
+                size,
+                size,
+                expected,
+                texels.len()
+            ));
+        }
+        Ok(Self { size, texels })
+    }
+
+    /// Create a cube map with faces of `size` by `size` texels,
+    /// evaluating `radiance` at the normalized direction of each texel center.
+    pub fn from_fn(size: u32, radiance: impl Fn(&Vector3<f32>) -> [f32; 3]) -> Self {
+        let size = size.max(1);
+        let texels = (0..6)
+            .flat_map(|face| {
+                (0..size * size)
+                    .map(move |texel| texel_direction(face, texel % size, texel / size, size))
+            })
+            .map(|direction| radiance(&direction))
+            .collect();
+        Self { size, texels }
+    }
+
+    /// Create a cube map with faces of `size` by `size` texels from an equirectangular
+    /// (latitude-longitude) panorama of `width` by `height` linear RGB `texels`.
+    ///
+    /// The top row of the panorama is straight up, its center column looks towards -Z.
+    pub fn from_equirect(
+        width: u32,
+        height: u32,
+        texels: &[[f32; 3]],
+        size: u32,
+    ) -> Result<Self, Error> {
+        if width == 0 || height == 0 || texels.len() != width as usize * height as usize {
+            return Err(format_err!(
+                "Equirectangular image of {}x{} texels needs {} texels, got {}",
+                width,
+                height,
+                width as usize * height as usize,
+                texels.len()
+            ));
+        }
+
+        let texel = |x: i64, y: i64| {
+            let x = x.rem_euclid(i64::from(width)) as usize;
+            let y = y.max(0).min(i64::from(height) - 1) as usize;
+            texels[y * width as usize + x]
+        };
+        Ok(Self::from_fn(size, |direction| {
+            let longitude = direction.x.atan2(-direction.z);
+            let latitude = direction.y.max(-1.0).min(1.0).acos();
+            let u = (longitude / (2.0 * PI) + 0.5) * width as f32 - 0.5;
+            let v = latitude / PI * height as f32 - 0.5;
+
+            // Bilinear filtering, wrapping around horizontally.
+            let (x, y) = (u.floor(), v.floor());
+            let (fx, fy) = (u - x, v - y);
+            let (x, y) = (x as i64, y as i64);
+            let mut sum = [0.0; 3];
+            add_scaled(&mut sum, &texel(x, y), (1.0 - fx) * (1.0 - fy));
+            add_scaled(&mut sum, &texel(x + 1, y), fx * (1.0 - fy));
+            add_scaled(&mut sum, &texel(x, y + 1), (1.0 - fx) * fy);
+            add_scaled(&mut sum, &texel(x + 1, y + 1), fx * fy);
+            sum
+        }))
+    }
+
+    /// Decode an equirectangular panorama and convert it to a cube map with faces of
+    /// `size` by `size` texels.
+    ///
+    /// Radiance HDR images are read as linear radiance, other formats are assumed
+    /// to be sRGB encoded.
+    pub fn decode_equirect(bytes: &[u8], size: u32) -> Result<Self, Error> {
+        use image::{hdr::HDRDecoder, ImageFormat};
+
+        let (width, height, texels) = match image::guess_format(bytes) {
+            Ok(ImageFormat::HDR) => {
+                let decoder = HDRDecoder::new(bytes).map_err(|e| format_err!("{}", e))?;
+                let metadata = decoder.metadata();
+                let texels = decoder
+                    .read_image_hdr()
+                    .map_err(|e| format_err!("{}", e))?
+                    .into_iter()
+                    .map(|pixel| pixel.0)
+                    .collect::<Vec<_>>();
+                (metadata.width, metadata.height, texels)
+            }
+            _ => {
+                let image = image::load_from_memory(bytes)
+                    .map_err(|e| format_err!("{}", e))?
+                    .to_rgb();
+                let texels = image
+                    .pixels()
+                    .map(|pixel| {
+                        let (r, g, b) = palette::Srgb::new(pixel[0], pixel[1], pixel[2])
+                            .into_format::<f32>()
+                            .into_linear()
+                            .into_components();
+                        [r, g, b]
+                    })
+                    .collect::<Vec<_>>();
+                (image.width(), image.height(), texels)
+            }
+        };
+        Self::from_equirect(width, height, &texels, size)
+    }
+
+    /// Returns texture data of a cube texture with the faces of this cube map.
+    pub fn texture_data(&self) -> TextureData {
+        cube_texture_builder(std::slice::from_ref(self)).into()
+    }
+
+    /// Width and height of each face, in texels.
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// Linear RGB texels of all faces.
+    pub fn texels(&self) -> &[[f32; 3]] {
+        &self.texels
+    }
+
+    /// Returns the radiance of the texel hit by `direction`.
+    pub fn sample(&self, direction: &Vector3<f32>) -> [f32; 3] {
+        let (face, s, t) = direction_face(direction);
+        let texel = |c: f32| (((c + 1.0) * 0.5 * self.size as f32) as u32).min(self.size - 1);
+        let index = (face * self.size + texel(t)) * self.size + texel(s);
+        self.texels[index as usize]
+    }
+
+    /// Returns this cube map resized to faces of `size` by `size` texels.
+    ///
+    /// Texels are averaged when `size` divides the current size.
+    pub fn resized(&self, size: u32) -> Self {
+        let size = size.max(1);
+        if size == self.size {
+            return self.clone();
+        }
+        if size > self.size || self.size % size != 0 {
+            return Self::from_fn(size, |direction| self.sample(direction));
+        }
+
+        let block = self.size / size;
+        let weight = 1.0 / (block * block) as f32;
+        let texels = (0..6)
+            .flat_map(|face| (0..size * size).map(move |texel| (face, texel % size, texel / size)))
+            .map(|(face, x, y)| {
+                let mut sum = [0.0; 3];
+                for row in y * block..(y + 1) * block {
+                    for column in x * block..(x + 1) * block {
+                        let index = (face * self.size + row) * self.size + column;
+                        add_scaled(&mut sum, &self.texels[index as usize], weight);
+                    }
+                }
+                sum
+            })
+            .collect();
+        Self { size, texels }
+    }
+
+    /// Convolve this cube map with a cosine lobe, giving the radiance reflected by
+    /// a white Lambertian surface facing each texel direction.
+    pub fn irradiance(&self, size: u32) -> Self {
+        let source = self.resized(IRRADIANCE_SIZE.min(self.size));
+        let samples = source.solid_angle_samples();
+        Self::from_fn(size, |normal| {
+            let mut sum = [0.0; 3];
+            for (direction, solid_angle, radiance) in &samples {
+                let cosine = normal.dot(direction);
+                if cosine > 0.0 {
+                    add_scaled(&mut sum, radiance, cosine * solid_angle / PI);
+                }
+            }
+            sum
+        })
+    }
+
+    /// Convolve this cube map with the GGX distribution of the given `roughness`,
+    /// assuming the view direction equals the surface normal.
+    pub fn prefiltered(&self, size: u32, roughness: f32) -> Self {
+        let roughness = roughness.max(0.0).min(1.0);
+        if roughness == 0.0 {
+            return self.resized(size);
+        }
+
+        // Sample a smaller cube map for rougher lobes to avoid noise from sparse samples.
+        let source_size = (size >> (roughness * 4.0).round() as u32).max(4).min(size);
+        let source = self.resized(source_size);
+        let alpha = roughness * roughness;
+        Self::from_fn(size, |normal| {
+            let (tangent, bitangent) = tangent_basis(normal);
+            let mut sum = [0.0; 3];
+            let mut total_weight = 0.0;
+            for i in 0..SPECULAR_SAMPLES {
+                let (u, v) = hammersley(i, SPECULAR_SAMPLES);
+                let phi = 2.0 * PI * u;
+                let cos_theta = ((1.0 - v) / (1.0 + (alpha * alpha - 1.0) * v)).sqrt();
+                let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+                let halfway = tangent * (sin_theta * phi.cos())
+                    + bitangent * (sin_theta * phi.sin())
+                    + normal * cos_theta;
+                let light = halfway * (2.0 * normal.dot(&halfway)) - normal;
+                let n_dot_l = normal.dot(&light);
+                if n_dot_l > 0.0 {
+                    add_scaled(&mut sum, &source.sample(&light), n_dot_l);
+                    total_weight += n_dot_l;
+                }
+            }
+            if total_weight > 0.0 {
+                sum.iter_mut().for_each(|c| *c /= total_weight);
+            }
+            sum
+        })
+    }
+
+    /// Project this cube map onto the spherical harmonics of [light probes](crate::light_probe).
+    pub fn spherical_harmonics(&self) -> ShCoefficients {
+        let mut coefficients = [[0.0; 3]; 9];
+        for (direction, solid_angle, radiance) in self.solid_angle_samples() {
+            for (coefficient, basis) in coefficients.iter_mut().zip(&sh_basis(&direction)) {
+                add_scaled(coefficient, &radiance, basis * solid_angle);
+            }
+        }
+        coefficients
+    }
+
+    fn solid_angle_samples(&self) -> Vec<(Vector3<f32>, f32, [f32; 3])> {
+        let size = self.size;
+        (0..6)
+            .flat_map(|face| (0..size * size).map(move |texel| (face, texel % size, texel / size)))
+            .zip(&self.texels)
+            .map(|((face, x, y), radiance)| {
+                let s = texel_coordinate(x, size);
+                let t = texel_coordinate(y, size);
+                let area = (2.0 / size as f32).powi(2);
+                let solid_angle = area / (1.0 + s * s + t * t).powf(1.5);
+                (texel_direction(face, x, y, size), solid_angle, *radiance)
+            })
+            .collect()
+    }
+}
+
+/// Create a builder of a texture with all faces of `cubemaps`.
+///
+/// A single cube map gives a cube texture, multiple ones a cube array texture.
+pub(crate) fn cube_texture_builder(cubemaps: &[HdrCubemap]) -> TextureBuilder<'static> {
+    let size = cubemaps[0].size();
+    let data = cubemaps
+        .iter()
+        .flat_map(|cubemap| {
+            debug_assert_eq!(cubemap.size(), size);
+            cubemap.texels().iter()
+        })
+        .map(|&[r, g, b]| Rgba32Sfloat {
+            repr: [r, g, b, 1.0],
+        })
+        .collect::<Vec<_>>();
+
+    TextureBuilder::new()
+        .with_kind(Kind::D2(size, size, 6 * cubemaps.len() as u16, 1))
+        .with_view_kind(if cubemaps.len() == 1 {
+            ViewKind::Cube
+        } else {
+            ViewKind::CubeArray
+        })
+        .with_data_width(size)
+        .with_data_height(size)
+        .with_sampler_info(SamplerInfo::new(Filter::Linear, WrapMode::Clamp))
+        .with_data(data)
+}
+
+pub(crate) fn texel_coordinate(texel: u32, size: u32) -> f32 {
+    2.0 * (texel as f32 + 0.5) / size as f32 - 1.0
+}
+
+/// Direction through the center of texel `x`, `y` of cube map `face`.
+pub(crate) fn texel_direction(face: u32, x: u32, y: u32, size: u32) -> Vector3<f32> {
+    let s = texel_coordinate(x, size);
+    let t = texel_coordinate(y, size);
+    let direction = match face {
+        0 => Vector3::new(1.0, -t, -s),
+        1 => Vector3::new(-1.0, -t, s),
+        2 => Vector3::new(s, 1.0, t),
+        3 => Vector3::new(s, -1.0, -t),
+        4 => Vector3::new(s, -t, 1.0),
+        _ => Vector3::new(-s, -t, -1.0),
+    };
+    direction.normalize()
+}
+
+/// Cube map face hit by `direction`, with face coordinates in -1..1 range.
+fn direction_face(direction: &Vector3<f32>) -> (u32, f32, f32) {
+    let (x, y, z) = (direction.x, direction.y, direction.z);
+    let (ax, ay, az) = (x.abs(), y.abs(), z.abs());
+    if ax >= ay && ax >= az {
+        if x > 0.0 {
+            (0, -z / ax, -y / ax)
+        } else {
+            (1, z / ax, -y / ax)
+        }
+    } else if ay >= az {
+        if y > 0.0 {
+            (2, x / ay, z / ay)
+        } else {
+            (3, x / ay, -z / ay)
+        }
+    } else if z > 0.0 {
+        (4, x / az, -y / az)
+    } else {
+        (5, -x / az, -y / az)
+    }
+}
+
+fn tangent_basis(normal: &Vector3<f32>) -> (Vector3<f32>, Vector3<f32>) {
+    let up = if normal.z.abs() < 0.999 {
+        Vector3::z()
+    } else {
+        Vector3::x()
+    };
+    let tangent = up.cross(normal).normalize();
+    (tangent, normal.cross(&tangent))
+}
+
+fn hammersley(i: u32, count: u32) -> (f32, f32) {
+    (
+        i as f32 / count as f32,
+        i.reverse_bits() as f32 / 4_294_967_296.0,
+    )
+}
+
+fn add_scaled(sum: &mut [f32; 3], value: &[f32; 3], scale: f32) {
+    for (sum, value) in sum.iter_mut().zip(value) {
+        *sum += value * scale;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: [f32; 3], expected: [f32; 3], epsilon: f32) {
+        for (actual, expected) in actual.iter().zip(&expected) {
+            assert!(
+                (actual - expected).abs() < epsilon,
+                "{:?} != {:?}",
+                actual,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn directions_map_to_their_texels() {
+        let size = 8;
+        for face in 0..6 {
+            for y in 0..size {
+                for x in 0..size {
+                    let (hit, s, t) = direction_face(&texel_direction(face, x, y, size));
+                    assert_eq!(hit, face);
+                    assert!((s - texel_coordinate(x, size)).abs() < 1e-5);
+                    assert!((t - texel_coordinate(y, size)).abs() < 1e-5);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn constant_environment_is_preserved() {
+        let cubemap = HdrCubemap::from_fn(16, |_| [0.5, 1.0, 2.0]);
+        for texel in cubemap.irradiance(4).texels() {
+            assert_close(*texel, [0.5, 1.0, 2.0], 0.02);
+        }
+        for texel in cubemap.prefiltered(8, 0.5).texels() {
+            assert_close(*texel, [0.5, 1.0, 2.0], 1e-4);
+        }
+    }
+
+    #[test]
+    fn irradiance_faces_the_light() {
+        let cubemap = HdrCubemap::from_fn(16, |direction| {
+            if direction.y > 0.0 {
+                [1.0; 3]
+            } else {
+                [0.0; 3]
+            }
+        });
+        let irradiance = cubemap.irradiance(5);
+        let up = irradiance.sample(&Vector3::y())[0];
+        let side = irradiance.sample(&Vector3::x())[0];
+        let down = irradiance.sample(&-Vector3::y())[0];
+        assert!(up > 0.95 && up < 1.05, "{}", up);
+        assert!(side > 0.4 && side < 0.6, "{}", side);
+        assert!(down < 0.05, "{}", down);
+    }
+
+    #[test]
+    fn spherical_harmonics_match_irradiance() {
+        let cubemap = HdrCubemap::from_fn(16, |direction| {
+            if direction.y > 0.0 {
+                [1.0; 3]
+            } else {
+                [0.0; 3]
+            }
+        });
+        let coefficients = cubemap.spherical_harmonics();
+        let irradiance = cubemap.irradiance(5);
+        for normal in &[Vector3::y(), Vector3::x(), -Vector3::y(), Vector3::z()] {
+            assert_close(
+                crate::light_probe::sh_irradiance(&coefficients, normal),
+                irradiance.sample(normal),
+                0.1,
+            );
+        }
+    }
+
+    #[test]
+    fn texel_count_is_checked() {
+        assert!(HdrCubemap::new(2, vec![[0.0; 3]; 24]).is_ok());
+        assert!(HdrCubemap::new(2, vec![[0.0; 3]; 23]).is_err());
+        assert!(HdrCubemap::new(0, vec![]).is_err());
+        assert!(HdrCubemap::from_equirect(4, 2, &[[0.0; 3]; 7], 2).is_err());
+    }
+
+    #[test]
+    fn equirect_rows_map_to_latitude() {
+        // Top half red, bottom half blue, with a green column looking towards -Z.
+        let (width, height) = (16, 8);
+        let texels = (0..height)
+            .flat_map(|y| {
+                (0..width).map(move |x| match (x, y < height / 2) {
+                    (7..=8, _) => [0.0, 1.0, 0.0],
+                    (_, true) => [1.0, 0.0, 0.0],
+                    (_, false) => [0.0, 0.0, 1.0],
+                })
+            })
+            .collect::<Vec<_>>();
+        let cubemap = HdrCubemap::from_equirect(width, height, &texels, 8).unwrap();
+
+        assert_close(cubemap.sample(&Vector3::y()), [1.0, 0.0, 0.0], 1e-4);
+        assert_close(cubemap.sample(&-Vector3::y()), [0.0, 0.0, 1.0], 1e-4);
+        let forward = cubemap.sample(&Vector3::new(0.0, 0.3, -1.0).normalize());
+        assert!(forward[1] > 0.9, "{:?}", forward);
+        let backward = cubemap.sample(&Vector3::new(0.0, 0.3, 1.0).normalize());
+        assert_close(backward, [1.0, 0.0, 0.0], 1e-4);
+    }
+}
@@ -7,6 +7,8 @@ use std::{error, fmt};
 pub(crate) enum Error {
     /// Failed to parse a Spritesheet from RON.
     LoadSpritesheetError(ron::de::Error),
+    /// Failed to parse light probes from RON.
+    LoadLightProbesError(ron::de::Error),
 }
 
 impl error::Error for Error {}
@@ -17,6 +19,7 @@ impl fmt::Display for Error {
 
         match *self {
             LoadSpritesheetError(..) => write!(fmt, "Failed to parse SpriteSheet"),
+            LoadLightProbesError(..) => write!(fmt, "Failed to parse light probes"),
         }
     }
 }
@@ -0,0 +1,68 @@
+//! Distance and height fog configuration.
+
+use amethyst_core::ecs::{Read, SystemData, World};
+
+/// How the opacity of the fog grows with distance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FogMode {
+    /// Linearly from `Fog::start` to `Fog::end`.
+    Linear,
+    /// Exponentially with the distance scaled by `Fog::density`.
+    Exponential,
+    /// Exponentially with the square of the distance scaled by `Fog::density`.
+    ExponentialSquared,
+}
+
+/// Resource configuring the fog applied by `RenderFog`.
+///
+/// All fields are read every frame, so the fog can change with the weather or time of day.
+/// While disabled, the pass copies its input unchanged.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fog {
+    /// Blend distant surfaces into the fog color.
+    pub enabled: bool,
+    /// How the opacity grows with distance.
+    pub mode: FogMode,
+    /// Linear color of the fog.
+    pub color: [f32; 3],
+    /// Density of the exponential modes, per world unit.
+    pub density: f32,
+    /// Distance from the camera where linear fog starts.
+    pub start: f32,
+    /// Distance from the camera where linear fog becomes opaque.
+    pub end: f32,
+    /// Rate at which the fog thins out with height above `base_height`, per world unit.
+    /// At zero the fog is uniform.
+    pub height_falloff: f32,
+    /// World height at which the fog has its full density.
+    pub base_height: f32,
+    /// Apply the fog to pixels where nothing was drawn, e.g. the skybox, as if they were
+    /// infinitely far. Rays towards the horizon are then hidden by the fog, while rays
+    /// leaving height fog upwards stay clear.
+    pub sky: bool,
+}
+
+impl Default for Fog {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            mode: FogMode::Exponential,
+            color: [0.5, 0.6, 0.7],
+            density: 0.02,
+            start: 10.0,
+            end: 100.0,
+            height_falloff: 0.0,
+            base_height: 0.0,
+            sky: true,
+        }
+    }
+}
+
+impl Fog {
+    /// Returns the `Fog` resource, or default parameters if there is none.
+    pub fn fetch(world: &World) -> Self {
+        <Option<Read<'_, Fog>>>::fetch(world)
+            .map(|p| *p)
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,711 @@
+//! Parsing of the KTX, KTX2 and DDS texture containers.
+use crate::types::{TextureData, TextureLevel};
+use amethyst_error::{format_err, Error};
+use rendy::{
+    hal::{
+        format::{Format, NUM_FORMATS},
+        image::{Kind, SamplerInfo, ViewKind},
+    },
+    texture::{MipLevels, TextureBuilder},
+};
+use std::num::NonZeroU8;
+
+const KTX_IDENTIFIER: [u8; 12] = [
+    0xAB, b'K', b'T', b'X', b' ', b'1', b'1', 0xBB, b'\r', b'\n', 0x1A, b'\n',
+];
+const KTX2_IDENTIFIER: [u8; 12] = [
+    0xAB, b'K', b'T', b'X', b' ', b'2', b'0', 0xBB, b'\r', b'\n', 0x1A, b'\n',
+];
+const KTX_ENDIANNESS: u32 = 0x0403_0201;
+const DDS_MAGIC: [u8; 4] = *b"DDS ";
+
+/// 2D texture read from a container, with all of its mip levels.
+#[derive(Debug)]
+pub(crate) struct ContainerTexture {
+    pub format: Format,
+    pub width: u32,
+    pub height: u32,
+    /// Number of array layers, six for each cube map.
+    pub layers: u16,
+    pub cube: bool,
+    /// Pixels of each mip level, for every layer one after the other.
+    pub levels: Vec<Vec<u8>>,
+}
+
+impl ContainerTexture {
+    /// Creates the texture data uploading every level as it is.
+    pub fn into_texture_data(self, sampler_info: SamplerInfo) -> TextureData {
+        let view_kind = match (self.cube, self.layers) {
+            (true, 6) => ViewKind::Cube,
+            (true, _) => ViewKind::CubeArray,
+            (false, 1) => ViewKind::D2,
+            (false, _) => ViewKind::D2Array,
+        };
+        let level_count = self.levels.len() as u8;
+        let (format, width, height) = (self.format, self.width, self.height);
+        let mut levels = self.levels.into_iter().enumerate().map(|(level, data)| {
+            let (data_width, data_height) = level_data_size(format, width, height, level);
+            TextureLevel {
+                data_width,
+                data_height,
+                data,
+            }
+        });
+        let base = levels.next().expect("Container textures have a base level");
+
+        let builder = TextureBuilder::new()
+            .with_kind(Kind::D2(width, height, self.layers, 1))
+            .with_view_kind(view_kind)
+            .with_data_width(base.data_width)
+            .with_data_height(base.data_height)
+            .with_mip_levels(MipLevels::RawLevels(
+                NonZeroU8::new(level_count).expect("Container textures have a base level"),
+            ))
+            .with_sampler_info(sampler_info)
+            .with_raw_data(base.data, format);
+        TextureData(builder, levels.collect(), None)
+    }
+}
+
+/// Size of a mip level in texels, rounded up to whole blocks.
+fn level_data_size(format: Format, width: u32, height: u32, level: usize) -> (u32, u32) {
+    let (block_width, block_height) = format.surface_desc().dim;
+    let round_up = |size: u32, block: u8| {
+        let block = u32::from(block);
+        (size + block - 1) / block * block
+    };
+    (
+        round_up((width >> level).max(1), block_width),
+        round_up((height >> level).max(1), block_height),
+    )
+}
+
+/// Number of bytes of one layer of a mip level.
+fn level_layer_size(format: Format, width: u32, height: u32, level: usize) -> usize {
+    let desc = format.surface_desc();
+    let (data_width, data_height) = level_data_size(format, width, height, level);
+    (data_width / u32::from(desc.dim.0)) as usize
+        * (data_height / u32::from(desc.dim.1)) as usize
+        * usize::from(desc.bits / 8)
+}
+
+fn read_bytes(bytes: &[u8], offset: usize, len: usize) -> Result<&[u8], Error> {
+    offset
+        .checked_add(len)
+        .and_then(|end| bytes.get(offset..end))
+        .ok_or_else(|| format_err!("Unexpected end of texture file"))
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Result<u32, Error> {
+    let word = read_bytes(bytes, offset, 4)?;
+    Ok(u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> Result<usize, Error> {
+    let value = u64::from(read_u32(bytes, offset)?) | u64::from(read_u32(bytes, offset + 4)?) << 32;
+    Ok(value as usize)
+}
+
+fn pad_to_4(size: usize) -> usize {
+    (size + 3) & !3
+}
+
+/// Checks the layout shared by all containers and returns the number of layers.
+fn layer_count(width: u32, height: u32, array_layers: u32, faces: u32) -> Result<u16, Error> {
+    if width == 0 || height == 0 {
+        return Err(format_err!("Only 2D textures are supported"));
+    }
+    if faces != 1 && faces != 6 {
+        return Err(format_err!("Cube maps need all six faces"));
+    }
+    let layers = array_layers.max(1) * faces;
+    if layers > u32::from(u16::max_value()) {
+        return Err(format_err!("Texture has too many layers"));
+    }
+    Ok(layers as u16)
+}
+
+/// Parses a KTX 1.1 file holding block-compressed data.
+pub(crate) fn parse_ktx(bytes: &[u8]) -> Result<ContainerTexture, Error> {
+    if bytes.len() < 64 || bytes[..12] != KTX_IDENTIFIER {
+        return Err(format_err!("Not a KTX 1.1 file"));
+    }
+    let swap = match read_u32(bytes, 12)? {
+        KTX_ENDIANNESS => false,
+        endianness if endianness.swap_bytes() == KTX_ENDIANNESS => true,
+        _ => return Err(format_err!("Invalid KTX endianness marker")),
+    };
+    let field = |offset: usize| -> Result<u32, Error> {
+        let value = read_u32(bytes, offset)?;
+        Ok(if swap { value.swap_bytes() } else { value })
+    };
+
+    let gl_type = field(16)?;
+    let gl_internal_format = field(28)?;
+    let width = field(36)?;
+    let height = field(40)?;
+    let depth = field(44)?;
+    let array_elements = field(48)?;
+    let faces = field(52)?;
+    let level_count = field(56)?.max(1) as usize;
+    let key_value_bytes = field(60)? as usize;
+
+    if gl_type != 0 {
+        return Err(format_err!("KTX file is not block-compressed"));
+    }
+    if depth != 0 {
+        return Err(format_err!("Only 2D textures are supported"));
+    }
+    let layers = layer_count(width, height, array_elements, faces)?;
+    let format = gl_compressed_format(gl_internal_format).ok_or_else(|| {
+        format_err!(
+            "Unsupported KTX internal format {:#06x}",
+            gl_internal_format
+        )
+    })?;
+
+    // Faces of cube maps which aren't arrays are stored separately, each padded to 4 bytes.
+    let separate_faces = faces == 6 && array_elements == 0;
+    let mut offset = 64 + key_value_bytes;
+    let mut levels = Vec::with_capacity(level_count);
+    for level in 0..level_count {
+        let image_size = field(offset)? as usize;
+        offset += 4;
+        let layer_size = level_layer_size(format, width, height, level);
+        let (chunks, chunk_size) = if separate_faces {
+            (6, layer_size)
+        } else {
+            (1, layer_size * usize::from(layers))
+        };
+        if image_size != chunk_size {
+            return Err(format_err!(
+                "KTX level {} has {} bytes, expected {} for {}x{} {:?}",
+                level,
+                image_size,
+                chunk_size,
+                width,
+                height,
+                format
+            ));
+        }
+
+        let mut data = Vec::with_capacity(chunks * chunk_size);
+        for _ in 0..chunks {
+            data.extend_from_slice(read_bytes(bytes, offset, chunk_size)?);
+            offset += pad_to_4(chunk_size);
+        }
+        levels.push(data);
+    }
+
+    Ok(ContainerTexture {
+        format,
+        width,
+        height,
+        layers,
+        cube: faces == 6,
+        levels,
+    })
+}
+
+/// Returns whether a KTX2 file holds sRGB colors if it is a Basis Universal texture, which has to
+/// be transcoded before it can be used.
+pub(crate) fn ktx2_basis_srgb(bytes: &[u8]) -> Result<Option<bool>, Error> {
+    const COLOR_MODEL_ETC1S: u8 = 163;
+    const COLOR_MODEL_UASTC: u8 = 166;
+    const TRANSFER_SRGB: u8 = 2;
+
+    if bytes.len() < 80 || bytes[..12] != KTX2_IDENTIFIER {
+        return Err(format_err!("Not a KTX2 file"));
+    }
+    if read_u32(bytes, 12)? != 0 {
+        return Ok(None);
+    }
+    // The basic data format descriptor block follows the total size of the descriptor.
+    let descriptor = read_bytes(bytes, read_u32(bytes, 48)? as usize + 4, 24)?;
+    match descriptor[8] {
+        COLOR_MODEL_ETC1S | COLOR_MODEL_UASTC => Ok(Some(descriptor[10] == TRANSFER_SRGB)),
+        _ => Ok(None),
+    }
+}
+
+/// Parses a KTX2 file without supercompression.
+pub(crate) fn parse_ktx2(bytes: &[u8]) -> Result<ContainerTexture, Error> {
+    if bytes.len() < 80 || bytes[..12] != KTX2_IDENTIFIER {
+        return Err(format_err!("Not a KTX2 file"));
+    }
+    let vk_format = read_u32(bytes, 12)?;
+    read_ktx2_levels(bytes, || {
+        vk_format_to_format(vk_format)
+            .ok_or_else(|| format_err!("Unsupported KTX2 format {}", vk_format))
+    })
+}
+
+/// Parses a KTX2 file holding UASTC blocks without supercompression, and returns whether the
+/// texture has an alpha channel.
+///
+/// UASTC blocks have the size of ASTC 4x4 blocks, so the levels are read as `Astc4x4Unorm`
+/// data, to be transcoded before they are uploaded.
+#[cfg_attr(not(feature = "basis-universal"), allow(dead_code))]
+pub(crate) fn parse_ktx2_uastc(bytes: &[u8]) -> Result<(ContainerTexture, bool), Error> {
+    const COLOR_MODEL_UASTC: u8 = 166;
+    const CHANNEL_RGBA: u8 = 3;
+    const CHANNEL_RRRG: u8 = 5;
+    const SUPERCOMPRESSION_BASIS_LZ: u32 = 1;
+
+    if bytes.len() < 80 || bytes[..12] != KTX2_IDENTIFIER {
+        return Err(format_err!("Not a KTX2 file"));
+    }
+    if read_u32(bytes, 44)? == SUPERCOMPRESSION_BASIS_LZ {
+        return Err(format_err!(
+            "ETC1S textures in KTX2 files are not supported, export them as .basis files"
+        ));
+    }
+    // The channel of the only sample follows the basic data format descriptor block header.
+    let descriptor = read_bytes(bytes, read_u32(bytes, 48)? as usize + 4, 28)?;
+    if descriptor[8] != COLOR_MODEL_UASTC {
+        return Err(format_err!("KTX2 file doesn't hold UASTC data"));
+    }
+    let channel = descriptor[27] & 0xF;
+    let alpha = channel == CHANNEL_RGBA || channel == CHANNEL_RRRG;
+    let texture = read_ktx2_levels(bytes, || Ok(Format::Astc4x4Unorm))?;
+    Ok((texture, alpha))
+}
+
+/// Reads the levels of a KTX2 file without supercompression, in the format returned by
+/// `format` once the layout has been checked.
+fn read_ktx2_levels(
+    bytes: &[u8],
+    format: impl FnOnce() -> Result<Format, Error>,
+) -> Result<ContainerTexture, Error> {
+    let width = read_u32(bytes, 20)?;
+    let height = read_u32(bytes, 24)?;
+    let depth = read_u32(bytes, 28)?;
+    let array_layers = read_u32(bytes, 32)?;
+    let faces = read_u32(bytes, 36)?;
+    let level_count = read_u32(bytes, 40)?.max(1) as usize;
+    let supercompression = read_u32(bytes, 44)?;
+
+    if supercompression != 0 {
+        return Err(format_err!(
+            "Supercompressed KTX2 files are not supported, found scheme {}",
+            supercompression
+        ));
+    }
+    if depth != 0 {
+        return Err(format_err!("Only 2D textures are supported"));
+    }
+    let layers = layer_count(width, height, array_layers, faces)?;
+    let format = format()?;
+
+    // The level index starts with the base level, and each level stores its layers in order.
+    let levels = (0..level_count)
+        .map(|level| {
+            let index = 80 + level * 24;
+            let offset = read_u64(bytes, index)?;
+            let length = read_u64(bytes, index + 8)?;
+            let expected = level_layer_size(format, width, height, level) * usize::from(layers);
+            if length != expected {
+                return Err(format_err!(
+                    "KTX2 level {} has {} bytes, expected {} for {}x{} {:?}",
+                    level,
+                    length,
+                    expected,
+                    width,
+                    height,
+                    format
+                ));
+            }
+            Ok(read_bytes(bytes, offset, length)?.to_vec())
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    Ok(ContainerTexture {
+        format,
+        width,
+        height,
+        layers,
+        cube: faces == 6,
+        levels,
+    })
+}
+
+/// Parses a DDS file, with or without the DX10 header extension.
+pub(crate) fn parse_dds(bytes: &[u8]) -> Result<ContainerTexture, Error> {
+    const MIPMAP_COUNT: u32 = 0x2_0000;
+    const FOURCC: u32 = 0x4;
+    const RGB: u32 = 0x40;
+    const CUBEMAP: u32 = 0x200;
+    const CUBEMAP_ALL_FACES: u32 = 0xFC00;
+    const VOLUME: u32 = 0x20_0000;
+
+    if bytes.len() < 128 || bytes[..4] != DDS_MAGIC {
+        return Err(format_err!("Not a DDS file"));
+    }
+    let flags = read_u32(bytes, 8)?;
+    let height = read_u32(bytes, 12)?;
+    let width = read_u32(bytes, 16)?;
+    let level_count = if flags & MIPMAP_COUNT != 0 {
+        read_u32(bytes, 28)?.max(1) as usize
+    } else {
+        1
+    };
+    let pixel_flags = read_u32(bytes, 80)?;
+    let four_cc = read_bytes(bytes, 84, 4)?;
+    let caps2 = read_u32(bytes, 112)?;
+
+    if caps2 & VOLUME != 0 {
+        return Err(format_err!("Only 2D textures are supported"));
+    }
+    let (format, array_layers, cube, data_offset) =
+        if pixel_flags & FOURCC != 0 && four_cc == b"DX10" {
+            let dxgi_format = read_u32(bytes, 128)?;
+            let dimension = read_u32(bytes, 132)?;
+            let misc_flags = read_u32(bytes, 136)?;
+            if dimension != 3 {
+                return Err(format_err!("Only 2D textures are supported"));
+            }
+            let format = dxgi_format_to_format(dxgi_format)
+                .ok_or_else(|| format_err!("Unsupported DXGI format {}", dxgi_format))?;
+            (format, read_u32(bytes, 140)?, misc_flags & 0x4 != 0, 148)
+        } else {
+            let format = if pixel_flags & FOURCC != 0 {
+                legacy_four_cc_format(four_cc)
+            } else if pixel_flags & RGB != 0 && read_u32(bytes, 88)? == 32 {
+                match read_u32(bytes, 92)? {
+                    0x0000_00FF => Some(Format::Rgba8Unorm),
+                    0x00FF_0000 => Some(Format::Bgra8Unorm),
+                    _ => None,
+                }
+            } else {
+                None
+            };
+            let format = format.ok_or_else(|| format_err!("Unsupported DDS pixel format"))?;
+            if caps2 & CUBEMAP != 0 && caps2 & CUBEMAP_ALL_FACES != CUBEMAP_ALL_FACES {
+                return Err(format_err!("Cube maps need all six faces"));
+            }
+            (format, 1, caps2 & CUBEMAP != 0, 128)
+        };
+    let layers = layer_count(width, height, array_layers, if cube { 6 } else { 1 })?;
+
+    // DDS stores the full mip chain of each layer in turn, while levels are uploaded with all
+    // of their layers at once.
+    let level_sizes = (0..level_count)
+        .map(|level| level_layer_size(format, width, height, level))
+        .collect::<Vec<_>>();
+    let mut levels = level_sizes
+        .iter()
+        .map(|size| Vec::with_capacity(size * usize::from(layers)))
+        .collect::<Vec<_>>();
+    let mut offset = data_offset;
+    for _ in 0..layers {
+        for (level, &size) in levels.iter_mut().zip(&level_sizes) {
+            level.extend_from_slice(read_bytes(bytes, offset, size)?);
+            offset += size;
+        }
+    }
+
+    Ok(ContainerTexture {
+        format,
+        width,
+        height,
+        layers,
+        cube,
+        levels,
+    })
+}
+
+/// Maps a `VkFormat` value to the equivalent `Format`.
+fn vk_format_to_format(vk_format: u32) -> Option<Format> {
+    if vk_format == 0 || vk_format as usize >= NUM_FORMATS {
+        return None;
+    }
+    // `Format` is declared in the order of `VkFormat`, with the same values.
+    Some(unsafe { std::mem::transmute::<u32, Format>(vk_format) })
+}
+
+/// Maps the four character code of a DDS file without the DX10 extension to a `Format`.
+fn legacy_four_cc_format(four_cc: &[u8]) -> Option<Format> {
+    Some(match four_cc {
+        b"DXT1" => Format::Bc1RgbaUnorm,
+        b"DXT3" => Format::Bc2Unorm,
+        b"DXT5" => Format::Bc3Unorm,
+        b"ATI1" | b"BC4U" => Format::Bc4Unorm,
+        b"BC4S" => Format::Bc4Snorm,
+        b"ATI2" | b"BC5U" => Format::Bc5Unorm,
+        b"BC5S" => Format::Bc5Snorm,
+        // D3DFMT_A16B16G16R16F and D3DFMT_A32B32G32R32F.
+        [113, 0, 0, 0] => Format::Rgba16Sfloat,
+        [116, 0, 0, 0] => Format::Rgba32Sfloat,
+        _ => return None,
+    })
+}
+
+/// Maps a `DXGI_FORMAT` value to the equivalent `Format`.
+fn dxgi_format_to_format(dxgi_format: u32) -> Option<Format> {
+    use Format::*;
+    Some(match dxgi_format {
+        2 => Rgba32Sfloat,
+        10 => Rgba16Sfloat,
+        26 => B10g11r11Ufloat,
+        28 => Rgba8Unorm,
+        29 => Rgba8Srgb,
+        41 => R32Sfloat,
+        49 => Rg8Unorm,
+        54 => R16Sfloat,
+        61 => R8Unorm,
+        71 => Bc1RgbaUnorm,
+        72 => Bc1RgbaSrgb,
+        74 => Bc2Unorm,
+        75 => Bc2Srgb,
+        77 => Bc3Unorm,
+        78 => Bc3Srgb,
+        80 => Bc4Unorm,
+        81 => Bc4Snorm,
+        83 => Bc5Unorm,
+        84 => Bc5Snorm,
+        87 => Bgra8Unorm,
+        91 => Bgra8Srgb,
+        95 => Bc6hUfloat,
+        96 => Bc6hSfloat,
+        98 => Bc7Unorm,
+        99 => Bc7Srgb,
+        _ => return None,
+    })
+}
+
+/// Maps an OpenGL compressed internal format to the equivalent `Format`.
+fn gl_compressed_format(gl_internal_format: u32) -> Option<Format> {
+    use Format::*;
+    Some(match gl_internal_format {
+        0x83F0 => Bc1RgbUnorm,
+        0x83F1 => Bc1RgbaUnorm,
+        0x83F2 => Bc2Unorm,
+        0x83F3 => Bc3Unorm,
+        0x8C4C => Bc1RgbSrgb,
+        0x8C4D => Bc1RgbaSrgb,
+        0x8C4E => Bc2Srgb,
+        0x8C4F => Bc3Srgb,
+        0x8DBB => Bc4Unorm,
+        0x8DBC => Bc4Snorm,
+        0x8DBD => Bc5Unorm,
+        0x8DBE => Bc5Snorm,
+        0x8E8C => Bc7Unorm,
+        0x8E8D => Bc7Srgb,
+        0x8E8E => Bc6hSfloat,
+        0x8E8F => Bc6hUfloat,
+        0x9270 => EacR11Unorm,
+        0x9271 => EacR11Snorm,
+        0x9272 => EacR11g11Unorm,
+        0x9273 => EacR11g11Snorm,
+        0x9274 => Etc2R8g8b8Unorm,
+        0x9275 => Etc2R8g8b8Srgb,
+        0x9276 => Etc2R8g8b8a1Unorm,
+        0x9277 => Etc2R8g8b8a1Srgb,
+        0x9278 => Etc2R8g8b8a8Unorm,
+        0x9279 => Etc2R8g8b8a8Srgb,
+        0x93B0 => Astc4x4Unorm,
+        0x93B1 => Astc5x4Unorm,
+        0x93B2 => Astc5x5Unorm,
+        0x93B3 => Astc6x5Unorm,
+        0x93B4 => Astc6x6Unorm,
+        0x93B5 => Astc8x5Unorm,
+        0x93B6 => Astc8x6Unorm,
+        0x93B7 => Astc8x8Unorm,
+        0x93B8 => Astc10x5Unorm,
+        0x93B9 => Astc10x6Unorm,
+        0x93BA => Astc10x8Unorm,
+        0x93BB => Astc10x10Unorm,
+        0x93BC => Astc12x10Unorm,
+        0x93BD => Astc12x12Unorm,
+        0x93D0 => Astc4x4Srgb,
+        0x93D1 => Astc5x4Srgb,
+        0x93D2 => Astc5x5Srgb,
+        0x93D3 => Astc6x5Srgb,
+        0x93D4 => Astc6x6Srgb,
+        0x93D5 => Astc8x5Srgb,
+        0x93D6 => Astc8x6Srgb,
+        0x93D7 => Astc8x8Srgb,
+        0x93D8 => Astc10x5Srgb,
+        0x93D9 => Astc10x6Srgb,
+        0x93DA => Astc10x8Srgb,
+        0x93DB => Astc10x10Srgb,
+        0x93DC => Astc12x10Srgb,
+        0x93DD => Astc12x12Srgb,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(words: &[u32]) -> Vec<u8> {
+        words
+            .iter()
+            .flat_map(|word| word.to_le_bytes().to_vec())
+            .collect()
+    }
+
+    fn ktx_file(
+        gl_internal_format: u32,
+        (width, height): (u32, u32),
+        faces: u32,
+        levels: &[&[u8]],
+    ) -> Vec<u8> {
+        let mut bytes = KTX_IDENTIFIER.to_vec();
+        bytes.extend(words(&[
+            KTX_ENDIANNESS,
+            0,
+            1,
+            0,
+            gl_internal_format,
+            0,
+            width,
+            height,
+            0,
+            0,
+            faces,
+            levels.len() as u32,
+            0,
+        ]));
+        for level in levels {
+            bytes.extend(words(&[(level.len() as u32) / faces]));
+            bytes.extend_from_slice(level);
+        }
+        bytes
+    }
+
+    #[test]
+    fn ktx_levels_are_parsed() {
+        // 8x4 BC1 texture: two 8 byte blocks, then one block for each smaller level.
+        let bytes = ktx_file(0x8C4D, (8, 4), 1, &[&[7; 16], &[8; 8], &[9; 8], &[10; 8]]);
+
+        let texture = parse_ktx(&bytes).unwrap();
+
+        assert_eq!(texture.format, Format::Bc1RgbaSrgb);
+        assert_eq!((texture.width, texture.height), (8, 4));
+        assert_eq!((texture.layers, texture.cube), (1, false));
+        assert_eq!(
+            texture.levels,
+            vec![vec![7; 16], vec![8; 8], vec![9; 8], vec![10; 8]]
+        );
+    }
+
+    #[test]
+    fn ktx_with_wrong_level_size_is_rejected() {
+        // A 6x6 texture takes 2x2 ASTC 4x4 blocks of 16 bytes, with the last ones partially used.
+        assert!(parse_ktx(&ktx_file(0x93B0, (6, 6), 1, &[&[0; 16]])).is_err());
+        assert!(parse_ktx(&ktx_file(0x93B0, (6, 6), 1, &[&[0; 64]])).is_ok());
+    }
+
+    #[test]
+    fn ktx_cube_map_faces_are_joined() {
+        let faces = (0..6).flat_map(|face| vec![face; 16]).collect::<Vec<u8>>();
+        let bytes = ktx_file(0x93B0, (4, 4), 6, &[&faces]);
+
+        let texture = parse_ktx(&bytes).unwrap();
+
+        assert_eq!((texture.layers, texture.cube), (6, true));
+        assert_eq!(texture.levels, vec![faces]);
+    }
+
+    #[test]
+    fn ktx2_levels_are_read_from_index() {
+        let mut bytes = KTX2_IDENTIFIER.to_vec();
+        // BC7 sRGB, 4x4 texels, 2 array layers and 2 levels.
+        bytes.extend(words(&[146, 1, 4, 4, 0, 2, 1, 2, 0]));
+        bytes.extend(words(&[0; 8]));
+        // The smaller level is stored first in the file.
+        bytes.extend(words(&[160, 0, 32, 0, 32, 0]));
+        bytes.extend(words(&[128, 0, 32, 0, 32, 0]));
+        bytes.extend(vec![2; 32]);
+        bytes.extend(vec![1; 32]);
+
+        let texture = parse_ktx2(&bytes).unwrap();
+
+        assert_eq!(texture.format, Format::Bc7Srgb);
+        assert_eq!((texture.layers, texture.cube), (2, false));
+        assert_eq!(texture.levels, vec![vec![1; 32], vec![2; 32]]);
+    }
+
+    #[test]
+    fn ktx2_basis_files_are_detected() {
+        let mut bytes = KTX2_IDENTIFIER.to_vec();
+        // Undefined format and BasisLZ supercompression.
+        bytes.extend(words(&[0, 1, 4, 4, 0, 0, 1, 1, 1]));
+        bytes.extend(words(&[104, 28, 0, 0, 0, 0, 0, 0]));
+        bytes.extend(words(&[0; 6]));
+        // Descriptor size, block header, then the ETC1S color model with sRGB transfer.
+        bytes.extend(words(&[28, 0, 0x0018_0002]));
+        bytes.extend_from_slice(&[163, 1, 2, 0]);
+        bytes.extend(words(&[0; 3]));
+
+        assert_eq!(ktx2_basis_srgb(&bytes).unwrap(), Some(true));
+        assert!(parse_ktx2(&bytes).is_err());
+    }
+
+    #[test]
+    fn ktx2_uastc_blocks_are_read_with_alpha() {
+        let mut bytes = KTX2_IDENTIFIER.to_vec();
+        // Undefined format, 8x4 texels, 1 level and no supercompression.
+        bytes.extend(words(&[0, 1, 8, 4, 0, 0, 1, 1, 0]));
+        bytes.extend(words(&[104, 44, 0, 0, 0, 0, 0, 0]));
+        bytes.extend(words(&[148, 0, 32, 0, 32, 0]));
+        // Descriptor size, block header, the UASTC color model and one RGBA sample.
+        bytes.extend(words(&[44, 0, 0x0028_0002]));
+        bytes.extend_from_slice(&[166, 1, 1, 0]);
+        bytes.extend(words(&[0x0303, 0, 0]));
+        bytes.extend(words(&[0x037F_0000, 0, 0, 0]));
+        bytes.extend(vec![7; 32]);
+
+        let (texture, alpha) = parse_ktx2_uastc(&bytes).unwrap();
+
+        assert!(alpha);
+        assert_eq!((texture.width, texture.height, texture.layers), (8, 4, 1));
+        assert_eq!(texture.levels, vec![vec![7; 32]]);
+    }
+
+    #[test]
+    fn ktx2_etc1s_files_are_rejected() {
+        let mut bytes = KTX2_IDENTIFIER.to_vec();
+        bytes.extend(words(&[0, 1, 4, 4, 0, 0, 1, 1, 1]));
+        bytes.extend(words(&[104, 28, 0, 0, 0, 0, 0, 0]));
+        bytes.extend(words(&[0; 6]));
+        bytes.extend(words(&[28, 0, 0x0018_0002]));
+        bytes.extend_from_slice(&[163, 1, 2, 0]);
+        bytes.extend(words(&[0; 3]));
+
+        assert!(parse_ktx2_uastc(&bytes).is_err());
+    }
+
+    #[test]
+    fn dds_layers_are_regrouped_by_level() {
+        let mut header = vec![0; 128];
+        header[..4].copy_from_slice(&DDS_MAGIC);
+        header[8..12].copy_from_slice(&0x2_0000u32.to_le_bytes());
+        header[12..16].copy_from_slice(&8u32.to_le_bytes());
+        header[16..20].copy_from_slice(&8u32.to_le_bytes());
+        header[28..32].copy_from_slice(&2u32.to_le_bytes());
+        header[80..84].copy_from_slice(&0x4u32.to_le_bytes());
+        header[84..88].copy_from_slice(b"DXT5");
+        header[112..116].copy_from_slice(&0xFE00u32.to_le_bytes());
+        let mut bytes = header;
+        for face in 0..6 {
+            bytes.extend(vec![face; 64]);
+            bytes.extend(vec![face + 10; 16]);
+        }
+
+        let texture = parse_dds(&bytes).unwrap();
+
+        assert_eq!(texture.format, Format::Bc3Unorm);
+        assert_eq!((texture.layers, texture.cube), (6, true));
+        assert_eq!(
+            texture.levels[0],
+            (0..6).flat_map(|face| vec![face; 64]).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            texture.levels[1],
+            (10..16).flat_map(|face| vec![face; 16]).collect::<Vec<_>>()
+        );
+    }
+}
@@ -3,10 +3,8 @@ use crate::{
     shape::{FromShape, ShapePrefab},
     types::{Mesh, MeshData},
 };
-use amethyst_assets::{
-    AssetPrefab, AssetStorage, Format, Handle, Loader, PrefabData, ProgressCounter,
-};
-use amethyst_core::ecs::{Entity, Read, ReadExpect, WriteStorage};
+use amethyst_assets::{AssetPrefab, Format, PrefabData, ProgressCounter};
+use amethyst_core::ecs::Entity;
 use amethyst_error::Error;
 use rendy::mesh::MeshBuilder;
 use serde::{Deserialize, Serialize};
@@ -58,11 +56,7 @@ impl<'a, V> PrefabData<'a> for MeshPrefab<V>
 where
     V: FromShape + Into<MeshBuilder<'static>>,
 {
-    type SystemData = (
-        ReadExpect<'a, Loader>,
-        WriteStorage<'a, Handle<Mesh>>,
-        Read<'a, AssetStorage<Mesh>>,
-    );
+    type SystemData = <ShapePrefab<V> as PrefabData<'a>>::SystemData;
     type Result = ();
 
     fn add_to_entity(
@@ -74,7 +68,7 @@ where
     ) -> Result<(), Error> {
         match self {
             MeshPrefab::Asset(m) => {
-                m.add_to_entity(entity, system_data, entities, children)?;
+                m.add_to_entity(entity, &mut system_data.0, entities, children)?;
             }
             MeshPrefab::Shape(s) => {
                 s.add_to_entity(entity, system_data, entities, children)?;
@@ -89,7 +83,7 @@ where
         system_data: &mut Self::SystemData,
     ) -> Result<bool, Error> {
         Ok(match self {
-            MeshPrefab::Asset(m) => m.load_sub_assets(progress, system_data)?,
+            MeshPrefab::Asset(m) => m.load_sub_assets(progress, &mut system_data.0)?,
             MeshPrefab::Shape(s) => s.load_sub_assets(progress, system_data)?,
         })
     }
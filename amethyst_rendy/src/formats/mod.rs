@@ -1,4 +1,5 @@
 //! Pre-defined graphical formats and data provided by amethyst_rendy
+pub(crate) mod container;
 pub mod mesh;
 pub mod mtl;
 pub mod texture;
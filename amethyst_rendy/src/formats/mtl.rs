@@ -2,7 +2,7 @@
 
 use crate::{
     formats::texture::TexturePrefab,
-    mtl::{Material, MaterialDefaults, TextureOffset},
+    mtl::{Material, MaterialDefaults, TextureOffset, TextureTransforms},
     transparent::Transparent,
     types::Texture,
 };
@@ -27,12 +27,22 @@ pub struct MaterialPrefab {
     pub ambient_occlusion: Option<TexturePrefab>,
     /// Cavity map.
     pub cavity: Option<TexturePrefab>,
+    /// Lightmap of externally baked lighting.
+    pub lightmap: Option<TexturePrefab>,
     /// Texture offset.
     pub uv_offset: TextureOffset,
+    /// Transforms of the texture coordinates of each map.
+    pub texture_transforms: TextureTransforms,
     /// Set material as `Transparent`
     pub transparent: bool,
     /// Alpha cutoff: the value below which we do not draw the pixel
     pub alpha_cutoff: f32,
+    /// Multiplier of the emission map.
+    pub emission_strength: f32,
+    /// Draw the material without lighting.
+    pub unlit: bool,
+    /// Multiplier of the lightmap.
+    pub lightmap_intensity: f32,
     /// Clone handle only
     #[serde(skip)]
     handle: Option<Handle<Material>>,
@@ -60,9 +70,14 @@ impl Default for MaterialPrefab {
             metallic_roughness: None,
             ambient_occlusion: None,
             cavity: None,
+            lightmap: None,
             uv_offset: TextureOffset::default(),
+            texture_transforms: TextureTransforms::default(),
             transparent: false,
             alpha_cutoff: std::f32::MIN_POSITIVE,
+            emission_strength: 1.0,
+            unlit: false,
+            lightmap_intensity: 1.0,
             handle: None,
         }
     }
@@ -141,6 +156,11 @@ impl<'a> PrefabData<'a> for MaterialPrefab {
                 ret = true;
             }
         }
+        if let Some(ref mut texture) = self.lightmap {
+            if texture.load_sub_assets(progress, tp_data)? {
+                ret = true;
+            }
+        }
 
         if self.handle.is_none() {
             let mtl = Material {
@@ -157,7 +177,16 @@ impl<'a> PrefabData<'a> for MaterialPrefab {
                 ),
                 cavity: load_handle(&self.cavity, &mat_default.0.cavity),
                 uv_offset: self.uv_offset.clone(),
+                texture_transforms: self.texture_transforms.clone(),
                 alpha_cutoff: self.alpha_cutoff,
+                emission_strength: self.emission_strength,
+                unlit: self.unlit,
+                lightmap: load_handle(&self.lightmap, &mat_default.0.lightmap),
+                lightmap_intensity: if self.lightmap.is_some() {
+                    self.lightmap_intensity
+                } else {
+                    0.0
+                },
             };
 
             self.handle
@@ -1,14 +1,23 @@
 //! Texture formats implementation.
-use crate::types::{Texture, TextureData};
+use super::container::{ktx2_basis_srgb, parse_dds, parse_ktx, parse_ktx2};
+use crate::{
+    color_grading::ColorLut,
+    environment_map::HdrCubemap,
+    transcode::TranscodeSource,
+    types::{Texture, TextureData},
+};
 use amethyst_assets::{
     AssetStorage, Format, Handle, Loader, PrefabData, ProgressCounter, SerializableFormat,
 };
 use amethyst_core::ecs::{Entity, Read, ReadExpect};
-use amethyst_error::Error;
+use amethyst_error::{format_err, Error};
 use rendy::{
+    factory::Factory,
     hal::{
         self,
-        image::{Filter, Kind, Size, ViewKind},
+        format::ImageFeature,
+        image::{Filter, Kind, SamplerInfo, Size, ViewKind, WrapMode},
+        Backend, Features, PhysicalDevice,
     },
     texture::{
         image::{load_from_image, ImageTextureConfig},
@@ -47,16 +56,29 @@ use serde::{Deserialize, Serialize};
 ///        })
 ///        .with_raw_data(handle.pixels, Format::Rgba8Unorm);
 ///
-///    let tex: Handle<Texture> = loader.load_from_data(TextureData(texture_builder), (), &texture_storage);
+///    let tex: Handle<Texture> = loader.load_from_data(TextureData::from(texture_builder), (), &texture_storage);
 /// ```
+///
+/// By default a full mip chain is generated on the GPU when the texture is uploaded, so that
+/// minified textures don't shimmer. Use `with_generate_mips(false)` to opt out, for example for
+/// textures that are always drawn at their own size. Formats deserialized from prefabs start from
+/// the defaults of `ImageTextureConfig` instead, so they need `generate_mips: true`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(transparent)]
 pub struct ImageFormat(pub ImageTextureConfig);
 
+impl ImageFormat {
+    /// Sets whether a mip chain is generated for the texture when it is uploaded.
+    pub fn with_generate_mips(mut self, generate_mips: bool) -> Self {
+        self.0.generate_mips = generate_mips;
+        self
+    }
+}
+
 impl Default for ImageFormat {
     fn default() -> Self {
         use rendy::{
-            hal::image::{Anisotropic, PackedColor, SamplerInfo, WrapMode},
+            hal::image::{Anisotropic, PackedColor},
             texture::image::{Repr, TextureKind},
         };
 
@@ -67,7 +89,7 @@ impl Default for ImageFormat {
             sampler_info: SamplerInfo {
                 min_filter: Filter::Nearest,
                 mag_filter: Filter::Nearest,
-                mip_filter: Filter::Nearest,
+                mip_filter: Filter::Linear,
                 wrap_mode: (WrapMode::Tile, WrapMode::Tile, WrapMode::Tile),
                 lod_bias: 0.0.into(),
                 lod_range: std::ops::Range {
@@ -79,7 +101,7 @@ impl Default for ImageFormat {
                 normalized: true,
                 anisotropic: Anisotropic::Off,
             },
-            generate_mips: false,
+            generate_mips: true,
             premultiply_alpha: true,
         })
     }
@@ -100,6 +122,268 @@ impl Format<TextureData> for ImageFormat {
     }
 }
 
+/// Format converting equirectangular (latitude-longitude) panoramas into cube map textures,
+/// for example to be used with `SkyboxCubemap`.
+///
+/// Radiance HDR images keep their linear radiance, other image formats are assumed to be sRGB.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EquirectFormat {
+    /// Width and height of each cube map face, in texels.
+    pub size: u32,
+}
+
+impl Default for EquirectFormat {
+    fn default() -> Self {
+        EquirectFormat { size: 512 }
+    }
+}
+
+amethyst_assets::register_format!("EQUIRECT", EquirectFormat as TextureData);
+impl Format<TextureData> for EquirectFormat {
+    fn name(&self) -> &'static str {
+        "EQUIRECT"
+    }
+
+    fn import_simple(&self, bytes: Vec<u8>) -> Result<TextureData, Error> {
+        HdrCubemap::decode_equirect(&bytes, self.size).map(|cubemap| cubemap.texture_data())
+    }
+}
+
+/// Format loading color lookup tables in the `.cube` format, for `ColorGrading`.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct CubeLutFormat;
+
+amethyst_assets::register_format!("CUBE_LUT", CubeLutFormat as TextureData);
+impl Format<TextureData> for CubeLutFormat {
+    fn name(&self) -> &'static str {
+        "CUBE_LUT"
+    }
+
+    fn import_simple(&self, bytes: Vec<u8>) -> Result<TextureData, Error> {
+        let text = std::str::from_utf8(&bytes).map_err(|e| amethyst_error::format_err!("{}", e))?;
+        ColorLut::from_cube(text).map(|lut| lut.texture_data())
+    }
+}
+
+/// Format loading color lookup tables from images of a horizontal strip of slices,
+/// e.g. 256x16 or 1024x32 texels, for `ColorGrading`.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct LutStripFormat;
+
+amethyst_assets::register_format!("LUT_STRIP", LutStripFormat as TextureData);
+impl Format<TextureData> for LutStripFormat {
+    fn name(&self) -> &'static str {
+        "LUT_STRIP"
+    }
+
+    fn import_simple(&self, bytes: Vec<u8>) -> Result<TextureData, Error> {
+        ColorLut::from_strip(&bytes).map(|lut| lut.texture_data())
+    }
+}
+
+/// Format loading textures from KTX 1.1 containers holding block-compressed data.
+///
+/// The BCn, ETC2/EAC and ASTC block formats are supported, along with mip chains, cube maps and
+/// array layers. The blocks are uploaded as they are stored in the file, so loading fails when
+/// the backend can't sample the format; use `TextureCompression::is_supported` to pick which
+/// variant of a texture to load.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KtxFormat {
+    /// Sampler of the loaded texture.
+    pub sampler_info: SamplerInfo,
+}
+
+impl Default for KtxFormat {
+    fn default() -> Self {
+        KtxFormat {
+            sampler_info: SamplerInfo::new(Filter::Linear, WrapMode::Tile),
+        }
+    }
+}
+
+amethyst_assets::register_format!("KTX", KtxFormat as TextureData);
+impl Format<TextureData> for KtxFormat {
+    fn name(&self) -> &'static str {
+        "KTX"
+    }
+
+    fn import_simple(&self, bytes: Vec<u8>) -> Result<TextureData, Error> {
+        parse_ktx(&bytes).map(|texture| texture.into_texture_data(self.sampler_info.clone()))
+    }
+}
+
+/// Format loading textures from KTX2 containers, with their mip chains, cube maps and array
+/// layers.
+///
+/// Any format without supercompression is supported, and uploaded as it is stored in the file.
+/// Basis Universal textures are transcoded when they are processed, see the `transcode` module.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Ktx2Format {
+    /// Sampler of the loaded texture.
+    pub sampler_info: SamplerInfo,
+}
+
+impl Default for Ktx2Format {
+    fn default() -> Self {
+        Ktx2Format {
+            sampler_info: SamplerInfo::new(Filter::Linear, WrapMode::Tile),
+        }
+    }
+}
+
+amethyst_assets::register_format!("KTX2", Ktx2Format as TextureData);
+impl Format<TextureData> for Ktx2Format {
+    fn name(&self) -> &'static str {
+        "KTX2"
+    }
+
+    fn import_simple(&self, bytes: Vec<u8>) -> Result<TextureData, Error> {
+        match ktx2_basis_srgb(&bytes)? {
+            Some(srgb) => Ok(TextureData(
+                TextureBuilder::new(),
+                Vec::new(),
+                Some(TranscodeSource {
+                    file: bytes,
+                    srgb,
+                    sampler_info: self.sampler_info.clone(),
+                }),
+            )),
+            None => parse_ktx2(&bytes)
+                .map(|texture| texture.into_texture_data(self.sampler_info.clone())),
+        }
+    }
+}
+
+/// Format loading Basis Universal textures from `.basis` files, transcoded when they are
+/// processed, see the `transcode` module.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BasisFormat {
+    /// Whether the texture holds sRGB colors, which `.basis` files don't record reliably.
+    pub srgb: bool,
+    /// Sampler of the loaded texture.
+    pub sampler_info: SamplerInfo,
+}
+
+impl Default for BasisFormat {
+    fn default() -> Self {
+        BasisFormat {
+            srgb: true,
+            sampler_info: SamplerInfo::new(Filter::Linear, WrapMode::Tile),
+        }
+    }
+}
+
+amethyst_assets::register_format!("BASIS", BasisFormat as TextureData);
+impl Format<TextureData> for BasisFormat {
+    fn name(&self) -> &'static str {
+        "BASIS"
+    }
+
+    fn import_simple(&self, bytes: Vec<u8>) -> Result<TextureData, Error> {
+        if !bytes.starts_with(b"sB") {
+            return Err(format_err!("Not a Basis Universal file"));
+        }
+        Ok(TextureData(
+            TextureBuilder::new(),
+            Vec::new(),
+            Some(TranscodeSource {
+                file: bytes,
+                srgb: self.srgb,
+                sampler_info: self.sampler_info.clone(),
+            }),
+        ))
+    }
+}
+
+/// Format loading textures from DDS containers, with their mip chains, cube maps and array
+/// layers.
+///
+/// The BCn formats and the common 8 bit, 16 bit float and 32 bit float color formats are
+/// supported. Files without the DX10 header extension carry no color space, so their color is
+/// loaded as linear.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DdsFormat {
+    /// Sampler of the loaded texture.
+    pub sampler_info: SamplerInfo,
+}
+
+impl Default for DdsFormat {
+    fn default() -> Self {
+        DdsFormat {
+            sampler_info: SamplerInfo::new(Filter::Linear, WrapMode::Tile),
+        }
+    }
+}
+
+amethyst_assets::register_format!("DDS", DdsFormat as TextureData);
+impl Format<TextureData> for DdsFormat {
+    fn name(&self) -> &'static str {
+        "DDS"
+    }
+
+    fn import_simple(&self, bytes: Vec<u8>) -> Result<TextureData, Error> {
+        parse_dds(&bytes).map(|texture| texture.into_texture_data(self.sampler_info.clone()))
+    }
+}
+
+/// Families of block-compressed texture formats, which backends support independently of each
+/// other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TextureCompression {
+    /// BC1 to BC7, also known as S3TC, RGTC and BPTC. Commonly supported on desktop GPUs.
+    Bc,
+    /// ETC2 and EAC. Commonly supported on mobile GPUs.
+    Etc2,
+    /// ASTC with low dynamic range. Supported on recent mobile GPUs.
+    Astc,
+}
+
+impl TextureCompression {
+    /// Returns the family of a block-compressed format, or `None` for uncompressed formats.
+    pub fn of(format: hal::format::Format) -> Option<Self> {
+        use hal::format::Format::*;
+        match format {
+            Bc1RgbUnorm | Bc1RgbSrgb | Bc1RgbaUnorm | Bc1RgbaSrgb | Bc2Unorm | Bc2Srgb
+            | Bc3Unorm | Bc3Srgb | Bc4Unorm | Bc4Snorm | Bc5Unorm | Bc5Snorm | Bc6hUfloat
+            | Bc6hSfloat | Bc7Unorm | Bc7Srgb => Some(TextureCompression::Bc),
+            Etc2R8g8b8Unorm | Etc2R8g8b8Srgb | Etc2R8g8b8a1Unorm | Etc2R8g8b8a1Srgb
+            | Etc2R8g8b8a8Unorm | Etc2R8g8b8a8Srgb | EacR11Unorm | EacR11Snorm | EacR11g11Unorm
+            | EacR11g11Snorm => Some(TextureCompression::Etc2),
+            // The remaining compressed formats are all ASTC.
+            format if format.surface_desc().is_compressed() => Some(TextureCompression::Astc),
+            _ => None,
+        }
+    }
+
+    /// Checks whether the backend of `factory` can sample textures of this family.
+    pub fn is_supported<B: Backend>(self, factory: &Factory<B>) -> bool {
+        let feature = match self {
+            TextureCompression::Bc => Features::FORMAT_BC,
+            TextureCompression::Etc2 => Features::FORMAT_ETC2,
+            TextureCompression::Astc => Features::FORMAT_ASTC_LDR,
+        };
+        factory.physical().features().contains(feature)
+    }
+}
+
+/// Checks whether the backend of `factory` can sample textures of `format` with optimal tiling,
+/// which is how textures are created.
+pub fn supports_texture_format<B: Backend>(
+    factory: &Factory<B>,
+    format: hal::format::Format,
+) -> bool {
+    factory
+        .physical()
+        .format_properties(Some(format))
+        .optimal_tiling
+        .contains(ImageFeature::SAMPLED)
+}
+
 /// `PrefabData` for loading `Texture`s.
 ///
 /// Will not add any `Component`s to the `Entity`, will only return a `Handle`
@@ -0,0 +1,41 @@
+//! FXAA anti-aliasing configuration.
+
+use amethyst_core::ecs::{Read, SystemData, World};
+
+/// Resource configuring the anti-aliasing applied by `RenderFxaa`.
+///
+/// All fields are read every frame, so FXAA can be toggled and tuned without rebuilding
+/// the render graph. While disabled, the pass copies its input unchanged.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FxaaParams {
+    /// Smooth detected edges.
+    pub enabled: bool,
+    /// Minimum local contrast, relative to the brightest neighbour, for a pixel to be
+    /// treated as an edge. Lower values smooth more edges at the cost of blurring texture detail.
+    pub edge_threshold: f32,
+    /// Minimum absolute local contrast for a pixel to be treated as an edge,
+    /// avoiding processing of dark areas.
+    pub edge_threshold_min: f32,
+    /// Amount of sub-pixel aliasing removal, between 0 and 1.
+    pub subpixel: f32,
+}
+
+impl Default for FxaaParams {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            edge_threshold: 0.166,
+            edge_threshold_min: 0.0833,
+            subpixel: 0.75,
+        }
+    }
+}
+
+impl FxaaParams {
+    /// Returns the `FxaaParams` resource, or default parameters if there is none.
+    pub fn fetch(world: &World) -> Self {
+        <Option<Read<'_, FxaaParams>>>::fetch(world)
+            .map(|p| *p)
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,532 @@
+//! Interactive transform gizmo for moving, rotating and scaling entities with the mouse.
+//!
+//! The handles are drawn by [DrawGizmo](crate::pass::DrawGizmo) for the entity selected in the
+//! [Gizmo] resource. With the `gizmo` feature enabled, `GizmoSystem` picks the handles with the
+//! mouse and applies the drag to the selected entity's `Transform`.
+
+use crate::debug_drawing::DebugLinesComponent;
+use amethyst_core::{
+    ecs::Entity,
+    geometry::Ray,
+    math::{Matrix4, Point3, Vector3},
+};
+use palette::Srgba;
+
+/// Number of segments used for rotation rings.
+const RING_POINTS: u32 = 48;
+
+/// Kind of transformation applied by the gizmo handles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GizmoMode {
+    /// Arrows moving the entity along the world axes.
+    Translate,
+    /// Rings rotating the entity around the world axes.
+    Rotate,
+    /// Handles scaling the entity along the world axes.
+    Scale,
+}
+
+impl Default for GizmoMode {
+    fn default() -> Self {
+        GizmoMode::Translate
+    }
+}
+
+/// One of the world axes a gizmo handle operates on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GizmoAxis {
+    /// X axis, drawn in red.
+    X,
+    /// Y axis, drawn in green.
+    Y,
+    /// Z axis, drawn in blue.
+    Z,
+}
+
+impl GizmoAxis {
+    /// All axes in handle order.
+    pub const ALL: [GizmoAxis; 3] = [GizmoAxis::X, GizmoAxis::Y, GizmoAxis::Z];
+
+    /// Unit vector of this axis.
+    pub fn direction(self) -> Vector3<f32> {
+        match self {
+            GizmoAxis::X => Vector3::x(),
+            GizmoAxis::Y => Vector3::y(),
+            GizmoAxis::Z => Vector3::z(),
+        }
+    }
+
+    fn index(self) -> usize {
+        match self {
+            GizmoAxis::X => 0,
+            GizmoAxis::Y => 1,
+            GizmoAxis::Z => 2,
+        }
+    }
+
+    fn color(self) -> Srgba {
+        match self {
+            GizmoAxis::X => Srgba::new(0.9, 0.2, 0.2, 1.0),
+            GizmoAxis::Y => Srgba::new(0.2, 0.9, 0.2, 1.0),
+            GizmoAxis::Z => Srgba::new(0.2, 0.4, 0.9, 1.0),
+        }
+    }
+}
+
+/// Resource controlling the transform gizmo.
+#[derive(Debug, Clone)]
+pub struct Gizmo {
+    /// Entity whose `Transform` is edited. No handles are drawn when `None`.
+    pub target: Option<Entity>,
+    /// Kind of handles to show.
+    pub mode: GizmoMode,
+    /// Length of the handles as a fraction of the distance to the camera,
+    /// which keeps them the same size on screen.
+    pub size: f32,
+    pub(crate) hovered: Option<GizmoAxis>,
+    pub(crate) dragged: Option<GizmoAxis>,
+}
+
+impl Default for Gizmo {
+    fn default() -> Self {
+        Self {
+            target: None,
+            mode: GizmoMode::default(),
+            size: 0.15,
+            hovered: None,
+            dragged: None,
+        }
+    }
+}
+
+impl Gizmo {
+    /// Creates a gizmo without a target.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Selects the entity to edit, or clears the selection.
+    pub fn select(&mut self, target: Option<Entity>) {
+        if self.target != target {
+            self.hovered = None;
+            self.dragged = None;
+        }
+        self.target = target;
+    }
+
+    /// Returns the axis currently being dragged, if any.
+    pub fn dragged(&self) -> Option<GizmoAxis> {
+        self.dragged
+    }
+
+    /// Returns the axis under the mouse cursor, if any.
+    pub fn hovered(&self) -> Option<GizmoAxis> {
+        self.hovered
+    }
+
+    /// Builds the handle lines for a gizmo centered at `center` with handles of length `length`.
+    pub(crate) fn handle_lines(&self, center: Point3<f32>, length: f32) -> DebugLinesComponent {
+        let mut lines = DebugLinesComponent::with_capacity(64).with_always_on_top();
+        let highlighted = self.dragged.or(self.hovered);
+
+        for &axis in &GizmoAxis::ALL {
+            let (color, width) = if highlighted == Some(axis) {
+                (Srgba::new(1.0, 0.9, 0.1, 1.0), 3.0)
+            } else {
+                (axis.color(), 2.0)
+            };
+            let dir = axis.direction();
+            let tip = center + dir * length;
+            let handle = length * 0.08;
+
+            match self.mode {
+                GizmoMode::Translate => {
+                    lines.add_line_with_width(center, tip, color, width);
+                    // Arrow head as a small cone of lines
+                    let base = center + dir * (length - handle * 2.0);
+                    lines.add_circle(base, dir, handle, 8, color);
+                    let (u, v) = (dir.yzx(), dir.zxy());
+                    for offset in &[u, -u, v, -v] {
+                        lines.add_line(base + offset * handle, tip, color);
+                    }
+                }
+                GizmoMode::Rotate => {
+                    lines.add_circle(center, dir, length, RING_POINTS, color);
+                }
+                GizmoMode::Scale => {
+                    lines.add_line_with_width(center, tip, color, width);
+                    let half = Vector3::repeat(handle);
+                    lines.add_box(tip - half, tip + half, color);
+                }
+            }
+        }
+
+        lines
+    }
+}
+
+/// Returns the distance from the ray at which it passes closest to the given axis line,
+/// together with the parameter of the closest point on the axis.
+/// Returns `None` when the ray is parallel to the axis.
+pub(crate) fn closest_on_axis(
+    ray: &Ray<f32>,
+    origin: &Point3<f32>,
+    axis: &Vector3<f32>,
+) -> Option<(f32, f32)> {
+    let w0 = ray.origin - origin;
+    let b = ray.direction.dot(axis);
+    let d = ray.direction.dot(&w0);
+    let e = axis.dot(&w0);
+    let denom = 1.0 - b * b;
+    if denom.abs() < 1e-6 {
+        return None;
+    }
+    let ray_t = (b * e - d) / denom;
+    let axis_t = (e - b * d) / denom;
+    let ray_point = ray.origin + ray.direction * ray_t.max(0.0);
+    let axis_point = origin + axis * axis_t;
+    Some(((ray_point - axis_point).norm(), axis_t))
+}
+
+/// Intersects the ray with the plane through `origin` perpendicular to `normal`.
+pub(crate) fn intersect_plane(
+    ray: &Ray<f32>,
+    origin: &Point3<f32>,
+    normal: &Vector3<f32>,
+) -> Option<Point3<f32>> {
+    let denom = ray.direction.dot(normal);
+    if denom.abs() < 1e-6 {
+        return None;
+    }
+    let t = (origin - ray.origin).dot(normal) / denom;
+    if t < 0.0 {
+        None
+    } else {
+        Some(ray.origin + ray.direction * t)
+    }
+}
+
+/// Angle of `point` around `normal` in the plane through `origin`.
+pub(crate) fn angle_around(
+    point: &Point3<f32>,
+    origin: &Point3<f32>,
+    normal: &Vector3<f32>,
+) -> f32 {
+    let (u, v) = (normal.yzx(), normal.zxy());
+    let offset = point - origin;
+    offset.dot(&v).atan2(offset.dot(&u))
+}
+
+/// Returns the matrix converting world space vectors into the space of the entity's parent,
+/// given its local and global matrices. Returns `None` when the global matrix is singular.
+pub(crate) fn world_to_parent(local: &Matrix4<f32>, global: &Matrix4<f32>) -> Option<Matrix4<f32>> {
+    global
+        .try_inverse()
+        .map(|world_to_local| local * world_to_local)
+}
+
+/// Finds the handle of `mode` hit by the ray, if any.
+pub(crate) fn pick_axis(
+    mode: GizmoMode,
+    ray: &Ray<f32>,
+    center: &Point3<f32>,
+    length: f32,
+) -> Option<GizmoAxis> {
+    let threshold = length * 0.1;
+    let mut best: Option<(GizmoAxis, f32)> = None;
+
+    for &axis in &GizmoAxis::ALL {
+        let dir = axis.direction();
+        let distance = match mode {
+            GizmoMode::Translate | GizmoMode::Scale => closest_on_axis(ray, center, &dir)
+                .filter(|&(_, t)| t >= 0.0 && t <= length)
+                .map(|(distance, _)| distance),
+            GizmoMode::Rotate => {
+                intersect_plane(ray, center, &dir).map(|hit| ((hit - center).norm() - length).abs())
+            }
+        };
+
+        if let Some(distance) = distance.filter(|&d| d < threshold) {
+            if best.map_or(true, |(_, d)| distance < d) {
+                best = Some((axis, distance));
+            }
+        }
+    }
+
+    best.map(|(axis, _)| axis)
+}
+
+#[cfg(feature = "gizmo")]
+pub use self::system::GizmoSystem;
+
+#[cfg(feature = "gizmo")]
+mod system {
+    use super::*;
+    use crate::camera::{ActiveCamera, Camera};
+    use amethyst_core::{
+        ecs::{Join, Read, ReadExpect, ReadStorage, System, Write, WriteStorage},
+        math::{Point2, Unit, UnitQuaternion, Vector2},
+        Transform,
+    };
+    use amethyst_input::{BindingTypes, InputHandler};
+    use amethyst_window::ScreenDimensions;
+    use std::marker::PhantomData;
+    use winit::MouseButton;
+
+    #[derive(Debug, Clone)]
+    struct DragStart {
+        transform: Transform,
+        world_to_parent: Matrix4<f32>,
+        center: Point3<f32>,
+        param: f32,
+    }
+
+    /// Picks gizmo handles with the left mouse button and applies the drag to the selected
+    /// entity's `Transform`. Handles operate on world axes, which are converted into the space of
+    /// the target's parent.
+    #[derive(Debug)]
+    pub struct GizmoSystem<T: BindingTypes> {
+        drag: Option<DragStart>,
+        was_down: bool,
+        marker: PhantomData<T>,
+    }
+
+    impl<T: BindingTypes> GizmoSystem<T> {
+        /// Create new gizmo system.
+        pub fn new() -> Self {
+            Self {
+                drag: None,
+                was_down: false,
+                marker: PhantomData,
+            }
+        }
+    }
+
+    impl<'a, T: BindingTypes> System<'a> for GizmoSystem<T> {
+        #[allow(clippy::type_complexity)]
+        type SystemData = (
+            Write<'a, Gizmo>,
+            Read<'a, InputHandler<T>>,
+            ReadExpect<'a, ScreenDimensions>,
+            Read<'a, ActiveCamera>,
+            ReadStorage<'a, Camera>,
+            WriteStorage<'a, Transform>,
+        );
+
+        fn run(
+            &mut self,
+            (mut gizmo, input, dimensions, active_camera, cameras, mut transforms): Self::SystemData,
+        ) {
+            let down = input.mouse_button_is_down(MouseButton::Left);
+            let pressed = down && !self.was_down;
+            self.was_down = down;
+
+            if !down {
+                self.drag = None;
+                gizmo.dragged = None;
+            }
+
+            let target = match gizmo.target {
+                Some(target) if transforms.contains(target) => target,
+                _ => {
+                    gizmo.hovered = None;
+                    gizmo.dragged = None;
+                    return;
+                }
+            };
+
+            let camera = active_camera
+                .entity
+                .and_then(|e| Some((cameras.get(e)?, transforms.get(e)?.clone())))
+                .or_else(|| {
+                    (&cameras, &transforms)
+                        .join()
+                        .next()
+                        .map(|(c, t)| (c, t.clone()))
+                });
+            let (camera, camera_transform) = match camera {
+                Some(camera) => camera,
+                None => return,
+            };
+            let mouse = match input.mouse_position() {
+                Some((x, y)) => Point2::new(x, y),
+                None => return,
+            };
+
+            let ray = camera.screen_ray(
+                mouse,
+                Vector2::new(dimensions.width(), dimensions.height()),
+                &camera_transform,
+            );
+            let camera_position = Point3::from(camera_transform.global_matrix().column(3).xyz());
+            let center = Point3::from(
+                transforms
+                    .get(target)
+                    .unwrap()
+                    .global_matrix()
+                    .column(3)
+                    .xyz(),
+            );
+            let length = (center - camera_position).norm() * gizmo.size;
+            let mode = gizmo.mode;
+
+            if gizmo.dragged.is_none() {
+                gizmo.hovered = pick_axis(mode, &ray, &center, length);
+                if let (true, Some(axis)) = (pressed, gizmo.hovered) {
+                    let transform = transforms.get(target).unwrap();
+                    let world_to_parent =
+                        world_to_parent(&transform.matrix(), transform.global_matrix());
+                    let param = drag_param(mode, &ray, &center, axis);
+                    if let (Some(world_to_parent), Some(param)) = (world_to_parent, param) {
+                        gizmo.dragged = Some(axis);
+                        self.drag = Some(DragStart {
+                            transform: transform.clone(),
+                            world_to_parent,
+                            center,
+                            param,
+                        });
+                    }
+                }
+                return;
+            }
+
+            let axis = gizmo.dragged.unwrap();
+            let start = match &self.drag {
+                Some(start) => start,
+                None => return,
+            };
+            // Measured from where the drag started, as the target moves with translation.
+            let param = match drag_param(mode, &ray, &start.center, axis) {
+                Some(param) => param,
+                None => return,
+            };
+            let transform = transforms.get_mut(target).unwrap();
+            let dir = axis.direction();
+
+            match mode {
+                GizmoMode::Translate => {
+                    let delta = start
+                        .world_to_parent
+                        .transform_vector(&(dir * (param - start.param)));
+                    transform.set_translation(start.transform.translation() + delta);
+                }
+                GizmoMode::Rotate => {
+                    let parent_axis = start.world_to_parent.transform_vector(&dir);
+                    if let Some(parent_axis) = Unit::try_new(parent_axis, std::f32::EPSILON) {
+                        let delta =
+                            UnitQuaternion::from_axis_angle(&parent_axis, param - start.param);
+                        transform.set_rotation(delta * start.transform.rotation());
+                    }
+                }
+                GizmoMode::Scale => {
+                    if start.param.abs() > std::f32::EPSILON {
+                        let mut scale = *start.transform.scale();
+                        scale[axis.index()] *= param / start.param;
+                        transform.set_scale(scale);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Position along the axis for linear handles, or angle around it for rotation rings.
+    fn drag_param(
+        mode: GizmoMode,
+        ray: &Ray<f32>,
+        center: &Point3<f32>,
+        axis: GizmoAxis,
+    ) -> Option<f32> {
+        let dir = axis.direction();
+        match mode {
+            GizmoMode::Translate | GizmoMode::Scale => {
+                closest_on_axis(ray, center, &dir).map(|(_, t)| t)
+            }
+            GizmoMode::Rotate => {
+                intersect_plane(ray, center, &dir).map(|hit| angle_around(&hit, center, &dir))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    fn ray(origin: [f32; 3], direction: [f32; 3]) -> Ray<f32> {
+        Ray {
+            origin: Point3::from(origin),
+            direction: Vector3::from(direction).normalize(),
+        }
+    }
+
+    #[test]
+    fn closest_on_axis_finds_crossing() {
+        let crossing = ray([2.0, 0.0, 5.0], [0.0, 0.0, -1.0]);
+        let (distance, t) = closest_on_axis(&crossing, &Point3::origin(), &Vector3::x()).unwrap();
+        assert_relative_eq!(distance, 0.0);
+        assert_relative_eq!(t, 2.0);
+
+        let parallel = ray([0.0, 1.0, 0.0], [1.0, 0.0, 0.0]);
+        assert!(closest_on_axis(&parallel, &Point3::origin(), &Vector3::x()).is_none());
+    }
+
+    #[test]
+    fn pick_translate_handles() {
+        let center = Point3::origin();
+        let hit_y = ray([0.0, 0.5, 5.0], [0.0, 0.0, -1.0]);
+        assert_eq!(
+            pick_axis(GizmoMode::Translate, &hit_y, &center, 1.0),
+            Some(GizmoAxis::Y)
+        );
+
+        let beyond_tip = ray([0.0, 1.5, 5.0], [0.0, 0.0, -1.0]);
+        assert_eq!(
+            pick_axis(GizmoMode::Translate, &beyond_tip, &center, 1.0),
+            None
+        );
+    }
+
+    #[test]
+    fn pick_rotate_ring() {
+        let center = Point3::origin();
+        let on_z_ring = ray([1.0, 0.0, 5.0], [0.0, 0.0, -1.0]);
+        assert_eq!(
+            pick_axis(GizmoMode::Rotate, &on_z_ring, &center, 1.0),
+            Some(GizmoAxis::Z)
+        );
+
+        let inside = ray([0.5, 0.1, 5.0], [0.0, 0.0, -1.0]);
+        assert_eq!(pick_axis(GizmoMode::Rotate, &inside, &center, 1.0), None);
+    }
+
+    #[test]
+    fn world_to_parent_undoes_parent_transform() {
+        use amethyst_core::math::{Translation3, UnitQuaternion};
+
+        let parent = Translation3::new(0.0, 5.0, 0.0).to_homogeneous()
+            * UnitQuaternion::from_axis_angle(&Vector3::z_axis(), std::f32::consts::FRAC_PI_2)
+                .to_homogeneous()
+            * Matrix4::new_scaling(2.0);
+        let local = Translation3::new(1.0, 0.0, 0.0).to_homogeneous();
+        let global = parent * local;
+
+        let world_to_parent = world_to_parent(&local, &global).unwrap();
+        let delta = world_to_parent.transform_vector(&Vector3::new(2.0, 0.0, 0.0));
+        assert_relative_eq!(delta, Vector3::new(0.0, -1.0, 0.0), epsilon = 1e-5);
+
+        // Moving by the converted delta locally moves the entity along the world axis.
+        let moved = parent * Translation3::from(Vector3::x() + delta).to_homogeneous();
+        let world_delta = moved.column(3).xyz() - global.column(3).xyz();
+        assert_relative_eq!(world_delta, Vector3::new(2.0, 0.0, 0.0), epsilon = 1e-5);
+    }
+
+    #[test]
+    fn angle_around_axis() {
+        let origin = Point3::origin();
+        let z = Vector3::z();
+        let a = angle_around(&Point3::new(1.0, 0.0, 0.0), &origin, &z);
+        let b = angle_around(&Point3::new(0.0, 1.0, 0.0), &origin, &z);
+        assert_relative_eq!((b - a).abs(), std::f32::consts::FRAC_PI_2, epsilon = 1e-5);
+    }
+}
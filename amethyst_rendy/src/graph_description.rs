@@ -0,0 +1,309 @@
+//! Description of the render graph built by [RenderingBundle](crate::RenderingBundle),
+//! for inspecting it with graph visualization tools.
+
+use amethyst_error::Error;
+use rendy::{
+    graph::{BufferAccess, ImageAccess},
+    hal,
+};
+use serde::Serialize;
+use std::fmt::Write;
+
+/// Resource describing the nodes of the current render graph, the images and buffers they
+/// access, and their dependencies. Updated by the `RenderingSystem` every time the graph
+/// is built.
+///
+/// Only graphs planned by [RenderingBundle](crate::RenderingBundle) are described, nodes and
+/// resources are indexed in the order they were added to the graph.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct RenderGraphDescription {
+    /// Nodes of the graph.
+    pub nodes: Vec<NodeDescription>,
+    /// Images managed by the graph.
+    pub images: Vec<ImageDescription>,
+    /// Buffers managed by the graph.
+    pub buffers: Vec<BufferDescription>,
+}
+
+/// Description of a render graph node.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NodeDescription {
+    /// Render target of render pass nodes, or the type of other nodes.
+    pub name: String,
+    /// Images accessed by the node.
+    pub images: Vec<ResourceAccessDescription>,
+    /// Buffers accessed by the node.
+    pub buffers: Vec<ResourceAccessDescription>,
+    /// Indices of the nodes this node explicitly depends on.
+    pub dependencies: Vec<usize>,
+}
+
+/// Description of a node access to an image or buffer.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ResourceAccessDescription {
+    /// Index of the image or buffer.
+    pub index: usize,
+    /// Whether the node writes to the resource.
+    pub write: bool,
+    /// Access flags.
+    pub access: String,
+    /// Image layout the node uses, `None` for buffers.
+    pub layout: Option<String>,
+    /// Pipeline stages accessing the resource.
+    pub stages: String,
+}
+
+/// Description of an image managed by the render graph.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ImageDescription {
+    /// Width in pixels.
+    pub width: u32,
+    /// Height in pixels.
+    pub height: u32,
+    /// Number of layers.
+    pub layers: u16,
+    /// Number of mipmap levels.
+    pub levels: u8,
+    /// Pixel format.
+    pub format: String,
+    /// Whether the image is cleared every frame.
+    pub clear: bool,
+}
+
+/// Description of a buffer managed by the render graph.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BufferDescription {
+    /// Size in bytes.
+    pub size: u64,
+}
+
+impl ResourceAccessDescription {
+    pub(crate) fn image(index: usize, access: &ImageAccess) -> Self {
+        use hal::image::Access;
+        Self {
+            index,
+            write: access.access.intersects(
+                Access::COLOR_ATTACHMENT_WRITE
+                    | Access::DEPTH_STENCIL_ATTACHMENT_WRITE
+                    | Access::SHADER_WRITE
+                    | Access::TRANSFER_WRITE
+                    | Access::HOST_WRITE
+                    | Access::MEMORY_WRITE,
+            ),
+            access: format!("{:?}", access.access),
+            layout: Some(format!("{:?}", access.layout)),
+            stages: format!("{:?}", access.stages),
+        }
+    }
+
+    pub(crate) fn buffer(index: usize, access: &BufferAccess) -> Self {
+        use hal::buffer::Access;
+        Self {
+            index,
+            write: access.access.intersects(
+                Access::SHADER_WRITE
+                    | Access::TRANSFER_WRITE
+                    | Access::HOST_WRITE
+                    | Access::MEMORY_WRITE,
+            ),
+            access: format!("{:?}", access.access),
+            layout: None,
+            stages: format!("{:?}", access.stages),
+        }
+    }
+}
+
+impl ImageDescription {
+    pub(crate) fn new(
+        kind: hal::image::Kind,
+        levels: hal::image::Level,
+        format: hal::format::Format,
+        clear: bool,
+    ) -> Self {
+        let extent = kind.extent();
+        Self {
+            width: extent.width,
+            height: extent.height,
+            layers: kind.num_layers(),
+            levels,
+            format: format!("{:?}", format),
+            clear,
+        }
+    }
+}
+
+impl RenderGraphDescription {
+    /// Render the graph in the DOT language of Graphviz.
+    ///
+    /// Nodes are boxes, images and buffers are ellipses connected to the nodes reading
+    /// and writing them, and explicit dependencies are dashed edges.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph render_graph {\n");
+        for (i, image) in self.images.iter().enumerate() {
+            writeln!(
+                dot,
+                "    image{} [shape=ellipse, label=\"image {}\\n{}x{} {}\"];",
+                i, i, image.width, image.height, image.format
+            )
+            .unwrap();
+        }
+        for (i, buffer) in self.buffers.iter().enumerate() {
+            writeln!(
+                dot,
+                "    buffer{} [shape=ellipse, label=\"buffer {}\\n{} bytes\"];",
+                i, i, buffer.size
+            )
+            .unwrap();
+        }
+        for (i, node) in self.nodes.iter().enumerate() {
+            writeln!(
+                dot,
+                "    node{} [shape=box, label=\"{}\"];",
+                i,
+                node.name.replace('"', "\\\"")
+            )
+            .unwrap();
+            let resources = node
+                .images
+                .iter()
+                .map(|access| ("image", access))
+                .chain(node.buffers.iter().map(|access| ("buffer", access)));
+            for (kind, access) in resources {
+                let (from, to) = if access.write {
+                    (format!("node{}", i), format!("{}{}", kind, access.index))
+                } else {
+                    (format!("{}{}", kind, access.index), format!("node{}", i))
+                };
+                writeln!(
+                    dot,
+                    "    {} -> {} [label=\"{}\"];",
+                    from,
+                    to,
+                    access.layout.as_ref().unwrap_or(&access.access)
+                )
+                .unwrap();
+            }
+            for dependency in &node.dependencies {
+                writeln!(dot, "    node{} -> node{} [style=dashed];", dependency, i).unwrap();
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Serialize the graph to JSON.
+    pub fn to_json(&self) -> Result<String, Error> {
+        serde_json::to_string_pretty(self).map_err(Error::new)
+    }
+}
+
+/// Name of the node type `N`, without module paths. Nodes built from a `NodeDesc` are
+/// named after the description.
+pub(crate) fn node_type_name<N>() -> String {
+    let full = std::any::type_name::<N>();
+    let mut name = String::new();
+    let mut ident = String::new();
+    for c in full.chars() {
+        match c {
+            ':' => ident.clear(),
+            c if c.is_alphanumeric() || c == '_' => ident.push(c),
+            c => {
+                name.push_str(&ident);
+                ident.clear();
+                name.push(c);
+            }
+        }
+    }
+    name.push_str(&ident);
+
+    let args = match name
+        .strip_prefix("DescBuilder<")
+        .and_then(|args| args.strip_suffix('>'))
+    {
+        Some(args) => args,
+        None => return name,
+    };
+    // The description is the last generic argument.
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in args.char_indices() {
+        match c {
+            '<' | '(' | '[' => depth += 1,
+            '>' | ')' | ']' => depth -= 1,
+            ',' if depth == 0 => start = i + 1,
+            _ => {}
+        }
+    }
+    args[start..].trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Mirrors the generic parameters of rendy's builder of nodes from descriptions.
+    #[allow(dead_code)]
+    struct DescBuilder<B, T, N>(B, T, N);
+
+    #[test]
+    fn node_type_names_skip_paths() {
+        assert_eq!(
+            node_type_name::<std::collections::HashMap<u32, std::string::String>>(),
+            "HashMap<u32, String>"
+        );
+        assert_eq!(
+            node_type_name::<
+                DescBuilder<u8, amethyst_core::ecs::World, crate::pass::ScreenshotReadbackDesc>,
+            >(),
+            "ScreenshotReadbackDesc"
+        );
+        assert_eq!(
+            node_type_name::<DescBuilder<u8, (u16, u32), Vec<(u8, u16)>>>(),
+            "Vec<(u8, u16)>"
+        );
+    }
+
+    #[test]
+    fn dot_connects_nodes_through_resources() {
+        let access = |index, write| ResourceAccessDescription {
+            index,
+            write,
+            access: String::new(),
+            layout: Some("General".into()),
+            stages: String::new(),
+        };
+        let description = RenderGraphDescription {
+            nodes: vec![
+                NodeDescription {
+                    name: "Main".into(),
+                    images: vec![access(0, true)],
+                    buffers: vec![],
+                    dependencies: vec![],
+                },
+                NodeDescription {
+                    name: "Readback".into(),
+                    images: vec![access(0, false)],
+                    buffers: vec![],
+                    dependencies: vec![0],
+                },
+            ],
+            images: vec![ImageDescription::new(
+                hal::image::Kind::D2(4, 2, 1, 1),
+                1,
+                hal::format::Format::Rgba8Srgb,
+                true,
+            )],
+            buffers: vec![],
+        };
+
+        let dot = description.to_dot();
+        assert!(dot.contains("image0 [shape=ellipse, label=\"image 0\\n4x2 Rgba8Srgb\"];"));
+        assert!(dot.contains("node0 -> image0 [label=\"General\"];"));
+        assert!(dot.contains("image0 -> node1 [label=\"General\"];"));
+        assert!(dot.contains("node0 -> node1 [style=dashed];"));
+        assert!(description
+            .to_json()
+            .unwrap()
+            .contains("\"name\": \"Readback\""));
+    }
+}
@@ -21,17 +21,28 @@
 //!
 //! * [`RenderingSystem`](crate::system::RenderingSystem)
 //! * [`VisibilitySortingSystem`](crate::visibility::VisibilitySortingSystem)
+//! * [`MeshUpdateSystem`](crate::dynamic_mesh::MeshUpdateSystem)
 //! * [`SpriteVisibilitySortingSystem`](crate::sprite_visibility::SpriteVisibilitySortingSystem)
+//! * [`LodSelectionSystem`](crate::lod::LodSelectionSystem)
+//! * [`ReflectionProbeSystem`](crate::reflection_probe::ReflectionProbeSystem)
+//! * [`TextureStreamingSystem`](crate::texture_streaming::TextureStreamingSystem)
+//! * [`WaterReflectionSystem`](crate::water::WaterReflectionSystem)
 //!
 //! ## Components
 //!
 //! * [`Camera`](camera::Camera)
 //! * [`SpriteVisibility`](sprite_visibility::SpriteVisibility)
+//! * [`SpriteOrder`](sprite_visibility::SpriteOrder)
 //! * [`Visibility`](visibility::Visibility)
 //! * [`BoundingSphere`](visibility::BoundingSphere)
+//! * [`BoundingBox`](visibility::BoundingBox)
+//! * [`LodGroup`](lod::LodGroup)
+//! * [`ReflectionProbe`](reflection_probe::ReflectionProbe)
 //! * [`DebugLinesComponent`](debug_drawing::DebugLinesComponent)
+//! * [`VertexNormalsDebug`](debug_drawing::VertexNormalsDebug)
 //! * [`Light`](light::Light)
 //! * [`Tint`](resources::Tint)
+//! * [`ScissorRect`](resources::ScissorRect)
 //! * [`JointTransforms`](skinning::JointTransforms)
 //! * [`SpriteRender`](sprite::SpriteRender)
 
@@ -44,6 +55,7 @@
 #![warn(clippy::all)]
 #![allow(clippy::new_without_default)]
 #![allow(unused_variables, dead_code)]
+#![recursion_limit = "256"]
 
 #[macro_use]
 extern crate amethyst_derive;
@@ -58,27 +70,69 @@ pub use rendy;
 
 pub mod pass;
 
+pub mod atlas;
 pub mod batch;
+pub mod billboard;
+pub mod bloom;
 pub mod bundle;
 pub mod camera;
+pub mod chromatic_aberration;
+pub mod color_grading;
+pub mod custom_attribute;
 pub mod debug_drawing;
+pub mod decal;
+pub mod dof;
+pub mod dynamic_mesh;
+pub mod environment_map;
 pub mod error;
+pub mod fog;
 pub mod formats;
+pub mod fxaa;
+pub mod gizmo;
+pub mod graph_description;
 pub mod light;
+pub mod light_cluster;
+pub mod light_probe;
+pub mod lightmap;
+pub mod lod;
+pub mod morph;
+pub mod motion_blur;
 pub mod mtl;
+pub mod multi_window;
+pub mod occlusion;
+pub mod outline;
+pub mod particle;
+pub mod picking;
 pub mod pipeline;
 pub mod plugins;
+pub mod reflection_probe;
+pub mod render_mode;
+pub mod render_texture;
 pub mod resources;
+pub mod screenshot;
 pub mod serde_shim;
+pub mod shader_reload;
+pub mod shadow;
 pub mod shape;
 pub mod skinning;
 pub mod sprite;
 pub mod sprite_visibility;
+pub mod ssao;
+pub mod ssr;
+pub mod stats;
 pub mod submodules;
 pub mod system;
+pub mod taa;
+pub mod terrain;
+pub mod texture_streaming;
+pub mod tonemap;
+pub mod transcode;
 pub mod transparent;
 pub mod types;
+pub mod viewport;
+pub mod vignette;
 pub mod visibility;
+pub mod water;
 
 pub mod pod;
 pub mod util;
@@ -88,14 +142,18 @@ mod render_test_bundle;
 
 #[doc(inline)]
 pub use crate::{
-    bundle::{RenderPlugin, RenderingBundle},
+    bundle::{PostProcessPlugin, RenderPlugin, RenderingBundle},
     camera::{ActiveCamera, Camera},
     formats::{
         mesh::MeshPrefab,
-        texture::{ImageFormat, TexturePrefab},
+        texture::{
+            BasisFormat, CubeLutFormat, DdsFormat, EquirectFormat, ImageFormat, Ktx2Format,
+            KtxFormat, LutStripFormat, TextureCompression, TexturePrefab,
+        },
     },
     mtl::{Material, MaterialDefaults},
     plugins::*,
+    render_mode::RenderMode,
     sprite::{Sprite, SpriteRender, SpriteSheet, SpriteSheetFormat},
     system::{GraphCreator, MeshProcessorSystem, RenderingSystem, TextureProcessorSystem},
     transparent::Transparent,
@@ -91,6 +91,9 @@ pub struct PointLight {
     /// Smoothness of the light-to-dark transition from the center to the
     /// radius.
     pub smoothness: f32,
+    /// Whether the light renders a shadow cube map. Only the lights closest to the camera
+    /// cast shadows, up to the budget set in `ShadowParams::point_shadows`.
+    pub casts_shadows: bool,
 }
 
 impl Default for PointLight {
@@ -100,6 +103,7 @@ impl Default for PointLight {
             intensity: 10.0,
             radius: 10.0,
             smoothness: 4.0,
+            casts_shadows: false,
         }
     }
 }
@@ -129,6 +133,13 @@ pub struct SpotLight {
     /// Smoothness of the light-to-dark transition from the center to the
     /// radius.
     pub smoothness: f32,
+    /// Whether the light renders a shadow map. Only the lights closest to the camera
+    /// cast shadows, up to the budget set in `ShadowParams::spot_shadows`.
+    pub casts_shadows: bool,
+    /// Depth comparison bias relative to the distance from the light, avoids shadow acne.
+    pub shadow_bias: f32,
+    /// Radius of shadow edge filtering in shadow map texels. Zero gives hard shadows.
+    pub shadow_softness: f32,
 }
 
 impl Default for SpotLight {
@@ -140,6 +151,9 @@ impl Default for SpotLight {
             intensity: 10.0,
             range: 10.0,
             smoothness: 4.0,
+            casts_shadows: false,
+            shadow_bias: 0.01,
+            shadow_softness: 1.0,
         }
     }
 }
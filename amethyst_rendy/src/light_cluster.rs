@@ -0,0 +1,183 @@
+//! Clustered assignment of point and spot lights to cells of the view frustum.
+//!
+//! The frustum is split into a grid of clusters, `CLUSTER_COLUMNS` by `CLUSTER_ROWS` in
+//! normalized device coordinates and `CLUSTER_SLICES` exponentially growing depth slices.
+//! Every light is listed in the clusters its sphere of influence overlaps, so fragments only
+//! shade the lights of their own cluster.
+
+use amethyst_core::math::{Matrix4, Point3, Vector3};
+
+/// Number of cluster columns across the screen.
+pub const CLUSTER_COLUMNS: usize = 16;
+/// Number of cluster rows across the screen.
+pub const CLUSTER_ROWS: usize = 9;
+/// Number of depth slices of clusters.
+pub const CLUSTER_SLICES: usize = 24;
+/// Total number of clusters.
+pub const CLUSTER_COUNT: usize = CLUSTER_COLUMNS * CLUSTER_ROWS * CLUSTER_SLICES;
+/// View distance where the first depth slice ends.
+pub const CLUSTER_NEAR: f32 = 0.1;
+/// View distance where the last depth slice starts, it extends to infinity.
+pub const CLUSTER_FAR: f32 = 1000.0;
+
+/// Lights overlapping every cluster of the view frustum.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LightClusters {
+    ranges: Vec<[u32; 2]>,
+    indices: Vec<u32>,
+}
+
+impl LightClusters {
+    /// Assign lights, given as world space position and radius, to the clusters of a camera
+    /// with the given view and projection matrices. Lights are referred to by their index.
+    pub fn assign<I>(view: &Matrix4<f32>, projection: &Matrix4<f32>, lights: I) -> Self
+    where
+        I: IntoIterator<Item = (Vector3<f32>, f32)>,
+    {
+        let mut cluster_lights = vec![Vec::new(); CLUSTER_COUNT];
+        for (index, (position, radius)) in lights.into_iter().enumerate() {
+            if let Some((columns, rows, slices)) = light_bounds(view, projection, position, radius)
+            {
+                for slice in slices.0..=slices.1 {
+                    for row in rows.0..=rows.1 {
+                        for column in columns.0..=columns.1 {
+                            cluster_lights[cluster_index(column, row, slice)].push(index as u32);
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut ranges = Vec::with_capacity(CLUSTER_COUNT);
+        let mut indices = Vec::new();
+        for lights in cluster_lights {
+            ranges.push([indices.len() as u32, lights.len() as u32]);
+            indices.extend(lights);
+        }
+        Self { ranges, indices }
+    }
+
+    /// Indices of the lights overlapping the cluster at `index`.
+    pub fn lights(&self, index: usize) -> &[u32] {
+        let [offset, count] = self.ranges[index];
+        &self.indices[offset as usize..(offset + count) as usize]
+    }
+
+    /// Offset into `indices` and count of the lights of every cluster.
+    pub fn ranges(&self) -> &[[u32; 2]] {
+        &self.ranges
+    }
+
+    /// Light indices of all clusters, one after another.
+    pub fn indices(&self) -> &[u32] {
+        &self.indices
+    }
+}
+
+/// Index of the cluster in `column`, `row` and depth `slice`.
+pub fn cluster_index(column: usize, row: usize, slice: usize) -> usize {
+    (slice * CLUSTER_ROWS + row) * CLUSTER_COLUMNS + column
+}
+
+/// Depth slice containing the view distance `distance`.
+pub fn cluster_slice(distance: f32) -> usize {
+    if distance <= CLUSTER_NEAR {
+        return 0;
+    }
+    let slice =
+        (distance / CLUSTER_NEAR).ln() / (CLUSTER_FAR / CLUSTER_NEAR).ln() * CLUSTER_SLICES as f32;
+    (slice as usize).min(CLUSTER_SLICES - 1)
+}
+
+type ClusterRange = (usize, usize);
+
+/// Ranges of columns, rows and slices overlapped by a light, or `None` if it is entirely
+/// behind the camera.
+fn light_bounds(
+    view: &Matrix4<f32>,
+    projection: &Matrix4<f32>,
+    position: Vector3<f32>,
+    radius: f32,
+) -> Option<(ClusterRange, ClusterRange, ClusterRange)> {
+    let center = view.transform_point(&Point3::from(position));
+    // The camera looks along negative z in view space.
+    let (near, far) = (-center.z - radius, -center.z + radius);
+    if far <= 0.0 {
+        return None;
+    }
+    let slices = (cluster_slice(near), cluster_slice(far));
+
+    // Screen rectangle bounding the corners of the box around the light, covering the
+    // whole screen when the box reaches behind the camera.
+    let mut min = [1.0f32; 2];
+    let mut max = [-1.0f32; 2];
+    for i in 0..8 {
+        let corner = Vector3::new(
+            if i & 1 == 0 { -radius } else { radius },
+            if i & 2 == 0 { -radius } else { radius },
+            if i & 4 == 0 { -radius } else { radius },
+        );
+        let clip = projection * (center + corner).to_homogeneous();
+        if clip.w <= 0.0001 {
+            min = [-1.0; 2];
+            max = [1.0; 2];
+            break;
+        }
+        for axis in 0..2 {
+            min[axis] = min[axis].min(clip[axis] / clip.w);
+            max[axis] = max[axis].max(clip[axis] / clip.w);
+        }
+    }
+    if min[0] > 1.0 || min[1] > 1.0 || max[0] < -1.0 || max[1] < -1.0 {
+        return None;
+    }
+
+    let cell = |ndc: f32, cells: usize| {
+        let cell = ((ndc.max(-1.0).min(1.0) * 0.5 + 0.5) * cells as f32) as usize;
+        cell.min(cells - 1)
+    };
+    Some((
+        (cell(min[0], CLUSTER_COLUMNS), cell(max[0], CLUSTER_COLUMNS)),
+        (cell(min[1], CLUSTER_ROWS), cell(max[1], CLUSTER_ROWS)),
+        slices,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use amethyst_core::math::Perspective3;
+
+    fn assign(lights: Vec<(Vector3<f32>, f32)>) -> LightClusters {
+        let projection = Perspective3::new(16.0 / 9.0, 1.0, 0.1, 100.0).to_homogeneous();
+        LightClusters::assign(&Matrix4::identity(), &projection, lights)
+    }
+
+    #[test]
+    fn light_in_front_covers_its_cluster() {
+        let clusters = assign(vec![(Vector3::new(0.0, 0.0, -10.0), 1.0)]);
+        let center = cluster_index(CLUSTER_COLUMNS / 2, CLUSTER_ROWS / 2, cluster_slice(10.0));
+        assert_eq!(clusters.lights(center), &[0]);
+        assert!(clusters.lights(cluster_index(0, 0, 0)).is_empty());
+        assert!(clusters.indices().len() < CLUSTER_COUNT);
+    }
+
+    #[test]
+    fn light_behind_camera_is_culled() {
+        let clusters = assign(vec![(Vector3::new(0.0, 0.0, 10.0), 1.0)]);
+        assert!(clusters.indices().is_empty());
+    }
+
+    #[test]
+    fn light_around_camera_covers_screen() {
+        let clusters = assign(vec![
+            (Vector3::new(0.0, 0.0, -50.0), 1.0),
+            (Vector3::new(0.0, 0.0, 0.0), 2.0),
+        ]);
+        for row in 0..CLUSTER_ROWS {
+            for column in 0..CLUSTER_COLUMNS {
+                assert_eq!(clusters.lights(cluster_index(column, row, 0)), &[1]);
+            }
+        }
+    }
+}
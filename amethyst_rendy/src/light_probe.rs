@@ -0,0 +1,451 @@
+//! Baked light probes providing the diffuse ambient light of dynamic meshes.
+use crate::error;
+use amethyst_assets::{Asset, Format, Handle};
+use amethyst_core::{
+    ecs::DenseVecStorage,
+    math::{Point3, Vector3},
+};
+use amethyst_error::{format_err, Error};
+use ron::de::from_bytes as from_ron_bytes;
+use serde::{Deserialize, Serialize};
+
+/// Spherical harmonics of the radiance around a point, up to the second band.
+///
+/// The RGB coefficients of the real basis functions of the world space direction
+/// `(x, y, z)`, in order: `1`, `y`, `z`, `x`, `xy`, `yz`, `3z² - 1`, `xz` and `x² - y²`.
+pub type ShCoefficients = [[f32; 3]; 9];
+
+/// Factors of the basis functions convolved with the cosine lobe and divided by pi,
+/// giving the radiance reflected by a white Lambertian surface.
+const IRRADIANCE_BANDS: [f32; 9] = [
+    1.0,
+    2.0 / 3.0,
+    2.0 / 3.0,
+    2.0 / 3.0,
+    0.25,
+    0.25,
+    0.25,
+    0.25,
+    0.25,
+];
+
+/// Size of the header of the storage buffer declared by `light_probe.frag`, in bytes.
+pub(crate) const LIGHT_PROBE_HEADER_SIZE: usize = 48;
+
+/// Evaluate the basis functions of [ShCoefficients] in the normalized `direction`.
+pub fn sh_basis(direction: &Vector3<f32>) -> [f32; 9] {
+    let (x, y, z) = (direction.x, direction.y, direction.z);
+    [
+        0.282_095,
+        0.488_603 * y,
+        0.488_603 * z,
+        0.488_603 * x,
+        1.092_548 * x * y,
+        1.092_548 * y * z,
+        0.315_392 * (3.0 * z * z - 1.0),
+        1.092_548 * x * z,
+        0.546_274 * (x * x - y * y),
+    ]
+}
+
+/// Radiance reflected by a white Lambertian surface facing `normal`, lit by the radiance
+/// of the given spherical harmonics.
+pub fn sh_irradiance(coefficients: &ShCoefficients, normal: &Vector3<f32>) -> [f32; 3] {
+    let mut irradiance = [0.0; 3];
+    for ((coefficient, basis), band) in coefficients
+        .iter()
+        .zip(&sh_basis(normal))
+        .zip(&IRRADIANCE_BANDS)
+    {
+        for (channel, value) in irradiance.iter_mut().zip(coefficient) {
+            *channel += value * basis * band;
+        }
+    }
+    irradiance.iter_mut().for_each(|c| *c = c.max(0.0));
+    irradiance
+}
+
+/// Light probe baked at a position.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LightProbe {
+    /// World space position of the probe.
+    pub position: [f32; 3],
+    /// Spherical harmonics of the radiance at the position.
+    pub coefficients: ShCoefficients,
+}
+
+/// Light probes as baked by an external tool, loaded with [LightProbeFormat].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum LightProbeData {
+    /// Probes laid out on a regular grid.
+    Grid {
+        /// World space position of the first probe.
+        origin: [f32; 3],
+        /// Distance between two neighbouring probes along each axis.
+        spacing: [f32; 3],
+        /// Number of probes along each axis.
+        counts: [u32; 3],
+        /// Coefficients of every probe, with x varying fastest, then y and z.
+        coefficients: Vec<ShCoefficients>,
+    },
+    /// Probes scattered around the scene, resampled onto a grid of the given spacing
+    /// covering all of them.
+    Set {
+        /// Distance between two neighbouring probes of the resampled grid along each axis.
+        spacing: [f32; 3],
+        /// Probes of the set.
+        probes: Vec<LightProbe>,
+    },
+}
+
+/// Regular grid of light probes sampled by the PBR pass through the [LightProbes] resource.
+///
+/// The diffuse ambient light of a mesh is interpolated from the eight probes around its
+/// origin, clamped to the probes at the border of the grid outside of it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LightProbeGrid {
+    origin: Point3<f32>,
+    spacing: Vector3<f32>,
+    counts: [u32; 3],
+    coefficients: Vec<ShCoefficients>,
+}
+
+impl Asset for LightProbeGrid {
+    const NAME: &'static str = "renderer::LightProbeGrid";
+    type Data = Self;
+    type HandleStorage = DenseVecStorage<Handle<Self>>;
+}
+
+impl LightProbeGrid {
+    /// Create a grid of `counts` probes from `origin`, `spacing` apart, from the coefficients
+    /// of every probe with x varying fastest, then y and z.
+    pub fn new(
+        origin: Point3<f32>,
+        spacing: Vector3<f32>,
+        counts: [u32; 3],
+        coefficients: Vec<ShCoefficients>,
+    ) -> Result<Self, Error> {
+        if spacing.iter().any(|s| !(*s > 0.0 && s.is_finite())) {
+            return Err(format_err!(
+                "Light probe spacing must be positive, got {:?}",
+                spacing
+            ));
+        }
+        let count = counts.iter().map(|c| *c as usize).product::<usize>();
+        if count == 0 || coefficients.len() != count {
+            return Err(format_err!(
+                "Light probe grid of {:?} probes has {} of them",
+                counts,
+                coefficients.len()
+            ));
+        }
+        Ok(Self {
+            origin,
+            spacing,
+            counts,
+            coefficients,
+        })
+    }
+
+    /// Resample scattered `probes` onto a grid of the given `spacing` covering all of them.
+    ///
+    /// Every probe of the grid is the inverse square distance weighted average of the four
+    /// nearest probes of the set.
+    pub fn from_probes(probes: &[LightProbe], spacing: Vector3<f32>) -> Result<Self, Error> {
+        if probes.is_empty() {
+            return Err(format_err!("Light probe set is empty"));
+        }
+        let position = |probe: &LightProbe| Vector3::from(probe.position);
+        let (min, max) = probes.iter().skip(1).fold(
+            (position(&probes[0]), position(&probes[0])),
+            |(min, max), probe| {
+                (
+                    min.zip_map(&position(probe), f32::min),
+                    max.zip_map(&position(probe), f32::max),
+                )
+            },
+        );
+        let extent = max - min;
+        let mut counts = [1; 3];
+        for (axis, count) in counts.iter_mut().enumerate() {
+            if spacing[axis] > 0.0 {
+                *count = (extent[axis] / spacing[axis]).ceil() as u32 + 1;
+            }
+        }
+
+        let mut coefficients = Vec::with_capacity(counts.iter().product::<u32>() as usize);
+        let mut nearest = Vec::with_capacity(probes.len());
+        for z in 0..counts[2] {
+            for y in 0..counts[1] {
+                for x in 0..counts[0] {
+                    let point =
+                        min + Vector3::new(x as f32, y as f32, z as f32).component_mul(&spacing);
+                    nearest.clear();
+                    nearest.extend(
+                        probes
+                            .iter()
+                            .map(|probe| ((position(probe) - point).norm_squared(), probe)),
+                    );
+                    nearest.sort_by(|a, b| a.0.partial_cmp(&b.0).expect("Probe position is NaN"));
+                    coefficients.push(weighted_average(&nearest[..nearest.len().min(4)]));
+                }
+            }
+        }
+        Self::new(Point3::from(min), spacing, counts, coefficients)
+    }
+
+    /// Create a grid from the loaded data of light probes.
+    pub fn from_data(data: LightProbeData) -> Result<Self, Error> {
+        match data {
+            LightProbeData::Grid {
+                origin,
+                spacing,
+                counts,
+                coefficients,
+            } => Self::new(origin.into(), spacing.into(), counts, coefficients),
+            LightProbeData::Set { spacing, probes } => Self::from_probes(&probes, spacing.into()),
+        }
+    }
+
+    /// World space position of the first probe.
+    pub fn origin(&self) -> &Point3<f32> {
+        &self.origin
+    }
+
+    /// Distance between two neighbouring probes along each axis.
+    pub fn spacing(&self) -> &Vector3<f32> {
+        &self.spacing
+    }
+
+    /// Number of probes along each axis.
+    pub fn counts(&self) -> [u32; 3] {
+        self.counts
+    }
+
+    /// Coefficients of every probe, with x varying fastest, then y and z.
+    pub fn coefficients(&self) -> &[ShCoefficients] {
+        &self.coefficients
+    }
+
+    /// Spherical harmonics at `point`, interpolated between the eight closest probes
+    /// as the PBR pass does.
+    pub fn sample(&self, point: &Point3<f32>) -> ShCoefficients {
+        let cell = (point - self.origin).component_div(&self.spacing);
+        let mut lower = [0; 3];
+        let mut upper = [0; 3];
+        let mut fraction = [0.0; 3];
+        for axis in 0..3 {
+            let last = self.counts[axis] - 1;
+            let cell = cell[axis].max(0.0).min(last as f32);
+            lower[axis] = (cell as u32).min(last);
+            upper[axis] = (lower[axis] + 1).min(last);
+            fraction[axis] = cell - lower[axis] as f32;
+        }
+
+        let mut sample = [[0.0; 3]; 9];
+        for corner in 0..8 {
+            let mut probe = [0; 3];
+            let mut weight = 1.0;
+            for axis in 0..3 {
+                if corner & (1 << axis) != 0 {
+                    probe[axis] = upper[axis];
+                    weight *= fraction[axis];
+                } else {
+                    probe[axis] = lower[axis];
+                    weight *= 1.0 - fraction[axis];
+                }
+            }
+            let index =
+                ((probe[2] * self.counts[1] + probe[1]) * self.counts[0] + probe[0]) as usize;
+            add_scaled(&mut sample, &self.coefficients[index], weight);
+        }
+        sample
+    }
+
+    /// Contents of the storage buffer declared by `light_probe.frag`.
+    pub(crate) fn buffer_data(&self, intensity: f32) -> Vec<u8> {
+        let mut data = Vec::with_capacity(LIGHT_PROBE_HEADER_SIZE + self.coefficients.len() * 144);
+        let floats = |data: &mut Vec<u8>, values: &[f32]| {
+            values
+                .iter()
+                .for_each(|value| data.extend_from_slice(&value.to_ne_bytes()))
+        };
+        floats(&mut data, self.origin.coords.as_slice());
+        floats(&mut data, &[intensity]);
+        floats(&mut data, self.spacing.as_slice());
+        data.extend_from_slice(&(self.coefficients.len() as i32).to_ne_bytes());
+        for count in self.counts.iter().chain(&[0]) {
+            data.extend_from_slice(&(*count as i32).to_ne_bytes());
+        }
+        for coefficient in self.coefficients.iter().flat_map(|c| c.iter()) {
+            floats(&mut data, coefficient);
+            floats(&mut data, &[0.0]);
+        }
+        data
+    }
+}
+
+/// Average the coefficients of probes by the inverse of their squared distance.
+fn weighted_average(probes: &[(f32, &LightProbe)]) -> ShCoefficients {
+    if let Some((_, probe)) = probes.iter().find(|(distance, _)| *distance < 1e-8) {
+        return probe.coefficients;
+    }
+    let total = probes
+        .iter()
+        .map(|(distance, _)| 1.0 / distance)
+        .sum::<f32>();
+    let mut average = [[0.0; 3]; 9];
+    for (distance, probe) in probes {
+        add_scaled(&mut average, &probe.coefficients, 1.0 / (distance * total));
+    }
+    average
+}
+
+fn add_scaled(sum: &mut ShCoefficients, coefficients: &ShCoefficients, scale: f32) {
+    for (sum, coefficient) in sum.iter_mut().zip(coefficients) {
+        for (sum, value) in sum.iter_mut().zip(coefficient) {
+            *sum += value * scale;
+        }
+    }
+}
+
+/// Format of externally baked light probes, a [LightProbeData] in RON.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LightProbeFormat;
+
+impl Format<LightProbeGrid> for LightProbeFormat {
+    fn name(&self) -> &'static str {
+        "LIGHT_PROBES"
+    }
+
+    fn import_simple(&self, bytes: Vec<u8>) -> Result<LightProbeGrid, Error> {
+        let data: LightProbeData =
+            from_ron_bytes(&bytes).map_err(error::Error::LoadLightProbesError)?;
+        LightProbeGrid::from_data(data)
+    }
+}
+
+/// Resource lighting the meshes of the PBR pass with baked light probes.
+///
+/// Once the grid is loaded, the diffuse ambient light of every mesh is interpolated from the
+/// probes around its origin, replacing the irradiance of the `EnvironmentMap` and reflection
+/// probes, or the `AmbientColor`. Specular reflections still come from the environment map
+/// and reflection probes.
+#[derive(Clone, Debug)]
+pub struct LightProbes {
+    /// Grid of light probes, loaded with [LightProbeFormat].
+    pub grid: Handle<LightProbeGrid>,
+    /// Scale applied to the light of the probes.
+    pub intensity: f32,
+}
+
+impl LightProbes {
+    /// Light meshes with the probes of `grid`.
+    pub fn new(grid: Handle<LightProbeGrid>) -> Self {
+        Self {
+            grid,
+            intensity: 1.0,
+        }
+    }
+
+    /// Set the scale applied to the light of the probes.
+    pub fn with_intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn constant(value: f32) -> ShCoefficients {
+        let mut coefficients = [[0.0; 3]; 9];
+        // Radiance of `value` in every direction.
+        coefficients[0] = [value / 0.282_095; 3];
+        coefficients
+    }
+
+    #[test]
+    fn constant_radiance_gives_constant_irradiance() {
+        let coefficients = constant(0.5);
+        for normal in &[
+            Vector3::x(),
+            -Vector3::y(),
+            Vector3::new(1.0, 1.0, 1.0).normalize(),
+        ] {
+            let irradiance = sh_irradiance(&coefficients, normal);
+            assert!((irradiance[0] - 0.5).abs() < 1e-5, "{:?}", irradiance);
+        }
+    }
+
+    #[test]
+    fn grid_is_interpolated_and_clamped() {
+        let grid = LightProbeGrid::new(
+            Point3::origin(),
+            Vector3::new(2.0, 1.0, 1.0),
+            [2, 1, 1],
+            vec![constant(0.0), constant(1.0)],
+        )
+        .unwrap();
+        let irradiance =
+            |x| sh_irradiance(&grid.sample(&Point3::new(x, 0.5, -3.0)), &Vector3::y())[0];
+        assert!(irradiance(-1.0).abs() < 1e-5);
+        assert!((irradiance(0.5) - 0.25).abs() < 1e-5);
+        assert!((irradiance(1.0) - 0.5).abs() < 1e-5);
+        assert!((irradiance(5.0) - 1.0).abs() < 1e-5);
+        assert_eq!(
+            grid.buffer_data(1.0).len(),
+            LIGHT_PROBE_HEADER_SIZE + 2 * 9 * 16
+        );
+    }
+
+    #[test]
+    fn grid_size_is_checked() {
+        let spacing = Vector3::new(1.0, 1.0, 1.0);
+        assert!(
+            LightProbeGrid::new(Point3::origin(), spacing, [2, 1, 1], vec![constant(0.0)]).is_err()
+        );
+        assert!(LightProbeGrid::new(Point3::origin(), spacing, [0, 1, 1], vec![]).is_err());
+        assert!(
+            LightProbeGrid::new(Point3::origin(), -spacing, [1, 1, 1], vec![constant(0.0)])
+                .is_err()
+        );
+        assert!(LightProbeGrid::from_probes(&[], spacing).is_err());
+    }
+
+    #[test]
+    fn set_is_resampled_onto_grid() {
+        let probe = |x, value| LightProbe {
+            position: [x, 1.0, 1.0],
+            coefficients: constant(value),
+        };
+        let grid = LightProbeGrid::from_probes(
+            &[probe(0.0, 0.0), probe(4.0, 1.0)],
+            Vector3::new(1.0, 1.0, 1.0),
+        )
+        .unwrap();
+        assert_eq!(grid.counts(), [5, 1, 1]);
+        assert_eq!(grid.origin(), &Point3::new(0.0, 1.0, 1.0));
+        assert_eq!(grid.coefficients()[0], constant(0.0));
+        assert_eq!(grid.coefficients()[4], constant(1.0));
+        let middle = sh_irradiance(&grid.coefficients()[2], &Vector3::z())[0];
+        assert!((middle - 0.5).abs() < 1e-5, "{}", middle);
+    }
+
+    #[test]
+    fn probes_are_loaded_from_ron() {
+        let ron = br#"Grid(
+            origin: (0.0, 0.0, 0.0),
+            spacing: (1.0, 1.0, 1.0),
+            counts: (1, 1, 1),
+            coefficients: [(
+                (1.0, 1.0, 1.0), (0.0, 0.0, 0.0), (0.0, 0.0, 0.0),
+                (0.0, 0.0, 0.0), (0.0, 0.0, 0.0), (0.0, 0.0, 0.0),
+                (0.0, 0.0, 0.0), (0.0, 0.0, 0.0), (0.0, 0.0, 0.0),
+            )],
+        )"#;
+        let grid = LightProbeFormat.import_simple(ron.to_vec()).unwrap();
+        assert_eq!(grid.coefficients()[0][0], [1.0; 3]);
+    }
+}
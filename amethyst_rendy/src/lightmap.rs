@@ -0,0 +1,22 @@
+//! Externally baked lighting applied with a second set of texture coordinates.
+use rendy::{hal::format::Format, mesh::AsAttribute};
+
+/// Type for the texture coordinates of a vertex in the lightmap of its material.
+///
+/// The PBR pass samples the `lightmap` of materials with these coordinates, or with the
+/// regular `TexCoord` of meshes lacking them. Lightmaps are usually baked onto a second,
+/// non-overlapping UV channel of the mesh.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub struct LightmapTexCoord(pub [f32; 2]);
+
+impl From<[f32; 2]> for LightmapTexCoord {
+    fn from(from: [f32; 2]) -> Self {
+        Self(from)
+    }
+}
+
+impl AsAttribute for LightmapTexCoord {
+    const NAME: &'static str = "lightmap_tex_coord";
+    const FORMAT: Format = Format::Rg32Sfloat;
+}
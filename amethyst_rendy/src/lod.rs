@@ -0,0 +1,249 @@
+//! Level of detail selection of 3D meshes by their distance to the camera.
+use crate::{
+    camera::{ActiveCamera, Camera},
+    types::Mesh,
+};
+use amethyst_assets::Handle;
+use amethyst_core::{
+    ecs::prelude::{
+        Component, DenseVecStorage, Entities, Join, Read, ReadStorage, System, WriteStorage,
+    },
+    math::{distance, Point3},
+    Transform,
+};
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+/// Component swapping the `Handle<Mesh>` of an entity by its distance to the active camera,
+/// with the [LodSelectionSystem].
+///
+/// Every level is the distance from which its mesh is used, the first level being used
+/// below the distance of the second one. To avoid flickering between two levels when the
+/// camera hovers around their distance, a coarser level is only selected once the camera is
+/// further than its distance by the `hysteresis` fraction of it, and a finer level once the
+/// camera is closer by the same fraction.
+#[derive(Clone, Debug)]
+pub struct LodGroup {
+    levels: Vec<(f32, Handle<Mesh>)>,
+    /// Fraction of the distance of a level by which the camera must cross it before switching.
+    pub hysteresis: f32,
+    current: usize,
+}
+
+impl LodGroup {
+    /// Create a group from levels of distances and meshes, in any order.
+    pub fn new(mut levels: Vec<(f32, Handle<Mesh>)>) -> Self {
+        levels.sort_by(|a, b| a.0.partial_cmp(&b.0).expect("LOD distance is NaN"));
+        LodGroup {
+            levels,
+            hysteresis: 0.1,
+            current: 0,
+        }
+    }
+
+    /// Set the fraction of the distance of a level by which the camera must cross it.
+    pub fn with_hysteresis(mut self, hysteresis: f32) -> Self {
+        self.hysteresis = hysteresis;
+        self
+    }
+
+    /// Levels of distances and meshes, sorted from the nearest.
+    pub fn levels(&self) -> &[(f32, Handle<Mesh>)] {
+        &self.levels
+    }
+
+    /// Index of the level currently selected.
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    /// Select the level used at `distance` from the camera, returning its mesh.
+    pub fn select(&mut self, distance: f32) -> Option<&Handle<Mesh>> {
+        let levels = &self.levels;
+        self.current = select_level(
+            |level| levels[level].0,
+            levels.len(),
+            self.current,
+            distance,
+            self.hysteresis,
+        );
+        self.levels.get(self.current).map(|(_, mesh)| mesh)
+    }
+}
+
+impl Component for LodGroup {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Select the level out of `count` used at `distance`, starting from the `current` one.
+fn select_level(
+    threshold: impl Fn(usize) -> f32,
+    count: usize,
+    current: usize,
+    distance: f32,
+    hysteresis: f32,
+) -> usize {
+    let mut level = current.min(count.saturating_sub(1));
+    while level + 1 < count && distance >= threshold(level + 1) * (1.0 + hysteresis) {
+        level += 1;
+    }
+    while level > 0 && distance < threshold(level) * (1.0 - hysteresis) {
+        level -= 1;
+    }
+    level
+}
+
+/// Swap the meshes of every [LodGroup] by its distance to the active camera.
+///
+/// Note that this should run after `Transform` has been updated for the current frame.
+#[derive(Default, Debug)]
+pub struct LodSelectionSystem;
+
+impl LodSelectionSystem {
+    /// Create new LOD selection system
+    pub fn new() -> Self {
+        LodSelectionSystem
+    }
+}
+
+impl<'a> System<'a> for LodSelectionSystem {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, ActiveCamera>,
+        ReadStorage<'a, Camera>,
+        ReadStorage<'a, Transform>,
+        WriteStorage<'a, LodGroup>,
+        WriteStorage<'a, Handle<Mesh>>,
+    );
+
+    fn run(
+        &mut self,
+        (entities, active, camera, transform, mut groups, mut meshes): Self::SystemData,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("lod_selection_system");
+
+        let origin = Point3::origin();
+        let mut camera_join = (&camera, &transform).join();
+        let camera_transform = match active
+            .entity
+            .and_then(|a| camera_join.get(a, &entities))
+            .or_else(|| camera_join.next())
+        {
+            Some((_, camera_transform)) => camera_transform,
+            None => return,
+        };
+        let camera_centroid = camera_transform.global_matrix().transform_point(&origin);
+
+        for (entity, transform, group) in (&entities, &transform, &mut groups).join() {
+            let centroid = transform.global_matrix().transform_point(&origin);
+            if let Some(mesh) = group.select(distance(&centroid, &camera_centroid)) {
+                if meshes.get(entity) != Some(mesh) {
+                    meshes
+                        .insert(entity, mesh.clone())
+                        .expect("Entity of the joined LodGroup is alive");
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::MeshData;
+    use amethyst_assets::{AssetStorage, Loader};
+    use amethyst_core::ecs::{Builder, RunNow, World, WorldExt};
+    use rayon::ThreadPoolBuilder;
+    use rendy::mesh::MeshBuilder;
+    use std::sync::Arc;
+
+    fn meshes(count: usize) -> Vec<Handle<Mesh>> {
+        let loader = Loader::new(".", Arc::new(ThreadPoolBuilder::new().build().unwrap()));
+        let storage = AssetStorage::<Mesh>::new();
+        (0..count)
+            .map(|_| loader.load_from_data(MeshData::from(MeshBuilder::new()), (), &storage))
+            .collect()
+    }
+
+    fn positioned(z: f32) -> Transform {
+        let mut transform = Transform::default();
+        transform.set_translation_xyz(0.0, 0.0, z);
+        transform.copy_local_to_global();
+        transform
+    }
+
+    #[test]
+    fn select_level_with_hysteresis() {
+        let thresholds = [0.0, 10.0, 20.0];
+        let select =
+            |current, distance| select_level(|level| thresholds[level], 3, current, distance, 0.1);
+
+        assert_eq!(select(0, 5.0), 0);
+        assert_eq!(select(0, 10.5), 0);
+        assert_eq!(select(0, 11.0), 1);
+        assert_eq!(select(0, 25.0), 2);
+        assert_eq!(select(1, 9.5), 1);
+        assert_eq!(select(1, 8.5), 0);
+        assert_eq!(select(2, 2.0), 0);
+        assert_eq!(select(0, 0.0), 0);
+        assert_eq!(select_level(|_| 0.0, 0, 0, 5.0, 0.1), 0);
+    }
+
+    #[test]
+    fn group_levels_are_sorted_by_distance() {
+        let handles = meshes(3);
+        let mut group = LodGroup::new(vec![
+            (50.0, handles[2].clone()),
+            (0.0, handles[0].clone()),
+            (20.0, handles[1].clone()),
+        ])
+        .with_hysteresis(0.0);
+        assert_eq!(
+            group.levels().iter().map(|l| l.0).collect::<Vec<_>>(),
+            vec![0.0, 20.0, 50.0]
+        );
+
+        assert_eq!(group.select(30.0), Some(&handles[1]));
+        assert_eq!(group.current(), 1);
+        assert_eq!(group.select(60.0), Some(&handles[2]));
+        assert_eq!(group.select(5.0), Some(&handles[0]));
+        assert_eq!(LodGroup::new(Vec::new()).select(5.0), None);
+    }
+
+    #[test]
+    fn system_swaps_meshes_by_camera_distance() {
+        let mut world = World::new();
+        let mut system = LodSelectionSystem::new();
+        System::setup(&mut system, &mut world);
+
+        let handles = meshes(2);
+        let group = LodGroup::new(vec![(0.0, handles[0].clone()), (10.0, handles[1].clone())]);
+        world
+            .create_entity()
+            .with(Camera::standard_3d(1.0, 1.0))
+            .with(positioned(0.0))
+            .build();
+        let near = world
+            .create_entity()
+            .with(group.clone())
+            .with(positioned(-5.0))
+            .build();
+        let far = world
+            .create_entity()
+            .with(group)
+            .with(positioned(-20.0))
+            .with(handles[0].clone())
+            .build();
+
+        system.run_now(&world);
+        let mesh_storage = world.read_storage::<Handle<Mesh>>();
+        assert_eq!(mesh_storage.get(near), Some(&handles[0]));
+        assert_eq!(mesh_storage.get(far), Some(&handles[1]));
+        assert_eq!(
+            world.read_storage::<LodGroup>().get(far).unwrap().current(),
+            1
+        );
+    }
+}
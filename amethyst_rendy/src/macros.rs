@@ -12,3 +12,18 @@ macro_rules! set_layout {
         )?.into()
     }
 }
+
+macro_rules! embedded_shader {
+    ($path:expr, $stage:expr $(,)?) => {
+        crate::shader_reload::ReloadableShader::new(
+            env!("CARGO_PKG_NAME"),
+            concat!("compiled/", $path),
+            rendy::shader::SpirvShader::from_bytes(
+                include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/compiled/", $path)),
+                $stage,
+                "main",
+            )
+            .unwrap(),
+        )
+    };
+}
@@ -0,0 +1,67 @@
+//! Morph targets (blend shapes) of meshes.
+use amethyst_assets::PrefabData;
+use amethyst_core::ecs::prelude::{Component, DenseVecStorage, Entity, WriteStorage};
+use amethyst_error::Error;
+use rendy::{
+    hal::format::Format,
+    mesh::{AsVertex, VertexFormat},
+};
+
+/// Maximum number of morph targets blended for a mesh.
+pub const MAX_MORPH_TARGETS: usize = 4;
+
+/// Type for vertex buffer value with the position offsets of every morph target.
+///
+/// Meshes with this attribute are drawn by passes supporting morph targets when their entity
+/// has [MorphWeights], offsetting the positions by the weighted sum of the targets.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub struct MorphPositions(pub [[f32; 3]; MAX_MORPH_TARGETS]);
+
+impl AsVertex for MorphPositions {
+    fn vertex() -> VertexFormat {
+        VertexFormat::new((
+            (Format::Rgb32Sfloat, "morph_position"),
+            (Format::Rgb32Sfloat, "morph_position"),
+            (Format::Rgb32Sfloat, "morph_position"),
+            (Format::Rgb32Sfloat, "morph_position"),
+        ))
+    }
+}
+
+/// Weights of the morph targets of the mesh of an entity.
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct MorphWeights(pub [f32; MAX_MORPH_TARGETS]);
+
+impl MorphWeights {
+    /// Create weights from a slice, ignoring targets past `MAX_MORPH_TARGETS`.
+    pub fn from_slice(weights: &[f32]) -> Self {
+        let mut morph = Self::default();
+        for (weight, value) in morph.0.iter_mut().zip(weights) {
+            *weight = *value;
+        }
+        morph
+    }
+}
+
+impl Component for MorphWeights {
+    type Storage = DenseVecStorage<Self>;
+}
+
+impl<'a> PrefabData<'a> for MorphWeights {
+    type SystemData = WriteStorage<'a, MorphWeights>;
+    type Result = ();
+
+    fn add_to_entity(
+        &self,
+        entity: Entity,
+        storage: &mut Self::SystemData,
+        _: &[Entity],
+        _: &[Entity],
+    ) -> Result<(), Error> {
+        storage
+            .insert(entity, *self)
+            .map(|_| ())
+            .map_err(Into::into)
+    }
+}
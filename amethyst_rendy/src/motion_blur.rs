@@ -0,0 +1,40 @@
+//! Motion blur configuration.
+
+use amethyst_core::ecs::{Read, SystemData, World};
+
+/// Maximum number of samples taken per pixel along its motion by motion blur.
+pub const MAX_MOTION_BLUR_SAMPLES: u32 = 32;
+
+/// Resource configuring the motion blur applied by `RenderMotionBlur`.
+///
+/// All fields are read every frame. While disabled, the pass copies its input unchanged.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MotionBlurParams {
+    /// Blur pixels along their motion since the previous frame.
+    pub enabled: bool,
+    /// Number of samples per pixel, up to `MAX_MOTION_BLUR_SAMPLES`.
+    /// More samples avoid visible steps in the blur of fast motion at a higher cost.
+    pub sample_count: u32,
+    /// Fraction of the motion during a frame the shutter is open for. At 1, pixels are
+    /// blurred along their whole motion since the previous frame.
+    pub shutter_scale: f32,
+}
+
+impl Default for MotionBlurParams {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            sample_count: 8,
+            shutter_scale: 0.5,
+        }
+    }
+}
+
+impl MotionBlurParams {
+    /// Returns the `MotionBlurParams` resource, or default parameters if there is none.
+    pub fn fetch(world: &World) -> Self {
+        <Option<Read<'_, MotionBlurParams>>>::fetch(world)
+            .map(|p| *p)
+            .unwrap_or_default()
+    }
+}
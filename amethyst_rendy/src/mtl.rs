@@ -22,6 +22,73 @@ impl Default for TextureOffset {
     }
 }
 
+/// Transform of the texture coordinates sampling one texture of a `Material`, as glTF's
+/// `KHR_texture_transform`: the coordinates are scaled, then rotated, then offset.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct TextureTransform {
+    /// Offset of the texture coordinates
+    pub offset: [f32; 2],
+    /// Rotation of the texture coordinates in radians
+    pub rotation: f32,
+    /// Scale of the texture coordinates
+    pub scale: [f32; 2],
+    /// Texture coordinates transformed: 0 for the `TexCoord` of meshes, 1 for their
+    /// [LightmapTexCoord](crate::lightmap::LightmapTexCoord).
+    pub tex_coord: u32,
+}
+
+impl Default for TextureTransform {
+    fn default() -> Self {
+        TextureTransform {
+            offset: [0.0, 0.0],
+            rotation: 0.0,
+            scale: [1.0, 1.0],
+            tex_coord: 0,
+        }
+    }
+}
+
+impl TextureTransform {
+    /// Rows of the affine matrix transforming `(u, v, 1)`.
+    pub fn rows(&self) -> [[f32; 3]; 2] {
+        let (sin, cos) = self.rotation.sin_cos();
+        [
+            [cos * self.scale[0], sin * self.scale[1], self.offset[0]],
+            [-sin * self.scale[0], cos * self.scale[1], self.offset[1]],
+        ]
+    }
+
+    /// The same transform for texture coordinates and textures with a flipped V axis.
+    pub fn flip_v(&self) -> Self {
+        let (sin, cos) = self.rotation.sin_cos();
+        TextureTransform {
+            offset: [
+                self.offset[0] + sin * self.scale[1],
+                1.0 - self.offset[1] - cos * self.scale[1],
+            ],
+            rotation: -self.rotation,
+            ..self.clone()
+        }
+    }
+}
+
+/// The `TextureTransform` of each texture of a `Material`. The cavity map uses the albedo one.
+#[derive(Debug, Clone, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct TextureTransforms {
+    /// Transform of the albedo map.
+    pub albedo: TextureTransform,
+    /// Transform of the emission map.
+    pub emission: TextureTransform,
+    /// Transform of the normal map.
+    pub normal: TextureTransform,
+    /// Transform of the metallic-roughness map.
+    pub metallic_roughness: TextureTransform,
+    /// Transform of the ambient occlusion map.
+    pub ambient_occlusion: TextureTransform,
+}
+
 /// A physically based Material with metallic workflow, fully utilized in PBR render pass.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Material {
@@ -41,6 +108,21 @@ pub struct Material {
     pub cavity: Handle<Texture>,
     /// Texture offset
     pub uv_offset: TextureOffset,
+    /// Transforms of the texture coordinates of each map, applied before `uv_offset`.
+    pub texture_transforms: TextureTransforms,
+    /// Multiplier of the emission map, for emission brighter than the texture can store.
+    pub emission_strength: f32,
+    /// Draw the albedo and emission as they are, ignoring lights.
+    pub unlit: bool,
+    /// Lightmap of externally baked lighting, sampled with the
+    /// [LightmapTexCoord](crate::lightmap::LightmapTexCoord) of meshes.
+    ///
+    /// It holds the linear light reflected by a white diffuse surface, replacing the diffuse
+    /// ambient lighting of the PBR pass. Bake indirect lighting only, or leave the baked lights
+    /// out of the scene, for lights not to be applied twice.
+    pub lightmap: Handle<Texture>,
+    /// Scale of the light of the lightmap, which is ignored when zero.
+    pub lightmap_intensity: f32,
 }
 
 impl Asset for Material {
@@ -81,6 +163,7 @@ pub type FullTextureSet = (
     TexMetallicRoughness,
     TexAmbientOcclusion,
     TexCavity,
+    TexLightmap,
 );
 
 macro_rules! impl_texture {
@@ -104,6 +187,7 @@ impl_texture!(TexNormal, normal);
 impl_texture!(TexMetallicRoughness, metallic_roughness);
 impl_texture!(TexAmbientOcclusion, ambient_occlusion);
 impl_texture!(TexCavity, cavity);
+impl_texture!(TexLightmap, lightmap);
 
 macro_rules! recursive_iter {
     (@value $first:expr, $($rest:expr),*) => { $first.chain(recursive_iter!(@value $($rest),*)) };
@@ -136,3 +220,62 @@ impl_texture_set_tuple!(A, B, C);
 impl_texture_set_tuple!(A, B, C, D);
 impl_texture_set_tuple!(A, B, C, D, E);
 impl_texture_set_tuple!(A, B, C, D, E, F);
+impl_texture_set_tuple!(A, B, C, D, E, F, G);
+
+#[cfg(test)]
+mod tests {
+    use super::TextureTransform;
+
+    fn apply(transform: &TextureTransform, [u, v]: [f32; 2]) -> [f32; 2] {
+        let [row_u, row_v] = transform.rows();
+        [
+            row_u[0] * u + row_u[1] * v + row_u[2],
+            row_v[0] * u + row_v[1] * v + row_v[2],
+        ]
+    }
+
+    fn assert_close(a: [f32; 2], b: [f32; 2]) {
+        assert!(
+            (a[0] - b[0]).abs() < 1e-5 && (a[1] - b[1]).abs() < 1e-5,
+            "{:?} != {:?}",
+            a,
+            b
+        );
+    }
+
+    #[test]
+    fn default_transform_is_identity() {
+        let transform = TextureTransform::default();
+        assert_close(apply(&transform, [0.25, 0.75]), [0.25, 0.75]);
+    }
+
+    #[test]
+    fn transform_scales_then_rotates_then_offsets() {
+        let transform = TextureTransform {
+            offset: [0.5, 0.25],
+            rotation: std::f32::consts::FRAC_PI_2,
+            scale: [2.0, 3.0],
+            tex_coord: 0,
+        };
+        // (1, 0) scaled to (2, 0), rotated to (0, -2), offset to (0.5, -1.75).
+        assert_close(apply(&transform, [1.0, 0.0]), [0.5, -1.75]);
+        // (0, 1) scaled to (0, 3), rotated to (3, 0), offset to (3.5, 0.25).
+        assert_close(apply(&transform, [0.0, 1.0]), [3.5, 0.25]);
+    }
+
+    #[test]
+    fn flipped_transform_matches_flipped_coordinates() {
+        let transform = TextureTransform {
+            offset: [0.1, 0.2],
+            rotation: 0.7,
+            scale: [1.5, 0.5],
+            tex_coord: 1,
+        };
+        let flipped = transform.flip_v();
+        assert_eq!(flipped.tex_coord, 1);
+        for &[u, v] in &[[0.0, 0.0], [1.0, 0.0], [0.3, 0.8]] {
+            let [su, sv] = apply(&transform, [u, 1.0 - v]);
+            assert_close(apply(&flipped, [u, v]), [su, 1.0 - sv]);
+        }
+    }
+}
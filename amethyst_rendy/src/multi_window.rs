@@ -0,0 +1,80 @@
+//! Rendering cameras into additional windows, e.g. for tool windows of editors or a second screen.
+
+use crate::{bundle::Target, camera::Camera};
+use amethyst_core::ecs::{
+    Component, DenseVecStorage, Entities, Entity, Join, ReadStorage, SystemData, World,
+};
+
+/// Component rendering a camera into an additional window instead of the main window.
+///
+/// The camera renders `target`, which is defined by
+/// [RenderToAdditionalWindow](crate::plugins::RenderToAdditionalWindow) and drawn by the
+/// plugins added with that target. `window` is the name of the window in the `Windows`
+/// resource of `amethyst_window`. The camera projection should have the aspect ratio of
+/// that window.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CameraWindow {
+    /// Render target drawn from the camera.
+    pub target: Target,
+    /// Name of the window presenting the rendered image.
+    pub window: String,
+}
+
+impl Component for CameraWindow {
+    type Storage = DenseVecStorage<Self>;
+}
+
+impl CameraWindow {
+    /// Create a `CameraWindow` rendering `target` into the window named `window`.
+    pub fn new(target: Target, window: impl Into<String>) -> Self {
+        Self {
+            target,
+            window: window.into(),
+        }
+    }
+}
+
+/// Find the camera entity rendering `target` into a window, along with its `CameraWindow`.
+///
+/// When several cameras render the same target, the first one found is used.
+pub fn window_camera(world: &World, target: Target) -> Option<(Entity, CameraWindow)> {
+    let (entities, cameras, windows) = <(
+        Entities<'_>,
+        ReadStorage<'_, Camera>,
+        ReadStorage<'_, CameraWindow>,
+    )>::fetch(world);
+    (&entities, &cameras, &windows)
+        .join()
+        .find(|(_, _, camera_window)| camera_window.target == target)
+        .map(|(entity, _, camera_window)| (entity, camera_window.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use amethyst_core::ecs::{Builder, WorldExt};
+
+    #[test]
+    fn finds_camera_of_window_target() {
+        let mut world = World::new();
+        world.register::<Camera>();
+        world.register::<CameraWindow>();
+        let tools = CameraWindow::new(Target::Custom("tools"), "tools");
+
+        world
+            .create_entity()
+            .with(Camera::standard_3d(1.0, 1.0))
+            .build();
+        let camera = world
+            .create_entity()
+            .with(Camera::standard_3d(1.0, 1.0))
+            .with(tools.clone())
+            .build();
+
+        assert_eq!(
+            window_camera(&world, Target::Custom("tools")),
+            Some((camera, tools))
+        );
+        assert_eq!(window_camera(&world, Target::Main), None);
+    }
+}
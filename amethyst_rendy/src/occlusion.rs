@@ -0,0 +1,84 @@
+//! Occlusion culling of entities hidden behind the geometry drawn in front of them.
+
+use amethyst_core::{
+    ecs::{hibitset::BitSet, prelude::Entity},
+    math::{Point3, Vector3},
+};
+
+/// Resource skipping entities found fully occluded, inserted by
+/// [RenderOcclusionCulling](crate::plugins::RenderOcclusionCulling).
+///
+/// Every frame, [VisibilitySortingSystem](crate::visibility::VisibilitySortingSystem) submits
+/// the world space bounding boxes of the entities left by frustum culling. They are tested on
+/// the GPU against a hierarchical depth buffer built from the depth of
+/// [`Target::Main`](crate::bundle::Target) once it has been drawn, and the results are read
+/// back once the frame has completed. An entity coming into view is therefore drawn a few
+/// frames late, which is only noticeable around fast moving occluders.
+#[derive(Debug, Clone)]
+pub struct OcclusionCulling {
+    /// Skip entities found occluded. When disabled, no boxes are tested either.
+    pub enabled: bool,
+    capacity: usize,
+    pub(crate) boxes: Vec<(Entity, Point3<f32>, Vector3<f32>)>,
+    pub(crate) occluded: BitSet,
+}
+
+impl OcclusionCulling {
+    /// Create a resource testing at most `capacity` entities every frame.
+    ///
+    /// Entities submitted past the capacity are never culled.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            enabled: true,
+            capacity,
+            boxes: Vec::with_capacity(capacity),
+            occluded: BitSet::new(),
+        }
+    }
+
+    /// Maximum number of entities tested every frame.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Check whether the entity was found fully occluded by the latest completed test.
+    pub fn is_occluded(&self, entity: Entity) -> bool {
+        self.occluded.contains(entity.id())
+    }
+
+    /// Submit the world space bounding box of a visible entity to be tested this frame.
+    pub(crate) fn submit(
+        &mut self,
+        entity: Entity,
+        center: Point3<f32>,
+        half_extents: Vector3<f32>,
+    ) {
+        if self.boxes.len() < self.capacity {
+            self.boxes.push((entity, center, half_extents));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use amethyst_core::ecs::{Builder, World, WorldExt};
+
+    #[test]
+    fn submit_respects_capacity() {
+        let mut world = World::new();
+        let first = world.create_entity().build();
+        let second = world.create_entity().build();
+        let mut occlusion = OcclusionCulling::new(1);
+
+        occlusion.submit(first, Point3::origin(), Vector3::new(1.0, 1.0, 1.0));
+        occlusion.submit(second, Point3::origin(), Vector3::new(1.0, 1.0, 1.0));
+        assert_eq!(occlusion.boxes.len(), 1);
+        assert_eq!(occlusion.boxes[0].0, first);
+
+        assert!(!occlusion.is_occluded(first));
+        occlusion.occluded.add(first.id());
+        assert!(occlusion.is_occluded(first));
+        assert!(!occlusion.is_occluded(second));
+    }
+}
@@ -0,0 +1,29 @@
+//! Screen-space outlines drawn around the silhouettes of selected meshes.
+use amethyst_core::ecs::prelude::{Component, DenseVecStorage};
+
+/// Widest outline in pixels drawn by `RenderOutline`, wider outlines are clamped.
+pub const MAX_OUTLINE_WIDTH: f32 = 16.0;
+
+/// Component drawing an outline around the silhouette of the mesh of its entity, to show
+/// selection in strategy games and editors.
+///
+/// The silhouette is drawn over all other surfaces, so outlines stay visible behind
+/// obstacles. Outlines are drawn by the `RenderOutline` post-processing plugin.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Outlined {
+    /// Linear RGBA color of the outline, blended over the image by its alpha.
+    pub color: [f32; 4],
+    /// Width of the outline in pixels, up to [MAX_OUTLINE_WIDTH].
+    pub width: f32,
+}
+
+impl Outlined {
+    /// Outline of the given color and width in pixels.
+    pub fn new(color: [f32; 4], width: f32) -> Self {
+        Outlined { color, width }
+    }
+}
+
+impl Component for Outlined {
+    type Storage = DenseVecStorage<Self>;
+}
@@ -0,0 +1,566 @@
+//! Particles emitted by entities and drawn as camera facing quads, simulated on the CPU or,
+//! for large particle counts, on the GPU.
+use crate::{
+    pod::{GpuParticleArgs, IntoPod},
+    types::Texture,
+};
+use amethyst_assets::Handle;
+use amethyst_core::{
+    ecs::prelude::{
+        Component, DenseVecStorage, Entities, Join, Read, ReadStorage, System, Write, WriteStorage,
+    },
+    math::{convert, Matrix4, Point3, Vector3},
+    timing::Time,
+    Transform,
+};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+/// Value of a [ParticleCurve], interpolated linearly between keys.
+pub trait ParticleCurveValue: Copy {
+    /// Interpolate between `self` and `other` by `t` in `0.0..=1.0`.
+    fn lerp(&self, other: &Self, t: f32) -> Self;
+}
+
+impl ParticleCurveValue for f32 {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl ParticleCurveValue for [f32; 4] {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        let mut value = *self;
+        for (v, o) in value.iter_mut().zip(other) {
+            *v = v.lerp(o, t);
+        }
+        value
+    }
+}
+
+/// Value changing over the lifetime of a particle.
+///
+/// Keys are pairs of a time, from `0.0` when a particle is emitted to `1.0` when it dies,
+/// and the value at that time. Values are interpolated linearly between keys, and clamped
+/// to the first and last key outside of them.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ParticleCurve<T> {
+    keys: Vec<(f32, T)>,
+}
+
+impl<T: ParticleCurveValue> ParticleCurve<T> {
+    /// Curve keeping the same value.
+    pub fn constant(value: T) -> Self {
+        Self {
+            keys: vec![(0.0, value)],
+        }
+    }
+
+    /// Curve going from `start` to `end` over the lifetime.
+    pub fn linear(start: T, end: T) -> Self {
+        Self {
+            keys: vec![(0.0, start), (1.0, end)],
+        }
+    }
+
+    /// Curve through the given keys, which are sorted by time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `keys` is empty.
+    pub fn from_keys(mut keys: Vec<(f32, T)>) -> Self {
+        assert!(!keys.is_empty(), "Particle curve needs at least one key");
+        keys.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+        Self { keys }
+    }
+
+    /// Value of the curve at time `t`.
+    pub fn sample(&self, t: f32) -> T {
+        let next = self.keys.iter().position(|(time, _)| *time > t);
+        match next {
+            Some(0) => self.keys[0].1,
+            Some(next) => {
+                let (start_time, start) = &self.keys[next - 1];
+                let (end_time, end) = &self.keys[next];
+                start.lerp(end, (t - start_time) / (end_time - start_time))
+            }
+            None => self.keys[self.keys.len() - 1].1,
+        }
+    }
+}
+
+/// Particle simulated by a [ParticleEmitter].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Particle {
+    /// World position
+    pub position: Vector3<f32>,
+    /// Velocity in units per second, before the speed curve of the emitter is applied
+    pub velocity: Vector3<f32>,
+    /// Seconds since the particle was emitted
+    pub age: f32,
+    /// Width and height of the quad
+    pub size: f32,
+    /// Linear RGBA color, multiplied with the texture
+    pub color: [f32; 4],
+}
+
+/// Component emitting particles from the position of its entity.
+///
+/// Particles are simulated in world space by the [ParticleSimulationSystem], so they
+/// don't follow the emitter once emitted. They are drawn with `texture` by the
+/// `RenderParticles` plugin, and alpha blended with premultiplied colors: a color with
+/// zero alpha is blended additively.
+#[derive(Clone, Debug)]
+pub struct ParticleEmitter {
+    /// Texture of the particles.
+    pub texture: Handle<Texture>,
+    /// Whether new particles are emitted.
+    pub emitting: bool,
+    /// Particles emitted per second.
+    pub rate: f32,
+    /// Seconds a particle lives.
+    pub lifetime: f32,
+    /// Maximum number of particles alive at once.
+    pub max_particles: usize,
+    /// Velocity of emitted particles in the local space of the emitter.
+    pub velocity: Vector3<f32>,
+    /// Maximum random offset added to every axis of the velocity.
+    pub velocity_spread: f32,
+    /// Acceleration applied to particles in world space, e.g. gravity.
+    pub acceleration: Vector3<f32>,
+    /// Factor of the velocity over the lifetime.
+    pub speed: ParticleCurve<f32>,
+    /// Size of the particles over the lifetime.
+    pub size: ParticleCurve<f32>,
+    /// Linear RGBA color of the particles over the lifetime.
+    pub color: ParticleCurve<[f32; 4]>,
+    particles: Vec<Particle>,
+    pending: f32,
+    seed: u32,
+}
+
+impl Component for ParticleEmitter {
+    type Storage = DenseVecStorage<Self>;
+}
+
+impl ParticleEmitter {
+    /// Create an emitter of white particles drawn with `texture`, emitting 10 particles
+    /// per second moving up for a second.
+    pub fn new(texture: Handle<Texture>) -> Self {
+        Self {
+            texture,
+            emitting: true,
+            rate: 10.0,
+            lifetime: 1.0,
+            max_particles: 1000,
+            velocity: Vector3::y(),
+            velocity_spread: 0.0,
+            acceleration: Vector3::zeros(),
+            speed: ParticleCurve::constant(1.0),
+            size: ParticleCurve::constant(0.1),
+            color: ParticleCurve::constant([1.0; 4]),
+            particles: Vec::new(),
+            pending: 0.0,
+            seed: 0,
+        }
+    }
+
+    /// Set the number of particles emitted per second.
+    pub fn with_rate(mut self, rate: f32) -> Self {
+        self.rate = rate;
+        self
+    }
+
+    /// Set the number of seconds a particle lives.
+    pub fn with_lifetime(mut self, lifetime: f32) -> Self {
+        self.lifetime = lifetime;
+        self
+    }
+
+    /// Set the maximum number of particles alive at once.
+    pub fn with_max_particles(mut self, max_particles: usize) -> Self {
+        self.max_particles = max_particles;
+        self
+    }
+
+    /// Set the velocity of emitted particles, and the maximum random offset added to every axis.
+    pub fn with_velocity(mut self, velocity: Vector3<f32>, spread: f32) -> Self {
+        self.velocity = velocity;
+        self.velocity_spread = spread;
+        self
+    }
+
+    /// Set the acceleration applied to particles.
+    pub fn with_acceleration(mut self, acceleration: Vector3<f32>) -> Self {
+        self.acceleration = acceleration;
+        self
+    }
+
+    /// Set the factor of the velocity over the lifetime.
+    pub fn with_speed(mut self, speed: ParticleCurve<f32>) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// Set the size of the particles over the lifetime.
+    pub fn with_size(mut self, size: ParticleCurve<f32>) -> Self {
+        self.size = size;
+        self
+    }
+
+    /// Set the color of the particles over the lifetime.
+    pub fn with_color(mut self, color: ParticleCurve<[f32; 4]>) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Particles currently alive.
+    pub fn particles(&self) -> &[Particle] {
+        &self.particles
+    }
+
+    /// Remove all particles.
+    pub fn clear(&mut self) {
+        self.particles.clear();
+        self.pending = 0.0;
+    }
+
+    /// Advance the simulation by `delta` seconds and emit particles from `origin`.
+    pub(crate) fn update(&mut self, origin: &Matrix4<f32>, delta: f32, seed: u32) {
+        if self.seed == 0 {
+            self.seed = seed.wrapping_mul(0x9E37_79B9) | 1;
+        }
+
+        let lifetime = self.lifetime.max(f32::EPSILON);
+        let acceleration = self.acceleration * delta;
+        let (speed, size, color) = (&self.speed, &self.size, &self.color);
+        self.particles.retain(|p| p.age + delta < lifetime);
+        for particle in &mut self.particles {
+            particle.age += delta;
+            let t = particle.age / lifetime;
+            particle.velocity += acceleration;
+            particle.position += particle.velocity * (speed.sample(t) * delta);
+            particle.size = size.sample(t);
+            particle.color = color.sample(t);
+        }
+
+        if !self.emitting {
+            self.pending = 0.0;
+            return;
+        }
+        self.pending += self.rate * delta;
+        let count = self.pending as usize;
+        self.pending -= count as f32;
+
+        let position = origin.transform_point(&Point3::origin()).coords;
+        let velocity = origin.transform_vector(&self.velocity);
+        for _ in 0..count.min(self.max_particles.saturating_sub(self.particles.len())) {
+            let spread = Vector3::new(self.random(), self.random(), self.random());
+            self.particles.push(Particle {
+                position,
+                velocity: velocity + spread * self.velocity_spread,
+                age: 0.0,
+                size: self.size.sample(0.0),
+                color: self.color.sample(0.0),
+            });
+        }
+    }
+
+    fn random(&mut self) -> f32 {
+        random(&mut self.seed)
+    }
+}
+
+/// Random value in `-1.0..1.0`, advancing a non-zero `seed`.
+fn random(seed: &mut u32) -> f32 {
+    // xorshift32
+    *seed ^= *seed << 13;
+    *seed ^= *seed >> 17;
+    *seed ^= *seed << 5;
+    (*seed as f32 / u32::MAX as f32) * 2.0 - 1.0
+}
+
+/// Emit and move the particles of every [ParticleEmitter].
+///
+/// Note that this should run after `Transform` has been updated for the current frame.
+#[derive(Default, Debug)]
+pub struct ParticleSimulationSystem;
+
+impl ParticleSimulationSystem {
+    /// Create new particle simulation system
+    pub fn new() -> Self {
+        ParticleSimulationSystem
+    }
+}
+
+impl<'a> System<'a> for ParticleSimulationSystem {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, Time>,
+        ReadStorage<'a, Transform>,
+        WriteStorage<'a, ParticleEmitter>,
+    );
+
+    fn run(&mut self, (entities, time, transforms, mut emitters): Self::SystemData) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("particle_simulation_system");
+
+        let delta = time.delta_seconds();
+        for (entity, transform, emitter) in (&entities, &transforms, &mut emitters).join() {
+            let origin = convert::<_, Matrix4<f32>>(*transform.global_matrix());
+            emitter.update(&origin, delta, entity.id() + 1);
+        }
+    }
+}
+
+/// Component emitting particles simulated on the GPU, for much larger particle counts.
+///
+/// Emitted particles are written to the [GpuParticles] resource by the
+/// [GpuParticleSpawnSystem], and simulated by the compute node of the `RenderGpuParticles`
+/// plugin. Particles can't be read back and aren't sorted, their size and color change
+/// linearly over the lifetime and they are all drawn with the texture of [GpuParticles].
+#[derive(Clone, Debug)]
+pub struct GpuParticleEmitter {
+    /// Whether new particles are emitted.
+    pub emitting: bool,
+    /// Particles emitted per second.
+    pub rate: f32,
+    /// Seconds a particle lives.
+    pub lifetime: f32,
+    /// Velocity of emitted particles in the local space of the emitter.
+    pub velocity: Vector3<f32>,
+    /// Maximum random offset added to every axis of the velocity.
+    pub velocity_spread: f32,
+    /// Acceleration applied to particles in world space, e.g. gravity.
+    pub acceleration: Vector3<f32>,
+    /// Size of the particles when emitted and when dying.
+    pub size: (f32, f32),
+    /// Linear RGBA color of the particles when emitted and when dying.
+    pub color: ([f32; 4], [f32; 4]),
+    pending: f32,
+    seed: u32,
+}
+
+impl Component for GpuParticleEmitter {
+    type Storage = DenseVecStorage<Self>;
+}
+
+impl Default for GpuParticleEmitter {
+    fn default() -> Self {
+        Self {
+            emitting: true,
+            rate: 100.0,
+            lifetime: 1.0,
+            velocity: Vector3::y(),
+            velocity_spread: 0.0,
+            acceleration: Vector3::zeros(),
+            size: (0.1, 0.1),
+            color: ([1.0; 4], [1.0; 4]),
+            pending: 0.0,
+            seed: 0,
+        }
+    }
+}
+
+impl GpuParticleEmitter {
+    /// Create an emitter of white particles, emitting 100 particles per second moving up
+    /// for a second.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Set the number of particles emitted per second.
+    pub fn with_rate(mut self, rate: f32) -> Self {
+        self.rate = rate;
+        self
+    }
+
+    /// Set the number of seconds a particle lives.
+    pub fn with_lifetime(mut self, lifetime: f32) -> Self {
+        self.lifetime = lifetime;
+        self
+    }
+
+    /// Set the velocity of emitted particles, and the maximum random offset added to every axis.
+    pub fn with_velocity(mut self, velocity: Vector3<f32>, spread: f32) -> Self {
+        self.velocity = velocity;
+        self.velocity_spread = spread;
+        self
+    }
+
+    /// Set the acceleration applied to particles.
+    pub fn with_acceleration(mut self, acceleration: Vector3<f32>) -> Self {
+        self.acceleration = acceleration;
+        self
+    }
+
+    /// Set the size of the particles when emitted and when dying.
+    pub fn with_size(mut self, start: f32, end: f32) -> Self {
+        self.size = (start, end);
+        self
+    }
+
+    /// Set the color of the particles when emitted and when dying.
+    pub fn with_color(mut self, start: [f32; 4], end: [f32; 4]) -> Self {
+        self.color = (start, end);
+        self
+    }
+
+    /// Emit the particles of the last `delta` seconds from `origin` into `spawns`.
+    pub(crate) fn emit(
+        &mut self,
+        origin: &Matrix4<f32>,
+        delta: f32,
+        seed: u32,
+        spawns: &mut Vec<GpuParticleArgs>,
+    ) {
+        if self.seed == 0 {
+            self.seed = seed.wrapping_mul(0x9E37_79B9) | 1;
+        }
+        if !self.emitting {
+            self.pending = 0.0;
+            return;
+        }
+        self.pending += self.rate * delta;
+        let count = self.pending as usize;
+        self.pending -= count as f32;
+
+        let position = origin.transform_point(&Point3::origin()).coords;
+        let velocity = origin.transform_vector(&self.velocity);
+        for _ in 0..count {
+            let spread = Vector3::new(
+                random(&mut self.seed),
+                random(&mut self.seed),
+                random(&mut self.seed),
+            );
+            let velocity = velocity + spread * self.velocity_spread;
+            spawns.push(GpuParticleArgs {
+                position: position.push(0.0).into_pod(),
+                velocity: velocity.push(self.lifetime.max(f32::EPSILON)).into_pod(),
+                acceleration: self.acceleration.push(0.0).into_pod(),
+                size: [self.size.0, self.size.1, 0.0, 0.0].into(),
+                color_start: self.color.0.into(),
+                color_end: self.color.1.into(),
+            });
+        }
+    }
+}
+
+/// Resource shared by all [GpuParticleEmitter]s.
+#[derive(Debug, Default)]
+pub struct GpuParticles {
+    /// Texture of the particles, nothing is drawn until it's set and loaded.
+    pub texture: Option<Handle<Texture>>,
+    /// Particles emitted this frame, uploaded to the GPU by the simulation node.
+    pub(crate) spawns: Vec<GpuParticleArgs>,
+}
+
+/// Emit the particles of every [GpuParticleEmitter] into the [GpuParticles] resource.
+///
+/// Note that this should run after `Transform` has been updated for the current frame.
+#[derive(Default, Debug)]
+pub struct GpuParticleSpawnSystem;
+
+impl GpuParticleSpawnSystem {
+    /// Create new GPU particle spawn system
+    pub fn new() -> Self {
+        GpuParticleSpawnSystem
+    }
+}
+
+impl<'a> System<'a> for GpuParticleSpawnSystem {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, Time>,
+        Write<'a, GpuParticles>,
+        ReadStorage<'a, Transform>,
+        WriteStorage<'a, GpuParticleEmitter>,
+    );
+
+    fn run(&mut self, (entities, time, mut particles, transforms, mut emitters): Self::SystemData) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("gpu_particle_spawn_system");
+
+        let delta = time.delta_seconds();
+        let spawns = &mut particles.spawns;
+        spawns.clear();
+        for (entity, transform, emitter) in (&entities, &transforms, &mut emitters).join() {
+            let origin = convert::<_, Matrix4<f32>>(*transform.global_matrix());
+            emitter.emit(&origin, delta, entity.id() + 1, spawns);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render_texture::CameraTarget;
+    use amethyst_assets::{AssetStorage, Loader};
+    use rayon::ThreadPoolBuilder;
+    use std::sync::Arc;
+
+    #[test]
+    fn curve_interpolates_between_keys() {
+        let curve = ParticleCurve::from_keys(vec![(1.0, 4.0), (0.0, 0.0), (0.5, 1.0)]);
+        assert_eq!(curve.sample(-1.0), 0.0);
+        assert_eq!(curve.sample(0.25), 0.5);
+        assert_eq!(curve.sample(0.75), 2.5);
+        assert_eq!(curve.sample(2.0), 4.0);
+        assert_eq!(ParticleCurve::constant(3.0).sample(0.5), 3.0);
+    }
+
+    #[test]
+    fn emitter_spawns_and_expires_particles() {
+        let loader = Loader::new(".", Arc::new(ThreadPoolBuilder::new().build().unwrap()));
+        let texture = loader.load_from_data(
+            CameraTarget::texture_data(1, 1),
+            (),
+            &AssetStorage::<Texture>::new(),
+        );
+
+        let mut emitter = ParticleEmitter::new(texture)
+            .with_rate(10.0)
+            .with_lifetime(0.5)
+            .with_size(ParticleCurve::linear(1.0, 0.0));
+        let origin = Matrix4::new_translation(&Vector3::new(1.0, 2.0, 3.0));
+
+        emitter.update(&origin, 0.25, 1);
+        assert_eq!(emitter.particles().len(), 2);
+        assert_eq!(emitter.particles()[0].position, Vector3::new(1.0, 2.0, 3.0));
+
+        emitter.update(&origin, 0.2, 1);
+        assert_eq!(emitter.particles().len(), 4);
+        assert!((emitter.particles()[0].size - 0.6).abs() < 1e-5);
+        assert!((emitter.particles()[0].position.y - 2.2).abs() < 1e-5);
+
+        emitter.emitting = false;
+        emitter.update(&origin, 0.35, 1);
+        assert_eq!(emitter.particles().len(), 2);
+    }
+
+    #[test]
+    fn gpu_emitter_spawns_at_rate() {
+        let mut emitter = GpuParticleEmitter::new()
+            .with_rate(10.0)
+            .with_lifetime(2.0)
+            .with_size(1.0, 0.5);
+        let origin = Matrix4::new_translation(&Vector3::new(1.0, 2.0, 3.0));
+        let mut spawns = Vec::new();
+
+        emitter.emit(&origin, 0.25, 1, &mut spawns);
+        assert_eq!(spawns.len(), 2);
+        emitter.emit(&origin, 0.05, 1, &mut spawns);
+        assert_eq!(spawns.len(), 3);
+
+        let spawn = &spawns[0];
+        assert_eq!(spawn.position, [1.0, 2.0, 3.0, 0.0].into());
+        assert_eq!(spawn.velocity, [0.0, 1.0, 0.0, 2.0].into());
+        assert_eq!(spawn.size, [1.0, 0.5, 0.0, 0.0].into());
+
+        emitter.emitting = false;
+        emitter.emit(&origin, 1.0, 1, &mut spawns);
+        assert_eq!(spawns.len(), 3);
+    }
+}
@@ -1,11 +1,19 @@
 use crate::{
-    batch::{GroupIterator, OrderedTwoLevelBatch, TwoLevelBatch},
+    batch::{GroupIterator, TwoLevelBatch},
+    lightmap::LightmapTexCoord,
+    morph::{MorphPositions, MorphWeights},
     mtl::{FullTextureSet, Material, StaticTextureSet},
-    pipeline::{PipelineDescBuilder, PipelinesBuilder},
-    pod::{SkinnedVertexArgs, VertexArgs},
+    pipeline::{PipelineCache, PipelineDescBuilder, PipelinesBuilder},
+    pod::{MorphVertexArgs, SkinnedVertexArgs, VertexArgs},
+    render_mode::RenderMode,
     resources::Tint,
+    shader_reload::ReloadableShader,
     skinning::JointTransforms,
-    submodules::{DynamicVertexBuffer, EnvironmentSub, MaterialId, MaterialSub, SkinningSub},
+    stats::DrawCounter,
+    submodules::{
+        shadow_map_access, AmbientOcclusionSub, DynamicVertexBuffer, EnvironmentMapSub,
+        EnvironmentSub, MaterialId, MaterialSub, ShadowSub, SkinningSub,
+    },
     transparent::Transparent,
     types::{Backend, Mesh},
     util,
@@ -25,12 +33,12 @@ use rendy::{
         render::{PrepareResult, RenderGroup, RenderGroupDesc},
         GraphContext, NodeBuffer, NodeImage,
     },
-    hal::{self, device::Device, pso},
-    mesh::{AsVertex, VertexFormat},
-    shader::{Shader, SpirvShader},
+    hal::{self, device::Device, pso, PhysicalDevice},
+    mesh::{AsVertex, Color, TexCoord, VertexFormat},
+    shader::Shader,
 };
 use smallvec::SmallVec;
-use std::marker::PhantomData;
+use std::{marker::PhantomData, ops::Range};
 
 macro_rules! profile_scope_impl {
     ($string:expr) => {
@@ -52,20 +60,75 @@ pub trait Base3DPassDef: 'static + std::fmt::Debug + Send + Sync {
     /// The [mtl::StaticTextureSet] type implementation for this pass
     type TextureSet: for<'a> StaticTextureSet<'a>;
 
-    /// Returns the vertex `SpirvShader` which will be used for this pass
-    fn vertex_shader() -> &'static SpirvShader;
+    /// Returns the vertex shader which will be used for this pass
+    fn vertex_shader() -> &'static ReloadableShader;
+
+    /// Returns the vertex shader which will be used for this pass on skinned meshes
+    fn vertex_skinned_shader() -> &'static ReloadableShader;
+
+    /// Returns the vertex shader which will be used for this pass on meshes with morph targets,
+    /// if this pass supports them
+    fn vertex_morph_shader() -> Option<&'static ReloadableShader> {
+        None
+    }
+
+    /// Returns the fragment shader which will be used for this pass
+    fn fragment_shader() -> &'static ReloadableShader;
+
+    /// Returns the fragment shader sampling the directional and point light shadow maps,
+    /// if this pass supports shadows
+    fn shadowed_fragment_shader() -> Option<&'static ReloadableShader> {
+        None
+    }
+
+    /// Returns the fragment shader accumulating weighted blended order-independent
+    /// transparency into an accumulation and a revealage output, if this pass supports it
+    fn oit_fragment_shader() -> Option<&'static ReloadableShader> {
+        None
+    }
+
+    /// Returns the fragment shader drawing the unlit, normals and overdraw [RenderMode]s,
+    /// if this pass supports them. It reads the mode from a `uint` fragment push constant.
+    fn debug_fragment_shader() -> Option<&'static ReloadableShader> {
+        None
+    }
+
+    /// Returns true if the fragment shaders of this pass sample the `EnvironmentMap` cube maps
+    /// at set 3, moving the shadow sets after it
+    fn uses_environment_map() -> bool {
+        false
+    }
+
+    /// Returns true if the fragment shaders of this pass sample the screen-space ambient
+    /// occlusion image at the set after the environment maps, moving the shadow sets after it
+    fn uses_ambient_occlusion() -> bool {
+        false
+    }
 
-    /// Returns the vertex `SpirvShader` which will be used for this pass on skinned meshes
-    fn vertex_skinned_shader() -> &'static SpirvShader;
+    /// Returns true if the vertex shaders of this pass read [LightmapTexCoord] from a vertex
+    /// buffer bound after the instance rate one, which is the `TexCoord` of meshes without
+    /// lightmap coordinates
+    fn uses_lightmap_coords() -> bool {
+        false
+    }
 
-    /// Returns the fragment `SpirvShader` which will be used for this pass
-    fn fragment_shader() -> &'static SpirvShader;
+    /// Returns the number of color attachments the fragment shaders of this pass write to
+    fn color_outputs() -> usize {
+        1
+    }
 
     /// Returns the `VertexFormat` of this pass
     fn base_format() -> Vec<VertexFormat>;
 
     /// Returns the `VertexFormat` of this pass for skinned meshes
     fn skinned_format() -> Vec<VertexFormat>;
+
+    /// Returns the `VertexFormat` of this pass for meshes with morph targets
+    fn morph_format() -> Vec<VertexFormat> {
+        let mut format = Self::base_format();
+        format.push(MorphPositions::vertex());
+        format
+    }
 }
 
 /// Draw opaque 3d meshes with specified shaders and texture set
@@ -73,6 +136,10 @@ pub trait Base3DPassDef: 'static + std::fmt::Debug + Send + Sync {
 #[derivative(Debug(bound = ""), Default(bound = ""))]
 pub struct DrawBase3DDesc<B: Backend, T: Base3DPassDef> {
     skinning: bool,
+    shadows: bool,
+    point_shadows: bool,
+    spot_shadows: bool,
+    ambient_occlusion: bool,
     marker: PhantomData<(B, T)>,
 }
 
@@ -86,6 +153,10 @@ impl<B: Backend, T: Base3DPassDef> DrawBase3DDesc<B, T> {
     pub fn skinned() -> Self {
         Self {
             skinning: true,
+            shadows: false,
+            point_shadows: false,
+            spot_shadows: false,
+            ambient_occlusion: false,
             marker: PhantomData,
         }
     }
@@ -95,68 +166,198 @@ impl<B: Backend, T: Base3DPassDef> DrawBase3DDesc<B, T> {
         self.skinning = skinned;
         self
     }
+
+    /// Create pass sampling the directional light shadow map if true is passed.
+    ///
+    /// The shadow map image must be passed to the render group builder with `with_image`.
+    /// Ignored if the pass doesn't provide a shadowed fragment shader.
+    pub fn with_shadows(mut self, shadows: bool) -> Self {
+        self.shadows = shadows;
+        self
+    }
+
+    /// Create pass also sampling the point light shadow atlas if true is passed.
+    ///
+    /// The atlas image must be passed to the render group builder with `with_image`
+    /// after the directional light shadow map. Ignored without `with_shadows`.
+    pub fn with_point_shadows(mut self, point_shadows: bool) -> Self {
+        self.point_shadows = point_shadows;
+        self
+    }
+
+    /// Create pass also sampling the spot light shadow atlas if true is passed.
+    ///
+    /// The atlas image must be passed to the render group builder with `with_image`
+    /// after the directional and point light shadow maps. Ignored without `with_shadows`.
+    pub fn with_spot_shadows(mut self, spot_shadows: bool) -> Self {
+        self.spot_shadows = spot_shadows;
+        self
+    }
+
+    /// Create pass sampling the screen-space ambient occlusion image if true is passed.
+    ///
+    /// The image must be passed to the render group builder with `with_image`
+    /// after the shadow maps. Ignored if the pass doesn't use ambient occlusion.
+    pub fn with_ambient_occlusion(mut self, ambient_occlusion: bool) -> Self {
+        self.ambient_occlusion = ambient_occlusion;
+        self
+    }
+
+    fn shadowed(&self) -> bool {
+        self.shadows && T::shadowed_fragment_shader().is_some()
+    }
 }
 
 impl<B: Backend, T: Base3DPassDef> RenderGroupDesc<B, World> for DrawBase3DDesc<B, T> {
+    fn colors(&self) -> usize {
+        T::color_outputs()
+    }
+
+    fn images(&self) -> Vec<rendy::graph::ImageAccess> {
+        let shadow_maps = if self.shadowed() {
+            1 + self.point_shadows as usize + self.spot_shadows as usize
+        } else {
+            0
+        };
+        let occlusion = self.ambient_occlusion && T::uses_ambient_occlusion();
+        vec![shadow_map_access(); shadow_maps + occlusion as usize]
+    }
+
     fn build(
         self,
-        _ctx: &GraphContext<B>,
+        ctx: &GraphContext<B>,
         factory: &mut Factory<B>,
-        _queue: QueueId,
-        _aux: &World,
+        queue: QueueId,
+        aux: &World,
         framebuffer_width: u32,
         framebuffer_height: u32,
         subpass: hal::pass::Subpass<'_, B>,
         _buffers: Vec<NodeBuffer>,
-        _images: Vec<NodeImage>,
+        images: Vec<NodeImage>,
     ) -> Result<Box<dyn RenderGroup<B, World>>, failure::Error> {
         profile_scope_impl!("build");
 
+        // The fragment shaders look up light clusters with the projection.
         let env = EnvironmentSub::new(
             factory,
             [
-                hal::pso::ShaderStageFlags::VERTEX,
+                hal::pso::ShaderStageFlags::VERTEX | hal::pso::ShaderStageFlags::FRAGMENT,
                 hal::pso::ShaderStageFlags::FRAGMENT,
             ],
         )?;
         let materials = MaterialSub::new(factory)?;
         let skinning = SkinningSub::new(factory)?;
+        let environment_map = if T::uses_environment_map() {
+            Some(EnvironmentMapSub::new(factory, queue)?)
+        } else {
+            None
+        };
+        let mut images = images.iter();
+        let shadows = if self.shadowed() {
+            let directional = images.next().expect("Shadow map image is passed");
+            let point = if self.point_shadows {
+                images.next()
+            } else {
+                None
+            };
+            let spot = if self.spot_shadows {
+                images.next()
+            } else {
+                None
+            };
+            Some(ShadowSub::new(ctx, factory, directional, point, spot)?)
+        } else {
+            None
+        };
+        let ambient_occlusion = if T::uses_ambient_occlusion() {
+            let image = if self.ambient_occlusion {
+                images.next()
+            } else {
+                None
+            };
+            Some(AmbientOcclusionSub::new(ctx, factory, queue, image)?)
+        } else {
+            None
+        };
 
         let mut vertex_format_base = T::base_format();
         let mut vertex_format_skinned = T::skinned_format();
+        let mut vertex_format_morph = T::morph_format();
+
+        let mut layouts = vec![
+            env.raw_layout(),
+            materials.raw_layout(),
+            skinning.raw_layout(),
+        ];
+        layouts.extend(environment_map.iter().map(|sub| sub.raw_layout()));
+        layouts.extend(ambient_occlusion.iter().map(|sub| sub.raw_layout()));
+        let fragment_shader = match shadows.as_ref() {
+            Some(shadows) => {
+                layouts.extend(shadows.raw_layouts().iter());
+                T::shadowed_fragment_shader().unwrap()
+            }
+            None => T::fragment_shader(),
+        };
+
+        let pipeline_cache = aux.try_fetch::<PipelineCache<B>>();
 
-        let (mut pipelines, pipeline_layout) = build_pipelines::<B, T>(
+        let pipeline_layout = create_pipeline_layout(factory, layouts)?;
+        let mode = supported_mode::<B, T>(factory, RenderMode::fetch(aux));
+        let pipelines = match build_pipelines::<B, T>(
             factory,
+            pipeline_cache.as_ref().and_then(|cache| cache.raw()),
             subpass,
             framebuffer_width,
             framebuffer_height,
             &vertex_format_base,
             &vertex_format_skinned,
+            T::vertex_morph_shader().map(|shader| (&vertex_format_morph[..], shader)),
+            fragment_shader,
             self.skinning,
-            false,
-            vec![
-                env.raw_layout(),
-                materials.raw_layout(),
-                skinning.raw_layout(),
-            ],
-        )?;
+            Blending::Opaque,
+            mode,
+            &pipeline_layout,
+        ) {
+            Ok(pipelines) => pipelines,
+            Err(e) => {
+                unsafe {
+                    factory.device().destroy_pipeline_layout(pipeline_layout);
+                }
+                return Err(e);
+            }
+        };
 
         vertex_format_base.sort();
         vertex_format_skinned.sort();
+        vertex_format_morph.sort();
 
+        let mut pipelines = pipelines.into_iter();
         Ok(Box::new(DrawBase3D::<B, T> {
-            pipeline_basic: pipelines.remove(0),
-            pipeline_skinned: pipelines.pop(),
+            pipeline_basic: pipelines.next().unwrap(),
+            pipeline_skinned: if self.skinning {
+                pipelines.next()
+            } else {
+                None
+            },
+            pipeline_morph: pipelines.next(),
             pipeline_layout,
+            mode,
             static_batches: Default::default(),
             skinned_batches: Default::default(),
+            morph_batches: Default::default(),
             vertex_format_base,
             vertex_format_skinned,
+            vertex_format_morph,
             env,
             materials,
             skinning,
+            environment_map,
+            ambient_occlusion,
+            shadows,
             models: DynamicVertexBuffer::new(),
             skinned_models: DynamicVertexBuffer::new(),
+            morph_models: DynamicVertexBuffer::new(),
+            white: DynamicVertexBuffer::new(),
             marker: PhantomData,
         }))
     }
@@ -164,21 +365,40 @@ impl<B: Backend, T: Base3DPassDef> RenderGroupDesc<B, World> for DrawBase3DDesc<
 
 /// Base implementation of a 3D render pass which can be consumed by actual 3D render passes,
 /// such as [pass::pbr::DrawPbr]
+///
+/// Visible entities are batched by material and mesh, their transforms and tints uploaded to
+/// an instance-rate vertex buffer, so every batch is drawn with a single instanced draw call.
+///
+/// When the pass supports morph targets, entities with [MorphWeights] are drawn with the
+/// `MorphPositions` of their mesh blended by the weights. Morph targets of skinned entities
+/// are ignored.
+///
+/// The `Color` vertex attributes of meshes are multiplied with their tint, meshes without them
+/// are drawn with white vertex colors.
 #[derive(Derivative)]
 #[derivative(Debug(bound = ""))]
 pub struct DrawBase3D<B: Backend, T: Base3DPassDef> {
-    pipeline_basic: B::GraphicsPipeline,
-    pipeline_skinned: Option<B::GraphicsPipeline>,
+    pipeline_basic: ColorPipelines<B>,
+    pipeline_skinned: Option<ColorPipelines<B>>,
+    pipeline_morph: Option<ColorPipelines<B>>,
     pipeline_layout: B::PipelineLayout,
+    mode: RenderMode,
     static_batches: TwoLevelBatch<MaterialId, u32, SmallVec<[VertexArgs; 4]>>,
     skinned_batches: TwoLevelBatch<MaterialId, u32, SmallVec<[SkinnedVertexArgs; 4]>>,
+    morph_batches: TwoLevelBatch<MaterialId, u32, SmallVec<[MorphVertexArgs; 4]>>,
     vertex_format_base: Vec<VertexFormat>,
     vertex_format_skinned: Vec<VertexFormat>,
+    vertex_format_morph: Vec<VertexFormat>,
     env: EnvironmentSub<B>,
     materials: MaterialSub<B, T::TextureSet>,
     skinning: SkinningSub<B>,
+    environment_map: Option<EnvironmentMapSub<B>>,
+    ambient_occlusion: Option<AmbientOcclusionSub<B>>,
+    shadows: Option<ShadowSub<B>>,
     models: DynamicVertexBuffer<B, VertexArgs>,
     skinned_models: DynamicVertexBuffer<B, SkinnedVertexArgs>,
+    morph_models: DynamicVertexBuffer<B, MorphVertexArgs>,
+    white: DynamicVertexBuffer<B, Color>,
     marker: PhantomData<T>,
 }
 
@@ -203,6 +423,7 @@ impl<B: Backend, T: Base3DPassDef> RenderGroup<B, World> for DrawBase3D<B, T> {
             materials,
             transforms,
             joints,
+            morph_weights,
             tints,
         ) = <(
             Read<'_, AssetStorage<Mesh>>,
@@ -214,28 +435,52 @@ impl<B: Backend, T: Base3DPassDef> RenderGroup<B, World> for DrawBase3D<B, T> {
             ReadStorage<'_, Handle<Material>>,
             ReadStorage<'_, Transform>,
             ReadStorage<'_, JointTransforms>,
+            ReadStorage<'_, MorphWeights>,
             ReadStorage<'_, Tint>,
         )>::fetch(resources);
 
         // Prepare environment
         self.env.process(factory, index, resources);
+        if let Some(environment_map) = self.environment_map.as_mut() {
+            environment_map.process(factory, resources);
+        }
+        if let Some(shadows) = self.shadows.as_mut() {
+            shadows.process(factory, index, resources);
+        }
         self.materials.maintain();
 
         self.static_batches.clear_inner();
         self.skinned_batches.clear_inner();
+        self.morph_batches.clear_inner();
 
         let materials_ref = &mut self.materials;
         let skinning_ref = &mut self.skinning;
         let statics_ref = &mut self.static_batches;
         let skinned_ref = &mut self.skinned_batches;
-
-        let static_input = || ((&materials, &meshes, &transforms, tints.maybe()), !&joints);
+        let morph_ref = &mut self.morph_batches;
+
+        let morphing = self.pipeline_morph.is_some();
+        let static_input = || {
+            (
+                (&materials, &meshes, &transforms, tints.maybe()),
+                !&joints,
+                morph_weights.maybe(),
+            )
+        };
         let skinned_input = || (&materials, &meshes, &transforms, tints.maybe(), &joints);
+        let morph_input = || {
+            (
+                (&materials, &meshes, &transforms, tints.maybe()),
+                !&joints,
+                &morph_weights,
+            )
+        };
         {
             profile_scope_impl!("prepare");
             (static_input(), &visibility.visible_unordered)
                 .join()
-                .map(|(((mat, mesh, tform, tint), _), _)| {
+                .filter(|((_, _, weights), _)| !morphing || weights.is_none())
+                .map(|(((mat, mesh, tform, tint), _, _), _)| {
                     ((mat, mesh.id()), VertexArgs::from_object_data(tform, tint))
                 })
                 .for_each_group(|(mat, mesh_id), data| {
@@ -269,12 +514,32 @@ impl<B: Backend, T: Base3DPassDef> RenderGroup<B, World> for DrawBase3D<B, T> {
                     }
                 });
         };
+        if morphing {
+            profile_scope_impl!("prepare_morph");
+
+            (morph_input(), &visibility.visible_unordered)
+                .join()
+                .map(|(((mat, mesh, tform, tint), _, weights), _)| {
+                    (
+                        (mat, mesh.id()),
+                        MorphVertexArgs::from_object_data(tform, tint, weights),
+                    )
+                })
+                .for_each_group(|(mat, mesh_id), data| {
+                    if mesh_storage.contains_id(mesh_id) {
+                        if let Some((mat, _)) = materials_ref.insert(factory, resources, mat) {
+                            morph_ref.insert(mat, mesh_id, data.drain(..));
+                        }
+                    }
+                });
+        }
 
         {
             profile_scope_impl!("write");
 
             self.static_batches.prune();
             self.skinned_batches.prune();
+            self.morph_batches.prune();
 
             self.models.write(
                 factory,
@@ -290,6 +555,20 @@ impl<B: Backend, T: Base3DPassDef> RenderGroup<B, World> for DrawBase3D<B, T> {
                 self.skinned_batches.data(),
             );
             self.skinning.commit(factory, index);
+
+            self.morph_models.write(
+                factory,
+                index,
+                self.morph_batches.count() as u64,
+                self.morph_batches.data(),
+            );
+
+            let instances = self
+                .static_batches
+                .count()
+                .max(self.skinned_batches.count())
+                .max(self.morph_batches.count());
+            write_white(&mut self.white, factory, index, instances);
         }
         PrepareResult::DrawRecord
     }
@@ -306,11 +585,26 @@ impl<B: Backend, T: Base3DPassDef> RenderGroup<B, World> for DrawBase3D<B, T> {
         let mesh_storage = <Read<'_, AssetStorage<Mesh>>>::fetch(resources);
         let models_loc = self.vertex_format_base.len() as u32;
         let skin_models_loc = self.vertex_format_skinned.len() as u32;
+        let morph_models_loc = self.vertex_format_morph.len() as u32;
+        let mut draws = DrawCounter::fetch(resources);
 
-        encoder.bind_graphics_pipeline(&self.pipeline_basic);
+        encoder.bind_graphics_pipeline(&self.pipeline_basic.plain);
+        push_render_mode(&mut encoder, &self.pipeline_layout, self.mode);
         self.env.bind(index, &self.pipeline_layout, 0, &mut encoder);
+        if let Some(environment_map) = self.environment_map.as_ref() {
+            environment_map.bind(&self.pipeline_layout, 3, &mut encoder);
+        }
+        let occlusion_set = 3 + self.environment_map.is_some() as u32;
+        if let Some(ambient_occlusion) = self.ambient_occlusion.as_ref() {
+            ambient_occlusion.bind(&self.pipeline_layout, occlusion_set, &mut encoder);
+        }
+        if let Some(shadows) = self.shadows.as_ref() {
+            let set_id = occlusion_set + self.ambient_occlusion.is_some() as u32;
+            shadows.bind(index, &self.pipeline_layout, set_id, &mut encoder);
+        }
 
         if self.models.bind(index, models_loc, 0, &mut encoder) {
+            let mut bound_colored = Some(false);
             let mut instances_drawn = 0;
             for (&mat_id, batches) in self.static_batches.iter() {
                 if self.materials.loaded(mat_id) {
@@ -321,6 +615,15 @@ impl<B: Backend, T: Base3DPassDef> RenderGroup<B, World> for DrawBase3D<B, T> {
                         if let Some(mesh) =
                             B::unwrap_mesh(unsafe { mesh_storage.get_by_id_unchecked(*mesh_id) })
                         {
+                            bind_lightmap_coords::<B, T>(mesh, models_loc + 1, &mut encoder);
+                            self.pipeline_basic.bind_colors(
+                                mesh,
+                                &self.white,
+                                index,
+                                color_binding::<T>(models_loc),
+                                &mut bound_colored,
+                                &mut encoder,
+                            );
                             mesh.bind_and_draw(
                                 0,
                                 &self.vertex_format_base,
@@ -328,6 +631,7 @@ impl<B: Backend, T: Base3DPassDef> RenderGroup<B, World> for DrawBase3D<B, T> {
                                 &mut encoder,
                             )
                             .unwrap();
+                            draws.draw(mesh.primitive(), mesh.len(), batch_data.len() as u32);
                         }
                         instances_drawn += batch_data.len() as u32;
                     }
@@ -336,8 +640,6 @@ impl<B: Backend, T: Base3DPassDef> RenderGroup<B, World> for DrawBase3D<B, T> {
         }
 
         if let Some(pipeline_skinned) = self.pipeline_skinned.as_ref() {
-            encoder.bind_graphics_pipeline(pipeline_skinned);
-
             if self
                 .skinned_models
                 .bind(index, skin_models_loc, 0, &mut encoder)
@@ -345,6 +647,7 @@ impl<B: Backend, T: Base3DPassDef> RenderGroup<B, World> for DrawBase3D<B, T> {
                 self.skinning
                     .bind(index, &self.pipeline_layout, 2, &mut encoder);
 
+                let mut bound_colored = None;
                 let mut instances_drawn = 0;
                 for (&mat_id, batches) in self.skinned_batches.iter() {
                     if self.materials.loaded(mat_id) {
@@ -355,6 +658,19 @@ impl<B: Backend, T: Base3DPassDef> RenderGroup<B, World> for DrawBase3D<B, T> {
                             if let Some(mesh) = B::unwrap_mesh(unsafe {
                                 mesh_storage.get_by_id_unchecked(*mesh_id)
                             }) {
+                                bind_lightmap_coords::<B, T>(
+                                    mesh,
+                                    skin_models_loc + 1,
+                                    &mut encoder,
+                                );
+                                pipeline_skinned.bind_colors(
+                                    mesh,
+                                    &self.white,
+                                    index,
+                                    color_binding::<T>(skin_models_loc),
+                                    &mut bound_colored,
+                                    &mut encoder,
+                                );
                                 mesh.bind_and_draw(
                                     0,
                                     &self.vertex_format_skinned,
@@ -362,6 +678,63 @@ impl<B: Backend, T: Base3DPassDef> RenderGroup<B, World> for DrawBase3D<B, T> {
                                     &mut encoder,
                                 )
                                 .unwrap();
+                                draws.draw(mesh.primitive(), mesh.len(), batch_data.len() as u32);
+                            }
+                            instances_drawn += batch_data.len() as u32;
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Some(pipeline_morph) = self.pipeline_morph.as_ref() {
+            if self
+                .morph_models
+                .bind(index, morph_models_loc, 0, &mut encoder)
+            {
+                let mut bound_colored = None;
+                let mut instances_drawn = 0;
+                for (&mat_id, batches) in self.morph_batches.iter() {
+                    if self.materials.loaded(mat_id) {
+                        self.materials
+                            .bind(&self.pipeline_layout, 1, mat_id, &mut encoder);
+                        for (mesh_id, batch_data) in batches {
+                            debug_assert!(mesh_storage.contains_id(*mesh_id));
+                            if let Some(mesh) = B::unwrap_mesh(unsafe {
+                                mesh_storage.get_by_id_unchecked(*mesh_id)
+                            }) {
+                                bind_lightmap_coords::<B, T>(
+                                    mesh,
+                                    morph_models_loc + 1,
+                                    &mut encoder,
+                                );
+                                pipeline_morph.bind_colors(
+                                    mesh,
+                                    &self.white,
+                                    index,
+                                    color_binding::<T>(morph_models_loc),
+                                    &mut bound_colored,
+                                    &mut encoder,
+                                );
+                                if let Err(error) = mesh.bind_and_draw(
+                                    0,
+                                    &self.vertex_format_morph,
+                                    instances_drawn..instances_drawn + batch_data.len() as u32,
+                                    &mut encoder,
+                                ) {
+                                    log::warn!(
+                                        "Trying to draw a morphed mesh that lacks {:?} vertex attributes. Pass {} requires attributes {:?}.",
+                                        error.not_found.attributes,
+                                        T::NAME,
+                                        T::morph_format(),
+                                    );
+                                } else {
+                                    draws.draw(
+                                        mesh.primitive(),
+                                        mesh.len(),
+                                        batch_data.len() as u32,
+                                    );
+                                }
                             }
                             instances_drawn += batch_data.len() as u32;
                         }
@@ -374,11 +747,12 @@ impl<B: Backend, T: Base3DPassDef> RenderGroup<B, World> for DrawBase3D<B, T> {
     fn dispose(mut self: Box<Self>, factory: &mut Factory<B>, _aux: &World) {
         profile_scope_impl!("dispose");
         unsafe {
-            factory
-                .device()
-                .destroy_graphics_pipeline(self.pipeline_basic);
+            self.pipeline_basic.dispose(factory);
             if let Some(pipeline) = self.pipeline_skinned.take() {
-                factory.device().destroy_graphics_pipeline(pipeline);
+                pipeline.dispose(factory);
+            }
+            if let Some(pipeline) = self.pipeline_morph.take() {
+                pipeline.dispose(factory);
             }
             factory
                 .device()
@@ -392,6 +766,7 @@ impl<B: Backend, T: Base3DPassDef> RenderGroup<B, World> for DrawBase3D<B, T> {
 #[derivative(Debug(bound = ""), Default(bound = ""))]
 pub struct DrawBase3DTransparentDesc<B: Backend, T: Base3DPassDef> {
     skinning: bool,
+    order_independent: bool,
     marker: PhantomData<(B, T)>,
 }
 
@@ -400,6 +775,7 @@ impl<B: Backend, T: Base3DPassDef> DrawBase3DTransparentDesc<B, T> {
     pub fn new() -> Self {
         Self {
             skinning: false,
+            order_independent: false,
             marker: PhantomData,
         }
     }
@@ -408,6 +784,7 @@ impl<B: Backend, T: Base3DPassDef> DrawBase3DTransparentDesc<B, T> {
     pub fn skinned() -> Self {
         Self {
             skinning: true,
+            order_independent: false,
             marker: PhantomData,
         }
     }
@@ -417,50 +794,122 @@ impl<B: Backend, T: Base3DPassDef> DrawBase3DTransparentDesc<B, T> {
         self.skinning = skinned;
         self
     }
+
+    /// Accumulate weighted blended order-independent transparency instead of blending in
+    /// order, into an `Rgba16Sfloat` accumulation output cleared to zero and a single channel
+    /// revealage output cleared to one, e.g. of [Target::OrderIndependentTransparency].
+    ///
+    /// Requires the [Base3DPassDef::oit_fragment_shader] of the pass. Debug render modes are
+    /// drawn shaded.
+    ///
+    /// [Target::OrderIndependentTransparency]: crate::bundle::Target::OrderIndependentTransparency
+    pub fn with_order_independence(mut self, order_independent: bool) -> Self {
+        self.order_independent = order_independent;
+        self
+    }
 }
 
 impl<B: Backend, T: Base3DPassDef> RenderGroupDesc<B, World> for DrawBase3DTransparentDesc<B, T> {
+    fn colors(&self) -> usize {
+        if self.order_independent {
+            2
+        } else {
+            T::color_outputs()
+        }
+    }
+
     fn build(
         self,
-        _ctx: &GraphContext<B>,
+        ctx: &GraphContext<B>,
         factory: &mut Factory<B>,
-        _queue: QueueId,
-        _aux: &World,
+        queue: QueueId,
+        aux: &World,
         framebuffer_width: u32,
         framebuffer_height: u32,
         subpass: hal::pass::Subpass<'_, B>,
         _buffers: Vec<NodeBuffer>,
         _images: Vec<NodeImage>,
     ) -> Result<Box<dyn RenderGroup<B, World>>, failure::Error> {
+        // The fragment shaders look up light clusters with the projection.
         let env = EnvironmentSub::new(
             factory,
             [
-                hal::pso::ShaderStageFlags::VERTEX,
+                hal::pso::ShaderStageFlags::VERTEX | hal::pso::ShaderStageFlags::FRAGMENT,
                 hal::pso::ShaderStageFlags::FRAGMENT,
             ],
         )?;
 
         let materials = MaterialSub::new(factory)?;
         let skinning = SkinningSub::new(factory)?;
+        let environment_map = if T::uses_environment_map() {
+            Some(EnvironmentMapSub::new(factory, queue)?)
+        } else {
+            None
+        };
+        // Transparent meshes aren't drawn to the occlusion image, so they are never occluded.
+        let ambient_occlusion = if T::uses_ambient_occlusion() {
+            Some(AmbientOcclusionSub::new(ctx, factory, queue, None)?)
+        } else {
+            None
+        };
 
         let mut vertex_format_base = T::base_format();
         let mut vertex_format_skinned = T::skinned_format();
 
-        let (mut pipelines, pipeline_layout) = build_pipelines::<B, T>(
+        let mut layouts = vec![
+            env.raw_layout(),
+            materials.raw_layout(),
+            skinning.raw_layout(),
+        ];
+        layouts.extend(environment_map.iter().map(|sub| sub.raw_layout()));
+        layouts.extend(ambient_occlusion.iter().map(|sub| sub.raw_layout()));
+
+        let (fragment_shader, blending, mode) = if self.order_independent {
+            let fragment_shader = T::oit_fragment_shader().ok_or_else(|| {
+                failure::format_err!(
+                    "Pass {} doesn't support order-independent transparency",
+                    T::NAME
+                )
+            })?;
+            (
+                fragment_shader,
+                Blending::OrderIndependent,
+                RenderMode::Shaded,
+            )
+        } else {
+            (
+                T::fragment_shader(),
+                Blending::Sorted,
+                supported_mode::<B, T>(factory, RenderMode::fetch(aux)),
+            )
+        };
+
+        let pipeline_cache = aux.try_fetch::<PipelineCache<B>>();
+
+        let pipeline_layout = create_pipeline_layout(factory, layouts)?;
+        let mut pipelines = match build_pipelines::<B, T>(
             factory,
+            pipeline_cache.as_ref().and_then(|cache| cache.raw()),
             subpass,
             framebuffer_width,
             framebuffer_height,
             &vertex_format_base,
             &vertex_format_skinned,
+            None,
+            fragment_shader,
             self.skinning,
-            true,
-            vec![
-                env.raw_layout(),
-                materials.raw_layout(),
-                skinning.raw_layout(),
-            ],
-        )?;
+            blending,
+            mode,
+            &pipeline_layout,
+        ) {
+            Ok(pipelines) => pipelines,
+            Err(e) => {
+                unsafe {
+                    factory.device().destroy_pipeline_layout(pipeline_layout);
+                }
+                return Err(e);
+            }
+        };
 
         vertex_format_base.sort();
         vertex_format_skinned.sort();
@@ -469,37 +918,62 @@ impl<B: Backend, T: Base3DPassDef> RenderGroupDesc<B, World> for DrawBase3DTrans
             pipeline_basic: pipelines.remove(0),
             pipeline_skinned: pipelines.pop(),
             pipeline_layout,
-            static_batches: Default::default(),
-            skinned_batches: Default::default(),
+            mode,
+            draws: Vec::new(),
+            old_draws: Vec::new(),
+            static_data: Vec::new(),
+            skinned_data: Vec::new(),
             vertex_format_base,
             vertex_format_skinned,
             env,
             materials,
             skinning,
+            environment_map,
+            ambient_occlusion,
             models: DynamicVertexBuffer::new(),
             skinned_models: DynamicVertexBuffer::new(),
+            white: DynamicVertexBuffer::new(),
             change: Default::default(),
             marker: PhantomData,
         }))
     }
 }
 
+/// Instances of a mesh drawn with one instanced draw call by [DrawBase3DTransparent].
+#[derive(Debug, Clone, PartialEq)]
+struct TransparentDraw {
+    skinned: bool,
+    material: MaterialId,
+    mesh: u32,
+    range: Range<u32>,
+}
+
 /// Draw transparent mesh with physically based lighting
+///
+/// Meshes are drawn back to front by their view space depth, static and skinned ones
+/// interleaved, so only consecutive entities sharing a material and mesh are batched into one
+/// instanced draw call.
 #[derive(Derivative)]
 #[derivative(Debug(bound = ""))]
 pub struct DrawBase3DTransparent<B: Backend, T: Base3DPassDef> {
-    pipeline_basic: B::GraphicsPipeline,
-    pipeline_skinned: Option<B::GraphicsPipeline>,
+    pipeline_basic: ColorPipelines<B>,
+    pipeline_skinned: Option<ColorPipelines<B>>,
     pipeline_layout: B::PipelineLayout,
-    static_batches: OrderedTwoLevelBatch<MaterialId, u32, VertexArgs>,
-    skinned_batches: OrderedTwoLevelBatch<MaterialId, u32, SkinnedVertexArgs>,
+    mode: RenderMode,
+    draws: Vec<TransparentDraw>,
+    old_draws: Vec<TransparentDraw>,
+    static_data: Vec<VertexArgs>,
+    skinned_data: Vec<SkinnedVertexArgs>,
     vertex_format_base: Vec<VertexFormat>,
     vertex_format_skinned: Vec<VertexFormat>,
     env: EnvironmentSub<B>,
     materials: MaterialSub<B, FullTextureSet>,
     skinning: SkinningSub<B>,
+    environment_map: Option<EnvironmentMapSub<B>>,
+    ambient_occlusion: Option<AmbientOcclusionSub<B>>,
     models: DynamicVertexBuffer<B, VertexArgs>,
     skinned_models: DynamicVertexBuffer<B, SkinnedVertexArgs>,
+    white: DynamicVertexBuffer<B, Color>,
     change: util::ChangeDetection,
     marker: PhantomData<T>,
 }
@@ -530,80 +1004,96 @@ impl<B: Backend, T: Base3DPassDef> RenderGroup<B, World> for DrawBase3DTranspare
         self.env.process(factory, index, resources);
         self.materials.maintain();
 
-        self.static_batches.swap_clear();
-        self.skinned_batches.swap_clear();
+        std::mem::swap(&mut self.draws, &mut self.old_draws);
+        self.draws.clear();
+        self.static_data.clear();
+        self.skinned_data.clear();
 
         let materials_ref = &mut self.materials;
         let skinning_ref = &mut self.skinning;
-        let statics_ref = &mut self.static_batches;
-        let skinned_ref = &mut self.skinned_batches;
-        let mut changed = false;
+        let draws_ref = &mut self.draws;
+        let statics_ref = &mut self.static_data;
+        let skinned_ref = &mut self.skinned_data;
+        let skinning = self.pipeline_skinned.is_some();
+        let mut changed = self
+            .environment_map
+            .as_mut()
+            .map_or(false, |environment_map| {
+                environment_map.process(factory, resources)
+            });
 
-        let mut joined = ((&materials, &meshes, &transforms, tints.maybe()), !&joints).join();
+        // Static and skinned meshes are grouped in a single pass to keep their relative order.
+        let mut joined = (
+            &materials,
+            &meshes,
+            &transforms,
+            tints.maybe(),
+            joints.maybe(),
+        )
+            .join();
         visibility
             .visible_ordered
             .iter()
             .filter_map(|e| joined.get_unchecked(e.id()))
-            .map(|((mat, mesh, tform, tint), _)| {
-                ((mat, mesh.id()), VertexArgs::from_object_data(tform, tint))
+            .filter(|(.., joints)| skinning || joints.is_none())
+            .map(|(mat, mesh, tform, tint, joints)| {
+                ((mat, mesh.id(), joints.is_some()), (tform, tint, joints))
             })
-            .for_each_group(|(mat, mesh_id), data| {
-                if mesh_storage.contains_id(mesh_id) {
-                    if let Some((mat, this_changed)) = materials_ref.insert(factory, resources, mat)
-                    {
-                        changed = changed || this_changed;
-                        statics_ref.insert(mat, mesh_id, data.drain(..));
-                    }
+            .for_each_group(|(mat, mesh_id, skinned), data| {
+                if !mesh_storage.contains_id(mesh_id) {
+                    return;
+                }
+                if let Some((mat, this_changed)) = materials_ref.insert(factory, resources, mat) {
+                    changed = changed || this_changed;
+                    let range = if skinned {
+                        let start = skinned_ref.len() as u32;
+                        skinned_ref.extend(data.drain(..).filter_map(|(tform, tint, joints)| {
+                            joints.map(|joints| {
+                                SkinnedVertexArgs::from_object_data(
+                                    tform,
+                                    tint,
+                                    skinning_ref.insert(joints),
+                                )
+                            })
+                        }));
+                        start..skinned_ref.len() as u32
+                    } else {
+                        let start = statics_ref.len() as u32;
+                        statics_ref.extend(
+                            data.drain(..)
+                                .map(|(tform, tint, _)| VertexArgs::from_object_data(tform, tint)),
+                        );
+                        start..statics_ref.len() as u32
+                    };
+                    draws_ref.push(TransparentDraw {
+                        skinned,
+                        material: mat,
+                        mesh: mesh_id,
+                        range,
+                    });
                 }
             });
 
-        if self.pipeline_skinned.is_some() {
-            let mut joined = (&materials, &meshes, &transforms, tints.maybe(), &joints).join();
-
-            visibility
-                .visible_ordered
-                .iter()
-                .filter_map(|e| joined.get_unchecked(e.id()))
-                .map(|(mat, mesh, tform, tint, joints)| {
-                    (
-                        (mat, mesh.id()),
-                        SkinnedVertexArgs::from_object_data(
-                            tform,
-                            tint,
-                            skinning_ref.insert(joints),
-                        ),
-                    )
-                })
-                .for_each_group(|(mat, mesh_id), data| {
-                    if mesh_storage.contains_id(mesh_id) {
-                        if let Some((mat, this_changed)) =
-                            materials_ref.insert(factory, resources, mat)
-                        {
-                            changed = changed || this_changed;
-                            skinned_ref.insert(mat, mesh_id, data.drain(..));
-                        }
-                    }
-                });
-        }
-
         self.models.write(
             factory,
             index,
-            self.static_batches.count() as u64,
-            Some(self.static_batches.data()),
+            self.static_data.len() as u64,
+            Some(&self.static_data),
         );
 
         self.skinned_models.write(
             factory,
             index,
-            self.skinned_batches.count() as u64,
-            Some(self.skinned_batches.data()),
+            self.skinned_data.len() as u64,
+            Some(&self.skinned_data),
         );
 
         self.skinning.commit(factory, index);
 
-        changed = changed || self.static_batches.changed();
-        changed = changed || self.skinned_batches.changed();
+        let instances = self.static_data.len().max(self.skinned_data.len());
+        write_white(&mut self.white, factory, index, instances);
+
+        changed = changed || self.draws != self.old_draws;
 
         self.change.prepare_result(index, changed)
     }
@@ -620,70 +1110,97 @@ impl<B: Backend, T: Base3DPassDef> RenderGroup<B, World> for DrawBase3DTranspare
         let mesh_storage = <Read<'_, AssetStorage<Mesh>>>::fetch(resources);
         let layout = &self.pipeline_layout;
         let encoder = &mut encoder;
+        let mut draws = DrawCounter::fetch(resources);
 
         let models_loc = self.vertex_format_base.len() as u32;
         let skin_models_loc = self.vertex_format_skinned.len() as u32;
 
-        encoder.bind_graphics_pipeline(&self.pipeline_basic);
+        encoder.bind_graphics_pipeline(&self.pipeline_basic.plain);
+        push_render_mode(encoder, layout, self.mode);
         self.env.bind(index, layout, 0, encoder);
+        if let Some(environment_map) = self.environment_map.as_ref() {
+            environment_map.bind(layout, 3, encoder);
+        }
+        if let Some(ambient_occlusion) = self.ambient_occlusion.as_ref() {
+            let set_id = 3 + self.environment_map.is_some() as u32;
+            ambient_occlusion.bind(layout, set_id, encoder);
+        }
 
-        if self.models.bind(index, models_loc, 0, encoder) {
-            for (&mat, batches) in self.static_batches.iter() {
-                if self.materials.loaded(mat) {
-                    self.materials.bind(layout, 1, mat, encoder);
-                    for (mesh, range) in batches {
-                        debug_assert!(mesh_storage.contains_id(*mesh));
-                        if let Some(mesh) =
-                            B::unwrap_mesh(unsafe { mesh_storage.get_by_id_unchecked(*mesh) })
-                        {
-                            if let Err(error) = mesh.bind_and_draw(
-                                0,
-                                &self.vertex_format_base,
-                                range.clone(),
-                                encoder,
-                            ) {
-                                log::warn!(
-                                    "Trying to draw a mesh that lacks {:?} vertex attributes. Pass {} requires attributes {:?}.",
-                                    error.not_found.attributes,
-                                    T::NAME,
-                                    T::base_format(),
-                                );
-                            }
-                        }
-                    }
+        if self.draws.iter().any(|draw| draw.skinned) {
+            self.skinning.bind(index, layout, 2, encoder);
+        }
+
+        // Skinned meshes bind more vertex buffers, overwriting the instance buffer of static
+        // meshes, so the pipeline and instance buffer are bound again whenever the kind changes.
+        let mut bound_skinned = None;
+        let mut bound_colored = None;
+        let mut bound_material = None;
+        for draw in &self.draws {
+            if !self.materials.loaded(draw.material) {
+                continue;
+            }
+            let pipelines = if draw.skinned {
+                match self.pipeline_skinned.as_ref() {
+                    Some(pipeline_skinned) => pipeline_skinned,
+                    None => continue,
+                }
+            } else {
+                &self.pipeline_basic
+            };
+            if bound_skinned != Some(draw.skinned) {
+                let bound = if draw.skinned {
+                    self.skinned_models.bind(index, skin_models_loc, 0, encoder)
+                } else {
+                    self.models.bind(index, models_loc, 0, encoder)
+                };
+                if !bound {
+                    continue;
                 }
+                bound_skinned = Some(draw.skinned);
+                bound_colored = None;
+            }
+            if bound_material != Some(draw.material) {
+                self.materials.bind(layout, 1, draw.material, encoder);
+                bound_material = Some(draw.material);
             }
-        }
 
-        if let Some(pipeline_skinned) = self.pipeline_skinned.as_ref() {
-            encoder.bind_graphics_pipeline(pipeline_skinned);
-
-            if self.skinned_models.bind(index, skin_models_loc, 0, encoder) {
-                self.skinning.bind(index, layout, 2, encoder);
-                for (&mat, batches) in self.skinned_batches.iter() {
-                    if self.materials.loaded(mat) {
-                        self.materials.bind(layout, 1, mat, encoder);
-                        for (mesh, range) in batches {
-                            debug_assert!(mesh_storage.contains_id(*mesh));
-                            if let Some(mesh) =
-                                B::unwrap_mesh(unsafe { mesh_storage.get_by_id_unchecked(*mesh) })
-                            {
-                                if let Err(error) = mesh.bind_and_draw(
-                                    0,
-                                    &self.vertex_format_skinned,
-                                    range.clone(),
-                                    encoder,
-                                ) {
-                                    log::warn!(
-                                        "Trying to draw a skinned mesh that lacks {:?} vertex attributes. Pass {} requires attributes {:?}.",
-                                        error.not_found.attributes,
-                                        T::NAME,
-                                        T::skinned_format(),
-                                    );
-                                }
-                            }
-                        }
+            debug_assert!(mesh_storage.contains_id(draw.mesh));
+            if let Some(mesh) =
+                B::unwrap_mesh(unsafe { mesh_storage.get_by_id_unchecked(draw.mesh) })
+            {
+                let (format, models_loc) = if draw.skinned {
+                    (&self.vertex_format_skinned, skin_models_loc)
+                } else {
+                    (&self.vertex_format_base, models_loc)
+                };
+                bind_lightmap_coords::<B, T>(mesh, models_loc + 1, encoder);
+                pipelines.bind_colors(
+                    mesh,
+                    &self.white,
+                    index,
+                    color_binding::<T>(models_loc),
+                    &mut bound_colored,
+                    encoder,
+                );
+                if let Err(error) = mesh.bind_and_draw(0, format, draw.range.clone(), encoder) {
+                    if draw.skinned {
+                        log::warn!(
+                            "Trying to draw a skinned mesh that lacks {:?} vertex attributes. Pass {} requires attributes {:?}.",
+                            error.not_found.attributes,
+                            T::NAME,
+                            T::skinned_format(),
+                        );
+                    } else {
+                        log::warn!(
+                            "Trying to draw a mesh that lacks {:?} vertex attributes. Pass {} requires attributes {:?}.",
+                            error.not_found.attributes,
+                            T::NAME,
+                            T::base_format(),
+                        );
                     }
+                } else {
+                    let instances = draw.range.end - draw.range.start;
+                    draws.draw(mesh.primitive(), mesh.len(), instances);
                 }
             }
         }
@@ -691,11 +1208,9 @@ impl<B: Backend, T: Base3DPassDef> RenderGroup<B, World> for DrawBase3DTranspare
 
     fn dispose(mut self: Box<Self>, factory: &mut Factory<B>, _aux: &World) {
         unsafe {
-            factory
-                .device()
-                .destroy_graphics_pipeline(self.pipeline_basic);
+            self.pipeline_basic.dispose(factory);
             if let Some(pipeline) = self.pipeline_skinned.take() {
-                factory.device().destroy_graphics_pipeline(pipeline);
+                pipeline.dispose(factory);
             }
             factory
                 .device()
@@ -704,105 +1219,323 @@ impl<B: Backend, T: Base3DPassDef> RenderGroup<B, World> for DrawBase3DTranspare
     }
 }
 
+/// Returns the mode drawn by a pass for the requested `RenderMode`, falling back to shaded for
+/// modes the pass or the device doesn't support.
+fn supported_mode<B: Backend, T: Base3DPassDef>(
+    factory: &Factory<B>,
+    mode: RenderMode,
+) -> RenderMode {
+    let supported = match mode {
+        RenderMode::Shaded => true,
+        RenderMode::Wireframe => factory
+            .physical()
+            .features()
+            .contains(hal::Features::NON_FILL_POLYGON_MODE),
+        _ => T::debug_fragment_shader().is_some(),
+    };
+    if supported {
+        mode
+    } else {
+        RenderMode::Shaded
+    }
+}
+
+/// Push the mode read by the debug fragment shaders, shared by all pipelines of a pass.
+fn push_render_mode<B: Backend>(
+    encoder: &mut RenderPassEncoder<'_, B>,
+    layout: &B::PipelineLayout,
+    mode: RenderMode,
+) {
+    unsafe {
+        encoder.push_constants(layout, pso::ShaderStageFlags::FRAGMENT, 0, &[mode as u32]);
+    }
+}
+
+fn create_pipeline_layout<B: Backend>(
+    factory: &Factory<B>,
+    layouts: Vec<&B::DescriptorSetLayout>,
+) -> Result<B::PipelineLayout, failure::Error> {
+    // The debug fragment shaders read the `RenderMode` from a push constant.
+    let pipeline_layout = unsafe {
+        factory
+            .device()
+            .create_pipeline_layout(layouts, Some((pso::ShaderStageFlags::FRAGMENT, 0..4)))
+    }?;
+    Ok(pipeline_layout)
+}
+
+/// How the pipelines of a pass blend the colors they draw.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Blending {
+    /// Replace colors and write depth.
+    Opaque,
+    /// Blend premultiplied colors in the order they're drawn.
+    Sorted,
+    /// Accumulate weighted blended order-independent transparency.
+    OrderIndependent,
+}
+
+/// Bind the `LightmapTexCoord` of `mesh` to `binding`, or its `TexCoord` when it has none,
+/// if the pass uses lightmap coordinates.
+fn bind_lightmap_coords<B: Backend, T: Base3DPassDef>(
+    mesh: &rendy::mesh::Mesh<B>,
+    binding: u32,
+    encoder: &mut RenderPassEncoder<'_, B>,
+) {
+    if T::uses_lightmap_coords()
+        && mesh
+            .bind(binding, &[LightmapTexCoord::vertex()], encoder)
+            .is_err()
+    {
+        let _ = mesh.bind(binding, &[TexCoord::vertex()], encoder);
+    }
+}
+
+/// Returns the binding of the vertex colors of a pass, after its instance rate binding at
+/// `models_loc` and the lightmap coordinates.
+fn color_binding<T: Base3DPassDef>(models_loc: u32) -> u32 {
+    models_loc + 1 + T::uses_lightmap_coords() as u32
+}
+
+/// A pipeline of a pass built for meshes without `Color` vertex attributes, reading white
+/// colors at instance rate, and for meshes with them.
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""))]
+struct ColorPipelines<B: Backend> {
+    plain: B::GraphicsPipeline,
+    colored: B::GraphicsPipeline,
+}
+
+impl<B: Backend> ColorPipelines<B> {
+    /// Bind the `Color` of `mesh` to `binding`, or the `white` instance colors when it has
+    /// none, along with the matching pipeline unless it is `bound` already.
+    fn bind_colors(
+        &self,
+        mesh: &rendy::mesh::Mesh<B>,
+        white: &DynamicVertexBuffer<B, Color>,
+        index: usize,
+        binding: u32,
+        bound: &mut Option<bool>,
+        encoder: &mut RenderPassEncoder<'_, B>,
+    ) {
+        let colored = mesh.bind(binding, &[Color::vertex()], encoder).is_ok();
+        if !colored {
+            white.bind(index, binding, 0, encoder);
+        }
+        if *bound != Some(colored) {
+            encoder.bind_graphics_pipeline(if colored { &self.colored } else { &self.plain });
+            *bound = Some(colored);
+        }
+    }
+
+    unsafe fn dispose(self, factory: &Factory<B>) {
+        factory.device().destroy_graphics_pipeline(self.plain);
+        factory.device().destroy_graphics_pipeline(self.colored);
+    }
+}
+
+/// Write white colors for `count` instances, read by the pipelines drawing meshes without
+/// vertex colors.
+pub(super) fn write_white<B: Backend>(
+    white: &mut DynamicVertexBuffer<B, Color>,
+    factory: &Factory<B>,
+    index: usize,
+    count: usize,
+) {
+    white.write(
+        factory,
+        index,
+        count as u64,
+        std::iter::repeat([Color([1.0; 4])]).take(count),
+    );
+}
+
 fn build_pipelines<B: Backend, T: Base3DPassDef>(
     factory: &Factory<B>,
+    cache: Option<&B::PipelineCache>,
     subpass: hal::pass::Subpass<'_, B>,
     framebuffer_width: u32,
     framebuffer_height: u32,
     vertex_format_base: &[VertexFormat],
     vertex_format_skinned: &[VertexFormat],
+    morph: Option<(&[VertexFormat], &ReloadableShader)>,
+    fragment_shader: &ReloadableShader,
     skinning: bool,
-    transparent: bool,
-    layouts: Vec<&B::DescriptorSetLayout>,
-) -> Result<(Vec<B::GraphicsPipeline>, B::PipelineLayout), failure::Error> {
-    let pipeline_layout = unsafe {
-        factory
-            .device()
-            .create_pipeline_layout(layouts, None as Option<(_, _)>)
-    }?;
-
-    let vertex_desc = vertex_format_base
-        .iter()
-        .map(|f| (f.clone(), pso::VertexInputRate::Vertex))
-        .chain(Some((
-            VertexArgs::vertex(),
+    blending: Blending,
+    mode: RenderMode,
+    pipeline_layout: &B::PipelineLayout,
+) -> Result<Vec<ColorPipelines<B>>, failure::Error> {
+    let lightmap_coords = if T::uses_lightmap_coords() {
+        Some((LightmapTexCoord::vertex(), pso::VertexInputRate::Vertex))
+    } else {
+        None
+    };
+    // The vertex descriptions of a pipeline without and with vertex colors.
+    let vertex_descs = |format: &[VertexFormat], instance: VertexFormat| {
+        let vertex_desc = format
+            .iter()
+            .map(|f| (f.clone(), pso::VertexInputRate::Vertex))
+            .chain(Some((instance, pso::VertexInputRate::Instance(1))))
+            .chain(lightmap_coords.clone())
+            .collect::<Vec<_>>();
+        [
             pso::VertexInputRate::Instance(1),
-        )))
-        .collect::<Vec<_>>();
+            pso::VertexInputRate::Vertex,
+        ]
+        .iter()
+        .map(|&rate| {
+            let mut vertex_desc = vertex_desc.clone();
+            vertex_desc.push((Color::vertex(), rate));
+            vertex_desc
+        })
+        .collect::<Vec<_>>()
+    };
+
+    let fragment_shader = if mode.uses_debug_shader() {
+        T::debug_fragment_shader().unwrap()
+    } else {
+        fragment_shader
+    };
+    let rasterizer = if mode == RenderMode::Wireframe {
+        pso::Rasterizer {
+            polygon_mode: pso::PolygonMode::Line(pso::State::Static(1.0)),
+            ..pso::Rasterizer::FILL
+        }
+    } else {
+        pso::Rasterizer::FILL
+    };
+    let transparent_depth_test = pso::DepthTest {
+        fun: pso::Comparison::Greater,
+        write: false,
+    };
+    let color_blend = |blend| pso::ColorBlendDesc {
+        mask: pso::ColorMask::ALL,
+        blend,
+    };
+    // Overdraw accumulates every fragment, hidden or not.
+    let (depth_test, blend_targets) = if mode == RenderMode::Overdraw {
+        (
+            pso::DepthTest {
+                fun: pso::Comparison::Always,
+                write: false,
+            },
+            vec![color_blend(Some(pso::BlendState::ADD)); T::color_outputs()],
+        )
+    } else {
+        match blending {
+            Blending::Opaque => (
+                pso::DepthTest {
+                    fun: pso::Comparison::Greater,
+                    write: true,
+                },
+                vec![color_blend(None); T::color_outputs()],
+            ),
+            Blending::Sorted => (
+                transparent_depth_test,
+                vec![color_blend(Some(pso::BlendState::PREMULTIPLIED_ALPHA)); T::color_outputs()],
+            ),
+            // Weighted colors are summed, and revealage is multiplied by `1 - alpha`.
+            Blending::OrderIndependent => {
+                let revealage = pso::BlendOp::Add {
+                    src: pso::Factor::Zero,
+                    dst: pso::Factor::OneMinusSrcColor,
+                };
+                (
+                    transparent_depth_test,
+                    vec![
+                        color_blend(Some(pso::BlendState::ADD)),
+                        color_blend(Some(pso::BlendState {
+                            color: revealage,
+                            alpha: revealage,
+                        })),
+                    ],
+                )
+            }
+        }
+    };
 
     let shader_vertex_basic = unsafe { T::vertex_shader().module(factory).unwrap() };
-    let shader_fragment = unsafe { T::fragment_shader().module(factory).unwrap() };
+    let shader_fragment = unsafe { fragment_shader.module(factory).unwrap() };
     let pipe_desc = PipelineDescBuilder::new()
-        .with_vertex_desc(&vertex_desc)
         .with_shaders(util::simple_shader_set(
             &shader_vertex_basic,
             Some(&shader_fragment),
         ))
-        .with_layout(&pipeline_layout)
+        .with_layout(pipeline_layout)
         .with_subpass(subpass)
         .with_framebuffer_size(framebuffer_width, framebuffer_height)
+        .with_rasterizer(rasterizer)
         .with_face_culling(pso::Face::BACK)
-        .with_depth_test(pso::DepthTest {
-            fun: pso::Comparison::Greater,
-            write: !transparent,
-        })
-        .with_blend_targets(vec![pso::ColorBlendDesc {
-            mask: pso::ColorMask::ALL,
-            blend: if transparent {
-                Some(pso::BlendState::PREMULTIPLIED_ALPHA)
-            } else {
-                None
-            },
-        }]);
+        .with_depth_test(depth_test)
+        .with_blend_targets(blend_targets);
 
-    let pipelines = if skinning {
-        let shader_vertex_skinned = unsafe { T::vertex_skinned_shader().module(factory).unwrap() };
+    let shader_vertex_skinned = if skinning {
+        Some(unsafe { T::vertex_skinned_shader().module(factory).unwrap() })
+    } else {
+        None
+    };
+    let shader_vertex_morph = morph.map(|(_, shader)| unsafe { shader.module(factory).unwrap() });
+
+    let mut variants = vec![(
+        vertex_descs(vertex_format_base, VertexArgs::vertex()),
+        &shader_vertex_basic,
+    )];
+    if let Some(shader_vertex_skinned) = shader_vertex_skinned.as_ref() {
+        variants.push((
+            vertex_descs(vertex_format_skinned, SkinnedVertexArgs::vertex()),
+            shader_vertex_skinned,
+        ));
+    }
+    if let (Some((vertex_format_morph, _)), Some(shader_vertex_morph)) =
+        (morph, shader_vertex_morph.as_ref())
+    {
+        variants.push((
+            vertex_descs(vertex_format_morph, MorphVertexArgs::vertex()),
+            shader_vertex_morph,
+        ));
+    }
 
-        let vertex_desc = vertex_format_skinned
-            .iter()
-            .map(|f| (f.clone(), pso::VertexInputRate::Vertex))
-            .chain(Some((
-                SkinnedVertexArgs::vertex(),
-                pso::VertexInputRate::Instance(1),
-            )))
-            .collect::<Vec<_>>();
+    // All pipelines without vertex colors, followed by all pipelines with them.
+    let mut builder = PipelinesBuilder::new();
+    for colored in 0..2 {
+        for (i, (vertex_descs, shader_vertex)) in variants.iter().enumerate() {
+            let desc = pipe_desc
+                .clone()
+                .with_vertex_desc(&vertex_descs[colored])
+                .with_shaders(util::simple_shader_set(
+                    *shader_vertex,
+                    Some(&shader_fragment),
+                ));
+            builder = if colored == 0 && i == 0 {
+                builder.with_pipeline(desc)
+            } else {
+                builder.with_child_pipeline(0, desc)
+            };
+        }
+    }
 
-        let pipe = PipelinesBuilder::new()
-            .with_pipeline(pipe_desc.clone())
-            .with_child_pipeline(
-                0,
-                pipe_desc
-                    .with_vertex_desc(&vertex_desc)
-                    .with_shaders(util::simple_shader_set(
-                        &shader_vertex_skinned,
-                        Some(&shader_fragment),
-                    )),
-            )
-            .build(factory, None);
+    let pipelines = builder.build_cached(factory, cache).map(|mut plain| {
+        let colored = plain.split_off(variants.len());
+        plain
+            .into_iter()
+            .zip(colored)
+            .map(|(plain, colored)| ColorPipelines { plain, colored })
+            .collect()
+    });
 
-        unsafe {
-            factory.destroy_shader_module(shader_vertex_skinned);
+    unsafe {
+        if let Some(shader) = shader_vertex_skinned {
+            factory.destroy_shader_module(shader);
         }
-
-        pipe
-    } else {
-        PipelinesBuilder::new()
-            .with_pipeline(pipe_desc)
-            .build(factory, None)
-    };
+        if let Some(shader) = shader_vertex_morph {
+            factory.destroy_shader_module(shader);
+        }
+    }
 
     unsafe {
         factory.destroy_shader_module(shader_vertex_basic);
         factory.destroy_shader_module(shader_fragment);
     }
 
-    match pipelines {
-        Err(e) => {
-            unsafe {
-                factory.device().destroy_pipeline_layout(pipeline_layout);
-            }
-            Err(e)
-        }
-        Ok(pipelines) => Ok((pipelines, pipeline_layout)),
-    }
+    pipelines
 }
@@ -0,0 +1,277 @@
+use crate::{
+    batch::OrderedOneLevelBatch,
+    billboard::Billboard,
+    pipeline::{PipelineCache, PipelineDescBuilder, PipelinesBuilder},
+    pod::{BillboardArgs, ViewArgs},
+    resources::Tint,
+    sprite::SpriteSheet,
+    stats::DrawCounter,
+    submodules::{
+        gather::CameraGatherer, DynamicUniform, DynamicVertexBuffer, TextureId, TextureSub,
+    },
+    types::{Backend, Texture},
+    util,
+};
+use amethyst_assets::AssetStorage;
+use amethyst_core::{
+    ecs::{Join, Read, ReadStorage, SystemData, World},
+    transform::Transform,
+    Hidden, HiddenPropagate,
+};
+use derivative::Derivative;
+use rendy::{
+    command::{QueueId, RenderPassEncoder},
+    factory::Factory,
+    graph::{
+        render::{PrepareResult, RenderGroup, RenderGroupDesc},
+        GraphContext, NodeBuffer, NodeImage,
+    },
+    hal::{self, device::Device, pso},
+    mesh::AsVertex,
+    shader::Shader,
+};
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+/// Draw the sprites of every [Billboard] as quads facing the camera, alpha blended back to
+/// front and depth tested against the opaque surfaces of the target.
+#[derive(Clone, Debug, PartialEq, Derivative)]
+#[derivative(Default(bound = ""))]
+pub struct DrawBillboardsDesc;
+
+impl DrawBillboardsDesc {
+    /// Create instance of `DrawBillboards` render group
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl<B: Backend> RenderGroupDesc<B, World> for DrawBillboardsDesc {
+    fn build(
+        self,
+        _ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        _queue: QueueId,
+        world: &World,
+        framebuffer_width: u32,
+        framebuffer_height: u32,
+        subpass: hal::pass::Subpass<'_, B>,
+        _buffers: Vec<NodeBuffer>,
+        _images: Vec<NodeImage>,
+    ) -> Result<Box<dyn RenderGroup<B, World>>, failure::Error> {
+        #[cfg(feature = "profiler")]
+        profile_scope!("build");
+
+        let env = DynamicUniform::new(factory, pso::ShaderStageFlags::VERTEX)?;
+        let textures = TextureSub::new(factory)?;
+
+        let pipeline_cache = world.try_fetch::<PipelineCache<B>>();
+        let (pipeline, pipeline_layout) = build_billboard_pipeline(
+            factory,
+            pipeline_cache.as_ref().and_then(|cache| cache.raw()),
+            subpass,
+            framebuffer_width,
+            framebuffer_height,
+            vec![env.raw_layout(), textures.raw_layout()],
+        )?;
+
+        Ok(Box::new(DrawBillboards::<B> {
+            pipeline,
+            pipeline_layout,
+            env,
+            textures,
+            vertex: DynamicVertexBuffer::new(),
+            billboards: Default::default(),
+        }))
+    }
+}
+
+/// Draws billboards back to front, batched by texture.
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""))]
+pub struct DrawBillboards<B: Backend> {
+    pipeline: B::GraphicsPipeline,
+    pipeline_layout: B::PipelineLayout,
+    env: DynamicUniform<B, ViewArgs>,
+    textures: TextureSub<B>,
+    vertex: DynamicVertexBuffer<B, BillboardArgs>,
+    billboards: OrderedOneLevelBatch<TextureId, BillboardArgs>,
+}
+
+impl<B: Backend> RenderGroup<B, World> for DrawBillboards<B> {
+    fn prepare(
+        &mut self,
+        factory: &Factory<B>,
+        _queue: QueueId,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        world: &World,
+    ) -> PrepareResult {
+        #[cfg(feature = "profiler")]
+        profile_scope!("prepare");
+
+        let (
+            sprite_sheet_storage,
+            tex_storage,
+            billboards,
+            transforms,
+            tints,
+            hiddens,
+            hidden_props,
+        ) = <(
+            Read<'_, AssetStorage<SpriteSheet>>,
+            Read<'_, AssetStorage<Texture>>,
+            ReadStorage<'_, Billboard>,
+            ReadStorage<'_, Transform>,
+            ReadStorage<'_, Tint>,
+            ReadStorage<'_, Hidden>,
+            ReadStorage<'_, HiddenPropagate>,
+        )>::fetch(world);
+
+        let camera = CameraGatherer::gather(world);
+        self.env.write(factory, index, camera.projview);
+
+        let mut sorted = Vec::new();
+        for (billboard, transform, tint, _, _) in (
+            &billboards,
+            &transforms,
+            tints.maybe(),
+            !&hiddens,
+            !&hidden_props,
+        )
+            .join()
+        {
+            let (args, texture) = match BillboardArgs::from_data(
+                &tex_storage,
+                &sprite_sheet_storage,
+                billboard,
+                transform,
+                tint,
+            ) {
+                Some(data) => data,
+                None => continue,
+            };
+            let tex_id = match self.textures.insert(
+                factory,
+                world,
+                texture,
+                hal::image::Layout::ShaderReadOnlyOptimal,
+            ) {
+                Some((tex_id, _)) => tex_id,
+                None => continue,
+            };
+            let view_z = (camera.view * transform.global_matrix().column(3)).z;
+            sorted.push((view_z, tex_id, args));
+        }
+        self.textures.maintain(factory, world);
+
+        self.billboards.swap_clear();
+        self.billboards.insert_back_to_front(sorted);
+
+        self.vertex.write(
+            factory,
+            index,
+            self.billboards.count() as u64,
+            Some(self.billboards.data()),
+        );
+
+        PrepareResult::DrawRecord
+    }
+
+    fn draw_inline(
+        &mut self,
+        mut encoder: RenderPassEncoder<'_, B>,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        world: &World,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("draw");
+
+        if self.billboards.count() == 0 {
+            return;
+        }
+
+        let layout = &self.pipeline_layout;
+        encoder.bind_graphics_pipeline(&self.pipeline);
+        self.env.bind(index, layout, 0, &mut encoder);
+        self.vertex.bind(index, 0, 0, &mut encoder);
+        let mut draws = DrawCounter::fetch(world);
+        for (&tex, range) in self.billboards.iter() {
+            if self.textures.loaded(tex) {
+                self.textures.bind(layout, 1, tex, &mut encoder);
+                let instances = range.end - range.start;
+                unsafe {
+                    encoder.draw(0..4, range);
+                }
+                draws.draw_quads(instances);
+            }
+        }
+    }
+
+    fn dispose(self: Box<Self>, factory: &mut Factory<B>, _world: &World) {
+        unsafe {
+            factory.device().destroy_graphics_pipeline(self.pipeline);
+            factory
+                .device()
+                .destroy_pipeline_layout(self.pipeline_layout);
+        }
+    }
+}
+
+fn build_billboard_pipeline<B: Backend>(
+    factory: &Factory<B>,
+    cache: Option<&B::PipelineCache>,
+    subpass: hal::pass::Subpass<'_, B>,
+    framebuffer_width: u32,
+    framebuffer_height: u32,
+    layouts: Vec<&B::DescriptorSetLayout>,
+) -> Result<(B::GraphicsPipeline, B::PipelineLayout), failure::Error> {
+    let pipeline_layout = unsafe {
+        factory
+            .device()
+            .create_pipeline_layout(layouts, None as Option<(_, _)>)
+    }?;
+
+    let shader_vertex = unsafe { super::BILLBOARD_VERTEX.module(factory).unwrap() };
+    let shader_fragment = unsafe { super::SPRITE_FRAGMENT.module(factory).unwrap() };
+
+    let pipes = PipelinesBuilder::new()
+        .with_pipeline(
+            PipelineDescBuilder::new()
+                .with_vertex_desc(&[(BillboardArgs::vertex(), pso::VertexInputRate::Instance(1))])
+                .with_input_assembler(pso::InputAssemblerDesc::new(hal::Primitive::TriangleStrip))
+                .with_shaders(util::simple_shader_set(
+                    &shader_vertex,
+                    Some(&shader_fragment),
+                ))
+                .with_layout(&pipeline_layout)
+                .with_subpass(subpass)
+                .with_framebuffer_size(framebuffer_width, framebuffer_height)
+                .with_blend_targets(vec![pso::ColorBlendDesc {
+                    mask: pso::ColorMask::ALL,
+                    blend: Some(pso::BlendState::ALPHA),
+                }])
+                .with_depth_test(pso::DepthTest {
+                    fun: pso::Comparison::Greater,
+                    write: false,
+                }),
+        )
+        .build_cached(factory, cache);
+
+    unsafe {
+        factory.destroy_shader_module(shader_vertex);
+        factory.destroy_shader_module(shader_fragment);
+    }
+
+    match pipes {
+        Err(e) => {
+            unsafe {
+                factory.device().destroy_pipeline_layout(pipeline_layout);
+            }
+            Err(e)
+        }
+        Ok(mut pipes) => Ok((pipes.remove(0), pipeline_layout)),
+    }
+}
@@ -0,0 +1,34 @@
+use crate::{bloom::BloomParams, pass::DrawPostProcessDesc};
+
+impl DrawPostProcessDesc {
+    /// Create instance of `DrawPostProcess` render group keeping the parts of its input
+    /// brighter than the [BloomParams] threshold.
+    pub fn bloom_bright_pass() -> Self {
+        Self::new(super::BLOOM_BRIGHT_FRAGMENT.clone()).with_constants(|world| {
+            let params = BloomParams::fetch(world);
+            vec![params.threshold, params.soft_knee]
+        })
+    }
+
+    /// Create instance of `DrawPostProcess` render group blurring its input along one axis.
+    ///
+    /// `texel_step` is the size of an output texel along the blurred axis
+    /// in texture coordinates, and zero along the other one.
+    pub fn bloom_blur(texel_step: [f32; 2]) -> Self {
+        Self::new(super::BLOOM_BLUR_FRAGMENT.clone()).with_constants(move |_| texel_step.to_vec())
+    }
+
+    /// Create instance of `DrawPostProcess` render group adding its second input to the first.
+    pub fn bloom_upsample() -> Self {
+        Self::new(super::BLOOM_COMBINE_FRAGMENT.clone()).with_constants(|_| vec![1.0, 1.0])
+    }
+
+    /// Create instance of `DrawPostProcess` render group adding the sum of `levels`
+    /// blurred levels in its second input to the first, scaled by the [BloomParams] intensity.
+    pub fn bloom_composite(levels: usize) -> Self {
+        Self::new(super::BLOOM_COMBINE_FRAGMENT.clone()).with_constants(move |world| {
+            let params = BloomParams::fetch(world);
+            vec![1.0, params.intensity / levels.max(1) as f32]
+        })
+    }
+}
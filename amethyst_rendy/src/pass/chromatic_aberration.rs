@@ -0,0 +1,12 @@
+use crate::{chromatic_aberration::ChromaticAberrationParams, pass::DrawPostProcessDesc};
+
+impl DrawPostProcessDesc {
+    /// Create instance of `DrawPostProcess` render group offsetting the red and blue channels
+    /// of its input towards the edges, as configured by [ChromaticAberrationParams].
+    pub fn chromatic_aberration() -> Self {
+        Self::new(super::CHROMATIC_ABERRATION_FRAGMENT.clone()).with_constants(|world| {
+            let params = ChromaticAberrationParams::fetch(world);
+            vec![if params.enabled { 1.0 } else { 0.0 }, params.intensity]
+        })
+    }
+}
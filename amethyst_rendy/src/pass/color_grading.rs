@@ -0,0 +1,19 @@
+use crate::{color_grading::ColorGrading, pass::DrawPostProcessDesc};
+
+impl DrawPostProcessDesc {
+    /// Create instance of `DrawPostProcess` render group grading the colors of its input
+    /// with the lookup table of the [ColorGrading] resource.
+    ///
+    /// The input is expected to be in the displayable range, after tonemapping.
+    pub fn color_grading() -> Self {
+        Self::new(super::COLOR_GRADING_FRAGMENT.clone())
+            .with_texture(|world| ColorGrading::fetch(world).lut)
+            .with_constants(|world| {
+                let grading = ColorGrading::fetch(world);
+                vec![
+                    if grading.lut.is_some() { 1.0 } else { 0.0 },
+                    grading.intensity.max(0.0).min(1.0),
+                ]
+            })
+    }
+}
@@ -0,0 +1,381 @@
+use super::{post_process::input_view, tonemap::build_compute_pipeline};
+use crate::{pipeline::PipelineCache, shader_reload::ReloadableShader, types::Backend, util};
+use amethyst_core::ecs::World;
+use derivative::Derivative;
+use rendy::{
+    command::{
+        CommandBuffer, CommandPool, Compute, ExecutableState, Family, MultiShot, PendingState,
+        SimultaneousUse, Submit,
+    },
+    factory::Factory,
+    frame::Frames,
+    graph::{
+        gfx_acquire_barriers, gfx_release_barriers, BufferAccess, GraphContext, ImageAccess, Node,
+        NodeBuffer, NodeDesc, NodeImage, NodeSubmittable,
+    },
+    hal::{self, device::Device, pso},
+    memory::{Dynamic, Write},
+    resource::{
+        Buffer, BufferInfo, DescriptorSet, DescriptorSetLayout, Escape, Filter,
+        Handle as RendyHandle, ImageView, Sampler, SamplerInfo, WrapMode,
+    },
+};
+use std::sync::Arc;
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+/// Maximum number of `f32` constants available to compute shaders of [DispatchCompute].
+pub const MAX_COMPUTE_CONSTANTS: usize = 32;
+
+type ConstantsFn = Arc<dyn Fn(&World) -> Vec<f32> + Send + Sync>;
+
+/// Number of workgroups dispatched by [DispatchCompute].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DispatchSize {
+    /// Fixed number of workgroups in each dimension.
+    Groups(u32, u32, u32),
+    /// Enough workgroups of the given width and height to cover every pixel of the image
+    /// at the given index of the node images.
+    Image {
+        /// Index of the image among the images passed to the node builder.
+        image: usize,
+        /// Width and height of a workgroup, matching `local_size_x` and `local_size_y`.
+        group_size: (u32, u32),
+    },
+}
+
+/// Describe a compute node dispatching a shader once per frame.
+///
+/// Resources are bound to `set = 0` in this order, and must be passed to the node builder
+/// with `with_image` and `with_buffer` in the same order:
+/// - images added with `with_sampled_image`, as `sampler2D`s read through their color or
+///   depth aspect,
+/// - images added with `with_storage_image`, as read and write `image2D`s in the general
+///   layout,
+/// - buffers added with `with_storage_buffer`, as read and write storage buffers,
+/// - with `with_constants`, a `vec4[MAX_COMPUTE_CONSTANTS / 4]` uniform block holding the
+///   values returned by the closure every frame.
+///
+/// Add the node to a target with
+/// [TargetPlanContext::add_compute](crate::bundle::TargetPlanContext::add_compute), so it runs
+/// before the pass of the target.
+#[derive(Clone, Derivative)]
+#[derivative(Debug)]
+pub struct DispatchComputeDesc {
+    shader: ReloadableShader,
+    size: DispatchSize,
+    sampled_images: usize,
+    storage_images: usize,
+    storage_buffers: usize,
+    #[derivative(Debug = "ignore")]
+    constants: Option<ConstantsFn>,
+}
+
+impl DispatchComputeDesc {
+    /// Create instance of `DispatchCompute` node running given compute shader
+    pub fn new(shader: impl Into<ReloadableShader>, size: DispatchSize) -> Self {
+        Self {
+            shader: shader.into(),
+            size,
+            sampled_images: 0,
+            storage_images: 0,
+            storage_buffers: 0,
+            constants: None,
+        }
+    }
+
+    /// Sample an image in the compute shader.
+    pub fn with_sampled_image(mut self) -> Self {
+        self.sampled_images += 1;
+        self
+    }
+
+    /// Read and write an image in the compute shader.
+    pub fn with_storage_image(mut self) -> Self {
+        self.storage_images += 1;
+        self
+    }
+
+    /// Read and write a storage buffer in the compute shader.
+    pub fn with_storage_buffer(mut self) -> Self {
+        self.storage_buffers += 1;
+        self
+    }
+
+    /// Constants computed from the world every frame.
+    ///
+    /// At most `MAX_COMPUTE_CONSTANTS` values are used.
+    pub fn with_constants(
+        mut self,
+        constants: impl Fn(&World) -> Vec<f32> + Send + Sync + 'static,
+    ) -> Self {
+        self.constants = Some(Arc::new(constants));
+        self
+    }
+}
+
+impl<B: Backend> NodeDesc<B, World> for DispatchComputeDesc {
+    type Node = DispatchCompute<B>;
+
+    fn buffers(&self) -> Vec<BufferAccess> {
+        vec![
+            BufferAccess {
+                access: hal::buffer::Access::SHADER_READ | hal::buffer::Access::SHADER_WRITE,
+                usage: hal::buffer::Usage::STORAGE,
+                stages: pso::PipelineStage::COMPUTE_SHADER,
+            };
+            self.storage_buffers
+        ]
+    }
+
+    fn images(&self) -> Vec<ImageAccess> {
+        let sampled = ImageAccess {
+            access: hal::image::Access::SHADER_READ,
+            usage: hal::image::Usage::SAMPLED,
+            layout: hal::image::Layout::ShaderReadOnlyOptimal,
+            stages: pso::PipelineStage::COMPUTE_SHADER,
+        };
+        let storage = ImageAccess {
+            access: hal::image::Access::SHADER_READ | hal::image::Access::SHADER_WRITE,
+            usage: hal::image::Usage::STORAGE,
+            layout: hal::image::Layout::General,
+            stages: pso::PipelineStage::COMPUTE_SHADER,
+        };
+        let mut images = vec![sampled; self.sampled_images];
+        images.extend(vec![storage; self.storage_images]);
+        images
+    }
+
+    fn build<'a>(
+        self,
+        ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        family: &mut Family<B>,
+        _queue: usize,
+        aux: &World,
+        buffers: Vec<NodeBuffer>,
+        images: Vec<NodeImage>,
+    ) -> Result<Self::Node, failure::Error> {
+        #[cfg(feature = "profiler")]
+        profile_scope!("build");
+
+        let groups = match self.size {
+            DispatchSize::Groups(x, y, z) => (x, y, z),
+            DispatchSize::Image { image, group_size } => {
+                let extent = images
+                    .get(image)
+                    .and_then(|image| ctx.get_image(image.id))
+                    .ok_or_else(|| failure::format_err!("Dispatch size image is not available"))?
+                    .kind()
+                    .extent();
+                (
+                    (extent.width + group_size.0 - 1) / group_size.0,
+                    (extent.height + group_size.1 - 1) / group_size.1,
+                    1,
+                )
+            }
+        };
+
+        let views = images
+            .iter()
+            .map(|image| input_view(ctx, factory, image))
+            .collect::<Result<Vec<_>, _>>()?;
+        let sampler = factory.get_sampler(SamplerInfo::new(Filter::Linear, WrapMode::Clamp))?;
+        let storage_buffers = buffers
+            .iter()
+            .map(|buffer| {
+                ctx.get_buffer(buffer.id)
+                    .ok_or_else(|| failure::format_err!("Compute storage buffer is not available"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let layout: RendyHandle<DescriptorSetLayout<B>> = set_layout! {
+            factory,
+            [self.sampled_images] CombinedImageSampler pso::ShaderStageFlags::COMPUTE,
+            [self.storage_images] StorageImage pso::ShaderStageFlags::COMPUTE,
+            [buffers.len()] StorageBuffer pso::ShaderStageFlags::COMPUTE,
+            [self.constants.is_some() as usize] UniformBuffer pso::ShaderStageFlags::COMPUTE
+        };
+        let pipeline_layout = unsafe {
+            factory
+                .device()
+                .create_pipeline_layout(Some(layout.raw()), None as Option<(_, _)>)
+        }?;
+        let pipeline_cache = aux.try_fetch::<PipelineCache<B>>();
+        let pipeline = build_compute_pipeline(
+            factory,
+            pipeline_cache.as_ref().and_then(|cache| cache.raw()),
+            &pipeline_layout,
+            &self.shader,
+        )?;
+
+        let mut command_pool = factory
+            .create_command_pool(family)?
+            .with_capability::<Compute>()
+            .expect("Graph builder must provide family with Compute capability");
+
+        // Each frame in flight has its own constants and command buffer recorded once.
+        let mut frames = Vec::new();
+        for command_buffer in command_pool.allocate_buffers(ctx.frames_in_flight as usize) {
+            let constants = match self.constants {
+                Some(_) => Some(factory.create_buffer(
+                    BufferInfo {
+                        size: (MAX_COMPUTE_CONSTANTS * std::mem::size_of::<f32>()) as u64,
+                        usage: hal::buffer::Usage::UNIFORM,
+                    },
+                    Dynamic,
+                )?),
+                None => None,
+            };
+            let set = factory.create_descriptor_set(layout.clone())?;
+            unsafe {
+                factory.write_descriptor_sets(
+                    views
+                        .iter()
+                        .enumerate()
+                        .map(|(index, view)| {
+                            if index < self.sampled_images {
+                                pso::Descriptor::CombinedImageSampler(
+                                    view.raw(),
+                                    hal::image::Layout::ShaderReadOnlyOptimal,
+                                    sampler.raw(),
+                                )
+                            } else {
+                                pso::Descriptor::Image(view.raw(), hal::image::Layout::General)
+                            }
+                        })
+                        .chain(
+                            storage_buffers
+                                .iter()
+                                .map(|buffer| pso::Descriptor::Buffer(buffer.raw(), None..None)),
+                        )
+                        .chain(
+                            constants
+                                .iter()
+                                .map(|buffer| pso::Descriptor::Buffer(buffer.raw(), None..None)),
+                        )
+                        .enumerate()
+                        .map(|(binding, desc)| util::desc_write(set.raw(), binding as u32, desc))
+                        .collect::<Vec<_>>(),
+                );
+            }
+
+            let mut recording = command_buffer.begin(MultiShot(SimultaneousUse), ());
+            let mut encoder = recording.encoder();
+            unsafe {
+                let (stages, barriers) = gfx_acquire_barriers(ctx, &buffers, &images);
+                encoder.pipeline_barrier(stages, hal::memory::Dependencies::empty(), barriers);
+
+                encoder.bind_compute_pipeline(&pipeline);
+                encoder.bind_compute_descriptor_sets(
+                    &pipeline_layout,
+                    0,
+                    Some(set.raw()),
+                    std::iter::empty(),
+                );
+                encoder.dispatch(groups.0, groups.1, groups.2);
+
+                let (stages, barriers) = gfx_release_barriers(ctx, &buffers, &images);
+                encoder.pipeline_barrier(stages, hal::memory::Dependencies::empty(), barriers);
+            }
+
+            let (submit, command_buffer) = recording.finish().submit();
+            frames.push(DispatchComputeFrame {
+                constants,
+                set,
+                command_buffer,
+                submit,
+            });
+        }
+
+        Ok(DispatchCompute {
+            pipeline_layout,
+            pipeline,
+            command_pool,
+            frames,
+            constants: self.constants,
+            _layout: layout,
+            _views: views,
+            _sampler: sampler,
+        })
+    }
+}
+
+#[derive(Debug)]
+struct DispatchComputeFrame<B: Backend> {
+    constants: Option<Escape<Buffer<B>>>,
+    set: Escape<DescriptorSet<B>>,
+    command_buffer:
+        CommandBuffer<B, Compute, PendingState<ExecutableState<MultiShot<SimultaneousUse>>>>,
+    submit: Submit<B, SimultaneousUse>,
+}
+
+/// Dispatches a compute shader over images and buffers of the graph.
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""))]
+pub struct DispatchCompute<B: Backend> {
+    pipeline_layout: B::PipelineLayout,
+    pipeline: B::ComputePipeline,
+    command_pool: CommandPool<B, Compute>,
+    frames: Vec<DispatchComputeFrame<B>>,
+    #[derivative(Debug = "ignore")]
+    constants: Option<ConstantsFn>,
+    _layout: RendyHandle<DescriptorSetLayout<B>>,
+    _views: Vec<Escape<ImageView<B>>>,
+    _sampler: RendyHandle<Sampler<B>>,
+}
+
+impl<'a, B: Backend> NodeSubmittable<'a, B> for DispatchCompute<B> {
+    type Submittable = &'a Submit<B, SimultaneousUse>;
+    type Submittables = &'a [Submit<B, SimultaneousUse>];
+}
+
+impl<B: Backend> Node<B, World> for DispatchCompute<B> {
+    type Capability = Compute;
+    type Desc = DispatchComputeDesc;
+
+    fn run<'a>(
+        &'a mut self,
+        _ctx: &GraphContext<B>,
+        factory: &Factory<B>,
+        aux: &World,
+        frames: &'a Frames<B>,
+    ) -> &'a [Submit<B, SimultaneousUse>] {
+        #[cfg(feature = "profiler")]
+        profile_scope!("run");
+
+        // The graph waits for the frame that last used this slot before running.
+        let index = frames.next().index() as usize % self.frames.len();
+        if let (Some(constants), Some(buffer)) =
+            (&self.constants, self.frames[index].constants.as_mut())
+        {
+            let mut values = [0.0; MAX_COMPUTE_CONSTANTS];
+            for (value, constant) in values.iter_mut().zip(constants(aux)) {
+                *value = constant;
+            }
+            let range = 0..buffer.size();
+            let mut mapped = buffer.map(factory.device(), range.clone()).unwrap();
+            unsafe {
+                let mut writer = mapped.write::<u8>(factory.device(), range).unwrap();
+                writer
+                    .slice()
+                    .copy_from_slice(util::slice_as_bytes(&values));
+            }
+        }
+
+        std::slice::from_ref(&self.frames[index].submit)
+    }
+
+    unsafe fn dispose(mut self, factory: &mut Factory<B>, _aux: &World) {
+        for frame in self.frames.drain(..) {
+            self.command_pool
+                .free_buffers(Some(frame.command_buffer.mark_complete()));
+        }
+        factory.destroy_command_pool(self.command_pool);
+        factory.device().destroy_compute_pipeline(self.pipeline);
+        factory
+            .device()
+            .destroy_pipeline_layout(self.pipeline_layout);
+    }
+}
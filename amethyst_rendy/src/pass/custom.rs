@@ -0,0 +1,324 @@
+use crate::{
+    pipeline::{PipelineCache, PipelineDescBuilder, PipelinesBuilder},
+    pod::{VertexArgs, ViewArgs},
+    resources::Tint,
+    shader_reload::ReloadableShader,
+    stats::DrawCounter,
+    submodules::{
+        gather::CameraGatherer, DynamicUniform, DynamicUniformArray, DynamicVertexBuffer,
+    },
+    types::{Backend, Mesh},
+    util,
+    visibility::Visibility,
+};
+use amethyst_assets::{AssetStorage, Handle};
+use amethyst_core::{
+    ecs::{Component, Join, Read, ReadExpect, ReadStorage, SystemData, World},
+    transform::Transform,
+};
+use derivative::Derivative;
+use glsl_layout::Std140;
+use rendy::{
+    command::{QueueId, RenderPassEncoder},
+    factory::Factory,
+    graph::{
+        render::{PrepareResult, RenderGroup, RenderGroupDesc},
+        GraphContext, NodeBuffer, NodeImage,
+    },
+    hal::{self, device::Device, pso},
+    mesh::{AsVertex, VertexFormat},
+    shader::Shader,
+};
+use std::marker::PhantomData;
+
+macro_rules! profile_scope_impl {
+    ($string:expr) => {
+        #[cfg(feature = "profiler")]
+        let _profile_scope = thread_profiler::ProfileScope::new(format!(
+            "{} {}: {}",
+            module_path!(),
+            <M as CustomMaterial>::NAME,
+            $string
+        ));
+    };
+}
+
+/// Define a material drawn with user provided shaders, attached to entities as a component.
+///
+/// Visible opaque entities with the material, a `Handle<Mesh>` and a `Transform` are drawn
+/// by [DrawCustom]. The shaders are given:
+/// * the camera `ViewArgs` uniform at set 0, binding 0,
+/// * the `Uniform` of the entity's material at set 1, binding 0,
+/// * the attributes of `vertex_format`, followed by the instance-rate `VertexArgs`.
+///
+/// Entities with a custom material shouldn't also have a `Handle<Material>`,
+/// or they are drawn by both passes.
+pub trait CustomMaterial: Component + std::fmt::Debug + Send + Sync {
+    /// The human readable name of this material
+    const NAME: &'static str;
+
+    /// Layout of the uniform passed to the shaders for every entity,
+    /// usually the `Std140` type of a struct deriving `AsStd140`
+    type Uniform: Std140;
+
+    /// Returns the vertex shader drawing this material
+    fn vertex_shader() -> &'static ReloadableShader;
+
+    /// Returns the fragment shader drawing this material
+    fn fragment_shader() -> &'static ReloadableShader;
+
+    /// Returns the `VertexFormat` of the meshes drawn with this material
+    fn vertex_format() -> Vec<VertexFormat>;
+
+    /// Returns the uniform data of this material
+    fn uniform(&self) -> Self::Uniform;
+}
+
+/// Draw opaque 3d meshes with a [CustomMaterial]
+#[derive(Clone, Derivative)]
+#[derivative(Debug(bound = ""), Default(bound = ""))]
+pub struct DrawCustomDesc<B: Backend, M: CustomMaterial> {
+    marker: PhantomData<(B, M)>,
+}
+
+impl<B: Backend, M: CustomMaterial> DrawCustomDesc<B, M> {
+    /// Create instance of `DrawCustom` render group
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl<B: Backend, M: CustomMaterial> RenderGroupDesc<B, World> for DrawCustomDesc<B, M> {
+    fn build(
+        self,
+        _ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        _queue: QueueId,
+        aux: &World,
+        framebuffer_width: u32,
+        framebuffer_height: u32,
+        subpass: hal::pass::Subpass<'_, B>,
+        _buffers: Vec<NodeBuffer>,
+        _images: Vec<NodeImage>,
+    ) -> Result<Box<dyn RenderGroup<B, World>>, failure::Error> {
+        profile_scope_impl!("build");
+
+        let env = DynamicUniform::new(
+            factory,
+            pso::ShaderStageFlags::VERTEX | pso::ShaderStageFlags::FRAGMENT,
+        )?;
+        let uniforms = DynamicUniformArray::new(
+            factory,
+            pso::ShaderStageFlags::VERTEX | pso::ShaderStageFlags::FRAGMENT,
+        )?;
+
+        let mut vertex_format = M::vertex_format();
+
+        let pipeline_cache = aux.try_fetch::<PipelineCache<B>>();
+
+        let (pipeline, pipeline_layout) = build_custom_pipeline::<B, M>(
+            factory,
+            pipeline_cache.as_ref().and_then(|cache| cache.raw()),
+            subpass,
+            framebuffer_width,
+            framebuffer_height,
+            &vertex_format,
+            vec![env.raw_layout(), uniforms.raw_layout()],
+        )?;
+
+        vertex_format.sort();
+
+        Ok(Box::new(DrawCustom::<B, M> {
+            pipeline,
+            pipeline_layout,
+            vertex_format,
+            env,
+            uniforms,
+            models: DynamicVertexBuffer::new(),
+            meshes: Vec::new(),
+            marker: PhantomData,
+        }))
+    }
+}
+
+/// Draws opaque 3d meshes with a [CustomMaterial], one draw call per entity.
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""))]
+pub struct DrawCustom<B: Backend, M: CustomMaterial> {
+    pipeline: B::GraphicsPipeline,
+    pipeline_layout: B::PipelineLayout,
+    vertex_format: Vec<VertexFormat>,
+    env: DynamicUniform<B, ViewArgs>,
+    uniforms: DynamicUniformArray<B, M::Uniform>,
+    models: DynamicVertexBuffer<B, VertexArgs>,
+    meshes: Vec<u32>,
+    marker: PhantomData<M>,
+}
+
+impl<B: Backend, M: CustomMaterial> RenderGroup<B, World> for DrawCustom<B, M> {
+    fn prepare(
+        &mut self,
+        factory: &Factory<B>,
+        _queue: QueueId,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        resources: &World,
+    ) -> PrepareResult {
+        profile_scope_impl!("prepare");
+
+        let (mesh_storage, visibility, materials, meshes, transforms, tints) =
+            <(
+                Read<'_, AssetStorage<Mesh>>,
+                ReadExpect<'_, Visibility>,
+                ReadStorage<'_, M>,
+                ReadStorage<'_, Handle<Mesh>>,
+                ReadStorage<'_, Transform>,
+                ReadStorage<'_, Tint>,
+            )>::fetch(resources);
+
+        self.env
+            .write(factory, index, CameraGatherer::gather(resources).projview);
+
+        self.meshes.clear();
+        let mut models = Vec::new();
+        let mut uniforms = Vec::new();
+        for (material, mesh, transform, tint, _) in (
+            &materials,
+            &meshes,
+            &transforms,
+            tints.maybe(),
+            &visibility.visible_unordered,
+        )
+            .join()
+        {
+            if mesh_storage.contains_id(mesh.id()) {
+                self.meshes.push(mesh.id());
+                models.push(VertexArgs::from_object_data(transform, tint));
+                uniforms.push(material.uniform());
+            }
+        }
+
+        self.models
+            .write(factory, index, models.len() as u64, Some(&models));
+        self.uniforms.write(factory, index, uniforms.into_iter());
+        PrepareResult::DrawRecord
+    }
+
+    fn draw_inline(
+        &mut self,
+        mut encoder: RenderPassEncoder<'_, B>,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        resources: &World,
+    ) {
+        profile_scope_impl!("draw");
+
+        if self.meshes.is_empty() {
+            return;
+        }
+
+        let mesh_storage = <Read<'_, AssetStorage<Mesh>>>::fetch(resources);
+        let models_loc = self.vertex_format.len() as u32;
+
+        encoder.bind_graphics_pipeline(&self.pipeline);
+        self.env.bind(index, &self.pipeline_layout, 0, &mut encoder);
+
+        if self.models.bind(index, models_loc, 0, &mut encoder) {
+            let mut draws = DrawCounter::fetch(resources);
+            for (instance, mesh_id) in self.meshes.iter().enumerate() {
+                debug_assert!(mesh_storage.contains_id(*mesh_id));
+                if let Some(mesh) =
+                    B::unwrap_mesh(unsafe { mesh_storage.get_by_id_unchecked(*mesh_id) })
+                {
+                    self.uniforms
+                        .bind(index, &self.pipeline_layout, 1, instance, &mut encoder);
+                    let instance = instance as u32;
+                    mesh.bind_and_draw(
+                        0,
+                        &self.vertex_format,
+                        instance..instance + 1,
+                        &mut encoder,
+                    )
+                    .unwrap();
+                    draws.draw(mesh.primitive(), mesh.len(), 1);
+                }
+            }
+        }
+    }
+
+    fn dispose(self: Box<Self>, factory: &mut Factory<B>, _aux: &World) {
+        profile_scope_impl!("dispose");
+        unsafe {
+            factory.device().destroy_graphics_pipeline(self.pipeline);
+            factory
+                .device()
+                .destroy_pipeline_layout(self.pipeline_layout);
+        }
+    }
+}
+
+fn build_custom_pipeline<B: Backend, M: CustomMaterial>(
+    factory: &Factory<B>,
+    cache: Option<&B::PipelineCache>,
+    subpass: hal::pass::Subpass<'_, B>,
+    framebuffer_width: u32,
+    framebuffer_height: u32,
+    vertex_format: &[VertexFormat],
+    layouts: Vec<&B::DescriptorSetLayout>,
+) -> Result<(B::GraphicsPipeline, B::PipelineLayout), failure::Error> {
+    let pipeline_layout = unsafe {
+        factory
+            .device()
+            .create_pipeline_layout(layouts, None as Option<(_, _)>)
+    }?;
+
+    let vertex_desc = vertex_format
+        .iter()
+        .map(|f| (f.clone(), pso::VertexInputRate::Vertex))
+        .chain(Some((
+            VertexArgs::vertex(),
+            pso::VertexInputRate::Instance(1),
+        )))
+        .collect::<Vec<_>>();
+
+    let shader_vertex = unsafe { M::vertex_shader().module(factory).unwrap() };
+    let shader_fragment = unsafe { M::fragment_shader().module(factory).unwrap() };
+
+    let pipes = PipelinesBuilder::new()
+        .with_pipeline(
+            PipelineDescBuilder::new()
+                .with_vertex_desc(&vertex_desc)
+                .with_shaders(util::simple_shader_set(
+                    &shader_vertex,
+                    Some(&shader_fragment),
+                ))
+                .with_layout(&pipeline_layout)
+                .with_subpass(subpass)
+                .with_framebuffer_size(framebuffer_width, framebuffer_height)
+                .with_face_culling(pso::Face::BACK)
+                .with_depth_test(pso::DepthTest {
+                    fun: pso::Comparison::Greater,
+                    write: true,
+                })
+                .with_blend_targets(vec![pso::ColorBlendDesc {
+                    mask: pso::ColorMask::ALL,
+                    blend: None,
+                }]),
+        )
+        .build_cached(factory, cache);
+
+    unsafe {
+        factory.destroy_shader_module(shader_vertex);
+        factory.destroy_shader_module(shader_fragment);
+    }
+
+    match pipes {
+        Err(e) => {
+            unsafe {
+                factory.device().destroy_pipeline_layout(pipeline_layout);
+            }
+            Err(e)
+        }
+        Ok(mut pipes) => Ok((pipes.remove(0), pipeline_layout)),
+    }
+}
@@ -1,12 +1,18 @@
 use crate::{
-    debug_drawing::{DebugLine, DebugLines, DebugLinesComponent, DebugLinesParams},
-    pipeline::{PipelineDescBuilder, PipelinesBuilder},
+    debug_drawing::{
+        DebugLine, DebugLines, DebugLinesComponent, DebugLinesParams, ScreenDebugLines,
+    },
+    pipeline::{PipelineCache, PipelineDescBuilder, PipelinesBuilder},
     pod::ViewArgs,
+    stats::DrawCounter,
     submodules::{gather::CameraGatherer, DynamicUniform, DynamicVertexBuffer},
     types::Backend,
     util,
 };
-use amethyst_core::ecs::{Join, Read, SystemData, World, Write, WriteStorage};
+use amethyst_core::{
+    ecs::{Join, Read, SystemData, World, Write, WriteStorage},
+    math::Matrix4,
+};
 use derivative::Derivative;
 use glsl_layout::*;
 use rendy::{
@@ -25,20 +31,29 @@ use rendy::{
 use thread_profiler::profile_scope;
 
 #[derive(Debug, Clone, AsStd140)]
-struct DebugLinesArgs {
-    screen_space_thickness: vec2,
+pub(super) struct DebugLinesArgs {
+    pub(super) screen_space_thickness: vec2,
+    pub(super) pixel_size: vec2,
 }
 
-/// Draw opaque sprites without lighting.
+/// Draw debug lines.
 #[derive(Clone, Debug, PartialEq, Derivative)]
 #[derivative(Default(bound = ""))]
-pub struct DrawDebugLinesDesc;
+pub struct DrawDebugLinesDesc {
+    screen_space: bool,
+}
 
 impl DrawDebugLinesDesc {
     /// Create instance of `DrawDebugLines` render group
     pub fn new() -> Self {
         Default::default()
     }
+
+    /// Create instance of `DrawDebugLines` render group drawing `ScreenDebugLines`
+    /// in pixel coordinates, on top of everything and without a camera.
+    pub fn screen_space() -> Self {
+        Self { screen_space: true }
+    }
 }
 
 impl<B: Backend> RenderGroupDesc<B, World> for DrawDebugLinesDesc {
@@ -47,7 +62,7 @@ impl<B: Backend> RenderGroupDesc<B, World> for DrawDebugLinesDesc {
         _ctx: &GraphContext<B>,
         factory: &mut Factory<B>,
         _queue: QueueId,
-        _aux: &World,
+        aux: &World,
         framebuffer_width: u32,
         framebuffer_height: u32,
         subpass: hal::pass::Subpass<'_, B>,
@@ -61,8 +76,11 @@ impl<B: Backend> RenderGroupDesc<B, World> for DrawDebugLinesDesc {
         let args = DynamicUniform::new(factory, pso::ShaderStageFlags::VERTEX)?;
         let vertex = DynamicVertexBuffer::new();
 
-        let (pipeline, pipeline_layout) = build_lines_pipeline(
+        let pipeline_cache = aux.try_fetch::<PipelineCache<B>>();
+
+        let (pipeline, pipeline_on_top, pipeline_layout) = build_lines_pipelines(
             factory,
+            pipeline_cache.as_ref().and_then(|cache| cache.raw()),
             subpass,
             framebuffer_width,
             framebuffer_height,
@@ -71,13 +89,17 @@ impl<B: Backend> RenderGroupDesc<B, World> for DrawDebugLinesDesc {
 
         Ok(Box::new(DrawDebugLines::<B> {
             pipeline,
+            pipeline_on_top,
             pipeline_layout,
             env,
             args,
             vertex,
             framebuffer_width: framebuffer_width as f32,
             framebuffer_height: framebuffer_height as f32,
+            screen_space: self.screen_space,
             lines: Vec::new(),
+            on_top_lines: Vec::new(),
+            on_top_start: 0,
             change: Default::default(),
         }))
     }
@@ -87,13 +109,17 @@ impl<B: Backend> RenderGroupDesc<B, World> for DrawDebugLinesDesc {
 #[derive(Debug)]
 pub struct DrawDebugLines<B: Backend> {
     pipeline: B::GraphicsPipeline,
+    pipeline_on_top: B::GraphicsPipeline,
     pipeline_layout: B::PipelineLayout,
     env: DynamicUniform<B, ViewArgs>,
     args: DynamicUniform<B, DebugLinesArgs>,
     vertex: DynamicVertexBuffer<B, DebugLine>,
     framebuffer_width: f32,
     framebuffer_height: f32,
+    screen_space: bool,
     lines: Vec<DebugLine>,
+    on_top_lines: Vec<DebugLine>,
+    on_top_start: usize,
     change: util::ChangeDetection,
 }
 
@@ -109,28 +135,53 @@ impl<B: Backend> RenderGroup<B, World> for DrawDebugLines<B> {
         #[cfg(feature = "profiler")]
         profile_scope!("prepare");
 
-        let (lines_comps, lines_res, line_params) = <(
+        let (lines_comps, lines_res, screen_lines_res, line_params) = <(
             WriteStorage<'_, DebugLinesComponent>,
             Option<Write<'_, DebugLines>>,
+            Option<Write<'_, ScreenDebugLines>>,
             Option<Read<'_, DebugLinesParams>>,
         )>::fetch(resources);
 
         let old_len = self.lines.len();
+        let old_on_top_start = self.on_top_start;
         self.lines.clear();
-        for lines_component in (&lines_comps).join() {
-            self.lines.extend_from_slice(lines_component.lines());
-        }
 
-        if let Some(mut lines_res) = lines_res {
-            self.lines.extend(lines_res.drain());
+        let view_args = if self.screen_space {
+            if let Some(mut screen_lines_res) = screen_lines_res {
+                self.on_top_lines.extend(screen_lines_res.drain());
+            }
+            screen_space_view_args(self.framebuffer_width, self.framebuffer_height)
+        } else {
+            for lines_component in (&lines_comps).join() {
+                if lines_component.always_on_top() {
+                    self.on_top_lines.extend_from_slice(lines_component.lines());
+                } else {
+                    self.lines.extend_from_slice(lines_component.lines());
+                }
+            }
+
+            if let Some(mut lines_res) = lines_res {
+                let target = if lines_res.always_on_top() {
+                    &mut self.on_top_lines
+                } else {
+                    &mut self.lines
+                };
+                target.extend(lines_res.drain());
+                target.extend_from_slice(lines_res.draw_timed_lines());
+            };
+
+            CameraGatherer::gather(resources).projview
         };
 
-        let cam = CameraGatherer::gather(resources);
+        // Lines drawn without depth testing go last in the shared instance buffer.
+        self.on_top_start = self.lines.len();
+        self.lines.append(&mut self.on_top_lines);
+
         let line_width = line_params
             .map(|p| p.line_width)
             .unwrap_or(DebugLinesParams::default().line_width);
 
-        self.env.write(factory, index, cam.projview);
+        self.env.write(factory, index, view_args);
         self.args.write(
             factory,
             index,
@@ -140,6 +191,7 @@ impl<B: Backend> RenderGroup<B, World> for DrawDebugLines<B> {
                     (line_width * 2.0) / self.framebuffer_height,
                 ]
                 .into(),
+                pixel_size: [2.0 / self.framebuffer_width, 2.0 / self.framebuffer_height].into(),
             }
             .std140(),
         );
@@ -151,7 +203,7 @@ impl<B: Backend> RenderGroup<B, World> for DrawDebugLines<B> {
                 .write(factory, index, self.lines.len() as u64, Some(&self.lines));
         }
 
-        let changed = old_len != self.lines.len();
+        let changed = old_len != self.lines.len() || old_on_top_start != self.on_top_start;
         self.change.prepare_result(index, changed)
     }
 
@@ -160,7 +212,7 @@ impl<B: Backend> RenderGroup<B, World> for DrawDebugLines<B> {
         mut encoder: RenderPassEncoder<'_, B>,
         index: usize,
         _subpass: hal::pass::Subpass<'_, B>,
-        _resources: &World,
+        resources: &World,
     ) {
         #[cfg(feature = "profiler")]
         profile_scope!("draw");
@@ -170,18 +222,36 @@ impl<B: Backend> RenderGroup<B, World> for DrawDebugLines<B> {
         }
 
         let layout = &self.pipeline_layout;
+        let on_top_start = self.on_top_start as u32;
+        let len = self.lines.len() as u32;
+
         encoder.bind_graphics_pipeline(&self.pipeline);
         self.env.bind(index, layout, 0, &mut encoder);
         self.args.bind(index, layout, 1, &mut encoder);
         self.vertex.bind(index, 0, 0, &mut encoder);
-        unsafe {
-            encoder.draw(0..4, 0..self.lines.len() as u32);
+        let mut draws = DrawCounter::fetch(resources);
+        if on_top_start > 0 {
+            unsafe {
+                encoder.draw(0..4, 0..on_top_start);
+            }
+            draws.draw_quads(on_top_start);
+        }
+
+        if on_top_start < len {
+            encoder.bind_graphics_pipeline(&self.pipeline_on_top);
+            unsafe {
+                encoder.draw(0..4, on_top_start..len);
+            }
+            draws.draw_quads(len - on_top_start);
         }
     }
 
     fn dispose(self: Box<Self>, factory: &mut Factory<B>, _aux: &World) {
         unsafe {
             factory.device().destroy_graphics_pipeline(self.pipeline);
+            factory
+                .device()
+                .destroy_graphics_pipeline(self.pipeline_on_top);
             factory
                 .device()
                 .destroy_pipeline_layout(self.pipeline_layout);
@@ -189,13 +259,33 @@ impl<B: Backend> RenderGroup<B, World> for DrawDebugLines<B> {
     }
 }
 
-fn build_lines_pipeline<B: Backend>(
+/// Projection mapping pixel coordinates with origin in the bottom left corner to clip space.
+fn screen_space_view_args(width: f32, height: f32) -> <ViewArgs as AsStd140>::Std140 {
+    let proj: [[f32; 4]; 4] = [
+        [2.0 / width, 0.0, 0.0, 0.0],
+        [0.0, -2.0 / height, 0.0, 0.0],
+        [0.0, 0.0, 0.0, 0.0],
+        [-1.0, 1.0, 0.0, 1.0],
+    ];
+    let view: [[f32; 4]; 4] = Matrix4::identity().into();
+
+    ViewArgs {
+        proj: proj.into(),
+        view: view.into(),
+        proj_view: proj.into(),
+        clip_plane: [0.0; 4].into(),
+    }
+    .std140()
+}
+
+pub(super) fn build_lines_pipelines<B: Backend>(
     factory: &Factory<B>,
+    cache: Option<&B::PipelineCache>,
     subpass: hal::pass::Subpass<'_, B>,
     framebuffer_width: u32,
     framebuffer_height: u32,
     layouts: Vec<&B::DescriptorSetLayout>,
-) -> Result<(B::GraphicsPipeline, B::PipelineLayout), failure::Error> {
+) -> Result<(B::GraphicsPipeline, B::GraphicsPipeline, B::PipelineLayout), failure::Error> {
     let pipeline_layout = unsafe {
         factory
             .device()
@@ -205,28 +295,31 @@ fn build_lines_pipeline<B: Backend>(
     let shader_vertex = unsafe { super::DEBUG_LINES_VERTEX.module(factory).unwrap() };
     let shader_fragment = unsafe { super::DEBUG_LINES_FRAGMENT.module(factory).unwrap() };
 
+    let pipe_desc = PipelineDescBuilder::new()
+        .with_vertex_desc(&[(DebugLine::vertex(), pso::VertexInputRate::Instance(1))])
+        .with_input_assembler(pso::InputAssemblerDesc::new(hal::Primitive::TriangleStrip))
+        .with_shaders(util::simple_shader_set(
+            &shader_vertex,
+            Some(&shader_fragment),
+        ))
+        .with_layout(&pipeline_layout)
+        .with_subpass(subpass)
+        .with_framebuffer_size(framebuffer_width, framebuffer_height)
+        .with_blend_targets(vec![pso::ColorBlendDesc {
+            mask: pso::ColorMask::ALL,
+            blend: Some(pso::BlendState::ALPHA),
+        }]);
+
     let pipes = PipelinesBuilder::new()
-        .with_pipeline(
-            PipelineDescBuilder::new()
-                .with_vertex_desc(&[(DebugLine::vertex(), pso::VertexInputRate::Instance(1))])
-                .with_input_assembler(pso::InputAssemblerDesc::new(hal::Primitive::TriangleStrip))
-                .with_shaders(util::simple_shader_set(
-                    &shader_vertex,
-                    Some(&shader_fragment),
-                ))
-                .with_layout(&pipeline_layout)
-                .with_subpass(subpass)
-                .with_framebuffer_size(framebuffer_width, framebuffer_height)
-                .with_blend_targets(vec![pso::ColorBlendDesc {
-                    mask: pso::ColorMask::ALL,
-                    blend: Some(pso::BlendState::ALPHA),
-                }])
-                .with_depth_test(pso::DepthTest {
-                    fun: pso::Comparison::GreaterEqual,
-                    write: true,
-                }),
+        .with_pipeline(pipe_desc.clone().with_depth_test(pso::DepthTest {
+            fun: pso::Comparison::GreaterEqual,
+            write: true,
+        }))
+        .with_child_pipeline(
+            0,
+            pipe_desc.with_depth_stencil(pso::DepthStencilDesc::default()),
         )
-        .build(factory, None);
+        .build_cached(factory, cache);
 
     unsafe {
         factory.destroy_shader_module(shader_vertex);
@@ -240,6 +333,9 @@ fn build_lines_pipeline<B: Backend>(
             }
             Err(e)
         }
-        Ok(mut pipes) => Ok((pipes.remove(0), pipeline_layout)),
+        Ok(mut pipes) => {
+            let pipeline_on_top = pipes.remove(1);
+            Ok((pipes.remove(0), pipeline_on_top, pipeline_layout))
+        }
     }
 }
@@ -0,0 +1,272 @@
+use super::{deferred::GBufferInput, post_process_input_access, GBUFFER_COLOR_IMAGES};
+use crate::{
+    batch::OrderedOneLevelBatch,
+    decal::Decal,
+    mtl::MaterialDefaults,
+    pipeline::{PipelineCache, PipelineDescBuilder, PipelinesBuilder},
+    pod::{DecalArgs, ViewArgs},
+    stats::DrawCounter,
+    submodules::{
+        gather::CameraGatherer, DynamicUniform, DynamicVertexBuffer, TextureId, TextureSub,
+    },
+    types::Backend,
+    util,
+};
+use amethyst_core::{
+    ecs::{Join, ReadExpect, ReadStorage, SystemData, World},
+    transform::Transform,
+    Hidden, HiddenPropagate,
+};
+use derivative::Derivative;
+use rendy::{
+    command::{QueueId, RenderPassEncoder},
+    factory::Factory,
+    graph::{
+        render::{PrepareResult, RenderGroup, RenderGroupDesc},
+        GraphContext, ImageAccess, NodeBuffer, NodeImage,
+    },
+    hal::{self, device::Device, pso},
+    mesh::AsVertex,
+    shader::Shader,
+};
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+/// Draw the boxes of every [Decal], projecting them onto the G-buffer written by `DrawGBuffer`.
+///
+/// The G-buffer color images and its depth image must be passed to the render group builder
+/// with `with_image`, in that order. The target needs two color images and no depth
+/// attachment: albedo and world space normal with premultiplied alpha, both cleared to zero.
+/// They are blended over the G-buffer by `DrawDeferredLighting` with decals enabled.
+#[derive(Clone, Debug, PartialEq, Derivative)]
+#[derivative(Default(bound = ""))]
+pub struct DrawDecalsDesc;
+
+impl DrawDecalsDesc {
+    /// Create instance of `DrawDecals` render group
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl<B: Backend> RenderGroupDesc<B, World> for DrawDecalsDesc {
+    fn images(&self) -> Vec<ImageAccess> {
+        vec![post_process_input_access(); GBUFFER_COLOR_IMAGES + 1]
+    }
+
+    fn depth(&self) -> bool {
+        false
+    }
+
+    fn colors(&self) -> usize {
+        super::DECAL_IMAGES
+    }
+
+    fn build(
+        self,
+        ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        _queue: QueueId,
+        world: &World,
+        framebuffer_width: u32,
+        framebuffer_height: u32,
+        subpass: hal::pass::Subpass<'_, B>,
+        _buffers: Vec<NodeBuffer>,
+        images: Vec<NodeImage>,
+    ) -> Result<Box<dyn RenderGroup<B, World>>, failure::Error> {
+        #[cfg(feature = "profiler")]
+        profile_scope!("build");
+
+        let env = DynamicUniform::new(
+            factory,
+            pso::ShaderStageFlags::VERTEX | pso::ShaderStageFlags::FRAGMENT,
+        )?;
+        let gbuffer = GBufferInput::new(ctx, factory, &images)?;
+        let textures = TextureSub::new(factory)?;
+
+        let pipeline_cache = world.try_fetch::<PipelineCache<B>>();
+        let (pipeline, pipeline_layout) = build_decal_pipeline(
+            factory,
+            pipeline_cache.as_ref().and_then(|cache| cache.raw()),
+            subpass,
+            framebuffer_width,
+            framebuffer_height,
+            vec![
+                env.raw_layout(),
+                gbuffer.raw_layout(),
+                textures.raw_layout(),
+                textures.raw_layout(),
+            ],
+        )?;
+
+        Ok(Box::new(DrawDecals::<B> {
+            pipeline,
+            pipeline_layout,
+            env,
+            gbuffer,
+            textures,
+            vertex: DynamicVertexBuffer::new(),
+            decals: Default::default(),
+        }))
+    }
+}
+
+/// Draws decals in the order they are joined, batched by albedo and normal texture.
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""))]
+pub struct DrawDecals<B: Backend> {
+    pipeline: B::GraphicsPipeline,
+    pipeline_layout: B::PipelineLayout,
+    env: DynamicUniform<B, ViewArgs>,
+    gbuffer: GBufferInput<B>,
+    textures: TextureSub<B>,
+    vertex: DynamicVertexBuffer<B, DecalArgs>,
+    decals: OrderedOneLevelBatch<(TextureId, TextureId), DecalArgs>,
+}
+
+impl<B: Backend> RenderGroup<B, World> for DrawDecals<B> {
+    fn prepare(
+        &mut self,
+        factory: &Factory<B>,
+        _queue: QueueId,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        world: &World,
+    ) -> PrepareResult {
+        #[cfg(feature = "profiler")]
+        profile_scope!("prepare");
+
+        let (decals, transforms, hiddens, hidden_props, defaults) = <(
+            ReadStorage<'_, Decal>,
+            ReadStorage<'_, Transform>,
+            ReadStorage<'_, Hidden>,
+            ReadStorage<'_, HiddenPropagate>,
+            ReadExpect<'_, MaterialDefaults>,
+        )>::fetch(world);
+
+        let camera = CameraGatherer::gather(world);
+        self.env.write(factory, index, camera.projview);
+
+        self.decals.swap_clear();
+        let layout = hal::image::Layout::ShaderReadOnlyOptimal;
+        for (decal, transform, _, _) in (&decals, &transforms, !&hiddens, !&hidden_props).join() {
+            let normal = decal.normal.as_ref().unwrap_or(&defaults.0.normal);
+            let albedo_id = self.textures.insert(factory, world, &decal.albedo, layout);
+            let normal_id = self.textures.insert(factory, world, normal, layout);
+            if let (Some((albedo_id, _)), Some((normal_id, _))) = (albedo_id, normal_id) {
+                self.decals.insert(
+                    (albedo_id, normal_id),
+                    Some(DecalArgs::from_decal(decal, transform)),
+                );
+            }
+        }
+        self.textures.maintain(factory, world);
+
+        self.vertex.write(
+            factory,
+            index,
+            self.decals.count() as u64,
+            Some(self.decals.data()),
+        );
+
+        PrepareResult::DrawRecord
+    }
+
+    fn draw_inline(
+        &mut self,
+        mut encoder: RenderPassEncoder<'_, B>,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        world: &World,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("draw");
+
+        if self.decals.count() == 0 {
+            return;
+        }
+
+        let layout = &self.pipeline_layout;
+        encoder.bind_graphics_pipeline(&self.pipeline);
+        self.env.bind(index, layout, 0, &mut encoder);
+        self.gbuffer.bind(layout, 1, &mut encoder);
+        self.vertex.bind(index, 0, 0, &mut encoder);
+        let mut draws = DrawCounter::fetch(world);
+        for (&(albedo, normal), range) in self.decals.iter() {
+            if self.textures.loaded(albedo) && self.textures.loaded(normal) {
+                self.textures.bind(layout, 2, albedo, &mut encoder);
+                self.textures.bind(layout, 3, normal, &mut encoder);
+                let instances = range.end - range.start;
+                unsafe {
+                    encoder.draw(0..36, range);
+                }
+                draws.draw(hal::Primitive::TriangleList, 36, instances);
+            }
+        }
+    }
+
+    fn dispose(self: Box<Self>, factory: &mut Factory<B>, _world: &World) {
+        unsafe {
+            factory.device().destroy_graphics_pipeline(self.pipeline);
+            factory
+                .device()
+                .destroy_pipeline_layout(self.pipeline_layout);
+        }
+    }
+}
+
+fn build_decal_pipeline<B: Backend>(
+    factory: &Factory<B>,
+    cache: Option<&B::PipelineCache>,
+    subpass: hal::pass::Subpass<'_, B>,
+    framebuffer_width: u32,
+    framebuffer_height: u32,
+    layouts: Vec<&B::DescriptorSetLayout>,
+) -> Result<(B::GraphicsPipeline, B::PipelineLayout), failure::Error> {
+    let pipeline_layout = unsafe {
+        factory
+            .device()
+            .create_pipeline_layout(layouts, None as Option<(_, _)>)
+    }?;
+
+    let shader_vertex = unsafe { super::DECAL_VERTEX.module(factory).unwrap() };
+    let shader_fragment = unsafe { super::DECAL_FRAGMENT.module(factory).unwrap() };
+
+    // Only the back faces of the boxes are drawn, so every covered pixel is shaded once, even
+    // with the camera inside a box.
+    let blend = pso::ColorBlendDesc {
+        mask: pso::ColorMask::ALL,
+        blend: Some(pso::BlendState::PREMULTIPLIED_ALPHA),
+    };
+    let pipes = PipelinesBuilder::new()
+        .with_pipeline(
+            PipelineDescBuilder::new()
+                .with_vertex_desc(&[(DecalArgs::vertex(), pso::VertexInputRate::Instance(1))])
+                .with_shaders(util::simple_shader_set(
+                    &shader_vertex,
+                    Some(&shader_fragment),
+                ))
+                .with_layout(&pipeline_layout)
+                .with_subpass(subpass)
+                .with_framebuffer_size(framebuffer_width, framebuffer_height)
+                .with_face_culling(pso::Face::FRONT)
+                .with_blend_targets(vec![blend; super::DECAL_IMAGES]),
+        )
+        .build_cached(factory, cache);
+
+    unsafe {
+        factory.destroy_shader_module(shader_vertex);
+        factory.destroy_shader_module(shader_fragment);
+    }
+
+    match pipes {
+        Err(e) => {
+            unsafe {
+                factory.device().destroy_pipeline_layout(pipeline_layout);
+            }
+            Err(e)
+        }
+        Ok(mut pipes) => Ok((pipes.remove(0), pipeline_layout)),
+    }
+}
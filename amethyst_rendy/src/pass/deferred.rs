@@ -0,0 +1,454 @@
+use super::{base_3d::*, post_process::input_view, post_process_input_access};
+use crate::{
+    light::Light,
+    mtl::FullTextureSet,
+    pipeline::{PipelineCache, PipelineDescBuilder, PipelinesBuilder},
+    pod::{DeferredPointLight, IntoPod},
+    shader_reload::ReloadableShader,
+    skinning::JointCombined,
+    stats::DrawCounter,
+    submodules::{DynamicVertexBuffer, EnvironmentMapSub, EnvironmentSub},
+    types::Backend,
+    util,
+};
+use amethyst_core::{
+    ecs::{Join, ReadStorage, SystemData, World},
+    math::{convert, Vector3},
+    transform::Transform,
+    Hidden,
+};
+use derivative::Derivative;
+use rendy::{
+    command::{QueueId, RenderPassEncoder},
+    factory::Factory,
+    graph::{
+        render::{PrepareResult, RenderGroup, RenderGroupDesc},
+        GraphContext, ImageAccess, NodeBuffer, NodeImage,
+    },
+    hal::{self, device::Device, pso},
+    mesh::{AsVertex, Normal, Position, Tangent, TexCoord, VertexFormat},
+    resource::{
+        DescriptorSet, DescriptorSetLayout, Escape, Filter, Handle as RendyHandle, ImageView,
+        Sampler, SamplerInfo, WrapMode,
+    },
+    shader::Shader,
+};
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+/// Number of color images of the G-buffer: albedo and ambient occlusion, normal and
+/// roughness, emission and metallic.
+pub const GBUFFER_COLOR_IMAGES: usize = 3;
+
+/// Number of color images written by `DrawDecals`: albedo and normal.
+pub const DECAL_IMAGES: usize = 2;
+
+/// Implementation of `Base3DPassDef` writing the physically-based material properties
+/// of opaque meshes to the G-buffer of the deferred shading path.
+#[derive(Debug)]
+pub struct GBufferPassDef;
+impl Base3DPassDef for GBufferPassDef {
+    const NAME: &'static str = "GBuffer";
+    type TextureSet = FullTextureSet;
+    fn vertex_shader() -> &'static ReloadableShader {
+        &super::POS_NORM_TANG_TEX_VERTEX
+    }
+    fn vertex_skinned_shader() -> &'static ReloadableShader {
+        &super::POS_NORM_TANG_TEX_SKIN_VERTEX
+    }
+    fn vertex_morph_shader() -> Option<&'static ReloadableShader> {
+        Some(&super::POS_NORM_TANG_TEX_MORPH_VERTEX)
+    }
+    fn fragment_shader() -> &'static ReloadableShader {
+        &super::GBUFFER_FRAGMENT
+    }
+    fn color_outputs() -> usize {
+        GBUFFER_COLOR_IMAGES
+    }
+    fn uses_lightmap_coords() -> bool {
+        true
+    }
+    fn base_format() -> Vec<VertexFormat> {
+        vec![
+            Position::vertex(),
+            Normal::vertex(),
+            Tangent::vertex(),
+            TexCoord::vertex(),
+        ]
+    }
+    fn skinned_format() -> Vec<VertexFormat> {
+        vec![
+            Position::vertex(),
+            Normal::vertex(),
+            Tangent::vertex(),
+            TexCoord::vertex(),
+            JointCombined::vertex(),
+        ]
+    }
+}
+
+/// Describes the G-buffer pass of the deferred shading path
+pub type DrawGBufferDesc<B> = DrawBase3DDesc<B, GBufferPassDef>;
+/// Draws the G-buffer pass of the deferred shading path
+pub type DrawGBuffer<B> = DrawBase3D<B, GBufferPassDef>;
+
+/// Shade the G-buffer written by `DrawGBuffer`, accumulating the light of every point light.
+///
+/// The G-buffer color images and its depth image must be passed to the render group
+/// builder with `with_image`, in that order, followed by the albedo and normal images
+/// written by `DrawDecals` when decals are enabled. The G-buffer depth is written to the
+/// depth attachment of the target, so transparent meshes and skyboxes can be drawn after it.
+#[derive(Clone, Debug, PartialEq, Derivative)]
+#[derivative(Default(bound = ""))]
+pub struct DrawDeferredLightingDesc {
+    decals: bool,
+}
+
+impl DrawDeferredLightingDesc {
+    /// Create instance of `DrawDeferredLighting` render group
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Blend the images written by `DrawDecals` over the G-buffer.
+    pub fn with_decals(mut self) -> Self {
+        self.decals = true;
+        self
+    }
+}
+
+impl<B: Backend> RenderGroupDesc<B, World> for DrawDeferredLightingDesc {
+    fn images(&self) -> Vec<ImageAccess> {
+        let decal_images = if self.decals { DECAL_IMAGES } else { 0 };
+        vec![post_process_input_access(); GBUFFER_COLOR_IMAGES + 1 + decal_images]
+    }
+
+    fn build(
+        self,
+        ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        queue: QueueId,
+        aux: &World,
+        framebuffer_width: u32,
+        framebuffer_height: u32,
+        subpass: hal::pass::Subpass<'_, B>,
+        _buffers: Vec<NodeBuffer>,
+        images: Vec<NodeImage>,
+    ) -> Result<Box<dyn RenderGroup<B, World>>, failure::Error> {
+        #[cfg(feature = "profiler")]
+        profile_scope!("build");
+
+        let env = EnvironmentSub::new(
+            factory,
+            [
+                pso::ShaderStageFlags::VERTEX | pso::ShaderStageFlags::FRAGMENT,
+                pso::ShaderStageFlags::FRAGMENT,
+            ],
+        )?;
+        let environment_map = EnvironmentMapSub::new(factory, queue)?;
+
+        let gbuffer = GBufferInput::new(ctx, factory, &images)?;
+
+        let pipeline_cache = aux.try_fetch::<PipelineCache<B>>();
+
+        let (pipeline, pipeline_point_lights, pipeline_layout) = build_deferred_pipelines(
+            factory,
+            pipeline_cache.as_ref().and_then(|cache| cache.raw()),
+            subpass,
+            framebuffer_width,
+            framebuffer_height,
+            self.decals,
+            vec![
+                env.raw_layout(),
+                gbuffer.raw_layout(),
+                environment_map.raw_layout(),
+            ],
+        )?;
+
+        Ok(Box::new(DrawDeferredLighting::<B> {
+            pipeline,
+            pipeline_point_lights,
+            pipeline_layout,
+            env,
+            environment_map,
+            gbuffer,
+            point_lights: DynamicVertexBuffer::new(),
+            point_light_count: 0,
+        }))
+    }
+}
+
+/// Shades the G-buffer of the deferred shading path.
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""))]
+pub struct DrawDeferredLighting<B: Backend> {
+    pipeline: B::GraphicsPipeline,
+    pipeline_point_lights: B::GraphicsPipeline,
+    pipeline_layout: B::PipelineLayout,
+    env: EnvironmentSub<B>,
+    environment_map: EnvironmentMapSub<B>,
+    gbuffer: GBufferInput<B>,
+    point_lights: DynamicVertexBuffer<B, DeferredPointLight>,
+    point_light_count: u32,
+}
+
+impl<B: Backend> RenderGroup<B, World> for DrawDeferredLighting<B> {
+    fn prepare(
+        &mut self,
+        factory: &Factory<B>,
+        _queue: QueueId,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        resources: &World,
+    ) -> PrepareResult {
+        #[cfg(feature = "profiler")]
+        profile_scope!("prepare");
+
+        self.env.process(factory, index, resources);
+        self.environment_map.process(factory, resources);
+
+        let (lights, transforms, hiddens) = <(
+            ReadStorage<'_, Light>,
+            ReadStorage<'_, Transform>,
+            ReadStorage<'_, Hidden>,
+        )>::fetch(resources);
+
+        let point_lights = (&lights, &transforms, !&hiddens)
+            .join()
+            .filter_map(|(light, transform, _)| match light {
+                Light::Point(light) if light.radius > 0.0 => Some(DeferredPointLight {
+                    position: convert::<_, Vector3<f32>>(transform.global_matrix().column(3).xyz())
+                        .into_pod(),
+                    radius: light.radius,
+                    color: light.color.into_pod(),
+                    intensity: light.intensity,
+                    smoothness: light.smoothness.max(1.0),
+                }),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        self.point_light_count = point_lights.len() as u32;
+        self.point_lights.write(
+            factory,
+            index,
+            point_lights.len() as u64,
+            Some(&point_lights),
+        );
+
+        // The environment is recorded into the command buffer.
+        PrepareResult::DrawRecord
+    }
+
+    fn draw_inline(
+        &mut self,
+        mut encoder: RenderPassEncoder<'_, B>,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        resources: &World,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("draw");
+
+        let layout = &self.pipeline_layout;
+        encoder.bind_graphics_pipeline(&self.pipeline);
+        self.env.bind(index, layout, 0, &mut encoder);
+        self.gbuffer.bind(layout, 1, &mut encoder);
+        self.environment_map.bind(layout, 2, &mut encoder);
+        let mut draws = DrawCounter::fetch(resources);
+        unsafe {
+            encoder.draw(0..3, 0..1);
+        }
+        draws.draw(hal::Primitive::TriangleList, 3, 1);
+
+        if self.point_light_count > 0 && self.point_lights.bind(index, 0, 0, &mut encoder) {
+            encoder.bind_graphics_pipeline(&self.pipeline_point_lights);
+            unsafe {
+                encoder.draw(0..6, 0..self.point_light_count);
+            }
+            draws.draw(hal::Primitive::TriangleList, 6, self.point_light_count);
+        }
+    }
+
+    fn dispose(self: Box<Self>, factory: &mut Factory<B>, _aux: &World) {
+        unsafe {
+            factory.device().destroy_graphics_pipeline(self.pipeline);
+            factory
+                .device()
+                .destroy_graphics_pipeline(self.pipeline_point_lights);
+            factory
+                .device()
+                .destroy_pipeline_layout(self.pipeline_layout);
+        }
+    }
+}
+
+/// Descriptor set sampling the G-buffer images, and the decal images when they are drawn,
+/// as laid out by `header/deferred.frag`.
+#[derive(Debug)]
+pub(crate) struct GBufferInput<B: Backend> {
+    layout: RendyHandle<DescriptorSetLayout<B>>,
+    set: Escape<DescriptorSet<B>>,
+    _views: Vec<Escape<ImageView<B>>>,
+    _sampler: RendyHandle<Sampler<B>>,
+}
+
+impl<B: Backend> GBufferInput<B> {
+    pub(crate) fn new(
+        ctx: &GraphContext<B>,
+        factory: &Factory<B>,
+        images: &[NodeImage],
+    ) -> Result<Self, failure::Error> {
+        let layout: RendyHandle<DescriptorSetLayout<B>> = set_layout! {
+            factory,
+            [images.len()] CombinedImageSampler pso::ShaderStageFlags::FRAGMENT
+        };
+        let views = images
+            .iter()
+            .map(|image| input_view(ctx, factory, image))
+            .collect::<Result<Vec<_>, _>>()?;
+        let sampler = factory.get_sampler(SamplerInfo::new(Filter::Nearest, WrapMode::Clamp))?;
+
+        let set = factory.create_descriptor_set(layout.clone())?;
+        unsafe {
+            factory.write_descriptor_sets(
+                views
+                    .iter()
+                    .enumerate()
+                    .map(|(binding, view)| {
+                        util::desc_write(
+                            set.raw(),
+                            binding as u32,
+                            pso::Descriptor::CombinedImageSampler(
+                                view.raw(),
+                                hal::image::Layout::ShaderReadOnlyOptimal,
+                                sampler.raw(),
+                            ),
+                        )
+                    })
+                    .collect::<Vec<_>>(),
+            );
+        }
+
+        Ok(Self {
+            layout,
+            set,
+            _views: views,
+            _sampler: sampler,
+        })
+    }
+
+    pub(crate) fn raw_layout(&self) -> &B::DescriptorSetLayout {
+        self.layout.raw()
+    }
+
+    pub(crate) fn bind(
+        &self,
+        pipeline_layout: &B::PipelineLayout,
+        set_id: u32,
+        encoder: &mut RenderPassEncoder<'_, B>,
+    ) {
+        unsafe {
+            encoder.bind_graphics_descriptor_sets(
+                pipeline_layout,
+                set_id,
+                Some(self.set.raw()),
+                std::iter::empty(),
+            );
+        }
+    }
+}
+
+fn build_deferred_pipelines<B: Backend>(
+    factory: &Factory<B>,
+    cache: Option<&B::PipelineCache>,
+    subpass: hal::pass::Subpass<'_, B>,
+    framebuffer_width: u32,
+    framebuffer_height: u32,
+    decals: bool,
+    layouts: Vec<&B::DescriptorSetLayout>,
+) -> Result<(B::GraphicsPipeline, B::GraphicsPipeline, B::PipelineLayout), failure::Error> {
+    let pipeline_layout = unsafe {
+        factory
+            .device()
+            .create_pipeline_layout(layouts, None as Option<(_, _)>)
+    }?;
+
+    let shader_vertex = unsafe { super::FULLSCREEN_VERTEX.module(factory).unwrap() };
+    let (fragment, point_fragment) = if decals {
+        (
+            &*super::DEFERRED_LIGHTING_DECALS_FRAGMENT,
+            &*super::DEFERRED_POINT_LIGHT_DECALS_FRAGMENT,
+        )
+    } else {
+        (
+            &*super::DEFERRED_LIGHTING_FRAGMENT,
+            &*super::DEFERRED_POINT_LIGHT_FRAGMENT,
+        )
+    };
+    let shader_fragment = unsafe { fragment.module(factory).unwrap() };
+    let shader_point_vertex =
+        unsafe { super::DEFERRED_POINT_LIGHT_VERTEX.module(factory).unwrap() };
+    let shader_point_fragment = unsafe { point_fragment.module(factory).unwrap() };
+
+    let pipes = PipelinesBuilder::new()
+        .with_pipeline(
+            PipelineDescBuilder::new()
+                .with_shaders(util::simple_shader_set(
+                    &shader_vertex,
+                    Some(&shader_fragment),
+                ))
+                .with_layout(&pipeline_layout)
+                .with_subpass(subpass)
+                .with_framebuffer_size(framebuffer_width, framebuffer_height)
+                .with_depth_test(pso::DepthTest {
+                    fun: pso::Comparison::Always,
+                    write: true,
+                })
+                .with_blend_targets(vec![pso::ColorBlendDesc {
+                    mask: pso::ColorMask::ALL,
+                    blend: None,
+                }]),
+        )
+        .with_pipeline(
+            PipelineDescBuilder::new()
+                .with_vertex_desc(&[(
+                    DeferredPointLight::vertex(),
+                    pso::VertexInputRate::Instance(1),
+                )])
+                .with_shaders(util::simple_shader_set(
+                    &shader_point_vertex,
+                    Some(&shader_point_fragment),
+                ))
+                .with_layout(&pipeline_layout)
+                .with_subpass(subpass)
+                .with_framebuffer_size(framebuffer_width, framebuffer_height)
+                .with_depth_stencil(pso::DepthStencilDesc::default())
+                .with_blend_targets(vec![pso::ColorBlendDesc {
+                    mask: pso::ColorMask::ALL,
+                    blend: Some(pso::BlendState::ADD),
+                }]),
+        )
+        .build_cached(factory, cache);
+
+    unsafe {
+        factory.destroy_shader_module(shader_vertex);
+        factory.destroy_shader_module(shader_fragment);
+        factory.destroy_shader_module(shader_point_vertex);
+        factory.destroy_shader_module(shader_point_fragment);
+    }
+
+    match pipes {
+        Err(e) => {
+            unsafe {
+                factory.device().destroy_pipeline_layout(pipeline_layout);
+            }
+            Err(e)
+        }
+        Ok(mut pipes) => {
+            let pipeline_point_lights = pipes.remove(1);
+            Ok((pipes.remove(0), pipeline_point_lights, pipeline_layout))
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use crate::{
+    dof::{DepthOfField, DofParams},
+    pass::DrawPostProcessDesc,
+    submodules::gather::CameraGatherer,
+};
+
+impl DrawPostProcessDesc {
+    /// Create instance of `DrawPostProcess` render group computing the circle of confusion
+    /// of every pixel from the depth buffer, stored in the alpha of the copied input colors.
+    ///
+    /// Sampled images are the input and the depth it was drawn with. Focus is set by the
+    /// [DepthOfField] of the active camera.
+    pub fn dof_coc() -> Self {
+        Self::new(super::DOF_COC_FRAGMENT.clone()).with_constants(|world| {
+            let params = DofParams::fetch(world);
+            let focus = DepthOfField::active(world).filter(|_| params.enabled);
+            let projection = CameraGatherer::gather(world).projection;
+            vec![
+                projection[(2, 2)],
+                projection[(3, 2)],
+                projection[(2, 3)],
+                projection[(3, 3)],
+                if focus.is_some() { 1.0 } else { 0.0 },
+                focus.map_or(0.0, |f| f.focus_distance.max(0.0)),
+                focus.map_or(0.0, |f| f.aperture.max(0.0)),
+            ]
+        })
+    }
+
+    /// Create instance of `DrawPostProcess` render group blurring colors by the circle
+    /// of confusion computed by `dof_coc`, as configured by [DofParams].
+    pub fn dof_bokeh() -> Self {
+        Self::new(super::DOF_BOKEH_FRAGMENT.clone()).with_constants(|world| {
+            let params = DofParams::fetch(world);
+            vec![
+                if params.enabled { 1.0 } else { 0.0 },
+                params.max_blur_radius.max(0.0),
+                params.sample_spacing,
+            ]
+        })
+    }
+}
@@ -1,9 +1,6 @@
 use super::base_3d::*;
-use crate::{mtl::TexAlbedo, skinning::JointCombined};
-use rendy::{
-    mesh::{AsVertex, Position, TexCoord, VertexFormat},
-    shader::SpirvShader,
-};
+use crate::{mtl::TexAlbedo, shader_reload::ReloadableShader, skinning::JointCombined};
+use rendy::mesh::{AsVertex, Position, TexCoord, VertexFormat};
 
 /// Implementation of `Base3DPassDef` to describe a flat 3D pass
 #[derive(Debug)]
@@ -11,15 +8,21 @@ pub struct FlatPassDef;
 impl Base3DPassDef for FlatPassDef {
     const NAME: &'static str = "Flat";
     type TextureSet = TexAlbedo;
-    fn vertex_shader() -> &'static SpirvShader {
+    fn vertex_shader() -> &'static ReloadableShader {
         &super::POS_TEX_VERTEX
     }
-    fn vertex_skinned_shader() -> &'static SpirvShader {
+    fn vertex_skinned_shader() -> &'static ReloadableShader {
         &super::POS_TEX_SKIN_VERTEX
     }
-    fn fragment_shader() -> &'static SpirvShader {
+    fn fragment_shader() -> &'static ReloadableShader {
         &super::FLAT_FRAGMENT
     }
+    fn oit_fragment_shader() -> Option<&'static ReloadableShader> {
+        Some(&super::FLAT_OIT_FRAGMENT)
+    }
+    fn debug_fragment_shader() -> Option<&'static ReloadableShader> {
+        Some(&super::DEBUG_POS_TEX_FRAGMENT)
+    }
     fn base_format() -> Vec<VertexFormat> {
         vec![Position::vertex(), TexCoord::vertex()]
     }
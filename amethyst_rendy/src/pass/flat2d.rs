@@ -1,10 +1,11 @@
 use crate::{
     batch::{GroupIterator, OneLevelBatch, OrderedOneLevelBatch},
-    pipeline::{PipelineDescBuilder, PipelinesBuilder},
+    pipeline::{PipelineCache, PipelineDescBuilder, PipelinesBuilder},
     pod::SpriteArgs,
-    resources::Tint,
+    resources::{ScissorRect, Tint},
     sprite::{SpriteRender, SpriteSheet},
     sprite_visibility::SpriteVisibility,
+    stats::DrawCounter,
     submodules::{DynamicVertexBuffer, FlatEnvironmentSub, TextureId, TextureSub},
     types::{Backend, Texture},
     util,
@@ -49,7 +50,7 @@ impl<B: Backend> RenderGroupDesc<B, World> for DrawFlat2DDesc {
         _ctx: &GraphContext<B>,
         factory: &mut Factory<B>,
         _queue: QueueId,
-        _aux: &World,
+        aux: &World,
         framebuffer_width: u32,
         framebuffer_height: u32,
         subpass: hal::pass::Subpass<'_, B>,
@@ -63,8 +64,11 @@ impl<B: Backend> RenderGroupDesc<B, World> for DrawFlat2DDesc {
         let textures = TextureSub::new(factory)?;
         let vertex = DynamicVertexBuffer::new();
 
+        let pipeline_cache = aux.try_fetch::<PipelineCache<B>>();
+
         let (pipeline, pipeline_layout) = build_sprite_pipeline(
             factory,
+            pipeline_cache.as_ref().and_then(|cache| cache.raw()),
             subpass,
             framebuffer_width,
             framebuffer_height,
@@ -79,6 +83,7 @@ impl<B: Backend> RenderGroupDesc<B, World> for DrawFlat2DDesc {
             textures,
             vertex,
             sprites: Default::default(),
+            framebuffer_rect: framebuffer_rect(framebuffer_width, framebuffer_height),
         }))
     }
 }
@@ -91,7 +96,8 @@ pub struct DrawFlat2D<B: Backend> {
     env: FlatEnvironmentSub<B>,
     textures: TextureSub<B>,
     vertex: DynamicVertexBuffer<B, SpriteArgs>,
-    sprites: OneLevelBatch<TextureId, SpriteArgs>,
+    sprites: OneLevelBatch<(TextureId, Option<ScissorRect>), SpriteArgs>,
+    framebuffer_rect: pso::Rect,
 }
 
 impl<B: Backend> RenderGroup<B, World> for DrawFlat2D<B> {
@@ -115,6 +121,7 @@ impl<B: Backend> RenderGroup<B, World> for DrawFlat2D<B> {
             sprite_renders,
             transforms,
             tints,
+            scissors,
         ) = <(
             Read<'_, AssetStorage<SpriteSheet>>,
             Read<'_, AssetStorage<Texture>>,
@@ -124,6 +131,7 @@ impl<B: Backend> RenderGroup<B, World> for DrawFlat2D<B> {
             ReadStorage<'_, SpriteRender>,
             ReadStorage<'_, Transform>,
             ReadStorage<'_, Tint>,
+            ReadStorage<'_, ScissorRect>,
         )>::fetch(world);
 
         self.env.process(factory, index, world);
@@ -141,10 +149,11 @@ impl<B: Backend> RenderGroup<B, World> for DrawFlat2D<B> {
                 &sprite_renders,
                 &transforms,
                 tints.maybe(),
+                scissors.maybe(),
                 &visibility.visible_unordered,
             )
                 .join()
-                .filter_map(|(sprite_render, global, tint, _)| {
+                .filter_map(|(sprite_render, global, tint, scissor, _)| {
                     let (batch_data, texture) = SpriteArgs::from_data(
                         &tex_storage,
                         &sprite_sheet_storage,
@@ -158,11 +167,9 @@ impl<B: Backend> RenderGroup<B, World> for DrawFlat2D<B> {
                         texture,
                         hal::image::Layout::ShaderReadOnlyOptimal,
                     )?;
-                    Some((tex_id, batch_data))
+                    Some(((tex_id, scissor.copied()), batch_data))
                 })
-                .for_each_group(|tex_id, batch_data| {
-                    sprites_ref.insert(tex_id, batch_data.drain(..))
-                });
+                .for_each_group(|key, batch_data| sprites_ref.insert(key, batch_data.drain(..)));
         }
 
         self.textures.maintain(factory, world);
@@ -188,7 +195,7 @@ impl<B: Backend> RenderGroup<B, World> for DrawFlat2D<B> {
         mut encoder: RenderPassEncoder<'_, B>,
         index: usize,
         _subpass: hal::pass::Subpass<'_, B>,
-        _world: &World,
+        world: &World,
     ) {
         #[cfg(feature = "profiler")]
         profile_scope!("draw opaque");
@@ -197,11 +204,17 @@ impl<B: Backend> RenderGroup<B, World> for DrawFlat2D<B> {
         encoder.bind_graphics_pipeline(&self.pipeline);
         self.env.bind(index, layout, 0, &mut encoder);
         self.vertex.bind(index, 0, 0, &mut encoder);
-        for (&tex, range) in self.sprites.iter() {
+        let mut draws = DrawCounter::fetch(world);
+        for (&(tex, scissor), range) in self.sprites.iter() {
             if self.textures.loaded(tex) {
-                self.textures.bind(layout, 1, tex, &mut encoder);
-                unsafe {
-                    encoder.draw(0..4, range);
+                if let Some(rect) = scissor_rect(scissor, self.framebuffer_rect) {
+                    self.textures.bind(layout, 1, tex, &mut encoder);
+                    let instances = range.end - range.start;
+                    unsafe {
+                        encoder.set_scissors(0, Some(&rect));
+                        encoder.draw(0..4, range);
+                    }
+                    draws.draw_quads(instances);
                 }
             }
         }
@@ -217,6 +230,8 @@ impl<B: Backend> RenderGroup<B, World> for DrawFlat2D<B> {
     }
 }
 /// Describes drawing transparent sprites without lighting.
+///
+/// Sprites with a `SpriteOrder` are drawn by this pass too, in the order of their layers.
 #[derive(Clone, Debug, PartialEq, Derivative)]
 #[derivative(Default(bound = ""))]
 pub struct DrawFlat2DTransparentDesc;
@@ -234,7 +249,7 @@ impl<B: Backend> RenderGroupDesc<B, World> for DrawFlat2DTransparentDesc {
         _ctx: &GraphContext<B>,
         factory: &mut Factory<B>,
         _queue: QueueId,
-        _world: &World,
+        world: &World,
         framebuffer_width: u32,
         framebuffer_height: u32,
         subpass: hal::pass::Subpass<'_, B>,
@@ -248,8 +263,11 @@ impl<B: Backend> RenderGroupDesc<B, World> for DrawFlat2DTransparentDesc {
         let textures = TextureSub::new(factory)?;
         let vertex = DynamicVertexBuffer::new();
 
+        let pipeline_cache = world.try_fetch::<PipelineCache<B>>();
+
         let (pipeline, pipeline_layout) = build_sprite_pipeline(
             factory,
+            pipeline_cache.as_ref().and_then(|cache| cache.raw()),
             subpass,
             framebuffer_width,
             framebuffer_height,
@@ -264,6 +282,7 @@ impl<B: Backend> RenderGroupDesc<B, World> for DrawFlat2DTransparentDesc {
             textures,
             vertex,
             sprites: Default::default(),
+            framebuffer_rect: framebuffer_rect(framebuffer_width, framebuffer_height),
             change: Default::default(),
         }))
     }
@@ -277,7 +296,8 @@ pub struct DrawFlat2DTransparent<B: Backend> {
     env: FlatEnvironmentSub<B>,
     textures: TextureSub<B>,
     vertex: DynamicVertexBuffer<B, SpriteArgs>,
-    sprites: OrderedOneLevelBatch<TextureId, SpriteArgs>,
+    sprites: OrderedOneLevelBatch<(TextureId, Option<ScissorRect>), SpriteArgs>,
+    framebuffer_rect: pso::Rect,
     change: util::ChangeDetection,
 }
 
@@ -293,15 +313,23 @@ impl<B: Backend> RenderGroup<B, World> for DrawFlat2DTransparent<B> {
         #[cfg(feature = "profiler")]
         profile_scope!("prepare transparent");
 
-        let (sprite_sheet_storage, tex_storage, visibility, sprite_renders, transforms, tints) =
-            <(
-                Read<'_, AssetStorage<SpriteSheet>>,
-                Read<'_, AssetStorage<Texture>>,
-                ReadExpect<'_, SpriteVisibility>,
-                ReadStorage<'_, SpriteRender>,
-                ReadStorage<'_, Transform>,
-                ReadStorage<'_, Tint>,
-            )>::fetch(world);
+        let (
+            sprite_sheet_storage,
+            tex_storage,
+            visibility,
+            sprite_renders,
+            transforms,
+            tints,
+            scissors,
+        ) = <(
+            Read<'_, AssetStorage<SpriteSheet>>,
+            Read<'_, AssetStorage<Texture>>,
+            ReadExpect<'_, SpriteVisibility>,
+            ReadStorage<'_, SpriteRender>,
+            ReadStorage<'_, Transform>,
+            ReadStorage<'_, Tint>,
+            ReadStorage<'_, ScissorRect>,
+        )>::fetch(world);
 
         self.env.process(factory, index, world);
         self.sprites.swap_clear();
@@ -314,12 +342,18 @@ impl<B: Backend> RenderGroup<B, World> for DrawFlat2DTransparent<B> {
             #[cfg(feature = "profiler")]
             profile_scope!("gather_sprites_trans");
 
-            let mut joined = (&sprite_renders, &transforms, tints.maybe()).join();
+            let mut joined = (
+                &sprite_renders,
+                &transforms,
+                tints.maybe(),
+                scissors.maybe(),
+            )
+                .join();
             visibility
                 .visible_ordered
                 .iter()
                 .filter_map(|e| joined.get_unchecked(e.id()))
-                .filter_map(|(sprite_render, global, tint)| {
+                .filter_map(|(sprite_render, global, tint, scissor)| {
                     let (batch_data, texture) = SpriteArgs::from_data(
                         &tex_storage,
                         &sprite_sheet_storage,
@@ -334,10 +368,10 @@ impl<B: Backend> RenderGroup<B, World> for DrawFlat2DTransparent<B> {
                         hal::image::Layout::ShaderReadOnlyOptimal,
                     )?;
                     changed = changed || this_changed;
-                    Some((tex_id, batch_data))
+                    Some(((tex_id, scissor.copied()), batch_data))
                 })
-                .for_each_group(|tex_id, batch_data| {
-                    sprites_ref.insert(tex_id, batch_data.drain(..));
+                .for_each_group(|key, batch_data| {
+                    sprites_ref.insert(key, batch_data.drain(..));
                 });
         }
         self.textures.maintain(factory, world);
@@ -363,7 +397,7 @@ impl<B: Backend> RenderGroup<B, World> for DrawFlat2DTransparent<B> {
         mut encoder: RenderPassEncoder<'_, B>,
         index: usize,
         _subpass: hal::pass::Subpass<'_, B>,
-        _world: &World,
+        world: &World,
     ) {
         #[cfg(feature = "profiler")]
         profile_scope!("draw transparent");
@@ -372,11 +406,17 @@ impl<B: Backend> RenderGroup<B, World> for DrawFlat2DTransparent<B> {
         encoder.bind_graphics_pipeline(&self.pipeline);
         self.env.bind(index, layout, 0, &mut encoder);
         self.vertex.bind(index, 0, 0, &mut encoder);
-        for (&tex, range) in self.sprites.iter() {
+        let mut draws = DrawCounter::fetch(world);
+        for (&(tex, scissor), range) in self.sprites.iter() {
             if self.textures.loaded(tex) {
-                self.textures.bind(layout, 1, tex, &mut encoder);
-                unsafe {
-                    encoder.draw(0..4, range);
+                if let Some(rect) = scissor_rect(scissor, self.framebuffer_rect) {
+                    self.textures.bind(layout, 1, tex, &mut encoder);
+                    let instances = range.end - range.start;
+                    unsafe {
+                        encoder.set_scissors(0, Some(&rect));
+                        encoder.draw(0..4, range);
+                    }
+                    draws.draw_quads(instances);
                 }
             }
         }
@@ -394,6 +434,7 @@ impl<B: Backend> RenderGroup<B, World> for DrawFlat2DTransparent<B> {
 
 fn build_sprite_pipeline<B: Backend>(
     factory: &Factory<B>,
+    cache: Option<&B::PipelineCache>,
     subpass: hal::pass::Subpass<'_, B>,
     framebuffer_width: u32,
     framebuffer_height: u32,
@@ -409,32 +450,42 @@ fn build_sprite_pipeline<B: Backend>(
     let shader_vertex = unsafe { super::SPRITE_VERTEX.module(factory).unwrap() };
     let shader_fragment = unsafe { super::SPRITE_FRAGMENT.module(factory).unwrap() };
 
+    let mut pipe_desc = PipelineDescBuilder::new()
+        .with_vertex_desc(&[(SpriteArgs::vertex(), pso::VertexInputRate::Instance(1))])
+        .with_input_assembler(pso::InputAssemblerDesc::new(hal::Primitive::TriangleStrip))
+        .with_shaders(util::simple_shader_set(
+            &shader_vertex,
+            Some(&shader_fragment),
+        ))
+        .with_layout(&pipeline_layout)
+        .with_subpass(subpass)
+        .with_framebuffer_size(framebuffer_width, framebuffer_height)
+        .with_blend_targets(vec![pso::ColorBlendDesc {
+            mask: pso::ColorMask::ALL,
+            blend: if transparent {
+                Some(pso::BlendState::PREMULTIPLIED_ALPHA)
+            } else {
+                None
+            },
+        }])
+        .with_depth_test(pso::DepthTest {
+            fun: pso::Comparison::Greater,
+            write: !transparent,
+        });
+    // Scissor is set per batch while drawing.
+    pipe_desc.set_baked_states(pso::BakedStates {
+        viewport: Some(pso::Viewport {
+            rect: framebuffer_rect(framebuffer_width, framebuffer_height),
+            depth: 0.0..1.0,
+        }),
+        scissor: None,
+        blend_color: None,
+        depth_bounds: None,
+    });
+
     let pipes = PipelinesBuilder::new()
-        .with_pipeline(
-            PipelineDescBuilder::new()
-                .with_vertex_desc(&[(SpriteArgs::vertex(), pso::VertexInputRate::Instance(1))])
-                .with_input_assembler(pso::InputAssemblerDesc::new(hal::Primitive::TriangleStrip))
-                .with_shaders(util::simple_shader_set(
-                    &shader_vertex,
-                    Some(&shader_fragment),
-                ))
-                .with_layout(&pipeline_layout)
-                .with_subpass(subpass)
-                .with_framebuffer_size(framebuffer_width, framebuffer_height)
-                .with_blend_targets(vec![pso::ColorBlendDesc {
-                    mask: pso::ColorMask::ALL,
-                    blend: if transparent {
-                        Some(pso::BlendState::PREMULTIPLIED_ALPHA)
-                    } else {
-                        None
-                    },
-                }])
-                .with_depth_test(pso::DepthTest {
-                    fun: pso::Comparison::Greater,
-                    write: !transparent,
-                }),
-        )
-        .build(factory, None);
+        .with_pipeline(pipe_desc)
+        .build_cached(factory, cache);
 
     unsafe {
         factory.destroy_shader_module(shader_vertex);
@@ -451,3 +502,21 @@ fn build_sprite_pipeline<B: Backend>(
         Ok(mut pipes) => Ok((pipes.remove(0), pipeline_layout)),
     }
 }
+
+fn framebuffer_rect(framebuffer_width: u32, framebuffer_height: u32) -> pso::Rect {
+    pso::Rect {
+        x: 0,
+        y: 0,
+        w: framebuffer_width as i16,
+        h: framebuffer_height as i16,
+    }
+}
+
+/// Returns the scissor of a batch, or `None` when the batch is clipped entirely.
+fn scissor_rect(scissor: Option<ScissorRect>, framebuffer: pso::Rect) -> Option<pso::Rect> {
+    match scissor {
+        Some(scissor) if scissor.is_empty() => None,
+        Some(scissor) => Some(scissor.to_rect()),
+        None => Some(framebuffer),
+    }
+}
@@ -0,0 +1,39 @@
+use crate::{
+    bundle::{Target, TargetImage},
+    fog::{Fog, FogMode},
+    pass::DrawPostProcessDesc,
+};
+
+impl DrawPostProcessDesc {
+    /// Create instance of `DrawPostProcess` render group blending surfaces into the fog
+    /// by their distance and height, as configured by [Fog].
+    ///
+    /// Distances are computed from the depth of [Target::Main].
+    pub fn fog() -> Self {
+        Self::new(super::FOG_FRAGMENT.clone())
+            .with_input(TargetImage::Depth(Target::Main))
+            .with_environment()
+            .with_constants(|world| {
+                let fog = Fog::fetch(world);
+                let [r, g, b] = fog.color;
+                let mode = match fog.mode {
+                    FogMode::Linear => 0.0,
+                    FogMode::Exponential => 1.0,
+                    FogMode::ExponentialSquared => 2.0,
+                };
+                vec![
+                    if fog.enabled { 1.0 } else { 0.0 },
+                    mode,
+                    fog.density.max(0.0),
+                    fog.start,
+                    r,
+                    g,
+                    b,
+                    fog.end,
+                    fog.height_falloff.max(0.0),
+                    fog.base_height,
+                    if fog.sky { 1.0 } else { 0.0 },
+                ]
+            })
+    }
+}
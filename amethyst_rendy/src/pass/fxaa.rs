@@ -0,0 +1,19 @@
+use crate::{fxaa::FxaaParams, pass::DrawPostProcessDesc};
+
+impl DrawPostProcessDesc {
+    /// Create instance of `DrawPostProcess` render group smoothing aliased edges
+    /// of its input as configured by [FxaaParams].
+    ///
+    /// The input is expected to be in the displayable range, after tonemapping.
+    pub fn fxaa() -> Self {
+        Self::new(super::FXAA_FRAGMENT.clone()).with_constants(|world| {
+            let params = FxaaParams::fetch(world);
+            vec![
+                if params.enabled { 1.0 } else { 0.0 },
+                params.edge_threshold,
+                params.edge_threshold_min,
+                params.subpixel.max(0.0).min(1.0),
+            ]
+        })
+    }
+}
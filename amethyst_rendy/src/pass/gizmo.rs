@@ -0,0 +1,194 @@
+use super::debug_lines::{build_lines_pipelines, DebugLinesArgs};
+use crate::{
+    debug_drawing::DebugLine,
+    gizmo::Gizmo,
+    pipeline::PipelineCache,
+    pod::ViewArgs,
+    stats::DrawCounter,
+    submodules::{gather::CameraGatherer, DynamicUniform, DynamicVertexBuffer},
+    types::Backend,
+    util,
+};
+use amethyst_core::{
+    ecs::{Read, ReadStorage, SystemData, World},
+    math::Point3,
+    transform::Transform,
+};
+use derivative::Derivative;
+use glsl_layout::*;
+use rendy::{
+    command::{QueueId, RenderPassEncoder},
+    factory::Factory,
+    graph::{
+        render::{PrepareResult, RenderGroup, RenderGroupDesc},
+        GraphContext, NodeBuffer, NodeImage,
+    },
+    hal::{self, device::Device, pso},
+};
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+/// Draw transform gizmo handles for the entity selected in the `Gizmo` resource.
+#[derive(Clone, Debug, PartialEq, Derivative)]
+#[derivative(Default(bound = ""))]
+pub struct DrawGizmoDesc;
+
+impl DrawGizmoDesc {
+    /// Create instance of `DrawGizmo` render group
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl<B: Backend> RenderGroupDesc<B, World> for DrawGizmoDesc {
+    fn build(
+        self,
+        _ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        _queue: QueueId,
+        aux: &World,
+        framebuffer_width: u32,
+        framebuffer_height: u32,
+        subpass: hal::pass::Subpass<'_, B>,
+        _buffers: Vec<NodeBuffer>,
+        _images: Vec<NodeImage>,
+    ) -> Result<Box<dyn RenderGroup<B, World>>, failure::Error> {
+        #[cfg(feature = "profiler")]
+        profile_scope!("build");
+
+        let env = DynamicUniform::new(factory, pso::ShaderStageFlags::VERTEX)?;
+        let args = DynamicUniform::new(factory, pso::ShaderStageFlags::VERTEX)?;
+        let vertex = DynamicVertexBuffer::new();
+
+        let pipeline_cache = aux.try_fetch::<PipelineCache<B>>();
+        // Handles are always drawn on top, so the depth tested variant is not needed.
+        let (depth_tested, pipeline, pipeline_layout) = build_lines_pipelines(
+            factory,
+            pipeline_cache.as_ref().and_then(|cache| cache.raw()),
+            subpass,
+            framebuffer_width,
+            framebuffer_height,
+            vec![env.raw_layout(), args.raw_layout()],
+        )?;
+        unsafe {
+            factory.device().destroy_graphics_pipeline(depth_tested);
+        }
+
+        Ok(Box::new(DrawGizmo::<B> {
+            pipeline,
+            pipeline_layout,
+            env,
+            args,
+            vertex,
+            framebuffer_width: framebuffer_width as f32,
+            framebuffer_height: framebuffer_height as f32,
+            lines: Vec::new(),
+            change: Default::default(),
+        }))
+    }
+}
+
+/// Draws transform gizmo handles
+#[derive(Debug)]
+pub struct DrawGizmo<B: Backend> {
+    pipeline: B::GraphicsPipeline,
+    pipeline_layout: B::PipelineLayout,
+    env: DynamicUniform<B, ViewArgs>,
+    args: DynamicUniform<B, DebugLinesArgs>,
+    vertex: DynamicVertexBuffer<B, DebugLine>,
+    framebuffer_width: f32,
+    framebuffer_height: f32,
+    lines: Vec<DebugLine>,
+    change: util::ChangeDetection,
+}
+
+impl<B: Backend> RenderGroup<B, World> for DrawGizmo<B> {
+    fn prepare(
+        &mut self,
+        factory: &Factory<B>,
+        _queue: QueueId,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        resources: &World,
+    ) -> PrepareResult {
+        #[cfg(feature = "profiler")]
+        profile_scope!("prepare");
+
+        let (gizmo, transforms) =
+            <(Option<Read<'_, Gizmo>>, ReadStorage<'_, Transform>)>::fetch(resources);
+
+        let old_len = self.lines.len();
+        self.lines.clear();
+
+        let target = gizmo
+            .as_ref()
+            .and_then(|g| g.target)
+            .and_then(|e| transforms.get(e));
+        let camera =
+            CameraGatherer::gather_camera_entity(resources).and_then(|e| transforms.get(e));
+
+        if let (Some(gizmo), Some(target), Some(camera)) = (&gizmo, target, camera) {
+            let center = Point3::from(target.global_matrix().column(3).xyz());
+            let eye = Point3::from(camera.global_matrix().column(3).xyz());
+            let length = (center - eye).norm() * gizmo.size;
+            self.lines
+                .extend_from_slice(gizmo.handle_lines(center, length).lines());
+        }
+
+        self.env
+            .write(factory, index, CameraGatherer::gather(resources).projview);
+        self.args.write(
+            factory,
+            index,
+            DebugLinesArgs {
+                screen_space_thickness: [
+                    2.0 / self.framebuffer_width,
+                    2.0 / self.framebuffer_height,
+                ]
+                .into(),
+                pixel_size: [2.0 / self.framebuffer_width, 2.0 / self.framebuffer_height].into(),
+            }
+            .std140(),
+        );
+        self.vertex
+            .write(factory, index, self.lines.len() as u64, Some(&self.lines));
+
+        self.change
+            .prepare_result(index, old_len != self.lines.len())
+    }
+
+    fn draw_inline(
+        &mut self,
+        mut encoder: RenderPassEncoder<'_, B>,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        resources: &World,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("draw");
+
+        if self.lines.is_empty() {
+            return;
+        }
+
+        let layout = &self.pipeline_layout;
+        encoder.bind_graphics_pipeline(&self.pipeline);
+        self.env.bind(index, layout, 0, &mut encoder);
+        self.args.bind(index, layout, 1, &mut encoder);
+        self.vertex.bind(index, 0, 0, &mut encoder);
+        unsafe {
+            encoder.draw(0..4, 0..self.lines.len() as u32);
+        }
+        DrawCounter::fetch(resources).draw_quads(self.lines.len() as u32);
+    }
+
+    fn dispose(self: Box<Self>, factory: &mut Factory<B>, _aux: &World) {
+        unsafe {
+            factory.device().destroy_graphics_pipeline(self.pipeline);
+            factory
+                .device()
+                .destroy_pipeline_layout(self.pipeline_layout);
+        }
+    }
+}
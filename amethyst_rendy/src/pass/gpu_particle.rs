@@ -0,0 +1,629 @@
+use super::tonemap::build_compute_pipeline;
+use crate::{
+    particle::GpuParticles,
+    pipeline::{PipelineCache, PipelineDescBuilder, PipelinesBuilder},
+    pod::{GpuParticleArgs, GpuParticleSimulationArgs, ViewArgs},
+    stats::DrawCounter,
+    submodules::{gather::CameraGatherer, DynamicUniform, TextureId, TextureSub},
+    types::Backend,
+    util,
+};
+use amethyst_core::{
+    ecs::{Read, SystemData, World},
+    timing::Time,
+};
+use derivative::Derivative;
+use glsl_layout::AsStd140;
+use rendy::{
+    command::{
+        CommandBuffer, CommandPool, Compute, ExecutableState, Family, MultiShot, PendingState,
+        QueueId, RenderPassEncoder, SimultaneousUse, Submit,
+    },
+    factory::{BufferState, Factory},
+    frame::Frames,
+    graph::{
+        gfx_acquire_barriers, gfx_release_barriers,
+        render::{PrepareResult, RenderGroup, RenderGroupDesc},
+        BufferAccess, GraphContext, Node, NodeBuffer, NodeDesc, NodeImage, NodeSubmittable,
+    },
+    hal::{self, device::Device, pso},
+    memory::{Data, Dynamic, Write},
+    resource::{
+        Buffer, BufferInfo, DescriptorSet, DescriptorSetLayout, Escape, Handle as RendyHandle,
+    },
+    shader::Shader,
+};
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+/// Size in bytes of a particle in the particle and spawn buffers.
+const PARTICLE_SIZE: u64 = std::mem::size_of::<<GpuParticleArgs as AsStd140>::Std140>() as u64;
+
+/// Size in bytes of the indirect draw command written by `GpuParticleSimulation`.
+pub const GPU_PARTICLE_DRAW_BUFFER_SIZE: u64 = 16;
+
+/// Workgroup size of the particle simulation compute shader.
+const SIMULATION_GROUP_SIZE: u32 = 256;
+
+/// Size in bytes of the particle buffer holding `capacity` particles simulated on the GPU.
+///
+/// The buffer holds two halves of `capacity` particles, one read and the other written by
+/// the simulation every frame.
+pub fn gpu_particle_buffer_size(capacity: u32) -> u64 {
+    2 * u64::from(capacity) * PARTICLE_SIZE
+}
+
+/// Describe simulating the particles of [GpuParticleEmitter](crate::particle::GpuParticleEmitter)s
+/// on the GPU.
+///
+/// The node takes two buffers, the particle buffer of `gpu_particle_buffer_size(capacity)`
+/// bytes and an indirect draw command of `GPU_PARTICLE_DRAW_BUFFER_SIZE` bytes, both read by
+/// [DrawGpuParticles]. Every frame it uploads up to `max_spawns` particles emitted into the
+/// [GpuParticles] resource through a staging buffer, then moves the living particles from one
+/// half of the particle buffer to the other, appending the emitted ones.
+#[derive(Debug)]
+pub struct GpuParticleSimulationDesc {
+    capacity: u32,
+    max_spawns: u32,
+}
+
+impl GpuParticleSimulationDesc {
+    /// Create instance of `GpuParticleSimulation` compute node
+    pub fn new(capacity: u32, max_spawns: u32) -> Self {
+        Self {
+            capacity,
+            max_spawns,
+        }
+    }
+}
+
+impl<B: Backend> NodeDesc<B, World> for GpuParticleSimulationDesc {
+    type Node = GpuParticleSimulation<B>;
+
+    fn buffers(&self) -> Vec<BufferAccess> {
+        vec![
+            BufferAccess {
+                access: hal::buffer::Access::SHADER_READ | hal::buffer::Access::SHADER_WRITE,
+                usage: hal::buffer::Usage::STORAGE,
+                stages: pso::PipelineStage::COMPUTE_SHADER,
+            },
+            BufferAccess {
+                access: hal::buffer::Access::SHADER_WRITE,
+                usage: hal::buffer::Usage::STORAGE | hal::buffer::Usage::TRANSFER_DST,
+                stages: pso::PipelineStage::COMPUTE_SHADER,
+            },
+        ]
+    }
+
+    fn build<'a>(
+        self,
+        ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        family: &mut Family<B>,
+        queue: usize,
+        aux: &World,
+        buffers: Vec<NodeBuffer>,
+        images: Vec<NodeImage>,
+    ) -> Result<Self::Node, failure::Error> {
+        #[cfg(feature = "profiler")]
+        profile_scope!("build");
+
+        let particles = ctx
+            .get_buffer(buffers[0].id)
+            .ok_or_else(|| failure::format_err!("Particle buffer is not available"))?;
+        let draw = ctx
+            .get_buffer(buffers[1].id)
+            .ok_or_else(|| failure::format_err!("Particle draw buffer is not available"))?;
+
+        let compute_state = BufferState {
+            queue: QueueId {
+                index: queue,
+                family: family.id(),
+            },
+            stage: pso::PipelineStage::COMPUTE_SHADER,
+            access: hal::buffer::Access::SHADER_READ | hal::buffer::Access::SHADER_WRITE,
+        };
+        let counts = factory.create_buffer(
+            BufferInfo {
+                size: 8,
+                usage: hal::buffer::Usage::STORAGE | hal::buffer::Usage::TRANSFER_DST,
+            },
+            Data,
+        )?;
+        unsafe {
+            factory.upload_buffer(&counts, 0, &[0u32; 2], None, compute_state)?;
+            factory.upload_buffer(draw, 0, &[4u32, 0, 0, 0], None, compute_state)?;
+        }
+
+        let layout: RendyHandle<DescriptorSetLayout<B>> = set_layout! {
+            factory,
+            [3] StorageBuffer pso::ShaderStageFlags::COMPUTE,
+            [1] UniformBuffer pso::ShaderStageFlags::COMPUTE,
+            [1] StorageBuffer pso::ShaderStageFlags::COMPUTE
+        };
+        let pipeline_layout = unsafe {
+            factory
+                .device()
+                .create_pipeline_layout(Some(layout.raw()), None as Option<(_, _)>)
+        }?;
+        let pipeline_cache = aux.try_fetch::<PipelineCache<B>>();
+        let cache = pipeline_cache.as_ref().and_then(|cache| cache.raw());
+        let simulate_pipeline = build_compute_pipeline(
+            factory,
+            cache,
+            &pipeline_layout,
+            &super::PARTICLE_SIMULATE_COMPUTE,
+        )?;
+        let finalize_pipeline = build_compute_pipeline(
+            factory,
+            cache,
+            &pipeline_layout,
+            &super::PARTICLE_FINALIZE_COMPUTE,
+        )?;
+
+        let mut command_pool = factory
+            .create_command_pool(family)?
+            .with_capability::<Compute>()
+            .expect("Graph builder must provide family with Compute capability");
+
+        // Every thread moves one living particle and appends one emitted particle.
+        let groups = (self.capacity.max(self.max_spawns) + SIMULATION_GROUP_SIZE - 1)
+            / SIMULATION_GROUP_SIZE;
+
+        // Each frame in flight has its own staging buffers and command buffer recorded once.
+        let mut frames = Vec::new();
+        for command_buffer in command_pool.allocate_buffers(ctx.frames_in_flight as usize) {
+            let args = factory.create_buffer(
+                BufferInfo {
+                    size: std::mem::size_of::<<GpuParticleSimulationArgs as AsStd140>::Std140>()
+                        as u64,
+                    usage: hal::buffer::Usage::UNIFORM,
+                },
+                Dynamic,
+            )?;
+            let spawns = factory.create_buffer(
+                BufferInfo {
+                    size: u64::from(self.max_spawns.max(1)) * PARTICLE_SIZE,
+                    usage: hal::buffer::Usage::STORAGE,
+                },
+                Dynamic,
+            )?;
+            let set = factory.create_descriptor_set(layout.clone())?;
+            unsafe {
+                factory.write_descriptor_sets(vec![
+                    util::desc_write(
+                        set.raw(),
+                        0,
+                        pso::Descriptor::Buffer(particles.raw(), None..None),
+                    ),
+                    util::desc_write(
+                        set.raw(),
+                        1,
+                        pso::Descriptor::Buffer(spawns.raw(), None..None),
+                    ),
+                    util::desc_write(
+                        set.raw(),
+                        2,
+                        pso::Descriptor::Buffer(counts.raw(), None..None),
+                    ),
+                    util::desc_write(
+                        set.raw(),
+                        3,
+                        pso::Descriptor::Buffer(args.raw(), None..None),
+                    ),
+                    util::desc_write(
+                        set.raw(),
+                        4,
+                        pso::Descriptor::Buffer(draw.raw(), None..None),
+                    ),
+                ]);
+            }
+
+            let mut recording = command_buffer.begin(MultiShot(SimultaneousUse), ());
+            let mut encoder = recording.encoder();
+            let shader_write =
+                pso::PipelineStage::COMPUTE_SHADER..pso::PipelineStage::COMPUTE_SHADER;
+            let shader_barrier = |buffer| {
+                hal::memory::Barrier::whole_buffer(
+                    buffer,
+                    hal::buffer::Access::SHADER_READ | hal::buffer::Access::SHADER_WRITE
+                        ..hal::buffer::Access::SHADER_READ | hal::buffer::Access::SHADER_WRITE,
+                )
+            };
+            unsafe {
+                let (stages, barriers) = gfx_acquire_barriers(ctx, &buffers, &images);
+                encoder.pipeline_barrier(stages, hal::memory::Dependencies::empty(), barriers);
+                // Particles and counts must be written by the previous frame before moving.
+                encoder.pipeline_barrier(
+                    shader_write.clone(),
+                    hal::memory::Dependencies::empty(),
+                    vec![
+                        shader_barrier(particles.raw()),
+                        shader_barrier(counts.raw()),
+                    ],
+                );
+
+                encoder.bind_compute_pipeline(&simulate_pipeline);
+                encoder.bind_compute_descriptor_sets(
+                    &pipeline_layout,
+                    0,
+                    Some(set.raw()),
+                    std::iter::empty(),
+                );
+                encoder.dispatch(groups, 1, 1);
+                encoder.pipeline_barrier(
+                    shader_write,
+                    hal::memory::Dependencies::empty(),
+                    Some(shader_barrier(counts.raw())),
+                );
+
+                encoder.bind_compute_pipeline(&finalize_pipeline);
+                encoder.dispatch(1, 1, 1);
+
+                let (stages, barriers) = gfx_release_barriers(ctx, &buffers, &images);
+                encoder.pipeline_barrier(stages, hal::memory::Dependencies::empty(), barriers);
+            }
+
+            let (submit, command_buffer) = recording.finish().submit();
+            frames.push(GpuParticleSimulationFrame {
+                args,
+                spawns,
+                set,
+                command_buffer,
+                submit,
+            });
+        }
+
+        Ok(GpuParticleSimulation {
+            capacity: self.capacity,
+            max_spawns: self.max_spawns,
+            parity: 0,
+            pipeline_layout,
+            simulate_pipeline,
+            finalize_pipeline,
+            command_pool,
+            frames,
+            _layout: layout,
+            _counts: counts,
+        })
+    }
+}
+
+#[derive(Debug)]
+struct GpuParticleSimulationFrame<B: Backend> {
+    args: Escape<Buffer<B>>,
+    spawns: Escape<Buffer<B>>,
+    set: Escape<DescriptorSet<B>>,
+    command_buffer:
+        CommandBuffer<B, Compute, PendingState<ExecutableState<MultiShot<SimultaneousUse>>>>,
+    submit: Submit<B, SimultaneousUse>,
+}
+
+/// Simulates particles on the GPU, ping-ponging them between the halves of a buffer.
+#[derive(Debug)]
+pub struct GpuParticleSimulation<B: Backend> {
+    capacity: u32,
+    max_spawns: u32,
+    parity: u32,
+    pipeline_layout: B::PipelineLayout,
+    simulate_pipeline: B::ComputePipeline,
+    finalize_pipeline: B::ComputePipeline,
+    command_pool: CommandPool<B, Compute>,
+    frames: Vec<GpuParticleSimulationFrame<B>>,
+    _layout: RendyHandle<DescriptorSetLayout<B>>,
+    _counts: Escape<Buffer<B>>,
+}
+
+impl<'a, B: Backend> NodeSubmittable<'a, B> for GpuParticleSimulation<B> {
+    type Submittable = &'a Submit<B, SimultaneousUse>;
+    type Submittables = &'a [Submit<B, SimultaneousUse>];
+}
+
+impl<B: Backend> Node<B, World> for GpuParticleSimulation<B> {
+    type Capability = Compute;
+    type Desc = GpuParticleSimulationDesc;
+
+    fn run<'a>(
+        &'a mut self,
+        _ctx: &GraphContext<B>,
+        factory: &Factory<B>,
+        aux: &World,
+        frames: &'a Frames<B>,
+    ) -> &'a [Submit<B, SimultaneousUse>] {
+        #[cfg(feature = "profiler")]
+        profile_scope!("run");
+
+        let particles = <Option<Read<'_, GpuParticles>>>::fetch(aux);
+        let spawns = particles.as_ref().map_or(&[][..], |p| &p.spawns[..]);
+        let spawns = &spawns[..spawns.len().min(self.max_spawns as usize)];
+        let delta_time = <Option<Read<'_, Time>>>::fetch(aux).map_or(0.0, |t| t.delta_seconds());
+        let args = GpuParticleSimulationArgs {
+            delta_time,
+            spawn_count: spawns.len() as u32,
+            parity: self.parity,
+            capacity: self.capacity,
+        }
+        .std140();
+        self.parity ^= 1;
+
+        // The graph waits for the frame that last used this slot before running.
+        let index = frames.next().index() as usize % self.frames.len();
+        let frame = &mut self.frames[index];
+        let range = 0..frame.args.size();
+        let mut mapped = frame.args.map(factory.device(), range.clone()).unwrap();
+        unsafe {
+            let mut writer = mapped.write::<u8>(factory.device(), range).unwrap();
+            writer
+                .slice()
+                .copy_from_slice(util::slice_as_bytes(&[args]));
+        }
+        if !spawns.is_empty() {
+            let spawns = spawns.iter().map(|s| s.std140()).collect::<Vec<_>>();
+            let bytes = util::slice_as_bytes(&spawns);
+            let range = 0..bytes.len() as u64;
+            let mut mapped = frame.spawns.map(factory.device(), range.clone()).unwrap();
+            unsafe {
+                let mut writer = mapped.write::<u8>(factory.device(), range).unwrap();
+                writer.slice().copy_from_slice(bytes);
+            }
+        }
+
+        std::slice::from_ref(&self.frames[index].submit)
+    }
+
+    unsafe fn dispose(mut self, factory: &mut Factory<B>, _aux: &World) {
+        for frame in self.frames.drain(..) {
+            self.command_pool
+                .free_buffers(Some(frame.command_buffer.mark_complete()));
+        }
+        factory.destroy_command_pool(self.command_pool);
+        factory
+            .device()
+            .destroy_compute_pipeline(self.simulate_pipeline);
+        factory
+            .device()
+            .destroy_compute_pipeline(self.finalize_pipeline);
+        factory
+            .device()
+            .destroy_pipeline_layout(self.pipeline_layout);
+    }
+}
+
+/// Draw the particles simulated by [GpuParticleSimulation] as camera facing quads.
+///
+/// The group takes the particle and indirect draw command buffers of the simulation node.
+/// Particles are depth tested, but not sorted, and blended with premultiplied colors.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DrawGpuParticlesDesc;
+
+impl DrawGpuParticlesDesc {
+    /// Create instance of `DrawGpuParticles` render group
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl<B: Backend> RenderGroupDesc<B, World> for DrawGpuParticlesDesc {
+    fn buffers(&self) -> Vec<BufferAccess> {
+        vec![
+            BufferAccess {
+                access: hal::buffer::Access::SHADER_READ,
+                usage: hal::buffer::Usage::STORAGE,
+                stages: pso::PipelineStage::VERTEX_SHADER,
+            },
+            BufferAccess {
+                access: hal::buffer::Access::INDIRECT_COMMAND_READ,
+                usage: hal::buffer::Usage::INDIRECT,
+                stages: pso::PipelineStage::DRAW_INDIRECT,
+            },
+        ]
+    }
+
+    fn build(
+        self,
+        ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        _queue: QueueId,
+        world: &World,
+        framebuffer_width: u32,
+        framebuffer_height: u32,
+        subpass: hal::pass::Subpass<'_, B>,
+        buffers: Vec<NodeBuffer>,
+        _images: Vec<NodeImage>,
+    ) -> Result<Box<dyn RenderGroup<B, World>>, failure::Error> {
+        #[cfg(feature = "profiler")]
+        profile_scope!("build");
+
+        let particles = ctx
+            .get_buffer(buffers[0].id)
+            .ok_or_else(|| failure::format_err!("Particle buffer is not available"))?;
+        let draw = ctx
+            .get_buffer(buffers[1].id)
+            .ok_or_else(|| failure::format_err!("Particle draw buffer is not available"))?
+            .clone();
+
+        let env = DynamicUniform::new(factory, pso::ShaderStageFlags::VERTEX)?;
+        let textures = TextureSub::new(factory)?;
+
+        let particles_layout: RendyHandle<DescriptorSetLayout<B>> = set_layout! {
+            factory,
+            [1] StorageBuffer pso::ShaderStageFlags::VERTEX
+        };
+        let particles_set = factory.create_descriptor_set(particles_layout.clone())?;
+        unsafe {
+            factory.write_descriptor_sets(Some(util::desc_write(
+                particles_set.raw(),
+                0,
+                pso::Descriptor::Buffer(particles.raw(), None..None),
+            )));
+        }
+
+        let pipeline_cache = world.try_fetch::<PipelineCache<B>>();
+        let (pipeline, pipeline_layout) = build_gpu_particle_pipeline(
+            factory,
+            pipeline_cache.as_ref().and_then(|cache| cache.raw()),
+            subpass,
+            framebuffer_width,
+            framebuffer_height,
+            vec![
+                env.raw_layout(),
+                textures.raw_layout(),
+                particles_layout.raw(),
+            ],
+        )?;
+
+        Ok(Box::new(DrawGpuParticles::<B> {
+            pipeline,
+            pipeline_layout,
+            env,
+            textures,
+            texture: None,
+            particles_set,
+            draw,
+        }))
+    }
+}
+
+/// Draws the particles of `GpuParticleSimulation` with an indirect draw.
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""))]
+pub struct DrawGpuParticles<B: Backend> {
+    pipeline: B::GraphicsPipeline,
+    pipeline_layout: B::PipelineLayout,
+    env: DynamicUniform<B, ViewArgs>,
+    textures: TextureSub<B>,
+    texture: Option<TextureId>,
+    particles_set: Escape<DescriptorSet<B>>,
+    draw: RendyHandle<Buffer<B>>,
+}
+
+impl<B: Backend> RenderGroup<B, World> for DrawGpuParticles<B> {
+    fn prepare(
+        &mut self,
+        factory: &Factory<B>,
+        _queue: QueueId,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        world: &World,
+    ) -> PrepareResult {
+        #[cfg(feature = "profiler")]
+        profile_scope!("prepare");
+
+        let camera = CameraGatherer::gather(world);
+        self.env.write(factory, index, camera.projview);
+
+        let particles = <Option<Read<'_, GpuParticles>>>::fetch(world);
+        self.texture = particles
+            .as_ref()
+            .and_then(|particles| particles.texture.as_ref())
+            .and_then(|texture| {
+                self.textures.insert(
+                    factory,
+                    world,
+                    texture,
+                    hal::image::Layout::ShaderReadOnlyOptimal,
+                )
+            })
+            .map(|(tex_id, _)| tex_id);
+        self.textures.maintain(factory, world);
+
+        PrepareResult::DrawRecord
+    }
+
+    fn draw_inline(
+        &mut self,
+        mut encoder: RenderPassEncoder<'_, B>,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        world: &World,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("draw");
+
+        let tex = match self.texture {
+            Some(tex) if self.textures.loaded(tex) => tex,
+            _ => return,
+        };
+
+        let layout = &self.pipeline_layout;
+        encoder.bind_graphics_pipeline(&self.pipeline);
+        self.env.bind(index, layout, 0, &mut encoder);
+        self.textures.bind(layout, 1, tex, &mut encoder);
+        unsafe {
+            encoder.bind_graphics_descriptor_sets(
+                layout,
+                2,
+                Some(self.particles_set.raw()),
+                std::iter::empty(),
+            );
+            encoder.draw_indirect(self.draw.raw(), 0, 1, GPU_PARTICLE_DRAW_BUFFER_SIZE as u32);
+        }
+        // The number of particles drawn is only known on the GPU.
+        DrawCounter::fetch(world).draw_indirect();
+    }
+
+    fn dispose(self: Box<Self>, factory: &mut Factory<B>, _world: &World) {
+        unsafe {
+            factory.device().destroy_graphics_pipeline(self.pipeline);
+            factory
+                .device()
+                .destroy_pipeline_layout(self.pipeline_layout);
+        }
+    }
+}
+
+fn build_gpu_particle_pipeline<B: Backend>(
+    factory: &Factory<B>,
+    cache: Option<&B::PipelineCache>,
+    subpass: hal::pass::Subpass<'_, B>,
+    framebuffer_width: u32,
+    framebuffer_height: u32,
+    layouts: Vec<&B::DescriptorSetLayout>,
+) -> Result<(B::GraphicsPipeline, B::PipelineLayout), failure::Error> {
+    let pipeline_layout = unsafe {
+        factory
+            .device()
+            .create_pipeline_layout(layouts, None as Option<(_, _)>)
+    }?;
+
+    let shader_vertex = unsafe { super::GPU_PARTICLE_VERTEX.module(factory).unwrap() };
+    let shader_fragment = unsafe { super::PARTICLE_FRAGMENT.module(factory).unwrap() };
+
+    let pipes = PipelinesBuilder::new()
+        .with_pipeline(
+            PipelineDescBuilder::new()
+                .with_input_assembler(pso::InputAssemblerDesc::new(hal::Primitive::TriangleStrip))
+                .with_shaders(util::simple_shader_set(
+                    &shader_vertex,
+                    Some(&shader_fragment),
+                ))
+                .with_layout(&pipeline_layout)
+                .with_subpass(subpass)
+                .with_framebuffer_size(framebuffer_width, framebuffer_height)
+                .with_blend_targets(vec![pso::ColorBlendDesc {
+                    mask: pso::ColorMask::ALL,
+                    blend: Some(pso::BlendState::PREMULTIPLIED_ALPHA),
+                }])
+                .with_depth_test(pso::DepthTest {
+                    fun: pso::Comparison::Greater,
+                    write: false,
+                }),
+        )
+        .build_cached(factory, cache);
+
+    unsafe {
+        factory.destroy_shader_module(shader_vertex);
+        factory.destroy_shader_module(shader_fragment);
+    }
+
+    match pipes {
+        Err(e) => {
+            unsafe {
+                factory.device().destroy_pipeline_layout(pipeline_layout);
+            }
+            Err(e)
+        }
+        Ok(mut pipes) => Ok((pipes.remove(0), pipeline_layout)),
+    }
+}
@@ -0,0 +1,250 @@
+use crate::{
+    palette::Srgba,
+    pipeline::{PipelineCache, PipelineDescBuilder, PipelinesBuilder},
+    pod::{IntoPod, ViewArgs},
+    stats::DrawCounter,
+    submodules::{gather::CameraGatherer, DynamicUniform},
+    types::Backend,
+    util,
+};
+use amethyst_core::ecs::{Read, SystemData, World};
+use derivative::Derivative;
+use glsl_layout::{float, vec4, AsStd140};
+use rendy::{
+    command::{QueueId, RenderPassEncoder},
+    factory::Factory,
+    graph::{
+        render::{PrepareResult, RenderGroup, RenderGroupDesc},
+        GraphContext, NodeBuffer, NodeImage,
+    },
+    hal::{self, device::Device, pso},
+    shader::Shader,
+};
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+/// Parameters of the reference grid drawn by `DrawGrid`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GridParams {
+    /// Distance between minor grid lines in world units.
+    pub spacing: f32,
+    /// Number of minor cells between major grid lines.
+    pub major_line_every: u32,
+    /// Color of minor grid lines.
+    pub minor_color: Srgba,
+    /// Color of major grid lines.
+    pub major_color: Srgba,
+    /// Distance from the camera at which the grid fades out completely.
+    pub fade_distance: f32,
+}
+
+impl Default for GridParams {
+    fn default() -> Self {
+        Self {
+            spacing: 1.0,
+            major_line_every: 10,
+            minor_color: Srgba::new(0.5, 0.5, 0.5, 0.4),
+            major_color: Srgba::new(0.8, 0.8, 0.8, 0.8),
+            fade_distance: 100.0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, AsStd140)]
+pub(crate) struct GridArgs {
+    minor_color: vec4,
+    major_color: vec4,
+    spacing: float,
+    major_spacing: float,
+    fade_distance: float,
+}
+
+impl GridParams {
+    pub(crate) fn uniform(&self) -> <GridArgs as AsStd140>::Std140 {
+        let spacing = self.spacing.max(std::f32::EPSILON);
+        GridArgs {
+            minor_color: self.minor_color.into_pod(),
+            major_color: self.major_color.into_pod(),
+            spacing,
+            major_spacing: spacing * self.major_line_every.max(1) as f32,
+            fade_distance: self.fade_distance,
+        }
+        .std140()
+    }
+}
+
+/// Describe drawing an endless reference grid on the XZ plane
+#[derive(Clone, Debug, PartialEq, Derivative)]
+#[derivative(Default(bound = ""))]
+pub struct DrawGridDesc;
+
+impl DrawGridDesc {
+    /// Create instance of `DrawGrid` render group
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl<B: Backend> RenderGroupDesc<B, World> for DrawGridDesc {
+    fn build(
+        self,
+        _ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        _queue: QueueId,
+        resources: &World,
+        framebuffer_width: u32,
+        framebuffer_height: u32,
+        subpass: hal::pass::Subpass<'_, B>,
+        _buffers: Vec<NodeBuffer>,
+        _images: Vec<NodeImage>,
+    ) -> Result<Box<dyn RenderGroup<B, World>>, failure::Error> {
+        #[cfg(feature = "profiler")]
+        profile_scope!("build");
+
+        let env = DynamicUniform::new(
+            factory,
+            pso::ShaderStageFlags::VERTEX | pso::ShaderStageFlags::FRAGMENT,
+        )?;
+        let args = DynamicUniform::new(factory, pso::ShaderStageFlags::FRAGMENT)?;
+
+        let pipeline_cache = resources.try_fetch::<PipelineCache<B>>();
+
+        let (pipeline, pipeline_layout) = build_grid_pipeline(
+            factory,
+            pipeline_cache.as_ref().and_then(|cache| cache.raw()),
+            subpass,
+            framebuffer_width,
+            framebuffer_height,
+            vec![env.raw_layout(), args.raw_layout()],
+        )?;
+
+        Ok(Box::new(DrawGrid::<B> {
+            pipeline,
+            pipeline_layout,
+            env,
+            args,
+        }))
+    }
+}
+
+/// Draws an endless reference grid on the XZ plane.
+/// Lines are computed per pixel, so the cost doesn't depend on the number of visible lines.
+#[derive(Debug)]
+pub struct DrawGrid<B: Backend> {
+    pipeline: B::GraphicsPipeline,
+    pipeline_layout: B::PipelineLayout,
+    env: DynamicUniform<B, ViewArgs>,
+    args: DynamicUniform<B, GridArgs>,
+}
+
+impl<B: Backend> RenderGroup<B, World> for DrawGrid<B> {
+    fn prepare(
+        &mut self,
+        factory: &Factory<B>,
+        _queue: QueueId,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        resources: &World,
+    ) -> PrepareResult {
+        #[cfg(feature = "profiler")]
+        profile_scope!("prepare");
+
+        let params = <Option<Read<'_, GridParams>>>::fetch(resources)
+            .map(|p| p.uniform())
+            .unwrap_or_else(|| GridParams::default().uniform());
+
+        self.env
+            .write(factory, index, CameraGatherer::gather(resources).projview);
+        let changed = self.args.write(factory, index, params);
+
+        if changed {
+            PrepareResult::DrawRecord
+        } else {
+            PrepareResult::DrawReuse
+        }
+    }
+
+    fn draw_inline(
+        &mut self,
+        mut encoder: RenderPassEncoder<'_, B>,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        resources: &World,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("draw");
+        encoder.bind_graphics_pipeline(&self.pipeline);
+        self.env.bind(index, &self.pipeline_layout, 0, &mut encoder);
+        self.args
+            .bind(index, &self.pipeline_layout, 1, &mut encoder);
+        unsafe {
+            encoder.draw(0..4, 0..1);
+        }
+        DrawCounter::fetch(resources).draw_quads(1);
+    }
+
+    fn dispose(self: Box<Self>, factory: &mut Factory<B>, _aux: &World) {
+        unsafe {
+            factory.device().destroy_graphics_pipeline(self.pipeline);
+            factory
+                .device()
+                .destroy_pipeline_layout(self.pipeline_layout);
+        }
+    }
+}
+
+fn build_grid_pipeline<B: Backend>(
+    factory: &Factory<B>,
+    cache: Option<&B::PipelineCache>,
+    subpass: hal::pass::Subpass<'_, B>,
+    framebuffer_width: u32,
+    framebuffer_height: u32,
+    layouts: Vec<&B::DescriptorSetLayout>,
+) -> Result<(B::GraphicsPipeline, B::PipelineLayout), failure::Error> {
+    let pipeline_layout = unsafe {
+        factory
+            .device()
+            .create_pipeline_layout(layouts, None as Option<(_, _)>)
+    }?;
+
+    let shader_vertex = unsafe { super::GRID_VERTEX.module(factory).unwrap() };
+    let shader_fragment = unsafe { super::GRID_FRAGMENT.module(factory).unwrap() };
+
+    let pipes = PipelinesBuilder::new()
+        .with_pipeline(
+            PipelineDescBuilder::new()
+                .with_input_assembler(pso::InputAssemblerDesc::new(hal::Primitive::TriangleStrip))
+                .with_shaders(util::simple_shader_set(
+                    &shader_vertex,
+                    Some(&shader_fragment),
+                ))
+                .with_layout(&pipeline_layout)
+                .with_subpass(subpass)
+                .with_framebuffer_size(framebuffer_width, framebuffer_height)
+                .with_depth_test(pso::DepthTest {
+                    fun: pso::Comparison::GreaterEqual,
+                    write: false,
+                })
+                .with_blend_targets(vec![pso::ColorBlendDesc {
+                    mask: pso::ColorMask::ALL,
+                    blend: Some(pso::BlendState::ALPHA),
+                }]),
+        )
+        .build_cached(factory, cache);
+
+    unsafe {
+        factory.destroy_shader_module(shader_vertex);
+        factory.destroy_shader_module(shader_fragment);
+    }
+
+    match pipes {
+        Err(e) => {
+            unsafe {
+                factory.device().destroy_pipeline_layout(pipeline_layout);
+            }
+            Err(e)
+        }
+        Ok(mut pipes) => Ok((pipes.remove(0), pipeline_layout)),
+    }
+}
@@ -0,0 +1,544 @@
+use crate::{
+    batch::{GroupIterator, OneLevelBatch, OrderedOneLevelBatch},
+    mtl::MaterialDefaults,
+    pipeline::{PipelineCache, PipelineDescBuilder, PipelinesBuilder},
+    pod::SpriteArgs,
+    resources::Tint,
+    sprite::{SpriteNormalMap, SpriteRender, SpriteSheet},
+    sprite_visibility::SpriteVisibility,
+    stats::DrawCounter,
+    submodules::{DynamicVertexBuffer, EnvironmentSub, TextureId, TextureSub},
+    types::{Backend, Texture},
+    util,
+};
+use amethyst_assets::{AssetStorage, Handle};
+use amethyst_core::{
+    ecs::{Join, Read, ReadExpect, ReadStorage, SystemData, World},
+    transform::Transform,
+};
+use derivative::Derivative;
+use rendy::{
+    command::{QueueId, RenderPassEncoder},
+    factory::Factory,
+    graph::{
+        render::{PrepareResult, RenderGroup, RenderGroupDesc},
+        GraphContext, NodeBuffer, NodeImage,
+    },
+    hal::{self, device::Device, pso},
+    mesh::AsVertex,
+    shader::Shader,
+};
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+/// Albedo and normal map textures of a batch of lit sprites.
+type LitSpriteTextures = (TextureId, TextureId);
+
+/// Draw opaque sprites lit by point and directional lights.
+///
+/// Sprites may carry a `SpriteNormalMap` to give them surface detail. Lights are positioned in
+/// 3D, so place point lights in front of the sprites: the closer a light is to the sprite plane,
+/// the more grazing its light. Point light `intensity` is a plain multiplier which falls off to
+/// zero at its `radius`.
+#[derive(Clone, Debug, PartialEq, Derivative)]
+#[derivative(Default(bound = ""))]
+pub struct DrawLit2DDesc;
+
+impl DrawLit2DDesc {
+    /// Create instance of `DrawLit2D` render group
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl<B: Backend> RenderGroupDesc<B, World> for DrawLit2DDesc {
+    fn build(
+        self,
+        _ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        _queue: QueueId,
+        world: &World,
+        framebuffer_width: u32,
+        framebuffer_height: u32,
+        subpass: hal::pass::Subpass<'_, B>,
+        _buffers: Vec<NodeBuffer>,
+        _images: Vec<NodeImage>,
+    ) -> Result<Box<dyn RenderGroup<B, World>>, failure::Error> {
+        #[cfg(feature = "profiler")]
+        profile_scope!("build");
+
+        let env = EnvironmentSub::new(
+            factory,
+            [
+                pso::ShaderStageFlags::VERTEX | pso::ShaderStageFlags::FRAGMENT,
+                pso::ShaderStageFlags::FRAGMENT,
+            ],
+        )?;
+        let textures = TextureSub::new(factory)?;
+        let vertex = DynamicVertexBuffer::new();
+
+        let pipeline_cache = world.try_fetch::<PipelineCache<B>>();
+
+        let (pipeline, pipeline_layout) = build_lit_sprite_pipeline(
+            factory,
+            pipeline_cache.as_ref().and_then(|cache| cache.raw()),
+            subpass,
+            framebuffer_width,
+            framebuffer_height,
+            false,
+            vec![
+                env.raw_layout(),
+                textures.raw_layout(),
+                textures.raw_layout(),
+            ],
+        )?;
+
+        Ok(Box::new(DrawLit2D::<B> {
+            pipeline,
+            pipeline_layout,
+            env,
+            textures,
+            vertex,
+            sprites: Default::default(),
+        }))
+    }
+}
+
+/// Draws opaque 2D sprites lit by point and directional lights.
+#[derive(Debug)]
+pub struct DrawLit2D<B: Backend> {
+    pipeline: B::GraphicsPipeline,
+    pipeline_layout: B::PipelineLayout,
+    env: EnvironmentSub<B>,
+    textures: TextureSub<B>,
+    vertex: DynamicVertexBuffer<B, SpriteArgs>,
+    sprites: OneLevelBatch<LitSpriteTextures, SpriteArgs>,
+}
+
+impl<B: Backend> RenderGroup<B, World> for DrawLit2D<B> {
+    fn prepare(
+        &mut self,
+        factory: &Factory<B>,
+        _queue: QueueId,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        world: &World,
+    ) -> PrepareResult {
+        #[cfg(feature = "profiler")]
+        profile_scope!("prepare opaque");
+
+        let (
+            sprite_sheet_storage,
+            tex_storage,
+            material_defaults,
+            visibility,
+            sprite_renders,
+            normal_maps,
+            transforms,
+            tints,
+        ) = <(
+            Read<'_, AssetStorage<SpriteSheet>>,
+            Read<'_, AssetStorage<Texture>>,
+            ReadExpect<'_, MaterialDefaults>,
+            ReadExpect<'_, SpriteVisibility>,
+            ReadStorage<'_, SpriteRender>,
+            ReadStorage<'_, SpriteNormalMap>,
+            ReadStorage<'_, Transform>,
+            ReadStorage<'_, Tint>,
+        )>::fetch(world);
+
+        self.env.process(factory, index, world);
+
+        let sprites_ref = &mut self.sprites;
+        let textures_ref = &mut self.textures;
+
+        sprites_ref.clear_inner();
+
+        {
+            #[cfg(feature = "profiler")]
+            profile_scope!("gather_visibility");
+
+            (
+                &sprite_renders,
+                &transforms,
+                tints.maybe(),
+                normal_maps.maybe(),
+                &visibility.visible_unordered,
+            )
+                .join()
+                .filter_map(|(sprite_render, global, tint, normal_map, _)| {
+                    let (batch_data, texture) = SpriteArgs::from_data(
+                        &tex_storage,
+                        &sprite_sheet_storage,
+                        sprite_render,
+                        global,
+                        tint,
+                    )?;
+                    let normal = lit_sprite_normal(&tex_storage, &material_defaults, normal_map);
+                    let (tex_id, _) = textures_ref.insert(
+                        factory,
+                        world,
+                        texture,
+                        hal::image::Layout::ShaderReadOnlyOptimal,
+                    )?;
+                    let (normal_id, _) = textures_ref.insert(
+                        factory,
+                        world,
+                        normal,
+                        hal::image::Layout::ShaderReadOnlyOptimal,
+                    )?;
+                    Some(((tex_id, normal_id), batch_data))
+                })
+                .for_each_group(|textures, batch_data| {
+                    sprites_ref.insert(textures, batch_data.drain(..))
+                });
+        }
+
+        self.textures.maintain(factory, world);
+
+        {
+            #[cfg(feature = "profiler")]
+            profile_scope!("write");
+
+            sprites_ref.prune();
+            self.vertex.write(
+                factory,
+                index,
+                self.sprites.count() as u64,
+                self.sprites.data(),
+            );
+        }
+
+        PrepareResult::DrawRecord
+    }
+
+    fn draw_inline(
+        &mut self,
+        mut encoder: RenderPassEncoder<'_, B>,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        world: &World,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("draw opaque");
+
+        let layout = &self.pipeline_layout;
+        encoder.bind_graphics_pipeline(&self.pipeline);
+        self.env.bind(index, layout, 0, &mut encoder);
+        self.vertex.bind(index, 0, 0, &mut encoder);
+        let mut draws = DrawCounter::fetch(world);
+        for (&(tex, normal), range) in self.sprites.iter() {
+            if self.textures.loaded(tex) && self.textures.loaded(normal) {
+                self.textures.bind(layout, 1, tex, &mut encoder);
+                self.textures.bind(layout, 2, normal, &mut encoder);
+                let instances = range.end - range.start;
+                unsafe {
+                    encoder.draw(0..4, range);
+                }
+                draws.draw_quads(instances);
+            }
+        }
+    }
+
+    fn dispose(self: Box<Self>, factory: &mut Factory<B>, _world: &World) {
+        unsafe {
+            factory.device().destroy_graphics_pipeline(self.pipeline);
+            factory
+                .device()
+                .destroy_pipeline_layout(self.pipeline_layout);
+        }
+    }
+}
+
+/// Describes drawing transparent sprites lit by point and directional lights.
+///
+/// See `DrawLit2DDesc` for how lights affect sprites. Sprites with a `SpriteOrder` are drawn by
+/// this pass too, in the order of their layers.
+#[derive(Clone, Debug, PartialEq, Derivative)]
+#[derivative(Default(bound = ""))]
+pub struct DrawLit2DTransparentDesc;
+
+impl DrawLit2DTransparentDesc {
+    /// Create instance of `DrawLit2DTransparent` render group
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl<B: Backend> RenderGroupDesc<B, World> for DrawLit2DTransparentDesc {
+    fn build(
+        self,
+        _ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        _queue: QueueId,
+        world: &World,
+        framebuffer_width: u32,
+        framebuffer_height: u32,
+        subpass: hal::pass::Subpass<'_, B>,
+        _buffers: Vec<NodeBuffer>,
+        _images: Vec<NodeImage>,
+    ) -> Result<Box<dyn RenderGroup<B, World>>, failure::Error> {
+        #[cfg(feature = "profiler")]
+        profile_scope!("build_trans");
+
+        let env = EnvironmentSub::new(
+            factory,
+            [
+                pso::ShaderStageFlags::VERTEX | pso::ShaderStageFlags::FRAGMENT,
+                pso::ShaderStageFlags::FRAGMENT,
+            ],
+        )?;
+        let textures = TextureSub::new(factory)?;
+        let vertex = DynamicVertexBuffer::new();
+
+        let pipeline_cache = world.try_fetch::<PipelineCache<B>>();
+
+        let (pipeline, pipeline_layout) = build_lit_sprite_pipeline(
+            factory,
+            pipeline_cache.as_ref().and_then(|cache| cache.raw()),
+            subpass,
+            framebuffer_width,
+            framebuffer_height,
+            true,
+            vec![
+                env.raw_layout(),
+                textures.raw_layout(),
+                textures.raw_layout(),
+            ],
+        )?;
+
+        Ok(Box::new(DrawLit2DTransparent::<B> {
+            pipeline,
+            pipeline_layout,
+            env,
+            textures,
+            vertex,
+            sprites: Default::default(),
+            change: Default::default(),
+        }))
+    }
+}
+
+/// Draws transparent sprites lit by point and directional lights.
+#[derive(Debug)]
+pub struct DrawLit2DTransparent<B: Backend> {
+    pipeline: B::GraphicsPipeline,
+    pipeline_layout: B::PipelineLayout,
+    env: EnvironmentSub<B>,
+    textures: TextureSub<B>,
+    vertex: DynamicVertexBuffer<B, SpriteArgs>,
+    sprites: OrderedOneLevelBatch<LitSpriteTextures, SpriteArgs>,
+    change: util::ChangeDetection,
+}
+
+impl<B: Backend> RenderGroup<B, World> for DrawLit2DTransparent<B> {
+    fn prepare(
+        &mut self,
+        factory: &Factory<B>,
+        _queue: QueueId,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        world: &World,
+    ) -> PrepareResult {
+        #[cfg(feature = "profiler")]
+        profile_scope!("prepare transparent");
+
+        let (
+            sprite_sheet_storage,
+            tex_storage,
+            material_defaults,
+            visibility,
+            sprite_renders,
+            normal_maps,
+            transforms,
+            tints,
+        ) = <(
+            Read<'_, AssetStorage<SpriteSheet>>,
+            Read<'_, AssetStorage<Texture>>,
+            ReadExpect<'_, MaterialDefaults>,
+            ReadExpect<'_, SpriteVisibility>,
+            ReadStorage<'_, SpriteRender>,
+            ReadStorage<'_, SpriteNormalMap>,
+            ReadStorage<'_, Transform>,
+            ReadStorage<'_, Tint>,
+        )>::fetch(world);
+
+        self.env.process(factory, index, world);
+        self.sprites.swap_clear();
+        let mut changed = false;
+
+        let sprites_ref = &mut self.sprites;
+        let textures_ref = &mut self.textures;
+
+        {
+            #[cfg(feature = "profiler")]
+            profile_scope!("gather_sprites_trans");
+
+            let mut joined = (
+                &sprite_renders,
+                &transforms,
+                tints.maybe(),
+                normal_maps.maybe(),
+            )
+                .join();
+            visibility
+                .visible_ordered
+                .iter()
+                .filter_map(|e| joined.get_unchecked(e.id()))
+                .filter_map(|(sprite_render, global, tint, normal_map)| {
+                    let (batch_data, texture) = SpriteArgs::from_data(
+                        &tex_storage,
+                        &sprite_sheet_storage,
+                        sprite_render,
+                        global,
+                        tint,
+                    )?;
+                    let normal = lit_sprite_normal(&tex_storage, &material_defaults, normal_map);
+                    let (tex_id, tex_changed) = textures_ref.insert(
+                        factory,
+                        world,
+                        texture,
+                        hal::image::Layout::ShaderReadOnlyOptimal,
+                    )?;
+                    let (normal_id, normal_changed) = textures_ref.insert(
+                        factory,
+                        world,
+                        normal,
+                        hal::image::Layout::ShaderReadOnlyOptimal,
+                    )?;
+                    changed = changed || tex_changed || normal_changed;
+                    Some(((tex_id, normal_id), batch_data))
+                })
+                .for_each_group(|textures, batch_data| {
+                    sprites_ref.insert(textures, batch_data.drain(..));
+                });
+        }
+        self.textures.maintain(factory, world);
+        changed = changed || self.sprites.changed();
+
+        {
+            #[cfg(feature = "profiler")]
+            profile_scope!("write");
+
+            self.vertex.write(
+                factory,
+                index,
+                self.sprites.count() as u64,
+                Some(self.sprites.data()),
+            );
+        }
+
+        self.change.prepare_result(index, changed)
+    }
+
+    fn draw_inline(
+        &mut self,
+        mut encoder: RenderPassEncoder<'_, B>,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        world: &World,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("draw transparent");
+
+        let layout = &self.pipeline_layout;
+        encoder.bind_graphics_pipeline(&self.pipeline);
+        self.env.bind(index, layout, 0, &mut encoder);
+        self.vertex.bind(index, 0, 0, &mut encoder);
+        let mut draws = DrawCounter::fetch(world);
+        for (&(tex, normal), range) in self.sprites.iter() {
+            if self.textures.loaded(tex) && self.textures.loaded(normal) {
+                self.textures.bind(layout, 1, tex, &mut encoder);
+                self.textures.bind(layout, 2, normal, &mut encoder);
+                let instances = range.end - range.start;
+                unsafe {
+                    encoder.draw(0..4, range);
+                }
+                draws.draw_quads(instances);
+            }
+        }
+    }
+
+    fn dispose(self: Box<Self>, factory: &mut Factory<B>, _aux: &World) {
+        unsafe {
+            factory.device().destroy_graphics_pipeline(self.pipeline);
+            factory
+                .device()
+                .destroy_pipeline_layout(self.pipeline_layout);
+        }
+    }
+}
+
+/// Selects the normal map of a sprite, falling back to the flat default normal while it loads.
+fn lit_sprite_normal<'a>(
+    tex_storage: &AssetStorage<Texture>,
+    material_defaults: &'a MaterialDefaults,
+    normal_map: Option<&'a SpriteNormalMap>,
+) -> &'a Handle<Texture> {
+    normal_map
+        .map(|normal_map| &normal_map.0)
+        .filter(|normal| tex_storage.contains(normal))
+        .unwrap_or(&material_defaults.0.normal)
+}
+
+fn build_lit_sprite_pipeline<B: Backend>(
+    factory: &Factory<B>,
+    cache: Option<&B::PipelineCache>,
+    subpass: hal::pass::Subpass<'_, B>,
+    framebuffer_width: u32,
+    framebuffer_height: u32,
+    transparent: bool,
+    layouts: Vec<&B::DescriptorSetLayout>,
+) -> Result<(B::GraphicsPipeline, B::PipelineLayout), failure::Error> {
+    let pipeline_layout = unsafe {
+        factory
+            .device()
+            .create_pipeline_layout(layouts, None as Option<(_, _)>)
+    }?;
+
+    let shader_vertex = unsafe { super::SPRITE_LIT_VERTEX.module(factory).unwrap() };
+    let shader_fragment = unsafe { super::SPRITE_LIT_FRAGMENT.module(factory).unwrap() };
+
+    let pipes = PipelinesBuilder::new()
+        .with_pipeline(
+            PipelineDescBuilder::new()
+                .with_vertex_desc(&[(SpriteArgs::vertex(), pso::VertexInputRate::Instance(1))])
+                .with_input_assembler(pso::InputAssemblerDesc::new(hal::Primitive::TriangleStrip))
+                .with_shaders(util::simple_shader_set(
+                    &shader_vertex,
+                    Some(&shader_fragment),
+                ))
+                .with_layout(&pipeline_layout)
+                .with_subpass(subpass)
+                .with_framebuffer_size(framebuffer_width, framebuffer_height)
+                .with_blend_targets(vec![pso::ColorBlendDesc {
+                    mask: pso::ColorMask::ALL,
+                    blend: if transparent {
+                        Some(pso::BlendState::PREMULTIPLIED_ALPHA)
+                    } else {
+                        None
+                    },
+                }])
+                .with_depth_test(pso::DepthTest {
+                    fun: pso::Comparison::Greater,
+                    write: !transparent,
+                }),
+        )
+        .build_cached(factory, cache);
+
+    unsafe {
+        factory.destroy_shader_module(shader_vertex);
+        factory.destroy_shader_module(shader_fragment);
+    }
+
+    match pipes {
+        Err(e) => {
+            unsafe {
+                factory.device().destroy_pipeline_layout(pipeline_layout);
+            }
+            Err(e)
+        }
+        Ok(mut pipes) => Ok((pipes.remove(0), pipeline_layout)),
+    }
+}
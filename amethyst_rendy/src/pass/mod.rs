@@ -1,105 +1,487 @@
 //! Passes and shaders implemented by amethyst
 
 mod base_3d;
+mod billboard;
+mod bloom;
+mod chromatic_aberration;
+mod color_grading;
+mod compute;
+mod custom;
 mod debug_lines;
+mod decal;
+mod deferred;
+mod dof;
 mod flat;
 mod flat2d;
+mod fog;
+mod fxaa;
+mod gizmo;
+mod gpu_particle;
+mod grid;
+mod lit2d;
+mod motion_blur;
+mod occlusion;
+mod oit;
+mod outline;
+mod particle;
 mod pbr;
+mod picking;
+mod post_process;
+mod reflection_probe;
+mod render_texture;
+mod screenshot;
 mod shaded;
+mod shadow;
 mod skybox;
+mod ssao;
+mod ssr;
+mod stats;
+mod taa;
+mod tonemap;
+mod velocity;
+mod vertex_normals;
+mod vignette;
+mod water;
 
-pub use self::{base_3d::*, debug_lines::*, flat::*, flat2d::*, pbr::*, shaded::*, skybox::*};
+pub use self::{
+    base_3d::*, billboard::*, compute::*, custom::*, debug_lines::*, decal::*, deferred::*,
+    flat::*, flat2d::*, gizmo::*, gpu_particle::*, grid::*, lit2d::*, occlusion::*, outline::*,
+    particle::*, pbr::*, picking::*, post_process::*, reflection_probe::*, render_texture::*,
+    screenshot::*, shaded::*, shadow::*, skybox::*, ssao::*, ssr::*, stats::*, taa::*, tonemap::*,
+    velocity::*, vertex_normals::*, water::*,
+};
 
-use rendy::{hal::pso::ShaderStageFlags, shader::SpirvShader};
+use crate::shader_reload::ReloadableShader;
+use rendy::hal::pso::ShaderStageFlags;
 
 lazy_static::lazy_static! {
-    static ref POS_TEX_VERTEX: SpirvShader = SpirvShader::from_bytes(
-        include_bytes!("../../compiled/vertex/pos_tex.vert.spv"),
+    static ref POS_TEX_VERTEX: ReloadableShader = embedded_shader!(
+        "vertex/pos_tex.vert.spv",
         ShaderStageFlags::VERTEX,
-        "main",
-    ).unwrap();
+    );
 
-    static ref POS_TEX_SKIN_VERTEX: SpirvShader = SpirvShader::from_bytes(
-        include_bytes!("../../compiled/vertex/pos_tex_skin.vert.spv"),
+    static ref POS_TEX_SKIN_VERTEX: ReloadableShader = embedded_shader!(
+        "vertex/pos_tex_skin.vert.spv",
         ShaderStageFlags::VERTEX,
-        "main",
-    ).unwrap();
+    );
 
-    static ref POS_NORM_TEX_VERTEX: SpirvShader = SpirvShader::from_bytes(
-        include_bytes!("../../compiled/vertex/pos_norm_tex.vert.spv"),
+    static ref POS_NORM_TEX_VERTEX: ReloadableShader = embedded_shader!(
+        "vertex/pos_norm_tex.vert.spv",
         ShaderStageFlags::VERTEX,
-        "main",
-    ).unwrap();
+    );
 
-    static ref POS_NORM_TEX_SKIN_VERTEX: SpirvShader = SpirvShader::from_bytes(
-        include_bytes!("../../compiled/vertex/pos_norm_tex_skin.vert.spv"),
+    static ref POS_NORM_TEX_SKIN_VERTEX: ReloadableShader = embedded_shader!(
+        "vertex/pos_norm_tex_skin.vert.spv",
         ShaderStageFlags::VERTEX,
-        "main",
-    ).unwrap();
+    );
 
-    static ref POS_NORM_TANG_TEX_VERTEX: SpirvShader = SpirvShader::from_bytes(
-        include_bytes!("../../compiled/vertex/pos_norm_tang_tex.vert.spv"),
+    static ref POS_NORM_TANG_TEX_VERTEX: ReloadableShader = embedded_shader!(
+        "vertex/pos_norm_tang_tex.vert.spv",
         ShaderStageFlags::VERTEX,
-        "main",
-    ).unwrap();
+    );
 
-    static ref POS_NORM_TANG_TEX_SKIN_VERTEX: SpirvShader = SpirvShader::from_bytes(
-        include_bytes!("../../compiled/vertex/pos_norm_tang_tex_skin.vert.spv"),
+    static ref POS_NORM_TANG_TEX_SKIN_VERTEX: ReloadableShader = embedded_shader!(
+        "vertex/pos_norm_tang_tex_skin.vert.spv",
         ShaderStageFlags::VERTEX,
-        "main",
-    ).unwrap();
+    );
 
-    static ref FLAT_FRAGMENT: SpirvShader = SpirvShader::from_bytes(
-        include_bytes!("../../compiled/fragment/flat.frag.spv"),
+    static ref POS_NORM_TANG_TEX_MORPH_VERTEX: ReloadableShader = embedded_shader!(
+        "vertex/pos_norm_tang_tex_morph.vert.spv",
+        ShaderStageFlags::VERTEX,
+    );
+
+    static ref FLAT_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/flat.frag.spv",
+        ShaderStageFlags::FRAGMENT,
+    );
+
+    static ref SHADED_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/shaded.frag.spv",
+        ShaderStageFlags::FRAGMENT,
+    );
+
+    static ref PBR_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/pbr.frag.spv",
+        ShaderStageFlags::FRAGMENT,
+    );
+
+    static ref PBR_SHADOWED_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/pbr_shadowed.frag.spv",
+        ShaderStageFlags::FRAGMENT,
+    );
+
+    static ref FLAT_OIT_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/flat_oit.frag.spv",
+        ShaderStageFlags::FRAGMENT,
+    );
+
+    static ref SHADED_OIT_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/shaded_oit.frag.spv",
+        ShaderStageFlags::FRAGMENT,
+    );
+
+    static ref PBR_OIT_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/pbr_oit.frag.spv",
+        ShaderStageFlags::FRAGMENT,
+    );
+
+    static ref SHADOW_VERTEX: ReloadableShader = embedded_shader!(
+        "vertex/shadow.vert.spv",
+        ShaderStageFlags::VERTEX,
+    );
+
+    static ref SPRITE_VERTEX: ReloadableShader = embedded_shader!(
+        "vertex/sprite.vert.spv",
+        ShaderStageFlags::VERTEX,
+    );
+
+    static ref SPRITE_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/sprite.frag.spv",
+        ShaderStageFlags::FRAGMENT,
+    );
+
+    static ref BILLBOARD_VERTEX: ReloadableShader = embedded_shader!(
+        "vertex/billboard.vert.spv",
+        ShaderStageFlags::VERTEX,
+    );
+
+    static ref SPRITE_LIT_VERTEX: ReloadableShader = embedded_shader!(
+        "vertex/sprite_lit.vert.spv",
+        ShaderStageFlags::VERTEX,
+    );
+
+    static ref SPRITE_LIT_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/sprite_lit.frag.spv",
+        ShaderStageFlags::FRAGMENT,
+    );
+
+    static ref PARTICLE_VERTEX: ReloadableShader = embedded_shader!(
+        "vertex/particle.vert.spv",
+        ShaderStageFlags::VERTEX,
+    );
+
+    static ref PARTICLE_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/particle.frag.spv",
+        ShaderStageFlags::FRAGMENT,
+    );
+
+    static ref GPU_PARTICLE_VERTEX: ReloadableShader = embedded_shader!(
+        "vertex/gpu_particle.vert.spv",
+        ShaderStageFlags::VERTEX,
+    );
+
+    static ref PARTICLE_SOFT_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/particle_soft.frag.spv",
+        ShaderStageFlags::FRAGMENT,
+    );
+
+    static ref SKYBOX_VERTEX: ReloadableShader = embedded_shader!(
+        "vertex/skybox.vert.spv",
+        ShaderStageFlags::VERTEX,
+    );
+
+    static ref SKYBOX_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/skybox.frag.spv",
+        ShaderStageFlags::FRAGMENT,
+    );
+
+    static ref SKYBOX_CUBEMAP_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/skybox_cubemap.frag.spv",
+        ShaderStageFlags::FRAGMENT,
+    );
+
+    static ref GRID_VERTEX: ReloadableShader = embedded_shader!(
+        "vertex/grid.vert.spv",
+        ShaderStageFlags::VERTEX,
+    );
+
+    static ref GRID_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/grid.frag.spv",
+        ShaderStageFlags::FRAGMENT,
+    );
+
+    static ref FULLSCREEN_VERTEX: ReloadableShader = embedded_shader!(
+        "vertex/fullscreen.vert.spv",
+        ShaderStageFlags::VERTEX,
+    );
+
+    static ref PASSTHROUGH_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/passthrough.frag.spv",
+        ShaderStageFlags::FRAGMENT,
+    );
+
+    static ref BLOOM_BRIGHT_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/bloom_bright.frag.spv",
+        ShaderStageFlags::FRAGMENT,
+    );
+
+    static ref BLOOM_BLUR_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/bloom_blur.frag.spv",
+        ShaderStageFlags::FRAGMENT,
+    );
+
+    static ref BLOOM_COMBINE_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/bloom_combine.frag.spv",
+        ShaderStageFlags::FRAGMENT,
+    );
+
+    static ref TONEMAP_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/tonemap.frag.spv",
+        ShaderStageFlags::FRAGMENT,
+    );
+
+    static ref TONEMAP_AUTO_EXPOSURE_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/tonemap_auto_exposure.frag.spv",
+        ShaderStageFlags::FRAGMENT,
+    );
+
+    static ref LUMINANCE_HISTOGRAM_COMPUTE: ReloadableShader = embedded_shader!(
+        "compute/luminance_histogram.comp.spv",
+        ShaderStageFlags::COMPUTE,
+    );
+
+    static ref EXPOSURE_AVERAGE_COMPUTE: ReloadableShader = embedded_shader!(
+        "compute/exposure_average.comp.spv",
+        ShaderStageFlags::COMPUTE,
+    );
+
+    static ref PARTICLE_SIMULATE_COMPUTE: ReloadableShader = embedded_shader!(
+        "compute/particle_simulate.comp.spv",
+        ShaderStageFlags::COMPUTE,
+    );
+
+    static ref PARTICLE_FINALIZE_COMPUTE: ReloadableShader = embedded_shader!(
+        "compute/particle_finalize.comp.spv",
+        ShaderStageFlags::COMPUTE,
+    );
+
+    static ref HIZ_DOWNSAMPLE_COMPUTE: ReloadableShader = embedded_shader!(
+        "compute/hiz_downsample.comp.spv",
+        ShaderStageFlags::COMPUTE,
+    );
+
+    static ref OCCLUSION_TEST_COMPUTE: ReloadableShader = embedded_shader!(
+        "compute/occlusion_test.comp.spv",
+        ShaderStageFlags::COMPUTE,
+    );
+
+    static ref FXAA_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/fxaa.frag.spv",
+        ShaderStageFlags::FRAGMENT,
+    );
+
+    static ref VELOCITY_VERTEX: ReloadableShader = embedded_shader!(
+        "vertex/velocity.vert.spv",
+        ShaderStageFlags::VERTEX,
+    );
+
+    static ref VELOCITY_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/velocity.frag.spv",
+        ShaderStageFlags::FRAGMENT,
+    );
+
+    static ref PICKING_VERTEX: ReloadableShader = embedded_shader!(
+        "vertex/picking.vert.spv",
+        ShaderStageFlags::VERTEX,
+    );
+
+    static ref PICKING_SKIN_VERTEX: ReloadableShader = embedded_shader!(
+        "vertex/picking_skin.vert.spv",
+        ShaderStageFlags::VERTEX,
+    );
+
+    static ref PICKING_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/picking.frag.spv",
+        ShaderStageFlags::FRAGMENT,
+    );
+
+    static ref SPRITE_PICKING_VERTEX: ReloadableShader = embedded_shader!(
+        "vertex/sprite_picking.vert.spv",
+        ShaderStageFlags::VERTEX,
+    );
+
+    static ref SPRITE_PICKING_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/sprite_picking.frag.spv",
+        ShaderStageFlags::FRAGMENT,
+    );
+
+    static ref TAA_RESOLVE_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/taa_resolve.frag.spv",
+        ShaderStageFlags::FRAGMENT,
+    );
+
+    static ref SSAO_NORMALS_VERTEX: ReloadableShader = embedded_shader!(
+        "vertex/ssao_normals.vert.spv",
+        ShaderStageFlags::VERTEX,
+    );
+
+    static ref SSAO_NORMALS_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/ssao_normals.frag.spv",
+        ShaderStageFlags::FRAGMENT,
+    );
+
+    static ref SSAO_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/ssao.frag.spv",
+        ShaderStageFlags::FRAGMENT,
+    );
+
+    static ref SSAO_BLUR_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/ssao_blur.frag.spv",
+        ShaderStageFlags::FRAGMENT,
+    );
+
+    static ref SSR_SURFACES_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/ssr_surfaces.frag.spv",
+        ShaderStageFlags::FRAGMENT,
+    );
+
+    static ref SSR_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/ssr.frag.spv",
+        ShaderStageFlags::FRAGMENT,
+    );
+
+    static ref DOF_COC_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/dof_coc.frag.spv",
+        ShaderStageFlags::FRAGMENT,
+    );
+
+    static ref DOF_BOKEH_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/dof_bokeh.frag.spv",
+        ShaderStageFlags::FRAGMENT,
+    );
+
+    static ref MOTION_BLUR_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/motion_blur.frag.spv",
+        ShaderStageFlags::FRAGMENT,
+    );
+
+    static ref FOG_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/fog.frag.spv",
         ShaderStageFlags::FRAGMENT,
-        "main",
-    ).unwrap();
+    );
 
-    static ref SHADED_FRAGMENT: SpirvShader = SpirvShader::from_bytes(
-        include_bytes!("../../compiled/fragment/shaded.frag.spv"),
+    static ref OIT_RESOLVE_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/oit_resolve.frag.spv",
         ShaderStageFlags::FRAGMENT,
-        "main",
-    ).unwrap();
+    );
 
-    static ref PBR_FRAGMENT: SpirvShader = SpirvShader::from_bytes(
-        include_bytes!("../../compiled/fragment/pbr.frag.spv"),
+    static ref DEBUG_POS_TEX_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/debug_pos_tex.frag.spv",
         ShaderStageFlags::FRAGMENT,
-        "main",
-    ).unwrap();
+    );
 
-    static ref SPRITE_VERTEX: SpirvShader = SpirvShader::from_bytes(
-        include_bytes!("../../compiled/vertex/sprite.vert.spv"),
+    static ref DEBUG_POS_NORM_TEX_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/debug_pos_norm_tex.frag.spv",
+        ShaderStageFlags::FRAGMENT,
+    );
+
+    static ref DEBUG_POS_NORM_TANG_TEX_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/debug_pos_norm_tang_tex.frag.spv",
+        ShaderStageFlags::FRAGMENT,
+    );
+
+    static ref OUTLINE_MASK_VERTEX: ReloadableShader = embedded_shader!(
+        "vertex/outline_mask.vert.spv",
+        ShaderStageFlags::VERTEX,
+    );
+
+    static ref OUTLINE_MASK_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/outline_mask.frag.spv",
+        ShaderStageFlags::FRAGMENT,
+    );
+
+    static ref OUTLINE_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/outline.frag.spv",
+        ShaderStageFlags::FRAGMENT,
+    );
+
+    static ref COLOR_GRADING_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/color_grading.frag.spv",
+        ShaderStageFlags::FRAGMENT,
+    );
+
+    static ref VIGNETTE_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/vignette.frag.spv",
+        ShaderStageFlags::FRAGMENT,
+    );
+
+    static ref CHROMATIC_ABERRATION_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/chromatic_aberration.frag.spv",
+        ShaderStageFlags::FRAGMENT,
+    );
+
+    static ref GBUFFER_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/gbuffer.frag.spv",
+        ShaderStageFlags::FRAGMENT,
+    );
+
+    static ref DEFERRED_LIGHTING_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/deferred_lighting.frag.spv",
+        ShaderStageFlags::FRAGMENT,
+    );
+
+    static ref DEFERRED_LIGHTING_DECALS_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/deferred_lighting_decals.frag.spv",
+        ShaderStageFlags::FRAGMENT,
+    );
+
+    static ref DEFERRED_POINT_LIGHT_VERTEX: ReloadableShader = embedded_shader!(
+        "vertex/deferred_point_light.vert.spv",
         ShaderStageFlags::VERTEX,
-        "main",
-    ).unwrap();
+    );
 
-    static ref SPRITE_FRAGMENT: SpirvShader = SpirvShader::from_bytes(
-        include_bytes!("../../compiled/fragment/sprite.frag.spv"),
+    static ref DEFERRED_POINT_LIGHT_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/deferred_point_light.frag.spv",
         ShaderStageFlags::FRAGMENT,
-        "main",
-    ).unwrap();
+    );
 
-    static ref SKYBOX_VERTEX: SpirvShader = SpirvShader::from_bytes(
-        include_bytes!("../../compiled/vertex/skybox.vert.spv"),
+    static ref DEFERRED_POINT_LIGHT_DECALS_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/deferred_point_light_decals.frag.spv",
+        ShaderStageFlags::FRAGMENT,
+    );
+
+    static ref DECAL_VERTEX: ReloadableShader = embedded_shader!(
+        "vertex/decal.vert.spv",
         ShaderStageFlags::VERTEX,
-        "main",
-    ).unwrap();
+    );
 
-    static ref SKYBOX_FRAGMENT: SpirvShader = SpirvShader::from_bytes(
-        include_bytes!("../../compiled/fragment/skybox.frag.spv"),
+    static ref DECAL_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/decal.frag.spv",
         ShaderStageFlags::FRAGMENT,
-        "main",
-    ).unwrap();
+    );
+
+    static ref WATER_VERTEX: ReloadableShader = embedded_shader!(
+        "vertex/water.vert.spv",
+        ShaderStageFlags::VERTEX,
+    );
+
+    static ref WATER_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/water.frag.spv",
+        ShaderStageFlags::FRAGMENT,
+    );
+
+    static ref DEBUG_LINES_VERTEX: ReloadableShader = embedded_shader!(
+        "vertex/debug_lines.vert.spv",
+        ShaderStageFlags::VERTEX,
+    );
+
+    static ref DEBUG_LINES_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/debug_lines.frag.spv",
+        ShaderStageFlags::FRAGMENT,
+    );
+
+    static ref VERTEX_NORMALS_VERTEX: ReloadableShader = embedded_shader!(
+        "vertex/vertex_normals.vert.spv",
+        ShaderStageFlags::VERTEX,
+    );
 
-    static ref DEBUG_LINES_VERTEX: SpirvShader = SpirvShader::from_bytes(
-        include_bytes!("../../compiled/vertex/debug_lines.vert.spv"),
+    static ref VERTEX_TANGENTS_VERTEX: ReloadableShader = embedded_shader!(
+        "vertex/vertex_tangents.vert.spv",
         ShaderStageFlags::VERTEX,
-        "main",
-    ).unwrap();
+    );
 
-    static ref DEBUG_LINES_FRAGMENT: SpirvShader = SpirvShader::from_bytes(
-        include_bytes!("../../compiled/fragment/debug_lines.frag.spv"),
+    static ref VERTEX_NORMALS_FRAGMENT: ReloadableShader = embedded_shader!(
+        "fragment/vertex_normals.frag.spv",
         ShaderStageFlags::FRAGMENT,
-        "main",
-    ).unwrap();
+    );
 }
@@ -0,0 +1,22 @@
+use crate::{
+    bundle::{Target, TargetImage},
+    motion_blur::{MotionBlurParams, MAX_MOTION_BLUR_SAMPLES},
+    pass::DrawPostProcessDesc,
+};
+
+impl DrawPostProcessDesc {
+    /// Create instance of `DrawPostProcess` render group blurring its input along the motion
+    /// drawn to [Target::Velocity] by `DrawVelocity`, as configured by [MotionBlurParams].
+    pub fn motion_blur() -> Self {
+        Self::new(super::MOTION_BLUR_FRAGMENT.clone())
+            .with_input(TargetImage::Color(Target::Velocity, 0))
+            .with_constants(|world| {
+                let params = MotionBlurParams::fetch(world);
+                vec![
+                    if params.enabled { 1.0 } else { 0.0 },
+                    params.sample_count.min(MAX_MOTION_BLUR_SAMPLES) as f32,
+                    params.shutter_scale.max(0.0),
+                ]
+            })
+    }
+}
@@ -0,0 +1,508 @@
+use super::{post_process::input_view, tonemap::build_compute_pipeline};
+use crate::{
+    bundle::ImageOptions, occlusion::OcclusionCulling, pipeline::PipelineCache, pod::OcclusionArgs,
+    submodules::gather::CameraGatherer, types::Backend, util,
+};
+use amethyst_core::ecs::{Entities, Entity, SystemData, World, WriteExpect};
+use glsl_layout::AsStd140;
+use rendy::{
+    command::{
+        CommandBuffer, CommandPool, Compute, ExecutableState, Family, MultiShot, PendingState,
+        SimultaneousUse, Submit,
+    },
+    factory::Factory,
+    frame::Frames,
+    graph::{
+        gfx_acquire_barriers, gfx_release_barriers, GraphContext, ImageAccess, Node, NodeBuffer,
+        NodeDesc, NodeImage, NodeSubmittable,
+    },
+    hal::{self, device::Device, format::Swizzle, pso},
+    memory::{Download, Dynamic, Write},
+    resource::{
+        Buffer, BufferInfo, DescriptorSet, DescriptorSetLayout, Escape, Filter,
+        Handle as RendyHandle, ImageView, ImageViewInfo, Sampler, SamplerInfo, ViewKind, WrapMode,
+    },
+};
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+/// Workgroup size of the hierarchical depth compute shader in each dimension.
+const HIZ_GROUP_SIZE: u32 = 8;
+
+/// Workgroup size of the occlusion test compute shader.
+const TEST_GROUP_SIZE: u32 = 64;
+
+/// Size of a bounding box in the storage buffer, a center and half extents padded to `vec4`s.
+const BOX_SIZE: u64 = 32;
+
+/// Options of the hierarchical depth image built by [HiZOcclusion] from a `width` by `height`
+/// depth image, with every mip level down to a single texel.
+pub fn hiz_image_options(width: u32, height: u32) -> ImageOptions {
+    let (width, height) = ((width / 2).max(1), (height / 2).max(1));
+    ImageOptions {
+        kind: hal::image::Kind::D2(width, height, 1, 1),
+        levels: (32 - width.max(height).leading_zeros()) as hal::image::Level,
+        format: hal::format::Format::R32Sfloat,
+        clear: None,
+    }
+}
+
+/// Describe testing the bounding boxes submitted to [OcclusionCulling] against a depth image.
+///
+/// The node takes two images, the depth image to test against and a hierarchical depth image
+/// created with [hiz_image_options] for it. Every frame, it builds the hierarchical depth
+/// image from the depth, tests at most `capacity` boxes against it and reads the results back
+/// into the resource once the frame has completed.
+#[derive(Debug)]
+pub struct HiZOcclusionDesc {
+    capacity: usize,
+}
+
+impl HiZOcclusionDesc {
+    /// Create instance of `HiZOcclusion` compute node testing at most `capacity` boxes
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity }
+    }
+}
+
+impl<B: Backend> NodeDesc<B, World> for HiZOcclusionDesc {
+    type Node = HiZOcclusion<B>;
+
+    fn images(&self) -> Vec<ImageAccess> {
+        vec![
+            ImageAccess {
+                access: hal::image::Access::SHADER_READ,
+                usage: hal::image::Usage::SAMPLED,
+                layout: hal::image::Layout::ShaderReadOnlyOptimal,
+                stages: pso::PipelineStage::COMPUTE_SHADER,
+            },
+            ImageAccess {
+                access: hal::image::Access::SHADER_READ | hal::image::Access::SHADER_WRITE,
+                usage: hal::image::Usage::SAMPLED | hal::image::Usage::STORAGE,
+                layout: hal::image::Layout::General,
+                stages: pso::PipelineStage::COMPUTE_SHADER,
+            },
+        ]
+    }
+
+    fn build<'a>(
+        self,
+        ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        family: &mut Family<B>,
+        _queue: usize,
+        aux: &World,
+        buffers: Vec<NodeBuffer>,
+        images: Vec<NodeImage>,
+    ) -> Result<Self::Node, failure::Error> {
+        #[cfg(feature = "profiler")]
+        profile_scope!("build");
+
+        let depth_extent = ctx
+            .get_image(images[0].id)
+            .ok_or_else(|| failure::format_err!("Occlusion depth image is not available"))?
+            .kind()
+            .extent();
+        let depth_view = input_view(ctx, factory, &images[0])?;
+        let hiz = ctx
+            .get_image(images[1].id)
+            .ok_or_else(|| failure::format_err!("Hierarchical depth image is not available"))?;
+        let hiz_extent = hiz.kind().extent();
+        let levels = hiz.levels();
+        let hiz_range = |levels| hal::image::SubresourceRange {
+            aspects: hal::format::Aspects::COLOR,
+            levels,
+            layers: 0..1,
+        };
+        let hiz_view = |levels| {
+            factory.create_image_view(
+                hiz.clone(),
+                ImageViewInfo {
+                    view_kind: ViewKind::D2,
+                    format: hiz.format(),
+                    swizzle: Swizzle::NO,
+                    range: hiz_range(levels),
+                },
+            )
+        };
+        let level_views = (0..levels)
+            .map(|level| hiz_view(level..level + 1))
+            .collect::<Result<Vec<_>, _>>()?;
+        let full_view = hiz_view(0..levels)?;
+        let sampler = factory.get_sampler(SamplerInfo::new(Filter::Nearest, WrapMode::Clamp))?;
+
+        let downsample_layout: RendyHandle<DescriptorSetLayout<B>> = set_layout! {
+            factory,
+            [1] CombinedImageSampler pso::ShaderStageFlags::COMPUTE,
+            [1] StorageImage pso::ShaderStageFlags::COMPUTE
+        };
+        let test_layout: RendyHandle<DescriptorSetLayout<B>> = set_layout! {
+            factory,
+            [1] CombinedImageSampler pso::ShaderStageFlags::COMPUTE,
+            [2] StorageBuffer pso::ShaderStageFlags::COMPUTE,
+            [1] UniformBuffer pso::ShaderStageFlags::COMPUTE
+        };
+        let downsample_pipeline_layout = unsafe {
+            factory
+                .device()
+                .create_pipeline_layout(Some(downsample_layout.raw()), None as Option<(_, _)>)
+        }?;
+        let test_pipeline_layout = unsafe {
+            factory
+                .device()
+                .create_pipeline_layout(Some(test_layout.raw()), None as Option<(_, _)>)
+        }?;
+        let pipeline_cache = aux.try_fetch::<PipelineCache<B>>();
+        let cache = pipeline_cache.as_ref().and_then(|cache| cache.raw());
+        let downsample_pipeline = build_compute_pipeline(
+            factory,
+            cache,
+            &downsample_pipeline_layout,
+            &super::HIZ_DOWNSAMPLE_COMPUTE,
+        )?;
+        let test_pipeline = build_compute_pipeline(
+            factory,
+            cache,
+            &test_pipeline_layout,
+            &super::OCCLUSION_TEST_COMPUTE,
+        )?;
+
+        // Every level is built from the depth image or the level before it.
+        let mut downsample_sets = Vec::new();
+        for (level, view) in level_views.iter().enumerate() {
+            let source = if level == 0 {
+                pso::Descriptor::CombinedImageSampler(
+                    depth_view.raw(),
+                    hal::image::Layout::ShaderReadOnlyOptimal,
+                    sampler.raw(),
+                )
+            } else {
+                pso::Descriptor::CombinedImageSampler(
+                    level_views[level - 1].raw(),
+                    hal::image::Layout::General,
+                    sampler.raw(),
+                )
+            };
+            let set = factory.create_descriptor_set(downsample_layout.clone())?;
+            unsafe {
+                factory.write_descriptor_sets(vec![
+                    util::desc_write(set.raw(), 0, source),
+                    util::desc_write(
+                        set.raw(),
+                        1,
+                        pso::Descriptor::Image(view.raw(), hal::image::Layout::General),
+                    ),
+                ]);
+            }
+            downsample_sets.push(set);
+        }
+
+        let mut command_pool = factory
+            .create_command_pool(family)?
+            .with_capability::<Compute>()
+            .expect("Graph builder must provide family with Compute capability");
+
+        // Each frame in flight has its own boxes and results, and command buffer recorded once.
+        let capacity = self.capacity.max(1) as u64;
+        let mut frames = Vec::new();
+        for command_buffer in command_pool.allocate_buffers(ctx.frames_in_flight as usize) {
+            let args = factory.create_buffer(
+                BufferInfo {
+                    size: std::mem::size_of::<<OcclusionArgs as AsStd140>::Std140>() as u64,
+                    usage: hal::buffer::Usage::UNIFORM,
+                },
+                Dynamic,
+            )?;
+            let boxes = factory.create_buffer(
+                BufferInfo {
+                    size: capacity * BOX_SIZE,
+                    usage: hal::buffer::Usage::STORAGE,
+                },
+                Dynamic,
+            )?;
+            let results = factory.create_buffer(
+                BufferInfo {
+                    size: capacity * std::mem::size_of::<u32>() as u64,
+                    usage: hal::buffer::Usage::STORAGE,
+                },
+                Download,
+            )?;
+            let set = factory.create_descriptor_set(test_layout.clone())?;
+            unsafe {
+                factory.write_descriptor_sets(vec![
+                    util::desc_write(
+                        set.raw(),
+                        0,
+                        pso::Descriptor::CombinedImageSampler(
+                            full_view.raw(),
+                            hal::image::Layout::General,
+                            sampler.raw(),
+                        ),
+                    ),
+                    util::desc_write(
+                        set.raw(),
+                        1,
+                        pso::Descriptor::Buffer(boxes.raw(), None..None),
+                    ),
+                    util::desc_write(
+                        set.raw(),
+                        2,
+                        pso::Descriptor::Buffer(results.raw(), None..None),
+                    ),
+                    util::desc_write(
+                        set.raw(),
+                        3,
+                        pso::Descriptor::Buffer(args.raw(), None..None),
+                    ),
+                ]);
+            }
+
+            let mut recording = command_buffer.begin(MultiShot(SimultaneousUse), ());
+            let mut encoder = recording.encoder();
+            let shader_write =
+                pso::PipelineStage::COMPUTE_SHADER..pso::PipelineStage::COMPUTE_SHADER;
+            unsafe {
+                let (stages, barriers) = gfx_acquire_barriers(ctx, &buffers, &images);
+                encoder.pipeline_barrier(stages, hal::memory::Dependencies::empty(), barriers);
+                // The previous frame must be done testing before the levels are rebuilt.
+                encoder.pipeline_barrier(
+                    shader_write.clone(),
+                    hal::memory::Dependencies::empty(),
+                    Some(hal::memory::Barrier::Image {
+                        states: (hal::image::Access::empty(), hal::image::Layout::Undefined)
+                            ..(
+                                hal::image::Access::SHADER_WRITE,
+                                hal::image::Layout::General,
+                            ),
+                        target: hiz.raw(),
+                        families: None,
+                        range: hiz_range(0..levels),
+                    }),
+                );
+
+                encoder.bind_compute_pipeline(&downsample_pipeline);
+                for (level, set) in downsample_sets.iter().enumerate() {
+                    encoder.bind_compute_descriptor_sets(
+                        &downsample_pipeline_layout,
+                        0,
+                        Some(set.raw()),
+                        std::iter::empty(),
+                    );
+                    let width = (hiz_extent.width >> level).max(1);
+                    let height = (hiz_extent.height >> level).max(1);
+                    encoder.dispatch(
+                        (width + HIZ_GROUP_SIZE - 1) / HIZ_GROUP_SIZE,
+                        (height + HIZ_GROUP_SIZE - 1) / HIZ_GROUP_SIZE,
+                        1,
+                    );
+                    let level = level as hal::image::Level;
+                    encoder.pipeline_barrier(
+                        shader_write.clone(),
+                        hal::memory::Dependencies::empty(),
+                        Some(hal::memory::Barrier::Image {
+                            states: (
+                                hal::image::Access::SHADER_WRITE,
+                                hal::image::Layout::General,
+                            )
+                                ..(hal::image::Access::SHADER_READ, hal::image::Layout::General),
+                            target: hiz.raw(),
+                            families: None,
+                            range: hiz_range(level..level + 1),
+                        }),
+                    );
+                }
+
+                encoder.bind_compute_pipeline(&test_pipeline);
+                encoder.bind_compute_descriptor_sets(
+                    &test_pipeline_layout,
+                    0,
+                    Some(set.raw()),
+                    std::iter::empty(),
+                );
+                encoder.dispatch(
+                    (capacity as u32 + TEST_GROUP_SIZE - 1) / TEST_GROUP_SIZE,
+                    1,
+                    1,
+                );
+                encoder.pipeline_barrier(
+                    pso::PipelineStage::COMPUTE_SHADER..pso::PipelineStage::HOST,
+                    hal::memory::Dependencies::empty(),
+                    Some(hal::memory::Barrier::whole_buffer(
+                        results.raw(),
+                        hal::buffer::Access::SHADER_WRITE..hal::buffer::Access::HOST_READ,
+                    )),
+                );
+
+                let (stages, barriers) = gfx_release_barriers(ctx, &buffers, &images);
+                encoder.pipeline_barrier(stages, hal::memory::Dependencies::empty(), barriers);
+            }
+
+            let (submit, command_buffer) = recording.finish().submit();
+            frames.push(HiZOcclusionFrame {
+                args,
+                boxes,
+                results,
+                tested: Vec::new(),
+                set,
+                command_buffer,
+                submit,
+            });
+        }
+
+        Ok(HiZOcclusion {
+            downsample_pipeline_layout,
+            test_pipeline_layout,
+            downsample_pipeline,
+            test_pipeline,
+            command_pool,
+            frames,
+            depth_size: [depth_extent.width as f32, depth_extent.height as f32],
+            levels: u32::from(levels),
+            _downsample_layout: downsample_layout,
+            _test_layout: test_layout,
+            _downsample_sets: downsample_sets,
+            _depth_view: depth_view,
+            _level_views: level_views,
+            _full_view: full_view,
+            _sampler: sampler,
+        })
+    }
+}
+
+#[derive(Debug)]
+struct HiZOcclusionFrame<B: Backend> {
+    args: Escape<Buffer<B>>,
+    boxes: Escape<Buffer<B>>,
+    results: Escape<Buffer<B>>,
+    // Entities of the boxes tested by the last submission of this frame.
+    tested: Vec<Entity>,
+    set: Escape<DescriptorSet<B>>,
+    command_buffer:
+        CommandBuffer<B, Compute, PendingState<ExecutableState<MultiShot<SimultaneousUse>>>>,
+    submit: Submit<B, SimultaneousUse>,
+}
+
+/// Builds a hierarchical depth image and tests the bounding boxes submitted to
+/// [OcclusionCulling] against it, passing the occluded entities of the last completed frame
+/// to the resource.
+#[derive(Debug)]
+pub struct HiZOcclusion<B: Backend> {
+    downsample_pipeline_layout: B::PipelineLayout,
+    test_pipeline_layout: B::PipelineLayout,
+    downsample_pipeline: B::ComputePipeline,
+    test_pipeline: B::ComputePipeline,
+    command_pool: CommandPool<B, Compute>,
+    frames: Vec<HiZOcclusionFrame<B>>,
+    depth_size: [f32; 2],
+    levels: u32,
+    _downsample_layout: RendyHandle<DescriptorSetLayout<B>>,
+    _test_layout: RendyHandle<DescriptorSetLayout<B>>,
+    _downsample_sets: Vec<Escape<DescriptorSet<B>>>,
+    _depth_view: Escape<ImageView<B>>,
+    _level_views: Vec<Escape<ImageView<B>>>,
+    _full_view: Escape<ImageView<B>>,
+    _sampler: RendyHandle<Sampler<B>>,
+}
+
+impl<'a, B: Backend> NodeSubmittable<'a, B> for HiZOcclusion<B> {
+    type Submittable = &'a Submit<B, SimultaneousUse>;
+    type Submittables = &'a [Submit<B, SimultaneousUse>];
+}
+
+impl<B: Backend> Node<B, World> for HiZOcclusion<B> {
+    type Capability = Compute;
+    type Desc = HiZOcclusionDesc;
+
+    fn run<'a>(
+        &'a mut self,
+        _ctx: &GraphContext<B>,
+        factory: &Factory<B>,
+        aux: &World,
+        frames: &'a Frames<B>,
+    ) -> &'a [Submit<B, SimultaneousUse>] {
+        #[cfg(feature = "profiler")]
+        profile_scope!("run");
+
+        let (entities, mut occlusion) =
+            <(Entities<'_>, WriteExpect<'_, OcclusionCulling>)>::fetch(aux);
+
+        // The graph waits for the frame that last used this slot before running.
+        let index = frames.next().index() as usize % self.frames.len();
+        let frame = &mut self.frames[index];
+        occlusion.occluded.clear();
+        if !frame.tested.is_empty() {
+            let range = 0..frame.tested.len() as u64 * std::mem::size_of::<u32>() as u64;
+            let mut mapped = frame.results.map(factory.device(), range.clone()).unwrap();
+            let visible = unsafe { mapped.read::<u32>(factory.device(), range).unwrap() };
+            for (entity, visible) in frame.tested.iter().zip(visible) {
+                if *visible == 0 && entities.is_alive(*entity) {
+                    occlusion.occluded.add(entity.id());
+                }
+            }
+        }
+
+        frame.tested.clear();
+        frame
+            .tested
+            .extend(occlusion.boxes.iter().map(|(entity, _, _)| *entity));
+        if !occlusion.boxes.is_empty() {
+            let boxes = occlusion
+                .boxes
+                .iter()
+                .map(|(_, center, half_extents)| {
+                    [
+                        [center.x, center.y, center.z, 0.0],
+                        [half_extents.x, half_extents.y, half_extents.z, 0.0],
+                    ]
+                })
+                .collect::<Vec<_>>();
+            let range = 0..boxes.len() as u64 * BOX_SIZE;
+            let mut mapped = frame.boxes.map(factory.device(), range.clone()).unwrap();
+            unsafe {
+                let mut writer = mapped.write::<u8>(factory.device(), range).unwrap();
+                writer.slice().copy_from_slice(util::slice_as_bytes(&boxes));
+            }
+        }
+
+        let camera = CameraGatherer::gather(aux);
+        let proj_view: [[f32; 4]; 4] = (camera.projection * camera.view).into();
+        let args = OcclusionArgs {
+            proj_view: proj_view.into(),
+            depth_size: self.depth_size.into(),
+            count: frame.tested.len() as u32,
+            levels: self.levels,
+        }
+        .std140();
+        let range = 0..frame.args.size();
+        let mut mapped = frame.args.map(factory.device(), range.clone()).unwrap();
+        unsafe {
+            let mut writer = mapped.write::<u8>(factory.device(), range).unwrap();
+            writer
+                .slice()
+                .copy_from_slice(util::slice_as_bytes(&[args]));
+        }
+
+        std::slice::from_ref(&self.frames[index].submit)
+    }
+
+    unsafe fn dispose(mut self, factory: &mut Factory<B>, _aux: &World) {
+        for frame in self.frames.drain(..) {
+            self.command_pool
+                .free_buffers(Some(frame.command_buffer.mark_complete()));
+        }
+        factory.destroy_command_pool(self.command_pool);
+        factory
+            .device()
+            .destroy_compute_pipeline(self.downsample_pipeline);
+        factory
+            .device()
+            .destroy_compute_pipeline(self.test_pipeline);
+        factory
+            .device()
+            .destroy_pipeline_layout(self.downsample_pipeline_layout);
+        factory
+            .device()
+            .destroy_pipeline_layout(self.test_pipeline_layout);
+    }
+}
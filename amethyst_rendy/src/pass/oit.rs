@@ -0,0 +1,14 @@
+use crate::{
+    bundle::{Target, TargetImage},
+    pass::DrawPostProcessDesc,
+};
+
+impl DrawPostProcessDesc {
+    /// Create instance of `DrawPostProcess` render group compositing the transparent meshes
+    /// accumulated in [Target::OrderIndependentTransparency] over its input.
+    pub fn oit_resolve() -> Self {
+        Self::new(super::OIT_RESOLVE_FRAGMENT.clone())
+            .with_input(TargetImage::Color(Target::OrderIndependentTransparency, 0))
+            .with_input(TargetImage::Color(Target::OrderIndependentTransparency, 1))
+    }
+}
@@ -0,0 +1,299 @@
+use crate::{
+    batch::{GroupIterator, OneLevelBatch},
+    bundle::{Target, TargetImage},
+    outline::Outlined,
+    pass::DrawPostProcessDesc,
+    pipeline::{PipelineCache, PipelineDescBuilder, PipelinesBuilder},
+    pod::{OutlineArgs, ViewArgs},
+    skinning::JointTransforms,
+    stats::DrawCounter,
+    submodules::{gather::CameraGatherer, DynamicUniform, DynamicVertexBuffer},
+    types::{Backend, Mesh},
+    util,
+};
+use amethyst_assets::{AssetStorage, Handle};
+use amethyst_core::{
+    ecs::{Join, Read, ReadStorage, SystemData, World},
+    transform::Transform,
+    Hidden, HiddenPropagate,
+};
+use derivative::Derivative;
+use rendy::{
+    command::{QueueId, RenderPassEncoder},
+    factory::Factory,
+    graph::{
+        render::{PrepareResult, RenderGroup, RenderGroupDesc},
+        GraphContext, NodeBuffer, NodeImage,
+    },
+    hal::{self, device::Device, pso},
+    mesh::{AsVertex, Position, VertexFormat},
+    shader::Shader,
+};
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+impl DrawPostProcessDesc {
+    /// Create instance of `DrawPostProcess` render group drawing outlines around the
+    /// silhouettes of [Outlined] meshes.
+    ///
+    /// Silhouettes are read from the two color outputs of `DrawOutlineMask` rendered
+    /// to `mask`.
+    pub fn outline(mask: Target) -> Self {
+        Self::new(super::OUTLINE_FRAGMENT.clone())
+            .with_input(TargetImage::Color(mask, 0))
+            .with_input(TargetImage::Color(mask, 1))
+            .with_constants(|world| {
+                let outlined = <ReadStorage<'_, Outlined>>::fetch(world);
+                // Pixels are searched only as far as the widest outline reaches.
+                let max_width = (&outlined)
+                    .join()
+                    .map(|outlined| outlined.width)
+                    .fold(0.0, f32::max);
+                vec![max_width.min(crate::outline::MAX_OUTLINE_WIDTH)]
+            })
+    }
+}
+
+/// Draw the silhouettes of [Outlined] static meshes with their outline color and width,
+/// sampled by the outline post-processing pass.
+///
+/// The target needs two color images cleared to zero, for the color and the width,
+/// and no depth attachment.
+#[derive(Clone, Debug, PartialEq, Derivative)]
+#[derivative(Default(bound = ""))]
+pub struct DrawOutlineMaskDesc;
+
+impl DrawOutlineMaskDesc {
+    /// Create instance of `DrawOutlineMask` render group
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl<B: Backend> RenderGroupDesc<B, World> for DrawOutlineMaskDesc {
+    fn depth(&self) -> bool {
+        false
+    }
+
+    fn colors(&self) -> usize {
+        2
+    }
+
+    fn build(
+        self,
+        _ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        _queue: QueueId,
+        aux: &World,
+        framebuffer_width: u32,
+        framebuffer_height: u32,
+        subpass: hal::pass::Subpass<'_, B>,
+        _buffers: Vec<NodeBuffer>,
+        _images: Vec<NodeImage>,
+    ) -> Result<Box<dyn RenderGroup<B, World>>, failure::Error> {
+        #[cfg(feature = "profiler")]
+        profile_scope!("build");
+
+        let env = DynamicUniform::new(factory, pso::ShaderStageFlags::VERTEX)?;
+        let pipeline_cache = aux.try_fetch::<PipelineCache<B>>();
+        let (pipeline, pipeline_layout) = build_outline_mask_pipeline(
+            factory,
+            pipeline_cache.as_ref().and_then(|cache| cache.raw()),
+            subpass,
+            framebuffer_width,
+            framebuffer_height,
+            vec![env.raw_layout()],
+        )?;
+
+        Ok(Box::new(DrawOutlineMask::<B> {
+            pipeline,
+            pipeline_layout,
+            env,
+            batches: Default::default(),
+            models: DynamicVertexBuffer::new(),
+            vertex_format: vec![Position::vertex()],
+        }))
+    }
+}
+
+/// Draws the silhouettes of outlined static meshes, over each other without depth testing.
+///
+/// Skinned meshes are not drawn.
+#[derive(Debug)]
+pub struct DrawOutlineMask<B: Backend> {
+    pipeline: B::GraphicsPipeline,
+    pipeline_layout: B::PipelineLayout,
+    env: DynamicUniform<B, ViewArgs>,
+    batches: OneLevelBatch<u32, OutlineArgs>,
+    models: DynamicVertexBuffer<B, OutlineArgs>,
+    vertex_format: Vec<VertexFormat>,
+}
+
+impl<B: Backend> RenderGroup<B, World> for DrawOutlineMask<B> {
+    fn prepare(
+        &mut self,
+        factory: &Factory<B>,
+        _queue: QueueId,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        resources: &World,
+    ) -> PrepareResult {
+        #[cfg(feature = "profiler")]
+        profile_scope!("prepare");
+
+        let (mesh_storage, meshes, outlined, transforms, joints, hiddens, hiddens_prop) =
+            <(
+                Read<'_, AssetStorage<Mesh>>,
+                ReadStorage<'_, Handle<Mesh>>,
+                ReadStorage<'_, Outlined>,
+                ReadStorage<'_, Transform>,
+                ReadStorage<'_, JointTransforms>,
+                ReadStorage<'_, Hidden>,
+                ReadStorage<'_, HiddenPropagate>,
+            )>::fetch(resources);
+
+        self.env
+            .write(factory, index, CameraGatherer::gather(resources).projview);
+
+        self.batches.clear_inner();
+        {
+            let batches = &mut self.batches;
+            (
+                &meshes,
+                &outlined,
+                &transforms,
+                !&joints,
+                !&hiddens,
+                !&hiddens_prop,
+            )
+                .join()
+                .map(|(mesh, outlined, transform, _, _, _)| {
+                    (mesh.id(), OutlineArgs::from_outlined(outlined, transform))
+                })
+                .for_each_group(|mesh_id, data| {
+                    if mesh_storage.contains_id(mesh_id) {
+                        batches.insert(mesh_id, data.drain(..));
+                    }
+                });
+        }
+        self.batches.prune();
+
+        self.models.write(
+            factory,
+            index,
+            self.batches.count() as u64,
+            self.batches.data(),
+        );
+
+        PrepareResult::DrawRecord
+    }
+
+    fn draw_inline(
+        &mut self,
+        mut encoder: RenderPassEncoder<'_, B>,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        resources: &World,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("draw");
+
+        if self.batches.count() == 0 {
+            return;
+        }
+
+        let mesh_storage = <Read<'_, AssetStorage<Mesh>>>::fetch(resources);
+
+        encoder.bind_graphics_pipeline(&self.pipeline);
+        self.env.bind(index, &self.pipeline_layout, 0, &mut encoder);
+        if !self
+            .models
+            .bind(index, self.vertex_format.len() as u32, 0, &mut encoder)
+        {
+            return;
+        }
+
+        let mut draws = DrawCounter::fetch(resources);
+        for (&mesh_id, range) in self.batches.iter() {
+            if let Some(mesh) = B::unwrap_mesh(unsafe { mesh_storage.get_by_id_unchecked(mesh_id) })
+            {
+                // Meshes without positions don't have a silhouette.
+                let instances = range.end - range.start;
+                if mesh
+                    .bind_and_draw(0, &self.vertex_format, range, &mut encoder)
+                    .is_ok()
+                {
+                    draws.draw(mesh.primitive(), mesh.len(), instances);
+                }
+            }
+        }
+    }
+
+    fn dispose(self: Box<Self>, factory: &mut Factory<B>, _aux: &World) {
+        unsafe {
+            factory.device().destroy_graphics_pipeline(self.pipeline);
+            factory
+                .device()
+                .destroy_pipeline_layout(self.pipeline_layout);
+        }
+    }
+}
+
+fn build_outline_mask_pipeline<B: Backend>(
+    factory: &Factory<B>,
+    cache: Option<&B::PipelineCache>,
+    subpass: hal::pass::Subpass<'_, B>,
+    framebuffer_width: u32,
+    framebuffer_height: u32,
+    layouts: Vec<&B::DescriptorSetLayout>,
+) -> Result<(B::GraphicsPipeline, B::PipelineLayout), failure::Error> {
+    let pipeline_layout = unsafe {
+        factory
+            .device()
+            .create_pipeline_layout(layouts, None as Option<(_, _)>)
+    }?;
+
+    let shader_vertex = unsafe { super::OUTLINE_MASK_VERTEX.module(factory).unwrap() };
+    let shader_fragment = unsafe { super::OUTLINE_MASK_FRAGMENT.module(factory).unwrap() };
+
+    let pipes = PipelinesBuilder::new()
+        .with_pipeline(
+            PipelineDescBuilder::new()
+                .with_vertex_desc(&[
+                    (Position::vertex(), pso::VertexInputRate::Vertex),
+                    (OutlineArgs::vertex(), pso::VertexInputRate::Instance(1)),
+                ])
+                .with_shaders(util::simple_shader_set(
+                    &shader_vertex,
+                    Some(&shader_fragment),
+                ))
+                .with_layout(&pipeline_layout)
+                .with_subpass(subpass)
+                .with_framebuffer_size(framebuffer_width, framebuffer_height)
+                .with_face_culling(pso::Face::BACK)
+                .with_blend_targets(vec![
+                    pso::ColorBlendDesc {
+                        mask: pso::ColorMask::ALL,
+                        blend: None,
+                    };
+                    2
+                ]),
+        )
+        .build_cached(factory, cache);
+
+    unsafe {
+        factory.destroy_shader_module(shader_vertex);
+        factory.destroy_shader_module(shader_fragment);
+    }
+
+    match pipes {
+        Err(e) => {
+            unsafe {
+                factory.device().destroy_pipeline_layout(pipeline_layout);
+            }
+            Err(e)
+        }
+        Ok(mut pipes) => Ok((pipes.remove(0), pipeline_layout)),
+    }
+}
@@ -0,0 +1,371 @@
+use super::post_process::{input_view, post_process_input_access};
+use crate::{
+    batch::OrderedOneLevelBatch,
+    particle::ParticleEmitter,
+    pipeline::{PipelineCache, PipelineDescBuilder, PipelinesBuilder},
+    pod::{ParticleArgs, ViewArgs},
+    stats::DrawCounter,
+    submodules::{
+        gather::CameraGatherer, DynamicUniform, DynamicVertexBuffer, TextureId, TextureSub,
+    },
+    types::Backend,
+    util,
+};
+use amethyst_core::{
+    ecs::{Join, ReadStorage, SystemData, World},
+    Hidden, HiddenPropagate,
+};
+use derivative::Derivative;
+use rendy::{
+    command::{QueueId, RenderPassEncoder},
+    factory::Factory,
+    graph::{
+        render::{PrepareResult, RenderGroup, RenderGroupDesc},
+        GraphContext, ImageAccess, NodeBuffer, NodeImage,
+    },
+    hal::{self, device::Device, pso},
+    mesh::AsVertex,
+    resource::{
+        DescriptorSet, DescriptorSetLayout, Escape, Filter, Handle as RendyHandle, ImageView,
+        Sampler, SamplerInfo, WrapMode,
+    },
+    shader::Shader,
+};
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+/// Draw the particles of every [ParticleEmitter] as camera facing quads.
+///
+/// By default particles are depth tested against the depth attachment of the target, so they
+/// intersect opaque surfaces with hard edges. Soft particles sample the depth image of
+/// another target instead, passed as the only image of the group, and fade out in front of
+/// opaque surfaces. They are meant for targets without a depth attachment, like a
+/// post-processing stage reading the depth of [Target::Main](crate::bundle::Target::Main).
+#[derive(Clone, Debug, PartialEq, Derivative)]
+#[derivative(Default)]
+pub struct DrawParticlesDesc {
+    soft: bool,
+    #[derivative(Default(value = "0.5"))]
+    fade_distance: f32,
+}
+
+impl DrawParticlesDesc {
+    /// Create instance of `DrawParticles` render group
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Fade particles out over `fade_distance` in front of the surfaces of the sampled depth
+    /// image, instead of testing them against the depth attachment.
+    pub fn soft(mut self, fade_distance: f32) -> Self {
+        self.soft = true;
+        self.fade_distance = fade_distance;
+        self
+    }
+}
+
+impl<B: Backend> RenderGroupDesc<B, World> for DrawParticlesDesc {
+    fn images(&self) -> Vec<ImageAccess> {
+        if self.soft {
+            vec![post_process_input_access()]
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn depth(&self) -> bool {
+        !self.soft
+    }
+
+    fn build(
+        self,
+        ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        _queue: QueueId,
+        world: &World,
+        framebuffer_width: u32,
+        framebuffer_height: u32,
+        subpass: hal::pass::Subpass<'_, B>,
+        _buffers: Vec<NodeBuffer>,
+        images: Vec<NodeImage>,
+    ) -> Result<Box<dyn RenderGroup<B, World>>, failure::Error> {
+        #[cfg(feature = "profiler")]
+        profile_scope!("build");
+
+        let env = DynamicUniform::new(
+            factory,
+            pso::ShaderStageFlags::VERTEX | pso::ShaderStageFlags::FRAGMENT,
+        )?;
+        let textures = TextureSub::new(factory)?;
+
+        let depth = match images.first() {
+            Some(image) if self.soft => Some(SoftDepth::new(ctx, factory, image)?),
+            _ => None,
+        };
+
+        let mut layouts = vec![env.raw_layout(), textures.raw_layout()];
+        layouts.extend(depth.iter().map(|depth| depth.layout.raw()));
+
+        let pipeline_cache = world.try_fetch::<PipelineCache<B>>();
+        let (pipeline, pipeline_layout) = build_particle_pipeline(
+            factory,
+            pipeline_cache.as_ref().and_then(|cache| cache.raw()),
+            subpass,
+            framebuffer_width,
+            framebuffer_height,
+            depth.is_some(),
+            layouts,
+        )?;
+
+        Ok(Box::new(DrawParticles::<B> {
+            pipeline,
+            pipeline_layout,
+            env,
+            textures,
+            depth,
+            fade_distance: self.fade_distance,
+            vertex: DynamicVertexBuffer::new(),
+            particles: Default::default(),
+        }))
+    }
+}
+
+/// Depth image sampled by soft particles.
+#[derive(Debug)]
+struct SoftDepth<B: Backend> {
+    layout: RendyHandle<DescriptorSetLayout<B>>,
+    set: Escape<DescriptorSet<B>>,
+    _view: Escape<ImageView<B>>,
+    _sampler: RendyHandle<Sampler<B>>,
+}
+
+impl<B: Backend> SoftDepth<B> {
+    fn new(
+        ctx: &GraphContext<B>,
+        factory: &Factory<B>,
+        image: &NodeImage,
+    ) -> Result<Self, failure::Error> {
+        let layout: RendyHandle<DescriptorSetLayout<B>> = set_layout! {
+            factory,
+            [1] CombinedImageSampler pso::ShaderStageFlags::FRAGMENT
+        };
+        let view = input_view(ctx, factory, image)?;
+        let sampler = factory.get_sampler(SamplerInfo::new(Filter::Nearest, WrapMode::Clamp))?;
+        let set = factory.create_descriptor_set(layout.clone())?;
+        unsafe {
+            factory.write_descriptor_sets(Some(util::desc_write(
+                set.raw(),
+                0,
+                pso::Descriptor::CombinedImageSampler(
+                    view.raw(),
+                    hal::image::Layout::ShaderReadOnlyOptimal,
+                    sampler.raw(),
+                ),
+            )));
+        }
+        Ok(Self {
+            layout,
+            set,
+            _view: view,
+            _sampler: sampler,
+        })
+    }
+}
+
+/// Draws particles back to front, batched by texture.
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""))]
+pub struct DrawParticles<B: Backend> {
+    pipeline: B::GraphicsPipeline,
+    pipeline_layout: B::PipelineLayout,
+    env: DynamicUniform<B, ViewArgs>,
+    textures: TextureSub<B>,
+    depth: Option<SoftDepth<B>>,
+    fade_distance: f32,
+    vertex: DynamicVertexBuffer<B, ParticleArgs>,
+    particles: OrderedOneLevelBatch<TextureId, ParticleArgs>,
+}
+
+impl<B: Backend> RenderGroup<B, World> for DrawParticles<B> {
+    fn prepare(
+        &mut self,
+        factory: &Factory<B>,
+        _queue: QueueId,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        world: &World,
+    ) -> PrepareResult {
+        #[cfg(feature = "profiler")]
+        profile_scope!("prepare");
+
+        let (emitters, hiddens, hidden_props) = <(
+            ReadStorage<'_, ParticleEmitter>,
+            ReadStorage<'_, Hidden>,
+            ReadStorage<'_, HiddenPropagate>,
+        )>::fetch(world);
+
+        let camera = CameraGatherer::gather(world);
+        self.env.write(factory, index, camera.projview);
+
+        let mut sorted = Vec::new();
+        for (emitter, _, _) in (&emitters, !&hiddens, !&hidden_props).join() {
+            if emitter.particles().is_empty() {
+                continue;
+            }
+            let tex_id = match self.textures.insert(
+                factory,
+                world,
+                &emitter.texture,
+                hal::image::Layout::ShaderReadOnlyOptimal,
+            ) {
+                Some((tex_id, _)) => tex_id,
+                None => continue,
+            };
+            sorted.extend(emitter.particles().iter().map(|particle| {
+                (
+                    (camera.view * particle.position.push(1.0)).z,
+                    tex_id,
+                    ParticleArgs::from_particle(particle),
+                )
+            }));
+        }
+        self.textures.maintain(factory, world);
+
+        self.particles.swap_clear();
+        self.particles.insert_back_to_front(sorted);
+
+        self.vertex.write(
+            factory,
+            index,
+            self.particles.count() as u64,
+            Some(self.particles.data()),
+        );
+
+        PrepareResult::DrawRecord
+    }
+
+    fn draw_inline(
+        &mut self,
+        mut encoder: RenderPassEncoder<'_, B>,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        world: &World,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("draw");
+
+        if self.particles.count() == 0 {
+            return;
+        }
+
+        let layout = &self.pipeline_layout;
+        encoder.bind_graphics_pipeline(&self.pipeline);
+        self.env.bind(index, layout, 0, &mut encoder);
+        if let Some(depth) = &self.depth {
+            unsafe {
+                encoder.bind_graphics_descriptor_sets(
+                    layout,
+                    2,
+                    Some(depth.set.raw()),
+                    std::iter::empty(),
+                );
+                encoder.push_constants(
+                    layout,
+                    pso::ShaderStageFlags::FRAGMENT,
+                    0,
+                    &[self.fade_distance.to_bits()],
+                );
+            }
+        }
+        self.vertex.bind(index, 0, 0, &mut encoder);
+        let mut draws = DrawCounter::fetch(world);
+        for (&tex, range) in self.particles.iter() {
+            if self.textures.loaded(tex) {
+                self.textures.bind(layout, 1, tex, &mut encoder);
+                let instances = range.end - range.start;
+                unsafe {
+                    encoder.draw(0..4, range);
+                }
+                draws.draw_quads(instances);
+            }
+        }
+    }
+
+    fn dispose(self: Box<Self>, factory: &mut Factory<B>, _world: &World) {
+        unsafe {
+            factory.device().destroy_graphics_pipeline(self.pipeline);
+            factory
+                .device()
+                .destroy_pipeline_layout(self.pipeline_layout);
+        }
+    }
+}
+
+fn build_particle_pipeline<B: Backend>(
+    factory: &Factory<B>,
+    cache: Option<&B::PipelineCache>,
+    subpass: hal::pass::Subpass<'_, B>,
+    framebuffer_width: u32,
+    framebuffer_height: u32,
+    soft: bool,
+    layouts: Vec<&B::DescriptorSetLayout>,
+) -> Result<(B::GraphicsPipeline, B::PipelineLayout), failure::Error> {
+    let push_constants = if soft {
+        Some((pso::ShaderStageFlags::FRAGMENT, 0..4))
+    } else {
+        None
+    };
+    let pipeline_layout = unsafe {
+        factory
+            .device()
+            .create_pipeline_layout(layouts, push_constants)
+    }?;
+
+    let shader_vertex = unsafe { super::PARTICLE_VERTEX.module(factory).unwrap() };
+    let shader_fragment = if soft {
+        unsafe { super::PARTICLE_SOFT_FRAGMENT.module(factory).unwrap() }
+    } else {
+        unsafe { super::PARTICLE_FRAGMENT.module(factory).unwrap() }
+    };
+
+    let mut pipe_desc = PipelineDescBuilder::new()
+        .with_vertex_desc(&[(ParticleArgs::vertex(), pso::VertexInputRate::Instance(1))])
+        .with_input_assembler(pso::InputAssemblerDesc::new(hal::Primitive::TriangleStrip))
+        .with_shaders(util::simple_shader_set(
+            &shader_vertex,
+            Some(&shader_fragment),
+        ))
+        .with_layout(&pipeline_layout)
+        .with_subpass(subpass)
+        .with_framebuffer_size(framebuffer_width, framebuffer_height)
+        .with_blend_targets(vec![pso::ColorBlendDesc {
+            mask: pso::ColorMask::ALL,
+            blend: Some(pso::BlendState::PREMULTIPLIED_ALPHA),
+        }]);
+    if !soft {
+        pipe_desc = pipe_desc.with_depth_test(pso::DepthTest {
+            fun: pso::Comparison::Greater,
+            write: false,
+        });
+    }
+
+    let pipes = PipelinesBuilder::new()
+        .with_pipeline(pipe_desc)
+        .build_cached(factory, cache);
+
+    unsafe {
+        factory.destroy_shader_module(shader_vertex);
+        factory.destroy_shader_module(shader_fragment);
+    }
+
+    match pipes {
+        Err(e) => {
+            unsafe {
+                factory.device().destroy_pipeline_layout(pipeline_layout);
+            }
+            Err(e)
+        }
+        Ok(mut pipes) => Ok((pipes.remove(0), pipeline_layout)),
+    }
+}
@@ -1,9 +1,6 @@
 use super::base_3d::*;
-use crate::{mtl::FullTextureSet, skinning::JointCombined};
-use rendy::{
-    mesh::{AsVertex, Normal, Position, Tangent, TexCoord, VertexFormat},
-    shader::SpirvShader,
-};
+use crate::{mtl::FullTextureSet, shader_reload::ReloadableShader, skinning::JointCombined};
+use rendy::mesh::{AsVertex, Normal, Position, Tangent, TexCoord, VertexFormat};
 
 /// Implementation of `Base3DPassDef` for Physically-based (PBR) rendering pass.
 #[derive(Debug)]
@@ -11,15 +8,36 @@ pub struct PbrPassDef;
 impl Base3DPassDef for PbrPassDef {
     const NAME: &'static str = "Pbr";
     type TextureSet = FullTextureSet;
-    fn vertex_shader() -> &'static SpirvShader {
+    fn vertex_shader() -> &'static ReloadableShader {
         &super::POS_NORM_TANG_TEX_VERTEX
     }
-    fn vertex_skinned_shader() -> &'static SpirvShader {
+    fn vertex_skinned_shader() -> &'static ReloadableShader {
         &super::POS_NORM_TANG_TEX_SKIN_VERTEX
     }
-    fn fragment_shader() -> &'static SpirvShader {
+    fn vertex_morph_shader() -> Option<&'static ReloadableShader> {
+        Some(&super::POS_NORM_TANG_TEX_MORPH_VERTEX)
+    }
+    fn fragment_shader() -> &'static ReloadableShader {
         &super::PBR_FRAGMENT
     }
+    fn oit_fragment_shader() -> Option<&'static ReloadableShader> {
+        Some(&super::PBR_OIT_FRAGMENT)
+    }
+    fn shadowed_fragment_shader() -> Option<&'static ReloadableShader> {
+        Some(&super::PBR_SHADOWED_FRAGMENT)
+    }
+    fn debug_fragment_shader() -> Option<&'static ReloadableShader> {
+        Some(&super::DEBUG_POS_NORM_TANG_TEX_FRAGMENT)
+    }
+    fn uses_environment_map() -> bool {
+        true
+    }
+    fn uses_ambient_occlusion() -> bool {
+        true
+    }
+    fn uses_lightmap_coords() -> bool {
+        true
+    }
     fn base_format() -> Vec<VertexFormat> {
         vec![
             Position::vertex(),
@@ -0,0 +1,780 @@
+use crate::{
+    batch::{GroupIterator, OneLevelBatch},
+    picking::{pick_matrix, Picker},
+    pipeline::{PipelineCache, PipelineDescBuilder, PipelinesBuilder},
+    pod::{PickingArgs, PickingVertexArgs, SpriteArgs, SpritePickingArgs},
+    resources::Tint,
+    skinning::{JointCombined, JointTransforms},
+    sprite::{SpriteRender, SpriteSheet},
+    stats::DrawCounter,
+    submodules::{
+        gather::CameraGatherer, DynamicUniform, DynamicVertexBuffer, SkinningSub, TextureId,
+        TextureSub,
+    },
+    types::{Backend, Mesh, Texture},
+    util,
+};
+use amethyst_assets::{AssetStorage, Handle};
+use amethyst_core::{
+    ecs::{Entities, Join, Read, ReadExpect, ReadStorage, SystemData, World, WriteExpect},
+    math::{convert, Matrix4, Vector3},
+    transform::Transform,
+    Hidden, HiddenPropagate,
+};
+use glsl_layout::*;
+use rendy::{
+    command::{
+        CommandBuffer, CommandPool, ExecutableState, Family, MultiShot, PendingState, QueueId,
+        RenderPassEncoder, SimultaneousUse, Submit, Transfer,
+    },
+    factory::Factory,
+    frame::Frames,
+    graph::{
+        gfx_acquire_barriers, gfx_release_barriers,
+        render::{PrepareResult, RenderGroup, RenderGroupDesc},
+        GraphContext, ImageAccess, Node, NodeBuffer, NodeDesc, NodeImage, NodeSubmittable,
+    },
+    hal::{self, device::Device, pso},
+    memory::Download,
+    mesh::{AsVertex, Position, VertexFormat},
+    resource::{Buffer, BufferInfo, Escape},
+    shader::Shader,
+};
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+/// Draw ids of the meshes, skinned meshes and sprites at the pixel requested by the [Picker].
+///
+/// The target is expected to be a single pixel with an `R32Uint` color output and depth,
+/// which the requested pixel of a `width` by `height` image from the active camera is
+/// projected onto.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DrawPickingDesc {
+    width: u32,
+    height: u32,
+}
+
+impl DrawPickingDesc {
+    /// Create instance of `DrawPicking` render group picking in a `width` by `height` image.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self { width, height }
+    }
+}
+
+impl<B: Backend> RenderGroupDesc<B, World> for DrawPickingDesc {
+    fn build(
+        self,
+        _ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        _queue: QueueId,
+        aux: &World,
+        framebuffer_width: u32,
+        framebuffer_height: u32,
+        subpass: hal::pass::Subpass<'_, B>,
+        _buffers: Vec<NodeBuffer>,
+        _images: Vec<NodeImage>,
+    ) -> Result<Box<dyn RenderGroup<B, World>>, failure::Error> {
+        #[cfg(feature = "profiler")]
+        profile_scope!("build");
+
+        let env = DynamicUniform::new(factory, pso::ShaderStageFlags::VERTEX)?;
+        let skinning = SkinningSub::new(factory)?;
+        let textures = TextureSub::new(factory)?;
+
+        let mut static_format = vec![Position::vertex()];
+        let mut skinned_format = vec![Position::vertex(), JointCombined::vertex()];
+        let pipeline_cache = aux.try_fetch::<PipelineCache<B>>();
+        let (mut mesh_pipelines, mesh_pipeline_layout) = build_mesh_picking_pipelines(
+            factory,
+            pipeline_cache.as_ref().and_then(|cache| cache.raw()),
+            subpass,
+            framebuffer_width,
+            framebuffer_height,
+            &static_format,
+            &skinned_format,
+            vec![env.raw_layout(), skinning.raw_layout()],
+        )?;
+        let (sprite_pipeline, sprite_pipeline_layout) = build_sprite_picking_pipeline(
+            factory,
+            pipeline_cache.as_ref().and_then(|cache| cache.raw()),
+            subpass,
+            framebuffer_width,
+            framebuffer_height,
+            vec![env.raw_layout(), textures.raw_layout()],
+        )?;
+        static_format.sort();
+        skinned_format.sort();
+
+        Ok(Box::new(DrawPicking::<B> {
+            static_pipeline: mesh_pipelines.remove(0),
+            skinned_pipeline: mesh_pipelines.remove(0),
+            mesh_pipeline_layout,
+            sprite_pipeline,
+            sprite_pipeline_layout,
+            env,
+            skinning,
+            textures,
+            static_batches: Default::default(),
+            skinned_batches: Default::default(),
+            sprite_batches: Default::default(),
+            static_models: DynamicVertexBuffer::new(),
+            skinned_models: DynamicVertexBuffer::new(),
+            sprites: DynamicVertexBuffer::new(),
+            static_format,
+            skinned_format,
+            width: self.width,
+            height: self.height,
+        }))
+    }
+}
+
+/// Draws ids of entities at the pixel requested by the [Picker], plus one so zero is no entity.
+///
+/// All meshes and sprites which aren't hidden are drawn, including transparent ones, and
+/// fully transparent texels of sprites are discarded. Nothing is drawn while no position
+/// is requested.
+#[derive(Debug)]
+pub struct DrawPicking<B: Backend> {
+    static_pipeline: B::GraphicsPipeline,
+    skinned_pipeline: B::GraphicsPipeline,
+    mesh_pipeline_layout: B::PipelineLayout,
+    sprite_pipeline: B::GraphicsPipeline,
+    sprite_pipeline_layout: B::PipelineLayout,
+    env: DynamicUniform<B, PickingArgs>,
+    skinning: SkinningSub<B>,
+    textures: TextureSub<B>,
+    static_batches: OneLevelBatch<u32, PickingVertexArgs>,
+    skinned_batches: OneLevelBatch<u32, PickingVertexArgs>,
+    sprite_batches: OneLevelBatch<TextureId, SpritePickingArgs>,
+    static_models: DynamicVertexBuffer<B, PickingVertexArgs>,
+    skinned_models: DynamicVertexBuffer<B, PickingVertexArgs>,
+    sprites: DynamicVertexBuffer<B, SpritePickingArgs>,
+    static_format: Vec<VertexFormat>,
+    skinned_format: Vec<VertexFormat>,
+    width: u32,
+    height: u32,
+}
+
+impl<B: Backend> RenderGroup<B, World> for DrawPicking<B> {
+    fn prepare(
+        &mut self,
+        factory: &Factory<B>,
+        _queue: QueueId,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        resources: &World,
+    ) -> PrepareResult {
+        #[cfg(feature = "profiler")]
+        profile_scope!("prepare");
+
+        let (
+            picker,
+            entities,
+            mesh_storage,
+            sprite_sheet_storage,
+            tex_storage,
+            meshes,
+            sprite_renders,
+            transforms,
+            joints,
+            tints,
+            hiddens,
+            hiddens_prop,
+        ) = <(
+            ReadExpect<'_, Picker>,
+            Entities<'_>,
+            Read<'_, AssetStorage<Mesh>>,
+            Read<'_, AssetStorage<SpriteSheet>>,
+            Read<'_, AssetStorage<Texture>>,
+            ReadStorage<'_, Handle<Mesh>>,
+            ReadStorage<'_, SpriteRender>,
+            ReadStorage<'_, Transform>,
+            ReadStorage<'_, JointTransforms>,
+            ReadStorage<'_, Tint>,
+            ReadStorage<'_, Hidden>,
+            ReadStorage<'_, HiddenPropagate>,
+        )>::fetch(resources);
+
+        self.static_batches.clear_inner();
+        self.skinned_batches.clear_inner();
+        self.sprite_batches.clear_inner();
+
+        if let Some((x, y)) = picker.position {
+            // Picked pixels are not jittered for temporal anti-aliasing.
+            let camera = CameraGatherer::gather(resources);
+            let unjitter =
+                Matrix4::new_translation(&Vector3::new(-camera.jitter.x, -camera.jitter.y, 0.0));
+            let proj_view: [[f32; 4]; 4] = (pick_matrix(x, y, self.width, self.height)
+                * unjitter
+                * camera.projection
+                * camera.view)
+                .into();
+            self.env.write(
+                factory,
+                index,
+                PickingArgs {
+                    proj_view: proj_view.into(),
+                }
+                .std140(),
+            );
+
+            let model = |transform: &Transform| -> mat4 {
+                let model: [[f32; 4]; 4] =
+                    convert::<_, Matrix4<f32>>(*transform.global_matrix()).into();
+                model.into()
+            };
+            let statics_ref = &mut self.static_batches;
+            (
+                &entities,
+                &meshes,
+                &transforms,
+                !&joints,
+                !&hiddens,
+                !&hiddens_prop,
+            )
+                .join()
+                .map(|(entity, mesh, transform, _, _, _)| {
+                    (
+                        mesh.id(),
+                        PickingVertexArgs {
+                            model: model(transform),
+                            entity: entity.id() + 1,
+                            joints_offset: 0,
+                        },
+                    )
+                })
+                .for_each_group(|mesh_id, data| {
+                    if mesh_storage.contains_id(mesh_id) {
+                        statics_ref.insert(mesh_id, data.drain(..));
+                    }
+                });
+
+            let skinning_ref = &mut self.skinning;
+            let skinned_ref = &mut self.skinned_batches;
+            (
+                &entities,
+                &meshes,
+                &transforms,
+                &joints,
+                !&hiddens,
+                !&hiddens_prop,
+            )
+                .join()
+                .map(|(entity, mesh, transform, joints, _, _)| {
+                    (
+                        mesh.id(),
+                        PickingVertexArgs {
+                            model: model(transform),
+                            entity: entity.id() + 1,
+                            joints_offset: skinning_ref.insert(joints),
+                        },
+                    )
+                })
+                .for_each_group(|mesh_id, data| {
+                    if mesh_storage.contains_id(mesh_id) {
+                        skinned_ref.insert(mesh_id, data.drain(..));
+                    }
+                });
+
+            let textures_ref = &mut self.textures;
+            let sprites_ref = &mut self.sprite_batches;
+            (
+                &entities,
+                &sprite_renders,
+                &transforms,
+                tints.maybe(),
+                !&hiddens,
+                !&hiddens_prop,
+            )
+                .join()
+                .filter_map(|(entity, sprite_render, transform, tint, _, _)| {
+                    let (sprite, texture) = SpriteArgs::from_data(
+                        &tex_storage,
+                        &sprite_sheet_storage,
+                        sprite_render,
+                        transform,
+                        tint,
+                    )?;
+                    let (tex_id, _) = textures_ref.insert(
+                        factory,
+                        resources,
+                        texture,
+                        hal::image::Layout::ShaderReadOnlyOptimal,
+                    )?;
+                    Some((
+                        tex_id,
+                        SpritePickingArgs {
+                            sprite,
+                            entity: entity.id() + 1,
+                        },
+                    ))
+                })
+                .for_each_group(|tex_id, data| sprites_ref.insert(tex_id, data.drain(..)));
+        }
+
+        self.textures.maintain(factory, resources);
+        self.static_batches.prune();
+        self.skinned_batches.prune();
+        self.sprite_batches.prune();
+
+        self.static_models.write(
+            factory,
+            index,
+            self.static_batches.count() as u64,
+            self.static_batches.data(),
+        );
+        self.skinned_models.write(
+            factory,
+            index,
+            self.skinned_batches.count() as u64,
+            self.skinned_batches.data(),
+        );
+        self.sprites.write(
+            factory,
+            index,
+            self.sprite_batches.count() as u64,
+            self.sprite_batches.data(),
+        );
+        self.skinning.commit(factory, index);
+
+        PrepareResult::DrawRecord
+    }
+
+    fn draw_inline(
+        &mut self,
+        mut encoder: RenderPassEncoder<'_, B>,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        resources: &World,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("draw");
+
+        let mesh_storage = <Read<'_, AssetStorage<Mesh>>>::fetch(resources);
+
+        let mut draws = DrawCounter::fetch(resources);
+        if self.static_batches.count() > 0 {
+            encoder.bind_graphics_pipeline(&self.static_pipeline);
+            self.env
+                .bind(index, &self.mesh_pipeline_layout, 0, &mut encoder);
+            if self
+                .static_models
+                .bind(index, self.static_format.len() as u32, 0, &mut encoder)
+            {
+                for (&mesh_id, range) in self.static_batches.iter() {
+                    if let Some(mesh) =
+                        B::unwrap_mesh(unsafe { mesh_storage.get_by_id_unchecked(mesh_id) })
+                    {
+                        // Meshes without positions can't be picked.
+                        let instances = range.end - range.start;
+                        if mesh
+                            .bind_and_draw(0, &self.static_format, range, &mut encoder)
+                            .is_ok()
+                        {
+                            draws.draw(mesh.primitive(), mesh.len(), instances);
+                        }
+                    }
+                }
+            }
+        }
+
+        if self.skinned_batches.count() > 0 {
+            encoder.bind_graphics_pipeline(&self.skinned_pipeline);
+            self.env
+                .bind(index, &self.mesh_pipeline_layout, 0, &mut encoder);
+            self.skinning
+                .bind(index, &self.mesh_pipeline_layout, 1, &mut encoder);
+            if self
+                .skinned_models
+                .bind(index, self.skinned_format.len() as u32, 0, &mut encoder)
+            {
+                for (&mesh_id, range) in self.skinned_batches.iter() {
+                    if let Some(mesh) =
+                        B::unwrap_mesh(unsafe { mesh_storage.get_by_id_unchecked(mesh_id) })
+                    {
+                        let instances = range.end - range.start;
+                        if mesh
+                            .bind_and_draw(0, &self.skinned_format, range, &mut encoder)
+                            .is_ok()
+                        {
+                            draws.draw(mesh.primitive(), mesh.len(), instances);
+                        }
+                    }
+                }
+            }
+        }
+
+        if self.sprite_batches.count() > 0 {
+            encoder.bind_graphics_pipeline(&self.sprite_pipeline);
+            self.env
+                .bind(index, &self.sprite_pipeline_layout, 0, &mut encoder);
+            self.sprites.bind(index, 0, 0, &mut encoder);
+            for (&tex, range) in self.sprite_batches.iter() {
+                if self.textures.loaded(tex) {
+                    self.textures
+                        .bind(&self.sprite_pipeline_layout, 1, tex, &mut encoder);
+                    let instances = range.end - range.start;
+                    unsafe {
+                        encoder.draw(0..4, range);
+                    }
+                    draws.draw_quads(instances);
+                }
+            }
+        }
+    }
+
+    fn dispose(self: Box<Self>, factory: &mut Factory<B>, _aux: &World) {
+        unsafe {
+            factory
+                .device()
+                .destroy_graphics_pipeline(self.static_pipeline);
+            factory
+                .device()
+                .destroy_graphics_pipeline(self.skinned_pipeline);
+            factory
+                .device()
+                .destroy_graphics_pipeline(self.sprite_pipeline);
+            factory
+                .device()
+                .destroy_pipeline_layout(self.mesh_pipeline_layout);
+            factory
+                .device()
+                .destroy_pipeline_layout(self.sprite_pipeline_layout);
+        }
+    }
+}
+
+fn picking_pipeline_desc<'a, B: Backend>(
+    subpass: hal::pass::Subpass<'a, B>,
+    framebuffer_width: u32,
+    framebuffer_height: u32,
+    pipeline_layout: &'a B::PipelineLayout,
+) -> PipelineDescBuilder<'a, B> {
+    PipelineDescBuilder::new()
+        .with_layout(pipeline_layout)
+        .with_subpass(subpass)
+        .with_framebuffer_size(framebuffer_width, framebuffer_height)
+        .with_depth_test(pso::DepthTest {
+            fun: pso::Comparison::Greater,
+            write: true,
+        })
+        .with_blend_targets(vec![pso::ColorBlendDesc {
+            mask: pso::ColorMask::ALL,
+            blend: None,
+        }])
+}
+
+fn build_mesh_picking_pipelines<B: Backend>(
+    factory: &Factory<B>,
+    cache: Option<&B::PipelineCache>,
+    subpass: hal::pass::Subpass<'_, B>,
+    framebuffer_width: u32,
+    framebuffer_height: u32,
+    static_format: &[VertexFormat],
+    skinned_format: &[VertexFormat],
+    layouts: Vec<&B::DescriptorSetLayout>,
+) -> Result<(Vec<B::GraphicsPipeline>, B::PipelineLayout), failure::Error> {
+    let pipeline_layout = unsafe {
+        factory
+            .device()
+            .create_pipeline_layout(layouts, None as Option<(_, _)>)
+    }?;
+
+    let shader_vertex = unsafe { super::PICKING_VERTEX.module(factory).unwrap() };
+    let shader_skin_vertex = unsafe { super::PICKING_SKIN_VERTEX.module(factory).unwrap() };
+    let shader_fragment = unsafe { super::PICKING_FRAGMENT.module(factory).unwrap() };
+
+    let vertex_desc = |formats: &[VertexFormat]| {
+        formats
+            .iter()
+            .map(|f| (f.clone(), pso::VertexInputRate::Vertex))
+            .chain(Some((
+                PickingVertexArgs::vertex(),
+                pso::VertexInputRate::Instance(1),
+            )))
+            .collect::<Vec<_>>()
+    };
+    let static_desc = vertex_desc(static_format);
+    let skinned_desc = vertex_desc(skinned_format);
+
+    let pipes = PipelinesBuilder::new()
+        .with_pipeline(
+            picking_pipeline_desc(
+                subpass,
+                framebuffer_width,
+                framebuffer_height,
+                &pipeline_layout,
+            )
+            .with_vertex_desc(&static_desc)
+            .with_shaders(util::simple_shader_set(
+                &shader_vertex,
+                Some(&shader_fragment),
+            )),
+        )
+        .with_pipeline(
+            picking_pipeline_desc(
+                subpass,
+                framebuffer_width,
+                framebuffer_height,
+                &pipeline_layout,
+            )
+            .with_vertex_desc(&skinned_desc)
+            .with_shaders(util::simple_shader_set(
+                &shader_skin_vertex,
+                Some(&shader_fragment),
+            )),
+        )
+        .build_cached(factory, cache);
+
+    unsafe {
+        factory.destroy_shader_module(shader_vertex);
+        factory.destroy_shader_module(shader_skin_vertex);
+        factory.destroy_shader_module(shader_fragment);
+    }
+
+    match pipes {
+        Err(e) => {
+            unsafe {
+                factory.device().destroy_pipeline_layout(pipeline_layout);
+            }
+            Err(e)
+        }
+        Ok(pipes) => Ok((pipes, pipeline_layout)),
+    }
+}
+
+fn build_sprite_picking_pipeline<B: Backend>(
+    factory: &Factory<B>,
+    cache: Option<&B::PipelineCache>,
+    subpass: hal::pass::Subpass<'_, B>,
+    framebuffer_width: u32,
+    framebuffer_height: u32,
+    layouts: Vec<&B::DescriptorSetLayout>,
+) -> Result<(B::GraphicsPipeline, B::PipelineLayout), failure::Error> {
+    let pipeline_layout = unsafe {
+        factory
+            .device()
+            .create_pipeline_layout(layouts, None as Option<(_, _)>)
+    }?;
+
+    let shader_vertex = unsafe { super::SPRITE_PICKING_VERTEX.module(factory).unwrap() };
+    let shader_fragment = unsafe { super::SPRITE_PICKING_FRAGMENT.module(factory).unwrap() };
+
+    let pipes = PipelinesBuilder::new()
+        .with_pipeline(
+            picking_pipeline_desc(
+                subpass,
+                framebuffer_width,
+                framebuffer_height,
+                &pipeline_layout,
+            )
+            .with_vertex_desc(&[(
+                SpritePickingArgs::vertex(),
+                pso::VertexInputRate::Instance(1),
+            )])
+            .with_input_assembler(pso::InputAssemblerDesc::new(hal::Primitive::TriangleStrip))
+            .with_shaders(util::simple_shader_set(
+                &shader_vertex,
+                Some(&shader_fragment),
+            )),
+        )
+        .build_cached(factory, cache);
+
+    unsafe {
+        factory.destroy_shader_module(shader_vertex);
+        factory.destroy_shader_module(shader_fragment);
+    }
+
+    match pipes {
+        Err(e) => {
+            unsafe {
+                factory.device().destroy_pipeline_layout(pipeline_layout);
+            }
+            Err(e)
+        }
+        Ok(mut pipes) => Ok((pipes.remove(0), pipeline_layout)),
+    }
+}
+
+/// Describe reading entity ids drawn by `DrawPicking` back into the [Picker].
+#[derive(Debug, Default)]
+pub struct PickingReadbackDesc;
+
+impl PickingReadbackDesc {
+    /// Create instance of `PickingReadback` node
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl<B: Backend> NodeDesc<B, World> for PickingReadbackDesc {
+    type Node = PickingReadback<B>;
+
+    fn images(&self) -> Vec<ImageAccess> {
+        vec![ImageAccess {
+            access: hal::image::Access::TRANSFER_READ,
+            usage: hal::image::Usage::TRANSFER_SRC,
+            layout: hal::image::Layout::TransferSrcOptimal,
+            stages: pso::PipelineStage::TRANSFER,
+        }]
+    }
+
+    fn build<'a>(
+        self,
+        ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        family: &mut Family<B>,
+        _queue: usize,
+        _aux: &World,
+        buffers: Vec<NodeBuffer>,
+        images: Vec<NodeImage>,
+    ) -> Result<Self::Node, failure::Error> {
+        #[cfg(feature = "profiler")]
+        profile_scope!("build");
+
+        let image = ctx
+            .get_image(images[0].id)
+            .ok_or_else(|| failure::format_err!("Picking image is not available"))?;
+
+        let mut command_pool = factory
+            .create_command_pool(family)?
+            .with_capability::<Transfer>()
+            .expect("Graph builder must provide family with Transfer capability");
+
+        // Each frame in flight copies into its own buffer, read once the frame has completed.
+        let mut frames = Vec::new();
+        for command_buffer in command_pool.allocate_buffers(ctx.frames_in_flight as usize) {
+            let buffer = factory.create_buffer(
+                BufferInfo {
+                    size: std::mem::size_of::<u32>() as u64,
+                    usage: hal::buffer::Usage::TRANSFER_DST,
+                },
+                Download,
+            )?;
+
+            let mut recording = command_buffer.begin(MultiShot(SimultaneousUse), ());
+            unsafe {
+                let (stages, barriers) = gfx_acquire_barriers(ctx, &buffers, &images);
+                recording.encoder().pipeline_barrier(
+                    stages,
+                    hal::memory::Dependencies::empty(),
+                    barriers,
+                );
+                // Copies from images to buffers aren't exposed by the encoder.
+                hal::command::RawCommandBuffer::copy_image_to_buffer(
+                    recording.raw(),
+                    image.raw(),
+                    images[0].layout,
+                    buffer.raw(),
+                    Some(hal::command::BufferImageCopy {
+                        buffer_offset: 0,
+                        buffer_width: 0,
+                        buffer_height: 0,
+                        image_layers: hal::image::SubresourceLayers {
+                            aspects: hal::format::Aspects::COLOR,
+                            level: 0,
+                            layers: 0..1,
+                        },
+                        image_offset: hal::image::Offset::ZERO,
+                        image_extent: hal::image::Extent {
+                            width: 1,
+                            height: 1,
+                            depth: 1,
+                        },
+                    }),
+                );
+                let mut encoder = recording.encoder();
+                encoder.pipeline_barrier(
+                    pso::PipelineStage::TRANSFER..pso::PipelineStage::HOST,
+                    hal::memory::Dependencies::empty(),
+                    Some(hal::memory::Barrier::whole_buffer(
+                        buffer.raw(),
+                        hal::buffer::Access::TRANSFER_WRITE..hal::buffer::Access::HOST_READ,
+                    )),
+                );
+                let (stages, barriers) = gfx_release_barriers(ctx, &buffers, &images);
+                encoder.pipeline_barrier(stages, hal::memory::Dependencies::empty(), barriers);
+            }
+
+            let (submit, command_buffer) = recording.finish().submit();
+            frames.push(PickingReadbackFrame {
+                buffer,
+                position: None,
+                command_buffer,
+                submit,
+            });
+        }
+
+        Ok(PickingReadback {
+            command_pool,
+            frames,
+        })
+    }
+}
+
+#[derive(Debug)]
+struct PickingReadbackFrame<B: Backend> {
+    buffer: Escape<Buffer<B>>,
+    // Position drawn into the image copied to `buffer`.
+    position: Option<(u32, u32)>,
+    command_buffer:
+        CommandBuffer<B, Transfer, PendingState<ExecutableState<MultiShot<SimultaneousUse>>>>,
+    submit: Submit<B, SimultaneousUse>,
+}
+
+/// Copies the picked entity id into a buffer every frame, and passes the id copied by the
+/// last completed frame to the [Picker].
+#[derive(Debug)]
+pub struct PickingReadback<B: Backend> {
+    command_pool: CommandPool<B, Transfer>,
+    frames: Vec<PickingReadbackFrame<B>>,
+}
+
+impl<'a, B: Backend> NodeSubmittable<'a, B> for PickingReadback<B> {
+    type Submittable = &'a Submit<B, SimultaneousUse>;
+    type Submittables = &'a [Submit<B, SimultaneousUse>];
+}
+
+impl<B: Backend> Node<B, World> for PickingReadback<B> {
+    type Capability = Transfer;
+    type Desc = PickingReadbackDesc;
+
+    fn run<'a>(
+        &'a mut self,
+        _ctx: &GraphContext<B>,
+        factory: &Factory<B>,
+        aux: &World,
+        frames: &'a Frames<B>,
+    ) -> &'a [Submit<B, SimultaneousUse>] {
+        #[cfg(feature = "profiler")]
+        profile_scope!("run");
+
+        let (entities, mut picker) = <(Entities<'_>, WriteExpect<'_, Picker>)>::fetch(aux);
+
+        // The graph waits for the frame that last used this slot before running.
+        let index = frames.next().index() as usize % self.frames.len();
+        let frame = &mut self.frames[index];
+        if let Some(position) = frame.position {
+            let range = 0..frame.buffer.size();
+            let mut mapped = frame.buffer.map(factory.device(), range.clone()).unwrap();
+            let id = unsafe { mapped.read::<u32>(factory.device(), range).unwrap()[0] };
+            let entity = id
+                .checked_sub(1)
+                .map(|id| entities.entity(id))
+                .filter(|&entity| entities.is_alive(entity));
+            picker.picked = Some((position, entity));
+        }
+        frame.position = picker.position;
+
+        std::slice::from_ref(&frame.submit)
+    }
+
+    unsafe fn dispose(mut self, factory: &mut Factory<B>, _aux: &World) {
+        for frame in self.frames.drain(..) {
+            self.command_pool
+                .free_buffers(Some(frame.command_buffer.mark_complete()));
+        }
+        factory.destroy_command_pool(self.command_pool);
+    }
+}
@@ -0,0 +1,739 @@
+use crate::{
+    bundle::{PostProcessPlugin, PostProcessStage, RenderOrder, RenderPlan, TargetImage},
+    pipeline::{PipelineCache, PipelineDescBuilder, PipelinesBuilder},
+    shader_reload::ReloadableShader,
+    stats::DrawCounter,
+    submodules::{EnvironmentMapSub, EnvironmentSub, TextureId, TextureSub},
+    types::{Backend, Texture},
+    util,
+};
+use amethyst_assets::Handle;
+use amethyst_core::ecs::World;
+use amethyst_error::Error;
+use derivative::Derivative;
+use rendy::{
+    command::{QueueId, RenderPassEncoder},
+    factory::{Factory, ImageState},
+    graph::{
+        render::{PrepareResult, RenderGroup, RenderGroupDesc},
+        BufferAccess, GraphContext, ImageAccess, NodeBuffer, NodeImage,
+    },
+    hal::{self, device::Device, format::Swizzle, pso},
+    resource::{
+        DescriptorSet, DescriptorSetLayout, Escape, Filter, Handle as RendyHandle, ImageView,
+        ImageViewInfo, Sampler, SamplerInfo, ViewKind, WrapMode,
+    },
+    shader::Shader,
+    texture::{pixel::Rgba8Unorm, Texture as RendyTexture, TextureBuilder},
+};
+use std::sync::Arc;
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+/// Maximum number of `f32` push constants available to post-processing fragment shaders.
+pub const MAX_POST_PROCESS_CONSTANTS: usize = 32;
+
+type ConstantsFn = Arc<dyn Fn(&World) -> Vec<f32> + Send + Sync>;
+type TextureFn = Arc<dyn Fn(&World) -> Option<Handle<Texture>> + Send + Sync>;
+
+/// Describe a fullscreen pass sampling images rendered by other targets.
+///
+/// The vertex shader draws a single triangle covering the framebuffer and passes
+/// texture coordinates in `location = 0`. Input images are bound as `sampler2D`s
+/// to `set = 0`, in the order they were added with `with_image`, depth images through
+/// their depth aspect. Values returned by
+/// the closure set with `with_constants` are pushed as fragment shader push constants.
+///
+/// Storage buffers added with `with_buffer` are bound after the images.
+/// With `with_environment`, the lit environment and environment cube maps of 3d passes
+/// are bound to sets 1 and 2. A texture asset set with `with_texture` is bound to
+/// the following set.
+///
+/// Used directly as a [PostProcessPlugin], binding 0 samples the previous step of the
+/// post-processing chain, followed by images registered with `with_input`.
+///
+/// With `with_viewport`, the triangle covers only a rectangle of the framebuffer and
+/// texture coordinates span that rectangle.
+#[derive(Clone, Derivative)]
+#[derivative(Debug)]
+pub struct DrawPostProcessDesc {
+    fragment: ReloadableShader,
+    inputs: Vec<TargetImage>,
+    sampled_images: usize,
+    storage_buffers: usize,
+    environment: bool,
+    viewport: Option<pso::Rect>,
+    depth: bool,
+    #[derivative(Debug = "ignore")]
+    constants: Option<ConstantsFn>,
+    #[derivative(Debug = "ignore")]
+    texture: Option<TextureFn>,
+}
+
+impl DrawPostProcessDesc {
+    /// Create instance of `DrawPostProcess` render group running given fragment shader
+    pub fn new(fragment: impl Into<ReloadableShader>) -> Self {
+        Self {
+            fragment: fragment.into(),
+            inputs: Vec::new(),
+            sampled_images: 0,
+            storage_buffers: 0,
+            environment: false,
+            viewport: None,
+            depth: false,
+            constants: None,
+            texture: None,
+        }
+    }
+
+    /// Create instance of `DrawPostProcess` render group copying its input unchanged
+    pub fn passthrough() -> Self {
+        Self::new(super::PASSTHROUGH_FRAGMENT.clone())
+    }
+
+    /// Sample an additional image produced by another render target.
+    ///
+    /// Used as a [PostProcessPlugin], the image is retrieved from the plan. Otherwise it
+    /// must also be passed to the group builder with `with_image`.
+    pub fn with_input(mut self, image: TargetImage) -> Self {
+        self.inputs.push(image);
+        self
+    }
+
+    /// Sample an additional image that isn't the output of a render target,
+    /// bound after the images registered with `with_input`.
+    ///
+    /// The image must be passed to the group builder with `with_image`.
+    pub fn with_sampled_image(mut self) -> Self {
+        self.sampled_images += 1;
+        self
+    }
+
+    /// Read a storage buffer in the fragment shader.
+    ///
+    /// The buffer must be passed to the group builder with `with_buffer`.
+    pub fn with_storage_buffer(mut self) -> Self {
+        self.storage_buffers += 1;
+        self
+    }
+
+    /// Bind the environment set of 3d passes to set 1, with view matrices and lights
+    /// visible to the fragment shader, and the `EnvironmentMap` cube maps to set 2.
+    pub fn with_environment(mut self) -> Self {
+        self.environment = true;
+        self
+    }
+
+    /// Draw only into the given rectangle of the framebuffer, in pixels.
+    pub fn with_viewport(mut self, rect: pso::Rect) -> Self {
+        self.viewport = Some(rect);
+        self
+    }
+
+    /// Allow adding the group to targets with a depth output, which it neither tests
+    /// nor writes.
+    pub fn with_depth_target(mut self) -> Self {
+        self.depth = true;
+        self
+    }
+
+    /// Push constants computed from the world every frame.
+    ///
+    /// At most `MAX_POST_PROCESS_CONSTANTS` values are used.
+    pub fn with_constants(
+        mut self,
+        constants: impl Fn(&World) -> Vec<f32> + Send + Sync + 'static,
+    ) -> Self {
+        self.constants = Some(Arc::new(constants));
+        self
+    }
+
+    /// Sample the texture asset returned by the closure every frame, bound to the set
+    /// following the images and environment sets.
+    ///
+    /// A 1x1 black texture is bound while there is none or it is still loading.
+    pub fn with_texture(
+        mut self,
+        texture: impl Fn(&World) -> Option<Handle<Texture>> + Send + Sync + 'static,
+    ) -> Self {
+        self.texture = Some(Arc::new(texture));
+        self
+    }
+}
+
+/// Access of an image sampled by a post-processing fragment shader.
+pub fn post_process_input_access() -> ImageAccess {
+    ImageAccess {
+        access: hal::image::Access::SHADER_READ,
+        usage: hal::image::Usage::SAMPLED,
+        layout: hal::image::Layout::ShaderReadOnlyOptimal,
+        stages: pso::PipelineStage::FRAGMENT_SHADER,
+    }
+}
+
+impl<B: Backend> RenderGroupDesc<B, World> for DrawPostProcessDesc {
+    fn buffers(&self) -> Vec<BufferAccess> {
+        vec![
+            BufferAccess {
+                access: hal::buffer::Access::SHADER_READ,
+                usage: hal::buffer::Usage::STORAGE,
+                stages: pso::PipelineStage::FRAGMENT_SHADER,
+            };
+            self.storage_buffers
+        ]
+    }
+
+    fn images(&self) -> Vec<ImageAccess> {
+        vec![post_process_input_access(); 1 + self.inputs.len() + self.sampled_images]
+    }
+
+    fn depth(&self) -> bool {
+        self.depth
+    }
+
+    fn build(
+        self,
+        ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        queue: QueueId,
+        aux: &World,
+        framebuffer_width: u32,
+        framebuffer_height: u32,
+        subpass: hal::pass::Subpass<'_, B>,
+        buffers: Vec<NodeBuffer>,
+        images: Vec<NodeImage>,
+    ) -> Result<Box<dyn RenderGroup<B, World>>, failure::Error> {
+        #[cfg(feature = "profiler")]
+        profile_scope!("build");
+
+        let layout: RendyHandle<DescriptorSetLayout<B>> = set_layout! {
+            factory,
+            [images.len()] CombinedImageSampler pso::ShaderStageFlags::FRAGMENT,
+            [buffers.len()] StorageBuffer pso::ShaderStageFlags::FRAGMENT
+        };
+
+        let views = images
+            .iter()
+            .map(|image| input_view(ctx, factory, image))
+            .collect::<Result<Vec<_>, _>>()?;
+        let sampler = factory.get_sampler(SamplerInfo::new(Filter::Linear, WrapMode::Clamp))?;
+
+        let storage_buffers = buffers
+            .iter()
+            .map(|buffer| {
+                ctx.get_buffer(buffer.id).ok_or_else(|| {
+                    failure::format_err!("Post-process storage buffer is not available")
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let set = factory.create_descriptor_set(layout.clone())?;
+        unsafe {
+            factory.write_descriptor_sets(
+                views
+                    .iter()
+                    .map(|view| {
+                        pso::Descriptor::CombinedImageSampler(
+                            view.raw(),
+                            hal::image::Layout::ShaderReadOnlyOptimal,
+                            sampler.raw(),
+                        )
+                    })
+                    .chain(
+                        storage_buffers
+                            .iter()
+                            .map(|buffer| pso::Descriptor::Buffer(buffer.raw(), None..None)),
+                    )
+                    .enumerate()
+                    .map(|(binding, desc)| util::desc_write(set.raw(), binding as u32, desc))
+                    .collect::<Vec<_>>(),
+            );
+        }
+
+        let environment = if self.environment {
+            Some((
+                EnvironmentSub::new(
+                    factory,
+                    [
+                        pso::ShaderStageFlags::FRAGMENT,
+                        pso::ShaderStageFlags::FRAGMENT,
+                    ],
+                )?,
+                EnvironmentMapSub::new(factory, queue)?,
+            ))
+        } else {
+            None
+        };
+
+        let texture = match self.texture {
+            Some(texture) => Some(PostProcessTexture::new(factory, queue, texture)?),
+            None => None,
+        };
+
+        let mut layouts = vec![layout.raw()];
+        if let Some((env, environment_map)) = &environment {
+            layouts.push(env.raw_layout());
+            layouts.push(environment_map.raw_layout());
+        }
+        if let Some(texture) = &texture {
+            layouts.push(texture.textures.raw_layout());
+        }
+        let pipeline_cache = aux.try_fetch::<PipelineCache<B>>();
+        let (pipeline, pipeline_layout) = build_post_process_pipeline(
+            factory,
+            pipeline_cache.as_ref().and_then(|cache| cache.raw()),
+            subpass,
+            framebuffer_width,
+            framebuffer_height,
+            self.viewport,
+            &self.fragment,
+            layouts,
+        )?;
+
+        Ok(Box::new(DrawPostProcess::<B> {
+            pipeline,
+            pipeline_layout,
+            set,
+            environment,
+            texture,
+            constants: self.constants,
+            _layout: layout,
+            _views: views,
+            _sampler: sampler,
+        }))
+    }
+}
+
+impl<B: Backend> PostProcessPlugin<B> for DrawPostProcessDesc {
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        stage: PostProcessStage,
+        _factory: &mut Factory<B>,
+        _world: &World,
+    ) -> Result<(), Error> {
+        let desc = self.clone();
+        plan.extend_target(stage.target, move |ctx| {
+            let inputs = desc.inputs.clone();
+            let mut group = desc.builder().with_image(ctx.get_image(stage.input)?);
+            for input in inputs {
+                group = group.with_image(ctx.get_image(input)?);
+            }
+            ctx.add(RenderOrder::LinearPostEffects, group)?;
+            Ok(())
+        });
+        Ok(())
+    }
+}
+
+/// Draws a fullscreen triangle with a fragment shader sampling its input images.
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""))]
+pub struct DrawPostProcess<B: Backend> {
+    pipeline: B::GraphicsPipeline,
+    pipeline_layout: B::PipelineLayout,
+    set: Escape<DescriptorSet<B>>,
+    environment: Option<(EnvironmentSub<B>, EnvironmentMapSub<B>)>,
+    texture: Option<PostProcessTexture<B>>,
+    #[derivative(Debug = "ignore")]
+    constants: Option<ConstantsFn>,
+    _layout: RendyHandle<DescriptorSetLayout<B>>,
+    _views: Vec<Escape<ImageView<B>>>,
+    _sampler: RendyHandle<Sampler<B>>,
+}
+
+impl<B: Backend> RenderGroup<B, World> for DrawPostProcess<B> {
+    fn prepare(
+        &mut self,
+        factory: &Factory<B>,
+        _queue: QueueId,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        resources: &World,
+    ) -> PrepareResult {
+        if let Some((env, environment_map)) = self.environment.as_mut() {
+            env.process(factory, index, resources);
+            environment_map.process(factory, resources);
+        }
+        if let Some(texture) = self.texture.as_mut() {
+            texture.process(factory, resources);
+        }
+
+        // Push constants, environment and texture sets are recorded into the command buffer.
+        if self.constants.is_some() || self.environment.is_some() || self.texture.is_some() {
+            PrepareResult::DrawRecord
+        } else {
+            PrepareResult::DrawReuse
+        }
+    }
+
+    fn draw_inline(
+        &mut self,
+        mut encoder: RenderPassEncoder<'_, B>,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        resources: &World,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("draw");
+
+        encoder.bind_graphics_pipeline(&self.pipeline);
+        unsafe {
+            encoder.bind_graphics_descriptor_sets(
+                &self.pipeline_layout,
+                0,
+                Some(self.set.raw()),
+                std::iter::empty(),
+            );
+        }
+        if let Some((env, environment_map)) = &self.environment {
+            env.bind(index, &self.pipeline_layout, 1, &mut encoder);
+            environment_map.bind(&self.pipeline_layout, 2, &mut encoder);
+        }
+        if let Some(texture) = &self.texture {
+            let set_id = 1 + 2 * self.environment.is_some() as u32;
+            texture.bind(&self.pipeline_layout, set_id, &mut encoder);
+        }
+        if let Some(constants) = &self.constants {
+            let constants = constants(resources)
+                .into_iter()
+                .take(MAX_POST_PROCESS_CONSTANTS)
+                .map(f32::to_bits)
+                .collect::<Vec<u32>>();
+            unsafe {
+                encoder.push_constants(
+                    &self.pipeline_layout,
+                    pso::ShaderStageFlags::FRAGMENT,
+                    0,
+                    &constants,
+                );
+            }
+        }
+        unsafe {
+            encoder.draw(0..3, 0..1);
+        }
+        DrawCounter::fetch(resources).draw(hal::Primitive::TriangleList, 3, 1);
+    }
+
+    fn dispose(self: Box<Self>, factory: &mut Factory<B>, _aux: &World) {
+        unsafe {
+            factory.device().destroy_graphics_pipeline(self.pipeline);
+            factory
+                .device()
+                .destroy_pipeline_layout(self.pipeline_layout);
+        }
+    }
+}
+
+/// Texture asset sampled by a post-processing pass, falling back to a black texture.
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""))]
+struct PostProcessTexture<B: Backend> {
+    #[derivative(Debug = "ignore")]
+    texture: TextureFn,
+    textures: TextureSub<B>,
+    current: Option<TextureId>,
+    fallback_set: Escape<DescriptorSet<B>>,
+    _fallback_layout: RendyHandle<DescriptorSetLayout<B>>,
+    _fallback: RendyTexture<B>,
+}
+
+impl<B: Backend> PostProcessTexture<B> {
+    fn new(
+        factory: &mut Factory<B>,
+        queue: QueueId,
+        texture: TextureFn,
+    ) -> Result<Self, failure::Error> {
+        let textures = TextureSub::new(factory)?;
+
+        // Same layout as the texture sets, so the fallback set can be bound in their place.
+        let fallback_layout: RendyHandle<DescriptorSetLayout<B>> = set_layout! {
+            factory,
+            [1] CombinedImageSampler pso::ShaderStageFlags::FRAGMENT
+        };
+        let fallback = TextureBuilder::new()
+            .with_kind(hal::image::Kind::D2(1, 1, 1, 1))
+            .with_view_kind(ViewKind::D2)
+            .with_data_width(1)
+            .with_data_height(1)
+            .with_sampler_info(SamplerInfo::new(Filter::Nearest, WrapMode::Clamp))
+            .with_data(vec![Rgba8Unorm {
+                repr: [0, 0, 0, 255],
+            }])
+            .build(
+                ImageState {
+                    queue,
+                    stage: pso::PipelineStage::FRAGMENT_SHADER,
+                    access: hal::image::Access::SHADER_READ,
+                    layout: hal::image::Layout::ShaderReadOnlyOptimal,
+                },
+                factory,
+            )?;
+        let fallback_set = factory.create_descriptor_set(fallback_layout.clone())?;
+        unsafe {
+            factory.write_descriptor_sets(Some(util::desc_write(
+                fallback_set.raw(),
+                0,
+                pso::Descriptor::CombinedImageSampler(
+                    fallback.view().raw(),
+                    hal::image::Layout::ShaderReadOnlyOptimal,
+                    fallback.sampler().raw(),
+                ),
+            )));
+        }
+
+        Ok(Self {
+            texture,
+            textures,
+            current: None,
+            fallback_set,
+            _fallback_layout: fallback_layout,
+            _fallback: fallback,
+        })
+    }
+
+    fn process(&mut self, factory: &Factory<B>, world: &World) {
+        self.textures.maintain(factory, world);
+        let textures = &mut self.textures;
+        self.current = (self.texture)(world).and_then(|handle| {
+            textures
+                .insert(
+                    factory,
+                    world,
+                    &handle,
+                    hal::image::Layout::ShaderReadOnlyOptimal,
+                )
+                .map(|(id, _)| id)
+        });
+    }
+
+    fn bind(
+        &self,
+        pipeline_layout: &B::PipelineLayout,
+        set_id: u32,
+        encoder: &mut RenderPassEncoder<'_, B>,
+    ) {
+        match self.current {
+            Some(id) if self.textures.loaded(id) => {
+                self.textures.bind(pipeline_layout, set_id, id, encoder)
+            }
+            _ => unsafe {
+                encoder.bind_graphics_descriptor_sets(
+                    pipeline_layout,
+                    set_id,
+                    Some(self.fallback_set.raw()),
+                    std::iter::empty(),
+                );
+            },
+        }
+    }
+}
+
+/// View of a sampled input image, reading depth from depth images.
+pub(crate) fn input_view<B: Backend>(
+    ctx: &GraphContext<B>,
+    factory: &Factory<B>,
+    image: &NodeImage,
+) -> Result<Escape<ImageView<B>>, failure::Error> {
+    let input = ctx
+        .get_image(image.id)
+        .ok_or_else(|| failure::format_err!("Post-process input image is not available"))?;
+    let aspects = if input
+        .format()
+        .surface_desc()
+        .aspects
+        .contains(hal::format::Aspects::DEPTH)
+    {
+        hal::format::Aspects::DEPTH
+    } else {
+        hal::format::Aspects::COLOR
+    };
+    let view = factory.create_image_view(
+        input.clone(),
+        ImageViewInfo {
+            view_kind: ViewKind::D2,
+            format: input.format(),
+            swizzle: Swizzle::NO,
+            range: hal::image::SubresourceRange {
+                aspects,
+                levels: 0..1,
+                layers: 0..1,
+            },
+        },
+    )?;
+    Ok(view)
+}
+
+fn build_post_process_pipeline<B: Backend>(
+    factory: &Factory<B>,
+    cache: Option<&B::PipelineCache>,
+    subpass: hal::pass::Subpass<'_, B>,
+    framebuffer_width: u32,
+    framebuffer_height: u32,
+    viewport: Option<pso::Rect>,
+    fragment_shader: &ReloadableShader,
+    layouts: Vec<&B::DescriptorSetLayout>,
+) -> Result<(B::GraphicsPipeline, B::PipelineLayout), failure::Error> {
+    let pipeline_layout = unsafe {
+        factory.device().create_pipeline_layout(
+            layouts,
+            Some((
+                pso::ShaderStageFlags::FRAGMENT,
+                0..(MAX_POST_PROCESS_CONSTANTS * 4) as u32,
+            )),
+        )
+    }?;
+
+    let shader_vertex = unsafe { super::FULLSCREEN_VERTEX.module(factory).unwrap() };
+    let shader_fragment = unsafe { fragment_shader.module(factory).unwrap() };
+
+    let mut pipeline = PipelineDescBuilder::new()
+        .with_shaders(util::simple_shader_set(
+            &shader_vertex,
+            Some(&shader_fragment),
+        ))
+        .with_layout(&pipeline_layout)
+        .with_subpass(subpass)
+        .with_framebuffer_size(framebuffer_width, framebuffer_height)
+        .with_blend_targets(vec![pso::ColorBlendDesc {
+            mask: pso::ColorMask::ALL,
+            blend: None,
+        }]);
+    if let Some(rect) = viewport {
+        pipeline.set_viewport_rect(rect);
+    }
+    let pipes = PipelinesBuilder::new()
+        .with_pipeline(pipeline)
+        .build_cached(factory, cache);
+
+    unsafe {
+        factory.destroy_shader_module(shader_vertex);
+        factory.destroy_shader_module(shader_fragment);
+    }
+
+    match pipes {
+        Err(e) => {
+            unsafe {
+                factory.device().destroy_pipeline_layout(pipeline_layout);
+            }
+            Err(e)
+        }
+        Ok(mut pipes) => Ok((pipes.remove(0), pipeline_layout)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        bloom::BloomParams,
+        bundle::Target,
+        dof::DofParams,
+        fog::{Fog, FogMode},
+        fxaa::FxaaParams,
+        vignette::VignetteParams,
+    };
+    use amethyst_core::ecs::WorldExt;
+
+    fn constants(desc: &DrawPostProcessDesc, world: &World) -> Vec<f32> {
+        (desc.constants.as_ref().expect("pass has no constants"))(world)
+    }
+
+    #[test]
+    fn constants_fit_in_push_constants() {
+        let world = World::new();
+        for desc in &[
+            DrawPostProcessDesc::fog(),
+            DrawPostProcessDesc::vignette(),
+            DrawPostProcessDesc::fxaa(),
+            DrawPostProcessDesc::chromatic_aberration(),
+            DrawPostProcessDesc::bloom_bright_pass(),
+            DrawPostProcessDesc::bloom_composite(5),
+            DrawPostProcessDesc::dof_bokeh(),
+            DrawPostProcessDesc::motion_blur(),
+            DrawPostProcessDesc::color_grading(),
+            DrawPostProcessDesc::ssr(Target::Main),
+        ] {
+            let len = constants(desc, &world).len();
+            assert!(len > 0 && len <= MAX_POST_PROCESS_CONSTANTS, "{:?}", desc);
+        }
+    }
+
+    #[test]
+    fn fog_constants_follow_shader_layout() {
+        let mut world = World::new();
+        world.insert(Fog {
+            enabled: true,
+            mode: FogMode::Linear,
+            color: [0.1, 0.2, 0.3],
+            density: -1.0,
+            start: 5.0,
+            end: 50.0,
+            height_falloff: 0.5,
+            base_height: 2.0,
+            sky: false,
+        });
+        assert_eq!(
+            constants(&DrawPostProcessDesc::fog(), &world),
+            vec![1.0, 0.0, 0.0, 5.0, 0.1, 0.2, 0.3, 50.0, 0.5, 2.0, 0.0]
+        );
+    }
+
+    #[test]
+    fn params_are_clamped_to_valid_ranges() {
+        let mut world = World::new();
+        world.insert(VignetteParams {
+            intensity: 2.0,
+            smoothness: -1.0,
+            ..Default::default()
+        });
+        world.insert(FxaaParams {
+            subpixel: 1.5,
+            ..Default::default()
+        });
+        world.insert(DofParams {
+            max_blur_radius: -4.0,
+            ..Default::default()
+        });
+
+        let vignette = constants(&DrawPostProcessDesc::vignette(), &world);
+        assert_eq!((vignette[1], vignette[3]), (1.0, 0.0));
+        assert_eq!(constants(&DrawPostProcessDesc::fxaa(), &world)[3], 1.0);
+        assert_eq!(constants(&DrawPostProcessDesc::dof_bokeh(), &world)[1], 0.0);
+    }
+
+    #[test]
+    fn defaults_are_used_without_resources() {
+        let world = World::new();
+        let defaults = VignetteParams::default();
+        assert_eq!(
+            constants(&DrawPostProcessDesc::vignette(), &world)[..3],
+            [1.0, defaults.intensity, defaults.radius]
+        );
+        assert_eq!(
+            constants(&DrawPostProcessDesc::bloom_bright_pass(), &world),
+            vec![
+                BloomParams::default().threshold,
+                BloomParams::default().soft_knee
+            ]
+        );
+    }
+
+    #[test]
+    fn bloom_composite_divides_intensity_by_levels() {
+        let mut world = World::new();
+        world.insert(BloomParams {
+            intensity: 0.6,
+            ..Default::default()
+        });
+        assert_eq!(
+            constants(&DrawPostProcessDesc::bloom_composite(3), &world),
+            vec![1.0, 0.2]
+        );
+        assert_eq!(
+            constants(&DrawPostProcessDesc::bloom_composite(0), &world),
+            vec![1.0, 0.6]
+        );
+    }
+}
@@ -0,0 +1,322 @@
+use crate::{
+    camera::ActiveCamera,
+    sprite_visibility::{SpriteVisibility, SpriteVisibilitySortingSystem},
+    taa::TaaJitter,
+    types::Backend,
+    visibility::{Visibility, VisibilitySortingSystem},
+};
+use amethyst_core::ecs::{Entity, RunNow, SystemData, World, Write};
+use rendy::{
+    command::{
+        CommandBuffer, CommandPool, ExecutableState, Family, Graphics, MultiShot, PendingState,
+        QueueId, RenderPassEncoder, SimultaneousUse, Submit,
+    },
+    factory::Factory,
+    frame::Frames,
+    graph::{
+        gfx_acquire_barriers, gfx_release_barriers,
+        render::{PrepareResult, RenderGroup, RenderGroupDesc},
+        GraphContext, ImageAccess, Node, NodeBuffer, NodeDesc, NodeImage, NodeSubmittable,
+    },
+    hal::{self, pso},
+    resource::{Handle, Image},
+};
+use std::sync::{Arc, Mutex};
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+/// State replaced while a camera is activated by `ActivateCamera`.
+#[derive(Debug, Default)]
+struct ActivationState {
+    camera: Option<Entity>,
+    jitter: Option<(u32, u32)>,
+}
+
+/// Describe a render group making a camera active while its render target is prepared.
+///
+/// Groups of the target added after it gather the activated camera and the entities
+/// visible to it, until the group returned by `restore` brings the previous camera back.
+/// The camera isn't jittered for temporal anti-aliasing while it is active.
+///
+/// The group draws nothing and expects a target with a single color output and depth.
+#[derive(Clone, Debug)]
+pub struct ActivateCameraDesc {
+    camera: Option<Entity>,
+    state: Arc<Mutex<ActivationState>>,
+}
+
+impl ActivateCameraDesc {
+    /// Create instance of `ActivateCamera` render group activating `camera`.
+    pub fn new(camera: Entity) -> Self {
+        Self {
+            camera: Some(camera),
+            state: Default::default(),
+        }
+    }
+
+    /// Describe the render group restoring the camera that was active before this one.
+    pub fn restore(&self) -> Self {
+        Self {
+            camera: None,
+            state: self.state.clone(),
+        }
+    }
+}
+
+impl<B: Backend> RenderGroupDesc<B, World> for ActivateCameraDesc {
+    fn build(
+        self,
+        _ctx: &GraphContext<B>,
+        _factory: &mut Factory<B>,
+        _queue: QueueId,
+        _aux: &World,
+        _framebuffer_width: u32,
+        _framebuffer_height: u32,
+        _subpass: hal::pass::Subpass<'_, B>,
+        _buffers: Vec<NodeBuffer>,
+        _images: Vec<NodeImage>,
+    ) -> Result<Box<dyn RenderGroup<B, World>>, failure::Error> {
+        Ok(Box::new(ActivateCamera {
+            camera: self.camera,
+            state: self.state,
+            visibility: VisibilitySortingSystem::new(),
+            sprite_visibility: SpriteVisibilitySortingSystem::new(),
+        }))
+    }
+}
+
+/// Makes a camera active for the following render groups of a target, or restores
+/// the previously active camera.
+#[derive(Debug)]
+pub struct ActivateCamera {
+    camera: Option<Entity>,
+    state: Arc<Mutex<ActivationState>>,
+    visibility: VisibilitySortingSystem,
+    sprite_visibility: SpriteVisibilitySortingSystem,
+}
+
+impl<B: Backend> RenderGroup<B, World> for ActivateCamera {
+    fn prepare(
+        &mut self,
+        _factory: &Factory<B>,
+        _queue: QueueId,
+        _index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        aux: &World,
+    ) -> PrepareResult {
+        #[cfg(feature = "profiler")]
+        profile_scope!("prepare");
+
+        {
+            let mut state = self.state.lock().unwrap();
+            let mut active = <Write<'_, ActiveCamera>>::fetch(aux);
+            let mut jitter = <Option<Write<'_, TaaJitter>>>::fetch(aux);
+            let extent = jitter.as_mut().map(|jitter| &mut jitter.extent);
+            match self.camera {
+                Some(camera) => {
+                    state.camera = active.entity.replace(camera);
+                    state.jitter = extent.and_then(|extent| extent.take());
+                }
+                None => {
+                    active.entity = state.camera.take();
+                    if let Some(extent) = extent {
+                        *extent = state.jitter.take();
+                    }
+                }
+            }
+        }
+
+        // Visibility is sorted again for the now active camera.
+        if aux.has_value::<Visibility>() {
+            self.visibility.run_now(aux);
+        }
+        if aux.has_value::<SpriteVisibility>() {
+            self.sprite_visibility.run_now(aux);
+        }
+        PrepareResult::DrawReuse
+    }
+
+    fn draw_inline(
+        &mut self,
+        _encoder: RenderPassEncoder<'_, B>,
+        _index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        _aux: &World,
+    ) {
+    }
+
+    fn dispose(self: Box<Self>, _factory: &mut Factory<B>, _aux: &World) {}
+}
+
+/// Describe copying a rendered image into the image of a texture asset.
+///
+/// The node copies its image into `texture`, which must have the same size and format
+/// and is kept in the layout textures are sampled in. It runs on the graphics queue,
+/// where textures are sampled.
+#[derive(Debug)]
+pub struct CopyToTextureDesc<B: Backend> {
+    texture: Handle<Image<B>>,
+}
+
+impl<B: Backend> CopyToTextureDesc<B> {
+    /// Create instance of `CopyToTexture` node copying into the `texture` image.
+    pub fn new(texture: Handle<Image<B>>) -> Self {
+        Self { texture }
+    }
+}
+
+impl<B: Backend> NodeDesc<B, World> for CopyToTextureDesc<B> {
+    type Node = CopyToTexture<B>;
+
+    fn images(&self) -> Vec<ImageAccess> {
+        vec![ImageAccess {
+            access: hal::image::Access::TRANSFER_READ,
+            usage: hal::image::Usage::TRANSFER_SRC,
+            layout: hal::image::Layout::TransferSrcOptimal,
+            stages: pso::PipelineStage::TRANSFER,
+        }]
+    }
+
+    fn build<'a>(
+        self,
+        ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        family: &mut Family<B>,
+        _queue: usize,
+        _aux: &World,
+        buffers: Vec<NodeBuffer>,
+        images: Vec<NodeImage>,
+    ) -> Result<Self::Node, failure::Error> {
+        #[cfg(feature = "profiler")]
+        profile_scope!("build");
+
+        let source = ctx
+            .get_image(images[0].id)
+            .ok_or_else(|| failure::format_err!("Rendered image is not available"))?;
+        let extent = source.kind().extent();
+        if self.texture.kind().extent() != extent || self.texture.format() != source.format() {
+            return Err(failure::format_err!(
+                "Texture of {:?} {:?} doesn't match rendered image of {:?} {:?}",
+                self.texture.kind().extent(),
+                self.texture.format(),
+                extent,
+                source.format(),
+            ));
+        }
+
+        let mut command_pool = factory
+            .create_command_pool(family)?
+            .with_capability::<Graphics>()
+            .expect("Graph builder must provide family with Graphics capability");
+
+        let command_buffer = command_pool.allocate_buffers(1).remove(0);
+        let mut recording = command_buffer.begin(MultiShot(SimultaneousUse), ());
+        let mut encoder = recording.encoder();
+        let layers = hal::image::SubresourceLayers {
+            aspects: hal::format::Aspects::COLOR,
+            level: 0,
+            layers: 0..1,
+        };
+        let range = hal::image::SubresourceRange {
+            aspects: hal::format::Aspects::COLOR,
+            levels: 0..1,
+            layers: 0..1,
+        };
+        let sampled = (
+            hal::image::Access::SHADER_READ,
+            hal::image::Layout::ShaderReadOnlyOptimal,
+        );
+        let written = (
+            hal::image::Access::TRANSFER_WRITE,
+            hal::image::Layout::TransferDstOptimal,
+        );
+        unsafe {
+            let (stages, barriers) = gfx_acquire_barriers(ctx, &buffers, &images);
+            encoder.pipeline_barrier(stages, hal::memory::Dependencies::empty(), barriers);
+            // The texture may still be sampled by the previous frame.
+            encoder.pipeline_barrier(
+                pso::PipelineStage::VERTEX_SHADER | pso::PipelineStage::FRAGMENT_SHADER
+                    ..pso::PipelineStage::TRANSFER,
+                hal::memory::Dependencies::empty(),
+                Some(hal::memory::Barrier::Image {
+                    states: sampled..written,
+                    target: self.texture.raw(),
+                    families: None,
+                    range: range.clone(),
+                }),
+            );
+            encoder.copy_image(
+                source.raw(),
+                images[0].layout,
+                self.texture.raw(),
+                hal::image::Layout::TransferDstOptimal,
+                Some(hal::command::ImageCopy {
+                    src_subresource: layers.clone(),
+                    src_offset: hal::image::Offset::ZERO,
+                    dst_subresource: layers,
+                    dst_offset: hal::image::Offset::ZERO,
+                    extent,
+                }),
+            );
+            encoder.pipeline_barrier(
+                pso::PipelineStage::TRANSFER
+                    ..pso::PipelineStage::VERTEX_SHADER | pso::PipelineStage::FRAGMENT_SHADER,
+                hal::memory::Dependencies::empty(),
+                Some(hal::memory::Barrier::Image {
+                    states: written..sampled,
+                    target: self.texture.raw(),
+                    families: None,
+                    range,
+                }),
+            );
+            let (stages, barriers) = gfx_release_barriers(ctx, &buffers, &images);
+            encoder.pipeline_barrier(stages, hal::memory::Dependencies::empty(), barriers);
+        }
+        let (submit, command_buffer) = recording.finish().submit();
+
+        Ok(CopyToTexture {
+            command_pool,
+            command_buffer,
+            submit,
+            texture: self.texture,
+        })
+    }
+}
+
+/// Copies a rendered image into the image of a texture asset every frame.
+#[derive(Debug)]
+pub struct CopyToTexture<B: Backend> {
+    command_pool: CommandPool<B, Graphics>,
+    command_buffer:
+        CommandBuffer<B, Graphics, PendingState<ExecutableState<MultiShot<SimultaneousUse>>>>,
+    submit: Submit<B, SimultaneousUse>,
+    // Keeps the image alive while commands copying into it are recorded.
+    texture: Handle<Image<B>>,
+}
+
+impl<'a, B: Backend> NodeSubmittable<'a, B> for CopyToTexture<B> {
+    type Submittable = &'a Submit<B, SimultaneousUse>;
+    type Submittables = &'a [Submit<B, SimultaneousUse>];
+}
+
+impl<B: Backend> Node<B, World> for CopyToTexture<B> {
+    type Capability = Graphics;
+    type Desc = CopyToTextureDesc<B>;
+
+    fn run<'a>(
+        &'a mut self,
+        _ctx: &GraphContext<B>,
+        _factory: &Factory<B>,
+        _aux: &World,
+        _frames: &'a Frames<B>,
+    ) -> &'a [Submit<B, SimultaneousUse>] {
+        std::slice::from_ref(&self.submit)
+    }
+
+    unsafe fn dispose(mut self, factory: &mut Factory<B>, _aux: &World) {
+        self.command_pool
+            .free_buffers(Some(self.command_buffer.mark_complete()));
+        factory.destroy_command_pool(self.command_pool);
+    }
+}
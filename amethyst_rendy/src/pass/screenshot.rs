@@ -0,0 +1,257 @@
+use crate::{
+    screenshot::{Screenshot, ScreenshotRequest},
+    types::Backend,
+};
+use amethyst_core::{
+    ecs::{Read, SystemData, World, WriteExpect},
+    ArcThreadPool,
+};
+use rendy::{
+    command::{
+        CommandBuffer, CommandPool, ExecutableState, Family, Graphics, MultiShot, PendingState,
+        SimultaneousUse, Submit,
+    },
+    factory::Factory,
+    frame::Frames,
+    graph::{
+        gfx_acquire_barriers, gfx_release_barriers, GraphContext, ImageAccess, Node, NodeBuffer,
+        NodeDesc, NodeImage, NodeSubmittable,
+    },
+    hal::{self, pso},
+    memory::Download,
+    resource::{Buffer, BufferInfo, Escape},
+};
+use std::path::PathBuf;
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+/// Describe copying the rendered image into CPU memory when a [ScreenshotRequest] is pending.
+///
+/// The image is expected to be in `Rgba8Srgb` format.
+#[derive(Debug, Default)]
+pub struct ScreenshotReadbackDesc;
+
+impl ScreenshotReadbackDesc {
+    /// Create instance of `ScreenshotReadback` node
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl<B: Backend> NodeDesc<B, World> for ScreenshotReadbackDesc {
+    type Node = ScreenshotReadback<B>;
+
+    fn images(&self) -> Vec<ImageAccess> {
+        vec![ImageAccess {
+            access: hal::image::Access::TRANSFER_READ,
+            usage: hal::image::Usage::TRANSFER_SRC,
+            layout: hal::image::Layout::TransferSrcOptimal,
+            stages: pso::PipelineStage::TRANSFER,
+        }]
+    }
+
+    fn build<'a>(
+        self,
+        ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        family: &mut Family<B>,
+        _queue: usize,
+        _aux: &World,
+        buffers: Vec<NodeBuffer>,
+        images: Vec<NodeImage>,
+    ) -> Result<Self::Node, failure::Error> {
+        #[cfg(feature = "profiler")]
+        profile_scope!("build");
+
+        let image = ctx
+            .get_image(images[0].id)
+            .ok_or_else(|| failure::format_err!("Screenshot image is not available"))?;
+        let extent = image.kind().extent();
+
+        let mut command_pool = factory
+            .create_command_pool(family)?
+            .with_capability::<Graphics>()
+            .expect("Graph builder must provide family with Graphics capability");
+
+        // Each frame in flight has its own buffer, read once the frame has completed, and
+        // command buffers recorded once with and without the copy, so frames without
+        // a request still transition the image as the graph expects.
+        let mut frames = Vec::new();
+        let mut command_buffers = command_pool
+            .allocate_buffers(ctx.frames_in_flight as usize * 2)
+            .into_iter();
+        while let (Some(copy_buffer), Some(idle_buffer)) =
+            (command_buffers.next(), command_buffers.next())
+        {
+            let buffer = factory.create_buffer(
+                BufferInfo {
+                    size: u64::from(extent.width * extent.height * 4),
+                    usage: hal::buffer::Usage::TRANSFER_DST,
+                },
+                Download,
+            )?;
+
+            let mut recording = copy_buffer.begin(MultiShot(SimultaneousUse), ());
+            unsafe {
+                let (stages, barriers) = gfx_acquire_barriers(ctx, &buffers, &images);
+                recording.encoder().pipeline_barrier(
+                    stages,
+                    hal::memory::Dependencies::empty(),
+                    barriers,
+                );
+                // Copies from images to buffers aren't exposed by the encoder.
+                hal::command::RawCommandBuffer::copy_image_to_buffer(
+                    recording.raw(),
+                    image.raw(),
+                    images[0].layout,
+                    buffer.raw(),
+                    Some(hal::command::BufferImageCopy {
+                        buffer_offset: 0,
+                        buffer_width: 0,
+                        buffer_height: 0,
+                        image_layers: hal::image::SubresourceLayers {
+                            aspects: hal::format::Aspects::COLOR,
+                            level: 0,
+                            layers: 0..1,
+                        },
+                        image_offset: hal::image::Offset::ZERO,
+                        image_extent: extent,
+                    }),
+                );
+                let mut encoder = recording.encoder();
+                encoder.pipeline_barrier(
+                    pso::PipelineStage::TRANSFER..pso::PipelineStage::HOST,
+                    hal::memory::Dependencies::empty(),
+                    Some(hal::memory::Barrier::whole_buffer(
+                        buffer.raw(),
+                        hal::buffer::Access::TRANSFER_WRITE..hal::buffer::Access::HOST_READ,
+                    )),
+                );
+                let (stages, barriers) = gfx_release_barriers(ctx, &buffers, &images);
+                encoder.pipeline_barrier(stages, hal::memory::Dependencies::empty(), barriers);
+            }
+            let (copy_submit, copy_buffer) = recording.finish().submit();
+
+            let mut recording = idle_buffer.begin(MultiShot(SimultaneousUse), ());
+            let mut encoder = recording.encoder();
+            unsafe {
+                let (stages, barriers) = gfx_acquire_barriers(ctx, &buffers, &images);
+                encoder.pipeline_barrier(stages, hal::memory::Dependencies::empty(), barriers);
+                let (stages, barriers) = gfx_release_barriers(ctx, &buffers, &images);
+                encoder.pipeline_barrier(stages, hal::memory::Dependencies::empty(), barriers);
+            }
+            let (idle_submit, idle_buffer) = recording.finish().submit();
+
+            frames.push(ScreenshotReadbackFrame {
+                buffer,
+                capture: None,
+                copy_buffer,
+                copy_submit,
+                idle_buffer,
+                idle_submit,
+            });
+        }
+
+        Ok(ScreenshotReadback {
+            command_pool,
+            frames,
+            width: extent.width,
+            height: extent.height,
+        })
+    }
+}
+
+#[derive(Debug)]
+struct ScreenshotReadbackFrame<B: Backend> {
+    buffer: Escape<Buffer<B>>,
+    // Request fulfilled by the image copied into `buffer`, with its PNG file path.
+    capture: Option<Option<PathBuf>>,
+    copy_buffer:
+        CommandBuffer<B, Graphics, PendingState<ExecutableState<MultiShot<SimultaneousUse>>>>,
+    copy_submit: Submit<B, SimultaneousUse>,
+    idle_buffer:
+        CommandBuffer<B, Graphics, PendingState<ExecutableState<MultiShot<SimultaneousUse>>>>,
+    idle_submit: Submit<B, SimultaneousUse>,
+}
+
+/// Copies the rendered image into a buffer on request, and passes the image copied by the
+/// last completed frame to the [ScreenshotRequest].
+#[derive(Debug)]
+pub struct ScreenshotReadback<B: Backend> {
+    command_pool: CommandPool<B, Graphics>,
+    frames: Vec<ScreenshotReadbackFrame<B>>,
+    width: u32,
+    height: u32,
+}
+
+impl<'a, B: Backend> NodeSubmittable<'a, B> for ScreenshotReadback<B> {
+    type Submittable = &'a Submit<B, SimultaneousUse>;
+    type Submittables = &'a [Submit<B, SimultaneousUse>];
+}
+
+impl<B: Backend> Node<B, World> for ScreenshotReadback<B> {
+    type Capability = Graphics;
+    type Desc = ScreenshotReadbackDesc;
+
+    fn run<'a>(
+        &'a mut self,
+        _ctx: &GraphContext<B>,
+        factory: &Factory<B>,
+        aux: &World,
+        frames: &'a Frames<B>,
+    ) -> &'a [Submit<B, SimultaneousUse>] {
+        #[cfg(feature = "profiler")]
+        profile_scope!("run");
+
+        let (mut request, pool) = <(
+            WriteExpect<'_, ScreenshotRequest>,
+            Option<Read<'_, ArcThreadPool>>,
+        )>::fetch(aux);
+
+        // The graph waits for the frame that last used this slot before running.
+        let index = frames.next().index() as usize % self.frames.len();
+        let frame = &mut self.frames[index];
+        if let Some(path) = frame.capture.take() {
+            let range = 0..frame.buffer.size();
+            let mut mapped = frame.buffer.map(factory.device(), range.clone()).unwrap();
+            let data = unsafe { mapped.read::<u8>(factory.device(), range).unwrap().to_vec() };
+            let screenshot = Screenshot {
+                width: self.width,
+                height: self.height,
+                data,
+            };
+            if let Some(path) = path {
+                let png = screenshot.clone();
+                let save = move || {
+                    if let Err(err) = png.save_png(&path) {
+                        log::error!("Failed to save screenshot to {:?}: {}", path, err);
+                    }
+                };
+                match pool {
+                    Some(pool) => pool.spawn(save),
+                    None => save(),
+                }
+            }
+            request.captured = Some(screenshot);
+        }
+
+        match request.pending.take() {
+            Some(path) => {
+                frame.capture = Some(path);
+                std::slice::from_ref(&frame.copy_submit)
+            }
+            None => std::slice::from_ref(&frame.idle_submit),
+        }
+    }
+
+    unsafe fn dispose(mut self, factory: &mut Factory<B>, _aux: &World) {
+        for frame in self.frames.drain(..) {
+            self.command_pool.free_buffers(vec![
+                frame.copy_buffer.mark_complete(),
+                frame.idle_buffer.mark_complete(),
+            ]);
+        }
+        factory.destroy_command_pool(self.command_pool);
+    }
+}
@@ -1,12 +1,10 @@
 use super::base_3d::*;
 use crate::{
     mtl::{TexAlbedo, TexEmission},
+    shader_reload::ReloadableShader,
     skinning::JointCombined,
 };
-use rendy::{
-    mesh::{AsVertex, Normal, Position, TexCoord, VertexFormat},
-    shader::SpirvShader,
-};
+use rendy::mesh::{AsVertex, Normal, Position, TexCoord, VertexFormat};
 
 /// Implementation of `Base3DPassDef` describing a simple shaded 3D pass.
 #[derive(Debug)]
@@ -14,15 +12,21 @@ pub struct ShadedPassDef;
 impl Base3DPassDef for ShadedPassDef {
     const NAME: &'static str = "Shaded";
     type TextureSet = (TexAlbedo, TexEmission);
-    fn vertex_shader() -> &'static SpirvShader {
+    fn vertex_shader() -> &'static ReloadableShader {
         &super::POS_NORM_TEX_VERTEX
     }
-    fn vertex_skinned_shader() -> &'static SpirvShader {
+    fn vertex_skinned_shader() -> &'static ReloadableShader {
         &super::POS_NORM_TEX_SKIN_VERTEX
     }
-    fn fragment_shader() -> &'static SpirvShader {
+    fn fragment_shader() -> &'static ReloadableShader {
         &super::SHADED_FRAGMENT
     }
+    fn oit_fragment_shader() -> Option<&'static ReloadableShader> {
+        Some(&super::SHADED_OIT_FRAGMENT)
+    }
+    fn debug_fragment_shader() -> Option<&'static ReloadableShader> {
+        Some(&super::DEBUG_POS_NORM_TEX_FRAGMENT)
+    }
     fn base_format() -> Vec<VertexFormat> {
         vec![Position::vertex(), Normal::vertex(), TexCoord::vertex()]
     }
@@ -0,0 +1,368 @@
+use crate::{
+    batch::{GroupIterator, OneLevelBatch},
+    pipeline::{PipelineCache, PipelineDescBuilder, PipelinesBuilder},
+    pod::VertexArgs,
+    stats::DrawCounter,
+    submodules::{gather::ShadowGatherer, DynamicVertexBuffer},
+    transparent::Transparent,
+    types::{Backend, Mesh},
+    util,
+};
+use amethyst_assets::{AssetStorage, Handle};
+use amethyst_core::{
+    ecs::{Join, Read, ReadStorage, SystemData, World},
+    math::Matrix4,
+    transform::Transform,
+    Hidden, HiddenPropagate,
+};
+use derivative::Derivative;
+use rendy::{
+    command::{QueueId, RenderPassEncoder},
+    factory::Factory,
+    graph::{
+        render::{PrepareResult, RenderGroup, RenderGroupDesc},
+        GraphContext, NodeBuffer, NodeImage,
+    },
+    hal::{self, device::Device, pso},
+    mesh::{AsVertex, Position, VertexFormat},
+    shader::Shader,
+};
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+/// Size of the push constants of the shadow vertex shader:
+/// a projection-view matrix followed by the tile rectangle.
+const PUSH_CONSTANTS_SIZE: u32 = 20;
+
+/// Kind of lights whose shadow maps are drawn by `DrawShadows`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Derivative)]
+#[derivative(Default)]
+enum ShadowCaster {
+    #[derivative(Default)]
+    Directional,
+    Point,
+    Spot,
+}
+
+/// Draw depth of opaque static meshes into a shadow map atlas.
+#[derive(Clone, Debug, PartialEq, Derivative)]
+#[derivative(Default(bound = ""))]
+pub struct DrawShadowsDesc {
+    caster: ShadowCaster,
+}
+
+impl DrawShadowsDesc {
+    /// Create instance of `DrawShadows` render group drawing directional light cascades
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Create instance of `DrawShadows` render group drawing cube map faces
+    /// of shadow casting point lights
+    pub fn point_lights() -> Self {
+        Self {
+            caster: ShadowCaster::Point,
+        }
+    }
+
+    /// Create instance of `DrawShadows` render group drawing shadow maps
+    /// of shadow casting spot lights
+    pub fn spot_lights() -> Self {
+        Self {
+            caster: ShadowCaster::Spot,
+        }
+    }
+}
+
+impl<B: Backend> RenderGroupDesc<B, World> for DrawShadowsDesc {
+    fn colors(&self) -> usize {
+        0
+    }
+
+    fn build(
+        self,
+        _ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        _queue: QueueId,
+        aux: &World,
+        framebuffer_width: u32,
+        framebuffer_height: u32,
+        subpass: hal::pass::Subpass<'_, B>,
+        _buffers: Vec<NodeBuffer>,
+        _images: Vec<NodeImage>,
+    ) -> Result<Box<dyn RenderGroup<B, World>>, failure::Error> {
+        #[cfg(feature = "profiler")]
+        profile_scope!("build");
+
+        let pipeline_cache = aux.try_fetch::<PipelineCache<B>>();
+        let (pipeline, pipeline_layout) = build_shadow_pipeline(
+            factory,
+            pipeline_cache.as_ref().and_then(|cache| cache.raw()),
+            subpass,
+            framebuffer_width,
+            framebuffer_height,
+        )?;
+
+        Ok(Box::new(DrawShadows::<B> {
+            pipeline,
+            pipeline_layout,
+            caster: self.caster,
+            batches: Default::default(),
+            models: DynamicVertexBuffer::new(),
+            vertex_format: vec![Position::vertex()],
+            tiles: Vec::new(),
+            atlas_size: (framebuffer_width, framebuffer_height),
+        }))
+    }
+}
+
+/// Draws depth of opaque static meshes into a shadow map atlas.
+///
+/// Each directional light cascade, point light cube map face or spot light is a tile of the atlas.
+/// Every mesh is drawn once per tile, with the scissor limiting rendering to that tile.
+#[derive(Debug)]
+pub struct DrawShadows<B: Backend> {
+    pipeline: B::GraphicsPipeline,
+    pipeline_layout: B::PipelineLayout,
+    caster: ShadowCaster,
+    batches: OneLevelBatch<u32, VertexArgs>,
+    models: DynamicVertexBuffer<B, VertexArgs>,
+    vertex_format: Vec<VertexFormat>,
+    tiles: Vec<(Matrix4<f32>, pso::Rect)>,
+    atlas_size: (u32, u32),
+}
+
+impl<B: Backend> DrawShadows<B> {
+    fn collect_tiles(&mut self, shadows: &ShadowGatherer) {
+        self.tiles.clear();
+        match self.caster {
+            ShadowCaster::Directional => {
+                let resolution = shadows.params.resolution.max(1);
+                for (column, cascade) in shadows.cascades.iter().enumerate() {
+                    self.tiles
+                        .push((*cascade, tile_rect(column as u32, 0, resolution)));
+                }
+            }
+            ShadowCaster::Point => {
+                let resolution = shadows.params.point_resolution.max(1);
+                for (row, faces) in shadows.point_faces.iter().enumerate() {
+                    for (column, face) in faces.iter().enumerate() {
+                        self.tiles
+                            .push((*face, tile_rect(column as u32, row as u32, resolution)));
+                    }
+                }
+            }
+            ShadowCaster::Spot => {
+                let resolution = shadows.params.spot_resolution.max(1);
+                for (column, proj_view) in shadows.spot_proj_view.iter().enumerate() {
+                    self.tiles
+                        .push((*proj_view, tile_rect(column as u32, 0, resolution)));
+                }
+            }
+        }
+    }
+}
+
+impl<B: Backend> RenderGroup<B, World> for DrawShadows<B> {
+    fn prepare(
+        &mut self,
+        factory: &Factory<B>,
+        _queue: QueueId,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        resources: &World,
+    ) -> PrepareResult {
+        #[cfg(feature = "profiler")]
+        profile_scope!("prepare");
+
+        let (mesh_storage, meshes, transforms, transparent, hiddens, hiddens_prop) =
+            <(
+                Read<'_, AssetStorage<Mesh>>,
+                ReadStorage<'_, Handle<Mesh>>,
+                ReadStorage<'_, Transform>,
+                ReadStorage<'_, Transparent>,
+                ReadStorage<'_, Hidden>,
+                ReadStorage<'_, HiddenPropagate>,
+            )>::fetch(resources);
+
+        self.collect_tiles(&ShadowGatherer::gather(resources));
+
+        self.batches.clear_inner();
+        if !self.tiles.is_empty() {
+            let batches = &mut self.batches;
+            (
+                &meshes,
+                &transforms,
+                !&transparent,
+                !&hiddens,
+                !&hiddens_prop,
+            )
+                .join()
+                .map(|(mesh, transform, _, _, _)| {
+                    (mesh.id(), VertexArgs::from_object_data(transform, None))
+                })
+                .for_each_group(|mesh_id, data| {
+                    if mesh_storage.contains_id(mesh_id) {
+                        batches.insert(mesh_id, data.drain(..));
+                    }
+                });
+        }
+        self.batches.prune();
+
+        self.models.write(
+            factory,
+            index,
+            self.batches.count() as u64,
+            self.batches.data(),
+        );
+
+        PrepareResult::DrawRecord
+    }
+
+    fn draw_inline(
+        &mut self,
+        mut encoder: RenderPassEncoder<'_, B>,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        resources: &World,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("draw");
+
+        if self.tiles.is_empty() || self.batches.count() == 0 {
+            return;
+        }
+
+        let mesh_storage = <Read<'_, AssetStorage<Mesh>>>::fetch(resources);
+        let layout = &self.pipeline_layout;
+
+        encoder.bind_graphics_pipeline(&self.pipeline);
+        if !self
+            .models
+            .bind(index, self.vertex_format.len() as u32, 0, &mut encoder)
+        {
+            return;
+        }
+
+        let (width, height) = (self.atlas_size.0 as f32, self.atlas_size.1 as f32);
+        let mut draws = DrawCounter::fetch(resources);
+        for (proj_view, tile) in &self.tiles {
+            let proj_view: [[f32; 4]; 4] = (*proj_view).into();
+            let tile_uv = [
+                f32::from(tile.x) / width,
+                f32::from(tile.y) / height,
+                f32::from(tile.w) / width,
+                f32::from(tile.h) / height,
+            ];
+            let constants = proj_view
+                .iter()
+                .flatten()
+                .chain(&tile_uv)
+                .map(|v| v.to_bits())
+                .collect::<Vec<u32>>();
+
+            unsafe {
+                encoder.set_scissors(0, Some(tile));
+                encoder.push_constants(layout, pso::ShaderStageFlags::VERTEX, 0, &constants);
+            }
+
+            for (&mesh_id, range) in self.batches.iter() {
+                if let Some(mesh) =
+                    B::unwrap_mesh(unsafe { mesh_storage.get_by_id_unchecked(mesh_id) })
+                {
+                    // Meshes without positions can't cast shadows.
+                    let instances = range.end - range.start;
+                    if mesh
+                        .bind_and_draw(0, &self.vertex_format, range, &mut encoder)
+                        .is_ok()
+                    {
+                        draws.draw(mesh.primitive(), mesh.len(), instances);
+                    }
+                }
+            }
+        }
+    }
+
+    fn dispose(self: Box<Self>, factory: &mut Factory<B>, _aux: &World) {
+        unsafe {
+            factory.device().destroy_graphics_pipeline(self.pipeline);
+            factory
+                .device()
+                .destroy_pipeline_layout(self.pipeline_layout);
+        }
+    }
+}
+
+fn tile_rect(column: u32, row: u32, resolution: u32) -> pso::Rect {
+    pso::Rect {
+        x: (column * resolution) as i16,
+        y: (row * resolution) as i16,
+        w: resolution as i16,
+        h: resolution as i16,
+    }
+}
+
+fn build_shadow_pipeline<B: Backend>(
+    factory: &Factory<B>,
+    cache: Option<&B::PipelineCache>,
+    subpass: hal::pass::Subpass<'_, B>,
+    framebuffer_width: u32,
+    framebuffer_height: u32,
+) -> Result<(B::GraphicsPipeline, B::PipelineLayout), failure::Error> {
+    let pipeline_layout = unsafe {
+        factory.device().create_pipeline_layout(
+            Vec::<&B::DescriptorSetLayout>::new(),
+            Some((pso::ShaderStageFlags::VERTEX, 0..PUSH_CONSTANTS_SIZE * 4)),
+        )
+    }?;
+
+    let shader_vertex = unsafe { super::SHADOW_VERTEX.module(factory).unwrap() };
+
+    let mut pipe_desc = PipelineDescBuilder::new()
+        .with_vertex_desc(&[
+            (Position::vertex(), pso::VertexInputRate::Vertex),
+            (VertexArgs::vertex(), pso::VertexInputRate::Instance(1)),
+        ])
+        .with_shaders(util::simple_shader_set(&shader_vertex, None))
+        .with_layout(&pipeline_layout)
+        .with_subpass(subpass)
+        .with_framebuffer_size(framebuffer_width, framebuffer_height)
+        .with_depth_test(pso::DepthTest {
+            fun: pso::Comparison::Greater,
+            write: true,
+        });
+    // Scissor is set per tile while drawing.
+    pipe_desc.set_baked_states(pso::BakedStates {
+        viewport: Some(pso::Viewport {
+            rect: pso::Rect {
+                x: 0,
+                y: 0,
+                w: framebuffer_width as i16,
+                h: framebuffer_height as i16,
+            },
+            depth: 0.0..1.0,
+        }),
+        scissor: None,
+        blend_color: None,
+        depth_bounds: None,
+    });
+
+    let pipes = PipelinesBuilder::new()
+        .with_pipeline(pipe_desc)
+        .build_cached(factory, cache);
+
+    unsafe {
+        factory.destroy_shader_module(shader_vertex);
+    }
+
+    match pipes {
+        Err(e) => {
+            unsafe {
+                factory.device().destroy_pipeline_layout(pipeline_layout);
+            }
+            Err(e)
+        }
+        Ok(mut pipes) => Ok((pipes.remove(0), pipeline_layout)),
+    }
+}
@@ -1,12 +1,16 @@
 use crate::{
     palette::Srgb,
-    pipeline::{PipelineDescBuilder, PipelinesBuilder},
+    pipeline::{PipelineCache, PipelineDescBuilder, PipelinesBuilder},
     pod::IntoPod,
+    resources::SkyboxCubemap,
+    shader_reload::ReloadableShader,
     shape::Shape,
-    submodules::{DynamicUniform, FlatEnvironmentSub},
-    types::Backend,
+    stats::DrawCounter,
+    submodules::{DynamicUniform, FlatEnvironmentSub, TextureId, TextureSub},
+    types::{Backend, Texture},
     util,
 };
+use amethyst_assets::Handle;
 use amethyst_core::ecs::{Read, SystemData, World};
 use derivative::Derivative;
 use glsl_layout::{vec3, AsStd140};
@@ -57,10 +61,14 @@ impl SkyboxSettings {
 }
 
 /// Describe drawing a skybox around the camera view
+///
+/// The skybox is a vertical gradient, or a cube map texture when a [SkyboxCubemap]
+/// resource exists or a default cube map is set with `with_cubemap`.
 #[derive(Clone, Debug, PartialEq, Derivative)]
 #[derivative(Default(bound = ""))]
 pub struct DrawSkyboxDesc {
     default_settings: SkyboxSettings,
+    default_cubemap: Option<Handle<Texture>>,
 }
 
 impl DrawSkyboxDesc {
@@ -76,6 +84,15 @@ impl DrawSkyboxDesc {
                 nadir_color,
                 zenith_color,
             },
+            default_cubemap: None,
+        }
+    }
+
+    /// Defines the cube map texture drawn when there is no [SkyboxCubemap] resource
+    pub fn with_cubemap(cubemap: Handle<Texture>) -> Self {
+        Self {
+            default_settings: Default::default(),
+            default_cubemap: Some(cubemap),
         }
     }
 }
@@ -86,7 +103,7 @@ impl<B: Backend> RenderGroupDesc<B, World> for DrawSkyboxDesc {
         _ctx: &GraphContext<B>,
         factory: &mut Factory<B>,
         queue: QueueId,
-        _resources: &World,
+        resources: &World,
         framebuffer_width: u32,
         framebuffer_height: u32,
         subpass: hal::pass::Subpass<'_, B>,
@@ -98,25 +115,48 @@ impl<B: Backend> RenderGroupDesc<B, World> for DrawSkyboxDesc {
 
         let env = FlatEnvironmentSub::new(factory)?;
         let colors = DynamicUniform::new(factory, pso::ShaderStageFlags::FRAGMENT)?;
+        let textures = TextureSub::new(factory)?;
         let mesh = Shape::Sphere(16, 16)
             .generate::<Vec<PosTex>>(None)
             .build(queue, factory)?;
+        let cube = Shape::Cube
+            .generate::<Vec<PosTex>>(None)
+            .build(queue, factory)?;
+
+        let pipeline_cache = resources.try_fetch::<PipelineCache<B>>();
 
         let (pipeline, pipeline_layout) = build_skybox_pipeline(
             factory,
+            pipeline_cache.as_ref().and_then(|cache| cache.raw()),
             subpass,
             framebuffer_width,
             framebuffer_height,
+            &super::SKYBOX_FRAGMENT,
             vec![env.raw_layout(), colors.raw_layout()],
         )?;
+        let (cubemap_pipeline, cubemap_pipeline_layout) = build_skybox_pipeline(
+            factory,
+            pipeline_cache.as_ref().and_then(|cache| cache.raw()),
+            subpass,
+            framebuffer_width,
+            framebuffer_height,
+            &super::SKYBOX_CUBEMAP_FRAGMENT,
+            vec![env.raw_layout(), textures.raw_layout()],
+        )?;
 
         Ok(Box::new(DrawSkybox::<B> {
             pipeline,
             pipeline_layout,
+            cubemap_pipeline,
+            cubemap_pipeline_layout,
             env,
             colors,
+            textures,
+            cubemap: None,
             mesh,
+            cube,
             default_settings: self.default_settings,
+            default_cubemap: self.default_cubemap,
         }))
     }
 }
@@ -126,10 +166,16 @@ impl<B: Backend> RenderGroupDesc<B, World> for DrawSkyboxDesc {
 pub struct DrawSkybox<B: Backend> {
     pipeline: B::GraphicsPipeline,
     pipeline_layout: B::PipelineLayout,
+    cubemap_pipeline: B::GraphicsPipeline,
+    cubemap_pipeline_layout: B::PipelineLayout,
     env: FlatEnvironmentSub<B>,
     colors: DynamicUniform<B, SkyboxUniform>,
+    textures: TextureSub<B>,
+    cubemap: Option<TextureId>,
     mesh: Mesh<B>,
+    cube: Mesh<B>,
     default_settings: SkyboxSettings,
+    default_cubemap: Option<Handle<Texture>>,
 }
 
 impl<B: Backend> RenderGroup<B, World> for DrawSkybox<B> {
@@ -148,8 +194,26 @@ impl<B: Backend> RenderGroup<B, World> for DrawSkybox<B> {
             .map(|s| s.uniform())
             .unwrap_or_else(|| self.default_settings.uniform());
 
+        let cubemap = <Option<Read<'_, SkyboxCubemap>>>::fetch(resources)
+            .map(|cubemap| cubemap.0.clone())
+            .or_else(|| self.default_cubemap.clone());
+
         self.env.process(factory, index, resources);
-        let changed = self.colors.write(factory, index, settings);
+        let mut changed = self.colors.write(factory, index, settings);
+
+        self.textures.maintain(factory, resources);
+        let cubemap = cubemap.and_then(|handle| {
+            self.textures.insert(
+                factory,
+                resources,
+                &handle,
+                hal::image::Layout::ShaderReadOnlyOptimal,
+            )
+        });
+        changed = changed || cubemap.map_or(false, |(_, loaded)| loaded);
+        let cubemap = cubemap.map(|(id, _)| id);
+        changed = changed || cubemap != self.cubemap;
+        self.cubemap = cubemap;
 
         if changed {
             PrepareResult::DrawRecord
@@ -163,20 +227,32 @@ impl<B: Backend> RenderGroup<B, World> for DrawSkybox<B> {
         mut encoder: RenderPassEncoder<'_, B>,
         index: usize,
         _subpass: hal::pass::Subpass<'_, B>,
-        _resources: &World,
+        resources: &World,
     ) {
         #[cfg(feature = "profiler")]
         profile_scope!("draw");
-        encoder.bind_graphics_pipeline(&self.pipeline);
-        self.env.bind(index, &self.pipeline_layout, 0, &mut encoder);
-        self.colors
-            .bind(index, &self.pipeline_layout, 1, &mut encoder);
-        self.mesh
-            .bind(0, &[PosTex::vertex()], &mut encoder)
-            .unwrap();
+        let mesh = match self.cubemap {
+            Some(cubemap) if self.textures.loaded(cubemap) => {
+                encoder.bind_graphics_pipeline(&self.cubemap_pipeline);
+                self.env
+                    .bind(index, &self.cubemap_pipeline_layout, 0, &mut encoder);
+                self.textures
+                    .bind(&self.cubemap_pipeline_layout, 1, cubemap, &mut encoder);
+                &self.cube
+            }
+            _ => {
+                encoder.bind_graphics_pipeline(&self.pipeline);
+                self.env.bind(index, &self.pipeline_layout, 0, &mut encoder);
+                self.colors
+                    .bind(index, &self.pipeline_layout, 1, &mut encoder);
+                &self.mesh
+            }
+        };
+        mesh.bind(0, &[PosTex::vertex()], &mut encoder).unwrap();
         unsafe {
-            encoder.draw(0..self.mesh.len(), 0..1);
+            encoder.draw(0..mesh.len(), 0..1);
         }
+        DrawCounter::fetch(resources).draw(mesh.primitive(), mesh.len(), 1);
     }
 
     fn dispose(self: Box<Self>, factory: &mut Factory<B>, _aux: &World) {
@@ -185,15 +261,23 @@ impl<B: Backend> RenderGroup<B, World> for DrawSkybox<B> {
             factory
                 .device()
                 .destroy_pipeline_layout(self.pipeline_layout);
+            factory
+                .device()
+                .destroy_graphics_pipeline(self.cubemap_pipeline);
+            factory
+                .device()
+                .destroy_pipeline_layout(self.cubemap_pipeline_layout);
         }
     }
 }
 
 fn build_skybox_pipeline<B: Backend>(
     factory: &Factory<B>,
+    cache: Option<&B::PipelineCache>,
     subpass: hal::pass::Subpass<'_, B>,
     framebuffer_width: u32,
     framebuffer_height: u32,
+    fragment_shader: &ReloadableShader,
     layouts: Vec<&B::DescriptorSetLayout>,
 ) -> Result<(B::GraphicsPipeline, B::PipelineLayout), failure::Error> {
     let pipeline_layout = unsafe {
@@ -203,7 +287,7 @@ fn build_skybox_pipeline<B: Backend>(
     }?;
 
     let shader_vertex = unsafe { super::SKYBOX_VERTEX.module(factory).unwrap() };
-    let shader_fragment = unsafe { super::SKYBOX_FRAGMENT.module(factory).unwrap() };
+    let shader_fragment = unsafe { fragment_shader.module(factory).unwrap() };
 
     let pipes = PipelinesBuilder::new()
         .with_pipeline(
@@ -225,7 +309,7 @@ fn build_skybox_pipeline<B: Backend>(
                     blend: None,
                 }]),
         )
-        .build(factory, None);
+        .build_cached(factory, cache);
 
     unsafe {
         factory.destroy_shader_module(shader_vertex);
@@ -0,0 +1,299 @@
+use crate::{
+    batch::{GroupIterator, OneLevelBatch},
+    pass::DrawPostProcessDesc,
+    pipeline::{PipelineCache, PipelineDescBuilder, PipelinesBuilder},
+    pod::{VertexArgs, ViewArgs},
+    skinning::JointTransforms,
+    ssao::{SsaoParams, MAX_SSAO_KERNEL_SIZE},
+    stats::DrawCounter,
+    submodules::{gather::CameraGatherer, DynamicUniform, DynamicVertexBuffer},
+    transparent::Transparent,
+    types::{Backend, Mesh},
+    util,
+};
+use amethyst_assets::{AssetStorage, Handle};
+use amethyst_core::{
+    ecs::{Join, Read, ReadStorage, SystemData, World},
+    transform::Transform,
+    Hidden, HiddenPropagate,
+};
+use derivative::Derivative;
+use rendy::{
+    command::{QueueId, RenderPassEncoder},
+    factory::Factory,
+    graph::{
+        render::{PrepareResult, RenderGroup, RenderGroupDesc},
+        GraphContext, NodeBuffer, NodeImage,
+    },
+    hal::{self, device::Device, pso},
+    mesh::{AsVertex, Normal, Position, VertexFormat},
+    shader::Shader,
+};
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+impl DrawPostProcessDesc {
+    /// Create instance of `DrawPostProcess` render group computing ambient occlusion
+    /// from view space normals and depth drawn by `DrawSsaoNormals`, as configured by [SsaoParams].
+    pub fn ssao() -> Self {
+        Self::new(super::SSAO_FRAGMENT.clone()).with_constants(|world| {
+            let params = SsaoParams::fetch(world);
+            let projection = CameraGatherer::gather(world).projection;
+            let mut constants = projection.as_slice().to_vec();
+            constants.extend_from_slice(&[
+                if params.enabled { 1.0 } else { 0.0 },
+                params.radius.max(0.0),
+                params.bias,
+                params.intensity,
+                params.kernel_size.max(1).min(MAX_SSAO_KERNEL_SIZE) as f32,
+            ]);
+            constants
+        })
+    }
+
+    /// Create instance of `DrawPostProcess` render group smoothing the noise of the ambient
+    /// occlusion image without blurring it across depth discontinuities.
+    ///
+    /// Sampled images are the occlusion image and the normals and depth it was computed from.
+    pub fn ssao_blur() -> Self {
+        Self::new(super::SSAO_BLUR_FRAGMENT.clone())
+            .with_constants(|world| vec![SsaoParams::fetch(world).radius.max(0.0)])
+    }
+}
+
+/// Draw view space normals and depth of opaque static meshes,
+/// sampled by screen-space ambient occlusion.
+#[derive(Clone, Debug, PartialEq, Derivative)]
+#[derivative(Default(bound = ""))]
+pub struct DrawSsaoNormalsDesc;
+
+impl DrawSsaoNormalsDesc {
+    /// Create instance of `DrawSsaoNormals` render group
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl<B: Backend> RenderGroupDesc<B, World> for DrawSsaoNormalsDesc {
+    fn build(
+        self,
+        _ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        _queue: QueueId,
+        aux: &World,
+        framebuffer_width: u32,
+        framebuffer_height: u32,
+        subpass: hal::pass::Subpass<'_, B>,
+        _buffers: Vec<NodeBuffer>,
+        _images: Vec<NodeImage>,
+    ) -> Result<Box<dyn RenderGroup<B, World>>, failure::Error> {
+        #[cfg(feature = "profiler")]
+        profile_scope!("build");
+
+        let env = DynamicUniform::new(factory, pso::ShaderStageFlags::VERTEX)?;
+        let pipeline_cache = aux.try_fetch::<PipelineCache<B>>();
+        let (pipeline, pipeline_layout) = build_ssao_normals_pipeline(
+            factory,
+            pipeline_cache.as_ref().and_then(|cache| cache.raw()),
+            subpass,
+            framebuffer_width,
+            framebuffer_height,
+            vec![env.raw_layout()],
+        )?;
+
+        let mut vertex_format = vec![Position::vertex(), Normal::vertex()];
+        vertex_format.sort();
+
+        Ok(Box::new(DrawSsaoNormals::<B> {
+            pipeline,
+            pipeline_layout,
+            env,
+            batches: Default::default(),
+            models: DynamicVertexBuffer::new(),
+            vertex_format,
+        }))
+    }
+}
+
+/// Draws view space normals and depth of opaque static meshes.
+///
+/// Surfaces are drawn without their normal maps. Skinned meshes are not drawn.
+#[derive(Debug)]
+pub struct DrawSsaoNormals<B: Backend> {
+    pipeline: B::GraphicsPipeline,
+    pipeline_layout: B::PipelineLayout,
+    env: DynamicUniform<B, ViewArgs>,
+    batches: OneLevelBatch<u32, VertexArgs>,
+    models: DynamicVertexBuffer<B, VertexArgs>,
+    vertex_format: Vec<VertexFormat>,
+}
+
+impl<B: Backend> RenderGroup<B, World> for DrawSsaoNormals<B> {
+    fn prepare(
+        &mut self,
+        factory: &Factory<B>,
+        _queue: QueueId,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        resources: &World,
+    ) -> PrepareResult {
+        #[cfg(feature = "profiler")]
+        profile_scope!("prepare");
+
+        let (mesh_storage, meshes, transforms, joints, transparent, hiddens, hiddens_prop) =
+            <(
+                Read<'_, AssetStorage<Mesh>>,
+                ReadStorage<'_, Handle<Mesh>>,
+                ReadStorage<'_, Transform>,
+                ReadStorage<'_, JointTransforms>,
+                ReadStorage<'_, Transparent>,
+                ReadStorage<'_, Hidden>,
+                ReadStorage<'_, HiddenPropagate>,
+            )>::fetch(resources);
+
+        self.env
+            .write(factory, index, CameraGatherer::gather(resources).projview);
+
+        self.batches.clear_inner();
+        {
+            let batches = &mut self.batches;
+            (
+                &meshes,
+                &transforms,
+                !&joints,
+                !&transparent,
+                !&hiddens,
+                !&hiddens_prop,
+            )
+                .join()
+                .map(|(mesh, transform, _, _, _, _)| {
+                    (mesh.id(), VertexArgs::from_object_data(transform, None))
+                })
+                .for_each_group(|mesh_id, data| {
+                    if mesh_storage.contains_id(mesh_id) {
+                        batches.insert(mesh_id, data.drain(..));
+                    }
+                });
+        }
+        self.batches.prune();
+
+        self.models.write(
+            factory,
+            index,
+            self.batches.count() as u64,
+            self.batches.data(),
+        );
+
+        PrepareResult::DrawRecord
+    }
+
+    fn draw_inline(
+        &mut self,
+        mut encoder: RenderPassEncoder<'_, B>,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        resources: &World,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("draw");
+
+        if self.batches.count() == 0 {
+            return;
+        }
+
+        let mesh_storage = <Read<'_, AssetStorage<Mesh>>>::fetch(resources);
+
+        encoder.bind_graphics_pipeline(&self.pipeline);
+        self.env.bind(index, &self.pipeline_layout, 0, &mut encoder);
+        if !self
+            .models
+            .bind(index, self.vertex_format.len() as u32, 0, &mut encoder)
+        {
+            return;
+        }
+
+        let mut draws = DrawCounter::fetch(resources);
+        for (&mesh_id, range) in self.batches.iter() {
+            if let Some(mesh) = B::unwrap_mesh(unsafe { mesh_storage.get_by_id_unchecked(mesh_id) })
+            {
+                // Meshes without normals can't be occluded.
+                let instances = range.end - range.start;
+                if mesh
+                    .bind_and_draw(0, &self.vertex_format, range, &mut encoder)
+                    .is_ok()
+                {
+                    draws.draw(mesh.primitive(), mesh.len(), instances);
+                }
+            }
+        }
+    }
+
+    fn dispose(self: Box<Self>, factory: &mut Factory<B>, _aux: &World) {
+        unsafe {
+            factory.device().destroy_graphics_pipeline(self.pipeline);
+            factory
+                .device()
+                .destroy_pipeline_layout(self.pipeline_layout);
+        }
+    }
+}
+
+fn build_ssao_normals_pipeline<B: Backend>(
+    factory: &Factory<B>,
+    cache: Option<&B::PipelineCache>,
+    subpass: hal::pass::Subpass<'_, B>,
+    framebuffer_width: u32,
+    framebuffer_height: u32,
+    layouts: Vec<&B::DescriptorSetLayout>,
+) -> Result<(B::GraphicsPipeline, B::PipelineLayout), failure::Error> {
+    let pipeline_layout = unsafe {
+        factory
+            .device()
+            .create_pipeline_layout(layouts, None as Option<(_, _)>)
+    }?;
+
+    let shader_vertex = unsafe { super::SSAO_NORMALS_VERTEX.module(factory).unwrap() };
+    let shader_fragment = unsafe { super::SSAO_NORMALS_FRAGMENT.module(factory).unwrap() };
+
+    let pipes = PipelinesBuilder::new()
+        .with_pipeline(
+            PipelineDescBuilder::new()
+                .with_vertex_desc(&[
+                    (Position::vertex(), pso::VertexInputRate::Vertex),
+                    (Normal::vertex(), pso::VertexInputRate::Vertex),
+                    (VertexArgs::vertex(), pso::VertexInputRate::Instance(1)),
+                ])
+                .with_shaders(util::simple_shader_set(
+                    &shader_vertex,
+                    Some(&shader_fragment),
+                ))
+                .with_layout(&pipeline_layout)
+                .with_subpass(subpass)
+                .with_framebuffer_size(framebuffer_width, framebuffer_height)
+                .with_depth_test(pso::DepthTest {
+                    fun: pso::Comparison::Greater,
+                    write: true,
+                })
+                .with_blend_targets(vec![pso::ColorBlendDesc {
+                    mask: pso::ColorMask::ALL,
+                    blend: None,
+                }]),
+        )
+        .build_cached(factory, cache);
+
+    unsafe {
+        factory.destroy_shader_module(shader_vertex);
+        factory.destroy_shader_module(shader_fragment);
+    }
+
+    match pipes {
+        Err(e) => {
+            unsafe {
+                factory.device().destroy_pipeline_layout(pipeline_layout);
+            }
+            Err(e)
+        }
+        Ok(mut pipes) => Ok((pipes.remove(0), pipeline_layout)),
+    }
+}
@@ -0,0 +1,351 @@
+use crate::{
+    batch::{GroupIterator, TwoLevelBatch},
+    bundle::{Target, TargetImage},
+    mtl::{FullTextureSet, Material},
+    pass::DrawPostProcessDesc,
+    pipeline::{PipelineCache, PipelineDescBuilder, PipelinesBuilder},
+    pod::{VertexArgs, ViewArgs},
+    resources::Tint,
+    skinning::JointTransforms,
+    ssr::SsrParams,
+    stats::DrawCounter,
+    submodules::{
+        gather::CameraGatherer, DynamicUniform, DynamicVertexBuffer, MaterialId, MaterialSub,
+    },
+    transparent::Transparent,
+    types::{Backend, Mesh},
+    util,
+};
+use amethyst_assets::{AssetStorage, Handle};
+use amethyst_core::{
+    ecs::{Join, Read, ReadStorage, SystemData, World},
+    transform::Transform,
+    Hidden, HiddenPropagate,
+};
+use derivative::Derivative;
+use rendy::{
+    command::{QueueId, RenderPassEncoder},
+    factory::Factory,
+    graph::{
+        render::{PrepareResult, RenderGroup, RenderGroupDesc},
+        GraphContext, NodeBuffer, NodeImage,
+    },
+    hal::{self, device::Device, pso},
+    mesh::{AsVertex, Color, Normal, Position, TexCoord, VertexFormat},
+    shader::Shader,
+};
+use smallvec::SmallVec;
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+impl DrawPostProcessDesc {
+    /// Create instance of `DrawPostProcess` render group blending screen-space reflections
+    /// into the image, as configured by [SsrParams].
+    ///
+    /// Reflecting surfaces are read from the two color outputs of `DrawSsrSurfaces`
+    /// rendered to `surfaces`.
+    pub fn ssr(surfaces: Target) -> Self {
+        Self::new(super::SSR_FRAGMENT.clone())
+            .with_input(TargetImage::Color(surfaces, 0))
+            .with_input(TargetImage::Color(surfaces, 1))
+            .with_environment()
+            .with_constants(|world| {
+                let params = SsrParams::fetch(world);
+                vec![
+                    if params.enabled { 1.0 } else { 0.0 },
+                    params.max_steps.max(1) as f32,
+                    params.refinement_steps as f32,
+                    params.max_distance.max(0.0),
+                    params.thickness.max(0.0),
+                    params.max_roughness.max(0.0).min(1.0),
+                    params.intensity,
+                ]
+            })
+    }
+}
+
+/// Draw view space normals, depth, fresnel reflectance and roughness of opaque
+/// static meshes, sampled by screen-space reflections.
+#[derive(Clone, Debug, PartialEq, Derivative)]
+#[derivative(Default(bound = ""))]
+pub struct DrawSsrSurfacesDesc;
+
+impl DrawSsrSurfacesDesc {
+    /// Create instance of `DrawSsrSurfaces` render group
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl<B: Backend> RenderGroupDesc<B, World> for DrawSsrSurfacesDesc {
+    fn colors(&self) -> usize {
+        2
+    }
+
+    fn build(
+        self,
+        _ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        _queue: QueueId,
+        aux: &World,
+        framebuffer_width: u32,
+        framebuffer_height: u32,
+        subpass: hal::pass::Subpass<'_, B>,
+        _buffers: Vec<NodeBuffer>,
+        _images: Vec<NodeImage>,
+    ) -> Result<Box<dyn RenderGroup<B, World>>, failure::Error> {
+        #[cfg(feature = "profiler")]
+        profile_scope!("build");
+
+        let env = DynamicUniform::new(
+            factory,
+            pso::ShaderStageFlags::VERTEX | pso::ShaderStageFlags::FRAGMENT,
+        )?;
+        let materials = MaterialSub::new(factory)?;
+        let pipeline_cache = aux.try_fetch::<PipelineCache<B>>();
+        let (pipeline, pipeline_layout) = build_ssr_surfaces_pipeline(
+            factory,
+            pipeline_cache.as_ref().and_then(|cache| cache.raw()),
+            subpass,
+            framebuffer_width,
+            framebuffer_height,
+            vec![env.raw_layout(), materials.raw_layout()],
+        )?;
+
+        let mut vertex_format = vec![Position::vertex(), Normal::vertex(), TexCoord::vertex()];
+        vertex_format.sort();
+
+        Ok(Box::new(DrawSsrSurfaces::<B> {
+            pipeline,
+            pipeline_layout,
+            env,
+            materials,
+            batches: Default::default(),
+            models: DynamicVertexBuffer::new(),
+            white: DynamicVertexBuffer::new(),
+            vertex_format,
+        }))
+    }
+}
+
+/// Draws the surfaces of opaque static meshes reflecting screen-space reflections.
+///
+/// Surfaces are drawn without their normal maps. Skinned meshes are not drawn.
+#[derive(Debug)]
+pub struct DrawSsrSurfaces<B: Backend> {
+    pipeline: B::GraphicsPipeline,
+    pipeline_layout: B::PipelineLayout,
+    env: DynamicUniform<B, ViewArgs>,
+    materials: MaterialSub<B, FullTextureSet>,
+    batches: TwoLevelBatch<MaterialId, u32, SmallVec<[VertexArgs; 4]>>,
+    models: DynamicVertexBuffer<B, VertexArgs>,
+    white: DynamicVertexBuffer<B, Color>,
+    vertex_format: Vec<VertexFormat>,
+}
+
+impl<B: Backend> RenderGroup<B, World> for DrawSsrSurfaces<B> {
+    fn prepare(
+        &mut self,
+        factory: &Factory<B>,
+        _queue: QueueId,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        resources: &World,
+    ) -> PrepareResult {
+        #[cfg(feature = "profiler")]
+        profile_scope!("prepare");
+
+        let (
+            mesh_storage,
+            meshes,
+            materials,
+            transforms,
+            tints,
+            joints,
+            transparent,
+            hiddens,
+            hiddens_prop,
+        ) = <(
+            Read<'_, AssetStorage<Mesh>>,
+            ReadStorage<'_, Handle<Mesh>>,
+            ReadStorage<'_, Handle<Material>>,
+            ReadStorage<'_, Transform>,
+            ReadStorage<'_, Tint>,
+            ReadStorage<'_, JointTransforms>,
+            ReadStorage<'_, Transparent>,
+            ReadStorage<'_, Hidden>,
+            ReadStorage<'_, HiddenPropagate>,
+        )>::fetch(resources);
+
+        self.env
+            .write(factory, index, CameraGatherer::gather(resources).projview);
+        self.materials.maintain();
+
+        self.batches.clear_inner();
+        {
+            let materials_ref = &mut self.materials;
+            let batches = &mut self.batches;
+            (
+                &materials,
+                &meshes,
+                &transforms,
+                tints.maybe(),
+                !&joints,
+                !&transparent,
+                !&hiddens,
+                !&hiddens_prop,
+            )
+                .join()
+                .map(|(mat, mesh, transform, tint, _, _, _, _)| {
+                    (
+                        (mat, mesh.id()),
+                        VertexArgs::from_object_data(transform, tint),
+                    )
+                })
+                .for_each_group(|(mat, mesh_id), data| {
+                    if mesh_storage.contains_id(mesh_id) {
+                        if let Some((mat, _)) = materials_ref.insert(factory, resources, mat) {
+                            batches.insert(mat, mesh_id, data.drain(..));
+                        }
+                    }
+                });
+        }
+        self.batches.prune();
+
+        self.models.write(
+            factory,
+            index,
+            self.batches.count() as u64,
+            self.batches.data(),
+        );
+        super::base_3d::write_white(&mut self.white, factory, index, self.batches.count());
+
+        PrepareResult::DrawRecord
+    }
+
+    fn draw_inline(
+        &mut self,
+        mut encoder: RenderPassEncoder<'_, B>,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        resources: &World,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("draw");
+
+        if self.batches.count() == 0 {
+            return;
+        }
+
+        let mesh_storage = <Read<'_, AssetStorage<Mesh>>>::fetch(resources);
+
+        encoder.bind_graphics_pipeline(&self.pipeline);
+        self.env.bind(index, &self.pipeline_layout, 0, &mut encoder);
+        // Vertex colors of meshes are ignored.
+        let models_loc = self.vertex_format.len() as u32;
+        if !self.models.bind(index, models_loc, 0, &mut encoder)
+            || !self.white.bind(index, models_loc + 1, 0, &mut encoder)
+        {
+            return;
+        }
+
+        let mut draws = DrawCounter::fetch(resources);
+        let mut instances_drawn = 0;
+        for (&mat_id, batches) in self.batches.iter() {
+            if self.materials.loaded(mat_id) {
+                self.materials
+                    .bind(&self.pipeline_layout, 1, mat_id, &mut encoder);
+                for (mesh_id, batch_data) in batches {
+                    if let Some(mesh) =
+                        B::unwrap_mesh(unsafe { mesh_storage.get_by_id_unchecked(*mesh_id) })
+                    {
+                        // Meshes without normals or texture coordinates don't reflect.
+                        let instances = instances_drawn..instances_drawn + batch_data.len() as u32;
+                        if mesh
+                            .bind_and_draw(0, &self.vertex_format, instances, &mut encoder)
+                            .is_ok()
+                        {
+                            draws.draw(mesh.primitive(), mesh.len(), batch_data.len() as u32);
+                        }
+                    }
+                    instances_drawn += batch_data.len() as u32;
+                }
+            }
+        }
+    }
+
+    fn dispose(self: Box<Self>, factory: &mut Factory<B>, _aux: &World) {
+        unsafe {
+            factory.device().destroy_graphics_pipeline(self.pipeline);
+            factory
+                .device()
+                .destroy_pipeline_layout(self.pipeline_layout);
+        }
+    }
+}
+
+fn build_ssr_surfaces_pipeline<B: Backend>(
+    factory: &Factory<B>,
+    cache: Option<&B::PipelineCache>,
+    subpass: hal::pass::Subpass<'_, B>,
+    framebuffer_width: u32,
+    framebuffer_height: u32,
+    layouts: Vec<&B::DescriptorSetLayout>,
+) -> Result<(B::GraphicsPipeline, B::PipelineLayout), failure::Error> {
+    let pipeline_layout = unsafe {
+        factory
+            .device()
+            .create_pipeline_layout(layouts, None as Option<(_, _)>)
+    }?;
+
+    let shader_vertex = unsafe { super::POS_NORM_TEX_VERTEX.module(factory).unwrap() };
+    let shader_fragment = unsafe { super::SSR_SURFACES_FRAGMENT.module(factory).unwrap() };
+
+    let pipes = PipelinesBuilder::new()
+        .with_pipeline(
+            PipelineDescBuilder::new()
+                .with_vertex_desc(&[
+                    (Position::vertex(), pso::VertexInputRate::Vertex),
+                    (Normal::vertex(), pso::VertexInputRate::Vertex),
+                    (TexCoord::vertex(), pso::VertexInputRate::Vertex),
+                    (VertexArgs::vertex(), pso::VertexInputRate::Instance(1)),
+                    (Color::vertex(), pso::VertexInputRate::Instance(1)),
+                ])
+                .with_shaders(util::simple_shader_set(
+                    &shader_vertex,
+                    Some(&shader_fragment),
+                ))
+                .with_layout(&pipeline_layout)
+                .with_subpass(subpass)
+                .with_framebuffer_size(framebuffer_width, framebuffer_height)
+                .with_face_culling(pso::Face::BACK)
+                .with_depth_test(pso::DepthTest {
+                    fun: pso::Comparison::Greater,
+                    write: true,
+                })
+                .with_blend_targets(vec![
+                    pso::ColorBlendDesc {
+                        mask: pso::ColorMask::ALL,
+                        blend: None,
+                    };
+                    2
+                ]),
+        )
+        .build_cached(factory, cache);
+
+    unsafe {
+        factory.destroy_shader_module(shader_vertex);
+        factory.destroy_shader_module(shader_fragment);
+    }
+
+    match pipes {
+        Err(e) => {
+            unsafe {
+                factory.device().destroy_pipeline_layout(pipeline_layout);
+            }
+            Err(e)
+        }
+        Ok(mut pipes) => Ok((pipes.remove(0), pipeline_layout)),
+    }
+}
@@ -0,0 +1,312 @@
+use crate::{
+    bundle::Target,
+    stats::{RenderGroupId, RenderStats},
+    types::Backend,
+};
+use amethyst_core::ecs::{SystemData, World, Write, WriteExpect};
+use rendy::{
+    command::{
+        CommandBuffer, CommandPool, ExecutableState, Family, Graphics, MultiShot, PendingState,
+        QueueId, RenderPassEncoder, SimultaneousUse, Submit,
+    },
+    factory::Factory,
+    frame::Frames,
+    graph::{
+        render::{PrepareResult, RenderGroup, RenderGroupBuilder},
+        BufferAccess, BufferId, GraphContext, ImageAccess, ImageId, Node, NodeBuffer, NodeDesc,
+        NodeId, NodeImage, NodeSubmittable,
+    },
+    hal::{self, device::Device, pso},
+};
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+/// Describe writing a GPU timestamp between the render groups of a target into [RenderStats].
+///
+/// The timestamps of a target are numbered from 0 to `last`, each written once all commands
+/// submitted before it completed. The render group `i` is drawn between timestamps `i` and
+/// `i + 1`, in a pass depending on the timer node of timestamp `i`, which the timer node of
+/// timestamp `i + 1` depends on.
+#[derive(Debug)]
+pub struct PassTimerDesc {
+    target: Target,
+    index: usize,
+    last: usize,
+}
+
+impl PassTimerDesc {
+    /// Create instance of `PassTimer` node writing timestamp `index` of the timestamps
+    /// `0..=last` of `target`.
+    pub fn new(target: Target, index: usize, last: usize) -> Self {
+        Self {
+            target,
+            index,
+            last,
+        }
+    }
+}
+
+impl<B: Backend> NodeDesc<B, World> for PassTimerDesc {
+    type Node = PassTimer<B>;
+
+    fn build<'a>(
+        self,
+        ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        family: &mut Family<B>,
+        _queue: usize,
+        _aux: &World,
+        _buffers: Vec<NodeBuffer>,
+        _images: Vec<NodeImage>,
+    ) -> Result<Self::Node, failure::Error> {
+        #[cfg(feature = "profiler")]
+        profile_scope!("build");
+
+        let frames_in_flight = ctx.frames_in_flight;
+        let query_pool = unsafe {
+            factory
+                .device()
+                .create_query_pool(hal::query::Type::Timestamp, frames_in_flight)
+        }
+        .map_err(|err| failure::format_err!("Failed to create query pool: {:?}", err))?;
+
+        let mut command_pool = factory
+            .create_command_pool(family)?
+            .with_capability::<Graphics>()
+            .expect("Graph builder must provide family with Graphics capability");
+
+        // Each frame in flight writes its own query, read once the frame has completed.
+        let frames = command_pool
+            .allocate_buffers(frames_in_flight as usize)
+            .into_iter()
+            .zip(0..)
+            .map(|(command_buffer, query)| {
+                let mut recording = command_buffer.begin(MultiShot(SimultaneousUse), ());
+                unsafe {
+                    // Queries aren't exposed by the encoder.
+                    let raw = recording.raw();
+                    hal::command::RawCommandBuffer::reset_query_pool(
+                        raw,
+                        &query_pool,
+                        query..query + 1,
+                    );
+                    hal::command::RawCommandBuffer::write_timestamp(
+                        raw,
+                        pso::PipelineStage::BOTTOM_OF_PIPE,
+                        hal::query::Query {
+                            pool: &query_pool,
+                            id: query,
+                        },
+                    );
+                }
+                let (submit, command_buffer) = recording.finish().submit();
+                PassTimerFrame {
+                    command_buffer,
+                    submit,
+                    written: false,
+                }
+            })
+            .collect();
+
+        Ok(PassTimer {
+            target: self.target,
+            index: self.index,
+            last: self.last,
+            query_pool,
+            command_pool,
+            frames,
+        })
+    }
+}
+
+#[derive(Debug)]
+struct PassTimerFrame<B: Backend> {
+    command_buffer:
+        CommandBuffer<B, Graphics, PendingState<ExecutableState<MultiShot<SimultaneousUse>>>>,
+    submit: Submit<B, SimultaneousUse>,
+    written: bool,
+}
+
+/// Writes a GPU timestamp between the render groups of a target, and passes the timestamp
+/// written by the last completed frame to [RenderStats].
+#[derive(Debug)]
+pub struct PassTimer<B: Backend> {
+    target: Target,
+    index: usize,
+    last: usize,
+    query_pool: B::QueryPool,
+    command_pool: CommandPool<B, Graphics>,
+    frames: Vec<PassTimerFrame<B>>,
+}
+
+impl<'a, B: Backend> NodeSubmittable<'a, B> for PassTimer<B> {
+    type Submittable = &'a Submit<B, SimultaneousUse>;
+    type Submittables = &'a [Submit<B, SimultaneousUse>];
+}
+
+impl<B: Backend> Node<B, World> for PassTimer<B> {
+    type Capability = Graphics;
+    type Desc = PassTimerDesc;
+
+    fn run<'a>(
+        &'a mut self,
+        _ctx: &GraphContext<B>,
+        factory: &Factory<B>,
+        aux: &World,
+        frames: &'a Frames<B>,
+    ) -> &'a [Submit<B, SimultaneousUse>] {
+        #[cfg(feature = "profiler")]
+        profile_scope!("run");
+
+        let mut stats = WriteExpect::<'_, RenderStats>::fetch(aux);
+
+        // The graph waits for the frame that last used this slot before running.
+        let index = frames.next().index() as usize % self.frames.len();
+        let frame = &mut self.frames[index];
+        if frame.written {
+            let query = index as u32;
+            let mut data = [0; 8];
+            let available = unsafe {
+                factory.device().get_query_pool_results(
+                    &self.query_pool,
+                    query..query + 1,
+                    &mut data,
+                    8,
+                    hal::query::ResultFlags::BITS_64,
+                )
+            };
+            if let Ok(true) = available {
+                let ticks = u64::from_ne_bytes(data);
+                stats.record_timestamp(self.target, self.index, self.last, ticks);
+            }
+        }
+        frame.written = true;
+
+        std::slice::from_ref(&frame.submit)
+    }
+
+    unsafe fn dispose(mut self, factory: &mut Factory<B>, _aux: &World) {
+        self.command_pool.free_buffers(
+            self.frames
+                .drain(..)
+                .map(|frame| frame.command_buffer.mark_complete()),
+        );
+        factory.destroy_command_pool(self.command_pool);
+        factory.device().destroy_query_pool(self.query_pool);
+    }
+}
+
+/// Render group builder counting the draw calls of a render group into [RenderStats].
+#[derive(Debug)]
+pub(crate) struct CountedGroupBuilder<B: Backend> {
+    id: RenderGroupId,
+    order: i32,
+    inner: Box<dyn RenderGroupBuilder<B, World>>,
+}
+
+impl<B: Backend> CountedGroupBuilder<B> {
+    pub(crate) fn new(
+        id: RenderGroupId,
+        order: i32,
+        inner: Box<dyn RenderGroupBuilder<B, World>>,
+    ) -> Self {
+        Self { id, order, inner }
+    }
+}
+
+impl<B: Backend> RenderGroupBuilder<B, World> for CountedGroupBuilder<B> {
+    fn colors(&self) -> usize {
+        self.inner.colors()
+    }
+
+    fn depth(&self) -> bool {
+        self.inner.depth()
+    }
+
+    fn buffers(&self) -> Vec<(BufferId, BufferAccess)> {
+        self.inner.buffers()
+    }
+
+    fn images(&self) -> Vec<(ImageId, ImageAccess)> {
+        self.inner.images()
+    }
+
+    fn dependencies(&self) -> Vec<NodeId> {
+        self.inner.dependencies()
+    }
+
+    fn build<'a>(
+        self: Box<Self>,
+        ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        queue: QueueId,
+        aux: &World,
+        framebuffer_width: u32,
+        framebuffer_height: u32,
+        subpass: hal::pass::Subpass<'_, B>,
+        buffers: Vec<NodeBuffer>,
+        images: Vec<NodeImage>,
+    ) -> Result<Box<dyn RenderGroup<B, World>>, failure::Error> {
+        let inner = self.inner.build(
+            ctx,
+            factory,
+            queue,
+            aux,
+            framebuffer_width,
+            framebuffer_height,
+            subpass,
+            buffers,
+            images,
+        )?;
+        if let Some(mut stats) = <Option<Write<'_, RenderStats>>>::fetch(aux) {
+            stats.add_group(self.id, self.order);
+        }
+        Ok(Box::new(CountedGroup { id: self.id, inner }))
+    }
+}
+
+/// Render group marking the draw calls recorded by the group it wraps as its own in
+/// [RenderStats], for the group to count them with a [DrawCounter](crate::stats::DrawCounter).
+#[derive(Debug)]
+struct CountedGroup<B: Backend> {
+    id: RenderGroupId,
+    inner: Box<dyn RenderGroup<B, World>>,
+}
+
+impl<B: Backend> RenderGroup<B, World> for CountedGroup<B> {
+    fn prepare(
+        &mut self,
+        factory: &Factory<B>,
+        queue: QueueId,
+        index: usize,
+        subpass: hal::pass::Subpass<'_, B>,
+        aux: &World,
+    ) -> PrepareResult {
+        self.inner.prepare(factory, queue, index, subpass, aux)
+    }
+
+    fn draw_inline(
+        &mut self,
+        encoder: RenderPassEncoder<'_, B>,
+        index: usize,
+        subpass: hal::pass::Subpass<'_, B>,
+        aux: &World,
+    ) {
+        // The stats are released while the group records, for its `DrawCounter`.
+        if let Some(mut stats) = <Option<Write<'_, RenderStats>>>::fetch(aux) {
+            stats.begin_recording(self.id);
+        }
+        self.inner.draw_inline(encoder, index, subpass, aux);
+        if let Some(mut stats) = <Option<Write<'_, RenderStats>>>::fetch(aux) {
+            stats.end_recording();
+        }
+    }
+
+    fn dispose(self: Box<Self>, factory: &mut Factory<B>, aux: &World) {
+        if let Some(mut stats) = <Option<Write<'_, RenderStats>>>::fetch(aux) {
+            stats.remove_group(self.id);
+        }
+        self.inner.dispose(factory, aux);
+    }
+}
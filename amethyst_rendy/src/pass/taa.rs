@@ -0,0 +1,176 @@
+use crate::{bundle::TargetImage, pass::DrawPostProcessDesc, taa::TaaParams, types::Backend};
+use amethyst_core::ecs::World;
+use rendy::{
+    command::{
+        CommandBuffer, CommandPool, ExecutableState, Family, MultiShot, PendingState,
+        SimultaneousUse, Submit, Transfer,
+    },
+    factory::Factory,
+    frame::Frames,
+    graph::{
+        gfx_acquire_barriers, gfx_release_barriers, GraphContext, ImageAccess, Node, NodeBuffer,
+        NodeDesc, NodeImage, NodeSubmittable,
+    },
+    hal::{self, pso},
+};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+impl DrawPostProcessDesc {
+    /// Create instance of `DrawPostProcess` render group blending its first input with
+    /// the reprojected history image, as configured by [TaaParams].
+    ///
+    /// Sampled images are the current image, the `velocity` image drawn by `DrawVelocity`
+    /// and the history image, holding the resolved image of the previous frame.
+    pub fn taa_resolve(velocity: TargetImage) -> Self {
+        // History is undefined until the first resolved image is copied into it.
+        let history_ready = Arc::new(AtomicBool::new(false));
+        Self::new(super::TAA_RESOLVE_FRAGMENT.clone())
+            .with_input(velocity)
+            .with_sampled_image()
+            .with_constants(move |world| {
+                let params = TaaParams::fetch(world);
+                let ready = history_ready.swap(true, Ordering::Relaxed);
+                if params.enabled && ready {
+                    vec![params.history_weight]
+                } else {
+                    vec![0.0]
+                }
+            })
+    }
+}
+
+/// Describe copying the resolved image of temporal anti-aliasing into its history image.
+///
+/// The node copies the first image into the second one, which must have the same size and format.
+#[derive(Debug, Default)]
+pub struct TaaHistoryDesc;
+
+impl TaaHistoryDesc {
+    /// Create instance of `TaaHistory` node
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl<B: Backend> NodeDesc<B, World> for TaaHistoryDesc {
+    type Node = TaaHistory<B>;
+
+    fn images(&self) -> Vec<ImageAccess> {
+        vec![
+            ImageAccess {
+                access: hal::image::Access::TRANSFER_READ,
+                usage: hal::image::Usage::TRANSFER_SRC,
+                layout: hal::image::Layout::TransferSrcOptimal,
+                stages: pso::PipelineStage::TRANSFER,
+            },
+            ImageAccess {
+                access: hal::image::Access::TRANSFER_WRITE,
+                usage: hal::image::Usage::TRANSFER_DST,
+                layout: hal::image::Layout::TransferDstOptimal,
+                stages: pso::PipelineStage::TRANSFER,
+            },
+        ]
+    }
+
+    fn build<'a>(
+        self,
+        ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        family: &mut Family<B>,
+        _queue: usize,
+        _aux: &World,
+        buffers: Vec<NodeBuffer>,
+        images: Vec<NodeImage>,
+    ) -> Result<Self::Node, failure::Error> {
+        #[cfg(feature = "profiler")]
+        profile_scope!("build");
+
+        let resolved = ctx
+            .get_image(images[0].id)
+            .ok_or_else(|| failure::format_err!("Resolved image is not available"))?;
+        let history = ctx
+            .get_image(images[1].id)
+            .ok_or_else(|| failure::format_err!("History image is not available"))?;
+
+        let mut command_pool = factory
+            .create_command_pool(family)?
+            .with_capability::<Transfer>()
+            .expect("Graph builder must provide family with Transfer capability");
+
+        let command_buffer = command_pool.allocate_buffers(1).remove(0);
+        let mut recording = command_buffer.begin(MultiShot(SimultaneousUse), ());
+        let mut encoder = recording.encoder();
+        let layers = hal::image::SubresourceLayers {
+            aspects: hal::format::Aspects::COLOR,
+            level: 0,
+            layers: 0..1,
+        };
+        unsafe {
+            let (stages, barriers) = gfx_acquire_barriers(ctx, &buffers, &images);
+            encoder.pipeline_barrier(stages, hal::memory::Dependencies::empty(), barriers);
+            encoder.copy_image(
+                resolved.raw(),
+                images[0].layout,
+                history.raw(),
+                images[1].layout,
+                Some(hal::command::ImageCopy {
+                    src_subresource: layers.clone(),
+                    src_offset: hal::image::Offset::ZERO,
+                    dst_subresource: layers,
+                    dst_offset: hal::image::Offset::ZERO,
+                    extent: resolved.kind().extent(),
+                }),
+            );
+            let (stages, barriers) = gfx_release_barriers(ctx, &buffers, &images);
+            encoder.pipeline_barrier(stages, hal::memory::Dependencies::empty(), barriers);
+        }
+        let (submit, command_buffer) = recording.finish().submit();
+
+        Ok(TaaHistory {
+            command_pool,
+            command_buffer,
+            submit,
+        })
+    }
+}
+
+/// Copies the resolved image of temporal anti-aliasing into its history image every frame.
+#[derive(Debug)]
+pub struct TaaHistory<B: Backend> {
+    command_pool: CommandPool<B, Transfer>,
+    command_buffer:
+        CommandBuffer<B, Transfer, PendingState<ExecutableState<MultiShot<SimultaneousUse>>>>,
+    submit: Submit<B, SimultaneousUse>,
+}
+
+impl<'a, B: Backend> NodeSubmittable<'a, B> for TaaHistory<B> {
+    type Submittable = &'a Submit<B, SimultaneousUse>;
+    type Submittables = &'a [Submit<B, SimultaneousUse>];
+}
+
+impl<B: Backend> Node<B, World> for TaaHistory<B> {
+    type Capability = Transfer;
+    type Desc = TaaHistoryDesc;
+
+    fn run<'a>(
+        &'a mut self,
+        _ctx: &GraphContext<B>,
+        _factory: &Factory<B>,
+        _aux: &World,
+        _frames: &'a Frames<B>,
+    ) -> &'a [Submit<B, SimultaneousUse>] {
+        std::slice::from_ref(&self.submit)
+    }
+
+    unsafe fn dispose(mut self, factory: &mut Factory<B>, _aux: &World) {
+        self.command_pool
+            .free_buffers(Some(self.command_buffer.mark_complete()));
+        factory.destroy_command_pool(self.command_pool);
+    }
+}
@@ -0,0 +1,412 @@
+use crate::{
+    pass::DrawPostProcessDesc, pipeline::PipelineCache, pod::ExposureArgs,
+    shader_reload::ReloadableShader, tonemap::ToneMapParams, types::Backend, util,
+};
+use amethyst_core::{
+    ecs::{Read, SystemData, World},
+    timing::Time,
+};
+use glsl_layout::AsStd140;
+use rendy::{
+    command::{
+        CommandBuffer, CommandPool, Compute, ExecutableState, Family, MultiShot, PendingState,
+        QueueId, SimultaneousUse, Submit,
+    },
+    factory::{BufferState, Factory},
+    frame::Frames,
+    graph::{
+        gfx_acquire_barriers, gfx_release_barriers, BufferAccess, GraphContext, ImageAccess, Node,
+        NodeBuffer, NodeDesc, NodeImage, NodeSubmittable,
+    },
+    hal::{self, device::Device, format::Swizzle, pso},
+    memory::{Data, Dynamic, Write},
+    resource::{
+        Buffer, BufferInfo, DescriptorSet, DescriptorSetLayout, Escape, Filter,
+        Handle as RendyHandle, ImageView, ImageViewInfo, Sampler, SamplerInfo, ViewKind, WrapMode,
+    },
+    shader::Shader,
+};
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+/// Number of bins of the luminance histogram.
+const HISTOGRAM_BINS: u64 = 256;
+
+/// Size of the buffer holding adapted average luminance written by `AutoExposure`.
+pub const EXPOSURE_BUFFER_SIZE: u64 = 16;
+
+/// Luminance assumed before the first frame is measured, mapped to unchanged exposure.
+const INITIAL_LUMINANCE: f32 = 0.18;
+
+/// Workgroup size of the histogram compute shader in each dimension.
+const HISTOGRAM_GROUP_SIZE: u32 = 16;
+
+impl DrawPostProcessDesc {
+    /// Create instance of `DrawPostProcess` render group tonemapping its input
+    /// with the [ToneMapParams] operator and exposure.
+    ///
+    /// With `auto_exposure`, the group reads average luminance from a storage buffer
+    /// written by [AutoExposure], which must be passed to the group builder with `with_buffer`.
+    pub fn tonemap(auto_exposure: bool) -> Self {
+        let desc = if auto_exposure {
+            Self::new(super::TONEMAP_AUTO_EXPOSURE_FRAGMENT.clone()).with_storage_buffer()
+        } else {
+            Self::new(super::TONEMAP_FRAGMENT.clone())
+        };
+        desc.with_constants(|world| {
+            let params = ToneMapParams::fetch(world);
+            vec![params.operator.index(), params.exposure_scale()]
+        })
+    }
+}
+
+/// Describe computing average luminance of an image with a histogram on the GPU.
+///
+/// The node samples a single image and writes the luminance, adapted over time
+/// with [ToneMapParams] adaptation speed, to a buffer of `EXPOSURE_BUFFER_SIZE` bytes.
+#[derive(Debug, Default)]
+pub struct AutoExposureDesc;
+
+impl AutoExposureDesc {
+    /// Create instance of `AutoExposure` compute node
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl<B: Backend> NodeDesc<B, World> for AutoExposureDesc {
+    type Node = AutoExposure<B>;
+
+    fn buffers(&self) -> Vec<BufferAccess> {
+        vec![BufferAccess {
+            access: hal::buffer::Access::SHADER_READ | hal::buffer::Access::SHADER_WRITE,
+            usage: hal::buffer::Usage::STORAGE | hal::buffer::Usage::TRANSFER_DST,
+            stages: pso::PipelineStage::COMPUTE_SHADER,
+        }]
+    }
+
+    fn images(&self) -> Vec<ImageAccess> {
+        vec![ImageAccess {
+            access: hal::image::Access::SHADER_READ,
+            usage: hal::image::Usage::SAMPLED,
+            layout: hal::image::Layout::ShaderReadOnlyOptimal,
+            stages: pso::PipelineStage::COMPUTE_SHADER,
+        }]
+    }
+
+    fn build<'a>(
+        self,
+        ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        family: &mut Family<B>,
+        queue: usize,
+        aux: &World,
+        buffers: Vec<NodeBuffer>,
+        images: Vec<NodeImage>,
+    ) -> Result<Self::Node, failure::Error> {
+        #[cfg(feature = "profiler")]
+        profile_scope!("build");
+
+        let exposure = ctx
+            .get_buffer(buffers[0].id)
+            .ok_or_else(|| failure::format_err!("Exposure buffer is not available"))?;
+        let source = ctx
+            .get_image(images[0].id)
+            .ok_or_else(|| failure::format_err!("Auto exposure input image is not available"))?;
+        let extent = source.kind().extent();
+
+        let compute_state = BufferState {
+            queue: QueueId {
+                index: queue,
+                family: family.id(),
+            },
+            stage: pso::PipelineStage::COMPUTE_SHADER,
+            access: hal::buffer::Access::SHADER_READ | hal::buffer::Access::SHADER_WRITE,
+        };
+        let histogram = factory.create_buffer(
+            BufferInfo {
+                size: HISTOGRAM_BINS * 4,
+                usage: hal::buffer::Usage::STORAGE | hal::buffer::Usage::TRANSFER_DST,
+            },
+            Data,
+        )?;
+        unsafe {
+            factory.upload_buffer(
+                &histogram,
+                0,
+                &[0u32; HISTOGRAM_BINS as usize],
+                None,
+                compute_state,
+            )?;
+            factory.upload_buffer(exposure, 0, &[INITIAL_LUMINANCE; 4], None, compute_state)?;
+        }
+
+        let view = factory.create_image_view(
+            source.clone(),
+            ImageViewInfo {
+                view_kind: ViewKind::D2,
+                format: source.format(),
+                swizzle: Swizzle::NO,
+                range: hal::image::SubresourceRange {
+                    aspects: hal::format::Aspects::COLOR,
+                    levels: 0..1,
+                    layers: 0..1,
+                },
+            },
+        )?;
+        let sampler = factory.get_sampler(SamplerInfo::new(Filter::Nearest, WrapMode::Clamp))?;
+
+        let layout: RendyHandle<DescriptorSetLayout<B>> = set_layout! {
+            factory,
+            [1] CombinedImageSampler pso::ShaderStageFlags::COMPUTE,
+            [2] StorageBuffer pso::ShaderStageFlags::COMPUTE,
+            [1] UniformBuffer pso::ShaderStageFlags::COMPUTE
+        };
+        let pipeline_layout = unsafe {
+            factory
+                .device()
+                .create_pipeline_layout(Some(layout.raw()), None as Option<(_, _)>)
+        }?;
+        let pipeline_cache = aux.try_fetch::<PipelineCache<B>>();
+        let cache = pipeline_cache.as_ref().and_then(|cache| cache.raw());
+        let histogram_pipeline = build_compute_pipeline(
+            factory,
+            cache,
+            &pipeline_layout,
+            &super::LUMINANCE_HISTOGRAM_COMPUTE,
+        )?;
+        let average_pipeline = build_compute_pipeline(
+            factory,
+            cache,
+            &pipeline_layout,
+            &super::EXPOSURE_AVERAGE_COMPUTE,
+        )?;
+
+        let mut command_pool = factory
+            .create_command_pool(family)?
+            .with_capability::<Compute>()
+            .expect("Graph builder must provide family with Compute capability");
+
+        // Each frame in flight has its own uniform and command buffer recorded once.
+        let mut frames = Vec::new();
+        for command_buffer in command_pool.allocate_buffers(ctx.frames_in_flight as usize) {
+            let args = factory.create_buffer(
+                BufferInfo {
+                    size: std::mem::size_of::<<ExposureArgs as AsStd140>::Std140>() as u64,
+                    usage: hal::buffer::Usage::UNIFORM,
+                },
+                Dynamic,
+            )?;
+            let set = factory.create_descriptor_set(layout.clone())?;
+            unsafe {
+                factory.write_descriptor_sets(vec![
+                    util::desc_write(
+                        set.raw(),
+                        0,
+                        pso::Descriptor::CombinedImageSampler(
+                            view.raw(),
+                            hal::image::Layout::ShaderReadOnlyOptimal,
+                            sampler.raw(),
+                        ),
+                    ),
+                    util::desc_write(
+                        set.raw(),
+                        1,
+                        pso::Descriptor::Buffer(histogram.raw(), None..None),
+                    ),
+                    util::desc_write(
+                        set.raw(),
+                        2,
+                        pso::Descriptor::Buffer(exposure.raw(), None..None),
+                    ),
+                    util::desc_write(
+                        set.raw(),
+                        3,
+                        pso::Descriptor::Buffer(args.raw(), None..None),
+                    ),
+                ]);
+            }
+
+            let mut recording = command_buffer.begin(MultiShot(SimultaneousUse), ());
+            let mut encoder = recording.encoder();
+            // Histogram must be cleared by the previous frame before counting.
+            let shader_write =
+                pso::PipelineStage::COMPUTE_SHADER..pso::PipelineStage::COMPUTE_SHADER;
+            let histogram_barrier = || {
+                hal::memory::Barrier::whole_buffer(
+                    histogram.raw(),
+                    hal::buffer::Access::SHADER_READ | hal::buffer::Access::SHADER_WRITE
+                        ..hal::buffer::Access::SHADER_READ | hal::buffer::Access::SHADER_WRITE,
+                )
+            };
+            unsafe {
+                let (stages, barriers) = gfx_acquire_barriers(ctx, &buffers, &images);
+                encoder.pipeline_barrier(stages, hal::memory::Dependencies::empty(), barriers);
+                encoder.pipeline_barrier(
+                    shader_write.clone(),
+                    hal::memory::Dependencies::empty(),
+                    Some(histogram_barrier()),
+                );
+
+                encoder.bind_compute_pipeline(&histogram_pipeline);
+                encoder.bind_compute_descriptor_sets(
+                    &pipeline_layout,
+                    0,
+                    Some(set.raw()),
+                    std::iter::empty(),
+                );
+                encoder.dispatch(
+                    (extent.width + HISTOGRAM_GROUP_SIZE - 1) / HISTOGRAM_GROUP_SIZE,
+                    (extent.height + HISTOGRAM_GROUP_SIZE - 1) / HISTOGRAM_GROUP_SIZE,
+                    1,
+                );
+                encoder.pipeline_barrier(
+                    shader_write,
+                    hal::memory::Dependencies::empty(),
+                    Some(histogram_barrier()),
+                );
+
+                encoder.bind_compute_pipeline(&average_pipeline);
+                encoder.dispatch(1, 1, 1);
+
+                let (stages, barriers) = gfx_release_barriers(ctx, &buffers, &images);
+                encoder.pipeline_barrier(stages, hal::memory::Dependencies::empty(), barriers);
+            }
+
+            let (submit, command_buffer) = recording.finish().submit();
+            frames.push(AutoExposureFrame {
+                args,
+                set,
+                command_buffer,
+                submit,
+            });
+        }
+
+        Ok(AutoExposure {
+            pipeline_layout,
+            histogram_pipeline,
+            average_pipeline,
+            command_pool,
+            frames,
+            _layout: layout,
+            _histogram: histogram,
+            _view: view,
+            _sampler: sampler,
+        })
+    }
+}
+
+#[derive(Debug)]
+struct AutoExposureFrame<B: Backend> {
+    args: Escape<Buffer<B>>,
+    set: Escape<DescriptorSet<B>>,
+    command_buffer:
+        CommandBuffer<B, Compute, PendingState<ExecutableState<MultiShot<SimultaneousUse>>>>,
+    submit: Submit<B, SimultaneousUse>,
+}
+
+/// Computes a luminance histogram of an image and adapts average luminance towards its mean.
+#[derive(Debug)]
+pub struct AutoExposure<B: Backend> {
+    pipeline_layout: B::PipelineLayout,
+    histogram_pipeline: B::ComputePipeline,
+    average_pipeline: B::ComputePipeline,
+    command_pool: CommandPool<B, Compute>,
+    frames: Vec<AutoExposureFrame<B>>,
+    _layout: RendyHandle<DescriptorSetLayout<B>>,
+    _histogram: Escape<Buffer<B>>,
+    _view: Escape<ImageView<B>>,
+    _sampler: RendyHandle<Sampler<B>>,
+}
+
+impl<'a, B: Backend> NodeSubmittable<'a, B> for AutoExposure<B> {
+    type Submittable = &'a Submit<B, SimultaneousUse>;
+    type Submittables = &'a [Submit<B, SimultaneousUse>];
+}
+
+impl<B: Backend> Node<B, World> for AutoExposure<B> {
+    type Capability = Compute;
+    type Desc = AutoExposureDesc;
+
+    fn run<'a>(
+        &'a mut self,
+        _ctx: &GraphContext<B>,
+        factory: &Factory<B>,
+        aux: &World,
+        frames: &'a Frames<B>,
+    ) -> &'a [Submit<B, SimultaneousUse>] {
+        #[cfg(feature = "profiler")]
+        profile_scope!("run");
+
+        let params = ToneMapParams::fetch(aux);
+        let delta_time = <Option<Read<'_, Time>>>::fetch(aux).map_or(0.0, |t| t.delta_seconds());
+        let args = ExposureArgs {
+            min_log_luminance: params.min_log_luminance,
+            log_luminance_range: (params.max_log_luminance - params.min_log_luminance)
+                .max(std::f32::EPSILON),
+            delta_time,
+            adaptation_speed: params.adaptation_speed,
+        }
+        .std140();
+
+        // The graph waits for the frame that last used this slot before running.
+        let index = frames.next().index() as usize % self.frames.len();
+        let frame = &mut self.frames[index];
+        let range = 0..frame.args.size();
+        let mut mapped = frame.args.map(factory.device(), range.clone()).unwrap();
+        unsafe {
+            let mut writer = mapped.write::<u8>(factory.device(), range).unwrap();
+            writer
+                .slice()
+                .copy_from_slice(util::slice_as_bytes(&[args]));
+        }
+
+        std::slice::from_ref(&self.frames[index].submit)
+    }
+
+    unsafe fn dispose(mut self, factory: &mut Factory<B>, _aux: &World) {
+        for frame in self.frames.drain(..) {
+            self.command_pool
+                .free_buffers(Some(frame.command_buffer.mark_complete()));
+        }
+        factory.destroy_command_pool(self.command_pool);
+        factory
+            .device()
+            .destroy_compute_pipeline(self.histogram_pipeline);
+        factory
+            .device()
+            .destroy_compute_pipeline(self.average_pipeline);
+        factory
+            .device()
+            .destroy_pipeline_layout(self.pipeline_layout);
+    }
+}
+
+pub(super) fn build_compute_pipeline<B: Backend>(
+    factory: &Factory<B>,
+    cache: Option<&B::PipelineCache>,
+    pipeline_layout: &B::PipelineLayout,
+    shader: &ReloadableShader,
+) -> Result<B::ComputePipeline, failure::Error> {
+    let module = unsafe { shader.module(factory) }?;
+    let pipeline = unsafe {
+        factory.device().create_compute_pipeline(
+            &pso::ComputePipelineDesc {
+                shader: pso::EntryPoint {
+                    entry: "main",
+                    module: &module,
+                    specialization: pso::Specialization::default(),
+                },
+                layout: pipeline_layout,
+                flags: pso::PipelineCreationFlags::empty(),
+                parent: pso::BasePipeline::None,
+            },
+            cache,
+        )
+    };
+    unsafe {
+        factory.destroy_shader_module(module);
+    }
+    Ok(pipeline?)
+}
@@ -0,0 +1,309 @@
+use crate::{
+    batch::{GroupIterator, OneLevelBatch},
+    pipeline::{PipelineCache, PipelineDescBuilder, PipelinesBuilder},
+    pod::{VelocityArgs, VelocityVertexArgs},
+    skinning::JointTransforms,
+    stats::DrawCounter,
+    submodules::{gather::CameraGatherer, DynamicUniform, DynamicVertexBuffer},
+    transparent::Transparent,
+    types::{Backend, Mesh},
+    util,
+};
+use amethyst_assets::{AssetStorage, Handle};
+use amethyst_core::{
+    ecs::{Entities, Entity, Join, Read, ReadStorage, SystemData, World},
+    math::{convert, Matrix4},
+    transform::Transform,
+    Hidden, HiddenPropagate,
+};
+use derivative::Derivative;
+use fnv::FnvHashMap;
+use glsl_layout::*;
+use rendy::{
+    command::{QueueId, RenderPassEncoder},
+    factory::Factory,
+    graph::{
+        render::{PrepareResult, RenderGroup, RenderGroupDesc},
+        GraphContext, NodeBuffer, NodeImage,
+    },
+    hal::{self, device::Device, pso},
+    mesh::{AsVertex, Position, VertexFormat},
+    shader::Shader,
+};
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+/// Draw screen-space motion of opaque static meshes since the previous frame,
+/// used by temporal anti-aliasing to reproject the image history.
+#[derive(Clone, Debug, PartialEq, Derivative)]
+#[derivative(Default(bound = ""))]
+pub struct DrawVelocityDesc;
+
+impl DrawVelocityDesc {
+    /// Create instance of `DrawVelocity` render group
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl<B: Backend> RenderGroupDesc<B, World> for DrawVelocityDesc {
+    fn build(
+        self,
+        _ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        _queue: QueueId,
+        aux: &World,
+        framebuffer_width: u32,
+        framebuffer_height: u32,
+        subpass: hal::pass::Subpass<'_, B>,
+        _buffers: Vec<NodeBuffer>,
+        _images: Vec<NodeImage>,
+    ) -> Result<Box<dyn RenderGroup<B, World>>, failure::Error> {
+        #[cfg(feature = "profiler")]
+        profile_scope!("build");
+
+        let env = DynamicUniform::new(factory, pso::ShaderStageFlags::VERTEX)?;
+        let pipeline_cache = aux.try_fetch::<PipelineCache<B>>();
+        let (pipeline, pipeline_layout) = build_velocity_pipeline(
+            factory,
+            pipeline_cache.as_ref().and_then(|cache| cache.raw()),
+            subpass,
+            framebuffer_width,
+            framebuffer_height,
+            vec![env.raw_layout()],
+        )?;
+
+        Ok(Box::new(DrawVelocity::<B> {
+            pipeline,
+            pipeline_layout,
+            env,
+            batches: Default::default(),
+            models: DynamicVertexBuffer::new(),
+            vertex_format: vec![Position::vertex()],
+            prev_models: FnvHashMap::default(),
+            prev_view: None,
+        }))
+    }
+}
+
+/// Draws the motion of opaque static meshes in texture coordinates per frame.
+///
+/// Model matrices of the previous frame are remembered per entity, so both camera and
+/// object movement are captured. Skinned meshes are not drawn.
+#[derive(Debug)]
+pub struct DrawVelocity<B: Backend> {
+    pipeline: B::GraphicsPipeline,
+    pipeline_layout: B::PipelineLayout,
+    env: DynamicUniform<B, VelocityArgs>,
+    batches: OneLevelBatch<u32, VelocityVertexArgs>,
+    models: DynamicVertexBuffer<B, VelocityVertexArgs>,
+    vertex_format: Vec<VertexFormat>,
+    prev_models: FnvHashMap<Entity, mat4>,
+    prev_view: Option<(mat4, vec2)>,
+}
+
+impl<B: Backend> RenderGroup<B, World> for DrawVelocity<B> {
+    fn prepare(
+        &mut self,
+        factory: &Factory<B>,
+        _queue: QueueId,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        resources: &World,
+    ) -> PrepareResult {
+        #[cfg(feature = "profiler")]
+        profile_scope!("prepare");
+
+        let (
+            entities,
+            mesh_storage,
+            meshes,
+            transforms,
+            joints,
+            transparent,
+            hiddens,
+            hiddens_prop,
+        ) = <(
+            Entities<'_>,
+            Read<'_, AssetStorage<Mesh>>,
+            ReadStorage<'_, Handle<Mesh>>,
+            ReadStorage<'_, Transform>,
+            ReadStorage<'_, JointTransforms>,
+            ReadStorage<'_, Transparent>,
+            ReadStorage<'_, Hidden>,
+            ReadStorage<'_, HiddenPropagate>,
+        )>::fetch(resources);
+
+        let camera = CameraGatherer::gather(resources);
+        let view = (
+            camera.projview.proj_view,
+            [camera.jitter.x, camera.jitter.y].into(),
+        );
+        let (prev_proj_view, prev_jitter) = self.prev_view.unwrap_or(view);
+        self.prev_view = Some(view);
+        self.env.write(
+            factory,
+            index,
+            VelocityArgs {
+                proj_view: view.0,
+                prev_proj_view,
+                jitter: view.1,
+                prev_jitter,
+            }
+            .std140(),
+        );
+
+        let prev_models = &self.prev_models;
+        let mut models = FnvHashMap::default();
+        self.batches.clear_inner();
+        {
+            let batches = &mut self.batches;
+            (
+                &entities,
+                &meshes,
+                &transforms,
+                !&joints,
+                !&transparent,
+                !&hiddens,
+                !&hiddens_prop,
+            )
+                .join()
+                .map(|(entity, mesh, transform, _, _, _, _)| {
+                    let model: [[f32; 4]; 4] =
+                        convert::<_, Matrix4<f32>>(*transform.global_matrix()).into();
+                    let model: mat4 = model.into();
+                    models.insert(entity, model);
+                    let prev_model = prev_models.get(&entity).copied().unwrap_or(model);
+                    (mesh.id(), VelocityVertexArgs { model, prev_model })
+                })
+                .for_each_group(|mesh_id, data| {
+                    if mesh_storage.contains_id(mesh_id) {
+                        batches.insert(mesh_id, data.drain(..));
+                    }
+                });
+        }
+        self.batches.prune();
+        self.prev_models = models;
+
+        self.models.write(
+            factory,
+            index,
+            self.batches.count() as u64,
+            self.batches.data(),
+        );
+
+        PrepareResult::DrawRecord
+    }
+
+    fn draw_inline(
+        &mut self,
+        mut encoder: RenderPassEncoder<'_, B>,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        resources: &World,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("draw");
+
+        if self.batches.count() == 0 {
+            return;
+        }
+
+        let mesh_storage = <Read<'_, AssetStorage<Mesh>>>::fetch(resources);
+
+        encoder.bind_graphics_pipeline(&self.pipeline);
+        self.env.bind(index, &self.pipeline_layout, 0, &mut encoder);
+        if !self
+            .models
+            .bind(index, self.vertex_format.len() as u32, 0, &mut encoder)
+        {
+            return;
+        }
+
+        let mut draws = DrawCounter::fetch(resources);
+        for (&mesh_id, range) in self.batches.iter() {
+            if let Some(mesh) = B::unwrap_mesh(unsafe { mesh_storage.get_by_id_unchecked(mesh_id) })
+            {
+                // Meshes without positions don't have any motion to draw.
+                let instances = range.end - range.start;
+                if mesh
+                    .bind_and_draw(0, &self.vertex_format, range, &mut encoder)
+                    .is_ok()
+                {
+                    draws.draw(mesh.primitive(), mesh.len(), instances);
+                }
+            }
+        }
+    }
+
+    fn dispose(self: Box<Self>, factory: &mut Factory<B>, _aux: &World) {
+        unsafe {
+            factory.device().destroy_graphics_pipeline(self.pipeline);
+            factory
+                .device()
+                .destroy_pipeline_layout(self.pipeline_layout);
+        }
+    }
+}
+
+fn build_velocity_pipeline<B: Backend>(
+    factory: &Factory<B>,
+    cache: Option<&B::PipelineCache>,
+    subpass: hal::pass::Subpass<'_, B>,
+    framebuffer_width: u32,
+    framebuffer_height: u32,
+    layouts: Vec<&B::DescriptorSetLayout>,
+) -> Result<(B::GraphicsPipeline, B::PipelineLayout), failure::Error> {
+    let pipeline_layout = unsafe {
+        factory
+            .device()
+            .create_pipeline_layout(layouts, None as Option<(_, _)>)
+    }?;
+
+    let shader_vertex = unsafe { super::VELOCITY_VERTEX.module(factory).unwrap() };
+    let shader_fragment = unsafe { super::VELOCITY_FRAGMENT.module(factory).unwrap() };
+
+    let pipes = PipelinesBuilder::new()
+        .with_pipeline(
+            PipelineDescBuilder::new()
+                .with_vertex_desc(&[
+                    (Position::vertex(), pso::VertexInputRate::Vertex),
+                    (
+                        VelocityVertexArgs::vertex(),
+                        pso::VertexInputRate::Instance(1),
+                    ),
+                ])
+                .with_shaders(util::simple_shader_set(
+                    &shader_vertex,
+                    Some(&shader_fragment),
+                ))
+                .with_layout(&pipeline_layout)
+                .with_subpass(subpass)
+                .with_framebuffer_size(framebuffer_width, framebuffer_height)
+                .with_depth_test(pso::DepthTest {
+                    fun: pso::Comparison::Greater,
+                    write: true,
+                })
+                .with_blend_targets(vec![pso::ColorBlendDesc {
+                    mask: pso::ColorMask::ALL,
+                    blend: None,
+                }]),
+        )
+        .build_cached(factory, cache);
+
+    unsafe {
+        factory.destroy_shader_module(shader_vertex);
+        factory.destroy_shader_module(shader_fragment);
+    }
+
+    match pipes {
+        Err(e) => {
+            unsafe {
+                factory.device().destroy_pipeline_layout(pipeline_layout);
+            }
+            Err(e)
+        }
+        Ok(mut pipes) => Ok((pipes.remove(0), pipeline_layout)),
+    }
+}
@@ -0,0 +1,324 @@
+use crate::{
+    debug_drawing::VertexNormalsDebug,
+    pipeline::{PipelineCache, PipelineDescBuilder, PipelinesBuilder},
+    pod::ViewArgs,
+    stats::DrawCounter,
+    submodules::{gather::CameraGatherer, DynamicUniform},
+    types::{Backend, Mesh},
+    util,
+};
+use amethyst_assets::{AssetStorage, Handle};
+use amethyst_core::{
+    ecs::{Join, Read, ReadStorage, SystemData, World},
+    transform::Transform,
+    Hidden, HiddenPropagate,
+};
+use derivative::Derivative;
+use rendy::{
+    command::{QueueId, RenderPassEncoder},
+    factory::Factory,
+    graph::{
+        render::{PrepareResult, RenderGroup, RenderGroupDesc},
+        GraphContext, NodeBuffer, NodeImage,
+    },
+    hal::{self, device::Device, pso},
+    mesh::{AsVertex, Normal, Position, Tangent, VertexFormat},
+    shader::Shader,
+};
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+/// Size of the push constants of the vertex normals shaders:
+/// a model matrix followed by the length of the lines.
+const PUSH_CONSTANTS_SIZE: u32 = 17;
+
+/// Draw the vertex normals, tangents and bitangents of meshes with a
+/// [VertexNormalsDebug] component as lines.
+#[derive(Clone, Debug, PartialEq, Derivative)]
+#[derivative(Default(bound = ""))]
+pub struct DrawVertexNormalsDesc;
+
+impl DrawVertexNormalsDesc {
+    /// Create instance of `DrawVertexNormals` render group
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl<B: Backend> RenderGroupDesc<B, World> for DrawVertexNormalsDesc {
+    fn build(
+        self,
+        _ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        _queue: QueueId,
+        world: &World,
+        framebuffer_width: u32,
+        framebuffer_height: u32,
+        subpass: hal::pass::Subpass<'_, B>,
+        _buffers: Vec<NodeBuffer>,
+        _images: Vec<NodeImage>,
+    ) -> Result<Box<dyn RenderGroup<B, World>>, failure::Error> {
+        #[cfg(feature = "profiler")]
+        profile_scope!("build");
+
+        let env = DynamicUniform::new(factory, pso::ShaderStageFlags::VERTEX)?;
+
+        let normals_format = vec![Position::vertex(), Normal::vertex()];
+        let tangents_format = vec![Position::vertex(), Normal::vertex(), Tangent::vertex()];
+
+        let pipeline_cache = world.try_fetch::<PipelineCache<B>>();
+        let (normals_pipeline, tangents_pipeline, pipeline_layout) =
+            build_vertex_normals_pipelines(
+                factory,
+                pipeline_cache.as_ref().and_then(|cache| cache.raw()),
+                subpass,
+                framebuffer_width,
+                framebuffer_height,
+                &normals_format,
+                &tangents_format,
+                vec![env.raw_layout()],
+            )?;
+
+        let mut normals_format = normals_format;
+        let mut tangents_format = tangents_format;
+        normals_format.sort();
+        tangents_format.sort();
+
+        Ok(Box::new(DrawVertexNormals::<B> {
+            normals_pipeline,
+            tangents_pipeline,
+            pipeline_layout,
+            env,
+            normals_format,
+            tangents_format,
+            meshes: Vec::new(),
+        }))
+    }
+}
+
+/// Mesh drawn by `DrawVertexNormals`, with the push constants of its lines.
+#[derive(Debug)]
+struct VertexNormalsMesh {
+    mesh_id: u32,
+    constants: [u32; PUSH_CONSTANTS_SIZE as usize],
+    tangents: bool,
+    vertex_count: Option<u32>,
+}
+
+/// Draws one line per vertex and vector of every mesh with a `VertexNormalsDebug` component.
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""))]
+pub struct DrawVertexNormals<B: Backend> {
+    normals_pipeline: B::GraphicsPipeline,
+    tangents_pipeline: B::GraphicsPipeline,
+    pipeline_layout: B::PipelineLayout,
+    env: DynamicUniform<B, ViewArgs>,
+    normals_format: Vec<VertexFormat>,
+    tangents_format: Vec<VertexFormat>,
+    meshes: Vec<VertexNormalsMesh>,
+}
+
+impl<B: Backend> RenderGroup<B, World> for DrawVertexNormals<B> {
+    fn prepare(
+        &mut self,
+        factory: &Factory<B>,
+        _queue: QueueId,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        world: &World,
+    ) -> PrepareResult {
+        #[cfg(feature = "profiler")]
+        profile_scope!("prepare");
+
+        let (mesh_storage, meshes, debugs, transforms, hiddens, hidden_props) =
+            <(
+                Read<'_, AssetStorage<Mesh>>,
+                ReadStorage<'_, Handle<Mesh>>,
+                ReadStorage<'_, VertexNormalsDebug>,
+                ReadStorage<'_, Transform>,
+                ReadStorage<'_, Hidden>,
+                ReadStorage<'_, HiddenPropagate>,
+            )>::fetch(world);
+
+        let camera = CameraGatherer::gather(world);
+        self.env.write(factory, index, camera.projview);
+
+        self.meshes.clear();
+        for (mesh, debug, transform, _, _) in
+            (&meshes, &debugs, &transforms, !&hiddens, !&hidden_props).join()
+        {
+            if !mesh_storage.contains_id(mesh.id()) {
+                continue;
+            }
+            let model: [[f32; 4]; 4] = (*transform.global_matrix()).into();
+            let mut constants = [0; PUSH_CONSTANTS_SIZE as usize];
+            for (constant, value) in constants
+                .iter_mut()
+                .zip(model.iter().flatten().chain(Some(&debug.length)))
+            {
+                *constant = value.to_bits();
+            }
+            self.meshes.push(VertexNormalsMesh {
+                mesh_id: mesh.id(),
+                constants,
+                tangents: debug.tangents,
+                vertex_count: debug.vertex_count,
+            });
+        }
+
+        PrepareResult::DrawRecord
+    }
+
+    fn draw_inline(
+        &mut self,
+        mut encoder: RenderPassEncoder<'_, B>,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        world: &World,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("draw");
+
+        if self.meshes.is_empty() {
+            return;
+        }
+
+        let mesh_storage = <Read<'_, AssetStorage<Mesh>>>::fetch(world);
+        let layout = &self.pipeline_layout;
+
+        // Both pipelines share the layout, so the environment stays bound when switching.
+        encoder.bind_graphics_pipeline(&self.normals_pipeline);
+        self.env.bind(index, layout, 0, &mut encoder);
+        let mut tangents_bound = false;
+        let mut draws = DrawCounter::fetch(world);
+
+        for item in &self.meshes {
+            let mesh =
+                match B::unwrap_mesh(unsafe { mesh_storage.get_by_id_unchecked(item.mesh_id) }) {
+                    Some(mesh) => mesh,
+                    None => continue,
+                };
+
+            // Meshes without tangents only get their normals drawn, and meshes without
+            // normals nothing.
+            let bound = if item.tangents {
+                mesh.bind(0, &self.tangents_format, &mut encoder)
+                    .ok()
+                    .map(|len| (len, true))
+            } else {
+                None
+            };
+            let (len, tangents) = match bound.or_else(|| {
+                mesh.bind(0, &self.normals_format, &mut encoder)
+                    .ok()
+                    .map(|len| (len, false))
+            }) {
+                Some(bound) => bound,
+                None => continue,
+            };
+            if tangents != tangents_bound {
+                encoder.bind_graphics_pipeline(if tangents {
+                    &self.tangents_pipeline
+                } else {
+                    &self.normals_pipeline
+                });
+                tangents_bound = tangents;
+            }
+
+            let lines = if tangents { 3 } else { 1 };
+            unsafe {
+                encoder.push_constants(layout, pso::ShaderStageFlags::VERTEX, 0, &item.constants);
+                encoder.draw(0..lines * 2, 0..item.vertex_count.unwrap_or(len));
+            }
+            draws.draw(
+                hal::Primitive::LineList,
+                lines * 2,
+                item.vertex_count.unwrap_or(len),
+            );
+        }
+    }
+
+    fn dispose(self: Box<Self>, factory: &mut Factory<B>, _world: &World) {
+        unsafe {
+            factory
+                .device()
+                .destroy_graphics_pipeline(self.normals_pipeline);
+            factory
+                .device()
+                .destroy_graphics_pipeline(self.tangents_pipeline);
+            factory
+                .device()
+                .destroy_pipeline_layout(self.pipeline_layout);
+        }
+    }
+}
+
+fn build_vertex_normals_pipelines<B: Backend>(
+    factory: &Factory<B>,
+    cache: Option<&B::PipelineCache>,
+    subpass: hal::pass::Subpass<'_, B>,
+    framebuffer_width: u32,
+    framebuffer_height: u32,
+    normals_format: &[VertexFormat],
+    tangents_format: &[VertexFormat],
+    layouts: Vec<&B::DescriptorSetLayout>,
+) -> Result<(B::GraphicsPipeline, B::GraphicsPipeline, B::PipelineLayout), failure::Error> {
+    let pipeline_layout = unsafe {
+        factory.device().create_pipeline_layout(
+            layouts,
+            Some((pso::ShaderStageFlags::VERTEX, 0..PUSH_CONSTANTS_SIZE * 4)),
+        )
+    }?;
+
+    let shader_normals = unsafe { super::VERTEX_NORMALS_VERTEX.module(factory).unwrap() };
+    let shader_tangents = unsafe { super::VERTEX_TANGENTS_VERTEX.module(factory).unwrap() };
+    let shader_fragment = unsafe { super::VERTEX_NORMALS_FRAGMENT.module(factory).unwrap() };
+
+    // Every vertex of the mesh is an instance of its lines.
+    let instanced = |formats: &[VertexFormat]| {
+        formats
+            .iter()
+            .map(|format| (format.clone(), pso::VertexInputRate::Instance(1)))
+            .collect::<Vec<_>>()
+    };
+    let pipe_desc = |shader_vertex| {
+        PipelineDescBuilder::new()
+            .with_input_assembler(pso::InputAssemblerDesc::new(hal::Primitive::LineList))
+            .with_shaders(util::simple_shader_set(
+                shader_vertex,
+                Some(&shader_fragment),
+            ))
+            .with_layout(&pipeline_layout)
+            .with_subpass(subpass)
+            .with_framebuffer_size(framebuffer_width, framebuffer_height)
+            .with_depth_test(pso::DepthTest {
+                fun: pso::Comparison::Greater,
+                write: false,
+            })
+    };
+
+    let pipes = PipelinesBuilder::new()
+        .with_pipeline(pipe_desc(&shader_normals).with_vertex_desc(&instanced(normals_format)))
+        .with_pipeline(pipe_desc(&shader_tangents).with_vertex_desc(&instanced(tangents_format)))
+        .build_cached(factory, cache);
+
+    unsafe {
+        factory.destroy_shader_module(shader_normals);
+        factory.destroy_shader_module(shader_tangents);
+        factory.destroy_shader_module(shader_fragment);
+    }
+
+    match pipes {
+        Err(e) => {
+            unsafe {
+                factory.device().destroy_pipeline_layout(pipeline_layout);
+            }
+            Err(e)
+        }
+        Ok(mut pipes) => {
+            let tangents = pipes.remove(1);
+            Ok((pipes.remove(0), tangents, pipeline_layout))
+        }
+    }
+}
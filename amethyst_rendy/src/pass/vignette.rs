@@ -0,0 +1,21 @@
+use crate::{pass::DrawPostProcessDesc, vignette::VignetteParams};
+
+impl DrawPostProcessDesc {
+    /// Create instance of `DrawPostProcess` render group tinting the edges of its input
+    /// as configured by [VignetteParams].
+    pub fn vignette() -> Self {
+        Self::new(super::VIGNETTE_FRAGMENT.clone()).with_constants(|world| {
+            let params = VignetteParams::fetch(world);
+            let [r, g, b] = params.color;
+            vec![
+                if params.enabled { 1.0 } else { 0.0 },
+                params.intensity.max(0.0).min(1.0),
+                params.radius,
+                params.smoothness.max(0.0),
+                r,
+                g,
+                b,
+            ]
+        })
+    }
+}
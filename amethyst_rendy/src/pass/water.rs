@@ -0,0 +1,293 @@
+use super::{deferred::GBufferInput, post_process_input_access};
+use crate::{
+    batch::OrderedOneLevelBatch,
+    camera::Camera,
+    pipeline::{PipelineCache, PipelineDescBuilder, PipelinesBuilder},
+    pod::{ViewArgs, WaterArgs},
+    render_texture::CameraTarget,
+    stats::DrawCounter,
+    submodules::{
+        gather::CameraGatherer, DynamicUniform, DynamicVertexBuffer, TextureId, TextureSub,
+    },
+    types::Backend,
+    util,
+    water::Water,
+};
+use amethyst_core::{
+    ecs::{Join, Read, ReadStorage, SystemData, World},
+    timing::Time,
+    transform::Transform,
+    Hidden, HiddenPropagate,
+};
+use derivative::Derivative;
+use rendy::{
+    command::{QueueId, RenderPassEncoder},
+    factory::Factory,
+    graph::{
+        render::{PrepareResult, RenderGroup, RenderGroupDesc},
+        GraphContext, ImageAccess, NodeBuffer, NodeImage,
+    },
+    hal::{self, device::Device, pso},
+    mesh::AsVertex,
+    shader::Shader,
+};
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+/// Draw the surface of every [Water], refracting and reflecting the scene.
+///
+/// The image and the depth image of the scene must be passed to the render group builder
+/// with `with_image`, in that order. They can't be attachments of the target, which has a
+/// single color image and no depth attachment, like a post-processing stage whose input is
+/// the image of [Target::Main](crate::bundle::Target::Main). Water surfaces replace the pixels
+/// they cover in front of the opaque surfaces of the depth image.
+#[derive(Clone, Debug, PartialEq, Derivative)]
+#[derivative(Default(bound = ""))]
+pub struct DrawWaterDesc;
+
+impl DrawWaterDesc {
+    /// Create instance of `DrawWater` render group
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl<B: Backend> RenderGroupDesc<B, World> for DrawWaterDesc {
+    fn images(&self) -> Vec<ImageAccess> {
+        vec![post_process_input_access(); 2]
+    }
+
+    fn depth(&self) -> bool {
+        false
+    }
+
+    fn build(
+        self,
+        ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        _queue: QueueId,
+        world: &World,
+        framebuffer_width: u32,
+        framebuffer_height: u32,
+        subpass: hal::pass::Subpass<'_, B>,
+        _buffers: Vec<NodeBuffer>,
+        images: Vec<NodeImage>,
+    ) -> Result<Box<dyn RenderGroup<B, World>>, failure::Error> {
+        #[cfg(feature = "profiler")]
+        profile_scope!("build");
+
+        let env = DynamicUniform::new(
+            factory,
+            pso::ShaderStageFlags::VERTEX | pso::ShaderStageFlags::FRAGMENT,
+        )?;
+        let scene = GBufferInput::new(ctx, factory, &images)?;
+        let textures = TextureSub::new(factory)?;
+
+        let pipeline_cache = world.try_fetch::<PipelineCache<B>>();
+        let (pipeline, pipeline_layout) = build_water_pipeline(
+            factory,
+            pipeline_cache.as_ref().and_then(|cache| cache.raw()),
+            subpass,
+            framebuffer_width,
+            framebuffer_height,
+            vec![
+                env.raw_layout(),
+                scene.raw_layout(),
+                textures.raw_layout(),
+                textures.raw_layout(),
+            ],
+        )?;
+
+        Ok(Box::new(DrawWater::<B> {
+            pipeline,
+            pipeline_layout,
+            env,
+            scene,
+            textures,
+            vertex: DynamicVertexBuffer::new(),
+            waters: Default::default(),
+        }))
+    }
+}
+
+/// Draws water surfaces in the order they are joined, batched by normal map and reflection
+/// texture.
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""))]
+pub struct DrawWater<B: Backend> {
+    pipeline: B::GraphicsPipeline,
+    pipeline_layout: B::PipelineLayout,
+    env: DynamicUniform<B, ViewArgs>,
+    scene: GBufferInput<B>,
+    textures: TextureSub<B>,
+    vertex: DynamicVertexBuffer<B, WaterArgs>,
+    waters: OrderedOneLevelBatch<(TextureId, TextureId), WaterArgs>,
+}
+
+impl<B: Backend> RenderGroup<B, World> for DrawWater<B> {
+    fn prepare(
+        &mut self,
+        factory: &Factory<B>,
+        _queue: QueueId,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        world: &World,
+    ) -> PrepareResult {
+        #[cfg(feature = "profiler")]
+        profile_scope!("prepare");
+
+        let (waters, transforms, cameras, targets, hiddens, hidden_props, time) =
+            <(
+                ReadStorage<'_, Water>,
+                ReadStorage<'_, Transform>,
+                ReadStorage<'_, Camera>,
+                ReadStorage<'_, CameraTarget>,
+                ReadStorage<'_, Hidden>,
+                ReadStorage<'_, HiddenPropagate>,
+                Option<Read<'_, Time>>,
+            )>::fetch(world);
+
+        let camera = CameraGatherer::gather(world);
+        self.env.write(factory, index, camera.projview);
+        let time = time.map_or(0.0, |time| time.absolute_time_seconds());
+
+        self.waters.swap_clear();
+        let layout = hal::image::Layout::ShaderReadOnlyOptimal;
+        for (water, transform, _, _) in (&waters, &transforms, !&hiddens, !&hidden_props).join() {
+            let normal_id = match self
+                .textures
+                .insert(factory, world, &water.normal_map, layout)
+            {
+                Some((normal_id, _)) => normal_id,
+                None => continue,
+            };
+            let textures = &mut self.textures;
+            let reflection = water.reflection.and_then(|entity| {
+                let proj_view =
+                    cameras.get(entity)?.matrix * transforms.get(entity)?.global_view_matrix();
+                let target = targets.get(entity)?;
+                let (reflection_id, _) =
+                    textures.insert(factory, world, &target.texture, layout)?;
+                Some((reflection_id, proj_view))
+            });
+            // The normal map takes the place of a missing reflection, which isn't sampled.
+            let reflection_id = reflection.map_or(normal_id, |(reflection_id, _)| reflection_id);
+            self.waters.insert(
+                (normal_id, reflection_id),
+                Some(WaterArgs::from_water(
+                    water,
+                    transform,
+                    reflection.map(|(_, proj_view)| proj_view),
+                    time,
+                )),
+            );
+        }
+        self.textures.maintain(factory, world);
+
+        self.vertex.write(
+            factory,
+            index,
+            self.waters.count() as u64,
+            Some(self.waters.data()),
+        );
+
+        PrepareResult::DrawRecord
+    }
+
+    fn draw_inline(
+        &mut self,
+        mut encoder: RenderPassEncoder<'_, B>,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        world: &World,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("draw");
+
+        if self.waters.count() == 0 {
+            return;
+        }
+
+        let layout = &self.pipeline_layout;
+        encoder.bind_graphics_pipeline(&self.pipeline);
+        self.env.bind(index, layout, 0, &mut encoder);
+        self.scene.bind(layout, 1, &mut encoder);
+        self.vertex.bind(index, 0, 0, &mut encoder);
+        let mut draws = DrawCounter::fetch(world);
+        for (&(normal, reflection), range) in self.waters.iter() {
+            if self.textures.loaded(normal) && self.textures.loaded(reflection) {
+                self.textures.bind(layout, 2, normal, &mut encoder);
+                self.textures.bind(layout, 3, reflection, &mut encoder);
+                let instances = range.end - range.start;
+                unsafe {
+                    encoder.draw(0..4, range);
+                }
+                draws.draw_quads(instances);
+            }
+        }
+    }
+
+    fn dispose(self: Box<Self>, factory: &mut Factory<B>, _world: &World) {
+        unsafe {
+            factory.device().destroy_graphics_pipeline(self.pipeline);
+            factory
+                .device()
+                .destroy_pipeline_layout(self.pipeline_layout);
+        }
+    }
+}
+
+fn build_water_pipeline<B: Backend>(
+    factory: &Factory<B>,
+    cache: Option<&B::PipelineCache>,
+    subpass: hal::pass::Subpass<'_, B>,
+    framebuffer_width: u32,
+    framebuffer_height: u32,
+    layouts: Vec<&B::DescriptorSetLayout>,
+) -> Result<(B::GraphicsPipeline, B::PipelineLayout), failure::Error> {
+    let pipeline_layout = unsafe {
+        factory
+            .device()
+            .create_pipeline_layout(layouts, None as Option<(_, _)>)
+    }?;
+
+    let shader_vertex = unsafe { super::WATER_VERTEX.module(factory).unwrap() };
+    let shader_fragment = unsafe { super::WATER_FRAGMENT.module(factory).unwrap() };
+
+    // Both sides of the surface are drawn, so it can be seen from under water. The refracted
+    // scene is part of the color written, so nothing is blended.
+    let pipes = PipelinesBuilder::new()
+        .with_pipeline(
+            PipelineDescBuilder::new()
+                .with_vertex_desc(&[(WaterArgs::vertex(), pso::VertexInputRate::Instance(1))])
+                .with_input_assembler(pso::InputAssemblerDesc::new(hal::Primitive::TriangleStrip))
+                .with_shaders(util::simple_shader_set(
+                    &shader_vertex,
+                    Some(&shader_fragment),
+                ))
+                .with_layout(&pipeline_layout)
+                .with_subpass(subpass)
+                .with_framebuffer_size(framebuffer_width, framebuffer_height)
+                .with_blend_targets(vec![pso::ColorBlendDesc {
+                    mask: pso::ColorMask::ALL,
+                    blend: None,
+                }]),
+        )
+        .build_cached(factory, cache);
+
+    unsafe {
+        factory.destroy_shader_module(shader_vertex);
+        factory.destroy_shader_module(shader_fragment);
+    }
+
+    match pipes {
+        Err(e) => {
+            unsafe {
+                factory.device().destroy_pipeline_layout(pipeline_layout);
+            }
+            Err(e)
+        }
+        Ok(mut pipes) => Ok((pipes.remove(0), pipeline_layout)),
+    }
+}
@@ -0,0 +1,98 @@
+//! Picking entities under a position of the window from their rendered geometry.
+
+use amethyst_core::{
+    ecs::Entity,
+    math::{Matrix4, Vector3},
+};
+
+/// Resource picking the entity drawn at a position of [`Target::Main`](crate::bundle::Target),
+/// inserted by [RenderPicking](crate::plugins::RenderPicking).
+///
+/// Entity ids of meshes, including skinned ones, and sprites are drawn for the requested pixel
+/// and read back from the GPU once the frame has completed, so results arrive a few frames
+/// after a position is requested. Positions are given in pixels of the main target from its
+/// top left corner, e.g. physical pixels of the window.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Picker {
+    pub(crate) position: Option<(u32, u32)>,
+    pub(crate) picked: Option<((u32, u32), Option<Entity>)>,
+}
+
+impl Picker {
+    /// Request picking at pixel `(x, y)` from now on, and return the entity found there.
+    ///
+    /// Returns `None` when there is no entity at the position, as well as until the first
+    /// result for it has been read back. Calling it every frame with the cursor position
+    /// keeps the result up to date.
+    pub fn pick(&mut self, x: u32, y: u32) -> Option<Entity> {
+        self.position = Some((x, y));
+        self.picked
+            .filter(|(position, _)| *position == (x, y))
+            .and_then(|(_, entity)| entity)
+    }
+
+    /// Stop drawing entity ids until the next position is requested.
+    pub fn stop(&mut self) {
+        self.position = None;
+    }
+
+    /// Position picking is requested at, if any.
+    pub fn position(&self) -> Option<(u32, u32)> {
+        self.position
+    }
+
+    /// Latest position read back from the GPU, along with the entity found there.
+    pub fn picked(&self) -> Option<((u32, u32), Option<Entity>)> {
+        self.picked
+    }
+}
+
+/// Transform from clip space of a `width` by `height` image to the clip space of its pixel
+/// at `(x, y)`, so the pixel covers a whole 1 by 1 image.
+pub(crate) fn pick_matrix(x: u32, y: u32, width: u32, height: u32) -> Matrix4<f32> {
+    let (width, height) = (width.max(1) as f32, height.max(1) as f32);
+    // Center of the pixel in normalized device coordinates, y pointing down.
+    let center_x = (x as f32 + 0.5) * 2.0 / width - 1.0;
+    let center_y = (y as f32 + 0.5) * 2.0 / height - 1.0;
+    Matrix4::new_nonuniform_scaling(&Vector3::new(width, height, 1.0))
+        * Matrix4::new_translation(&Vector3::new(-center_x, -center_y, 0.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use amethyst_core::{
+        ecs::{Builder, World, WorldExt},
+        math::Vector4,
+    };
+
+    #[test]
+    fn pick_matrix_covers_pixel() {
+        let pick = pick_matrix(3, 1, 8, 4);
+        let project = |x: f32, y: f32, w: f32| {
+            let clip = pick * Vector4::new(x * w, y * w, 0.5 * w, w);
+            (clip.x / clip.w, clip.y / clip.w)
+        };
+        // Pixel (3, 1) spans x from -0.25 to 0 and y from -0.5 to 0 in the image.
+        assert_eq!(project(-0.125, -0.25, 2.0), (0.0, 0.0));
+        assert_eq!(project(-0.25, -0.5, 1.0), (-1.0, -1.0));
+        assert_eq!(project(0.0, 0.0, 4.0), (1.0, 1.0));
+    }
+
+    #[test]
+    fn pick_returns_entity_at_requested_position() {
+        let mut world = World::new();
+        let entity = world.create_entity().build();
+        let mut picker = Picker::default();
+
+        assert_eq!(picker.pick(10, 20), None);
+        assert_eq!(picker.position(), Some((10, 20)));
+        picker.picked = Some(((10, 20), Some(entity)));
+        assert_eq!(picker.pick(10, 20), Some(entity));
+        // Results of another position aren't returned.
+        assert_eq!(picker.pick(11, 20), None);
+
+        picker.stop();
+        assert_eq!(picker.position(), None);
+    }
+}
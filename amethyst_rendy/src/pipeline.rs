@@ -16,6 +16,7 @@ use rendy::{
     },
     mesh::VertexFormat,
 };
+use std::path::PathBuf;
 
 #[cfg(feature = "profiler")]
 use thread_profiler::profile_scope;
@@ -213,6 +214,24 @@ impl<'a, B: Backend> PipelineDescBuilder<'a, B> {
         })
     }
 
+    /// Build drawing only into the provided rectangle of the framebuffer.
+    pub fn with_viewport_rect(mut self, rect: Rect) -> Self {
+        self.set_viewport_rect(rect);
+        self
+    }
+    /// Set to draw only into the provided rectangle of the framebuffer.
+    pub fn set_viewport_rect(&mut self, rect: Rect) {
+        let old_baked_states = self.baked_states.clone();
+        self.set_baked_states(BakedStates {
+            viewport: Some(Viewport {
+                rect,
+                depth: old_baked_states.viewport.map_or(0.0..1.0, |v| v.depth),
+            }),
+            scissor: Some(rect),
+            ..old_baked_states
+        })
+    }
+
     /// Build with the provided `DepthTest`
     pub fn with_depth_test(mut self, depth_test: DepthTest) -> Self {
         self.set_depth_test(depth_test);
@@ -341,7 +360,13 @@ impl<'a, B: Backend> PipelinesBuilder<'a, B> {
     }
 
     /// Finalize and construct the `GraphicsPipeline`
-    pub fn build(
+    pub fn build(self, factory: &Factory<B>) -> Result<Vec<B::GraphicsPipeline>, failure::Error> {
+        self.build_cached(factory, None)
+    }
+
+    /// Finalize and construct the `GraphicsPipeline`, reusing and filling the given pipeline
+    /// cache, usually the one of the `PipelineCache` resource.
+    pub fn build_cached(
         self,
         factory: &Factory<B>,
         cache: Option<&B::PipelineCache>,
@@ -367,3 +392,70 @@ impl<'a, B: Backend> PipelinesBuilder<'a, B> {
         Ok(pipelines.into_iter().map(|p| p.unwrap()).collect())
     }
 }
+
+/// Resource holding the pipeline cache passed to `PipelinesBuilder::build_cached` by render
+/// passes, inserted by the `RenderingSystem`.
+///
+/// When given a file path, the cache is loaded from the file at startup and written back
+/// to it on shutdown, so pipelines compiled by previous runs don't have to be compiled again.
+#[derive(Debug)]
+pub struct PipelineCache<B: Backend> {
+    raw: Option<B::PipelineCache>,
+    path: Option<PathBuf>,
+}
+
+impl<B: Backend> PipelineCache<B> {
+    pub(crate) fn load(factory: &Factory<B>, path: Option<PathBuf>) -> Self {
+        let data = path.as_ref().and_then(|path| match std::fs::read(path) {
+            Ok(data) => Some(data),
+            Err(err) => {
+                log::debug!("Pipeline cache {:?} not loaded: {}", path, err);
+                None
+            }
+        });
+        // Drivers ignore data from other devices, but fall back to an empty cache just in case.
+        let raw = unsafe {
+            factory
+                .device()
+                .create_pipeline_cache(data.as_deref())
+                .or_else(|_| factory.device().create_pipeline_cache(None))
+        };
+        let raw = match raw {
+            Ok(raw) => Some(raw),
+            Err(err) => {
+                log::warn!("Failed to create pipeline cache: {:?}", err);
+                None
+            }
+        };
+        Self { raw, path }
+    }
+
+    /// Get the raw pipeline cache, if it could be created.
+    pub fn raw(&self) -> Option<&B::PipelineCache> {
+        self.raw.as_ref()
+    }
+
+    /// Write the cache to its file, if any, and destroy it.
+    pub(crate) unsafe fn dispose(self, factory: &Factory<B>) {
+        let raw = match self.raw {
+            Some(raw) => raw,
+            None => return,
+        };
+        if let Some(path) = &self.path {
+            let saved = factory
+                .device()
+                .get_pipeline_cache_data(&raw)
+                .map_err(|err| format!("{:?}", err))
+                .and_then(|data| {
+                    if let Some(dir) = path.parent() {
+                        std::fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+                    }
+                    std::fs::write(path, data).map_err(|err| err.to_string())
+                });
+            if let Err(err) = saved {
+                log::warn!("Failed to save pipeline cache to {:?}: {}", path, err);
+            }
+        }
+        factory.device().destroy_pipeline_cache(raw);
+    }
+}
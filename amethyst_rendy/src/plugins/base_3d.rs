@@ -0,0 +1,148 @@
+use super::{add_lod_system, add_texture_streaming_system, add_visibility_system};
+use crate::{
+    bundle::{RenderOrder, RenderPlan, RenderPlugin, Target, TargetImage},
+    pass::*,
+    render_mode::RenderMode,
+    transparent::TransparencyMode,
+    Backend, Factory,
+};
+use amethyst_core::ecs::{DispatcherBuilder, World};
+use amethyst_error::Error;
+use rendy::graph::render::RenderGroupDesc;
+
+/// A `RenderPlugin` for forward rendering of 3d objects using flat shading.
+pub type RenderFlat3D = RenderBase3D<crate::pass::FlatPassDef>;
+/// A `RenderPlugin` for forward rendering of 3d objects using shaded shading.
+pub type RenderShaded3D = RenderBase3D<crate::pass::ShadedPassDef>;
+/// A `RenderPlugin` for forward rendering of 3d objects using physically-based shading.
+///
+/// Ambient lighting comes from the nearest captured
+/// [ReflectionProbe](crate::reflection_probe::ReflectionProbe), or else from the
+/// [EnvironmentMap](crate::environment_map::EnvironmentMap) resource when present, with its
+/// diffuse part taken from baked [LightProbes](crate::light_probe::LightProbes) when present.
+/// Point and spot lights are only shaded by the fragments within their reach, which are found by
+/// [clustering](crate::light_cluster) the view frustum.
+pub type RenderPbr3D = RenderBase3D<crate::pass::PbrPassDef>;
+
+/// A `RenderPlugin` for forward rendering of 3d objects.
+/// Generic over 3d pass rendering method.
+#[derive(derivative::Derivative)]
+#[derivative(Default(bound = ""), Debug(bound = ""))]
+pub struct RenderBase3D<D: Base3DPassDef> {
+    target: Target,
+    skinning: bool,
+    render_mode: Option<RenderMode>,
+    marker: std::marker::PhantomData<D>,
+}
+
+impl<D: Base3DPassDef> RenderBase3D<D> {
+    /// Set target to which 3d meshes will be rendered.
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Enable rendering for skinned meshes.
+    ///
+    /// NOTE: You must register `VertexSkinningBundle` yourself.
+    pub fn with_skinning(mut self) -> Self {
+        self.skinning = true;
+        self
+    }
+}
+
+impl<B: Backend, D: Base3DPassDef> RenderPlugin<B> for RenderBase3D<D> {
+    fn on_build<'a, 'b>(
+        &mut self,
+        world: &mut World,
+        builder: &mut DispatcherBuilder<'a, 'b>,
+    ) -> Result<(), Error> {
+        add_visibility_system(world, builder);
+        add_lod_system(world, builder);
+        add_texture_streaming_system::<B>(world, builder);
+        Ok(())
+    }
+
+    fn should_rebuild(&mut self, world: &World) -> bool {
+        self.render_mode != Some(RenderMode::fetch(world))
+    }
+
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        _factory: &mut Factory<B>,
+        world: &World,
+    ) -> Result<(), Error> {
+        // Passes build the pipelines of the current mode.
+        self.render_mode = Some(RenderMode::fetch(world));
+        let skinning = self.skinning;
+        plan.extend_target(self.target, move |ctx| {
+            // Shadows are sampled only when `RenderShadows` defines the shadow map target.
+            let (shadow_map, point_shadow_map, spot_shadow_map) =
+                if D::shadowed_fragment_shader().is_some() {
+                    (
+                        ctx.try_get_image(TargetImage::Depth(Target::ShadowMap))?,
+                        ctx.try_get_image(TargetImage::Depth(Target::PointShadowMap))?,
+                        ctx.try_get_image(TargetImage::Depth(Target::SpotShadowMap))?,
+                    )
+                } else {
+                    (None, None, None)
+                };
+            let point_shadow_map = shadow_map.and(point_shadow_map);
+            let spot_shadow_map = shadow_map.and(spot_shadow_map);
+            // Ambient occlusion is sampled only when `RenderSsao` defines its target.
+            let ambient_occlusion = if D::uses_ambient_occlusion() {
+                ctx.try_get_image(TargetImage::Color(Target::AmbientOcclusion, 0))?
+            } else {
+                None
+            };
+            let mut opaque = DrawBase3DDesc::<B, D>::new()
+                .with_skinning(skinning)
+                .with_shadows(shadow_map.is_some())
+                .with_point_shadows(point_shadow_map.is_some())
+                .with_spot_shadows(spot_shadow_map.is_some())
+                .with_ambient_occlusion(ambient_occlusion.is_some())
+                .builder();
+            for image in shadow_map
+                .into_iter()
+                .chain(point_shadow_map)
+                .chain(spot_shadow_map)
+                .chain(ambient_occlusion)
+            {
+                opaque = opaque.with_image(image);
+            }
+            ctx.add(RenderOrder::Opaque, opaque)?;
+            Ok(())
+        });
+        extend_transparent_3d::<B, D>(plan, world, self.target, skinning);
+        Ok(())
+    }
+}
+
+/// Draw transparent 3d meshes to `target`, or accumulate them for
+/// [RenderOrderIndependentTransparency](super::RenderOrderIndependentTransparency) when drawn to
+/// [Target::Main] with that [TransparencyMode].
+pub(super) fn extend_transparent_3d<B: Backend, D: Base3DPassDef>(
+    plan: &mut RenderPlan<B>,
+    world: &World,
+    target: Target,
+    skinning: bool,
+) {
+    let order_independent = target == Target::Main
+        && TransparencyMode::fetch(world) == TransparencyMode::WeightedBlended;
+    let target = if order_independent {
+        Target::OrderIndependentTransparency
+    } else {
+        target
+    };
+    plan.extend_target(target, move |ctx| {
+        ctx.add(
+            RenderOrder::Transparent,
+            DrawBase3DTransparentDesc::<B, D>::new()
+                .with_skinning(skinning)
+                .with_order_independence(order_independent)
+                .builder(),
+        )?;
+        Ok(())
+    });
+}
@@ -0,0 +1,51 @@
+use crate::{
+    billboard::Billboard,
+    bundle::{RenderOrder, RenderPlan, RenderPlugin, Target},
+    pass::*,
+    Backend, Factory,
+};
+use amethyst_core::ecs::{DispatcherBuilder, World, WorldExt};
+use amethyst_error::Error;
+use rendy::graph::render::RenderGroupDesc;
+
+/// A [RenderPlugin] drawing the sprites of [Billboard]s as quads facing the camera, with
+/// the transparent objects of the target.
+#[derive(Default, Debug)]
+pub struct RenderBillboards {
+    target: Target,
+}
+
+impl RenderBillboards {
+    /// Set target to which billboards will be rendered.
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+}
+
+impl<B: Backend> RenderPlugin<B> for RenderBillboards {
+    fn on_build<'a, 'b>(
+        &mut self,
+        world: &mut World,
+        _builder: &mut DispatcherBuilder<'a, 'b>,
+    ) -> Result<(), Error> {
+        world.register::<Billboard>();
+        Ok(())
+    }
+
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        _factory: &mut Factory<B>,
+        _world: &World,
+    ) -> Result<(), Error> {
+        plan.extend_target(self.target, |ctx| {
+            ctx.add(
+                RenderOrder::Transparent,
+                DrawBillboardsDesc::new().builder(),
+            )?;
+            Ok(())
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,151 @@
+use super::define_post_process_pass;
+use crate::{
+    bloom::{BloomParams, MAX_BLOOM_LEVELS},
+    bundle::{PostProcessPlugin, PostProcessStage, RenderPlan, Target, TargetImage},
+    pass::*,
+    Backend, Factory,
+};
+use amethyst_core::ecs::{DispatcherBuilder, Read, SystemData, World};
+use amethyst_error::Error;
+
+/// A [PostProcessPlugin] adding a glow around bright parts of the image.
+///
+/// Pixels brighter than a threshold are blurred with a separable gaussian at successively
+/// halved resolutions, and the blurred levels are added to the image. Threshold, intensity
+/// and the number of levels are configured with the [BloomParams] resource.
+#[derive(Default, Debug)]
+pub struct RenderBloom {
+    params: BloomParams,
+    levels: Option<usize>,
+}
+
+/// Render targets of the bright pass followed by horizontal blur, vertical blur
+/// and upsampling targets of each bloom level.
+const BLOOM_BRIGHT_TARGET: Target = Target::Custom("bloom_bright");
+const BLOOM_LEVEL_TARGETS: [(Target, Target, Target); MAX_BLOOM_LEVELS] = [
+    (
+        Target::Custom("bloom_blur_h_0"),
+        Target::Custom("bloom_blur_v_0"),
+        Target::Custom("bloom_up_0"),
+    ),
+    (
+        Target::Custom("bloom_blur_h_1"),
+        Target::Custom("bloom_blur_v_1"),
+        Target::Custom("bloom_up_1"),
+    ),
+    (
+        Target::Custom("bloom_blur_h_2"),
+        Target::Custom("bloom_blur_v_2"),
+        Target::Custom("bloom_up_2"),
+    ),
+    (
+        Target::Custom("bloom_blur_h_3"),
+        Target::Custom("bloom_blur_v_3"),
+        Target::Custom("bloom_up_3"),
+    ),
+    (
+        Target::Custom("bloom_blur_h_4"),
+        Target::Custom("bloom_blur_v_4"),
+        Target::Custom("bloom_up_4"),
+    ),
+    (
+        Target::Custom("bloom_blur_h_5"),
+        Target::Custom("bloom_blur_v_5"),
+        Target::Custom("bloom_up_5"),
+    ),
+];
+
+impl RenderBloom {
+    /// Create bloom plugin with the given initial `BloomParams`.
+    pub fn with_params(params: BloomParams) -> Self {
+        Self {
+            params,
+            levels: None,
+        }
+    }
+}
+impl<B: Backend> PostProcessPlugin<B> for RenderBloom {
+    fn on_build<'a, 'b>(
+        &mut self,
+        world: &mut World,
+        _builder: &mut DispatcherBuilder<'a, 'b>,
+    ) -> Result<(), Error> {
+        world.insert(self.params);
+        Ok(())
+    }
+
+    fn should_rebuild(&mut self, world: &World) -> bool {
+        let params = <Option<Read<'_, BloomParams>>>::fetch(world).map_or(self.params, |p| *p);
+        self.levels != Some(params.level_count())
+    }
+
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        stage: PostProcessStage,
+        factory: &mut Factory<B>,
+        world: &World,
+    ) -> Result<(), Error> {
+        if let Some(params) = <Option<Read<'_, BloomParams>>>::fetch(world) {
+            self.params = *params;
+        }
+        let levels = self.params.level_count();
+        self.levels = Some(levels);
+
+        define_post_process_pass(
+            plan,
+            BLOOM_BRIGHT_TARGET,
+            stage.image_options(2),
+            DrawPostProcessDesc::bloom_bright_pass(),
+            vec![stage.input],
+        )?;
+
+        // Downsample and blur each level from the previous one.
+        let mut source = BLOOM_BRIGHT_TARGET;
+        for (level, &(horizontal, vertical, _)) in BLOOM_LEVEL_TARGETS[..levels].iter().enumerate()
+        {
+            let options = stage.image_options(2 << level);
+            let extent = options.kind.extent();
+            define_post_process_pass(
+                plan,
+                horizontal,
+                options.clone(),
+                DrawPostProcessDesc::bloom_blur([1.0 / extent.width as f32, 0.0]),
+                vec![TargetImage::Color(source, 0)],
+            )?;
+            define_post_process_pass(
+                plan,
+                vertical,
+                options,
+                DrawPostProcessDesc::bloom_blur([0.0, 1.0 / extent.height as f32]),
+                vec![TargetImage::Color(horizontal, 0)],
+            )?;
+            source = vertical;
+        }
+
+        // Accumulate levels from the smallest one up.
+        for level in (0..levels - 1).rev() {
+            let (_, vertical, upsample) = BLOOM_LEVEL_TARGETS[level];
+            define_post_process_pass(
+                plan,
+                upsample,
+                stage.image_options(2 << level),
+                DrawPostProcessDesc::bloom_upsample(),
+                vec![
+                    TargetImage::Color(vertical, 0),
+                    TargetImage::Color(source, 0),
+                ],
+            )?;
+            source = upsample;
+        }
+
+        PostProcessPlugin::<B>::on_plan(
+            &mut DrawPostProcessDesc::bloom_composite(levels)
+                .with_input(TargetImage::Color(source, 0)),
+            plan,
+            stage,
+            factory,
+            world,
+        )
+    }
+}
@@ -0,0 +1,51 @@
+use crate::{
+    bundle::{PostProcessPlugin, PostProcessStage, RenderPlan},
+    chromatic_aberration::ChromaticAberrationParams,
+    pass::*,
+    Backend, Factory,
+};
+use amethyst_core::ecs::{DispatcherBuilder, World};
+use amethyst_error::Error;
+
+/// A [PostProcessPlugin] imitating the color fringes of a lens by offsetting the red and blue
+/// channels of the image towards its edges.
+///
+/// The offset is configured at runtime through the [ChromaticAberrationParams] resource.
+#[derive(Default, Debug)]
+pub struct RenderChromaticAberration {
+    params: ChromaticAberrationParams,
+}
+
+impl RenderChromaticAberration {
+    /// Create chromatic aberration plugin with the given initial `ChromaticAberrationParams`.
+    pub fn with_params(params: ChromaticAberrationParams) -> Self {
+        Self { params }
+    }
+}
+
+impl<B: Backend> PostProcessPlugin<B> for RenderChromaticAberration {
+    fn on_build<'a, 'b>(
+        &mut self,
+        world: &mut World,
+        _builder: &mut DispatcherBuilder<'a, 'b>,
+    ) -> Result<(), Error> {
+        world.insert(self.params);
+        Ok(())
+    }
+
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        stage: PostProcessStage,
+        factory: &mut Factory<B>,
+        world: &World,
+    ) -> Result<(), Error> {
+        PostProcessPlugin::<B>::on_plan(
+            &mut DrawPostProcessDesc::chromatic_aberration(),
+            plan,
+            stage,
+            factory,
+            world,
+        )
+    }
+}
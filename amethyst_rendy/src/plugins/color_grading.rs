@@ -0,0 +1,53 @@
+use crate::{
+    bundle::{PostProcessPlugin, PostProcessStage, RenderPlan},
+    color_grading::ColorGrading,
+    pass::*,
+    Backend, Factory,
+};
+use amethyst_core::ecs::{DispatcherBuilder, World};
+use amethyst_error::Error;
+
+/// A [PostProcessPlugin] grading the colors of the image with a 3D lookup table.
+///
+/// The lookup table and the strength of the grading are set with the [ColorGrading] resource,
+/// tables are loaded from `.cube` files with [CubeLutFormat] or from strip images with
+/// [LutStripFormat]. Add it after [RenderToneMap](super::RenderToneMap), as lookup tables map
+/// displayable colors.
+#[derive(Default, Debug)]
+pub struct RenderColorGrading {
+    grading: ColorGrading,
+}
+
+impl RenderColorGrading {
+    /// Create color grading plugin with the given initial `ColorGrading`.
+    pub fn with_grading(grading: ColorGrading) -> Self {
+        Self { grading }
+    }
+}
+
+impl<B: Backend> PostProcessPlugin<B> for RenderColorGrading {
+    fn on_build<'a, 'b>(
+        &mut self,
+        world: &mut World,
+        _builder: &mut DispatcherBuilder<'a, 'b>,
+    ) -> Result<(), Error> {
+        world.insert(self.grading.clone());
+        Ok(())
+    }
+
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        stage: PostProcessStage,
+        factory: &mut Factory<B>,
+        world: &World,
+    ) -> Result<(), Error> {
+        PostProcessPlugin::<B>::on_plan(
+            &mut DrawPostProcessDesc::color_grading(),
+            plan,
+            stage,
+            factory,
+            world,
+        )
+    }
+}
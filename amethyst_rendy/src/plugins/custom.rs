@@ -0,0 +1,55 @@
+use super::{add_lod_system, add_texture_streaming_system, add_visibility_system};
+use crate::{
+    bundle::{RenderOrder, RenderPlan, RenderPlugin, Target},
+    pass::*,
+    Backend, Factory,
+};
+use amethyst_core::ecs::{DispatcherBuilder, World, WorldExt};
+use amethyst_error::Error;
+use rendy::graph::render::RenderGroupDesc;
+
+/// A `RenderPlugin` for forward rendering of 3d objects with a [CustomMaterial] component.
+#[derive(derivative::Derivative)]
+#[derivative(Default(bound = ""), Debug(bound = ""))]
+pub struct RenderCustom3D<M: CustomMaterial> {
+    target: Target,
+    marker: std::marker::PhantomData<M>,
+}
+
+impl<M: CustomMaterial> RenderCustom3D<M> {
+    /// Set target to which 3d meshes will be rendered.
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+}
+
+impl<B: Backend, M: CustomMaterial> RenderPlugin<B> for RenderCustom3D<M>
+where
+    M::Storage: Default,
+{
+    fn on_build<'a, 'b>(
+        &mut self,
+        world: &mut World,
+        builder: &mut DispatcherBuilder<'a, 'b>,
+    ) -> Result<(), Error> {
+        world.register::<M>();
+        add_visibility_system(world, builder);
+        add_lod_system(world, builder);
+        add_texture_streaming_system::<B>(world, builder);
+        Ok(())
+    }
+
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        _factory: &mut Factory<B>,
+        _world: &World,
+    ) -> Result<(), Error> {
+        plan.extend_target(self.target, |ctx| {
+            ctx.add(RenderOrder::Opaque, DrawCustomDesc::<B, M>::new().builder())?;
+            Ok(())
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,73 @@
+use crate::{
+    bundle::{RenderOrder, RenderPlan, RenderPlugin, Target},
+    pass::*,
+    Backend, Factory,
+};
+use amethyst_core::ecs::World;
+use amethyst_error::Error;
+use rendy::graph::render::RenderGroupDesc;
+
+/// A [RenderPlugin] for drawing debug lines.
+/// Use with [debug_drawing::DebugLines] resource or [debug_drawing::DebugLinesComponent].
+#[derive(Default, Debug)]
+pub struct RenderDebugLines {
+    target: Target,
+}
+
+impl RenderDebugLines {
+    /// Set target to which debug lines will be rendered.
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+}
+
+impl<B: Backend> RenderPlugin<B> for RenderDebugLines {
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        _factory: &mut Factory<B>,
+        _world: &World,
+    ) -> Result<(), Error> {
+        plan.extend_target(self.target, |ctx| {
+            ctx.add(
+                RenderOrder::BeforeTransparent,
+                DrawDebugLinesDesc::new().builder(),
+            )?;
+            Ok(())
+        });
+        Ok(())
+    }
+}
+/// A [RenderPlugin] for drawing debug lines given in screen space pixels.
+/// Use with [debug_drawing::ScreenDebugLines] resource.
+#[derive(Default, Debug)]
+pub struct RenderScreenDebugLines {
+    target: Target,
+}
+
+impl RenderScreenDebugLines {
+    /// Set target to which screen space debug lines will be rendered.
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+}
+
+impl<B: Backend> RenderPlugin<B> for RenderScreenDebugLines {
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        _factory: &mut Factory<B>,
+        _world: &World,
+    ) -> Result<(), Error> {
+        plan.extend_target(self.target, |ctx| {
+            ctx.add(
+                RenderOrder::Overlay,
+                DrawDebugLinesDesc::screen_space().builder(),
+            )?;
+            Ok(())
+        });
+        Ok(())
+    }
+}
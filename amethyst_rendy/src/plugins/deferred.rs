@@ -0,0 +1,185 @@
+use super::{
+    add_lod_system, add_texture_streaming_system, add_visibility_system,
+    base_3d::extend_transparent_3d,
+};
+use crate::{
+    bundle::{
+        ImageOptions, RenderOrder, RenderPlan, RenderPlugin, Target, TargetImage, TargetPlanOutputs,
+    },
+    decal::Decal,
+    pass::*,
+    render_mode::RenderMode,
+    Backend, Factory, Format, Kind,
+};
+use amethyst_core::ecs::{DispatcherBuilder, World, WorldExt};
+use amethyst_error::{format_err, Error};
+use rendy::{
+    graph::render::RenderGroupDesc,
+    hal::command::{ClearDepthStencil, ClearValue},
+};
+
+/// A `RenderPlugin` for deferred rendering of 3d objects using physically-based shading.
+///
+/// Material properties of opaque meshes are drawn to a G-buffer first, then shaded once per
+/// pixel of the target. Point lights are accumulated by drawing the screen area covered by
+/// their `radius`, so their count isn't limited like with `RenderPbr3D` and only pixels
+/// within reach pay for them. Transparent meshes are drawn with forward shading afterwards.
+///
+/// [Decal](crate::decal::Decal) components are projected onto the G-buffer when enabled with
+/// `with_decals`.
+///
+/// Shadow maps and screen-space ambient occlusion aren't sampled by the deferred lighting.
+/// Add it after the plugin defining its target, e.g. `RenderToWindow`.
+#[derive(Default, Debug)]
+pub struct RenderDeferredPbr3D {
+    target: Target,
+    skinning: bool,
+    decals: bool,
+    render_mode: Option<RenderMode>,
+}
+
+/// Render target of the G-buffer of `RenderDeferredPbr3D`.
+const GBUFFER_TARGET: Target = Target::Custom("gbuffer");
+
+/// Render target of the decals of `RenderDeferredPbr3D`.
+const DECAL_TARGET: Target = Target::Custom("decals");
+
+impl RenderDeferredPbr3D {
+    /// Set target to which 3d meshes will be rendered.
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Enable rendering for skinned meshes.
+    ///
+    /// NOTE: You must register `VertexSkinningBundle` yourself.
+    pub fn with_skinning(mut self) -> Self {
+        self.skinning = true;
+        self
+    }
+
+    /// Enable projecting [Decal](crate::decal::Decal) components onto the G-buffer.
+    pub fn with_decals(mut self) -> Self {
+        self.decals = true;
+        self
+    }
+}
+
+impl<B: Backend> RenderPlugin<B> for RenderDeferredPbr3D {
+    fn on_build<'a, 'b>(
+        &mut self,
+        world: &mut World,
+        builder: &mut DispatcherBuilder<'a, 'b>,
+    ) -> Result<(), Error> {
+        add_visibility_system(world, builder);
+        add_lod_system(world, builder);
+        add_texture_streaming_system::<B>(world, builder);
+        world.register::<Decal>();
+        Ok(())
+    }
+
+    fn should_rebuild(&mut self, world: &World) -> bool {
+        self.render_mode != Some(RenderMode::fetch(world))
+    }
+
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        factory: &mut Factory<B>,
+        world: &World,
+    ) -> Result<(), Error> {
+        self.render_mode = Some(RenderMode::fetch(world));
+        let metadata = plan.target_metadata(self.target, factory).ok_or_else(|| {
+            format_err!(
+                "Deferred shading requires {:?} target to be defined first.",
+                self.target
+            )
+        })?;
+        let options = |format, clear| ImageOptions {
+            kind: Kind::D2(metadata.width(), metadata.height(), 1, 1),
+            levels: 1,
+            format,
+            clear,
+        };
+        let clear_color = || Some(ClearValue::Color([0.0; 4].into()));
+
+        plan.define_pass(
+            GBUFFER_TARGET,
+            TargetPlanOutputs {
+                colors: vec![
+                    crate::bundle::OutputColor::Image(options(Format::Rgba8Srgb, clear_color())),
+                    crate::bundle::OutputColor::Image(options(Format::Rgba16Sfloat, clear_color())),
+                    crate::bundle::OutputColor::Image(options(Format::Rgba16Sfloat, clear_color())),
+                ],
+                depth: Some(options(
+                    Format::D32Sfloat,
+                    Some(ClearValue::DepthStencil(ClearDepthStencil(0.0, 0))),
+                )),
+            },
+        )?;
+        let skinning = self.skinning;
+        plan.extend_target(GBUFFER_TARGET, move |ctx| {
+            ctx.add(
+                RenderOrder::Opaque,
+                DrawGBufferDesc::<B>::new()
+                    .with_skinning(skinning)
+                    .builder(),
+            )?;
+            Ok(())
+        });
+
+        let decals = self.decals;
+        if decals {
+            plan.define_pass(
+                DECAL_TARGET,
+                TargetPlanOutputs {
+                    colors: vec![
+                        crate::bundle::OutputColor::Image(options(
+                            Format::Rgba8Srgb,
+                            clear_color(),
+                        )),
+                        crate::bundle::OutputColor::Image(options(
+                            Format::Rgba16Sfloat,
+                            clear_color(),
+                        )),
+                    ],
+                    depth: None,
+                },
+            )?;
+            plan.extend_target(DECAL_TARGET, |ctx| {
+                let mut decals = DrawDecalsDesc::new().builder();
+                for i in 0..GBUFFER_COLOR_IMAGES {
+                    decals =
+                        decals.with_image(ctx.get_image(TargetImage::Color(GBUFFER_TARGET, i))?);
+                }
+                decals = decals.with_image(ctx.get_image(TargetImage::Depth(GBUFFER_TARGET))?);
+                ctx.add(RenderOrder::Opaque, decals)?;
+                Ok(())
+            });
+        }
+
+        plan.extend_target(self.target, move |ctx| {
+            let mut lighting = if decals {
+                DrawDeferredLightingDesc::new().with_decals().builder()
+            } else {
+                DrawDeferredLightingDesc::new().builder()
+            };
+            for i in 0..GBUFFER_COLOR_IMAGES {
+                lighting =
+                    lighting.with_image(ctx.get_image(TargetImage::Color(GBUFFER_TARGET, i))?);
+            }
+            lighting = lighting.with_image(ctx.get_image(TargetImage::Depth(GBUFFER_TARGET))?);
+            if decals {
+                for i in 0..DECAL_IMAGES {
+                    lighting =
+                        lighting.with_image(ctx.get_image(TargetImage::Color(DECAL_TARGET, i))?);
+                }
+            }
+            ctx.add(RenderOrder::Opaque, lighting)?;
+            Ok(())
+        });
+        extend_transparent_3d::<B, PbrPassDef>(plan, world, self.target, skinning);
+        Ok(())
+    }
+}
@@ -0,0 +1,71 @@
+use super::define_post_process_pass;
+use crate::{
+    bundle::{PostProcessPlugin, PostProcessStage, RenderPlan, Target, TargetImage},
+    dof::{DepthOfField, DofParams},
+    pass::*,
+    Backend, Factory,
+};
+use amethyst_core::ecs::{DispatcherBuilder, World, WorldExt};
+use amethyst_error::Error;
+
+/// A [PostProcessPlugin] blurring surfaces in front of and behind the focus of the camera.
+///
+/// A circle of confusion is computed for every pixel from the depth of [Target::Main], which must
+/// have a depth output, and colors are gathered over a bokeh disk of that size. Focus distance and
+/// aperture are read from the [DepthOfField] component of the active camera, while blur radius and
+/// sample count are configured with the [DofParams] resource. Add it before
+/// [RenderBloom](super::RenderBloom) and [RenderToneMap](super::RenderToneMap), as it blends linear
+/// HDR colors.
+#[derive(Default, Debug)]
+pub struct RenderDepthOfField {
+    params: DofParams,
+}
+
+/// Render target of the circle of confusion pass of depth of field.
+const DOF_COC_TARGET: Target = Target::Custom("dof_coc");
+
+impl RenderDepthOfField {
+    /// Create depth of field plugin with the given initial `DofParams`.
+    pub fn with_params(params: DofParams) -> Self {
+        Self { params }
+    }
+}
+
+impl<B: Backend> PostProcessPlugin<B> for RenderDepthOfField {
+    fn on_build<'a, 'b>(
+        &mut self,
+        world: &mut World,
+        _builder: &mut DispatcherBuilder<'a, 'b>,
+    ) -> Result<(), Error> {
+        world.insert(self.params);
+        world.register::<DepthOfField>();
+        Ok(())
+    }
+
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        stage: PostProcessStage,
+        factory: &mut Factory<B>,
+        world: &World,
+    ) -> Result<(), Error> {
+        define_post_process_pass(
+            plan,
+            DOF_COC_TARGET,
+            stage.image_options(1),
+            DrawPostProcessDesc::dof_coc(),
+            vec![stage.input, TargetImage::Depth(Target::Main)],
+        )?;
+
+        PostProcessPlugin::<B>::on_plan(
+            &mut DrawPostProcessDesc::dof_bokeh(),
+            plan,
+            PostProcessStage {
+                input: TargetImage::Color(DOF_COC_TARGET, 0),
+                ..stage
+            },
+            factory,
+            world,
+        )
+    }
+}
@@ -0,0 +1,52 @@
+use super::add_sprite_visibility_system;
+use crate::{
+    bundle::{RenderOrder, RenderPlan, RenderPlugin, Target},
+    pass::*,
+    Backend, Factory,
+};
+use amethyst_core::ecs::{DispatcherBuilder, World};
+use amethyst_error::Error;
+use rendy::graph::render::RenderGroupDesc;
+
+/// A [RenderPlugin] for drawing 2d objects with flat shading.
+/// Required to display sprites defined with [SpriteRender] component.
+#[derive(Default, Debug)]
+pub struct RenderFlat2D {
+    target: Target,
+}
+
+impl RenderFlat2D {
+    /// Set target to which 2d sprites will be rendered.
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+}
+
+impl<B: Backend> RenderPlugin<B> for RenderFlat2D {
+    fn on_build<'a, 'b>(
+        &mut self,
+        world: &mut World,
+        builder: &mut DispatcherBuilder<'a, 'b>,
+    ) -> Result<(), Error> {
+        add_sprite_visibility_system(world, builder);
+        Ok(())
+    }
+
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        _factory: &mut Factory<B>,
+        _world: &World,
+    ) -> Result<(), Error> {
+        plan.extend_target(self.target, |ctx| {
+            ctx.add(RenderOrder::Opaque, DrawFlat2DDesc::new().builder())?;
+            ctx.add(
+                RenderOrder::Transparent,
+                DrawFlat2DTransparentDesc::new().builder(),
+            )?;
+            Ok(())
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,54 @@
+use crate::{
+    bundle::{PostProcessPlugin, PostProcessStage, RenderPlan},
+    fog::Fog,
+    pass::*,
+    Backend, Factory,
+};
+use amethyst_core::ecs::{DispatcherBuilder, World};
+use amethyst_error::Error;
+
+/// A [PostProcessPlugin] blending distant surfaces into fog, so scenes don't end in a hard
+/// horizon.
+///
+/// Distances are computed from the depth of [Target::Main](crate::bundle::Target::Main), which must
+/// have a depth output. Mode, color, density and height falloff are configured at runtime through
+/// the [Fog] resource. Add it before [RenderBloom](super::RenderBloom) and
+/// [RenderToneMap](super::RenderToneMap), as it blends linear HDR colors.
+#[derive(Default, Debug)]
+pub struct RenderFog {
+    fog: Fog,
+}
+
+impl RenderFog {
+    /// Create fog plugin with the given initial `Fog`.
+    pub fn with_fog(fog: Fog) -> Self {
+        Self { fog }
+    }
+}
+
+impl<B: Backend> PostProcessPlugin<B> for RenderFog {
+    fn on_build<'a, 'b>(
+        &mut self,
+        world: &mut World,
+        _builder: &mut DispatcherBuilder<'a, 'b>,
+    ) -> Result<(), Error> {
+        world.insert(self.fog);
+        Ok(())
+    }
+
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        stage: PostProcessStage,
+        factory: &mut Factory<B>,
+        world: &World,
+    ) -> Result<(), Error> {
+        PostProcessPlugin::<B>::on_plan(
+            &mut DrawPostProcessDesc::fog(),
+            plan,
+            stage,
+            factory,
+            world,
+        )
+    }
+}
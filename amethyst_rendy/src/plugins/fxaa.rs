@@ -0,0 +1,52 @@
+use crate::{
+    bundle::{PostProcessPlugin, PostProcessStage, RenderPlan},
+    fxaa::FxaaParams,
+    pass::*,
+    Backend, Factory,
+};
+use amethyst_core::ecs::{DispatcherBuilder, World};
+use amethyst_error::Error;
+
+/// A [PostProcessPlugin] smoothing aliased edges with fast approximate anti-aliasing.
+///
+/// A cheaper alternative to multisampling, which can be enabled, disabled and tuned at runtime
+/// through the [FxaaParams] resource. Add it after [RenderToneMap](super::RenderToneMap), as edges
+/// are detected from the displayable colors.
+#[derive(Default, Debug)]
+pub struct RenderFxaa {
+    params: FxaaParams,
+}
+
+impl RenderFxaa {
+    /// Create FXAA plugin with the given initial `FxaaParams`.
+    pub fn with_params(params: FxaaParams) -> Self {
+        Self { params }
+    }
+}
+
+impl<B: Backend> PostProcessPlugin<B> for RenderFxaa {
+    fn on_build<'a, 'b>(
+        &mut self,
+        world: &mut World,
+        _builder: &mut DispatcherBuilder<'a, 'b>,
+    ) -> Result<(), Error> {
+        world.insert(self.params);
+        Ok(())
+    }
+
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        stage: PostProcessStage,
+        factory: &mut Factory<B>,
+        world: &World,
+    ) -> Result<(), Error> {
+        PostProcessPlugin::<B>::on_plan(
+            &mut DrawPostProcessDesc::fxaa(),
+            plan,
+            stage,
+            factory,
+            world,
+        )
+    }
+}
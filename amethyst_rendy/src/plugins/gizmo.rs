@@ -0,0 +1,54 @@
+use crate::{
+    bundle::{RenderOrder, RenderPlan, RenderPlugin, Target},
+    gizmo::Gizmo,
+    pass::*,
+    Backend, Factory,
+};
+use amethyst_core::ecs::{DispatcherBuilder, World};
+use amethyst_error::Error;
+use rendy::graph::render::RenderGroupDesc;
+
+/// RenderPlugin for rendering transform gizmo handles of the entity selected in the
+/// [Gizmo](crate::gizmo::Gizmo) resource.
+///
+/// To edit transforms with the mouse, enable the `gizmo` feature and add
+/// `GizmoSystem` to your dispatcher.
+#[derive(Default, Debug)]
+pub struct RenderGizmo {
+    target: Target,
+}
+
+impl RenderGizmo {
+    /// Set target to which gizmo handles will be rendered.
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+}
+
+impl<B: Backend> RenderPlugin<B> for RenderGizmo {
+    fn on_build<'a, 'b>(
+        &mut self,
+        world: &mut World,
+        _builder: &mut DispatcherBuilder<'a, 'b>,
+    ) -> Result<(), Error> {
+        world.entry::<Gizmo>().or_insert_with(Gizmo::default);
+        Ok(())
+    }
+
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        _factory: &mut Factory<B>,
+        _world: &World,
+    ) -> Result<(), Error> {
+        plan.extend_target(self.target, |ctx| {
+            ctx.add(
+                RenderOrder::AfterTransparent,
+                DrawGizmoDesc::new().builder(),
+            )?;
+            Ok(())
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,39 @@
+use crate::{
+    bundle::{RenderOrder, RenderPlan, RenderPlugin, Target},
+    pass::*,
+    Backend, Factory,
+};
+use amethyst_core::ecs::World;
+use amethyst_error::Error;
+use rendy::graph::render::RenderGroupDesc;
+
+/// RenderPlugin for rendering an endless reference grid on the XZ plane.
+///
+/// Spacing and colors are read from the [GridParams] resource if present.
+#[derive(Default, Debug)]
+pub struct RenderGrid {
+    target: Target,
+}
+
+impl RenderGrid {
+    /// Set target to which the grid will be rendered.
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+}
+
+impl<B: Backend> RenderPlugin<B> for RenderGrid {
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        _factory: &mut Factory<B>,
+        _world: &World,
+    ) -> Result<(), Error> {
+        plan.extend_target(self.target, |ctx| {
+            ctx.add(RenderOrder::AfterOpaque, DrawGridDesc::new().builder())?;
+            Ok(())
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,57 @@
+use super::add_sprite_visibility_system;
+use crate::{
+    bundle::{RenderOrder, RenderPlan, RenderPlugin, Target},
+    pass::*,
+    sprite::SpriteNormalMap,
+    Backend, Factory,
+};
+use amethyst_core::ecs::{DispatcherBuilder, World, WorldExt};
+use amethyst_error::Error;
+use rendy::graph::render::RenderGroupDesc;
+
+/// A [RenderPlugin] for drawing 2d objects lit by point and directional lights.
+/// Use instead of [RenderFlat2D](super::RenderFlat2D) to display sprites defined with
+/// [SpriteRender] component, optionally with a [SpriteNormalMap].
+///
+/// [SpriteRender]: crate::sprite::SpriteRender
+#[derive(Default, Debug)]
+pub struct RenderLit2D {
+    target: Target,
+}
+
+impl RenderLit2D {
+    /// Set target to which lit 2d sprites will be rendered.
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+}
+
+impl<B: Backend> RenderPlugin<B> for RenderLit2D {
+    fn on_build<'a, 'b>(
+        &mut self,
+        world: &mut World,
+        builder: &mut DispatcherBuilder<'a, 'b>,
+    ) -> Result<(), Error> {
+        world.register::<SpriteNormalMap>();
+        add_sprite_visibility_system(world, builder);
+        Ok(())
+    }
+
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        _factory: &mut Factory<B>,
+        _world: &World,
+    ) -> Result<(), Error> {
+        plan.extend_target(self.target, |ctx| {
+            ctx.add(RenderOrder::Opaque, DrawLit2DDesc::new().builder())?;
+            ctx.add(
+                RenderOrder::Transparent,
+                DrawLit2DTransparentDesc::new().builder(),
+            )?;
+            Ok(())
+        });
+        Ok(())
+    }
+}
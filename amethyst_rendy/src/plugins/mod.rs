@@ -0,0 +1,223 @@
+//! Set of predefined implementations of `RenderPlugin` for use with `RenderingBundle`.
+
+mod base_3d;
+mod billboard;
+mod bloom;
+mod chromatic_aberration;
+mod color_grading;
+mod custom;
+mod debug_lines;
+mod deferred;
+mod dof;
+mod flat2d;
+mod fog;
+mod fxaa;
+mod gizmo;
+mod grid;
+mod lit2d;
+mod motion_blur;
+mod occlusion;
+mod offscreen;
+mod oit;
+mod outline;
+mod particle;
+mod picking;
+mod reflection_probe;
+mod render_texture;
+mod screenshot;
+#[cfg(feature = "shader-hot-reload")]
+mod shader_reload;
+mod shadow;
+mod skybox;
+mod ssao;
+mod ssr;
+mod stats;
+mod taa;
+mod tonemap;
+mod vertex_normals;
+mod viewport;
+mod vignette;
+mod water;
+#[cfg(feature = "window")]
+mod window;
+
+#[cfg(feature = "shader-hot-reload")]
+pub use self::shader_reload::*;
+#[cfg(feature = "window")]
+pub use self::window::*;
+pub use self::{
+    base_3d::*, billboard::*, bloom::*, chromatic_aberration::*, color_grading::*, custom::*,
+    debug_lines::*, deferred::*, dof::*, flat2d::*, fog::*, fxaa::*, gizmo::*, grid::*, lit2d::*,
+    motion_blur::*, occlusion::*, offscreen::*, oit::*, outline::*, particle::*, picking::*,
+    reflection_probe::*, render_texture::*, screenshot::*, shadow::*, skybox::*, ssao::*, ssr::*,
+    stats::*, taa::*, tonemap::*, vertex_normals::*, viewport::*, vignette::*, water::*,
+};
+
+use crate::{
+    bundle::{
+        ImageOptions, PostProcessStage, RenderOrder, RenderPlan, Target, TargetImage,
+        TargetPlanOutputs,
+    },
+    lod::{LodGroup, LodSelectionSystem},
+    pass::*,
+    sprite_visibility::{SpriteVisibility, SpriteVisibilitySortingSystem},
+    texture_streaming::{TextureStreaming, TextureStreamingSystem},
+    visibility::{Visibility, VisibilitySortingSystem},
+    Backend, Factory, Format,
+};
+use amethyst_core::ecs::{storage::MaskedStorage, DispatcherBuilder, Entity, World, WorldExt};
+use amethyst_error::Error;
+use rendy::{
+    graph::render::RenderGroupDesc,
+    hal::command::{ClearDepthStencil, ClearValue},
+};
+
+/// Define `target` with a color output and depth of the same size, drawn from `camera`.
+fn define_camera_pass<B: Backend>(
+    plan: &mut RenderPlan<B>,
+    target: Target,
+    camera: Entity,
+    color: ImageOptions,
+) -> Result<(), Error> {
+    let depth = ImageOptions {
+        format: Format::D32Sfloat,
+        clear: Some(ClearValue::DepthStencil(ClearDepthStencil(0.0, 0))),
+        ..color.clone()
+    };
+    plan.define_pass(
+        target,
+        TargetPlanOutputs {
+            colors: vec![crate::bundle::OutputColor::Image(color)],
+            depth: Some(depth),
+        },
+    )?;
+    activate_camera(plan, target, camera);
+    Ok(())
+}
+
+/// Make `camera` the active camera while `target` is drawn.
+fn activate_camera<B: Backend>(plan: &mut RenderPlan<B>, target: Target, camera: Entity) {
+    let activate = ActivateCameraDesc::new(camera);
+    let restore = activate.restore();
+    plan.extend_target(target, move |ctx| {
+        ctx.add(i32::MIN, activate.builder())?;
+        ctx.add(i32::MAX, restore.builder())?;
+        Ok(())
+    });
+}
+
+/// Add the system sorting entities visible to the active camera, unless another plugin did,
+/// so meshes can be drawn to several targets.
+fn add_visibility_system(world: &mut World, builder: &mut DispatcherBuilder<'_, '_>) {
+    if !world.has_value::<Visibility>() {
+        world.insert(Visibility::default());
+        builder.add(VisibilitySortingSystem::new(), "visibility_system", &[]);
+    }
+}
+
+/// Add the system selecting the meshes of every [LodGroup], unless another plugin did.
+fn add_lod_system(world: &mut World, builder: &mut DispatcherBuilder<'_, '_>) {
+    if !world.has_value::<MaskedStorage<LodGroup>>() {
+        world.register::<LodGroup>();
+        builder.add(LodSelectionSystem::new(), "lod_selection_system", &[]);
+    }
+}
+
+/// Marks that a plugin added the [TextureStreamingSystem].
+#[derive(Debug, Default)]
+struct TextureStreamingAdded;
+
+/// Add the system streaming the textures in [TextureStreaming], unless another plugin did.
+fn add_texture_streaming_system<B: Backend>(
+    world: &mut World,
+    builder: &mut DispatcherBuilder<'_, '_>,
+) {
+    if !world.has_value::<TextureStreamingAdded>() {
+        world.insert(TextureStreamingAdded);
+        if !world.has_value::<TextureStreaming>() {
+            world.insert(TextureStreaming::default());
+        }
+        builder.add(
+            TextureStreamingSystem::<B>::new(),
+            "texture_streaming_system",
+            &[],
+        );
+    }
+}
+
+/// Add the system sorting sprites visible to the active camera, unless another plugin did,
+/// so sprites can be drawn to several targets.
+fn add_sprite_visibility_system(world: &mut World, builder: &mut DispatcherBuilder<'_, '_>) {
+    if !world.has_value::<SpriteVisibility>() {
+        world.insert(SpriteVisibility::default());
+        builder.add(
+            SpriteVisibilitySortingSystem::new(),
+            "sprite_visibility_system",
+            &[],
+        );
+    }
+}
+
+/// Define `target` drawing a fullscreen pass over the color outputs of `inputs`.
+fn define_post_process_pass<B: Backend>(
+    plan: &mut RenderPlan<B>,
+    target: Target,
+    options: ImageOptions,
+    desc: DrawPostProcessDesc,
+    inputs: Vec<TargetImage>,
+) -> Result<(), Error> {
+    plan.define_pass(
+        target,
+        TargetPlanOutputs {
+            colors: vec![crate::bundle::OutputColor::Image(options)],
+            depth: None,
+        },
+    )?;
+    // The first input takes the place of the chain input, the rest are additional inputs.
+    let desc = inputs[1..]
+        .iter()
+        .fold(desc, |desc, input| desc.with_input(*input));
+    plan.extend_target(target, move |ctx| {
+        let mut group = desc.builder();
+        for input in inputs {
+            group = group.with_image(ctx.get_image(input)?);
+        }
+        ctx.add(RenderOrder::LinearPostEffects, group)?;
+        Ok(())
+    });
+    Ok(())
+}
+
+/// Define [Target::Velocity] drawing the motion of opaque meshes at the size of `stage`,
+/// unless an earlier effect of the chain already defined it.
+fn define_velocity_pass<B: Backend>(
+    plan: &mut RenderPlan<B>,
+    stage: PostProcessStage,
+    factory: &mut Factory<B>,
+) -> Result<(), Error> {
+    if plan.target_metadata(Target::Velocity, factory).is_some() {
+        return Ok(());
+    }
+
+    let options = stage.image_options(1);
+    plan.define_pass(
+        Target::Velocity,
+        TargetPlanOutputs {
+            colors: vec![crate::bundle::OutputColor::Image(ImageOptions {
+                format: Format::Rg16Sfloat,
+                clear: Some(ClearValue::Color([0.0; 4].into())),
+                ..options.clone()
+            })],
+            depth: Some(ImageOptions {
+                format: Format::D32Sfloat,
+                clear: Some(ClearValue::DepthStencil(ClearDepthStencil(0.0, 0))),
+                ..options
+            }),
+        },
+    )?;
+    plan.extend_target(Target::Velocity, |ctx| {
+        ctx.add(RenderOrder::Opaque, DrawVelocityDesc::new().builder())?;
+        Ok(())
+    });
+    Ok(())
+}
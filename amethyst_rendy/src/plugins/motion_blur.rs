@@ -0,0 +1,56 @@
+use super::define_velocity_pass;
+use crate::{
+    bundle::{PostProcessPlugin, PostProcessStage, RenderPlan},
+    motion_blur::MotionBlurParams,
+    pass::*,
+    Backend, Factory,
+};
+use amethyst_core::ecs::{DispatcherBuilder, World};
+use amethyst_error::Error;
+
+/// A [PostProcessPlugin] blurring moving objects along their motion since the previous frame.
+///
+/// Opaque meshes are drawn to a velocity image, shared with [RenderTaa](super::RenderTaa), and
+/// every pixel is averaged along its motion. The motion of the camera is included, but empty parts
+/// of the image, like the skybox, are not blurred. The number of samples and shutter scale are
+/// configured with the [MotionBlurParams] resource. Add it before [RenderBloom](super::RenderBloom)
+/// and [RenderToneMap](super::RenderToneMap), as it blends linear HDR colors.
+#[derive(Default, Debug)]
+pub struct RenderMotionBlur {
+    params: MotionBlurParams,
+}
+
+impl RenderMotionBlur {
+    /// Create motion blur plugin with the given initial `MotionBlurParams`.
+    pub fn with_params(params: MotionBlurParams) -> Self {
+        Self { params }
+    }
+}
+
+impl<B: Backend> PostProcessPlugin<B> for RenderMotionBlur {
+    fn on_build<'a, 'b>(
+        &mut self,
+        world: &mut World,
+        _builder: &mut DispatcherBuilder<'a, 'b>,
+    ) -> Result<(), Error> {
+        world.insert(self.params);
+        Ok(())
+    }
+
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        stage: PostProcessStage,
+        factory: &mut Factory<B>,
+        world: &World,
+    ) -> Result<(), Error> {
+        define_velocity_pass(plan, stage, factory)?;
+        PostProcessPlugin::<B>::on_plan(
+            &mut DrawPostProcessDesc::motion_blur(),
+            plan,
+            stage,
+            factory,
+            world,
+        )
+    }
+}
@@ -0,0 +1,77 @@
+use crate::{
+    bundle::{RenderPlan, RenderPlugin, Target, TargetImage},
+    occlusion::OcclusionCulling,
+    pass::*,
+    Backend, Factory,
+};
+use amethyst_core::ecs::{DispatcherBuilder, World};
+use amethyst_error::{format_err, Error};
+use rendy::graph::NodeDesc;
+
+/// A [RenderPlugin] skipping the draws of entities fully occluded by the geometry drawn in
+/// front of them, see the [OcclusionCulling] resource.
+///
+/// Once [`Target::Main`] has been drawn, its depth is downsampled into a hierarchical depth buffer
+/// and the bounding boxes of the entities left by frustum culling are tested against it in a
+/// compute pass. Only meshes drawn from [Visibility](crate::visibility::Visibility) are culled,
+/// which excludes the shadow and deferred passes. Add it after the plugin defining the main target,
+/// e.g. `RenderToWindow`.
+#[derive(Debug)]
+pub struct RenderOcclusionCulling {
+    capacity: usize,
+}
+
+impl Default for RenderOcclusionCulling {
+    fn default() -> Self {
+        Self { capacity: 4096 }
+    }
+}
+
+impl RenderOcclusionCulling {
+    /// Test at most `capacity` entities every frame.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+}
+
+impl<B: Backend> RenderPlugin<B> for RenderOcclusionCulling {
+    fn on_build<'a, 'b>(
+        &mut self,
+        world: &mut World,
+        _builder: &mut DispatcherBuilder<'a, 'b>,
+    ) -> Result<(), Error> {
+        world.insert(OcclusionCulling::new(self.capacity));
+        Ok(())
+    }
+
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        factory: &mut Factory<B>,
+        _world: &World,
+    ) -> Result<(), Error> {
+        let metadata = plan.target_metadata(Target::Main, factory).ok_or_else(|| {
+            format_err!(
+                "Occlusion culling requires {:?} target to be defined first.",
+                Target::Main
+            )
+        })?;
+        let hiz = hiz_image_options(metadata.width(), metadata.height());
+        let capacity = self.capacity;
+        plan.extend_target_after(Target::Main, move |ctx| {
+            let depth = ctx.get_image(TargetImage::Depth(Target::Main))?;
+            let pyramid = ctx
+                .graph()
+                .create_image(hiz.kind, hiz.levels, hiz.format, None);
+            ctx.add_compute(
+                HiZOcclusionDesc::new(capacity)
+                    .builder()
+                    .with_image(depth)
+                    .with_image(pyramid),
+            );
+            Ok(())
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,88 @@
+use crate::{
+    bundle::{ImageOptions, RenderPlan, RenderPlugin, Target, TargetPlanOutputs},
+    Backend, Factory, Format, Kind,
+};
+use amethyst_core::ecs::{DispatcherBuilder, World};
+use amethyst_error::Error;
+use rendy::hal::command::{ClearColor, ClearDepthStencil, ClearValue};
+
+/// A [RenderPlugin] rendering [`Target::Main`] into an image instead of a window, so frames
+/// can be rendered without any window or display, e.g. in CI containers or on servers
+/// generating thumbnails.
+///
+/// Use it in place of `RenderToWindow` and add [RenderScreenshot](super::RenderScreenshot) to read
+/// rendered frames back into CPU memory through the
+/// [ScreenshotRequest](crate::screenshot::ScreenshotRequest) resource. The image is `width` by
+/// `height` pixels in `Rgba8Srgb`, like screenshots. With the `window` feature, it also inserts
+/// `ScreenDimensions` of that size unless a window did, for the systems laying out the UI or
+/// picking with the mouse.
+#[derive(Debug)]
+pub struct RenderOffscreen {
+    width: u32,
+    height: u32,
+    clear: ClearColor,
+}
+
+impl RenderOffscreen {
+    /// Create RenderOffscreen plugin rendering `width` by `height` pixels frames.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            clear: ClearColor::Sfloat([0.0, 0.0, 0.0, 1.0]),
+        }
+    }
+
+    /// Clear the image with specified color every frame.
+    pub fn with_clear(mut self, clear: impl Into<ClearColor>) -> Self {
+        self.clear = clear.into();
+        self
+    }
+}
+
+impl<B: Backend> RenderPlugin<B> for RenderOffscreen {
+    fn on_build<'a, 'b>(
+        &mut self,
+        world: &mut World,
+        _builder: &mut DispatcherBuilder<'a, 'b>,
+    ) -> Result<(), Error> {
+        #[cfg(feature = "window")]
+        {
+            use amethyst_window::ScreenDimensions;
+            if !world.has_value::<ScreenDimensions>() {
+                world.insert(ScreenDimensions::new(self.width, self.height, 1.0));
+            }
+        }
+        #[cfg(not(feature = "window"))]
+        let _ = world;
+        Ok(())
+    }
+
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        _factory: &mut Factory<B>,
+        _world: &World,
+    ) -> Result<(), Error> {
+        let color = ImageOptions {
+            kind: Kind::D2(self.width, self.height, 1, 1),
+            levels: 1,
+            format: Format::Rgba8Srgb,
+            clear: Some(ClearValue::Color(self.clear)),
+        };
+        let depth = ImageOptions {
+            format: Format::D32Sfloat,
+            clear: Some(ClearValue::DepthStencil(ClearDepthStencil(0.0, 0))),
+            ..color.clone()
+        };
+
+        plan.add_root(Target::Main);
+        plan.define_pass(
+            Target::Main,
+            TargetPlanOutputs {
+                colors: vec![crate::bundle::OutputColor::Image(color)],
+                depth: Some(depth),
+            },
+        )
+    }
+}
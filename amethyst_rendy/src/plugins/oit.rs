@@ -0,0 +1,72 @@
+use crate::{
+    bundle::{
+        ImageOptions, PostProcessPlugin, PostProcessStage, RenderPlan, Target, TargetPlanOutputs,
+    },
+    pass::*,
+    transparent::TransparencyMode,
+    Backend, Factory, Format,
+};
+use amethyst_core::ecs::{DispatcherBuilder, World};
+use amethyst_error::Error;
+use rendy::hal::command::ClearValue;
+
+/// A [PostProcessPlugin] blending transparent meshes with weighted blended order-independent
+/// transparency instead of sorting them, for scenes with lots of intersecting transparent
+/// geometry like foliage.
+///
+/// The 3d plugins drawing to [Target::Main] accumulate their transparent meshes in any order into
+/// [Target::OrderIndependentTransparency], depth tested against the opaque meshes of the main
+/// target, which must have a depth output. They are composited over the image of the
+/// post-processing chain, see [TransparencyMode::WeightedBlended]. Add it first, before
+/// [RenderBloom](super::RenderBloom) and [RenderToneMap](super::RenderToneMap), as it blends linear
+/// HDR colors.
+#[derive(Default, Debug)]
+pub struct RenderOrderIndependentTransparency;
+
+impl<B: Backend> PostProcessPlugin<B> for RenderOrderIndependentTransparency {
+    fn on_build<'a, 'b>(
+        &mut self,
+        world: &mut World,
+        _builder: &mut DispatcherBuilder<'a, 'b>,
+    ) -> Result<(), Error> {
+        world.insert(TransparencyMode::WeightedBlended);
+        Ok(())
+    }
+
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        stage: PostProcessStage,
+        factory: &mut Factory<B>,
+        world: &World,
+    ) -> Result<(), Error> {
+        let accumulation = ImageOptions {
+            clear: Some(ClearValue::Color([0.0; 4].into())),
+            ..stage.image_options(1)
+        };
+        let revealage = ImageOptions {
+            format: Format::R16Sfloat,
+            clear: Some(ClearValue::Color([1.0; 4].into())),
+            ..stage.image_options(1)
+        };
+        plan.define_pass(
+            Target::OrderIndependentTransparency,
+            TargetPlanOutputs {
+                colors: vec![
+                    crate::bundle::OutputColor::Image(accumulation),
+                    crate::bundle::OutputColor::Image(revealage),
+                ],
+                depth: None,
+            },
+        )?;
+        plan.share_depth(Target::OrderIndependentTransparency, Target::Main);
+
+        PostProcessPlugin::<B>::on_plan(
+            &mut DrawPostProcessDesc::oit_resolve(),
+            plan,
+            stage,
+            factory,
+            world,
+        )
+    }
+}
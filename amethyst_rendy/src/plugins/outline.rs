@@ -0,0 +1,75 @@
+use crate::{
+    bundle::{
+        ImageOptions, PostProcessPlugin, PostProcessStage, RenderOrder, RenderPlan, Target,
+        TargetPlanOutputs,
+    },
+    outline::Outlined,
+    pass::*,
+    Backend, Factory, Format,
+};
+use amethyst_core::ecs::{DispatcherBuilder, World, WorldExt};
+use amethyst_error::Error;
+use rendy::{graph::render::RenderGroupDesc, hal::command::ClearValue};
+
+/// A [PostProcessPlugin] drawing outlines around the silhouettes of [Outlined] meshes, to
+/// show selection.
+///
+/// Silhouettes are drawn with their outline color and width in a prepass, without depth
+/// testing, so outlines stay visible behind other surfaces. Pixels around the silhouettes
+/// are then blended with the color of the nearest silhouette within its width. Add it after
+/// [RenderToneMap](super::RenderToneMap), so outline colors are not affected by exposure.
+#[derive(Default, Debug)]
+pub struct RenderOutline;
+
+/// Render target of the silhouette prepass of outlines.
+const OUTLINE_MASK_TARGET: Target = Target::Custom("outline_mask");
+
+impl<B: Backend> PostProcessPlugin<B> for RenderOutline {
+    fn on_build<'a, 'b>(
+        &mut self,
+        world: &mut World,
+        _builder: &mut DispatcherBuilder<'a, 'b>,
+    ) -> Result<(), Error> {
+        world.register::<Outlined>();
+        Ok(())
+    }
+
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        stage: PostProcessStage,
+        factory: &mut Factory<B>,
+        world: &World,
+    ) -> Result<(), Error> {
+        let options = stage.image_options(1);
+        let mask_image = |format| {
+            crate::bundle::OutputColor::Image(ImageOptions {
+                format,
+                clear: Some(ClearValue::Color([0.0; 4].into())),
+                ..options.clone()
+            })
+        };
+        plan.define_pass(
+            OUTLINE_MASK_TARGET,
+            TargetPlanOutputs {
+                colors: vec![
+                    mask_image(Format::Rgba16Sfloat),
+                    mask_image(Format::R16Sfloat),
+                ],
+                depth: None,
+            },
+        )?;
+        plan.extend_target(OUTLINE_MASK_TARGET, |ctx| {
+            ctx.add(RenderOrder::Opaque, DrawOutlineMaskDesc::new().builder())?;
+            Ok(())
+        });
+
+        PostProcessPlugin::<B>::on_plan(
+            &mut DrawPostProcessDesc::outline(OUTLINE_MASK_TARGET),
+            plan,
+            stage,
+            factory,
+            world,
+        )
+    }
+}
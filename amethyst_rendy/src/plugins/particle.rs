@@ -0,0 +1,215 @@
+use crate::{
+    bundle::{
+        PostProcessPlugin, PostProcessStage, RenderOrder, RenderPlan, RenderPlugin, Target,
+        TargetImage,
+    },
+    particle::{
+        GpuParticleEmitter, GpuParticleSpawnSystem, ParticleEmitter, ParticleSimulationSystem,
+    },
+    pass::*,
+    Backend, Factory,
+};
+use amethyst_core::ecs::{storage::MaskedStorage, DispatcherBuilder, World, WorldExt};
+use amethyst_error::Error;
+use rendy::graph::{render::RenderGroupDesc, NodeDesc};
+
+/// Add the system simulating particles, unless another plugin did.
+fn add_particle_system(world: &mut World, builder: &mut DispatcherBuilder<'_, '_>) {
+    if !world.has_value::<MaskedStorage<ParticleEmitter>>() {
+        world.register::<ParticleEmitter>();
+        builder.add(
+            ParticleSimulationSystem::new(),
+            "particle_simulation_system",
+            &[],
+        );
+    }
+}
+
+/// A plugin drawing the particles of [ParticleEmitter]s as camera facing quads.
+///
+/// Used as a [RenderPlugin], particles are drawn with the transparent objects of the target and
+/// depth tested, so they are cut off with hard edges where they intersect opaque surfaces. Used as
+/// a [PostProcessPlugin], they are drawn over the image of the post-processing chain and fade out
+/// over the fade distance in front of the opaque surfaces of [Target::Main], without the sharp
+/// intersections. Add it before [RenderBloom](super::RenderBloom) and
+/// [RenderToneMap](super::RenderToneMap), as it blends linear HDR colors.
+#[derive(Debug)]
+pub struct RenderParticles {
+    target: Target,
+    fade_distance: f32,
+}
+
+impl Default for RenderParticles {
+    fn default() -> Self {
+        Self {
+            target: Target::default(),
+            fade_distance: 0.5,
+        }
+    }
+}
+
+impl RenderParticles {
+    /// Set target to which particles will be rendered, when used as a [RenderPlugin].
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Set the distance over which soft particles fade out in front of opaque surfaces,
+    /// when used as a [PostProcessPlugin].
+    pub fn with_fade_distance(mut self, fade_distance: f32) -> Self {
+        self.fade_distance = fade_distance;
+        self
+    }
+}
+
+impl<B: Backend> RenderPlugin<B> for RenderParticles {
+    fn on_build<'a, 'b>(
+        &mut self,
+        world: &mut World,
+        builder: &mut DispatcherBuilder<'a, 'b>,
+    ) -> Result<(), Error> {
+        add_particle_system(world, builder);
+        Ok(())
+    }
+
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        _factory: &mut Factory<B>,
+        _world: &World,
+    ) -> Result<(), Error> {
+        plan.extend_target(self.target, |ctx| {
+            ctx.add(RenderOrder::Transparent, DrawParticlesDesc::new().builder())?;
+            Ok(())
+        });
+        Ok(())
+    }
+}
+
+impl<B: Backend> PostProcessPlugin<B> for RenderParticles {
+    fn on_build<'a, 'b>(
+        &mut self,
+        world: &mut World,
+        builder: &mut DispatcherBuilder<'a, 'b>,
+    ) -> Result<(), Error> {
+        add_particle_system(world, builder);
+        Ok(())
+    }
+
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        stage: PostProcessStage,
+        _factory: &mut Factory<B>,
+        _world: &World,
+    ) -> Result<(), Error> {
+        let fade_distance = self.fade_distance;
+        plan.extend_target(stage.target, move |ctx| {
+            let group = DrawPostProcessDesc::passthrough()
+                .builder()
+                .with_image(ctx.get_image(stage.input)?);
+            ctx.add(RenderOrder::BeforeTransparent, group)?;
+            let group = DrawParticlesDesc::new()
+                .soft(fade_distance)
+                .builder()
+                .with_image(ctx.get_image(TargetImage::Depth(Target::Main))?);
+            ctx.add(RenderOrder::Transparent, group)?;
+            Ok(())
+        });
+        Ok(())
+    }
+}
+
+/// A [RenderPlugin] simulating the particles of [GpuParticleEmitter]s on the GPU with a compute
+/// shader, for particle counts too large to simulate on the CPU.
+///
+/// Up to `capacity` particles live at once, in a buffer ping-ponged between frames and drawn
+/// with an indirect draw, so particles never come back to the CPU. Emitters feed up to
+/// `max_spawns` new particles per frame through a staging buffer. Particles are drawn with the
+/// transparent objects of the target, with the texture of the
+/// [GpuParticles](crate::particle::GpuParticles) resource.
+#[derive(Debug)]
+pub struct RenderGpuParticles {
+    target: Target,
+    capacity: u32,
+    max_spawns: u32,
+}
+
+impl Default for RenderGpuParticles {
+    fn default() -> Self {
+        Self {
+            target: Target::default(),
+            capacity: 131_072,
+            max_spawns: 8192,
+        }
+    }
+}
+
+impl RenderGpuParticles {
+    /// Set target to which particles will be rendered.
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Set the maximum number of particles alive at once.
+    pub fn with_capacity(mut self, capacity: u32) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Set the maximum number of particles emitted per frame, extra ones are dropped.
+    pub fn with_max_spawns(mut self, max_spawns: u32) -> Self {
+        self.max_spawns = max_spawns;
+        self
+    }
+}
+
+impl<B: Backend> RenderPlugin<B> for RenderGpuParticles {
+    fn on_build<'a, 'b>(
+        &mut self,
+        world: &mut World,
+        builder: &mut DispatcherBuilder<'a, 'b>,
+    ) -> Result<(), Error> {
+        if !world.has_value::<MaskedStorage<GpuParticleEmitter>>() {
+            world.register::<GpuParticleEmitter>();
+            builder.add(
+                GpuParticleSpawnSystem::new(),
+                "gpu_particle_spawn_system",
+                &[],
+            );
+        }
+        Ok(())
+    }
+
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        _factory: &mut Factory<B>,
+        _world: &World,
+    ) -> Result<(), Error> {
+        let (capacity, max_spawns) = (self.capacity, self.max_spawns);
+        plan.extend_target(self.target, move |ctx| {
+            let particles = ctx
+                .graph()
+                .create_buffer(gpu_particle_buffer_size(capacity));
+            let draw = ctx.graph().create_buffer(GPU_PARTICLE_DRAW_BUFFER_SIZE);
+            ctx.add_compute(
+                GpuParticleSimulationDesc::new(capacity, max_spawns)
+                    .builder()
+                    .with_buffer(particles)
+                    .with_buffer(draw),
+            );
+            ctx.add(
+                RenderOrder::Transparent,
+                DrawGpuParticlesDesc::new()
+                    .builder()
+                    .with_buffer(particles)
+                    .with_buffer(draw),
+            )?;
+            Ok(())
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,88 @@
+use crate::{
+    bundle::{
+        ImageOptions, RenderOrder, RenderPlan, RenderPlugin, Target, TargetImage, TargetPlanOutputs,
+    },
+    pass::*,
+    picking::Picker,
+    Backend, Factory, Format, Kind,
+};
+use amethyst_core::ecs::{DispatcherBuilder, World};
+use amethyst_error::{format_err, Error};
+use rendy::{
+    graph::{render::RenderGroupDesc, NodeDesc},
+    hal::command::{ClearColor, ClearDepthStencil, ClearValue},
+};
+
+/// A [RenderPlugin] drawing entity ids at the position requested by the [Picker] resource,
+/// so entities under the cursor can be picked by their rendered geometry.
+///
+/// Meshes, including skinned ones, and sprites are drawn from the active camera into
+/// [`Target::Picking`], a single pixel onto which the requested pixel of [`Target::Main`] is
+/// projected. The id is read back once the frame has completed, see [Picker::pick]. Add it
+/// after the plugin defining the main target, e.g. `RenderToWindow`.
+#[derive(Default, Debug)]
+pub struct RenderPicking;
+
+impl<B: Backend> RenderPlugin<B> for RenderPicking {
+    fn on_build<'a, 'b>(
+        &mut self,
+        world: &mut World,
+        _builder: &mut DispatcherBuilder<'a, 'b>,
+    ) -> Result<(), Error> {
+        world.insert(Picker::default());
+        Ok(())
+    }
+
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        factory: &mut Factory<B>,
+        _world: &World,
+    ) -> Result<(), Error> {
+        let main = plan.target_metadata(Target::Main, factory).ok_or_else(|| {
+            format_err!("Picking requires {:?} target to be defined.", Target::Main)
+        })?;
+        let (width, height) = (main.width(), main.height());
+
+        let pixel = |format, clear| ImageOptions {
+            kind: Kind::D2(1, 1, 1, 1),
+            levels: 1,
+            format,
+            clear: Some(clear),
+        };
+        plan.define_pass(
+            Target::Picking,
+            TargetPlanOutputs {
+                colors: vec![crate::bundle::OutputColor::Image(pixel(
+                    Format::R32Uint,
+                    ClearValue::Color(ClearColor::Uint([0; 4])),
+                ))],
+                depth: Some(pixel(
+                    Format::D32Sfloat,
+                    ClearValue::DepthStencil(ClearDepthStencil(0.0, 0)),
+                )),
+            },
+        )?;
+        plan.extend_target(Target::Picking, move |ctx| {
+            ctx.add(
+                RenderOrder::Opaque,
+                DrawPickingDesc::new(width, height).builder(),
+            )?;
+            Ok(())
+        });
+
+        plan.extend_target(Target::Main, |ctx| {
+            let image = ctx.get_image(TargetImage::Color(Target::Picking, 0))?;
+            let node = ctx.get_node(Target::Picking)?;
+            let readback = ctx.graph().add_node(
+                PickingReadbackDesc::new()
+                    .builder()
+                    .with_image(image)
+                    .with_dependency(node),
+            );
+            ctx.add_dep(readback);
+            Ok(())
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,124 @@
+use super::define_camera_pass;
+use crate::{
+    bundle::{ImageOptions, RenderPlan, RenderPlugin, Target, TargetImage},
+    pass::*,
+    reflection_probe::{ReflectionProbe, ReflectionProbeSystem, ReflectionProbes},
+    Backend, Factory, Format, Kind,
+};
+use amethyst_core::ecs::{DispatcherBuilder, Entity, Read, SystemData, World, WorldExt};
+use amethyst_error::Error;
+use rendy::{
+    graph::NodeDesc,
+    hal::command::{ClearColor, ClearValue},
+};
+
+/// A [RenderPlugin] capturing the surroundings of [ReflectionProbe]s for the image-based
+/// lighting of nearby PBR meshes.
+///
+/// While a probe is being captured, it renders the cube map faces of the probe into
+/// [`Target::ReflectionProbe`] from a camera created for it, one face per frame. Add the
+/// plugins drawing the surroundings with that target, e.g.
+/// `RenderPbr3D::default().with_target(Target::ReflectionProbe)` and a skybox. The render
+/// graph is rebuilt when a capture starts and ends.
+///
+/// Make the main camera the [ActiveCamera](crate::ActiveCamera), as the capture camera could
+/// otherwise be used as the main camera during captures.
+#[derive(Debug)]
+pub struct RenderReflectionProbes {
+    resolution: u32,
+    near: f32,
+    clear: ClearColor,
+    camera: Option<Entity>,
+}
+
+impl Default for RenderReflectionProbes {
+    fn default() -> Self {
+        Self {
+            resolution: 64,
+            near: 0.1,
+            clear: ClearColor::Sfloat([0.0, 0.0, 0.0, 1.0]),
+            camera: None,
+        }
+    }
+}
+
+impl RenderReflectionProbes {
+    /// Capture cube maps with faces of `resolution` by `resolution` pixels.
+    pub fn with_resolution(mut self, resolution: u32) -> Self {
+        self.resolution = resolution;
+        self
+    }
+
+    /// Capture cube maps with the given near clipping plane.
+    pub fn with_near(mut self, near: f32) -> Self {
+        self.near = near;
+        self
+    }
+
+    /// Clear the captured faces with specified color.
+    pub fn with_clear(mut self, clear: impl Into<ClearColor>) -> Self {
+        self.clear = clear.into();
+        self
+    }
+}
+
+impl<B: Backend> RenderPlugin<B> for RenderReflectionProbes {
+    fn on_build<'a, 'b>(
+        &mut self,
+        world: &mut World,
+        builder: &mut DispatcherBuilder<'a, 'b>,
+    ) -> Result<(), Error> {
+        world.register::<ReflectionProbe>();
+        world.insert(ReflectionProbes::new(self.resolution, self.near));
+        builder.add(ReflectionProbeSystem::new(), "reflection_probe_system", &[]);
+        Ok(())
+    }
+
+    fn should_rebuild(&mut self, world: &World) -> bool {
+        let camera = <Option<Read<'_, ReflectionProbes>>>::fetch(world)
+            .and_then(|probes| probes.capture_camera());
+        if camera != self.camera {
+            self.camera = camera;
+            return true;
+        }
+        false
+    }
+
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        _factory: &mut Factory<B>,
+        world: &World,
+    ) -> Result<(), Error> {
+        let camera = match self.camera {
+            Some(camera) => camera,
+            None => return Ok(()),
+        };
+        let resolution = <Read<'_, ReflectionProbes>>::fetch(world).resolution();
+        define_camera_pass(
+            plan,
+            Target::ReflectionProbe,
+            camera,
+            ImageOptions {
+                kind: Kind::D2(resolution, resolution, 1, 1),
+                levels: 1,
+                format: Format::Rgba16Sfloat,
+                clear: Some(ClearValue::Color(self.clear)),
+            },
+        )?;
+
+        plan.extend_target(Target::Main, move |ctx| {
+            let image = ctx.get_image(TargetImage::Color(Target::ReflectionProbe, 0))?;
+            let node = ctx.get_node(Target::ReflectionProbe)?;
+            let readback = ctx.graph().add_node(
+                ReflectionProbeReadbackDesc::new()
+                    .builder()
+                    .with_image(image)
+                    .with_dependency(node),
+            );
+            ctx.add_dep(readback);
+            Ok(())
+        });
+        Ok(())
+    }
+}
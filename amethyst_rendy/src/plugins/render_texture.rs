@@ -0,0 +1,134 @@
+use super::define_camera_pass;
+use crate::{
+    bundle::{ImageOptions, RenderPlan, RenderPlugin, Target, TargetImage},
+    pass::*,
+    render_texture::{target_camera, CameraTarget, RENDER_TEXTURE_FORMAT},
+    types::Texture,
+    Backend, Factory, Kind,
+};
+use amethyst_assets::AssetStorage;
+use amethyst_core::ecs::{DispatcherBuilder, Entity, Read, SystemData, World, WorldExt};
+use amethyst_error::Error;
+use rendy::{
+    graph::NodeDesc,
+    hal::command::{ClearColor, ClearValue},
+};
+
+/// A [RenderPlugin] rendering the camera with a [CameraTarget] for `target` into its texture,
+/// e.g. for mirrors, security monitors or minimaps.
+///
+/// It defines `target` with the size of the `CameraTarget`. Add the plugins drawing the view
+/// with the same target, e.g. `RenderPbr3D::default().with_target(target)`. The image is
+/// copied into the texture before [`Target::Main`] is drawn, so the main view samples the
+/// texture of the same frame. Nothing is rendered while there is no such camera or its
+/// texture is loading.
+///
+/// Make the main camera the [ActiveCamera](crate::ActiveCamera), as it could otherwise be
+/// any camera. Images drawn for the active camera in other targets, like shadow cascades and
+/// screen-space ambient occlusion, follow the main camera.
+#[derive(Debug)]
+pub struct RenderToTexture {
+    target: Target,
+    clear: ClearColor,
+    camera: Option<(Entity, CameraTarget, u32)>,
+}
+
+impl RenderToTexture {
+    /// Create RenderToTexture plugin rendering `target` from the camera targeting it.
+    pub fn new(target: Target) -> Self {
+        Self {
+            target,
+            clear: ClearColor::Sfloat([0.0, 0.0, 0.0, 1.0]),
+            camera: None,
+        }
+    }
+
+    /// Clear the texture with specified color every frame.
+    pub fn with_clear(mut self, clear: impl Into<ClearColor>) -> Self {
+        self.clear = clear.into();
+        self
+    }
+}
+
+impl<B: Backend> RenderPlugin<B> for RenderToTexture {
+    fn on_build<'a, 'b>(
+        &mut self,
+        world: &mut World,
+        _builder: &mut DispatcherBuilder<'a, 'b>,
+    ) -> Result<(), Error> {
+        world.register::<CameraTarget>();
+        Ok(())
+    }
+
+    fn should_rebuild(&mut self, world: &World) -> bool {
+        let textures = <Option<Read<'_, AssetStorage<Texture>>>>::fetch(world);
+        // The version changes when the texture is reloaded into a new image.
+        let camera = target_camera(world, self.target).and_then(|(entity, camera_target)| {
+            let version = textures.as_ref()?.get_version(&camera_target.texture)?;
+            Some((entity, camera_target, version))
+        });
+        if camera != self.camera {
+            self.camera = camera;
+            return true;
+        }
+        false
+    }
+
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        _factory: &mut Factory<B>,
+        world: &World,
+    ) -> Result<(), Error> {
+        let (camera, camera_target) = match &self.camera {
+            Some((camera, camera_target, _)) => (*camera, camera_target),
+            None => return Ok(()),
+        };
+        let textures = <Read<'_, AssetStorage<Texture>>>::fetch(world);
+        let texture = match textures
+            .get(&camera_target.texture)
+            .and_then(B::unwrap_texture)
+        {
+            Some(texture) => texture.image().clone(),
+            None => return Ok(()),
+        };
+        let kind = Kind::D2(camera_target.width, camera_target.height, 1, 1);
+        if texture.kind().extent() != kind.extent() || texture.format() != RENDER_TEXTURE_FORMAT {
+            log::warn!(
+                "Texture of camera target {:?} isn't {}x{} {:?}, skipping it.",
+                self.target,
+                camera_target.width,
+                camera_target.height,
+                RENDER_TEXTURE_FORMAT,
+            );
+            return Ok(());
+        }
+
+        define_camera_pass(
+            plan,
+            self.target,
+            camera,
+            ImageOptions {
+                kind,
+                levels: 1,
+                format: RENDER_TEXTURE_FORMAT,
+                clear: Some(ClearValue::Color(self.clear)),
+            },
+        )?;
+
+        let target = self.target;
+        plan.extend_target(Target::Main, move |ctx| {
+            let image = ctx.get_image(TargetImage::Color(target, 0))?;
+            let node = ctx.get_node(target)?;
+            let copy = ctx.graph().add_node(
+                CopyToTextureDesc::new(texture)
+                    .builder()
+                    .with_image(image)
+                    .with_dependency(node),
+            );
+            ctx.add_dep(copy);
+            Ok(())
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,75 @@
+use super::define_post_process_pass;
+use crate::{
+    bundle::{
+        ImageOptions, PostProcessPlugin, PostProcessStage, RenderOrder, RenderPlan, Target,
+        TargetImage,
+    },
+    pass::*,
+    screenshot::{FrameCapture, FrameCaptureSystem, ScreenshotRequest},
+    Backend, Factory, Format,
+};
+use amethyst_core::ecs::{DispatcherBuilder, World};
+use amethyst_error::Error;
+use rendy::graph::{render::RenderGroupDesc, NodeDesc};
+
+/// A [PostProcessPlugin] capturing the final image of the window on request, see
+/// [ScreenshotRequest].
+///
+/// The image is drawn unchanged into an `Rgba8Srgb` image as well, which is copied into CPU
+/// memory in frames a screenshot is requested. Add it last, so the screenshot includes all
+/// other effects. Frames are also recorded to PNG files through the [FrameCapture] resource.
+#[derive(Default, Debug)]
+pub struct RenderScreenshot;
+
+/// Render target of the image captured by screenshots.
+const SCREENSHOT_TARGET: Target = Target::Custom("screenshot");
+
+impl<B: Backend> PostProcessPlugin<B> for RenderScreenshot {
+    fn on_build<'a, 'b>(
+        &mut self,
+        world: &mut World,
+        builder: &mut DispatcherBuilder<'a, 'b>,
+    ) -> Result<(), Error> {
+        world.insert(ScreenshotRequest::default());
+        world.insert(FrameCapture::default());
+        builder.add(FrameCaptureSystem::new(), "frame_capture_system", &[]);
+        Ok(())
+    }
+
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        stage: PostProcessStage,
+        _factory: &mut Factory<B>,
+        _world: &World,
+    ) -> Result<(), Error> {
+        define_post_process_pass(
+            plan,
+            SCREENSHOT_TARGET,
+            ImageOptions {
+                format: Format::Rgba8Srgb,
+                ..stage.image_options(1)
+            },
+            DrawPostProcessDesc::passthrough(),
+            vec![stage.input],
+        )?;
+
+        plan.extend_target(stage.target, move |ctx| {
+            let captured = ctx.get_image(TargetImage::Color(SCREENSHOT_TARGET, 0))?;
+            let node = ctx.get_node(SCREENSHOT_TARGET)?;
+            let readback = ctx.graph().add_node(
+                ScreenshotReadbackDesc::new()
+                    .builder()
+                    .with_image(captured)
+                    .with_dependency(node),
+            );
+            ctx.add_dep(readback);
+            let group = DrawPostProcessDesc::passthrough()
+                .builder()
+                .with_image(ctx.get_image(stage.input)?);
+            ctx.add(RenderOrder::DisplayPostEffects, group)?;
+            Ok(())
+        });
+        Ok(())
+    }
+}
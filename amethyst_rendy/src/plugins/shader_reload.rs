@@ -0,0 +1,68 @@
+use crate::{
+    bundle::{RenderPlan, RenderPlugin},
+    Backend, Factory,
+};
+use amethyst_core::ecs::World;
+use amethyst_error::Error;
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+/// A [RenderPlugin] rebuilding the render graph when the SPIR-V file of a built-in shader
+/// changes, so shaders can be iterated on without restarting the game.
+///
+/// Shaders are reloaded from the directories registered with `with_shader_root` for the name
+/// of the crate embedding them, e.g. `amethyst_rendy`, in which their files are in `compiled`.
+/// Recompile the GLSL sources with `make` there to pick up changes. Shaders of crates without
+/// a registered directory are not reloaded. Requires the `shader-hot-reload` feature.
+#[derive(Debug)]
+pub struct RenderShaderHotReload {
+    interval: Duration,
+    last_check: Instant,
+    roots: HashMap<String, PathBuf>,
+}
+
+impl Default for RenderShaderHotReload {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(1),
+            last_check: Instant::now(),
+            roots: HashMap::new(),
+        }
+    }
+}
+
+impl RenderShaderHotReload {
+    /// Check shader files for changes every `interval`.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Reload the shaders embedded by the crate `name` from the crate's checkout at `dir`.
+    pub fn with_shader_root(mut self, name: impl Into<String>, dir: impl Into<PathBuf>) -> Self {
+        self.roots.insert(name.into(), dir.into());
+        self
+    }
+}
+
+impl<B: Backend> RenderPlugin<B> for RenderShaderHotReload {
+    fn should_rebuild(&mut self, _world: &World) -> bool {
+        if self.last_check.elapsed() < self.interval {
+            return false;
+        }
+        self.last_check = Instant::now();
+        crate::shader_reload::reload_modified_shaders(&self.roots)
+    }
+
+    fn on_plan(
+        &mut self,
+        _plan: &mut RenderPlan<B>,
+        _factory: &mut Factory<B>,
+        _world: &World,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+}
@@ -0,0 +1,129 @@
+use crate::{
+    bundle::{ImageOptions, RenderOrder, RenderPlan, RenderPlugin, Target},
+    pass::*,
+    shadow::ShadowParams,
+    Backend, Factory, Format, Kind,
+};
+use amethyst_core::ecs::{DispatcherBuilder, Read, SystemData, World};
+use amethyst_error::Error;
+use rendy::{
+    graph::render::RenderGroupDesc,
+    hal::command::{ClearDepthStencil, ClearValue},
+};
+
+/// A `RenderPlugin` rendering cascaded shadow maps of the first directional light,
+/// cube maps of point lights and depth maps of spot lights with `casts_shadows` set.
+///
+/// Shadows are sampled by `RenderPbr3D`. Cascade count, resolution, filtering and
+/// the point and spot light shadow budgets are configured with the [ShadowParams] resource.
+#[derive(Default, Debug)]
+pub struct RenderShadows {
+    params: ShadowParams,
+    atlas_sizes: Option<ShadowAtlasSizes>,
+}
+
+type ShadowAtlasSizes = ((u32, u32), Option<(u32, u32)>, Option<(u32, u32)>);
+
+fn shadow_atlas_sizes(params: &ShadowParams) -> ShadowAtlasSizes {
+    (
+        params.atlas_size(),
+        params.point_atlas_size(),
+        params.spot_atlas_size(),
+    )
+}
+
+fn shadow_atlas_options(width: u32, height: u32) -> ImageOptions {
+    ImageOptions {
+        kind: Kind::D2(width, height, 1, 1),
+        levels: 1,
+        format: Format::D32Sfloat,
+        clear: Some(ClearValue::DepthStencil(ClearDepthStencil(0.0, 0))),
+    }
+}
+
+impl RenderShadows {
+    /// Create shadows plugin with the given initial `ShadowParams`.
+    pub fn with_params(params: ShadowParams) -> Self {
+        Self {
+            params,
+            atlas_sizes: None,
+        }
+    }
+}
+
+impl<B: Backend> RenderPlugin<B> for RenderShadows {
+    fn on_build<'a, 'b>(
+        &mut self,
+        world: &mut World,
+        _builder: &mut DispatcherBuilder<'a, 'b>,
+    ) -> Result<(), Error> {
+        world.insert(self.params);
+        Ok(())
+    }
+
+    fn should_rebuild(&mut self, world: &World) -> bool {
+        let params = <Option<Read<'_, ShadowParams>>>::fetch(world).map_or(self.params, |p| *p);
+        self.atlas_sizes != Some(shadow_atlas_sizes(&params))
+    }
+
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        _factory: &mut Factory<B>,
+        world: &World,
+    ) -> Result<(), Error> {
+        if let Some(params) = <Option<Read<'_, ShadowParams>>>::fetch(world) {
+            self.params = *params;
+        }
+        let atlas_sizes = shadow_atlas_sizes(&self.params);
+        self.atlas_sizes = Some(atlas_sizes);
+        let ((width, height), point_atlas_size, spot_atlas_size) = atlas_sizes;
+
+        plan.define_pass(
+            Target::ShadowMap,
+            crate::bundle::TargetPlanOutputs {
+                colors: vec![],
+                depth: Some(shadow_atlas_options(width, height)),
+            },
+        )?;
+        plan.extend_target(Target::ShadowMap, |ctx| {
+            ctx.add(RenderOrder::Opaque, DrawShadowsDesc::new().builder())?;
+            Ok(())
+        });
+
+        if let Some((width, height)) = point_atlas_size {
+            plan.define_pass(
+                Target::PointShadowMap,
+                crate::bundle::TargetPlanOutputs {
+                    colors: vec![],
+                    depth: Some(shadow_atlas_options(width, height)),
+                },
+            )?;
+            plan.extend_target(Target::PointShadowMap, |ctx| {
+                ctx.add(
+                    RenderOrder::Opaque,
+                    DrawShadowsDesc::point_lights().builder(),
+                )?;
+                Ok(())
+            });
+        }
+
+        if let Some((width, height)) = spot_atlas_size {
+            plan.define_pass(
+                Target::SpotShadowMap,
+                crate::bundle::TargetPlanOutputs {
+                    colors: vec![],
+                    depth: Some(shadow_atlas_options(width, height)),
+                },
+            )?;
+            plan.extend_target(Target::SpotShadowMap, |ctx| {
+                ctx.add(
+                    RenderOrder::Opaque,
+                    DrawShadowsDesc::spot_lights().builder(),
+                )?;
+                Ok(())
+            });
+        }
+        Ok(())
+    }
+}
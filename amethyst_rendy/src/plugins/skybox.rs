@@ -0,0 +1,57 @@
+use crate::{
+    bundle::{RenderOrder, RenderPlan, RenderPlugin, Target},
+    pass::*,
+    Backend, Factory,
+};
+use amethyst_core::ecs::World;
+use amethyst_error::Error;
+use palette::Srgb;
+use rendy::graph::render::RenderGroupDesc;
+
+/// RenderPlugin for rendering skyboxes.
+///
+/// Draws a gradient between the nadir and zenith colors, or the cube map texture
+/// of the [SkyboxCubemap](crate::resources::SkyboxCubemap) resource once it is loaded.
+#[derive(Default, Debug)]
+pub struct RenderSkybox {
+    target: Target,
+    colors: Option<(Srgb, Srgb)>,
+}
+
+impl RenderSkybox {
+    /// Create skybox with specified nadir and zenith colors.
+    pub fn with_colors(nadir_color: Srgb, zenith_color: Srgb) -> Self {
+        Self {
+            target: Default::default(),
+            colors: Some((nadir_color, zenith_color)),
+        }
+    }
+
+    /// Set target to which skybox will be rendered.
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+}
+
+impl<B: Backend> RenderPlugin<B> for RenderSkybox {
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        _factory: &mut Factory<B>,
+        _world: &World,
+    ) -> Result<(), Error> {
+        let colors = self.colors;
+        plan.extend_target(self.target, move |ctx| {
+            let group = if let Some((nadir, zenith)) = colors {
+                DrawSkyboxDesc::with_colors(nadir, zenith).builder()
+            } else {
+                DrawSkyboxDesc::new().builder()
+            };
+
+            ctx.add(RenderOrder::AfterOpaque, group)?;
+            Ok(())
+        });
+        Ok(())
+    }
+}
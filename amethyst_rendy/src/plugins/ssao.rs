@@ -0,0 +1,113 @@
+use super::define_post_process_pass;
+use crate::{
+    bundle::{
+        ImageOptions, RenderOrder, RenderPlan, RenderPlugin, Target, TargetImage, TargetPlanOutputs,
+    },
+    pass::*,
+    ssao::SsaoParams,
+    Backend, Factory, Format, Kind,
+};
+use amethyst_core::ecs::{DispatcherBuilder, World};
+use amethyst_error::{format_err, Error};
+use rendy::{
+    graph::render::RenderGroupDesc,
+    hal::command::{ClearDepthStencil, ClearValue},
+};
+
+/// A `RenderPlugin` computing screen-space ambient occlusion, which darkens the ambient
+/// lighting of `RenderPbr3D` in creases and corners.
+///
+/// View space normals and depth of opaque static meshes are drawn in a prepass. Occlusion is
+/// estimated from them by sampling a hemisphere around every pixel, and then blurred.
+/// Radius, bias, intensity and the number of samples are configured with the [SsaoParams]
+/// resource. Add it after the plugin defining its target, e.g. `RenderToWindow`.
+#[derive(Default, Debug)]
+pub struct RenderSsao {
+    target: Target,
+    params: SsaoParams,
+}
+
+/// Render targets of the normal and depth prepass and of the unblurred ambient occlusion.
+const SSAO_NORMALS_TARGET: Target = Target::Custom("ssao_normals");
+const SSAO_OCCLUSION_TARGET: Target = Target::Custom("ssao_occlusion");
+
+impl RenderSsao {
+    /// Create SSAO plugin with the given initial `SsaoParams`.
+    pub fn with_params(params: SsaoParams) -> Self {
+        Self {
+            params,
+            ..Default::default()
+        }
+    }
+
+    /// Set target whose 3d meshes are occluded, the occlusion image matches its size.
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+}
+
+impl<B: Backend> RenderPlugin<B> for RenderSsao {
+    fn on_build<'a, 'b>(
+        &mut self,
+        world: &mut World,
+        _builder: &mut DispatcherBuilder<'a, 'b>,
+    ) -> Result<(), Error> {
+        world.insert(self.params);
+        Ok(())
+    }
+
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        factory: &mut Factory<B>,
+        _world: &World,
+    ) -> Result<(), Error> {
+        let metadata = plan.target_metadata(self.target, factory).ok_or_else(|| {
+            format_err!(
+                "Screen-space ambient occlusion requires {:?} target to be defined first.",
+                self.target
+            )
+        })?;
+        let options = |format, clear| ImageOptions {
+            kind: Kind::D2(metadata.width(), metadata.height(), 1, 1),
+            levels: 1,
+            format,
+            clear,
+        };
+
+        plan.define_pass(
+            SSAO_NORMALS_TARGET,
+            TargetPlanOutputs {
+                colors: vec![crate::bundle::OutputColor::Image(options(
+                    Format::Rgba16Sfloat,
+                    Some(ClearValue::Color([0.0; 4].into())),
+                ))],
+                depth: Some(options(
+                    Format::D32Sfloat,
+                    Some(ClearValue::DepthStencil(ClearDepthStencil(0.0, 0))),
+                )),
+            },
+        )?;
+        plan.extend_target(SSAO_NORMALS_TARGET, |ctx| {
+            ctx.add(RenderOrder::Opaque, DrawSsaoNormalsDesc::new().builder())?;
+            Ok(())
+        });
+
+        let normals = TargetImage::Color(SSAO_NORMALS_TARGET, 0);
+        define_post_process_pass(
+            plan,
+            SSAO_OCCLUSION_TARGET,
+            options(Format::R8Unorm, None),
+            DrawPostProcessDesc::ssao(),
+            vec![normals],
+        )?;
+        define_post_process_pass(
+            plan,
+            Target::AmbientOcclusion,
+            options(Format::R8Unorm, None),
+            DrawPostProcessDesc::ssao_blur(),
+            vec![TargetImage::Color(SSAO_OCCLUSION_TARGET, 0), normals],
+        )
+    }
+}
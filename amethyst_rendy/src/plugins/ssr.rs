@@ -0,0 +1,92 @@
+use crate::{
+    bundle::{
+        ImageOptions, PostProcessPlugin, PostProcessStage, RenderOrder, RenderPlan, Target,
+        TargetPlanOutputs,
+    },
+    pass::*,
+    ssr::SsrParams,
+    Backend, Factory, Format,
+};
+use amethyst_core::ecs::{DispatcherBuilder, World};
+use amethyst_error::Error;
+use rendy::{
+    graph::render::RenderGroupDesc,
+    hal::command::{ClearDepthStencil, ClearValue},
+};
+
+/// A [PostProcessPlugin] reflecting surfaces visible on screen in metallic and smooth surfaces.
+///
+/// Normals, depth, reflectance and roughness of opaque static meshes are drawn in a prepass. For
+/// every reflecting pixel, the reflected ray is marched through the depth buffer and the image is
+/// sampled where it hits a surface. Rays that leave the screen or don't hit anything keep the
+/// reflection of the `EnvironmentMap`. Step counts, distance and intensity are configured with the
+/// [SsrParams] resource. Add it before [RenderBloom](super::RenderBloom) and
+/// [RenderToneMap](super::RenderToneMap), as it blends linear HDR colors.
+#[derive(Default, Debug)]
+pub struct RenderSsr {
+    params: SsrParams,
+}
+
+/// Render target of the surfaces prepass of screen-space reflections.
+const SSR_SURFACES_TARGET: Target = Target::Custom("ssr_surfaces");
+
+impl RenderSsr {
+    /// Create SSR plugin with the given initial `SsrParams`.
+    pub fn with_params(params: SsrParams) -> Self {
+        Self { params }
+    }
+}
+
+impl<B: Backend> PostProcessPlugin<B> for RenderSsr {
+    fn on_build<'a, 'b>(
+        &mut self,
+        world: &mut World,
+        _builder: &mut DispatcherBuilder<'a, 'b>,
+    ) -> Result<(), Error> {
+        world.insert(self.params);
+        Ok(())
+    }
+
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        stage: PostProcessStage,
+        factory: &mut Factory<B>,
+        world: &World,
+    ) -> Result<(), Error> {
+        let options = stage.image_options(1);
+        let surface_image = |format| {
+            crate::bundle::OutputColor::Image(ImageOptions {
+                format,
+                clear: Some(ClearValue::Color([0.0; 4].into())),
+                ..options.clone()
+            })
+        };
+        plan.define_pass(
+            SSR_SURFACES_TARGET,
+            TargetPlanOutputs {
+                colors: vec![
+                    surface_image(Format::Rgba16Sfloat),
+                    surface_image(Format::Rgba8Unorm),
+                ],
+                depth: Some(ImageOptions {
+                    format: Format::D32Sfloat,
+                    clear: Some(ClearValue::DepthStencil(ClearDepthStencil(0.0, 0))),
+                    ..options.clone()
+                }),
+            },
+        )?;
+        plan.extend_target(SSR_SURFACES_TARGET, |ctx| {
+            ctx.add(RenderOrder::Opaque, DrawSsrSurfacesDesc::new().builder())?;
+            Ok(())
+        });
+
+        PostProcessPlugin::<B>::on_plan(
+            &mut DrawPostProcessDesc::ssr(SSR_SURFACES_TARGET),
+            plan,
+            stage,
+            factory,
+            world,
+        )
+    }
+}
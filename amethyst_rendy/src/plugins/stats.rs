@@ -0,0 +1,50 @@
+use crate::{
+    bundle::{RenderPlan, RenderPlugin},
+    stats::RenderStats,
+    Backend, Factory,
+};
+use amethyst_core::ecs::{DispatcherBuilder, World};
+use amethyst_error::Error;
+
+/// A [RenderPlugin] measuring the GPU time of every render pass and render group, and the
+/// draw calls of every render group, into the [RenderStats] resource, e.g. for performance
+/// overlays.
+///
+/// While profiling, every render group is drawn in its own render pass, with timestamps
+/// written in between, and targets render to an image copied to the window surface.
+/// Timestamps are converted with the period of the GPU timestamp counter, which the
+/// graphics backend doesn't report. It is measured against the CPU clock unless set with
+/// `with_timestamp_period`.
+#[derive(Debug, Default)]
+pub struct RenderPassStats {
+    timestamp_period: Option<f32>,
+}
+
+impl RenderPassStats {
+    /// Convert timestamps with `timestamp_period` nanoseconds per tick instead of measuring it.
+    pub fn with_timestamp_period(mut self, timestamp_period: f32) -> Self {
+        self.timestamp_period = Some(timestamp_period);
+        self
+    }
+}
+
+impl<B: Backend> RenderPlugin<B> for RenderPassStats {
+    fn on_build<'a, 'b>(
+        &mut self,
+        world: &mut World,
+        _builder: &mut DispatcherBuilder<'a, 'b>,
+    ) -> Result<(), Error> {
+        world.insert(RenderStats::new(self.timestamp_period));
+        Ok(())
+    }
+
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        _factory: &mut Factory<B>,
+        _world: &World,
+    ) -> Result<(), Error> {
+        plan.time_passes();
+        Ok(())
+    }
+}
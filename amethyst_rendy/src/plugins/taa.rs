@@ -0,0 +1,111 @@
+use super::define_velocity_pass;
+use crate::{
+    bundle::{
+        PostProcessPlugin, PostProcessStage, RenderOrder, RenderPlan, Target, TargetImage,
+        TargetPlanOutputs,
+    },
+    pass::*,
+    taa::{TaaJitter, TaaParams},
+    Backend, Factory,
+};
+use amethyst_core::ecs::{DispatcherBuilder, SystemData, World, WriteExpect};
+use amethyst_error::Error;
+use rendy::graph::{render::RenderGroupDesc, NodeDesc};
+use std::{cell::Cell, rc::Rc};
+
+/// A [PostProcessPlugin] smoothing aliasing by accumulating jittered frames over time.
+///
+/// The camera projection is offset by a different sub-pixel amount every frame. Opaque meshes are
+/// drawn to a velocity image, used to reproject the anti-aliased image of the previous frame, which
+/// is then blended with the current one. Add it before [RenderBloom](super::RenderBloom) and
+/// [RenderToneMap](super::RenderToneMap), as it blends linear HDR colors. It can be enabled,
+/// disabled and tuned at runtime through the [TaaParams] resource.
+#[derive(Default, Debug)]
+pub struct RenderTaa {
+    params: TaaParams,
+}
+
+/// Render target of the resolve pass of temporal anti-aliasing.
+const TAA_RESOLVE_TARGET: Target = Target::Custom("taa_resolve");
+
+impl RenderTaa {
+    /// Create TAA plugin with the given initial `TaaParams`.
+    pub fn with_params(params: TaaParams) -> Self {
+        Self { params }
+    }
+}
+
+impl<B: Backend> PostProcessPlugin<B> for RenderTaa {
+    fn on_build<'a, 'b>(
+        &mut self,
+        world: &mut World,
+        _builder: &mut DispatcherBuilder<'a, 'b>,
+    ) -> Result<(), Error> {
+        world.insert(self.params);
+        world.insert(TaaJitter::default());
+        Ok(())
+    }
+
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        stage: PostProcessStage,
+        factory: &mut Factory<B>,
+        world: &World,
+    ) -> Result<(), Error> {
+        <WriteExpect<'_, TaaJitter>>::fetch(world).extent = Some((stage.width, stage.height));
+
+        define_velocity_pass(plan, stage, factory)?;
+
+        let color = stage.image_options(1);
+        // The history image is written after the resolve pass, so it isn't a render target
+        // output the resolve pass could depend on.
+        let history = Rc::new(Cell::new(None));
+        plan.define_pass(
+            TAA_RESOLVE_TARGET,
+            TargetPlanOutputs {
+                colors: vec![crate::bundle::OutputColor::Image(color.clone())],
+                depth: None,
+            },
+        )?;
+        let resolve_history = history.clone();
+        plan.extend_target(TAA_RESOLVE_TARGET, move |ctx| {
+            let velocity = TargetImage::Color(Target::Velocity, 0);
+            let history_image =
+                ctx.graph()
+                    .create_image(color.kind, color.levels, color.format, None);
+            resolve_history.set(Some(history_image));
+            let group = DrawPostProcessDesc::taa_resolve(velocity)
+                .builder()
+                .with_image(ctx.get_image(stage.input)?)
+                .with_image(ctx.get_image(velocity)?)
+                .with_image(history_image);
+            ctx.add(RenderOrder::LinearPostEffects, group)?;
+            Ok(())
+        });
+
+        plan.extend_target(stage.target, move |ctx| {
+            let resolved = ctx.get_image(TargetImage::Color(TAA_RESOLVE_TARGET, 0))?;
+            let resolve_node = ctx.get_node(TAA_RESOLVE_TARGET)?;
+            let history_image = history
+                .get()
+                .expect("Resolve target defines the history image");
+            let node = ctx.graph().add_node(
+                TaaHistoryDesc::new()
+                    .builder()
+                    .with_image(resolved)
+                    .with_image(history_image)
+                    .with_dependency(resolve_node),
+            );
+            ctx.add_dep(node);
+            ctx.add(
+                RenderOrder::LinearPostEffects,
+                DrawPostProcessDesc::passthrough()
+                    .builder()
+                    .with_image(resolved),
+            )?;
+            Ok(())
+        });
+        Ok(())
+    }
+}
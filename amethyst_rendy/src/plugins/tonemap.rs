@@ -0,0 +1,83 @@
+use crate::{
+    bundle::{PostProcessPlugin, PostProcessStage, RenderOrder, RenderPlan},
+    pass::*,
+    tonemap::ToneMapParams,
+    Backend, Factory,
+};
+use amethyst_core::ecs::{DispatcherBuilder, Read, SystemData, World};
+use amethyst_error::Error;
+use rendy::graph::{render::RenderGroupDesc, NodeDesc};
+
+/// A [PostProcessPlugin] mapping the high dynamic range image to the displayable range.
+///
+/// The operator and exposure are configured with the [ToneMapParams] resource. With auto
+/// exposure enabled, average luminance of the image is measured with a histogram on the GPU
+/// and exposure adapts to it over time. Add it after effects working on linear HDR colors,
+/// like [RenderBloom](super::RenderBloom).
+#[derive(Default, Debug)]
+pub struct RenderToneMap {
+    params: ToneMapParams,
+    auto_exposure: Option<bool>,
+}
+
+impl RenderToneMap {
+    /// Create tonemapping plugin with the given initial `ToneMapParams`.
+    pub fn with_params(params: ToneMapParams) -> Self {
+        Self {
+            params,
+            auto_exposure: None,
+        }
+    }
+}
+
+impl<B: Backend> PostProcessPlugin<B> for RenderToneMap {
+    fn on_build<'a, 'b>(
+        &mut self,
+        world: &mut World,
+        _builder: &mut DispatcherBuilder<'a, 'b>,
+    ) -> Result<(), Error> {
+        world.insert(self.params);
+        Ok(())
+    }
+
+    fn should_rebuild(&mut self, world: &World) -> bool {
+        let params = <Option<Read<'_, ToneMapParams>>>::fetch(world).map_or(self.params, |p| *p);
+        self.auto_exposure != Some(params.auto_exposure)
+    }
+
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        stage: PostProcessStage,
+        _factory: &mut Factory<B>,
+        world: &World,
+    ) -> Result<(), Error> {
+        self.params = ToneMapParams::fetch(world);
+        let auto_exposure = self.params.auto_exposure;
+        self.auto_exposure = Some(auto_exposure);
+
+        plan.extend_target(stage.target, move |ctx| {
+            let input = ctx.get_image(stage.input)?;
+            let group = if auto_exposure {
+                let exposure = ctx.graph().create_buffer(EXPOSURE_BUFFER_SIZE);
+                ctx.add_compute(
+                    AutoExposureDesc::new()
+                        .builder()
+                        .with_image(input)
+                        .with_buffer(exposure),
+                );
+                DrawPostProcessDesc::tonemap(true)
+                    .builder()
+                    .with_image(input)
+                    .with_buffer(exposure)
+            } else {
+                DrawPostProcessDesc::tonemap(false)
+                    .builder()
+                    .with_image(input)
+            };
+            ctx.add(RenderOrder::ToneMap, group)?;
+            Ok(())
+        });
+        Ok(())
+    }
+}
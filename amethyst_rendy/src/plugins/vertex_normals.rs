@@ -0,0 +1,51 @@
+use crate::{
+    bundle::{RenderOrder, RenderPlan, RenderPlugin, Target},
+    debug_drawing::VertexNormalsDebug,
+    pass::*,
+    Backend, Factory,
+};
+use amethyst_core::ecs::{DispatcherBuilder, World, WorldExt};
+use amethyst_error::Error;
+use rendy::graph::render::RenderGroupDesc;
+
+/// A [RenderPlugin] for drawing the vertex normals, tangents and bitangents of meshes as lines.
+/// Use with [debug_drawing::VertexNormalsDebug] on the mesh entities to inspect.
+#[derive(Default, Debug)]
+pub struct RenderVertexNormals {
+    target: Target,
+}
+
+impl RenderVertexNormals {
+    /// Set target to which the lines will be rendered.
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+}
+
+impl<B: Backend> RenderPlugin<B> for RenderVertexNormals {
+    fn on_build<'a, 'b>(
+        &mut self,
+        world: &mut World,
+        _builder: &mut DispatcherBuilder<'a, 'b>,
+    ) -> Result<(), Error> {
+        world.register::<VertexNormalsDebug>();
+        Ok(())
+    }
+
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        _factory: &mut Factory<B>,
+        _world: &World,
+    ) -> Result<(), Error> {
+        plan.extend_target(self.target, |ctx| {
+            ctx.add(
+                RenderOrder::BeforeTransparent,
+                DrawVertexNormalsDesc::new().builder(),
+            )?;
+            Ok(())
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,113 @@
+use super::define_camera_pass;
+use crate::{
+    bundle::{ImageOptions, RenderOrder, RenderPlan, RenderPlugin, Target, TargetImage},
+    pass::*,
+    viewport::{viewport_camera, CameraViewport},
+    Backend, Factory, Kind,
+};
+use amethyst_core::ecs::{DispatcherBuilder, Entity, World, WorldExt};
+use amethyst_error::{format_err, Error};
+use rendy::{
+    graph::render::RenderGroupDesc,
+    hal::command::{ClearColor, ClearValue},
+};
+
+/// A [RenderPlugin] rendering the camera with a [CameraViewport] for `target` into its
+/// rectangle of [`Target::Main`], e.g. for split-screen.
+///
+/// It defines `target` with the size of the viewport. Add the plugins drawing the view with
+/// the same target, e.g. `RenderPbr3D::default().with_target(target)`, and one
+/// `RenderViewport` per camera. Viewports are drawn into the main target before opaque
+/// objects, so plugins drawing to the whole window, like the UI, draw over them. Add it after
+/// the plugin defining the main target, e.g. `RenderToWindow`.
+///
+/// Make one of the cameras the [ActiveCamera](crate::ActiveCamera), as it could otherwise be
+/// any camera. Images drawn for the active camera in other targets, like shadow cascades and
+/// screen-space ambient occlusion, follow the active camera.
+#[derive(Debug)]
+pub struct RenderViewport {
+    target: Target,
+    clear: ClearColor,
+    camera: Option<(Entity, CameraViewport)>,
+}
+
+impl RenderViewport {
+    /// Create RenderViewport plugin rendering `target` from the camera targeting it.
+    pub fn new(target: Target) -> Self {
+        Self {
+            target,
+            clear: ClearColor::Sfloat([0.0, 0.0, 0.0, 1.0]),
+            camera: None,
+        }
+    }
+
+    /// Clear the viewport with specified color every frame.
+    pub fn with_clear(mut self, clear: impl Into<ClearColor>) -> Self {
+        self.clear = clear.into();
+        self
+    }
+}
+
+impl<B: Backend> RenderPlugin<B> for RenderViewport {
+    fn on_build<'a, 'b>(
+        &mut self,
+        world: &mut World,
+        _builder: &mut DispatcherBuilder<'a, 'b>,
+    ) -> Result<(), Error> {
+        world.register::<CameraViewport>();
+        Ok(())
+    }
+
+    fn should_rebuild(&mut self, world: &World) -> bool {
+        let camera = viewport_camera(world, self.target);
+        if camera != self.camera {
+            self.camera = camera;
+            return true;
+        }
+        false
+    }
+
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        factory: &mut Factory<B>,
+        _world: &World,
+    ) -> Result<(), Error> {
+        let (camera, viewport) = match self.camera {
+            Some(camera) => camera,
+            None => return Ok(()),
+        };
+        let main = plan.target_metadata(Target::Main, factory).ok_or_else(|| {
+            format_err!("Viewports require {:?} target to be defined.", Target::Main)
+        })?;
+        let rect = viewport.rect(main.width(), main.height());
+        if rect.w <= 0 || rect.h <= 0 {
+            return Ok(());
+        }
+
+        // Colors are kept in the format of the main target when post-processing is enabled.
+        define_camera_pass(
+            plan,
+            self.target,
+            camera,
+            ImageOptions {
+                kind: Kind::D2(rect.w as u32, rect.h as u32, 1, 1),
+                levels: 1,
+                format: crate::bundle::POST_PROCESS_FORMAT,
+                clear: Some(ClearValue::Color(self.clear)),
+            },
+        )?;
+
+        let target = self.target;
+        plan.extend_target(Target::Main, move |ctx| {
+            let group = DrawPostProcessDesc::passthrough()
+                .with_viewport(rect)
+                .with_depth_target()
+                .builder()
+                .with_image(ctx.get_image(TargetImage::Color(target, 0))?);
+            ctx.add(RenderOrder::BeforeOpaque, group)?;
+            Ok(())
+        });
+        Ok(())
+    }
+}
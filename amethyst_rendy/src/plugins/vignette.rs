@@ -0,0 +1,51 @@
+use crate::{
+    bundle::{PostProcessPlugin, PostProcessStage, RenderPlan},
+    pass::*,
+    vignette::VignetteParams,
+    Backend, Factory,
+};
+use amethyst_core::ecs::{DispatcherBuilder, World};
+use amethyst_error::Error;
+
+/// A [PostProcessPlugin] darkening or tinting the edges of the image.
+///
+/// Intensity, size and color are configured at runtime through the [VignetteParams] resource,
+/// e.g. to flash the screen edges as damage feedback.
+#[derive(Default, Debug)]
+pub struct RenderVignette {
+    params: VignetteParams,
+}
+
+impl RenderVignette {
+    /// Create vignette plugin with the given initial `VignetteParams`.
+    pub fn with_params(params: VignetteParams) -> Self {
+        Self { params }
+    }
+}
+
+impl<B: Backend> PostProcessPlugin<B> for RenderVignette {
+    fn on_build<'a, 'b>(
+        &mut self,
+        world: &mut World,
+        _builder: &mut DispatcherBuilder<'a, 'b>,
+    ) -> Result<(), Error> {
+        world.insert(self.params);
+        Ok(())
+    }
+
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        stage: PostProcessStage,
+        factory: &mut Factory<B>,
+        world: &World,
+    ) -> Result<(), Error> {
+        PostProcessPlugin::<B>::on_plan(
+            &mut DrawPostProcessDesc::vignette(),
+            plan,
+            stage,
+            factory,
+            world,
+        )
+    }
+}
@@ -0,0 +1,57 @@
+use crate::{
+    bundle::{PostProcessPlugin, PostProcessStage, RenderOrder, RenderPlan, Target, TargetImage},
+    pass::*,
+    render_texture::CameraTarget,
+    water::{Water, WaterReflectionSystem},
+    Backend, Factory,
+};
+use amethyst_core::ecs::{DispatcherBuilder, World, WorldExt};
+use amethyst_error::Error;
+use rendy::graph::render::RenderGroupDesc;
+
+/// A [PostProcessPlugin] drawing the surfaces of [Water] entities, refracting the image and
+/// depth of [Target::Main] behind them and reflecting the image of their reflection cameras.
+///
+/// The main target must have a depth output. A surface reflects the image of a camera with a
+/// [CameraTarget], rendered by [RenderToTexture](super::RenderToTexture) and the plugins drawing
+/// the reflected scene with its target, and placed every frame by the [WaterReflectionSystem]. Only
+/// opaque surfaces hide the water, so transparent meshes of the main target in front of it are
+/// refracted with the scene. Add it before [RenderBloom](super::RenderBloom) and
+/// [RenderToneMap](super::RenderToneMap), as it blends linear HDR colors.
+#[derive(Default, Debug)]
+pub struct RenderWater;
+
+impl<B: Backend> PostProcessPlugin<B> for RenderWater {
+    fn on_build<'a, 'b>(
+        &mut self,
+        world: &mut World,
+        builder: &mut DispatcherBuilder<'a, 'b>,
+    ) -> Result<(), Error> {
+        world.register::<Water>();
+        world.register::<CameraTarget>();
+        builder.add(WaterReflectionSystem::new(), "water_reflection_system", &[]);
+        Ok(())
+    }
+
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        stage: PostProcessStage,
+        _factory: &mut Factory<B>,
+        _world: &World,
+    ) -> Result<(), Error> {
+        plan.extend_target(stage.target, move |ctx| {
+            let group = DrawPostProcessDesc::passthrough()
+                .builder()
+                .with_image(ctx.get_image(stage.input)?);
+            ctx.add(RenderOrder::BeforeTransparent, group)?;
+            let group = DrawWaterDesc::new()
+                .builder()
+                .with_image(ctx.get_image(stage.input)?)
+                .with_image(ctx.get_image(TargetImage::Depth(Target::Main))?);
+            ctx.add(RenderOrder::Transparent, group)?;
+            Ok(())
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,256 @@
+use super::activate_camera;
+use crate::{
+    bundle::{ImageOptions, OutputColor, RenderPlan, RenderPlugin, Target, TargetPlanOutputs},
+    multi_window::{window_camera, CameraWindow},
+    Backend, Factory, Format, Kind,
+};
+use amethyst_config::{Config, ConfigError};
+use amethyst_core::{
+    ecs::{DispatcherBuilder, Entity, Read, ReadExpect, SystemData, World, WorldExt},
+    SystemBundle,
+};
+use amethyst_error::Error;
+use amethyst_window::{
+    DisplayConfig, PresentMode, ScreenDimensions, Window, WindowBundle, WindowId, Windows,
+};
+use rendy::hal::{
+    self,
+    command::{ClearColor, ClearDepthStencil, ClearValue},
+};
+use std::path::Path;
+
+/// A [RenderPlugin] for opening a window and displaying a render target to it.
+///
+/// When you provide [`DisplayConfig`], it opens a window for you using [`WindowBundle`].
+///
+/// Frames are presented with the [`PresentMode`] resource, initialized from the config or
+/// `with_present_mode`, which can be changed at runtime to turn vertical sync on or off.
+/// Other modes than `Fifo` render into an image copied to the window surface, and fall back
+/// to `Fifo` when the surface doesn't support them.
+#[derive(Default, Debug)]
+pub struct RenderToWindow {
+    target: Target,
+    config: Option<DisplayConfig>,
+    dimensions: Option<ScreenDimensions>,
+    dirty: bool,
+    clear: Option<ClearColor>,
+    present_mode: PresentMode,
+}
+
+impl RenderToWindow {
+    /// Create RenderToWindow plugin with [`WindowBundle`] using specified config path.
+    pub fn from_config_path(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        Ok(Self::from_config(DisplayConfig::load(path)?))
+    }
+
+    /// Create RenderToWindow plugin with [`WindowBundle`] using specified config.
+    pub fn from_config(display_config: DisplayConfig) -> Self {
+        Self {
+            present_mode: display_config.present_mode,
+            config: Some(display_config),
+            ..Default::default()
+        }
+    }
+
+    /// Select render target which will be presented to window.
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Clear window with specified color every frame.
+    pub fn with_clear(mut self, clear: impl Into<ClearColor>) -> Self {
+        self.clear = Some(clear.into());
+        self
+    }
+
+    /// Present frames with the given mode, overriding the one of the config.
+    pub fn with_present_mode(mut self, present_mode: PresentMode) -> Self {
+        self.present_mode = present_mode;
+        self
+    }
+}
+
+impl<B: Backend> RenderPlugin<B> for RenderToWindow {
+    fn on_build<'a, 'b>(
+        &mut self,
+        world: &mut World,
+        builder: &mut DispatcherBuilder<'a, 'b>,
+    ) -> Result<(), Error> {
+        if let Some(config) = self.config.take() {
+            WindowBundle::from_config(config).build(world, builder)?;
+        }
+        world.insert(self.present_mode);
+
+        Ok(())
+    }
+
+    #[allow(clippy::map_clone)]
+    fn should_rebuild(&mut self, world: &World) -> bool {
+        if let Some(present_mode) = world.try_fetch::<PresentMode>() {
+            if *present_mode != self.present_mode {
+                self.present_mode = *present_mode;
+                self.dirty = true;
+            }
+        }
+        let new_dimensions = world.try_fetch::<ScreenDimensions>();
+        if self.dimensions.as_ref() != new_dimensions.as_deref() {
+            self.dirty = true;
+            self.dimensions = new_dimensions.map(|d| (*d).clone());
+            return false;
+        }
+        self.dirty
+    }
+
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        factory: &mut Factory<B>,
+        world: &World,
+    ) -> Result<(), Error> {
+        self.dirty = false;
+
+        let window = <ReadExpect<'_, Window>>::fetch(world);
+        let surface = factory.create_surface(&window);
+        let format = factory.get_surface_format(&surface);
+        let dimensions = self.dimensions.as_ref().unwrap();
+        let window_kind = Kind::D2(dimensions.width() as u32, dimensions.height() as u32, 1, 1);
+
+        let depth_options = ImageOptions {
+            kind: window_kind,
+            levels: 1,
+            format: Format::D32Sfloat,
+            clear: Some(ClearValue::DepthStencil(ClearDepthStencil(0.0, 0))),
+        };
+
+        let clear = self.clear.map(ClearValue::Color);
+        let image = ImageOptions {
+            kind: window_kind,
+            levels: 1,
+            format,
+            clear,
+        };
+        let color = match self.present_mode {
+            PresentMode::Fifo => OutputColor::Surface(surface, clear),
+            PresentMode::Mailbox => {
+                OutputColor::Present(surface, hal::window::PresentMode::Mailbox, image)
+            }
+            PresentMode::Immediate => {
+                OutputColor::Present(surface, hal::window::PresentMode::Immediate, image)
+            }
+        };
+
+        plan.add_root(Target::Main);
+        plan.define_pass(
+            self.target,
+            crate::bundle::TargetPlanOutputs {
+                colors: vec![color],
+                depth: Some(depth_options),
+            },
+        )?;
+
+        Ok(())
+    }
+}
+
+/// A [RenderPlugin] rendering the camera with a [CameraWindow] for `target` into its window
+/// from the [Windows] resource, e.g. for tool windows of editors or a second screen.
+///
+/// It defines `target` with the size of the window. Add the plugins drawing the view
+/// with the same target, e.g. `RenderPbr3D::default().with_target(target)`. Nothing is
+/// rendered while there is no such camera or its window isn't open. The render graph is
+/// rebuilt when the window is opened, resized or closed.
+///
+/// Make the main camera the [ActiveCamera](crate::ActiveCamera), as it could otherwise be
+/// any camera. Images drawn for the active camera in other targets, like shadow cascades and
+/// screen-space ambient occlusion, follow the main camera.
+#[derive(Debug)]
+pub struct RenderToAdditionalWindow {
+    target: Target,
+    clear: Option<ClearColor>,
+    window: Option<(Entity, CameraWindow, WindowId, (u32, u32))>,
+}
+
+impl RenderToAdditionalWindow {
+    /// Create RenderToAdditionalWindow plugin rendering `target` from the camera
+    /// targeting it.
+    pub fn new(target: Target) -> Self {
+        Self {
+            target,
+            clear: None,
+            window: None,
+        }
+    }
+
+    /// Clear the window with specified color every frame.
+    pub fn with_clear(mut self, clear: impl Into<ClearColor>) -> Self {
+        self.clear = Some(clear.into());
+        self
+    }
+}
+
+impl<B: Backend> RenderPlugin<B> for RenderToAdditionalWindow {
+    fn on_build<'a, 'b>(
+        &mut self,
+        world: &mut World,
+        _builder: &mut DispatcherBuilder<'a, 'b>,
+    ) -> Result<(), Error> {
+        world.register::<CameraWindow>();
+        Ok(())
+    }
+
+    fn should_rebuild(&mut self, world: &World) -> bool {
+        let windows = <Option<Read<'_, Windows>>>::fetch(world);
+        let window = window_camera(world, self.target).and_then(|(entity, camera_window)| {
+            let windows = windows.as_ref()?;
+            let id = windows.get(&camera_window.window)?.id();
+            let dimensions = windows.dimensions(&camera_window.window)?;
+            let size = (dimensions.width() as u32, dimensions.height() as u32);
+            Some((entity, camera_window, id, size))
+        });
+        if window != self.window {
+            self.window = window;
+            return true;
+        }
+        false
+    }
+
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        factory: &mut Factory<B>,
+        world: &World,
+    ) -> Result<(), Error> {
+        let (camera, camera_window, (width, height)) = match &self.window {
+            Some((camera, camera_window, _, size)) => (*camera, camera_window, *size),
+            None => return Ok(()),
+        };
+        let windows = <Read<'_, Windows>>::fetch(world);
+        let surface = match windows.get(&camera_window.window) {
+            Some(window) => factory.create_surface(window),
+            None => return Ok(()),
+        };
+
+        let depth_options = ImageOptions {
+            kind: Kind::D2(width, height, 1, 1),
+            levels: 1,
+            format: Format::D32Sfloat,
+            clear: Some(ClearValue::DepthStencil(ClearDepthStencil(0.0, 0))),
+        };
+
+        plan.add_root(self.target);
+        plan.define_pass(
+            self.target,
+            TargetPlanOutputs {
+                colors: vec![OutputColor::Surface(
+                    surface,
+                    self.clear.map(ClearValue::Color),
+                )],
+                depth: Some(depth_options),
+            },
+        )?;
+        activate_camera(plan, self.target, camera);
+
+        Ok(())
+    }
+}
@@ -1,13 +1,19 @@
 //! GPU POD data types.
 use crate::{
+    billboard::{Billboard, BillboardMode},
+    decal::Decal,
+    morph::MorphWeights,
     mtl,
+    outline::{Outlined, MAX_OUTLINE_WIDTH},
+    particle::Particle,
     resources::Tint as TintComponent,
     sprite::{SpriteRender, SpriteSheet},
     types::Texture,
+    water::Water,
 };
 use amethyst_assets::{AssetStorage, Handle};
 use amethyst_core::{
-    math::{convert, Matrix4, Vector4},
+    math::{convert, Matrix4, Vector2, Vector3, Vector4},
     Transform,
 };
 use glsl_layout::*;
@@ -42,12 +48,40 @@ impl TextureOffset {
     }
 }
 
+/// Texture coordinate transform
+/// ```glsl,ignore
+/// struct UvTransform {
+///    vec4 u;
+///    vec4 v;
+/// };
+/// ```
+#[derive(Clone, Copy, Debug, AsStd140)]
+#[repr(C, align(16))]
+pub struct UvTransform {
+    /// Row of the transformed U coordinate, with the texture coordinate set in `w`
+    pub u: vec4,
+    /// Row of the transformed V coordinate
+    pub v: vec4,
+}
+
+impl UvTransform {
+    /// Helper function from proper type to Pod type.
+    pub fn from_transform(transform: &crate::mtl::TextureTransform) -> Self {
+        let [u, v] = transform.rows();
+        UvTransform {
+            u: [u[0], u[1], u[2], transform.tex_coord as f32].into(),
+            v: [v[0], v[1], v[2], 0.0].into(),
+        }
+    }
+}
+
 /// ViewArgs
 /// ```glsl,ignore
 /// uniform ViewArgs {
 ///    uniform mat4 proj;
 ///    uniform mat4 view;
 ///    uniform mat4 proj_view;
+///    uniform vec4 clip_plane;
 /// };
 /// ```
 #[derive(Clone, Copy, Debug, AsStd140)]
@@ -59,6 +93,8 @@ pub struct ViewArgs {
     pub view: mat4,
     /// Premultiplied Proj-View matrix
     pub proj_view: mat4,
+    /// World space `ClipPlane` of the camera, zero without one
+    pub clip_plane: vec4,
 }
 
 /// Tint
@@ -174,22 +210,392 @@ impl SkinnedVertexArgs {
     }
 }
 
+/// Morphed Instance-rate vertex arguments.
+/// ```glsl,ignore
+///  mat4 model;
+///  vec4 tint;
+///  vec4 morph_weights;
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+#[repr(C, packed)]
+pub struct MorphVertexArgs {
+    /// Instance-rate model matrix
+    pub model: mat4,
+    /// Instance-rate `Tint`
+    pub tint: vec4,
+    /// Instance-rate `MorphWeights`
+    pub morph_weights: vec4,
+}
+
+impl AsVertex for MorphVertexArgs {
+    fn vertex() -> VertexFormat {
+        VertexFormat::new((
+            Model::vertex(),
+            Tint::vertex(),
+            (Format::Rgba32Sfloat, "morph_weights"),
+        ))
+    }
+}
+
+impl MorphVertexArgs {
+    /// Populate `MorphVertexArgs` from the supplied `Transform`, `TintComponent` and `MorphWeights`
+    #[inline]
+    pub fn from_object_data(
+        transform: &Transform,
+        tint: Option<&TintComponent>,
+        weights: &MorphWeights,
+    ) -> Self {
+        let model: [[f32; 4]; 4] = convert::<_, Matrix4<f32>>(*transform.global_matrix()).into();
+        MorphVertexArgs {
+            model: model.into(),
+            tint: tint.map_or([1.0; 4].into(), |t| {
+                // Shaders expect linear RGBA; convert sRGBA to linear RGBA
+                let (r, g, b, a) = t.0.into_linear().into_components();
+                [r, g, b, a].into()
+            }),
+            morph_weights: weights.0.into(),
+        }
+    }
+}
+
+/// Particle Instance-rate vertex arguments.
+/// ```glsl,ignore
+///  vec3 position;
+///  float size;
+///  vec4 color;
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+#[repr(C, packed)]
+pub struct ParticleArgs {
+    /// World position of the particle
+    pub position: vec3,
+    /// Width and height of the particle quad
+    pub size: float,
+    /// Linear RGBA color of the particle
+    pub color: vec4,
+}
+
+impl AsVertex for ParticleArgs {
+    fn vertex() -> VertexFormat {
+        VertexFormat::new((
+            (Format::Rgb32Sfloat, "position"),
+            (Format::R32Sfloat, "size"),
+            (Format::Rgba32Sfloat, "color"),
+        ))
+    }
+}
+
+impl ParticleArgs {
+    /// Populate `ParticleArgs` from a simulated `Particle`
+    #[inline]
+    pub fn from_particle(particle: &Particle) -> Self {
+        ParticleArgs {
+            position: particle.position.into_pod(),
+            size: particle.size,
+            color: particle.color.into(),
+        }
+    }
+}
+
+/// Decal Instance-rate vertex arguments.
+/// ```glsl,ignore
+///  mat4 model;
+///  vec4 color;
+///  float normal_strength;
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+#[repr(C, packed)]
+pub struct DecalArgs {
+    /// Transform of the unit box of the decal to world space
+    pub model: mat4,
+    /// Linear RGBA color multiplying the albedo texture
+    pub color: vec4,
+    /// Weight of the normal map, zero without one
+    pub normal_strength: float,
+}
+
+impl AsVertex for DecalArgs {
+    fn vertex() -> VertexFormat {
+        VertexFormat::new((
+            Model::vertex(),
+            (Format::Rgba32Sfloat, "color"),
+            (Format::R32Sfloat, "normal_strength"),
+        ))
+    }
+}
+
+impl DecalArgs {
+    /// Populate `DecalArgs` from a `Decal` and its `Transform`
+    #[inline]
+    pub fn from_decal(decal: &Decal, transform: &Transform) -> Self {
+        let model = convert::<_, Matrix4<f32>>(*transform.global_matrix())
+            * Matrix4::new_nonuniform_scaling(&decal.size);
+        let model: [[f32; 4]; 4] = model.into();
+        DecalArgs {
+            model: model.into(),
+            color: decal.color.into(),
+            normal_strength: if decal.normal.is_some() { 1.0 } else { 0.0 },
+        }
+    }
+}
+
+/// Outline mask Instance-rate vertex arguments.
+/// ```glsl,ignore
+///  mat4 model;
+///  vec4 color;
+///  float width;
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+#[repr(C, packed)]
+pub struct OutlineArgs {
+    /// Instance-rate model matrix
+    pub model: mat4,
+    /// Linear RGBA color of the outline
+    pub color: vec4,
+    /// Width of the outline in pixels
+    pub width: float,
+}
+
+impl AsVertex for OutlineArgs {
+    fn vertex() -> VertexFormat {
+        VertexFormat::new((
+            Model::vertex(),
+            (Format::Rgba32Sfloat, "color"),
+            (Format::R32Sfloat, "width"),
+        ))
+    }
+}
+
+impl OutlineArgs {
+    /// Populate `OutlineArgs` from an `Outlined` and its `Transform`
+    #[inline]
+    pub fn from_outlined(outlined: &Outlined, transform: &Transform) -> Self {
+        let model: [[f32; 4]; 4] = convert::<_, Matrix4<f32>>(*transform.global_matrix()).into();
+        OutlineArgs {
+            model: model.into(),
+            color: outlined.color.into(),
+            width: outlined.width.max(0.0).min(MAX_OUTLINE_WIDTH),
+        }
+    }
+}
+
+/// Water surface Instance-rate vertex arguments.
+/// ```glsl,ignore
+///  mat4 model;
+///  mat4 reflection;
+///  vec4 waves;
+///  vec4 surface;
+///  vec4 deep_color;
+///  vec4 sky_color;
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+#[repr(C, packed)]
+pub struct WaterArgs {
+    /// Transform of the unit square of the surface, on the xz plane, to world space
+    pub model: mat4,
+    /// Projection and view matrix of the reflection camera
+    pub reflection: mat4,
+    /// Offsets of the two layers of the normal map, in texture coordinates
+    pub waves: vec4,
+    /// Normal map repetitions per world unit, wave strength, refraction and reflection
+    /// distortion
+    pub surface: vec4,
+    /// Linear deep color in `rgb`, visibility distance in `a`
+    pub deep_color: vec4,
+    /// Linear sky color in `rgb`, reflectance in `a`
+    pub sky_color: vec4,
+    /// Whether the reflection camera texture is bound, one or zero
+    pub has_reflection: float,
+}
+
+impl AsVertex for WaterArgs {
+    fn vertex() -> VertexFormat {
+        VertexFormat::new((
+            Model::vertex(),
+            (Format::Rgba32Sfloat, "reflection"),
+            (Format::Rgba32Sfloat, "reflection"),
+            (Format::Rgba32Sfloat, "reflection"),
+            (Format::Rgba32Sfloat, "reflection"),
+            (Format::Rgba32Sfloat, "waves"),
+            (Format::Rgba32Sfloat, "surface"),
+            (Format::Rgba32Sfloat, "deep_color"),
+            (Format::Rgba32Sfloat, "sky_color"),
+            (Format::R32Sfloat, "has_reflection"),
+        ))
+    }
+}
+
+impl WaterArgs {
+    /// Populate `WaterArgs` from a `Water` and its `Transform`, with the projection and view
+    /// matrix of its reflection camera if it has one, at `time` seconds.
+    #[inline]
+    pub fn from_water(
+        water: &Water,
+        transform: &Transform,
+        reflection: Option<Matrix4<f32>>,
+        time: f64,
+    ) -> Self {
+        let model = convert::<_, Matrix4<f32>>(*transform.global_matrix())
+            * Matrix4::new_nonuniform_scaling(&Vector3::new(water.size.x, 1.0, water.size.y));
+        let model: [[f32; 4]; 4] = model.into();
+        let has_reflection = if reflection.is_some() { 1.0 } else { 0.0 };
+        let reflection: [[f32; 4]; 4] = reflection.unwrap_or_else(Matrix4::identity).into();
+        let repeat = 1.0 / water.normal_tiling.max(std::f32::EPSILON);
+        // Offsets wrap around in double precision, so waves don't stutter after hours.
+        let offset = |velocity: f32| (f64::from(velocity * repeat) * time).rem_euclid(1.0) as f32;
+        let [first, second] = water.wave_velocities;
+        let [r, g, b] = water.deep_color;
+        let [sky_r, sky_g, sky_b] = water.sky_color;
+        WaterArgs {
+            model: model.into(),
+            reflection: reflection.into(),
+            waves: [
+                offset(first.x),
+                offset(first.y),
+                offset(second.x),
+                offset(second.y),
+            ]
+            .into(),
+            surface: [
+                repeat,
+                water.wave_strength,
+                water.refraction_distortion,
+                water.reflection_distortion,
+            ]
+            .into(),
+            deep_color: [r, g, b, water.visibility].into(),
+            sky_color: [sky_r, sky_g, sky_b, water.reflectance].into(),
+            has_reflection,
+        }
+    }
+}
+
+/// GPU simulated particle, stored in the particle and spawn buffers
+/// ```glsl,ignore
+/// struct Particle {
+///    vec4 position;
+///    vec4 velocity;
+///    vec4 acceleration;
+///    vec4 size;
+///    vec4 color_start;
+///    vec4 color_end;
+/// };
+/// ```
+#[derive(Clone, Copy, Debug, AsStd140)]
+pub struct GpuParticleArgs {
+    /// World position in `xyz`, seconds since the particle was emitted in `w`
+    pub position: vec4,
+    /// Velocity in `xyz`, seconds the particle lives in `w`
+    pub velocity: vec4,
+    /// Acceleration in world space in `xyz`, unused `w`
+    pub acceleration: vec4,
+    /// Size when emitted in `x` and when dying in `y`, unused `zw`
+    pub size: vec4,
+    /// Linear RGBA color when emitted
+    pub color_start: vec4,
+    /// Linear RGBA color when dying
+    pub color_end: vec4,
+}
+
+/// GPU particle simulation Uniform
+/// ```glsl,ignore
+/// uniform SimulationArgs {
+///    float delta_time;
+///    uint spawn_count;
+///    uint parity;
+///    uint capacity;
+/// };
+/// ```
+#[derive(Clone, Copy, Debug, AsStd140)]
+pub struct GpuParticleSimulationArgs {
+    /// Time elapsed since the previous frame in seconds
+    pub delta_time: float,
+    /// Number of particles in the spawn buffer
+    pub spawn_count: uint,
+    /// Half of the particle buffer read this frame, the other half is written
+    pub parity: uint,
+    /// Number of particles in each half of the particle buffer
+    pub capacity: uint,
+}
+
 /// point light struct
 /// ```glsl,ignore
 /// struct PointLight {
 ///    vec3 position;
+///    float radius;
 ///    vec3 color;
 ///    float intensity;
+///    float smoothness;
 /// };
 /// ```
 #[derive(Clone, Copy, Debug, AsStd140)]
 pub struct PointLight {
     /// Light world position
     pub position: vec3,
+    /// Radius beyond which the light has no effect
+    pub radius: float,
     /// Light color
     pub color: vec3,
     /// Light intensity (0 - infinity)
     pub intensity: float,
+    /// Exponent of the falloff window towards the radius
+    pub smoothness: float,
+}
+
+/// Reflection probe struct
+/// ```glsl,ignore
+/// struct ReflectionProbe {
+///    vec3 position;
+///    float intensity;
+///    vec3 box_min;
+///    vec3 box_max;
+/// };
+/// ```
+#[derive(Clone, Copy, Debug, AsStd140)]
+pub struct ReflectionProbeArgs {
+    /// World position the surroundings were captured from
+    pub position: vec3,
+    /// Scale applied to the lighting from the probe
+    pub intensity: float,
+    /// Minimum corner of the world space box affected by the probe
+    pub box_min: vec3,
+    /// Maximum corner of the world space box affected by the probe
+    pub box_max: vec3,
+}
+
+/// Instance-rate point light of the deferred shading path
+/// ```glsl,ignore
+///  vec3 position;
+///  float radius;
+///  vec3 color;
+///  float intensity;
+///  float smoothness;
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+#[repr(C, align(4))]
+pub struct DeferredPointLight {
+    /// Light world position
+    pub position: vec3,
+    /// Radius beyond which the light has no effect
+    pub radius: float,
+    /// Light color
+    pub color: vec3,
+    /// Light intensity (0 - infinity)
+    pub intensity: float,
+    /// Exponent of the falloff window towards the radius
+    pub smoothness: float,
+}
+
+impl AsVertex for DeferredPointLight {
+    fn vertex() -> VertexFormat {
+        VertexFormat::new((
+            (Format::Rgb32Sfloat, "position"),
+            (Format::R32Sfloat, "radius"),
+            (Format::Rgb32Sfloat, "color"),
+            (Format::R32Sfloat, "intensity"),
+            (Format::R32Sfloat, "smoothness"),
+        ))
+    }
 }
 
 /// directional light struct
@@ -220,6 +626,9 @@ pub struct DirectionalLight {
 ///    float intensity;
 ///    float range;
 ///    float smoothness;
+///    int shadow_index;
+///    float shadow_bias;
+///    float shadow_softness;
 /// };
 /// ```
 #[derive(Clone, Copy, Debug, AsStd140)]
@@ -238,6 +647,30 @@ pub struct SpotLight {
     pub range: float,
     /// Spotlight smoothness
     pub smoothness: float,
+    /// Index of the light's tile in the spot light shadow atlas, -1 if it casts no shadows
+    pub shadow_index: int,
+    /// Shadow depth comparison bias, relative to depth
+    pub shadow_bias: float,
+    /// Radius of shadow filtering in texels
+    pub shadow_softness: float,
+}
+
+/// Spot light shadows Uniform
+/// ```glsl,ignore
+/// uniform SpotShadows {
+///    mat4 spot_shadow_proj_view[4];
+///    vec2 spot_shadow_texel_size;
+///    int spot_shadow_columns;
+/// };
+/// ```
+#[derive(Clone, Copy, Debug, AsStd140)]
+pub struct SpotShadows {
+    /// Projection-view matrix of each shadow casting spot light
+    pub proj_view: [mat4; 4],
+    /// Size of a single texel of the spot light shadow atlas in texture coordinates
+    pub texel_size: vec2,
+    /// Number of light tiles in the spot light shadow atlas
+    pub columns: int,
 }
 
 /// Environment Uniform
@@ -248,6 +681,7 @@ pub struct SpotLight {
 ///    int point_light_count;
 ///    int directional_light_count;
 ///    int spot_light_count;
+///    float environment_map_intensity;
 /// };
 /// ```
 #[derive(Clone, Copy, Debug, AsStd140)]
@@ -262,6 +696,205 @@ pub struct Environment {
     pub directional_light_count: int,
     /// Number of spot lights
     pub spot_light_count: int,
+    /// Intensity of image-based lighting, zero without an `EnvironmentMap`
+    pub environment_map_intensity: float,
+}
+
+/// Auto exposure Uniform
+/// ```glsl,ignore
+/// uniform ExposureArgs {
+///    float min_log_luminance;
+///    float log_luminance_range;
+///    float delta_time;
+///    float adaptation_speed;
+/// };
+/// ```
+#[derive(Clone, Copy, Debug, AsStd140)]
+pub struct ExposureArgs {
+    /// Base 2 logarithm of the lowest luminance in the histogram
+    pub min_log_luminance: float,
+    /// Range of base 2 logarithms of luminance covered by the histogram
+    pub log_luminance_range: float,
+    /// Time elapsed since the previous frame in seconds
+    pub delta_time: float,
+    /// Rate of exposure adaptation per second
+    pub adaptation_speed: float,
+}
+
+/// Occlusion test Uniform
+/// ```glsl,ignore
+/// uniform OcclusionArgs {
+///    mat4 proj_view;
+///    vec2 depth_size;
+///    uint count;
+///    uint levels;
+/// };
+/// ```
+#[derive(Clone, Copy, Debug, AsStd140)]
+pub struct OcclusionArgs {
+    /// Projection and view matrix of the camera the depth was drawn from
+    pub proj_view: mat4,
+    /// Size of the tested depth image in pixels
+    pub depth_size: vec2,
+    /// Number of bounding boxes to test
+    pub count: uint,
+    /// Number of levels of the hierarchical depth image
+    pub levels: uint,
+}
+
+/// Velocity pass Uniform
+/// ```glsl,ignore
+/// uniform VelocityArgs {
+///    mat4 proj_view;
+///    mat4 prev_proj_view;
+///    vec2 jitter;
+///    vec2 prev_jitter;
+/// };
+/// ```
+#[derive(Clone, Copy, Debug, AsStd140)]
+#[repr(C, align(16))]
+pub struct VelocityArgs {
+    /// Projection-view matrix of this frame
+    pub proj_view: mat4,
+    /// Projection-view matrix of the previous frame
+    pub prev_proj_view: mat4,
+    /// Jitter offset included in `proj_view`, in normalized device coordinates
+    pub jitter: vec2,
+    /// Jitter offset included in `prev_proj_view`, in normalized device coordinates
+    pub prev_jitter: vec2,
+}
+
+/// Instance-rate vertex arguments of the velocity pass
+/// ```glsl,ignore
+///  mat4 model;
+///  mat4 prev_model;
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+#[repr(C, align(16))]
+pub struct VelocityVertexArgs {
+    /// Instance-rate model matrix
+    pub model: mat4,
+    /// Instance-rate model matrix of the previous frame
+    pub prev_model: mat4,
+}
+
+impl AsVertex for VelocityVertexArgs {
+    fn vertex() -> VertexFormat {
+        VertexFormat::new((
+            Model::vertex(),
+            (
+                (Format::Rgba32Sfloat, "prev_model"),
+                (Format::Rgba32Sfloat, "prev_model"),
+                (Format::Rgba32Sfloat, "prev_model"),
+                (Format::Rgba32Sfloat, "prev_model"),
+            ),
+        ))
+    }
+}
+
+/// Picking pass Uniform
+/// ```glsl,ignore
+/// uniform PickingArgs {
+///    mat4 proj_view;
+/// };
+/// ```
+#[derive(Clone, Copy, Debug, AsStd140)]
+#[repr(C, align(16))]
+pub struct PickingArgs {
+    /// Projection-view matrix of the camera, narrowed to the picked pixel
+    pub proj_view: mat4,
+}
+
+/// Picking instance-rate vertex arguments of meshes
+/// ```glsl,ignore
+///  mat4 model;
+///  uint entity;
+///  uint joints_offset;
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+#[repr(C, packed)]
+pub struct PickingVertexArgs {
+    /// Instance-rate model matrix
+    pub model: mat4,
+    /// Instance-rate entity id, plus one so zero is no entity
+    pub entity: u32,
+    /// Instance-rate joint offset as `u32`, unused by static meshes
+    pub joints_offset: u32,
+}
+
+impl AsVertex for PickingVertexArgs {
+    fn vertex() -> VertexFormat {
+        VertexFormat::new((
+            Model::vertex(),
+            (Format::R32Uint, "entity"),
+            JointsOffset::vertex(),
+        ))
+    }
+}
+
+/// Picking instance-rate vertex arguments of sprites
+/// ```glsl,ignore
+/// vec2 dir_x;
+/// vec2 dir_y;
+/// vec2 pos;
+/// vec2 u_offset;
+/// vec2 v_offset;
+/// float depth;
+/// vec4 tint;
+/// uint entity;
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+#[repr(C, align(4))]
+pub struct SpritePickingArgs {
+    /// Sprite arguments, as drawn by sprite passes
+    pub sprite: SpriteArgs,
+    /// Entity id, plus one so zero is no entity
+    pub entity: u32,
+}
+
+impl AsVertex for SpritePickingArgs {
+    fn vertex() -> VertexFormat {
+        VertexFormat::new((SpriteArgs::vertex(), (Format::R32Uint, "entity")))
+    }
+}
+
+/// Shadow map Uniform
+/// ```glsl,ignore
+/// uniform ShadowArgs {
+///    mat4 cascade_proj_view[4];
+///    mat4 point_proj_view[24];
+///    int cascade_count;
+///    int pcf_radius;
+///    float depth_bias;
+///    vec2 texel_size;
+///    int point_shadow_count;
+///    int point_atlas_rows;
+///    float point_depth_bias;
+///    vec2 point_texel_size;
+/// };
+/// ```
+#[derive(Clone, Copy, Debug, AsStd140)]
+pub struct ShadowArgs {
+    /// Light-space projection-view matrix of each cascade
+    pub cascade_proj_view: [mat4; 4],
+    /// Projection-view matrices of point light cube map faces, six per light
+    pub point_proj_view: [mat4; 24],
+    /// Number of valid cascades, zero when there are no shadows
+    pub cascade_count: int,
+    /// Radius of percentage-closer filtering in texels
+    pub pcf_radius: int,
+    /// Depth comparison bias
+    pub depth_bias: float,
+    /// Size of a single texel of the shadow atlas in texture coordinates
+    pub texel_size: vec2,
+    /// Number of point lights casting shadows, which are the first point lights of the environment
+    pub point_shadow_count: int,
+    /// Number of light rows in the point light shadow atlas
+    pub point_atlas_rows: int,
+    /// Depth comparison bias of point lights, relative to depth
+    pub point_depth_bias: float,
+    /// Size of a single texel of the point light shadow atlas in texture coordinates
+    pub point_texel_size: vec2,
 }
 
 /// Material Uniform
@@ -269,6 +902,14 @@ pub struct Environment {
 /// uniform Material {
 ///    UvOffset uv_offset;
 ///    float alpha_cutoff;
+///    float emission_strength;
+///    uint unlit;
+///    float lightmap_intensity;
+///    UvTransform albedo_transform;
+///    UvTransform emission_transform;
+///    UvTransform normal_transform;
+///    UvTransform metallic_roughness_transform;
+///    UvTransform ambient_occlusion_transform;
 /// };
 /// ```
 #[derive(Clone, Copy, Debug, AsStd140)]
@@ -278,6 +919,22 @@ pub struct Material {
     pub uv_offset: TextureOffset,
     /// Material alpha cutoff
     pub alpha_cutoff: float,
+    /// Multiplier of the emission map
+    pub emission_strength: float,
+    /// Non-zero when the material ignores lights
+    pub unlit: uint,
+    /// Scale of the lightmap, zero without one
+    pub lightmap_intensity: float,
+    /// Texture coordinate transform of the albedo and cavity maps
+    pub albedo_transform: UvTransform,
+    /// Texture coordinate transform of the emission map
+    pub emission_transform: UvTransform,
+    /// Texture coordinate transform of the normal map
+    pub normal_transform: UvTransform,
+    /// Texture coordinate transform of the metallic-roughness map
+    pub metallic_roughness_transform: UvTransform,
+    /// Texture coordinate transform of the ambient occlusion map
+    pub ambient_occlusion_transform: UvTransform,
 }
 
 impl Material {
@@ -286,6 +943,18 @@ impl Material {
         Material {
             uv_offset: TextureOffset::from_offset(&mat.uv_offset),
             alpha_cutoff: mat.alpha_cutoff,
+            emission_strength: mat.emission_strength,
+            unlit: mat.unlit.into(),
+            lightmap_intensity: mat.lightmap_intensity,
+            albedo_transform: UvTransform::from_transform(&mat.texture_transforms.albedo),
+            emission_transform: UvTransform::from_transform(&mat.texture_transforms.emission),
+            normal_transform: UvTransform::from_transform(&mat.texture_transforms.normal),
+            metallic_roughness_transform: UvTransform::from_transform(
+                &mat.texture_transforms.metallic_roughness,
+            ),
+            ambient_occlusion_transform: UvTransform::from_transform(
+                &mat.texture_transforms.ambient_occlusion,
+            ),
         }
     }
 }
@@ -379,6 +1048,87 @@ impl SpriteArgs {
     }
 }
 
+/// Billboard Instance-rate vertex arguments.
+/// ```glsl,ignore
+///  vec3 position;
+///  uint cylindrical;
+///  vec2 size;
+///  vec2 offset;
+///  vec2 u_offset;
+///  vec2 v_offset;
+///  vec4 tint;
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+#[repr(C, packed)]
+pub struct BillboardArgs {
+    /// World position of the billboard
+    pub position: vec3,
+    /// Whether the billboard only turns around the world y axis
+    pub cylindrical: uint,
+    /// Width and height of the quad
+    pub size: vec2,
+    /// Offset of the quad center from the position, along the quad axes
+    pub offset: vec2,
+    /// Left and right texture coordinate of the sprite
+    pub u_offset: vec2,
+    /// Top and bottom texture coordinate of the sprite
+    pub v_offset: vec2,
+    /// Linear RGBA tint of the billboard
+    pub tint: vec4,
+}
+
+impl AsVertex for BillboardArgs {
+    fn vertex() -> VertexFormat {
+        VertexFormat::new((
+            (Format::Rgb32Sfloat, "position"),
+            (Format::R32Uint, "cylindrical"),
+            (Format::Rg32Sfloat, "size"),
+            (Format::Rg32Sfloat, "offset"),
+            (Format::Rg32Sfloat, "u_offset"),
+            (Format::Rg32Sfloat, "v_offset"),
+            (Format::Rgba32Sfloat, "tint"),
+        ))
+    }
+}
+
+impl BillboardArgs {
+    /// Extracts POD vertex data for a billboard, returning `None` while its sprite sheet
+    /// or texture isn't loaded.
+    pub fn from_data<'a>(
+        tex_storage: &AssetStorage<Texture>,
+        sprite_storage: &'a AssetStorage<SpriteSheet>,
+        billboard: &Billboard,
+        transform: &Transform,
+        tint: Option<&TintComponent>,
+    ) -> Option<(Self, &'a Handle<Texture>)> {
+        let sprite_sheet = sprite_storage.get(&billboard.sprite.sprite_sheet)?;
+        if !tex_storage.contains(&sprite_sheet.texture) {
+            return None;
+        }
+
+        let sprite = &sprite_sheet.sprites[billboard.sprite.sprite_number];
+        let matrix = convert::<_, Matrix4<f32>>(*transform.global_matrix());
+        let scale = Vector2::new(matrix.column(0).xyz().norm(), matrix.column(1).xyz().norm());
+
+        Some((
+            BillboardArgs {
+                position: matrix.column(3).xyz().into_pod(),
+                cylindrical: (billboard.mode == BillboardMode::Cylindrical) as u32,
+                size: [sprite.width * scale.x, sprite.height * scale.y].into(),
+                offset: [sprite.offsets[0] * scale.x, sprite.offsets[1] * scale.y].into(),
+                u_offset: [sprite.tex_coords.left, sprite.tex_coords.right].into(),
+                v_offset: [sprite.tex_coords.top, sprite.tex_coords.bottom].into(),
+                tint: tint.map_or([1.0; 4].into(), |t| {
+                    // Shaders expect linear RGBA; convert sRGBA to linear RGBA
+                    let (r, g, b, a) = t.0.into_linear().into_components();
+                    [r, g, b, a].into()
+                }),
+            },
+            &sprite_sheet.texture,
+        ))
+    }
+}
+
 /// Trait for auto conversion into standard GLSL POD types.
 pub trait IntoPod<T> {
     /// Converts `Self` to the supplied `T` GLSL type.
@@ -399,6 +1149,13 @@ impl IntoPod<vec3> for amethyst_core::math::Vector3<f32> {
     }
 }
 
+impl IntoPod<vec4> for amethyst_core::math::Vector4<f32> {
+    fn into_pod(self) -> vec4 {
+        let arr: [f32; 4] = self.into();
+        arr.into()
+    }
+}
+
 impl IntoPod<vec3> for palette::Srgb {
     fn into_pod(self) -> vec3 {
         let (r, g, b) = self.into_components();
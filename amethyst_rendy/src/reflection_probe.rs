@@ -0,0 +1,562 @@
+//! Reflection probes capturing their surroundings for the image-based lighting of nearby meshes.
+
+use crate::{
+    camera::Camera,
+    environment_map::{convolve, cube_texture_builder, HdrCubemap},
+    pod::ReflectionProbeArgs,
+    types::Texture,
+};
+use amethyst_assets::{AssetStorage, Handle, Loader};
+use amethyst_core::{
+    ecs::prelude::{
+        Component, DenseVecStorage, Entities, Entity, Join, Read, ReadExpect, System, Write,
+        WriteStorage,
+    },
+    math::{Point3, UnitQuaternion, Vector3},
+    Transform,
+};
+use rendy::hal::image::ViewKind;
+use std::f32::consts::FRAC_PI_2;
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+/// Maximum number of captured reflection probes. Further probes are not captured.
+pub const MAX_REFLECTION_PROBES: usize = 16;
+
+/// Frames to wait for the rendered faces of a capture to be read back before rendering the
+/// missing ones again, e.g. when the render graph was rebuilt while they were in flight.
+const RESUBMIT_FRAMES: u32 = 8;
+
+/// Forward and up directions of the camera rendering each cube map face,
+/// in +X, -X, +Y, -Y, +Z, -Z order.
+const FACES: [([f32; 3], [f32; 3]); 6] = [
+    ([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+    ([-1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+    ([0.0, 1.0, 0.0], [0.0, 0.0, -1.0]),
+    ([0.0, -1.0, 0.0], [0.0, 0.0, 1.0]),
+    ([0.0, 0.0, 1.0], [0.0, 1.0, 0.0]),
+    ([0.0, 0.0, -1.0], [0.0, 1.0, 0.0]),
+];
+
+/// When a [ReflectionProbe] captures its surroundings.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProbeUpdate {
+    /// Captured once, as soon as the probe exists, e.g. when the level is loaded.
+    Once,
+    /// Captured whenever requested with [ReflectionProbe::request_capture].
+    OnDemand,
+}
+
+/// Component placing a reflection probe at the position of its entity.
+///
+/// The probe renders a cube map of its surroundings with
+/// [RenderReflectionProbes](crate::plugins::RenderReflectionProbes), which replaces the
+/// [EnvironmentMap](crate::environment_map::EnvironmentMap) in the image-based lighting of
+/// the PBR meshes whose origin is within the box of the probe. A mesh within the boxes of
+/// several probes uses the nearest one. Reflections are corrected for the parallax between
+/// the probe and the reflecting surface by projecting them onto the box, so the box should
+/// match the walls of a room the probe is placed in. Meshes drawn with deferred shading are
+/// only lit by the environment map.
+///
+/// The box is aligned with the world axes and centered on the position of the probe when it
+/// was captured. Moving the probe only takes effect at its next capture.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReflectionProbe {
+    /// Half of the size of the box affected by the probe, along each world axis.
+    pub half_extents: Vector3<f32>,
+    /// Scale applied to the lighting from the probe.
+    pub intensity: f32,
+    /// When the probe captures its surroundings.
+    pub update: ProbeUpdate,
+    requested: bool,
+}
+
+impl ReflectionProbe {
+    /// Create a probe affecting a box of `half_extents` around it, captured once.
+    pub fn new(half_extents: Vector3<f32>) -> Self {
+        Self {
+            half_extents,
+            intensity: 1.0,
+            update: ProbeUpdate::Once,
+            requested: false,
+        }
+    }
+
+    /// Returns this probe with the given intensity.
+    pub fn with_intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    /// Returns this probe captured when the given `update` happens.
+    pub fn with_update(mut self, update: ProbeUpdate) -> Self {
+        self.update = update;
+        self
+    }
+
+    /// Request capturing the surroundings of the probe again, e.g. after they changed.
+    ///
+    /// Probes are captured one after the other, each over several frames.
+    pub fn request_capture(&mut self) {
+        self.requested = true;
+    }
+
+    /// Whether a requested capture hasn't started yet.
+    pub fn is_capture_requested(&self) -> bool {
+        self.requested
+    }
+}
+
+impl Component for ReflectionProbe {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Capture of a probe in progress.
+#[derive(Debug)]
+struct Capture {
+    serial: u64,
+    entity: Entity,
+    position: Option<Point3<f32>>,
+    next_face: usize,
+    rendering: Option<usize>,
+    faces: Vec<Option<Vec<[f32; 3]>>>,
+    waited: u32,
+}
+
+/// Convolved cube maps of a captured probe.
+#[derive(Debug)]
+struct CapturedProbe {
+    entity: Entity,
+    position: Point3<f32>,
+    half_extents: Vector3<f32>,
+    intensity: f32,
+    irradiance: HdrCubemap,
+    specular: Vec<HdrCubemap>,
+}
+
+impl CapturedProbe {
+    fn args(&self) -> ReflectionProbeArgs {
+        let position: [f32; 3] = self.position.coords.into();
+        let box_min: [f32; 3] = (self.position - self.half_extents).coords.into();
+        let box_max: [f32; 3] = (self.position + self.half_extents).coords.into();
+        ReflectionProbeArgs {
+            position: position.into(),
+            intensity: self.intensity,
+            box_min: box_min.into(),
+            box_max: box_max.into(),
+        }
+    }
+}
+
+/// Resource holding the captured [ReflectionProbe]s, inserted by
+/// [RenderReflectionProbes](crate::plugins::RenderReflectionProbes).
+///
+/// Captured cube maps are convolved like those of an
+/// [EnvironmentMap](crate::environment_map::EnvironmentMap) and loaded into cube map array
+/// textures shared by all probes.
+#[derive(Debug)]
+pub struct ReflectionProbes {
+    resolution: u32,
+    near: f32,
+    camera: Option<Entity>,
+    serial: u64,
+    capture: Option<Capture>,
+    captured: Vec<CapturedProbe>,
+    textures: Option<(Handle<Texture>, Handle<Texture>)>,
+    version: u32,
+}
+
+impl ReflectionProbes {
+    /// Create a resource capturing probes into cube maps with faces of `resolution` by
+    /// `resolution` pixels, rendered with the `near` clipping plane.
+    pub fn new(resolution: u32, near: f32) -> Self {
+        Self {
+            resolution: resolution.max(1),
+            near,
+            camera: None,
+            serial: 0,
+            capture: None,
+            captured: Vec::new(),
+            textures: None,
+            version: 0,
+        }
+    }
+
+    /// Width and height of the captured cube map faces, in pixels.
+    pub fn resolution(&self) -> u32 {
+        self.resolution
+    }
+
+    /// Whether the probe of `entity` has been captured and lights meshes.
+    pub fn is_captured(&self, entity: Entity) -> bool {
+        self.captured.iter().any(|probe| probe.entity == entity)
+    }
+
+    /// Whether a probe is being captured.
+    pub fn is_capturing(&self) -> bool {
+        self.capture.is_some()
+    }
+
+    /// Find the captured probe lighting a mesh with its origin at `origin`, the nearest one
+    /// whose box contains it.
+    pub fn nearest(&self, origin: &Point3<f32>) -> Option<Entity> {
+        nearest_probe(
+            self.captured
+                .iter()
+                .map(|probe| (probe.position, probe.half_extents)),
+            origin,
+        )
+        .map(|index| self.captured[index].entity)
+    }
+
+    /// Camera entity rendering the faces of the probe being captured.
+    pub(crate) fn capture_camera(&self) -> Option<Entity> {
+        self.capture.as_ref().and(self.camera)
+    }
+
+    /// Serial of the capture in progress and the face of its probe rendered this frame.
+    pub(crate) fn rendering(&self) -> Option<(u64, usize)> {
+        let capture = self.capture.as_ref()?;
+        capture.rendering.map(|face| (capture.serial, face))
+    }
+
+    /// Receive the `Rgba16Sfloat` pixels of `face` rendered for capture `serial`.
+    pub(crate) fn receive(&mut self, serial: u64, face: usize, pixels: &[u16]) {
+        if let Some(capture) = self.capture.as_mut().filter(|c| c.serial == serial) {
+            capture.faces[face] = Some(face_texels(self.resolution, pixels));
+        }
+    }
+
+    /// Irradiance and specular cube map array textures of all captured probes.
+    pub(crate) fn textures(&self) -> Option<&(Handle<Texture>, Handle<Texture>)> {
+        self.textures.as_ref()
+    }
+
+    /// Changes whenever the textures or arguments of the captured probes change.
+    pub(crate) fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Arguments of the captured probes, in the order of their cube maps in the textures.
+    pub(crate) fn args(&self) -> Vec<ReflectionProbeArgs> {
+        self.captured.iter().map(CapturedProbe::args).collect()
+    }
+
+    fn load_textures(&mut self, loader: &Loader, storage: &AssetStorage<Texture>) {
+        self.version = self.version.wrapping_add(1);
+        if self.captured.is_empty() {
+            self.textures = None;
+            return;
+        }
+
+        let irradiance = self
+            .captured
+            .iter()
+            .map(|probe| probe.irradiance.clone())
+            .collect::<Vec<_>>();
+        let specular = self
+            .captured
+            .iter()
+            .flat_map(|probe| probe.specular.iter().cloned())
+            .collect::<Vec<_>>();
+        // A single probe still needs array views, which are indexed by probe.
+        let load = |cubemaps: &[HdrCubemap]| {
+            let data = cube_texture_builder(cubemaps)
+                .with_view_kind(ViewKind::CubeArray)
+                .into();
+            loader.load_from_data(data, (), storage)
+        };
+        self.textures = Some((load(&irradiance), load(&specular)));
+    }
+}
+
+impl Default for ReflectionProbes {
+    fn default() -> Self {
+        Self::new(64, 0.1)
+    }
+}
+
+/// Index of the nearest of the `probes` positions and half extents whose box contains
+/// `origin`.
+fn nearest_probe(
+    probes: impl Iterator<Item = (Point3<f32>, Vector3<f32>)>,
+    origin: &Point3<f32>,
+) -> Option<usize> {
+    probes
+        .enumerate()
+        .filter(|(_, (position, half_extents))| {
+            let offset = origin - position;
+            (0..3).all(|axis| offset[axis].abs() <= half_extents[axis])
+        })
+        .map(|(index, (position, _))| (index, (origin - position).norm()))
+        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+        .map(|(index, _)| index)
+}
+
+/// Rotation of the camera rendering cube map `face`, looking down its negative Z axis.
+fn face_rotation(face: usize) -> UnitQuaternion<f32> {
+    let (forward, up) = FACES[face];
+    UnitQuaternion::face_towards(&-Vector3::from(forward), &Vector3::from(up))
+}
+
+/// Texels of a cube map face of `size` by `size` from `Rgba16Sfloat` `pixels`.
+///
+/// Cameras render the faces as seen from inside the cube, mirrored from the orientation of
+/// cube map faces seen from outside, so rows are reversed.
+fn face_texels(size: u32, pixels: &[u16]) -> Vec<[f32; 3]> {
+    let size = size as usize;
+    pixels
+        .chunks_exact(size * 4)
+        .take(size)
+        .flat_map(|row| {
+            row.chunks_exact(4)
+                .rev()
+                .map(|pixel| [half(pixel[0]), half(pixel[1]), half(pixel[2])])
+        })
+        .collect()
+}
+
+/// Convert a half precision float to a finite, non-negative `f32`.
+fn half(bits: u16) -> f32 {
+    let exponent = i32::from((bits >> 10) & 0x1f);
+    let mantissa = f32::from(bits & 0x3ff);
+    let value = match exponent {
+        0 => mantissa * 2f32.powi(-24),
+        0x1f if mantissa == 0.0 => std::f32::INFINITY,
+        0x1f => 0.0,
+        _ => (1.0 + mantissa / 1024.0) * 2f32.powi(exponent - 15),
+    };
+    if bits & 0x8000 != 0 {
+        0.0
+    } else {
+        value.min(65504.0)
+    }
+}
+
+/// Capture the surroundings of every [ReflectionProbe] due for it, one probe at a time.
+///
+/// It moves a camera through the faces of the cube map of the probe being captured, rendered
+/// by [RenderReflectionProbes](crate::plugins::RenderReflectionProbes), then convolves the
+/// cube map once all faces have been read back. Convolution happens on the thread of this
+/// system, taking longer with higher resolutions.
+#[derive(Default, Debug)]
+pub struct ReflectionProbeSystem;
+
+impl ReflectionProbeSystem {
+    /// Create new reflection probe system
+    pub fn new() -> Self {
+        ReflectionProbeSystem
+    }
+}
+
+impl<'a> System<'a> for ReflectionProbeSystem {
+    type SystemData = (
+        Entities<'a>,
+        Write<'a, ReflectionProbes>,
+        WriteStorage<'a, ReflectionProbe>,
+        WriteStorage<'a, Transform>,
+        WriteStorage<'a, Camera>,
+        ReadExpect<'a, Loader>,
+        Read<'a, AssetStorage<Texture>>,
+    );
+
+    fn run(
+        &mut self,
+        (entities, mut probes, mut reflection_probes, mut transforms, mut cameras, loader, storage): Self::SystemData,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("reflection_probe_system");
+
+        let probes = &mut *probes;
+
+        // Removed probes no longer light meshes, changed ones light them differently.
+        let mut changed = false;
+        let captured = probes.captured.len();
+        probes
+            .captured
+            .retain(|probe| reflection_probes.get(probe.entity).is_some());
+        let mut reload = probes.captured.len() != captured;
+        for probe in &mut probes.captured {
+            let component = reflection_probes.get(probe.entity).unwrap();
+            if probe.half_extents != component.half_extents
+                || probe.intensity != component.intensity
+            {
+                probe.half_extents = component.half_extents;
+                probe.intensity = component.intensity;
+                changed = true;
+            }
+        }
+
+        if let Some(mut capture) = probes.capture.take() {
+            let camera = probes.camera.unwrap();
+            let position = transforms
+                .get(capture.entity)
+                .filter(|_| reflection_probes.contains(capture.entity))
+                .map(|transform| {
+                    capture.position.unwrap_or_else(|| {
+                        transform.global_matrix().transform_point(&Point3::origin())
+                    })
+                });
+            match position {
+                None => {
+                    // The probe was removed while being captured.
+                    cameras.remove(camera);
+                }
+                Some(position) if capture.next_face < FACES.len() => {
+                    // The camera has no parent, so its global matrix is updated right away.
+                    let face = capture.next_face;
+                    let mut transform = Transform::default();
+                    transform.set_translation(position.coords);
+                    transform.set_rotation(face_rotation(face));
+                    transform.copy_local_to_global();
+                    transforms
+                        .insert(camera, transform)
+                        .expect("Capture camera is alive");
+
+                    capture.position = Some(position);
+                    capture.rendering = Some(face);
+                    capture.next_face += 1;
+                    capture.waited = 0;
+                    probes.capture = Some(capture);
+                }
+                Some(position) if capture.faces.iter().all(Option::is_some) => {
+                    cameras.remove(camera);
+                    let texels = capture.faces.into_iter().flatten().flatten().collect();
+                    match HdrCubemap::new(probes.resolution, texels) {
+                        Ok(cubemap) => {
+                            let (irradiance, specular) = convolve(&cubemap);
+                            let component = reflection_probes.get(capture.entity).unwrap();
+                            let probe = CapturedProbe {
+                                entity: capture.entity,
+                                position,
+                                half_extents: component.half_extents,
+                                intensity: component.intensity,
+                                irradiance,
+                                specular,
+                            };
+                            let captured = &mut probes.captured;
+                            match captured.iter_mut().find(|p| p.entity == probe.entity) {
+                                Some(previous) => *previous = probe,
+                                None => captured.push(probe),
+                            }
+                            reload = true;
+                        }
+                        Err(err) => log::error!("Failed to capture reflection probe: {}", err),
+                    }
+                }
+                Some(_) => {
+                    capture.rendering = None;
+                    capture.waited += 1;
+                    if capture.waited > RESUBMIT_FRAMES {
+                        capture.next_face =
+                            capture.faces.iter().position(Option::is_none).unwrap_or(0);
+                    }
+                    probes.capture = Some(capture);
+                }
+            }
+        } else {
+            let is_captured = |entity| probes.is_captured(entity);
+            let can_capture = probes.captured.len() < MAX_REFLECTION_PROBES;
+            let next = (&entities, &mut reflection_probes, &transforms)
+                .join()
+                .find(|(entity, probe, _)| {
+                    (probe.requested
+                        || (probe.update == ProbeUpdate::Once && !is_captured(*entity)))
+                        && (can_capture || is_captured(*entity))
+                })
+                .map(|(entity, probe, _)| {
+                    probe.requested = false;
+                    entity
+                });
+
+            // The first face is rendered from the next frame, once the transform of the probe
+            // and the render graph are up to date.
+            if let Some(entity) = next {
+                let camera = *probes.camera.get_or_insert_with(|| entities.create());
+                cameras
+                    .insert(camera, Camera::perspective(1.0, FRAC_PI_2, probes.near))
+                    .expect("Capture camera is alive");
+                probes.serial += 1;
+                probes.capture = Some(Capture {
+                    serial: probes.serial,
+                    entity,
+                    position: None,
+                    next_face: 0,
+                    rendering: None,
+                    faces: vec![None; FACES.len()],
+                    waited: 0,
+                });
+            }
+        }
+
+        if reload {
+            probes.load_textures(&loader, &storage);
+        } else if changed {
+            probes.version = probes.version.wrapping_add(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::environment_map::{texel_coordinate, texel_direction};
+
+    #[test]
+    fn faces_are_mirrored_cube_map_faces() {
+        let size = 4;
+        for face in 0..6 {
+            let rotation = face_rotation(face);
+            for y in 0..size {
+                for x in 0..size {
+                    // Camera space direction through the pixel, rows from the top.
+                    let s = texel_coordinate(x, size);
+                    let t = texel_coordinate(y, size);
+                    let direction = rotation * Vector3::new(s, -t, -1.0).normalize();
+                    let expected = texel_direction(face as u32, size - 1 - x, y, size);
+                    assert!(
+                        (direction - expected).norm() < 1e-5,
+                        "face {} pixel {}, {}: {:?} != {:?}",
+                        face,
+                        x,
+                        y,
+                        direction,
+                        expected
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn face_texels_reverse_rows() {
+        // 2x2 pixels of 1.0, 2.0 / 0.5, -1.0 in every channel.
+        let pixels = [0x3c00, 0x4000, 0x3800, 0xbc00]
+            .iter()
+            .flat_map(|&value| vec![value; 4])
+            .collect::<Vec<u16>>();
+        assert_eq!(
+            face_texels(2, &pixels),
+            vec![[2.0; 3], [1.0; 3], [0.0; 3], [0.5; 3]]
+        );
+        assert_eq!(half(0x7c00), 65504.0);
+        assert_eq!(half(0x0001), 2f32.powi(-24));
+    }
+
+    #[test]
+    fn nearest_probe_containing_origin() {
+        let probes = [
+            (Point3::new(0.0, 0.0, 0.0), Vector3::new(10.0, 10.0, 10.0)),
+            (Point3::new(4.0, 0.0, 0.0), Vector3::new(2.0, 2.0, 2.0)),
+            (Point3::new(20.0, 0.0, 0.0), Vector3::new(5.0, 5.0, 5.0)),
+        ];
+        let nearest = |x| nearest_probe(probes.iter().cloned(), &Point3::new(x, 1.0, 0.0));
+
+        assert_eq!(nearest(-3.0), Some(0));
+        assert_eq!(nearest(3.0), Some(1));
+        assert_eq!(nearest(9.0), Some(0));
+        assert_eq!(nearest(16.0), Some(2));
+        assert_eq!(nearest(30.0), None);
+    }
+}
@@ -0,0 +1,44 @@
+//! Debug views of the 3d mesh passes.
+use amethyst_core::ecs::{Read, SystemData, World};
+use derivative::Derivative;
+
+/// Resource switching the 3d mesh passes between their shading and debug views, so scenes
+/// can be inspected without writing custom shaders.
+///
+/// Respected by `DrawPbr`, `DrawShaded`, `DrawFlat` and their transparent variants. The 3d
+/// plugins rebuild the render graph when it changes, so it's not meant to change every frame. The G-buffer pass of the deferred path
+/// supports only `Shaded` and `Wireframe`. Passes draw modes they or the device don't
+/// support shaded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Derivative)]
+#[derivative(Default)]
+pub enum RenderMode {
+    /// Draw meshes with the shading of their pass.
+    #[derivative(Default)]
+    Shaded,
+    /// Draw the edges of triangles with the shading of their pass. Requires the
+    /// `NON_FILL_POLYGON_MODE` device feature.
+    Wireframe,
+    /// Draw the albedo of materials multiplied by the tint, without lighting.
+    Unlit,
+    /// Draw world space vertex normals mapped to colors. Meshes of passes without normals
+    /// show the normals of their faces.
+    Normals,
+    /// Add a constant color for every fragment drawn to a pixel, without depth testing, to
+    /// show how many times pixels are shaded.
+    Overdraw,
+}
+
+impl RenderMode {
+    /// Returns the `RenderMode` resource, or `Shaded` if there is none.
+    pub fn fetch(world: &World) -> Self {
+        <Option<Read<'_, RenderMode>>>::fetch(world)
+            .map(|mode| *mode)
+            .unwrap_or_default()
+    }
+
+    /// Returns true if the mode is drawn by the debug fragment shader of a pass, which reads
+    /// the mode from a push constant.
+    pub(crate) fn uses_debug_shader(self) -> bool {
+        self != RenderMode::Shaded && self != RenderMode::Wireframe
+    }
+}
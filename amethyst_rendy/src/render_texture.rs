@@ -0,0 +1,132 @@
+//! Rendering cameras into textures, e.g. for mirrors, security monitors or minimaps.
+
+use crate::{
+    bundle::Target,
+    camera::Camera,
+    types::{Texture, TextureData},
+};
+use amethyst_assets::Handle;
+use amethyst_core::ecs::{
+    Component, DenseVecStorage, Entities, Entity, Join, ReadStorage, SystemData, World,
+};
+use rendy::{
+    hal::{
+        format::Format,
+        image::{Filter, Kind, SamplerInfo, ViewKind, WrapMode},
+    },
+    texture::{pixel::Rgba8Srgb, TextureBuilder},
+};
+
+/// Format of the color image rendered by cameras with a [CameraTarget] and of their textures.
+pub const RENDER_TEXTURE_FORMAT: Format = Format::Rgba8Srgb;
+
+/// Component rendering a camera into a texture instead of the window.
+///
+/// The camera renders `target`, which is defined by
+/// [RenderToTexture](crate::plugins::RenderToTexture) and drawn by the plugins added with that
+/// target. The rendered image is copied into `texture` every frame, so it can be used by
+/// materials, sprites or UI images like any other texture.
+///
+/// The texture must be `width` by `height` pixels in [RENDER_TEXTURE_FORMAT] with a single
+/// mip level, e.g. loaded from [CameraTarget::texture_data].
+#[derive(Debug, Clone, PartialEq)]
+pub struct CameraTarget {
+    /// Render target drawn from the camera.
+    pub target: Target,
+    /// Texture receiving the rendered image.
+    pub texture: Handle<Texture>,
+    /// Width of the rendered image in pixels.
+    pub width: u32,
+    /// Height of the rendered image in pixels.
+    pub height: u32,
+}
+
+impl Component for CameraTarget {
+    type Storage = DenseVecStorage<Self>;
+}
+
+impl CameraTarget {
+    /// Create a `CameraTarget` rendering `target` into a `width` by `height` pixels `texture`.
+    pub fn new(target: Target, texture: Handle<Texture>, width: u32, height: u32) -> Self {
+        Self {
+            target,
+            texture,
+            width,
+            height,
+        }
+    }
+
+    /// Data of a black texture to be rendered into by a camera target of the given size.
+    pub fn texture_data(width: u32, height: u32) -> TextureData {
+        let data = vec![
+            Rgba8Srgb {
+                repr: [0, 0, 0, 255],
+            };
+            (width * height) as usize
+        ];
+        TextureBuilder::new()
+            .with_kind(Kind::D2(width, height, 1, 1))
+            .with_view_kind(ViewKind::D2)
+            .with_data_width(width)
+            .with_data_height(height)
+            .with_sampler_info(SamplerInfo::new(Filter::Linear, WrapMode::Clamp))
+            .with_data(data)
+            .into()
+    }
+}
+
+/// Find the camera entity rendering `target`, along with its `CameraTarget`.
+///
+/// When several cameras render the same target, the first one found is used.
+pub fn target_camera(world: &World, target: Target) -> Option<(Entity, CameraTarget)> {
+    let (entities, cameras, targets) = <(
+        Entities<'_>,
+        ReadStorage<'_, Camera>,
+        ReadStorage<'_, CameraTarget>,
+    )>::fetch(world);
+    (&entities, &cameras, &targets)
+        .join()
+        .find(|(_, _, camera_target)| camera_target.target == target)
+        .map(|(entity, _, camera_target)| (entity, camera_target.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use amethyst_assets::{AssetStorage, Loader};
+    use amethyst_core::ecs::{Builder, WorldExt};
+    use rayon::ThreadPoolBuilder;
+    use std::sync::Arc;
+
+    #[test]
+    fn finds_camera_of_target() {
+        let mut world = World::new();
+        world.register::<Camera>();
+        world.register::<CameraTarget>();
+        let loader = Loader::new(".", Arc::new(ThreadPoolBuilder::new().build().unwrap()));
+        let texture = loader.load_from_data(
+            CameraTarget::texture_data(64, 32),
+            (),
+            &AssetStorage::<Texture>::new(),
+        );
+        let mirror = CameraTarget::new(Target::Custom("mirror"), texture, 64, 32);
+
+        world
+            .create_entity()
+            .with(Camera::standard_3d(1.0, 1.0))
+            .build();
+        // Targets without a camera are ignored.
+        world.create_entity().with(mirror.clone()).build();
+        let camera = world
+            .create_entity()
+            .with(Camera::standard_3d(1.0, 1.0))
+            .with(mirror.clone())
+            .build();
+
+        assert_eq!(
+            target_camera(&world, Target::Custom("mirror")),
+            Some((camera, mirror))
+        );
+        assert_eq!(target_camera(&world, Target::Custom("monitor")), None);
+    }
+}
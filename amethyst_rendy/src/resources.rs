@@ -1,9 +1,11 @@
 //! `amethyst` rendering ecs resources
 //!
 
-use amethyst_assets::PrefabData;
+use crate::types::Texture;
+use amethyst_assets::{Handle, PrefabData};
 use amethyst_core::ecs::{Component, DenseVecStorage, Entity, Write};
 use amethyst_error::Error;
+use rendy::hal::pso::Rect;
 
 /// The ambient color of a scene
 #[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
@@ -31,6 +33,13 @@ impl<'a> PrefabData<'a> for AmbientColor {
     }
 }
 
+/// Cube map texture drawn by the skybox in place of its gradient colors.
+///
+/// The texture must have a cube view, such as textures loaded with `EquirectFormat`
+/// or with `ImageFormat` using `TextureKind::Cube`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SkyboxCubemap(pub Handle<Texture>);
+
 /// A single object tinting applied in multiplicative mode (modulation)
 #[derive(Clone, Copy, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct Tint(#[serde(with = "crate::serde_shim::srgba")] pub palette::Srgba);
@@ -45,3 +54,94 @@ impl Into<[f32; 4]> for Tint {
         [r, g, b, a]
     }
 }
+
+/// Rectangle of the render target outside of which an entity isn't drawn, in pixels with the
+/// origin at the top left corner of the target.
+///
+/// Sprites drawn by `DrawFlat2D` and `DrawFlat2DTransparent` are clipped to their own
+/// `ScissorRect`, UI elements to the rectangles of themselves and their parents. Entities with
+/// the same texture and scissor rectangle are still drawn in a single batch.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize,
+)]
+pub struct ScissorRect {
+    /// Distance of the left edge from the left edge of the target.
+    pub x: u32,
+    /// Distance of the top edge from the top edge of the target.
+    pub y: u32,
+    /// Width of the rectangle.
+    pub width: u32,
+    /// Height of the rectangle.
+    pub height: u32,
+}
+
+impl Component for ScissorRect {
+    type Storage = DenseVecStorage<Self>;
+}
+
+impl ScissorRect {
+    /// Create a rectangle from its top left corner and size.
+    pub fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Returns the part of the rectangle inside of `other`, which is empty if they don't overlap.
+    pub fn intersection(&self, other: &ScissorRect) -> ScissorRect {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = (self.x + self.width).min(other.x + other.width);
+        let bottom = (self.y + self.height).min(other.y + other.height);
+        ScissorRect {
+            x,
+            y,
+            width: right.saturating_sub(x),
+            height: bottom.saturating_sub(y),
+        }
+    }
+
+    /// Whether the rectangle has no area, clipping everything.
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    /// Returns the rectangle passed to `set_scissors` of the encoder, limited to the largest
+    /// coordinates of a `Rect`.
+    pub fn to_rect(&self) -> Rect {
+        let clamp = |v: u32| v.min(i16::max_value() as u32) as i16;
+        Rect {
+            x: clamp(self.x),
+            y: clamp(self.y),
+            w: clamp(self.width),
+            h: clamp(self.height),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scissor_rects_intersect() {
+        let a = ScissorRect::new(10, 20, 100, 50);
+        assert_eq!(
+            a.intersection(&ScissorRect::new(50, 0, 100, 40)),
+            ScissorRect::new(50, 20, 60, 20)
+        );
+        assert!(a.intersection(&ScissorRect::new(200, 0, 10, 10)).is_empty());
+        assert_eq!(
+            ScissorRect::new(1, 2, 100_000, 3).to_rect(),
+            Rect {
+                x: 1,
+                y: 2,
+                w: i16::max_value(),
+                h: 3
+            }
+        );
+    }
+}
@@ -0,0 +1,296 @@
+//! Capturing the rendered window into CPU memory or PNG files.
+
+use amethyst_core::{
+    ecs::{System, Write},
+    Time,
+};
+use amethyst_error::Error;
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+/// Resource requesting screenshots of the window, captured by
+/// [RenderScreenshot](crate::plugins::RenderScreenshot).
+///
+/// The final image of the frame rendered after the request is copied from the GPU once the
+/// frame has completed, a few frames later, and can then be taken with `take`. Requests made
+/// before the previous one is captured are merged into it.
+#[derive(Debug, Default)]
+pub struct ScreenshotRequest {
+    pub(crate) pending: Option<Option<PathBuf>>,
+    pub(crate) captured: Option<Screenshot>,
+}
+
+impl ScreenshotRequest {
+    /// Request capturing the next frame.
+    pub fn request(&mut self) {
+        if self.pending.is_none() {
+            self.pending = Some(None);
+        }
+    }
+
+    /// Request capturing the next frame and writing it to a PNG file at `path`.
+    ///
+    /// The file is written on the thread pool of the application when there is one.
+    /// It is also available from `take` afterwards.
+    pub fn request_png(&mut self, path: impl Into<PathBuf>) {
+        self.pending = Some(Some(path.into()));
+    }
+
+    /// Whether a requested screenshot hasn't started being captured yet.
+    pub fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Take the last captured screenshot, if any.
+    pub fn take(&mut self) -> Option<Screenshot> {
+        self.captured.take()
+    }
+}
+
+/// Image of a captured frame.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Screenshot {
+    /// Width of the image in pixels.
+    pub width: u32,
+    /// Height of the image in pixels.
+    pub height: u32,
+    /// Pixels in sRGB encoded RGBA with 8 bits per channel, row by row from the top left.
+    pub data: Vec<u8>,
+}
+
+impl Screenshot {
+    /// Write the screenshot to a PNG file at `path`.
+    pub fn save_png(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        image::save_buffer_with_format(
+            path,
+            &self.data,
+            self.width,
+            self.height,
+            image::ColorType::RGBA(8),
+            image::ImageFormat::PNG,
+        )
+        .map_err(Error::new)
+    }
+}
+
+/// Resource recording the window as a sequence of numbered PNG files at a fixed framerate
+/// while the game runs, e.g. for trailers and bug reports, captured by
+/// [RenderScreenshot](crate::plugins::RenderScreenshot).
+///
+/// Frames are captured by the [FrameCaptureSystem] whenever the game time reaches the next
+/// frame of the recording, into `frame_00000.png`, `frame_00001.png` and so on. Encode them
+/// into a video with an external tool, e.g.
+/// `ffmpeg -framerate 30 -i frame_%05d.png trailer.mp4`.
+///
+/// While recording, the system advances the game time by exactly one frame of the recording
+/// per rendered frame with [Time::set_forced_delta_time], so every frame is captured and the
+/// video plays at the speed of the game however long frames take to render.
+#[derive(Debug, Default)]
+pub struct FrameCapture {
+    recording: Option<Recording>,
+}
+
+#[derive(Debug)]
+struct Recording {
+    directory: PathBuf,
+    interval: Duration,
+    // Game time since the start of the recording, and the time of the next frame.
+    elapsed: Duration,
+    next: Duration,
+    frames: u32,
+}
+
+impl FrameCapture {
+    /// Start recording `framerate` frames per second of game time into `directory`, which is
+    /// created if it doesn't exist. Any previous recording is stopped.
+    pub fn start(&mut self, directory: impl Into<PathBuf>, framerate: u32) -> Result<(), Error> {
+        let directory = directory.into();
+        std::fs::create_dir_all(&directory).map_err(Error::new)?;
+        self.recording = Some(Recording {
+            directory,
+            interval: Duration::from_secs(1) / framerate.max(1),
+            elapsed: Duration::default(),
+            next: Duration::default(),
+            frames: 0,
+        });
+        Ok(())
+    }
+
+    /// Stop recording. Returns the number of frames requested by the recording.
+    pub fn stop(&mut self) -> Option<u32> {
+        self.recording.take().map(|recording| recording.frames)
+    }
+
+    /// Whether a recording is running.
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// Game time between two frames of the running recording.
+    fn interval(&self) -> Option<Duration> {
+        self.recording.as_ref().map(|recording| recording.interval)
+    }
+
+    /// Advance the recording by `delta` of game time, returning the path of the frame to
+    /// capture if the next frame is due.
+    fn advance(&mut self, delta: Duration) -> Option<PathBuf> {
+        let recording = self.recording.as_mut()?;
+        recording.elapsed += delta;
+        if recording.elapsed < recording.next {
+            return None;
+        }
+        // Frames missed while the game rendered slower than the recording are skipped.
+        while recording.next <= recording.elapsed {
+            recording.next += recording.interval;
+        }
+        let path = recording
+            .directory
+            .join(format!("frame_{:05}.png", recording.frames));
+        recording.frames += 1;
+        Some(path)
+    }
+}
+
+/// Request screenshots of the frames recorded by the [FrameCapture] resource, forcing the
+/// delta time of the game to the framerate of the recording while it runs.
+#[derive(Default, Debug)]
+pub struct FrameCaptureSystem {
+    forcing_delta: bool,
+}
+
+impl FrameCaptureSystem {
+    /// Create new frame capture system
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<'a> System<'a> for FrameCaptureSystem {
+    type SystemData = (
+        Write<'a, Time>,
+        Write<'a, FrameCapture>,
+        Write<'a, ScreenshotRequest>,
+    );
+
+    fn run(&mut self, (mut time, mut capture, mut request): Self::SystemData) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("frame_capture_system");
+
+        // The delta forced here applies from the next frame on, the first frame of a recording
+        // is captured right away. Frames with a forced delta advance the recording by exactly
+        // one frame, as the game time goes through rounding to seconds.
+        let delta = match capture.interval() {
+            Some(interval) if self.forcing_delta => interval,
+            _ => time.delta_time(),
+        };
+        if let Some(path) = capture.advance(delta) {
+            request.request_png(path);
+        }
+        match capture.interval() {
+            Some(interval) => {
+                time.set_forced_delta_time(Some(interval));
+                self.forcing_delta = true;
+            }
+            None if self.forcing_delta => {
+                time.set_forced_delta_time(None);
+                self.forcing_delta = false;
+            }
+            None => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use amethyst_core::ecs::{RunNow, World, WorldExt};
+
+    #[test]
+    fn requests_are_merged_until_captured() {
+        let mut request = ScreenshotRequest::default();
+        assert!(!request.is_pending());
+
+        request.request_png("first.png");
+        request.request();
+        assert_eq!(request.pending, Some(Some(PathBuf::from("first.png"))));
+
+        request.pending = None;
+        request.captured = Some(Screenshot {
+            width: 1,
+            height: 1,
+            data: vec![255, 0, 0, 255],
+        });
+        assert!(!request.is_pending());
+        assert_eq!(request.take().map(|s| s.data), Some(vec![255, 0, 0, 255]));
+        assert_eq!(request.take(), None);
+    }
+
+    #[test]
+    fn frames_are_captured_at_fixed_rate() {
+        let directory = std::env::temp_dir().join("amethyst_frame_capture_test");
+        let mut capture = FrameCapture::default();
+        assert_eq!(capture.advance(Duration::from_millis(10)), None);
+
+        capture.start(&directory, 10).unwrap();
+        let frame = |capture: &mut FrameCapture, millis| {
+            capture
+                .advance(Duration::from_millis(millis))
+                .map(|path| path.file_name().unwrap().to_str().unwrap().to_owned())
+        };
+        assert_eq!(frame(&mut capture, 60).as_deref(), Some("frame_00000.png"));
+        assert_eq!(frame(&mut capture, 60).as_deref(), Some("frame_00001.png"));
+        assert_eq!(frame(&mut capture, 60), None);
+        assert_eq!(frame(&mut capture, 60).as_deref(), Some("frame_00002.png"));
+        // A long frame skips the frames it spans.
+        assert_eq!(frame(&mut capture, 500).as_deref(), Some("frame_00003.png"));
+        assert_eq!(frame(&mut capture, 50), None);
+        assert_eq!(frame(&mut capture, 10).as_deref(), Some("frame_00004.png"));
+        assert_eq!(capture.stop(), Some(5));
+        assert!(!capture.is_recording());
+        std::fs::remove_dir(directory).ok();
+    }
+
+    #[test]
+    fn system_forces_delta_time_while_recording() {
+        let directory = std::env::temp_dir().join("amethyst_frame_capture_system_test");
+        let mut world = World::new();
+        let mut system = FrameCaptureSystem::new();
+        System::setup(&mut system, &mut world);
+        world
+            .write_resource::<FrameCapture>()
+            .start(&directory, 25)
+            .unwrap();
+
+        // Frames are captured one per rendered frame however long they take.
+        for frame in 0..3 {
+            system.run_now(&world);
+            let path = world.write_resource::<ScreenshotRequest>().pending.take();
+            assert_eq!(
+                path.flatten()
+                    .unwrap()
+                    .file_name()
+                    .unwrap()
+                    .to_str()
+                    .unwrap(),
+                format!("frame_{:05}.png", frame)
+            );
+            world
+                .write_resource::<Time>()
+                .set_delta_time(Duration::from_millis(200));
+        }
+        assert_eq!(
+            world.read_resource::<Time>().delta_real_time(),
+            Duration::from_millis(40)
+        );
+
+        world.write_resource::<FrameCapture>().stop();
+        system.run_now(&world);
+        assert_eq!(world.read_resource::<Time>().forced_delta_time(), None);
+        std::fs::remove_dir(directory).ok();
+    }
+}
@@ -0,0 +1,179 @@
+//! Reloading shaders from their SPIR-V files at runtime.
+//!
+//! Shaders are only watched with the `shader-hot-reload` feature, otherwise a
+//! [ReloadableShader] is just its embedded SPIR-V.
+
+use rendy::{
+    hal::pso::ShaderStageFlags,
+    shader::{Shader, SpirvShader},
+};
+use std::{
+    borrow::Cow,
+    path::{Path, PathBuf},
+};
+
+#[cfg(feature = "shader-hot-reload")]
+use rendy::hal::pso::read_spirv;
+#[cfg(feature = "shader-hot-reload")]
+use std::{collections::HashMap, sync::Mutex, time::SystemTime};
+
+#[cfg(feature = "shader-hot-reload")]
+lazy_static::lazy_static! {
+    static ref WATCHED_SHADERS: Mutex<HashMap<(String, PathBuf), WatchedShader>> = Default::default();
+}
+
+#[cfg(feature = "shader-hot-reload")]
+#[derive(Debug, Default)]
+struct WatchedShader {
+    checked: bool,
+    modified: Option<SystemTime>,
+    reloaded: Option<Vec<u32>>,
+}
+
+/// Shader embedded into the binary, replaced by the SPIR-V file it was compiled to
+/// whenever [RenderShaderHotReload](crate::plugins::RenderShaderHotReload) finds the file
+/// has changed.
+///
+/// The file is identified by a path relative to a named shader root, usually the crate
+/// embedding the shader, which is mapped to a directory by `RenderShaderHotReload`.
+/// Pipelines are created from the embedded shader until the file changes, so the file doesn't
+/// have to exist outside of development. Shaders converted from a `SpirvShader` are never
+/// reloaded.
+#[derive(Debug, Clone)]
+pub struct ReloadableShader {
+    file: Option<(String, PathBuf)>,
+    embedded: SpirvShader,
+}
+
+impl ReloadableShader {
+    /// Create a shader from `embedded` SPIR-V, reloaded from the file at `path` relative to
+    /// the shader root named `root`.
+    pub fn new(root: impl Into<String>, path: impl Into<PathBuf>, embedded: SpirvShader) -> Self {
+        let file = (root.into(), path.into());
+        #[cfg(feature = "shader-hot-reload")]
+        WATCHED_SHADERS
+            .lock()
+            .unwrap()
+            .entry(file.clone())
+            .or_default();
+        Self {
+            file: Some(file),
+            embedded,
+        }
+    }
+
+    /// Name of the shader root and path relative to it of the SPIR-V file the shader is
+    /// reloaded from.
+    pub fn file(&self) -> Option<(&str, &Path)> {
+        self.file
+            .as_ref()
+            .map(|(root, path)| (root.as_str(), path.as_path()))
+    }
+}
+
+impl From<SpirvShader> for ReloadableShader {
+    fn from(embedded: SpirvShader) -> Self {
+        Self {
+            file: None,
+            embedded,
+        }
+    }
+}
+
+impl Shader for ReloadableShader {
+    fn spirv(&self) -> Result<Cow<'_, [u32]>, failure::Error> {
+        #[cfg(feature = "shader-hot-reload")]
+        {
+            if let Some(file) = &self.file {
+                let watched = WATCHED_SHADERS.lock().unwrap();
+                if let Some(spirv) = watched.get(file).and_then(|s| s.reloaded.as_ref()) {
+                    return Ok(Cow::Owned(spirv.clone()));
+                }
+            }
+        }
+        self.embedded.spirv()
+    }
+
+    fn entry(&self) -> &str {
+        self.embedded.entry()
+    }
+
+    fn stage(&self) -> ShaderStageFlags {
+        self.embedded.stage()
+    }
+}
+
+#[cfg(feature = "shader-hot-reload")]
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Reload the SPIR-V files of all shaders modified since they were last checked, looking
+/// them up in the directories of their shader `roots`.
+///
+/// The first check of a file only records its modification time. Returns whether any shader
+/// was reloaded. Files failing to load are logged and skipped.
+#[cfg(feature = "shader-hot-reload")]
+pub(crate) fn reload_modified_shaders(roots: &HashMap<String, PathBuf>) -> bool {
+    let mut reloaded = false;
+    for ((root, path), shader) in WATCHED_SHADERS.lock().unwrap().iter_mut() {
+        let path = match roots.get(root) {
+            Some(dir) => dir.join(path),
+            None => continue,
+        };
+        let modified = modified_time(&path);
+        if !shader.checked {
+            shader.checked = true;
+            shader.modified = modified;
+            continue;
+        }
+        if modified.is_none() || modified == shader.modified {
+            continue;
+        }
+        shader.modified = modified;
+        match std::fs::File::open(&path).and_then(read_spirv) {
+            Ok(spirv) => {
+                log::info!("Reloaded shader {:?}", path);
+                shader.reloaded = Some(spirv);
+                reloaded = true;
+            }
+            Err(err) => log::error!("Failed to reload shader {:?}: {}", path, err),
+        }
+    }
+    reloaded
+}
+
+#[cfg(all(test, feature = "shader-hot-reload"))]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn spirv_bytes(words: &[u32]) -> Vec<u8> {
+        words
+            .iter()
+            .flat_map(|w| w.to_le_bytes().to_vec())
+            .collect()
+    }
+
+    #[test]
+    fn shader_is_replaced_by_modified_file() {
+        let file = format!("amethyst_shader_reload_{}.spv", std::process::id());
+        let path = std::env::temp_dir().join(&file);
+        let _ = std::fs::remove_file(&path);
+        let embedded = SpirvShader::new(vec![0x0723_0203, 1], ShaderStageFlags::VERTEX, "main");
+        let shader = ReloadableShader::new("test", &file, embedded);
+        let roots = std::iter::once(("test".to_string(), std::env::temp_dir())).collect();
+        assert!(!reload_modified_shaders(&roots));
+        assert_eq!(&*shader.spirv().unwrap(), &[0x0723_0203, 1]);
+
+        std::fs::File::create(&path)
+            .unwrap()
+            .write_all(&spirv_bytes(&[0x0723_0203, 2]))
+            .unwrap();
+        assert!(reload_modified_shaders(&roots));
+        assert_eq!(&*shader.spirv().unwrap(), &[0x0723_0203, 2]);
+        assert_eq!(shader.stage(), ShaderStageFlags::VERTEX);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
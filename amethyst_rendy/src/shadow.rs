@@ -0,0 +1,365 @@
+//! Shadow mapping configuration and light-space projection helpers.
+
+use crate::camera::Camera;
+use amethyst_core::math::{Isometry3, Matrix4, Point3, Translation3, Vector3};
+
+/// Maximum number of cascades used for directional light shadows.
+pub const MAX_SHADOW_CASCADES: usize = 4;
+
+/// Minimum number of cascades used for directional light shadows.
+pub const MIN_SHADOW_CASCADES: usize = 2;
+
+/// Maximum number of point lights casting shadows at the same time.
+pub const MAX_POINT_SHADOWS: usize = 4;
+
+/// Maximum number of spot lights casting shadows at the same time.
+pub const MAX_SPOT_SHADOWS: usize = 4;
+
+/// Distance from a point or spot light at which its shadow map starts.
+const LIGHT_SHADOW_NEAR: f32 = 0.05;
+
+/// Resource configuring the shadow maps rendered by `RenderShadows`.
+///
+/// Changing `cascades` or `resolution` rebuilds the render graph.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShadowParams {
+    /// Number of cascades used for the directional light, clamped between 2 and 4.
+    pub cascades: usize,
+    /// Width and height of the depth map of a single cascade, in texels.
+    pub resolution: u32,
+    /// Distance from the camera up to which shadows are rendered.
+    pub max_distance: f32,
+    /// Blend between uniform (0.0) and logarithmic (1.0) distribution of cascade splits.
+    pub split_lambda: f32,
+    /// Depth offset applied when comparing against the shadow map to avoid shadow acne.
+    pub depth_bias: f32,
+    /// Radius of the percentage-closer filtering kernel in texels. Zero gives hard shadows.
+    pub pcf_radius: u32,
+    /// Number of point lights with `casts_shadows` set that render shadows,
+    /// at most 4. Lights closest to the camera are picked first.
+    pub point_shadows: usize,
+    /// Width and height of a single cube map face of a point light shadow, in texels.
+    pub point_resolution: u32,
+    /// Depth comparison bias of point light shadows, relative to the distance from the light.
+    pub point_depth_bias: f32,
+    /// Number of spot lights with `casts_shadows` set that render shadows,
+    /// at most 4. Lights closest to the camera are picked first.
+    pub spot_shadows: usize,
+    /// Width and height of the shadow map of a single spot light, in texels.
+    pub spot_resolution: u32,
+}
+
+impl Default for ShadowParams {
+    fn default() -> Self {
+        Self {
+            cascades: 4,
+            resolution: 1024,
+            max_distance: 100.0,
+            split_lambda: 0.75,
+            depth_bias: 0.001,
+            pcf_radius: 1,
+            point_shadows: 2,
+            point_resolution: 512,
+            point_depth_bias: 0.02,
+            spot_shadows: 2,
+            spot_resolution: 1024,
+        }
+    }
+}
+
+impl ShadowParams {
+    /// Number of cascades actually rendered.
+    pub fn cascade_count(&self) -> usize {
+        self.cascades
+            .max(MIN_SHADOW_CASCADES)
+            .min(MAX_SHADOW_CASCADES)
+    }
+
+    /// Size of the shadow atlas holding all cascades side by side.
+    pub fn atlas_size(&self) -> (u32, u32) {
+        let resolution = self.resolution.max(1);
+        (resolution * self.cascade_count() as u32, resolution)
+    }
+
+    /// Number of point light shadows that can be rendered at the same time.
+    pub fn point_shadow_budget(&self) -> usize {
+        self.point_shadows.min(MAX_POINT_SHADOWS)
+    }
+
+    /// Size of the atlas holding point light cube maps, one row of six faces per light.
+    /// `None` when point light shadows are disabled.
+    pub fn point_atlas_size(&self) -> Option<(u32, u32)> {
+        let budget = self.point_shadow_budget() as u32;
+        if budget == 0 {
+            return None;
+        }
+        let resolution = self.point_resolution.max(1);
+        Some((resolution * 6, resolution * budget))
+    }
+
+    /// Number of spot light shadows that can be rendered at the same time.
+    pub fn spot_shadow_budget(&self) -> usize {
+        self.spot_shadows.min(MAX_SPOT_SHADOWS)
+    }
+
+    /// Size of the atlas holding spot light shadow maps side by side.
+    /// `None` when spot light shadows are disabled.
+    pub fn spot_atlas_size(&self) -> Option<(u32, u32)> {
+        let budget = self.spot_shadow_budget() as u32;
+        if budget == 0 {
+            return None;
+        }
+        let resolution = self.spot_resolution.max(1);
+        Some((resolution * budget, resolution))
+    }
+}
+
+/// Returns `cascades + 1` view distances splitting the `near..far` range into cascades.
+///
+/// `lambda` blends between uniform (0.0) and logarithmic (1.0) split distribution.
+pub fn cascade_splits(near: f32, far: f32, cascades: usize, lambda: f32) -> Vec<f32> {
+    (0..=cascades)
+        .map(|i| {
+            let p = i as f32 / cascades as f32;
+            let logarithmic = near * (far / near).powf(p);
+            let uniform = near + (far - near) * p;
+            lambda * logarithmic + (1.0 - lambda) * uniform
+        })
+        .collect()
+}
+
+/// Computes projection-view matrices of a directional light, one per cascade,
+/// each covering a consecutive slice of the camera frustum.
+///
+/// Cascades are fitted with bounding spheres and snapped to shadow map texels,
+/// so shadows don't shimmer when the camera moves or rotates.
+pub fn directional_cascades(
+    camera: &Camera,
+    camera_transform: &Matrix4<f32>,
+    direction: &Vector3<f32>,
+    params: &ShadowParams,
+) -> Vec<Matrix4<f32>> {
+    let count = params.cascade_count();
+
+    // Two points on each frustum edge in view space. Reverse-Z puts the near plane at depth 1.
+    let edges = [(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)]
+        .iter()
+        .map(|&(x, y)| {
+            (
+                camera.inverse.transform_point(&Point3::new(x, y, 1.0)),
+                camera.inverse.transform_point(&Point3::new(x, y, 0.5)),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let near = (-edges[0].0.z).max(std::f32::EPSILON);
+    let far = params.max_distance.max(near * 2.0);
+    let splits = cascade_splits(near, far, count, params.split_lambda);
+
+    let direction = direction
+        .try_normalize(std::f32::EPSILON)
+        .unwrap_or_else(|| -Vector3::y());
+    let up = if direction.y.abs() > 0.99 {
+        Vector3::z()
+    } else {
+        Vector3::y()
+    };
+    let light_view =
+        Isometry3::look_at_rh(&Point3::origin(), &Point3::from(direction), &up).to_homogeneous();
+
+    splits
+        .windows(2)
+        .map(|range| {
+            let corners = edges
+                .iter()
+                .flat_map(|(a, b)| {
+                    range.iter().map(move |d| {
+                        let t = (-d - a.z) / (b.z - a.z);
+                        a + (b - a) * t
+                    })
+                })
+                .map(|p| camera_transform.transform_point(&p))
+                .collect::<Vec<_>>();
+
+            let center = corners
+                .iter()
+                .fold(Vector3::zeros(), |sum, p| sum + p.coords)
+                / corners.len() as f32;
+            let center = Point3::from(center);
+            let radius = corners
+                .iter()
+                .map(|p| (p - center).norm())
+                .fold(0.0f32, f32::max);
+            // Quantize the radius so the texel size doesn't change with camera rotation.
+            let radius = (radius * 16.0).ceil() / 16.0;
+
+            let texel = 2.0 * radius / params.resolution.max(1) as f32;
+            let light_center = light_view.transform_point(&center);
+            let snapped = Translation3::new(
+                -(light_center.x / texel).floor() * texel,
+                -(light_center.y / texel).floor() * texel,
+                0.0,
+            );
+
+            // Objects behind the slice, up to `max_distance` away, still cast shadows into it.
+            let z_near = -light_center.z - radius - params.max_distance;
+            let z_far = -light_center.z + radius;
+
+            orthographic(radius, z_near, z_far) * snapped.to_homogeneous() * light_view
+        })
+        .collect()
+}
+
+/// Computes projection-view matrices of the six cube map faces of a point light,
+/// ordered +X, -X, +Y, -Y, +Z, -Z.
+pub fn point_light_faces(position: &Point3<f32>) -> [Matrix4<f32>; 6] {
+    let projection =
+        Camera::perspective(1.0, std::f32::consts::FRAC_PI_2, LIGHT_SHADOW_NEAR).matrix;
+    let face = |direction: Vector3<f32>, up: Vector3<f32>| {
+        projection * Isometry3::look_at_rh(position, &(position + direction), &up).to_homogeneous()
+    };
+    [
+        face(Vector3::x(), -Vector3::y()),
+        face(-Vector3::x(), -Vector3::y()),
+        face(Vector3::y(), Vector3::z()),
+        face(-Vector3::y(), -Vector3::z()),
+        face(Vector3::z(), -Vector3::y()),
+        face(-Vector3::z(), -Vector3::y()),
+    ]
+}
+
+/// Computes the projection-view matrix of a spot light shadow map covering its whole cone.
+///
+/// `angle` is the angle between the light direction and the cone edge.
+pub fn spot_light_proj_view(
+    position: &Point3<f32>,
+    direction: &Vector3<f32>,
+    angle: f32,
+) -> Matrix4<f32> {
+    let direction = direction
+        .try_normalize(std::f32::EPSILON)
+        .unwrap_or_else(|| -Vector3::y());
+    let up = if direction.y.abs() > 0.99 {
+        Vector3::z()
+    } else {
+        Vector3::y()
+    };
+    let fov = (angle * 2.0).max(0.01).min(std::f32::consts::PI - 0.01);
+    Camera::perspective(1.0, fov, LIGHT_SHADOW_NEAR).matrix
+        * Isometry3::look_at_rh(position, &(position + direction), &up).to_homogeneous()
+}
+
+/// Symmetric orthographic projection with depth going from 1 at `z_near` to 0 at `z_far`.
+fn orthographic(half_extent: f32, z_near: f32, z_far: f32) -> Matrix4<f32> {
+    let mut matrix = Matrix4::identity();
+    matrix[(0, 0)] = 1.0 / half_extent;
+    matrix[(1, 1)] = -1.0 / half_extent;
+    matrix[(2, 2)] = 1.0 / (z_far - z_near);
+    matrix[(2, 3)] = z_far / (z_far - z_near);
+    matrix
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn splits_cover_range() {
+        let splits = cascade_splits(0.1, 100.0, 4, 0.75);
+        assert_eq!(splits.len(), 5);
+        assert_relative_eq!(splits[0], 0.1, epsilon = 1e-5);
+        assert_relative_eq!(splits[4], 100.0, epsilon = 1e-3);
+        assert!(splits.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn cascade_count_is_clamped() {
+        let mut params = ShadowParams::default();
+        params.cascades = 1;
+        assert_eq!(params.cascade_count(), MIN_SHADOW_CASCADES);
+        params.cascades = 8;
+        assert_eq!(params.cascade_count(), MAX_SHADOW_CASCADES);
+        assert_eq!(
+            params.atlas_size(),
+            (4 * params.resolution, params.resolution)
+        );
+
+        params.point_shadows = 0;
+        assert_eq!(params.point_atlas_size(), None);
+        params.point_shadows = 16;
+        assert_eq!(
+            params.point_atlas_size(),
+            Some((
+                6 * params.point_resolution,
+                MAX_POINT_SHADOWS as u32 * params.point_resolution
+            ))
+        );
+    }
+
+    #[test]
+    fn spot_light_covers_its_cone() {
+        let position = Point3::new(0.0, 4.0, 0.0);
+        let direction = Vector3::new(0.0, -1.0, 0.0);
+        let angle = std::f32::consts::FRAC_PI_6;
+        let proj_view = spot_light_proj_view(&position, &direction, angle);
+
+        let center = proj_view.transform_point(&Point3::new(0.0, 0.0, 0.0));
+        assert_relative_eq!(center.x, 0.0, epsilon = 1e-5);
+        assert_relative_eq!(center.y, 0.0, epsilon = 1e-5);
+        assert!(center.z > 0.0 && center.z < 1.0);
+
+        // A point on the cone edge lands on the edge of the shadow map
+        let edge = Point3::new(4.0 * angle.tan(), 0.0, 0.0);
+        let edge = proj_view.transform_point(&edge);
+        assert_relative_eq!(edge.x.abs().max(edge.y.abs()), 1.0, epsilon = 1e-4);
+    }
+
+    #[test]
+    fn point_faces_cover_their_axis() {
+        let position = Point3::new(1.0, 2.0, 3.0);
+        let faces = point_light_faces(&position);
+        let axes = [
+            Vector3::x(),
+            -Vector3::x(),
+            Vector3::y(),
+            -Vector3::y(),
+            Vector3::z(),
+            -Vector3::z(),
+        ];
+        for (face, axis) in faces.iter().zip(&axes) {
+            let clip = face.transform_point(&(position + axis * 5.0));
+            assert_relative_eq!(clip.x, 0.0, epsilon = 1e-5);
+            assert_relative_eq!(clip.y, 0.0, epsilon = 1e-5);
+            assert!(clip.z > 0.0 && clip.z < 1.0);
+
+            // Points behind the face are outside of its depth range
+            let behind = face.transform_point(&(position - axis * 5.0));
+            assert!(behind.z < 0.0 || behind.z > 1.0);
+        }
+    }
+
+    #[test]
+    fn cascades_contain_their_frustum_slice() {
+        let camera = Camera::perspective(1.5, std::f32::consts::FRAC_PI_3, 0.1);
+        let camera_transform = Matrix4::new_translation(&Vector3::new(3.0, 2.0, 5.0));
+        let params = ShadowParams {
+            max_distance: 50.0,
+            ..Default::default()
+        };
+        let direction = Vector3::new(-0.3, -1.0, 0.2);
+        let cascades = directional_cascades(&camera, &camera_transform, &direction, &params);
+        assert_eq!(cascades.len(), params.cascade_count());
+
+        let splits = cascade_splits(0.1, 50.0, params.cascade_count(), params.split_lambda);
+        for (cascade, range) in cascades.iter().zip(splits.windows(2)) {
+            // Points on the view axis inside the slice
+            for &d in range {
+                let world = camera_transform.transform_point(&Point3::new(0.0, 0.0, -d));
+                let clip = cascade.transform_point(&world);
+                assert!(clip.x.abs() <= 1.0 && clip.y.abs() <= 1.0);
+                assert!(clip.z >= 0.0 && clip.z <= 1.0);
+            }
+        }
+    }
+}
@@ -1,12 +1,14 @@
 //! Basic shape prefabs.
-use crate::types::Mesh;
-use amethyst_assets::{AssetStorage, Handle, Loader, PrefabData, Progress, ProgressCounter};
+use crate::{types::Mesh, visibility::BoundingBox};
+use amethyst_assets::{
+    AssetPrefab, AssetStorage, Handle, Loader, PrefabData, Progress, ProgressCounter,
+};
 use amethyst_core::{
     ecs::{
         prelude::{Entity, Read, ReadExpect, World, WriteStorage},
         shred::{ResourceId, SystemData},
     },
-    math::Vector3,
+    math::{Point3, Vector3},
 };
 use amethyst_error::Error;
 use genmesh::{
@@ -45,6 +47,8 @@ pub struct ShapePrefab<V> {
     #[serde(default)]
     shape_scale: Option<(f32, f32, f32)>,
     #[serde(skip)]
+    bounds: Option<BoundingBox>,
+    #[serde(skip)]
     _m: PhantomData<V>,
 }
 
@@ -53,9 +57,8 @@ where
     V: FromShape + Into<MeshBuilder<'static>>,
 {
     type SystemData = (
-        ReadExpect<'a, Loader>,
-        WriteStorage<'a, Handle<Mesh>>,
-        Read<'a, AssetStorage<Mesh>>,
+        <AssetPrefab<Mesh> as PrefabData<'a>>::SystemData,
+        WriteStorage<'a, BoundingBox>,
     );
     type Result = ();
 
@@ -66,11 +69,14 @@ where
         _: &[Entity],
         _: &[Entity],
     ) -> Result<(), Error> {
-        let (_, ref mut meshes, _) = system_data;
+        let ((_, ref mut meshes, _), ref mut bounds) = system_data;
         let self_handle = self.handle.as_ref().expect(
             "`ShapePrefab::load_sub_assets` was not called before `ShapePrefab::add_to_entity`",
         );
         meshes.insert(entity, self_handle.clone())?;
+        if let Some(ref self_bounds) = self.bounds {
+            bounds.insert(entity, self_bounds.clone())?;
+        }
         Ok(())
     }
 
@@ -79,12 +85,11 @@ where
         progress: &mut ProgressCounter,
         system_data: &mut <Self as PrefabData<'_>>::SystemData,
     ) -> Result<bool, Error> {
-        let (loader, _, mesh_storage) = system_data;
-        self.handle = Some(loader.load_from_data(
-            self.shape.generate::<V>(self.shape_scale).into(),
-            progress,
-            &mesh_storage,
-        ));
+        let ((loader, _, mesh_storage), _) = system_data;
+        let shape = self.shape.generate_internal(self.shape_scale);
+        self.bounds = Some(shape.bounds());
+        let builder: MeshBuilder<'static> = V::from(&shape).into();
+        self.handle = Some(loader.load_from_data(builder.into(), progress, mesh_storage));
         Ok(true)
     }
 }
@@ -128,6 +133,11 @@ pub type InternalVertexData = ([f32; 3], [f32; 3], [f32; 2], [f32; 3]);
 pub struct InternalShape(Vec<InternalVertexData>);
 
 impl InternalShape {
+    /// Bounding box around the positions of the vertices of the shape.
+    pub fn bounds(&self) -> BoundingBox {
+        BoundingBox::from_points(self.0.iter().map(|v| Point3::from(v.0)))
+    }
+
     fn map_into<T, F: FnMut(&InternalVertexData) -> T>(&self, f: F) -> Vec<T> {
         self.0.iter().map(f).collect()
     }
@@ -205,6 +215,15 @@ impl Shape {
         V::from(&self.generate_internal(scale)).into()
     }
 
+    /// Bounding box of the `Shape`, to cull entities that use the generated `Mesh`.
+    ///
+    /// ### Parameters:
+    ///
+    /// - `scale`: Scale the shape by the given amounts along the x, y, z axes
+    pub fn bounds(&self, scale: Option<(f32, f32, f32)>) -> BoundingBox {
+        self.generate_internal(scale).bounds()
+    }
+
     /// Generate vertices for the `Shape`, in format `V`
     ///
     /// ### Parameters:
@@ -389,4 +408,11 @@ mod tests {
             Shape::Plane(None).generate::<Vec<PosNormTangTex>>(None)
         );
     }
+
+    #[test]
+    fn test_bounds() {
+        let bounds = Shape::Cube.bounds(Some((2.0, 1.0, 0.5)));
+        assert_eq!(bounds.min, Point3::new(-2.0, -1.0, -0.5));
+        assert_eq!(bounds.max, Point3::new(2.0, 1.0, 0.5));
+    }
 }
@@ -0,0 +1,223 @@
+//! Runtime packing of sprite images into shared atlas textures.
+//!
+//! 2D scenes built from many separate images need a draw call per texture. Packing the images
+//! into a few atlas pages when loading lets the sprite passes batch them together.
+use image::RgbaImage;
+use rendy::hal::image::{Filter, SamplerInfo, WrapMode};
+
+use super::{Sprite, SpriteSheet, TextureCoordinates};
+use crate::{
+    atlas::{compose_pages, pack, page_texture_data, region_coordinates, Placement},
+    types::Texture,
+};
+use amethyst_assets::{AssetStorage, Handle, Loader};
+
+/// Identifies an image added to a `SpriteAtlasBuilder`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct AtlasImageId(usize);
+
+/// Packs the images of many sprite sheets into a few shared atlas textures.
+///
+/// Every added image becomes a `SpriteSheet` whose sprites are remapped to the area of the atlas
+/// page the image was packed into. Sprite sheets on the same page share one texture, so their
+/// sprites are drawn in the same batches.
+///
+/// # Example
+///
+/// ```ignore
+/// let mut atlas = SpriteAtlasBuilder::new();
+/// let hero = atlas.add(image::open("hero.png")?.to_rgba(), hero_sprites);
+/// let coin = atlas.add_image(image::open("coin.png")?.to_rgba());
+/// let atlas = atlas.build(&loader, &texture_storage, &sprite_sheet_storage);
+///
+/// let hero_sheet = atlas.sprite_sheet(hero).clone();
+/// ```
+#[derive(Debug)]
+pub struct SpriteAtlasBuilder {
+    max_size: u32,
+    padding: u32,
+    sampler_info: SamplerInfo,
+    images: Vec<(RgbaImage, Vec<Sprite>)>,
+}
+
+impl Default for SpriteAtlasBuilder {
+    fn default() -> Self {
+        SpriteAtlasBuilder {
+            max_size: 2048,
+            padding: 1,
+            sampler_info: SamplerInfo::new(Filter::Nearest, WrapMode::Clamp),
+            images: Vec::new(),
+        }
+    }
+}
+
+impl SpriteAtlasBuilder {
+    /// Creates an empty atlas builder with pages of up to 2048x2048 pixels.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Sets the maximum width and height of an atlas page. Images larger than this get a page
+    /// of their own.
+    pub fn with_max_size(mut self, max_size: u32) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Sets the number of transparent pixels left between packed images, which keeps filtering
+    /// from bleeding neighbouring images into each other.
+    pub fn with_padding(mut self, padding: u32) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Sets the sampler of the atlas textures.
+    pub fn with_sampler_info(mut self, sampler_info: SamplerInfo) -> Self {
+        self.sampler_info = sampler_info;
+        self
+    }
+
+    /// Adds an image in sRGB with straight alpha, along with its sprites. The texture coordinates
+    /// of the sprites are relative to the image, as generated by `Sprite::from_pixel_values`.
+    pub fn add(&mut self, image: RgbaImage, sprites: Vec<Sprite>) -> AtlasImageId {
+        self.images.push((image, sprites));
+        AtlasImageId(self.images.len() - 1)
+    }
+
+    /// Adds an image in sRGB with straight alpha, as a single sprite covering the whole image.
+    pub fn add_image(&mut self, image: RgbaImage) -> AtlasImageId {
+        let (width, height) = image.dimensions();
+        let sprite =
+            Sprite::from_pixel_values(width, height, width, height, 0, 0, [0.0; 2], false, false);
+        self.add(image, vec![sprite])
+    }
+
+    /// Packs all added images, then loads the atlas textures and sprite sheets.
+    pub fn build(
+        self,
+        loader: &Loader,
+        texture_storage: &AssetStorage<Texture>,
+        sprite_sheet_storage: &AssetStorage<SpriteSheet>,
+    ) -> SpriteAtlas {
+        let sizes = self
+            .images
+            .iter()
+            .map(|(image, _)| image.dimensions())
+            .collect::<Vec<_>>();
+        let (page_sizes, placements) = pack(&sizes, self.max_size, self.padding);
+
+        let textures = compose_pages(
+            self.images.iter().map(|(image, _)| image),
+            &placements,
+            &page_sizes,
+        )
+        .into_iter()
+        .map(|page| {
+            loader.load_from_data(
+                page_texture_data(page, self.sampler_info.clone()),
+                (),
+                texture_storage,
+            )
+        })
+        .collect::<Vec<_>>();
+
+        let sprite_sheets = self
+            .images
+            .into_iter()
+            .zip(placements)
+            .map(|((image, sprites), placement)| {
+                let sheet = SpriteSheet {
+                    texture: textures[placement.page].clone(),
+                    sprites: sprites
+                        .into_iter()
+                        .map(|sprite| {
+                            remap_sprite(
+                                sprite,
+                                image.dimensions(),
+                                &placement,
+                                page_sizes[placement.page],
+                            )
+                        })
+                        .collect(),
+                };
+                loader.load_from_data(sheet, (), sprite_sheet_storage)
+            })
+            .collect();
+
+        SpriteAtlas {
+            textures,
+            sprite_sheets,
+        }
+    }
+}
+
+/// Textures and sprite sheets created by a `SpriteAtlasBuilder`.
+#[derive(Debug, Clone)]
+pub struct SpriteAtlas {
+    textures: Vec<Handle<Texture>>,
+    sprite_sheets: Vec<Handle<SpriteSheet>>,
+}
+
+impl SpriteAtlas {
+    /// Returns the sprite sheet of an image added to the builder.
+    pub fn sprite_sheet(&self, id: AtlasImageId) -> &Handle<SpriteSheet> {
+        &self.sprite_sheets[id.0]
+    }
+
+    /// Returns the textures of all atlas pages.
+    pub fn textures(&self) -> &[Handle<Texture>] {
+        &self.textures
+    }
+}
+
+/// Maps the texture coordinates of a sprite from its source image to its atlas page.
+fn remap_sprite(
+    mut sprite: Sprite,
+    image_size: (u32, u32),
+    placement: &Placement,
+    page_size: (u32, u32),
+) -> Sprite {
+    let coords = &sprite.tex_coords;
+    let (left, top) =
+        region_coordinates(placement, (coords.left, coords.top), image_size, page_size);
+    let (right, bottom) = region_coordinates(
+        placement,
+        (coords.right, coords.bottom),
+        image_size,
+        page_size,
+    );
+    sprite.tex_coords = TextureCoordinates {
+        left,
+        right,
+        bottom,
+        top,
+    };
+    sprite
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sprites_are_remapped_to_page() {
+        let sprite = Sprite::from_pixel_values(64, 32, 32, 32, 32, 0, [0.0; 2], false, false);
+        let placement = Placement {
+            page: 0,
+            x: 64,
+            y: 32,
+        };
+
+        let sprite = remap_sprite(sprite, (64, 32), &placement, (128, 128));
+
+        assert_eq!(
+            sprite.tex_coords,
+            TextureCoordinates {
+                left: 0.75,
+                right: 1.0,
+                top: 0.25,
+                bottom: 0.5,
+            }
+        );
+    }
+}
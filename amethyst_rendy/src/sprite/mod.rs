@@ -7,6 +7,7 @@ use amethyst_assets::{Asset, Format, Handle};
 use amethyst_core::ecs::prelude::{Component, DenseVecStorage};
 use amethyst_error::Error;
 
+pub mod atlas;
 pub mod prefab;
 
 /// An asset handle to sprite sheet metadata.
@@ -197,6 +198,18 @@ impl Component for SpriteRender {
     type Storage = DenseVecStorage<Self>;
 }
 
+/// Tangent space normal map of a sprite, used by the lit 2D passes.
+///
+/// The texture must share the layout of the sprite sheet texture, as it is sampled with the
+/// texture coordinates of the sprite. Sprites without a normal map are lit as flat surfaces
+/// facing the camera.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpriteNormalMap(pub Handle<Texture>);
+
+impl Component for SpriteNormalMap {
+    type Storage = DenseVecStorage<Self>;
+}
+
 /// Represents one sprite in `SpriteList`.
 /// Positions originate in the top-left corner (bitmap image convention).
 #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
@@ -1,16 +1,22 @@
 //! Transparency, visibility sorting and camera centroid culling for 2D Sprites.
 use crate::{
     camera::{ActiveCamera, Camera},
+    sprite::SpriteRender,
     transparent::Transparent,
 };
+use amethyst_assets::PrefabData;
 use amethyst_core::{
     ecs::{
         hibitset::BitSet,
-        prelude::{Entities, Entity, Join, Read, ReadStorage, System, Write},
+        prelude::{
+            Component, DenseVecStorage, Entities, Entity, Join, Read, ReadStorage, System, Write,
+            WriteStorage,
+        },
     },
     math::{Point3, Vector3},
     Hidden, HiddenPropagate, Transform,
 };
+use amethyst_error::Error;
 use derivative::Derivative;
 use std::cmp::Ordering;
 
@@ -27,11 +33,50 @@ pub struct SpriteVisibility {
     pub visible_ordered: Vec<Entity>,
 }
 
-/// Determines what entities to be drawn. Will also sort transparent entities back to front based on
-/// position on the Z axis.
+/// Explicit draw order of a sprite, for deterministic ordering without tiny Z offsets.
+///
+/// Sprites with a `SpriteOrder` are drawn in order along with the `Transparent` sprites, without
+/// writing depth, so that a higher layer always covers a lower one regardless of Z. Within a
+/// layer, sprites are drawn from far to near, then grouped by sprite sheet. Transparent sprites
+/// without a `SpriteOrder` are on layer 0.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    serde::Deserialize,
+    serde::Serialize,
+    PrefabData,
+)]
+#[prefab(Component)]
+#[serde(default)]
+pub struct SpriteOrder {
+    /// Layer of the sprite. Higher layers are drawn on top of lower ones.
+    pub layer: i32,
+}
+
+impl SpriteOrder {
+    /// Creates a sprite order on the given layer.
+    pub fn new(layer: i32) -> Self {
+        SpriteOrder { layer }
+    }
+}
+
+impl Component for SpriteOrder {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Determines what entities to be drawn. Will also sort transparent and explicitly ordered
+/// entities by `SpriteOrder` layer, then back to front based on position on the Z axis, then by
+/// sprite sheet.
 ///
 /// The sprite render pass should draw all sprites without semi-transparent pixels, then draw the
-/// sprites with semi-transparent pixels from far to near.
+/// ordered sprites in the given order.
 ///
 /// Note that this should run after `Transform` has been updated for the current frame, and
 /// before rendering occurs.
@@ -39,18 +84,35 @@ pub struct SpriteVisibility {
 #[derivative(Default(bound = ""), Debug(bound = ""))]
 pub struct SpriteVisibilitySortingSystem {
     centroids: Vec<Internals>,
-    transparent: Vec<Internals>,
+    ordered: Vec<Internals>,
 }
 
 #[derive(Debug, Clone)]
 struct Internals {
     entity: Entity,
-    transparent: bool,
+    ordered: bool,
+    layer: i32,
+    sprite_sheet: u32,
     centroid: Point3<f32>,
     camera_distance: f32,
     from_camera: Vector3<f32>,
 }
 
+impl Internals {
+    /// Draw order of ordered sprites: by layer, then far to near, then by sprite sheet.
+    fn draw_order(&self, other: &Self) -> Ordering {
+        self.layer
+            .cmp(&other.layer)
+            .then_with(|| {
+                other
+                    .camera_distance
+                    .partial_cmp(&self.camera_distance)
+                    .unwrap_or(Ordering::Equal)
+            })
+            .then_with(|| self.sprite_sheet.cmp(&other.sprite_sheet))
+    }
+}
+
 impl SpriteVisibilitySortingSystem {
     /// Returns a new sprite visibility sorting system
     pub fn new() -> Self {
@@ -67,12 +129,25 @@ impl<'a> System<'a> for SpriteVisibilitySortingSystem {
         Read<'a, ActiveCamera>,
         ReadStorage<'a, Camera>,
         ReadStorage<'a, Transparent>,
+        ReadStorage<'a, SpriteOrder>,
+        ReadStorage<'a, SpriteRender>,
         ReadStorage<'a, Transform>,
     );
 
     fn run(
         &mut self,
-        (entities, mut visibility, hidden, hidden_prop, active, camera, transparent, transform): Self::SystemData,
+        (
+            entities,
+            mut visibility,
+            hidden,
+            hidden_prop,
+            active,
+            camera,
+            transparent,
+            sprite_order,
+            sprite_render,
+            transform,
+        ): Self::SystemData,
     ) {
         #[cfg(feature = "profiler")]
         profile_scope!("sprite_visibility_sorting_system");
@@ -101,7 +176,11 @@ impl<'a> System<'a> for SpriteVisibilitySortingSystem {
                 .filter(|(_, c)| (c - camera_centroid).dot(&camera_backward) < 0.0)
                 .map(|(entity, centroid)| Internals {
                     entity,
-                    transparent: transparent.contains(entity),
+                    ordered: transparent.contains(entity) || sprite_order.contains(entity),
+                    layer: sprite_order.get(entity).map_or(0, |order| order.layer),
+                    sprite_sheet: sprite_render
+                        .get(entity)
+                        .map_or(0, |sprite| sprite.sprite_sheet.id()),
                     centroid,
                     camera_distance: (centroid.z - camera_centroid.z).abs(),
                     from_camera: centroid - camera_centroid,
@@ -112,25 +191,61 @@ impl<'a> System<'a> for SpriteVisibilitySortingSystem {
         visibility.visible_unordered.extend(
             self.centroids
                 .iter()
-                .filter(|c| !c.transparent)
+                .filter(|c| !c.ordered)
                 .map(|c| c.entity.id()),
         );
 
-        self.transparent.clear();
-        self.transparent
-            .extend(self.centroids.drain(..).filter(|c| c.transparent));
+        self.ordered.clear();
+        self.ordered
+            .extend(self.centroids.drain(..).filter(|c| c.ordered));
 
-        // Note: Smaller Z values are placed first, so that semi-transparent sprite colors blend
-        // correctly.
-        self.transparent.sort_by(|a, b| {
-            b.camera_distance
-                .partial_cmp(&a.camera_distance)
-                .unwrap_or(Ordering::Equal)
-        });
+        // Note: Smaller Z values are placed first within a layer, so that semi-transparent sprite
+        // colors blend correctly.
+        self.ordered.sort_by(Internals::draw_order);
 
         visibility.visible_ordered.clear();
         visibility
             .visible_ordered
-            .extend(self.transparent.iter().map(|c| c.entity));
+            .extend(self.ordered.iter().map(|c| c.entity));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use amethyst_core::ecs::{Builder, World, WorldExt};
+
+    fn internals(entity: Entity, layer: i32, camera_distance: f32, sprite_sheet: u32) -> Internals {
+        Internals {
+            entity,
+            ordered: true,
+            layer,
+            sprite_sheet,
+            centroid: Point3::origin(),
+            camera_distance,
+            from_camera: Vector3::zeros(),
+        }
+    }
+
+    #[test]
+    fn sprites_are_ordered_by_layer_then_depth_then_sheet() {
+        let mut world = World::new();
+        let entities = (0..4)
+            .map(|_| world.create_entity().build())
+            .collect::<Vec<_>>();
+        let mut ordered = vec![
+            internals(entities[0], 1, 20.0, 0),
+            internals(entities[1], 0, 5.0, 0),
+            internals(entities[2], 0, 10.0, 1),
+            internals(entities[3], 0, 10.0, 0),
+        ];
+
+        ordered.sort_by(Internals::draw_order);
+
+        let order = ordered.iter().map(|c| c.entity).collect::<Vec<_>>();
+        assert_eq!(
+            order,
+            vec![entities[3], entities[2], entities[1], entities[0]]
+        );
     }
 }
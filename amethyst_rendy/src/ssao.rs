@@ -0,0 +1,47 @@
+//! Screen-space ambient occlusion configuration.
+
+use amethyst_core::ecs::{Read, SystemData, World};
+
+/// Maximum number of hemisphere samples taken per pixel by screen-space ambient occlusion.
+pub const MAX_SSAO_KERNEL_SIZE: u32 = 64;
+
+/// Resource configuring the screen-space ambient occlusion computed by `RenderSsao`.
+///
+/// All fields are read every frame. While disabled, the ambient term of `RenderPbr3D`
+/// is left unoccluded.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SsaoParams {
+    /// Darken the ambient lighting of occluded surfaces.
+    pub enabled: bool,
+    /// Radius of the sampled hemisphere around each surface point, in world units.
+    pub radius: f32,
+    /// Depth difference, in world units, below which samples don't occlude,
+    /// avoiding self-occlusion of flat surfaces.
+    pub bias: f32,
+    /// Strength of the occlusion. Values above 1 darken occluded areas further.
+    pub intensity: f32,
+    /// Number of hemisphere samples per pixel, up to `MAX_SSAO_KERNEL_SIZE`.
+    /// More samples reduce noise at a higher cost.
+    pub kernel_size: u32,
+}
+
+impl Default for SsaoParams {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            radius: 0.5,
+            bias: 0.025,
+            intensity: 1.0,
+            kernel_size: 16,
+        }
+    }
+}
+
+impl SsaoParams {
+    /// Returns the `SsaoParams` resource, or default parameters if there is none.
+    pub fn fetch(world: &World) -> Self {
+        <Option<Read<'_, SsaoParams>>>::fetch(world)
+            .map(|p| *p)
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,74 @@
+//! Screen-space reflection configuration.
+
+use amethyst_core::ecs::{Read, SystemData, World};
+
+/// Preset step counts of the ray traced by screen-space reflections.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SsrQuality {
+    /// Few steps, missing thin and distant reflected surfaces.
+    Low,
+    /// Balance between cost and accuracy.
+    Medium,
+    /// Many steps, for precise reflections at a higher cost.
+    High,
+}
+
+/// Resource configuring the screen-space reflections drawn by `RenderSsr`.
+///
+/// All fields are read every frame. Where no reflected surface is found on screen,
+/// surfaces keep the reflection of the `EnvironmentMap` lit passes already draw.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SsrParams {
+    /// Blend reflections of surfaces visible on screen into metallic and smooth surfaces.
+    pub enabled: bool,
+    /// Number of steps the reflected ray is marched through the depth buffer.
+    pub max_steps: u32,
+    /// Number of binary search steps refining the intersection once a step passed behind
+    /// a surface.
+    pub refinement_steps: u32,
+    /// Maximum length of the reflected ray, in world units.
+    pub max_distance: f32,
+    /// Depth, in world units, assumed for surfaces in the depth buffer. Rays passing further
+    /// behind a surface don't hit it.
+    pub thickness: f32,
+    /// Roughness above which surfaces don't receive screen-space reflections.
+    /// Reflections fade out as roughness approaches it.
+    pub max_roughness: f32,
+    /// Strength of the reflections.
+    pub intensity: f32,
+}
+
+impl Default for SsrParams {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_steps: 32,
+            refinement_steps: 4,
+            max_distance: 20.0,
+            thickness: 0.5,
+            max_roughness: 0.6,
+            intensity: 1.0,
+        }
+    }
+}
+
+impl SsrParams {
+    /// Returns the `SsrParams` resource, or default parameters if there is none.
+    pub fn fetch(world: &World) -> Self {
+        <Option<Read<'_, SsrParams>>>::fetch(world)
+            .map(|p| *p)
+            .unwrap_or_default()
+    }
+
+    /// Set the step counts of the reflected ray to those of a `quality` preset.
+    pub fn with_quality(mut self, quality: SsrQuality) -> Self {
+        let (max_steps, refinement_steps) = match quality {
+            SsrQuality::Low => (16, 2),
+            SsrQuality::Medium => (32, 4),
+            SsrQuality::High => (64, 8),
+        };
+        self.max_steps = max_steps;
+        self.refinement_steps = refinement_steps;
+        self
+    }
+}
@@ -0,0 +1,479 @@
+//! GPU time spent rendering each render target and render group, draw calls recorded by the
+//! render groups and GPU memory used by the renderer.
+
+use crate::bundle::Target;
+use amethyst_core::ecs::{SystemData, World, Write};
+use rendy::{
+    hal::{memory::Properties, Primitive},
+    memory::TotalMemoryUtilization,
+};
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// Time the timestamp period is measured over against the CPU clock before GPU times are
+/// published.
+const CALIBRATION_TIME: Duration = Duration::from_secs(1);
+
+/// Render group of a render target, by position in the order the groups of the target draw.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RenderGroupId {
+    /// Target the group draws to.
+    pub target: Target,
+    /// Position of the group among the groups of the target.
+    pub index: usize,
+}
+
+/// Draw calls recorded by a render group, measured by
+/// [RenderPassStats](crate::plugins::RenderPassStats).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RenderGroupStats {
+    /// Order the group was added to its target with, e.g. `RenderOrder::Opaque`.
+    pub order: i32,
+    /// Number of draw calls the group recorded.
+    pub draw_calls: u32,
+    /// Number of triangles drawn by the draw calls of the group.
+    pub triangles: u64,
+    gpu_ticks: Option<u64>,
+}
+
+/// Resource with the GPU time and draw calls of every render pass and render group, measured
+/// by [RenderPassStats](crate::plugins::RenderPassStats).
+///
+/// Timings come from timestamps written between the render groups of every pass, which are
+/// drawn in separate render passes for that, and are published once the frame has completed,
+/// a few frames later. They include any work the GPU overlaps with the group, so they are most
+/// meaningful for the heavier groups.
+///
+/// Timestamps are converted to time with the period of the GPU timestamp counter. Unless it's
+/// given, the period is measured against the CPU clock, and GPU times are only available once
+/// it was measured for a second.
+///
+/// Draw calls are counted when the render groups record them, render groups reusing their
+/// commands keep the counts of the last recording. Render groups of other crates are only
+/// counted if they report their draw calls with a [DrawCounter].
+#[derive(Debug, Default)]
+pub struct RenderStats {
+    timestamp_period: Option<f32>,
+    period_given: bool,
+    calibration: Option<(Target, u64, Instant)>,
+    timestamps: HashMap<Target, Vec<u64>>,
+    passes: HashMap<Target, u64>,
+    groups: HashMap<RenderGroupId, RenderGroupStats>,
+    recording: Option<RenderGroupId>,
+}
+
+impl RenderStats {
+    /// Create stats converting timestamps with `timestamp_period` nanoseconds per tick, or
+    /// measuring the period when `None`.
+    pub fn new(timestamp_period: Option<f32>) -> Self {
+        Self {
+            timestamp_period,
+            period_given: timestamp_period.is_some(),
+            ..Default::default()
+        }
+    }
+
+    /// Period of the GPU timestamp counter in nanoseconds per tick, if it's known.
+    pub fn timestamp_period(&self) -> Option<f32> {
+        self.timestamp_period
+    }
+
+    /// GPU time spent on the render pass of `target` in milliseconds.
+    pub fn pass_gpu_ms(&self, target: Target) -> Option<f32> {
+        self.to_ms(*self.passes.get(&target)?)
+    }
+
+    /// GPU time of every measured render pass in milliseconds.
+    pub fn passes(&self) -> impl Iterator<Item = (Target, f32)> + '_ {
+        self.passes
+            .iter()
+            .filter_map(move |(target, ticks)| Some((*target, self.to_ms(*ticks)?)))
+    }
+
+    /// Sum of the GPU time of all measured render passes in milliseconds.
+    pub fn total_gpu_ms(&self) -> f32 {
+        self.passes().map(|(_, ms)| ms).sum()
+    }
+
+    /// Draw calls of the render group `id`.
+    pub fn group(&self, id: RenderGroupId) -> Option<&RenderGroupStats> {
+        self.groups.get(&id)
+    }
+
+    /// GPU time spent on the render group `id` in milliseconds.
+    pub fn group_gpu_ms(&self, id: RenderGroupId) -> Option<f32> {
+        self.to_ms(self.groups.get(&id)?.gpu_ticks?)
+    }
+
+    /// Draw calls of every render group.
+    pub fn groups(&self) -> impl Iterator<Item = (RenderGroupId, &RenderGroupStats)> {
+        self.groups.iter().map(|(id, stats)| (*id, stats))
+    }
+
+    /// Number of draw calls recorded by all render groups.
+    pub fn total_draw_calls(&self) -> u32 {
+        self.groups.values().map(|group| group.draw_calls).sum()
+    }
+
+    /// Number of triangles drawn by all render groups.
+    pub fn total_triangles(&self) -> u64 {
+        self.groups.values().map(|group| group.triangles).sum()
+    }
+
+    fn to_ms(&self, ticks: u64) -> Option<f32> {
+        let nanos = ticks as f64 * f64::from(self.timestamp_period?);
+        Some((nanos / 1_000_000.0) as f32)
+    }
+
+    pub(crate) fn add_group(&mut self, id: RenderGroupId, order: i32) {
+        self.groups.insert(
+            id,
+            RenderGroupStats {
+                order,
+                ..Default::default()
+            },
+        );
+    }
+
+    pub(crate) fn remove_group(&mut self, id: RenderGroupId) {
+        self.groups.remove(&id);
+    }
+
+    /// Start counting the draw calls recorded by the group `id`, replacing its previous counts.
+    pub(crate) fn begin_recording(&mut self, id: RenderGroupId) {
+        if let Some(group) = self.groups.get_mut(&id) {
+            group.draw_calls = 0;
+            group.triangles = 0;
+        }
+        self.recording = Some(id);
+    }
+
+    pub(crate) fn end_recording(&mut self) {
+        self.recording = None;
+    }
+
+    fn record_draw(&mut self, triangles: u64) {
+        if let Some(group) = self.recording.and_then(|id| self.groups.get_mut(&id)) {
+            group.draw_calls += 1;
+            group.triangles += triangles;
+        }
+    }
+
+    /// Record timestamp `index` of the `last + 1` timestamps written around the render groups
+    /// of `target` in a frame, all recorded in order once the frame has completed.
+    pub(crate) fn record_timestamp(
+        &mut self,
+        target: Target,
+        index: usize,
+        last: usize,
+        ticks: u64,
+    ) {
+        self.record_timestamp_at(target, index, last, ticks, Instant::now());
+    }
+
+    fn record_timestamp_at(
+        &mut self,
+        target: Target,
+        index: usize,
+        last: usize,
+        ticks: u64,
+        now: Instant,
+    ) {
+        if index == 0 {
+            self.calibrate(target, ticks, now);
+        }
+
+        let timestamps = self.timestamps.entry(target).or_default();
+        if timestamps.len() != index {
+            // A timestamp of the frame wasn't available, skip it.
+            timestamps.clear();
+            return;
+        }
+        timestamps.push(ticks);
+        if index < last {
+            return;
+        }
+
+        let timestamps = std::mem::take(timestamps);
+        self.passes
+            .insert(target, ticks.wrapping_sub(timestamps[0]));
+        for (index, pair) in timestamps.windows(2).enumerate() {
+            if let Some(group) = self.groups.get_mut(&RenderGroupId { target, index }) {
+                group.gpu_ticks = Some(pair[1].wrapping_sub(pair[0]));
+            }
+        }
+    }
+
+    /// Measure the timestamp period from the ticks of the first timestamp of a target in
+    /// successive frames, unless it was given.
+    fn calibrate(&mut self, target: Target, ticks: u64, now: Instant) {
+        if self.period_given {
+            return;
+        }
+        match self.calibration {
+            Some((calibrated, start_ticks, start)) if calibrated == target => {
+                if ticks <= start_ticks {
+                    // The counter was reset, start over.
+                    self.calibration = Some((target, ticks, now));
+                } else if now - start >= CALIBRATION_TIME {
+                    let nanos = (now - start).as_nanos() as f64;
+                    self.timestamp_period = Some((nanos / (ticks - start_ticks) as f64) as f32);
+                }
+            }
+            Some(_) => {}
+            None => self.calibration = Some((target, ticks, now)),
+        }
+    }
+}
+
+/// Counts the draw calls a render group records into the [RenderStats] resource, when they
+/// are measured by [RenderPassStats](crate::plugins::RenderPassStats).
+///
+/// Fetch it in `draw_inline` and report every draw call recorded with the encoder.
+pub struct DrawCounter<'a> {
+    stats: Option<Write<'a, RenderStats>>,
+}
+
+impl std::fmt::Debug for DrawCounter<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DrawCounter")
+            .field("stats", &self.stats.as_deref())
+            .finish()
+    }
+}
+
+impl<'a> DrawCounter<'a> {
+    /// Fetch the counter from the world passed to the render group.
+    pub fn fetch(aux: &'a World) -> Self {
+        Self {
+            stats: <Option<Write<'a, RenderStats>>>::fetch(aux),
+        }
+    }
+
+    /// Count a draw call of `instances` instances with `vertices` vertices, or indices for
+    /// indexed draws, assembled into `primitive`s.
+    pub fn draw(&mut self, primitive: Primitive, vertices: u32, instances: u32) {
+        if let Some(stats) = &mut self.stats {
+            stats.record_draw(u64::from(triangles(primitive, vertices)) * u64::from(instances));
+        }
+    }
+
+    /// Count a draw call of `instances` quads drawn as triangle strips of 4 vertices.
+    pub fn draw_quads(&mut self, instances: u32) {
+        self.draw(Primitive::TriangleStrip, 4, instances);
+    }
+
+    /// Count an indirect draw call, whose triangles aren't known on the CPU.
+    pub fn draw_indirect(&mut self) {
+        if let Some(stats) = &mut self.stats {
+            stats.record_draw(0);
+        }
+    }
+}
+
+/// Number of triangles assembled from `vertices` vertices into `primitive`s.
+fn triangles(primitive: Primitive, vertices: u32) -> u32 {
+    match primitive {
+        Primitive::TriangleList => vertices / 3,
+        Primitive::TriangleStrip => vertices.saturating_sub(2),
+        Primitive::TriangleListAdjacency => vertices / 6,
+        Primitive::TriangleStripAdjacency => (vertices / 2).saturating_sub(2),
+        _ => 0,
+    }
+}
+
+/// Resource with the GPU memory allocated by the renderer, updated by the
+/// [RenderingSystem](crate::system::RenderingSystem) every frame.
+///
+/// Only memory allocated through rendy is counted, not memory used internally by the driver.
+/// Allocations steadily growing along with the number of textures or meshes while the scene
+/// doesn't change point at leaked asset handles.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GpuMemoryStats {
+    /// Usage of every memory heap of the device.
+    pub heaps: Vec<HeapUsage>,
+    /// Number of loaded textures.
+    pub textures: usize,
+    /// Number of loaded meshes.
+    pub meshes: usize,
+}
+
+/// Memory usage of a heap of the device.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct HeapUsage {
+    /// Size of the heap in bytes.
+    pub size: u64,
+    /// Bytes allocated from the heap, including the unused space of allocator blocks.
+    pub allocated: u64,
+    /// Bytes used by buffers and images.
+    pub used: u64,
+    /// Whether the heap is in video memory, rather than system memory visible to the GPU.
+    pub device_local: bool,
+}
+
+impl GpuMemoryStats {
+    /// Bytes allocated from all heaps.
+    pub fn allocated(&self) -> u64 {
+        self.heaps.iter().map(|heap| heap.allocated).sum()
+    }
+
+    /// Bytes allocated from the heaps in video memory.
+    pub fn device_local_allocated(&self) -> u64 {
+        self.heaps
+            .iter()
+            .filter(|heap| heap.device_local)
+            .map(|heap| heap.allocated)
+            .sum()
+    }
+
+    pub(crate) fn update(
+        &mut self,
+        utilization: &TotalMemoryUtilization,
+        textures: usize,
+        meshes: usize,
+    ) {
+        self.heaps.clear();
+        self.heaps.extend(
+            utilization
+                .heaps
+                .iter()
+                .enumerate()
+                .map(|(index, heap)| HeapUsage {
+                    size: heap.size,
+                    allocated: heap.utilization.used,
+                    used: heap.utilization.effective,
+                    device_local: utilization.types.iter().any(|ty| {
+                        ty.heap_index == index && ty.properties.contains(Properties::DEVICE_LOCAL)
+                    }),
+                }),
+        );
+        self.textures = textures;
+        self.meshes = meshes;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use amethyst_core::ecs::WorldExt;
+
+    #[test]
+    fn group_times_are_measured_between_timestamps() {
+        let mut stats = RenderStats::new(Some(2.0));
+        let group = |index| RenderGroupId {
+            target: Target::Main,
+            index,
+        };
+        stats.add_group(group(0), 100);
+        stats.add_group(group(1), 200);
+
+        // Timestamps of a frame missing its first one are skipped.
+        stats.record_timestamp(Target::Main, 1, 2, 100);
+        stats.record_timestamp(Target::Main, 2, 2, 200);
+        assert_eq!(stats.pass_gpu_ms(Target::Main), None);
+
+        stats.record_timestamp(Target::Main, 0, 2, 1_000_000);
+        stats.record_timestamp(Target::ShadowMap, 0, 1, 0);
+        stats.record_timestamp(Target::Main, 1, 2, 1_500_000);
+        stats.record_timestamp(Target::Main, 2, 2, 2_500_000);
+        stats.record_timestamp(Target::ShadowMap, 1, 1, 500_000);
+        assert_eq!(stats.pass_gpu_ms(Target::Main), Some(3.0));
+        assert_eq!(stats.pass_gpu_ms(Target::ShadowMap), Some(1.0));
+        assert_eq!(stats.total_gpu_ms(), 4.0);
+        assert_eq!(stats.group_gpu_ms(group(0)), Some(1.0));
+        assert_eq!(stats.group_gpu_ms(group(1)), Some(2.0));
+        assert_eq!(stats.group(group(1)).map(|g| g.order), Some(200));
+    }
+
+    #[test]
+    fn timestamp_period_is_measured_against_the_cpu_clock() {
+        let mut stats = RenderStats::default();
+        let start = Instant::now();
+        stats.record_timestamp_at(Target::Main, 0, 1, 1000, start);
+        stats.record_timestamp_at(Target::Main, 1, 1, 1500, start);
+        assert_eq!(stats.timestamp_period(), None);
+        assert_eq!(stats.pass_gpu_ms(Target::Main), None);
+
+        // Other targets don't disturb the measurement.
+        let later = start + Duration::from_millis(1500);
+        stats.record_timestamp_at(Target::ShadowMap, 0, 1, 1_000_000, later);
+        stats.record_timestamp_at(Target::Main, 0, 1, 1000 + 15_000_000, later);
+        assert_eq!(stats.timestamp_period(), Some(100.0));
+        assert_eq!(stats.pass_gpu_ms(Target::Main), Some(0.05));
+
+        let mut given = RenderStats::new(Some(1.0));
+        given.record_timestamp_at(Target::Main, 0, 1, 1000, start);
+        given.record_timestamp_at(Target::Main, 0, 1, 2000, later);
+        assert_eq!(given.timestamp_period(), Some(1.0));
+    }
+
+    #[test]
+    fn draws_are_counted_for_the_recording_group() {
+        let mut world = World::new();
+        world.insert(RenderStats::default());
+        let group = RenderGroupId {
+            target: Target::Main,
+            index: 0,
+        };
+        world.fetch_mut::<RenderStats>().add_group(group, 0);
+
+        let record = |world: &World| {
+            world.fetch_mut::<RenderStats>().begin_recording(group);
+            {
+                let mut draws = DrawCounter::fetch(world);
+                draws.draw(Primitive::TriangleList, 36, 2);
+                draws.draw_quads(10);
+                draws.draw(Primitive::LineList, 2, 1);
+            }
+            world.fetch_mut::<RenderStats>().end_recording();
+        };
+        record(&world);
+        record(&world);
+        // Draws outside of a group aren't counted.
+        DrawCounter::fetch(&world).draw_quads(1);
+
+        let stats = world.fetch::<RenderStats>();
+        assert_eq!(
+            stats.group(group).map(|g| (g.draw_calls, g.triangles)),
+            Some((3, 44))
+        );
+        assert_eq!((stats.total_draw_calls(), stats.total_triangles()), (3, 44));
+    }
+
+    #[test]
+    fn memory_is_summed_over_heaps() {
+        use rendy::memory::{MemoryHeapUtilization, MemoryTypeUtilization, MemoryUtilization};
+
+        let heap = |size, used, effective| MemoryHeapUtilization {
+            utilization: MemoryUtilization { used, effective },
+            size,
+        };
+        let ty = |heap_index, properties| MemoryTypeUtilization {
+            utilization: MemoryUtilization {
+                used: 0,
+                effective: 0,
+            },
+            properties,
+            heap_index,
+        };
+        let utilization = TotalMemoryUtilization {
+            types: vec![
+                ty(1, Properties::CPU_VISIBLE | Properties::COHERENT),
+                ty(0, Properties::DEVICE_LOCAL),
+            ],
+            heaps: vec![heap(4096, 1024, 800), heap(8192, 256, 200)],
+        };
+
+        let mut stats = GpuMemoryStats::default();
+        stats.update(&utilization, 3, 2);
+        assert_eq!(stats.heaps.len(), 2);
+        assert!(stats.heaps[0].device_local);
+        assert!(!stats.heaps[1].device_local);
+        assert_eq!(stats.heaps[0].used, 800);
+        assert_eq!(stats.allocated(), 1280);
+        assert_eq!(stats.device_local_allocated(), 1024);
+        assert_eq!((stats.textures, stats.meshes), (3, 2));
+    }
+}
@@ -0,0 +1,137 @@
+//! Ambient occlusion submodule for sampling the image rendered by `RenderSsao`.
+use crate::{
+    rendy::{
+        command::{QueueId, RenderPassEncoder},
+        factory::{Factory, ImageState},
+        graph::{GraphContext, NodeImage},
+        hal::{self, device::Device, format::Swizzle, pso::Descriptor},
+        resource::{
+            DescriptorSet, DescriptorSetLayout, Escape, Filter, Handle as RendyHandle, ImageView,
+            ImageViewInfo, Sampler, SamplerInfo, ViewKind, WrapMode,
+        },
+        texture::{pixel::Rgba8Unorm, Texture as RendyTexture, TextureBuilder},
+    },
+    types::Backend,
+    util,
+};
+
+#[derive(Debug)]
+enum OcclusionSource<B: Backend> {
+    Image(Escape<ImageView<B>>, RendyHandle<Sampler<B>>),
+    Fallback(RendyTexture<B>),
+}
+
+/// Submodule binding the screen-space ambient occlusion image.
+///
+/// A white texture is bound when no occlusion image is passed to the render group.
+#[derive(Debug)]
+pub struct AmbientOcclusionSub<B: Backend> {
+    layout: RendyHandle<DescriptorSetLayout<B>>,
+    set: Escape<DescriptorSet<B>>,
+    _source: OcclusionSource<B>,
+}
+
+impl<B: Backend> AmbientOcclusionSub<B> {
+    /// Create a new `AmbientOcclusionSub` sampling `image`, or uploading
+    /// a white fallback texture on `queue` when there is none.
+    pub fn new(
+        ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        queue: QueueId,
+        image: Option<&NodeImage>,
+    ) -> Result<Self, failure::Error> {
+        let layout: RendyHandle<DescriptorSetLayout<B>> = set_layout! {
+            factory,
+            [1] CombinedImageSampler hal::pso::ShaderStageFlags::FRAGMENT
+        };
+
+        let source = match image {
+            Some(image) => {
+                let occlusion = ctx
+                    .get_image(image.id)
+                    .ok_or_else(|| failure::format_err!("Occlusion image is not available"))?;
+                let view = factory.create_image_view(
+                    occlusion.clone(),
+                    ImageViewInfo {
+                        view_kind: ViewKind::D2,
+                        format: occlusion.format(),
+                        swizzle: Swizzle::NO,
+                        range: hal::image::SubresourceRange {
+                            aspects: hal::format::Aspects::COLOR,
+                            levels: 0..1,
+                            layers: 0..1,
+                        },
+                    },
+                )?;
+                let sampler =
+                    factory.get_sampler(SamplerInfo::new(Filter::Nearest, WrapMode::Clamp))?;
+                OcclusionSource::Image(view, sampler)
+            }
+            None => {
+                let white = TextureBuilder::new()
+                    .with_kind(hal::image::Kind::D2(1, 1, 1, 1))
+                    .with_view_kind(ViewKind::D2)
+                    .with_data_width(1)
+                    .with_data_height(1)
+                    .with_sampler_info(SamplerInfo::new(Filter::Nearest, WrapMode::Clamp))
+                    .with_data(vec![Rgba8Unorm { repr: [255; 4] }])
+                    .build(
+                        ImageState {
+                            queue,
+                            stage: hal::pso::PipelineStage::FRAGMENT_SHADER,
+                            access: hal::image::Access::SHADER_READ,
+                            layout: hal::image::Layout::ShaderReadOnlyOptimal,
+                        },
+                        factory,
+                    )?;
+                OcclusionSource::Fallback(white)
+            }
+        };
+
+        let (view, sampler) = match &source {
+            OcclusionSource::Image(view, sampler) => (view.raw(), sampler.raw()),
+            OcclusionSource::Fallback(texture) => (texture.view().raw(), texture.sampler().raw()),
+        };
+        let set = factory.create_descriptor_set(layout.clone())?;
+        unsafe {
+            factory.write_descriptor_sets(Some(util::desc_write(
+                set.raw(),
+                0,
+                Descriptor::CombinedImageSampler(
+                    view,
+                    hal::image::Layout::ShaderReadOnlyOptimal,
+                    sampler,
+                ),
+            )));
+        }
+
+        Ok(Self {
+            layout,
+            set,
+            _source: source,
+        })
+    }
+
+    /// Returns the raw `DescriptorSetLayout` of the occlusion image
+    pub fn raw_layout(&self) -> &B::DescriptorSetLayout {
+        self.layout.raw()
+    }
+
+    /// Bind the occlusion image to set `set_id`.
+    #[inline]
+    pub fn bind(
+        &self,
+        pipeline_layout: &B::PipelineLayout,
+        set_id: u32,
+        encoder: &mut RenderPassEncoder<'_, B>,
+    ) {
+        unsafe {
+            encoder.bind_graphics_descriptor_sets(
+                pipeline_layout,
+                set_id,
+                Some(self.set.raw()),
+                std::iter::empty(),
+            );
+        }
+    }
+}
@@ -1,7 +1,9 @@
 //! Environment submodule for shared environmental descriptor set data.
 //! Fetches and sets projection and lighting descriptor set information.
 use crate::{
+    environment_map::EnvironmentMap,
     light::Light,
+    light_cluster::{LightClusters, CLUSTER_COUNT},
     pod::{self, IntoPod},
     rendy::{
         command::RenderPassEncoder,
@@ -10,12 +12,12 @@ use crate::{
         memory::Write as _,
         resource::{Buffer, DescriptorSet, DescriptorSetLayout, Escape, Handle as RendyHandle},
     },
-    submodules::gather::{AmbientGatherer, CameraGatherer},
+    submodules::gather::{AmbientGatherer, CameraGatherer, ShadowGatherer},
     types::Backend,
     util::{self, TapCountIter},
 };
 use amethyst_core::{
-    ecs::{Join, ReadStorage, SystemData, World},
+    ecs::{Entities, Join, Read, ReadStorage, SystemData, World},
     math::{convert, Vector3},
     transform::Transform,
 };
@@ -24,7 +26,7 @@ use glsl_layout::*;
 #[cfg(feature = "profiler")]
 use thread_profiler::profile_scope;
 
-const MAX_POINT_LIGHTS: usize = 128;
+const MAX_POINT_LIGHTS: usize = 256;
 const MAX_DIR_LIGHTS: usize = 16;
 const MAX_SPOT_LIGHTS: usize = 128;
 
@@ -43,6 +45,7 @@ pub struct EnvironmentSub<B: Backend> {
 #[derive(Debug)]
 struct PerImageEnvironmentSub<B: Backend> {
     buffer: Option<Escape<Buffer<B>>>,
+    clusters: Option<Escape<Buffer<B>>>,
     set: Escape<DescriptorSet<B>>,
 }
 
@@ -54,7 +57,12 @@ impl<B: Backend> EnvironmentSub<B> {
         flags: [hal::pso::ShaderStageFlags; 2],
     ) -> Result<Self, failure::Error> {
         Ok(Self {
-            layout: set_layout! {factory, [1] UniformBuffer flags[0], [4] UniformBuffer flags[1]},
+            layout: set_layout! {
+                factory,
+                [1] UniformBuffer flags[0],
+                [5] UniformBuffer flags[1],
+                [1] StorageBuffer flags[1]
+            },
             per_image: Vec::new(),
         })
     }
@@ -96,6 +104,7 @@ impl<B: Backend> PerImageEnvironmentSub<B> {
     fn new(factory: &Factory<B>, layout: &RendyHandle<DescriptorSetLayout<B>>) -> Self {
         Self {
             buffer: None,
+            clusters: None,
             set: factory.create_descriptor_set(layout.clone()).unwrap(),
         }
     }
@@ -128,6 +137,7 @@ impl<B: Backend> PerImageEnvironmentSub<B> {
         let plight_buf_size = util::align_size::<pod::PointLight>(align, MAX_POINT_LIGHTS);
         let dlight_buf_size = util::align_size::<pod::DirectionalLight>(align, MAX_DIR_LIGHTS);
         let slight_buf_size = util::align_size::<pod::SpotLight>(align, MAX_SPOT_LIGHTS);
+        let spot_shadows_size = util::align_size::<pod::SpotShadows>(align, 1);
 
         let projview_range = 0..projview_size;
         let env_range = util::next_range(&projview_range, env_buf_size);
@@ -135,7 +145,9 @@ impl<B: Backend> PerImageEnvironmentSub<B> {
         let dlight_range = util::next_range(&plight_range, dlight_buf_size);
         let slight_range = util::next_range(&dlight_range, slight_buf_size);
 
-        let whole_range = 0..slight_range.end;
+        let spot_shadows_range = util::next_range(&slight_range, spot_shadows_size);
+
+        let whole_range = 0..spot_shadows_range.end;
 
         let new_buffer = util::ensure_buffer(
             &factory,
@@ -156,6 +168,8 @@ impl<B: Backend> PerImageEnvironmentSub<B> {
                 let desc_plight = Descriptor::Buffer(buffer, opt_range(plight_range.clone()));
                 let desc_dlight = Descriptor::Buffer(buffer, opt_range(dlight_range.clone()));
                 let desc_slight = Descriptor::Buffer(buffer, opt_range(slight_range.clone()));
+                let desc_spot_shadows =
+                    Descriptor::Buffer(buffer, opt_range(spot_shadows_range.clone()));
 
                 unsafe {
                     factory.write_descriptor_sets(vec![
@@ -164,6 +178,7 @@ impl<B: Backend> PerImageEnvironmentSub<B> {
                         desc_write(env_set, 2, desc_plight),
                         desc_write(env_set, 3, desc_dlight),
                         desc_write(env_set, 4, desc_slight),
+                        desc_write(env_set, 5, desc_spot_shadows),
                     ]);
                 }
             }
@@ -171,6 +186,9 @@ impl<B: Backend> PerImageEnvironmentSub<B> {
             let CameraGatherer {
                 camera_position,
                 projview,
+                projection,
+                view,
+                ..
             } = CameraGatherer::gather(world);
 
             let mut mapped = buffer.map(factory, whole_range.clone()).unwrap();
@@ -183,29 +201,51 @@ impl<B: Backend> PerImageEnvironmentSub<B> {
                 point_light_count: 0,
                 directional_light_count: 0,
                 spot_light_count: 0,
+                environment_map_intensity: <Option<Read<'_, EnvironmentMap>>>::fetch(world)
+                    .map_or(0.0, |environment_map| environment_map.intensity),
             }
             .std140();
 
-            let (lights, transforms) =
-                <(ReadStorage<'_, Light>, ReadStorage<'_, Transform>)>::fetch(world);
+            let (entities, lights, transforms) = <(
+                Entities<'_>,
+                ReadStorage<'_, Light>,
+                ReadStorage<'_, Transform>,
+            )>::fetch(world);
 
-            let point_lights = (&lights, &transforms)
-                .join()
+            // Shadow casting point lights go first, in the order of their shadow maps.
+            let shadow_casters = ShadowGatherer::point_casters(world);
+            let point_lights = shadow_casters
+                .iter()
+                .filter_map(|(entity, _)| Some((lights.get(*entity)?, transforms.get(*entity)?)))
+                .chain(
+                    (&entities, &lights, &transforms)
+                        .join()
+                        .filter(|(entity, _, _)| {
+                            !shadow_casters.iter().any(|(caster, _)| caster == entity)
+                        })
+                        .map(|(_, light, transform)| (light, transform)),
+                )
                 .filter_map(|(light, transform)| match light {
-                    Light::Point(light) => Some(
-                        pod::PointLight {
-                            position: convert::<_, Vector3<f32>>(
-                                transform.global_matrix().column(3).xyz(),
-                            )
-                            .into_pod(),
-                            color: light.color.into_pod(),
-                            intensity: light.intensity,
-                        }
-                        .std140(),
-                    ),
+                    Light::Point(light) => {
+                        let position =
+                            convert::<_, Vector3<f32>>(transform.global_matrix().column(3).xyz());
+                        Some((
+                            position,
+                            light.radius,
+                            pod::PointLight {
+                                position: position.into_pod(),
+                                radius: light.radius,
+                                color: light.color.into_pod(),
+                                intensity: light.intensity,
+                                smoothness: light.smoothness.max(1.0),
+                            }
+                            .std140(),
+                        ))
+                    }
                     _ => None,
                 })
-                .take(MAX_POINT_LIGHTS);
+                .take(MAX_POINT_LIGHTS)
+                .collect::<Vec<_>>();
 
             let dir_lights = lights
                 .join()
@@ -222,35 +262,64 @@ impl<B: Backend> PerImageEnvironmentSub<B> {
                 })
                 .take(MAX_DIR_LIGHTS);
 
-            let spot_lights = (&lights, &transforms)
+            let spot_casters = ShadowGatherer::spot_casters(world);
+            let spot_shadows = ShadowGatherer::spot_shadows(world, &spot_casters);
+            let spot_lights = (&entities, &lights, &transforms)
                 .join()
-                .filter_map(|(light, transform)| {
+                .filter_map(|(entity, light, transform)| {
                     if let Light::Spot(ref light) = *light {
-                        Some(
+                        let shadow_index = spot_casters
+                            .iter()
+                            .position(|(caster, _)| *caster == entity)
+                            .map_or(-1, |index| index as i32);
+                        let position =
+                            convert::<_, Vector3<f32>>(transform.global_matrix().column(3).xyz());
+                        Some((
+                            position,
+                            light.range,
                             pod::SpotLight {
-                                position: convert::<_, Vector3<f32>>(
-                                    transform.global_matrix().column(3).xyz(),
-                                )
-                                .into_pod(),
+                                position: position.into_pod(),
                                 color: light.color.into_pod(),
                                 direction: light.direction.into_pod(),
                                 angle: light.angle.cos(),
                                 intensity: light.intensity,
                                 range: light.range,
                                 smoothness: light.smoothness,
+                                shadow_index,
+                                shadow_bias: light.shadow_bias,
+                                shadow_softness: light.shadow_softness,
                             }
                             .std140(),
-                        )
+                        ))
                     } else {
                         None
                     }
                 })
-                .take(MAX_SPOT_LIGHTS);
+                .take(MAX_SPOT_LIGHTS)
+                .collect::<Vec<_>>();
+
+            // Spot lights are indexed after the point lights.
+            let clusters = LightClusters::assign(
+                &view,
+                &projection,
+                point_lights
+                    .iter()
+                    .map(|(position, radius, _)| (*position, *radius))
+                    .chain(
+                        spot_lights
+                            .iter()
+                            .map(|(position, range, _)| (*position, *range)),
+                    ),
+            );
+            write_clusters(factory, &mut self.clusters, &self.set, &clusters);
 
             use util::{usize_range, write_into_slice};
             write_into_slice(
                 &mut dst_slice[usize_range(plight_range)],
-                point_lights.tap_count(&mut env.point_light_count),
+                point_lights
+                    .into_iter()
+                    .map(|(_, _, light)| light)
+                    .tap_count(&mut env.point_light_count),
             );
             write_into_slice(
                 &mut dst_slice[usize_range(dlight_range)],
@@ -258,12 +327,58 @@ impl<B: Backend> PerImageEnvironmentSub<B> {
             );
             write_into_slice(
                 &mut dst_slice[usize_range(slight_range)],
-                spot_lights.tap_count(&mut env.spot_light_count),
+                spot_lights
+                    .into_iter()
+                    .map(|(_, _, light)| light)
+                    .tap_count(&mut env.spot_light_count),
             );
             write_into_slice(&mut dst_slice[usize_range(projview_range)], Some(projview));
             write_into_slice(&mut dst_slice[usize_range(env_range)], Some(env));
+            write_into_slice(
+                &mut dst_slice[usize_range(spot_shadows_range)],
+                Some(spot_shadows),
+            );
         }
 
         new_buffer
     }
 }
+
+/// Writes the light ranges of all clusters, followed by the light indices they refer to.
+fn write_clusters<B: Backend>(
+    factory: &Factory<B>,
+    buffer: &mut Option<Escape<Buffer<B>>>,
+    set: &DescriptorSet<B>,
+    clusters: &LightClusters,
+) {
+    let ranges_size = (CLUSTER_COUNT * std::mem::size_of::<[u32; 2]>()) as u64;
+    let indices_size = (clusters.indices().len().max(1) * std::mem::size_of::<u32>()) as u64;
+    let whole_range = 0..ranges_size + indices_size;
+
+    let new_buffer = util::ensure_buffer(
+        factory,
+        buffer,
+        hal::buffer::Usage::STORAGE,
+        rendy::memory::Dynamic,
+        whole_range.end,
+    )
+    .unwrap();
+    if let Some(buffer) = buffer.as_mut() {
+        if new_buffer {
+            unsafe {
+                factory.write_descriptor_sets(Some(util::desc_write(
+                    set.raw(),
+                    6,
+                    Descriptor::Buffer(buffer.raw(), None..None),
+                )));
+            }
+        }
+
+        let mut mapped = buffer.map(factory, whole_range.clone()).unwrap();
+        let mut writer = unsafe { mapped.write::<u8>(factory, whole_range).unwrap() };
+        let dst_slice = unsafe { writer.slice() };
+        let (ranges, indices) = dst_slice.split_at_mut(ranges_size as usize);
+        util::write_into_slice(ranges, clusters.ranges().iter().copied());
+        util::write_into_slice(indices, clusters.indices().iter().copied());
+    }
+}
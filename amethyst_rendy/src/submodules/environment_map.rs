@@ -0,0 +1,290 @@
+//! Environment map submodule for image-based lighting.
+use crate::{
+    environment_map::{cube_texture_builder, EnvironmentMap, HdrCubemap, SPECULAR_LEVELS},
+    light_probe::{LightProbeGrid, LightProbes, LIGHT_PROBE_HEADER_SIZE},
+    pod::ReflectionProbeArgs,
+    reflection_probe::{ReflectionProbes, MAX_REFLECTION_PROBES},
+    rendy::{
+        command::{QueueId, RenderPassEncoder},
+        factory::{Factory, ImageState},
+        hal::{self, device::Device},
+        memory::Dynamic,
+        resource::{
+            Buffer, BufferInfo, DescriptorSet, DescriptorSetLayout, Escape, Handle as RendyHandle,
+        },
+        texture::Texture as RendyTexture,
+    },
+    types::{Backend, Texture},
+    util,
+};
+use amethyst_assets::{AssetStorage, Handle};
+use amethyst_core::ecs::{Read, SystemData, World};
+use glsl_layout::AsStd140;
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+/// Irradiance and specular textures with their versions.
+type LoadedMaps = (Handle<Texture>, Handle<Texture>, (u32, u32));
+
+/// Textures and probes written to the descriptor set.
+#[derive(Debug, Default, PartialEq)]
+struct BoundMaps {
+    environment: Option<LoadedMaps>,
+    probes: Option<(LoadedMaps, u32)>,
+    light_probes: Option<(Handle<LightProbeGrid>, u32, f32)>,
+}
+
+/// Submodule binding the irradiance and specular cube maps of the `EnvironmentMap` resource,
+/// along with those of the captured reflection probes and the grid of `LightProbes`.
+///
+/// Black cube maps are bound while there is no loaded environment map or reflection probe,
+/// and an empty grid while there are no loaded light probes.
+#[derive(Debug)]
+pub struct EnvironmentMapSub<B: Backend> {
+    layout: RendyHandle<DescriptorSetLayout<B>>,
+    set: Escape<DescriptorSet<B>>,
+    probes: Escape<Buffer<B>>,
+    light_probes: Escape<Buffer<B>>,
+    fallback: [RendyTexture<B>; 3],
+    bound: Option<BoundMaps>,
+}
+
+impl<B: Backend> EnvironmentMapSub<B> {
+    /// Create a new `EnvironmentMapSub`, uploading fallback cube maps on `queue`.
+    pub fn new(factory: &mut Factory<B>, queue: QueueId) -> Result<Self, failure::Error> {
+        let layout: RendyHandle<DescriptorSetLayout<B>> = set_layout! {
+            factory,
+            [4] CombinedImageSampler hal::pso::ShaderStageFlags::FRAGMENT,
+            [1] UniformBuffer hal::pso::ShaderStageFlags::FRAGMENT,
+            [1] StorageBuffer hal::pso::ShaderStageFlags::FRAGMENT
+        };
+
+        let black = HdrCubemap::from_fn(1, |_| [0.0; 3]);
+        let state = ImageState {
+            queue,
+            stage: hal::pso::PipelineStage::FRAGMENT_SHADER,
+            access: hal::image::Access::SHADER_READ,
+            layout: hal::image::Layout::ShaderReadOnlyOptimal,
+        };
+        let specular = vec![black; SPECULAR_LEVELS];
+        let irradiance = cube_texture_builder(&specular[..1]).build(state, factory)?;
+        let probe_irradiance = cube_texture_builder(&specular[..1])
+            .with_view_kind(hal::image::ViewKind::CubeArray)
+            .build(state, factory)?;
+        let specular = cube_texture_builder(&specular).build(state, factory)?;
+
+        let sub = Self {
+            set: factory.create_descriptor_set(layout.clone())?,
+            probes: create_probe_buffer(factory, &[])?,
+            light_probes: create_light_probe_buffer(factory, None)?,
+            layout,
+            fallback: [irradiance, specular, probe_irradiance],
+            bound: None,
+        };
+        sub.write_set(factory, None, None);
+        Ok(sub)
+    }
+
+    /// Returns the raw `DescriptorSetLayout` of the environment cube maps
+    pub fn raw_layout(&self) -> &B::DescriptorSetLayout {
+        self.layout.raw()
+    }
+
+    /// Picks up a new or reloaded `EnvironmentMap` once both of its textures are loaded,
+    /// the textures of newly captured reflection probes and newly loaded light probes.
+    ///
+    /// Returns true if the bound cube maps changed.
+    pub fn process(&mut self, factory: &Factory<B>, world: &World) -> bool {
+        #[cfg(feature = "profiler")]
+        profile_scope!("process");
+
+        let (environment_map, probes, light_probes, grid_storage, tex_storage) =
+            <(
+                Option<Read<'_, EnvironmentMap>>,
+                Option<Read<'_, ReflectionProbes>>,
+                Option<Read<'_, LightProbes>>,
+                Option<Read<'_, AssetStorage<LightProbeGrid>>>,
+                Read<'_, AssetStorage<Texture>>,
+            )>::fetch(world);
+
+        let loaded = |irradiance: &Handle<Texture>, specular: &Handle<Texture>| {
+            let (irradiance_texture, irradiance_version) =
+                tex_storage.get_with_version(irradiance)?;
+            let (specular_texture, specular_version) = tex_storage.get_with_version(specular)?;
+            Some((
+                (irradiance_texture, specular_texture),
+                (
+                    irradiance.clone(),
+                    specular.clone(),
+                    (*irradiance_version, *specular_version),
+                ),
+            ))
+        };
+        let environment = environment_map
+            .as_ref()
+            .and_then(|map| loaded(&map.irradiance, &map.specular));
+        let probe_maps = probes.as_ref().and_then(|probes| {
+            let (irradiance, specular) = probes.textures()?;
+            loaded(irradiance, specular)
+        });
+        let light_probe_grid = light_probes.as_ref().and_then(|light_probes| {
+            let (grid, version) = grid_storage
+                .as_ref()?
+                .get_with_version(&light_probes.grid)?;
+            Some((
+                grid,
+                (light_probes.grid.clone(), *version, light_probes.intensity),
+            ))
+        });
+
+        let bound = BoundMaps {
+            environment: environment.as_ref().map(|(_, maps)| maps.clone()),
+            probes: probes
+                .as_ref()
+                .and_then(|probes| Some((probe_maps.as_ref()?.1.clone(), probes.version()))),
+            light_probes: light_probe_grid.as_ref().map(|(_, bound)| bound.clone()),
+        };
+        if self.bound.as_ref() == Some(&bound) {
+            return false;
+        }
+
+        let args = match (&probe_maps, &probes) {
+            (Some(_), Some(probes)) => probes.args(),
+            _ => Vec::new(),
+        };
+        self.set = factory.create_descriptor_set(self.layout.clone()).unwrap();
+        self.probes = create_probe_buffer(factory, &args).unwrap();
+        self.light_probes = create_light_probe_buffer(
+            factory,
+            light_probe_grid.map(|(grid, (_, _, intensity))| (grid, intensity)),
+        )
+        .unwrap();
+        self.write_set(
+            factory,
+            environment.map(|(textures, _)| textures),
+            probe_maps.map(|(textures, _)| textures),
+        );
+        self.bound = Some(bound);
+        true
+    }
+
+    /// Write the given irradiance and specular textures of the environment map and the
+    /// reflection probes to the descriptor set, or fallbacks in their place.
+    fn write_set<'a>(
+        &'a self,
+        factory: &Factory<B>,
+        environment: Option<(&'a Texture, &'a Texture)>,
+        probes: Option<(&'a Texture, &'a Texture)>,
+    ) {
+        let layout = hal::image::Layout::ShaderReadOnlyOptimal;
+        let fallback = |index: usize| {
+            let texture: &RendyTexture<B> = &self.fallback[index];
+            hal::pso::Descriptor::CombinedImageSampler(
+                texture.view().raw(),
+                layout,
+                texture.sampler().raw(),
+            )
+        };
+        let textures = |textures: Option<(&'a Texture, &'a Texture)>, fallbacks: [usize; 2]| {
+            textures
+                .and_then(|(irradiance, specular)| {
+                    Some([
+                        util::texture_desc(irradiance, layout)?,
+                        util::texture_desc(specular, layout)?,
+                    ])
+                })
+                .unwrap_or_else(|| [fallback(fallbacks[0]), fallback(fallbacks[1])])
+        };
+
+        let [irradiance, specular] = textures(environment, [0, 1]);
+        let [probe_irradiance, probe_specular] = textures(probes, [2, 1]);
+        let descriptors = vec![
+            irradiance,
+            specular,
+            probe_irradiance,
+            probe_specular,
+            hal::pso::Descriptor::Buffer(self.probes.raw(), None..None),
+            hal::pso::Descriptor::Buffer(self.light_probes.raw(), None..None),
+        ];
+        unsafe {
+            factory.write_descriptor_sets(
+                descriptors
+                    .into_iter()
+                    .enumerate()
+                    .map(|(binding, desc)| util::desc_write(self.set.raw(), binding as u32, desc))
+                    .collect::<Vec<_>>(),
+            );
+        }
+    }
+
+    /// Bind the environment cube maps to set `set_id`.
+    #[inline]
+    pub fn bind(
+        &self,
+        pipeline_layout: &B::PipelineLayout,
+        set_id: u32,
+        encoder: &mut RenderPassEncoder<'_, B>,
+    ) {
+        unsafe {
+            encoder.bind_graphics_descriptor_sets(
+                pipeline_layout,
+                set_id,
+                Some(self.set.raw()),
+                std::iter::empty(),
+            );
+        }
+    }
+}
+
+/// Create a uniform buffer with the arguments of reflection probes, as declared by
+/// `reflection_probe.frag`.
+fn create_probe_buffer<B: Backend>(
+    factory: &Factory<B>,
+    args: &[ReflectionProbeArgs],
+) -> Result<Escape<Buffer<B>>, failure::Error> {
+    let array_size = util::align_size::<ReflectionProbeArgs>(16, MAX_REFLECTION_PROBES) as usize;
+    let mut data = vec![0u8; array_size + 16];
+    util::write_into_slice(
+        &mut data[..array_size],
+        args.iter().map(|args| args.std140()),
+    );
+    data[array_size..array_size + 4].copy_from_slice(&(args.len() as i32).to_ne_bytes());
+
+    let mut buffer = factory.create_buffer(
+        BufferInfo {
+            size: data.len() as u64,
+            usage: hal::buffer::Usage::UNIFORM,
+        },
+        Dynamic,
+    )?;
+    unsafe {
+        factory.upload_visible_buffer(&mut buffer, 0, &data)?;
+    }
+    Ok(buffer)
+}
+
+/// Create a storage buffer with the probes of a light probe grid scaled by an intensity, as
+/// declared by `light_probe.frag`, or without any probe.
+fn create_light_probe_buffer<B: Backend>(
+    factory: &Factory<B>,
+    grid: Option<(&LightProbeGrid, f32)>,
+) -> Result<Escape<Buffer<B>>, failure::Error> {
+    // Keep room for one coefficient, as buffers cannot be empty past their header.
+    let data = grid.map_or_else(
+        || vec![0u8; LIGHT_PROBE_HEADER_SIZE + 16],
+        |(grid, intensity)| grid.buffer_data(intensity),
+    );
+
+    let mut buffer = factory.create_buffer(
+        BufferInfo {
+            size: data.len() as u64,
+            usage: hal::buffer::Usage::STORAGE,
+        },
+        Dynamic,
+    )?;
+    unsafe {
+        factory.upload_visible_buffer(&mut buffer, 0, &data)?;
+    }
+    Ok(buffer)
+}
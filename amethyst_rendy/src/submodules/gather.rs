@@ -1,15 +1,22 @@
 //! Helper gatherer structures for collecting information about the world.
 use crate::{
-    camera::{ActiveCamera, Camera},
+    camera::{ActiveCamera, Camera, ClipPlane},
+    light::Light,
     pod::{self, IntoPod},
     resources::AmbientColor,
+    shadow::{
+        directional_cascades, point_light_faces, spot_light_proj_view, ShadowParams,
+        MAX_POINT_SHADOWS, MAX_SHADOW_CASCADES, MAX_SPOT_SHADOWS,
+    },
+    taa::TaaJitter,
 };
 use amethyst_core::{
     ecs::{Entities, Entity, Join, Read, ReadStorage, SystemData, World},
-    math::{convert, Matrix4, Vector3},
+    math::{convert, Matrix4, Point3, Vector2, Vector3},
     transform::Transform,
 };
 use glsl_layout::*;
+use std::cmp::Ordering;
 
 #[cfg(feature = "profiler")]
 use thread_profiler::profile_scope;
@@ -23,6 +30,13 @@ pub struct CameraGatherer {
     pub camera_position: vec3,
     /// Fetched camera projection matrix.
     pub projview: Std140<pod::ViewArgs>,
+    /// Sub-pixel offset applied to the projection for temporal anti-aliasing,
+    /// in normalized device coordinates. Zero when it is not active.
+    pub jitter: Vector2<f32>,
+    /// Projection matrix of `projview`, including the jitter offset.
+    pub projection: Matrix4<f32>,
+    /// View matrix of `projview`.
+    pub view: Matrix4<f32>,
 }
 
 impl CameraGatherer {
@@ -64,55 +78,71 @@ impl CameraGatherer {
     /// the appropriate camera to use for projection, and returns the camera position and extracted
     /// projection matrix.
     ///
-    /// The matrix returned is the camera's `Projection` matrix and the camera `Transform::global_view_matrix`
+    /// The matrix returned is the camera's `Projection` matrix and the camera `Transform::global_view_matrix`.
+    /// The projection is offset by the `TaaJitter` of this frame while temporal anti-aliasing is active.
+    /// The view arguments carry the `ClipPlane` of the camera, zero if it has none.
     pub fn gather(world: &World) -> Self {
         #[cfg(feature = "profiler")]
         profile_scope!("gather_cameras");
 
-        let (active_camera, cameras, transforms) = <(
+        let (active_camera, cameras, transforms, clip_planes) = <(
             Read<'_, ActiveCamera>,
             ReadStorage<'_, Camera>,
             ReadStorage<'_, Transform>,
+            ReadStorage<'_, ClipPlane>,
         )>::fetch(world);
 
         let defcam = Camera::standard_2d(1.0, 1.0);
         let identity = Transform::default();
 
-        let (camera, transform) = active_camera
+        let (camera, transform, clip_plane) = active_camera
             .entity
             .as_ref()
             .and_then(|ac| {
-                cameras
-                    .get(*ac)
-                    .map(|camera| (camera, transforms.get(*ac).unwrap_or(&identity)))
+                cameras.get(*ac).map(|camera| {
+                    (
+                        camera,
+                        transforms.get(*ac).unwrap_or(&identity),
+                        clip_planes.get(*ac),
+                    )
+                })
             })
             .unwrap_or_else(|| {
-                (&cameras, &transforms)
+                (&cameras, &transforms, clip_planes.maybe())
                     .join()
                     .next()
-                    .unwrap_or((&defcam, &identity))
+                    .unwrap_or((&defcam, &identity, None))
             });
 
         let camera_position =
             convert::<_, Vector3<f32>>(transform.global_matrix().column(3).xyz()).into_pod();
 
-        let proj = &camera.matrix;
+        let jitter = TaaJitter::offset(world).unwrap_or_else(Vector2::zeros);
+        let proj = Matrix4::new_translation(&Vector3::new(jitter.x, jitter.y, 0.0)) * camera.matrix;
         let view = transform.global_view_matrix();
 
-        let proj_view: [[f32; 4]; 4] = ((*proj) * view).into();
-        let proj: [[f32; 4]; 4] = (*proj).into();
-        let view: [[f32; 4]; 4] = convert::<_, Matrix4<f32>>(transform.global_view_matrix()).into();
+        let proj_view: [[f32; 4]; 4] = (proj * view).into();
+        let projection = proj;
+        let view_matrix = view;
+        let proj: [[f32; 4]; 4] = proj.into();
+        let view: [[f32; 4]; 4] = view.into();
 
         let projview = pod::ViewArgs {
             proj: proj.into(),
             view: view.into(),
             proj_view: proj_view.into(),
+            clip_plane: clip_plane
+                .map_or([0.0; 4], |plane| plane.to_vector().into())
+                .into(),
         }
         .std140();
 
         Self {
             camera_position,
             projview,
+            jitter,
+            projection,
+            view: view_matrix,
         }
     }
 }
@@ -130,3 +160,205 @@ impl AmbientGatherer {
         })
     }
 }
+
+/// Helper `ShadowGatherer` for computing light-space matrices of shadow casting lights.
+#[derive(Debug)]
+pub struct ShadowGatherer {
+    /// Shadow configuration, default if no `ShadowParams` resource exists.
+    pub params: ShadowParams,
+    /// Projection-view matrices of the directional light cascades.
+    pub cascades: Vec<Matrix4<f32>>,
+    /// Projection-view matrices of cube map faces of each shadow casting point light.
+    pub point_faces: Vec<[Matrix4<f32>; 6]>,
+    /// Projection-view matrices of each shadow casting spot light.
+    pub spot_proj_view: Vec<Matrix4<f32>>,
+}
+
+impl ShadowGatherer {
+    /// Collect `ShadowParams`, the active camera and shadow casting lights
+    /// and compute light-space matrices for the directional light cascades, point light faces
+    /// and spot lights.
+    ///
+    /// There are no cascades when there is no camera or directional light.
+    pub fn gather(world: &World) -> Self {
+        #[cfg(feature = "profiler")]
+        profile_scope!("gather_shadows");
+
+        let params = <Option<Read<'_, ShadowParams>>>::fetch(world)
+            .map(|p| *p)
+            .unwrap_or_default();
+
+        let camera = CameraGatherer::gather_camera_entity(world);
+        let (cameras, transforms, lights) = <(
+            ReadStorage<'_, Camera>,
+            ReadStorage<'_, Transform>,
+            ReadStorage<'_, Light>,
+        )>::fetch(world);
+
+        let direction = lights.join().find_map(|light| match light {
+            Light::Directional(light) => Some(light.direction),
+            _ => None,
+        });
+
+        let cascades = match (camera, direction) {
+            (Some(camera), Some(direction)) => directional_cascades(
+                cameras.get(camera).unwrap(),
+                transforms.get(camera).unwrap().global_matrix(),
+                &direction,
+                &params,
+            ),
+            _ => Vec::new(),
+        };
+
+        let point_faces = Self::point_casters(world)
+            .iter()
+            .map(|(_, position)| point_light_faces(position))
+            .collect();
+
+        let spot_proj_view = Self::spot_casters(world)
+            .into_iter()
+            .map(|(_, proj_view)| proj_view)
+            .collect();
+
+        Self {
+            params,
+            cascades,
+            point_faces,
+            spot_proj_view,
+        }
+    }
+
+    /// Collect point lights which render shadows this frame, with their world positions.
+    ///
+    /// These are the lights with `casts_shadows` set closest to the camera, up to the budget
+    /// in `ShadowParams`. Empty if there is no `ShadowParams` resource.
+    pub fn point_casters(world: &World) -> Vec<(Entity, Point3<f32>)> {
+        Self::closest_casters(
+            world,
+            ShadowParams::point_shadow_budget,
+            |light, position| match light {
+                Light::Point(light) if light.casts_shadows => Some(position),
+                _ => None,
+            },
+        )
+    }
+
+    /// Collect spot lights which render shadows this frame, with their projection-view matrices.
+    ///
+    /// These are the lights with `casts_shadows` set closest to the camera, up to the budget
+    /// in `ShadowParams`. Empty if there is no `ShadowParams` resource.
+    pub fn spot_casters(world: &World) -> Vec<(Entity, Matrix4<f32>)> {
+        Self::closest_casters(
+            world,
+            ShadowParams::spot_shadow_budget,
+            |light, position| match light {
+                Light::Spot(light) if light.casts_shadows => Some(spot_light_proj_view(
+                    &position,
+                    &light.direction,
+                    light.angle,
+                )),
+                _ => None,
+            },
+        )
+    }
+
+    fn closest_casters<T>(
+        world: &World,
+        budget: fn(&ShadowParams) -> usize,
+        select: impl Fn(&Light, Point3<f32>) -> Option<T>,
+    ) -> Vec<(Entity, T)> {
+        let params = match <Option<Read<'_, ShadowParams>>>::fetch(world) {
+            Some(params) => *params,
+            None => return Vec::new(),
+        };
+
+        let camera = CameraGatherer::gather_camera_entity(world);
+        let (entities, transforms, lights) = <(
+            Entities<'_>,
+            ReadStorage<'_, Transform>,
+            ReadStorage<'_, Light>,
+        )>::fetch(world);
+
+        let camera_position = camera
+            .and_then(|camera| transforms.get(camera))
+            .map_or_else(Point3::origin, |t| {
+                Point3::from(t.global_matrix().column(3).xyz())
+            });
+
+        let mut casters = (&entities, &lights, &transforms)
+            .join()
+            .filter_map(|(entity, light, transform)| {
+                let position = Point3::from(transform.global_matrix().column(3).xyz());
+                select(light, position).map(|caster| {
+                    let distance = (position - camera_position).norm_squared();
+                    (distance, entity, caster)
+                })
+            })
+            .collect::<Vec<_>>();
+        casters.sort_by(|(a, _, _), (b, _, _)| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+        casters.truncate(budget(&params));
+        casters
+            .into_iter()
+            .map(|(_, entity, caster)| (entity, caster))
+            .collect()
+    }
+
+    /// Returns projection-view matrices of shadow casting spot lights collected
+    /// with `spot_casters` in their uniform layout.
+    pub fn spot_shadows(
+        world: &World,
+        casters: &[(Entity, Matrix4<f32>)],
+    ) -> Std140<pod::SpotShadows> {
+        let params = <Option<Read<'_, ShadowParams>>>::fetch(world)
+            .map(|p| *p)
+            .unwrap_or_default();
+        let (width, height) = params.spot_atlas_size().unwrap_or((1, 1));
+        let identity: [[f32; 4]; 4] = Matrix4::<f32>::identity().into();
+        let mut spot_shadows = pod::SpotShadows {
+            proj_view: [identity.into(); MAX_SPOT_SHADOWS],
+            texel_size: [1.0 / width as f32, 1.0 / height as f32].into(),
+            columns: params.spot_shadow_budget().max(1) as i32,
+        };
+        for (dst, (_, proj_view)) in spot_shadows.proj_view.iter_mut().zip(casters) {
+            let matrix: [[f32; 4]; 4] = (*proj_view).into();
+            *dst = matrix.into();
+        }
+        spot_shadows.std140()
+    }
+
+    /// Returns the gathered matrices and parameters in their uniform layout.
+    pub fn args(&self) -> Std140<pod::ShadowArgs> {
+        let params = &self.params;
+        let (width, height) = params.atlas_size();
+        let (point_width, point_height) = params.point_atlas_size().unwrap_or((1, 1));
+
+        let identity: [[f32; 4]; 4] = Matrix4::<f32>::identity().into();
+        let mut args = pod::ShadowArgs {
+            cascade_proj_view: [identity.into(); MAX_SHADOW_CASCADES],
+            point_proj_view: [identity.into(); MAX_POINT_SHADOWS * 6],
+            cascade_count: self.cascades.len() as i32,
+            pcf_radius: params.pcf_radius as i32,
+            depth_bias: params.depth_bias,
+            texel_size: [1.0 / width as f32, 1.0 / height as f32].into(),
+            point_shadow_count: self.point_faces.len() as i32,
+            point_atlas_rows: params.point_shadow_budget() as i32,
+            point_depth_bias: params.point_depth_bias,
+            point_texel_size: [1.0 / point_width as f32, 1.0 / point_height as f32].into(),
+        };
+
+        for (dst, cascade) in args.cascade_proj_view.iter_mut().zip(&self.cascades) {
+            let matrix: [[f32; 4]; 4] = (*cascade).into();
+            *dst = matrix.into();
+        }
+        for (dst, face) in args
+            .point_proj_view
+            .iter_mut()
+            .zip(self.point_faces.iter().flatten())
+        {
+            let matrix: [[f32; 4]; 4] = (*face).into();
+            *dst = matrix.into();
+        }
+
+        args.std140()
+    }
+}
@@ -11,6 +11,7 @@ use crate::{
             Buffer, BufferInfo, DescriptorSet, DescriptorSetLayout, Escape, Handle as RendyHandle,
         },
     },
+    texture_streaming::TextureStreaming,
     types::{Backend, Texture},
     util,
 };
@@ -130,6 +131,7 @@ enum MaterialState<B: Backend> {
         slot: usize,
         generation: u32,
         handle: WeakHandle<Material>,
+        streaming_generation: u64,
     },
 }
 
@@ -214,16 +216,22 @@ impl<B: Backend, T: for<'a> StaticTextureSet<'a>> MaterialSub<B, T> {
         profile_scope!("try_insert");
 
         use util::{desc_write, slice_as_bytes, texture_desc};
-        let (mat_storage, tex_storage) = <(
+        let (mat_storage, tex_storage, streaming) = <(
             Read<'_, AssetStorage<Material>>,
             Read<'_, AssetStorage<Texture>>,
+            Option<Read<'_, TextureStreaming>>,
         )>::fetch(world);
+        let resolve = |t| {
+            streaming
+                .as_ref()
+                .map_or(t, |streaming| streaming.resolve(t))
+        };
 
         let mat = mat_storage.get(handle)?;
 
         let has_tex = T::textures(mat).any(|t| {
             !tex_storage
-                .get(t)
+                .get(resolve(t))
                 .map_or(false, |tex| B::unwrap_texture(tex).is_some())
         });
         if has_tex {
@@ -255,7 +263,7 @@ impl<B: Backend, T: for<'a> StaticTextureSet<'a>> MaterialSub<B, T> {
                     set,
                     (i + 1) as u32,
                     texture_desc(
-                        tex_storage.get(t).unwrap(),
+                        tex_storage.get(resolve(t)).unwrap(),
                         hal::image::Layout::ShaderReadOnlyOptimal,
                     )
                     .unwrap(),
@@ -270,6 +278,7 @@ impl<B: Backend, T: for<'a> StaticTextureSet<'a>> MaterialSub<B, T> {
             slot,
             generation: self.generation,
             handle: handle.downgrade(),
+            streaming_generation: streaming.map_or(0, |streaming| streaming.generation()),
         })
     }
 
@@ -283,16 +292,21 @@ impl<B: Backend, T: for<'a> StaticTextureSet<'a>> MaterialSub<B, T> {
         #[cfg(feature = "profiler")]
         profile_scope!("insert");
 
+        let streaming_generation = world
+            .try_fetch::<TextureStreaming>()
+            .map_or(0, |streaming| streaming.generation());
         let id = self.lookup.forward(handle.id());
         match self.materials.get_mut(id) {
             Some(MaterialState::Loaded {
                 slot,
                 generation,
                 handle,
+                streaming_generation: loaded_streaming_generation,
                 ..
             }) => {
-                // If handle is dead, new material was loaded (handle id reused)
-                if handle.is_dead() {
+                // If handle is dead, new material was loaded (handle id reused), and textures
+                // are bound again when the resident level of a streamed texture changed.
+                if handle.is_dead() || *loaded_streaming_generation != streaming_generation {
                     self.allocator.release(*slot);
                 } else {
                     // Material loaded and ready
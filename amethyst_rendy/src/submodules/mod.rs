@@ -1,7 +1,10 @@
 //! Various helpers and implementations for sub functions of render passes.
+mod ambient_occlusion;
 mod environment;
+mod environment_map;
 mod flat_environment;
 mod material;
+mod shadow;
 mod skinning;
 mod texture;
 mod uniform;
@@ -9,9 +12,12 @@ mod vertex;
 
 pub mod gather;
 
+pub use ambient_occlusion::*;
 pub use environment::*;
+pub use environment_map::*;
 pub use flat_environment::*;
 pub use material::*;
+pub use shadow::*;
 pub use skinning::*;
 pub use texture::*;
 pub use uniform::*;
@@ -0,0 +1,162 @@
+//! Shadow submodule for sampling shadow maps rendered by the shadow targets.
+use crate::{
+    pod::ShadowArgs,
+    rendy::{
+        command::RenderPassEncoder,
+        factory::Factory,
+        graph::{GraphContext, NodeImage},
+        hal::{self, device::Device, format::Swizzle, pso::Descriptor},
+        resource::{
+            DescriptorSet, DescriptorSetLayout, Escape, Filter, Handle as RendyHandle, ImageView,
+            ImageViewInfo, Sampler, SamplerInfo, ViewKind, WrapMode,
+        },
+    },
+    submodules::{gather::ShadowGatherer, DynamicUniform},
+    types::Backend,
+    util,
+};
+use amethyst_core::ecs::World;
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+/// Access of a shadow map image by render groups sampling it in fragment shaders.
+pub fn shadow_map_access() -> rendy::graph::ImageAccess {
+    rendy::graph::ImageAccess {
+        access: hal::image::Access::SHADER_READ,
+        usage: hal::image::Usage::SAMPLED,
+        layout: hal::image::Layout::ShaderReadOnlyOptimal,
+        stages: hal::pso::PipelineStage::FRAGMENT_SHADER,
+    }
+}
+
+/// Submodule binding shadow maps together with the light-space matrices used to render them.
+///
+/// Occupies two consecutive descriptor sets: the `ShadowArgs` uniform followed by
+/// the directional light shadow map and the point and spot light shadow atlases.
+#[derive(Debug)]
+pub struct ShadowSub<B: Backend> {
+    args: DynamicUniform<B, ShadowArgs>,
+    layout: RendyHandle<DescriptorSetLayout<B>>,
+    set: Escape<DescriptorSet<B>>,
+    _views: Vec<Escape<ImageView<B>>>,
+    _sampler: RendyHandle<Sampler<B>>,
+}
+
+impl<B: Backend> ShadowSub<B> {
+    /// Create a new `ShadowSub` sampling the shadow maps passed to the render group.
+    ///
+    /// Point and spot light shadow atlases are optional.
+    pub fn new(
+        ctx: &GraphContext<B>,
+        factory: &Factory<B>,
+        directional: &NodeImage,
+        point: Option<&NodeImage>,
+        spot: Option<&NodeImage>,
+    ) -> Result<Self, failure::Error> {
+        let args = DynamicUniform::new(factory, hal::pso::ShaderStageFlags::FRAGMENT)?;
+        let layout: RendyHandle<DescriptorSetLayout<B>> = set_layout! {
+            factory,
+            [3] CombinedImageSampler hal::pso::ShaderStageFlags::FRAGMENT
+        };
+
+        let views = Some(directional)
+            .into_iter()
+            .chain(point)
+            .chain(spot)
+            .map(|image| depth_view(ctx, factory, image))
+            .collect::<Result<Vec<_>, _>>()?;
+        let sampler = factory.get_sampler(SamplerInfo::new(Filter::Nearest, WrapMode::Clamp))?;
+
+        let set = factory.create_descriptor_set(layout.clone())?;
+        // The directional shadow map fills bindings of disabled shadow atlases.
+        let mut rest = views[1..].iter();
+        let point_view = point.and_then(|_| rest.next()).unwrap_or(&views[0]);
+        let spot_view = spot.and_then(|_| rest.next()).unwrap_or(&views[0]);
+        unsafe {
+            factory.write_descriptor_sets(
+                [&views[0], point_view, spot_view]
+                    .iter()
+                    .enumerate()
+                    .map(|(binding, view)| {
+                        util::desc_write(
+                            set.raw(),
+                            binding as u32,
+                            Descriptor::CombinedImageSampler(
+                                view.raw(),
+                                hal::image::Layout::ShaderReadOnlyOptimal,
+                                sampler.raw(),
+                            ),
+                        )
+                    })
+                    .collect::<Vec<_>>(),
+            );
+        }
+
+        Ok(Self {
+            args,
+            layout,
+            set,
+            _views: views,
+            _sampler: sampler,
+        })
+    }
+
+    /// Returns the raw `DescriptorSetLayout`s of the shadow uniform and shadow map sets.
+    pub fn raw_layouts(&self) -> [&B::DescriptorSetLayout; 2] {
+        [self.args.raw_layout(), self.layout.raw()]
+    }
+
+    /// Computes and writes light-space matrices for the current frame.
+    pub fn process(&mut self, factory: &Factory<B>, index: usize, world: &World) -> bool {
+        #[cfg(feature = "profiler")]
+        profile_scope!("process");
+
+        self.args
+            .write(factory, index, ShadowGatherer::gather(world).args())
+    }
+
+    /// Binds the shadow uniform to set `set_id` and the shadow maps to `set_id + 1`.
+    #[inline]
+    pub fn bind(
+        &self,
+        index: usize,
+        pipeline_layout: &B::PipelineLayout,
+        set_id: u32,
+        encoder: &mut RenderPassEncoder<'_, B>,
+    ) {
+        self.args.bind(index, pipeline_layout, set_id, encoder);
+        unsafe {
+            encoder.bind_graphics_descriptor_sets(
+                pipeline_layout,
+                set_id + 1,
+                Some(self.set.raw()),
+                std::iter::empty(),
+            );
+        }
+    }
+}
+
+fn depth_view<B: Backend>(
+    ctx: &GraphContext<B>,
+    factory: &Factory<B>,
+    image: &NodeImage,
+) -> Result<Escape<ImageView<B>>, failure::Error> {
+    let shadow_map = ctx
+        .get_image(image.id)
+        .ok_or_else(|| failure::format_err!("Shadow map image is not available"))?;
+    let view = factory.create_image_view(
+        shadow_map.clone(),
+        ImageViewInfo {
+            view_kind: ViewKind::D2,
+            format: shadow_map.format(),
+            swizzle: Swizzle::NO,
+            range: hal::image::SubresourceRange {
+                aspects: hal::format::Aspects::DEPTH,
+                levels: 0..1,
+                layers: 0..1,
+            },
+        },
+    )?;
+    Ok(view)
+}
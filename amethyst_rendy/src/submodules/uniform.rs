@@ -3,7 +3,7 @@ use crate::{
     rendy::{
         command::RenderPassEncoder,
         factory::Factory,
-        hal::{self, device::Device},
+        hal::{self, adapter::PhysicalDevice, device::Device},
         memory::{MappedRange, Write},
         resource::{
             Buffer, BufferInfo, DescriptorSet, DescriptorSetLayout, Escape, Handle as RendyHandle,
@@ -13,6 +13,7 @@ use crate::{
     util,
 };
 use core::marker::PhantomData;
+use derivative::Derivative;
 use glsl_layout::AsStd140;
 
 /// Provides per-image abstraction for an arbitrary `DescriptorSet`.
@@ -149,3 +150,130 @@ where
         }
     }
 }
+
+/// Provides per-image abstraction for an array of uniforms, one per drawn instance,
+/// bound one element at a time with a dynamic offset.
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""))]
+pub struct DynamicUniformArray<B: Backend, T: AsStd140>
+where
+    T::Std140: Sized,
+{
+    layout: RendyHandle<DescriptorSetLayout<B>>,
+    stride: u64,
+    per_image: Vec<PerImageDynamicUniformArray<B>>,
+    marker: PhantomData<fn() -> T>,
+}
+
+#[derive(Debug)]
+struct PerImageDynamicUniformArray<B: Backend> {
+    buffer: Option<Escape<Buffer<B>>>,
+    set: Escape<DescriptorSet<B>>,
+}
+
+impl<B: Backend, T: AsStd140> DynamicUniformArray<B, T>
+where
+    T::Std140: Sized,
+{
+    /// Create a new `DynamicUniformArray`, allocating descriptor set memory using the provided
+    /// `Factory`. Allocate to the supplied shader.
+    pub fn new(
+        factory: &Factory<B>,
+        flags: hal::pso::ShaderStageFlags,
+    ) -> Result<Self, failure::Error> {
+        let align = factory
+            .physical()
+            .limits()
+            .min_uniform_buffer_offset_alignment;
+        Ok(Self {
+            layout: factory
+                .create_descriptor_set_layout(util::set_layout_bindings(Some((
+                    1,
+                    hal::pso::DescriptorType::UniformBufferDynamic,
+                    flags,
+                ))))?
+                .into(),
+            stride: util::align_size::<T>(align, 1),
+            per_image: Vec::new(),
+            marker: PhantomData,
+        })
+    }
+
+    /// Returns the `DescriptSetLayout` for this set.
+    pub fn raw_layout(&self) -> &B::DescriptorSetLayout {
+        self.layout.raw()
+    }
+
+    /// Write the `items` to this descriptor set memory, growing it if needed.
+    pub fn write(
+        &mut self,
+        factory: &Factory<B>,
+        index: usize,
+        items: impl ExactSizeIterator<Item = T::Std140>,
+    ) {
+        while self.per_image.len() <= index {
+            self.per_image.push(PerImageDynamicUniformArray {
+                buffer: None,
+                set: factory.create_descriptor_set(self.layout.clone()).unwrap(),
+            });
+        }
+        let this_image = &mut self.per_image[index];
+
+        let size = items.len() as u64 * self.stride;
+        if size == 0 {
+            return;
+        }
+        let grow = match this_image.buffer.as_ref() {
+            Some(buffer) => buffer.size() < size,
+            None => true,
+        };
+        if grow {
+            let buffer = factory
+                .create_buffer(
+                    BufferInfo {
+                        size: (items.len() as u64).next_power_of_two() * self.stride,
+                        usage: hal::buffer::Usage::UNIFORM,
+                    },
+                    rendy::memory::Dynamic,
+                )
+                .unwrap();
+            let elem_size = std::mem::size_of::<T::Std140>() as u64;
+            let desc = hal::pso::Descriptor::Buffer(buffer.raw(), Some(0)..Some(elem_size));
+            unsafe {
+                let set = this_image.set.raw();
+                factory.write_descriptor_sets(Some(util::desc_write(set, 0, desc)));
+            }
+            this_image.buffer = Some(buffer);
+        }
+
+        let buffer = this_image.buffer.as_mut().unwrap();
+        let mut mapped = buffer.map(factory.device(), 0..size).unwrap();
+        let mut writer = unsafe { mapped.write::<u8>(factory.device(), 0..size).unwrap() };
+        let slice = unsafe { writer.slice() };
+        for (chunk, item) in slice.chunks_mut(self.stride as usize).zip(items) {
+            let item = [item];
+            let bytes = util::slice_as_bytes(&item);
+            chunk[..bytes.len()].copy_from_slice(bytes);
+        }
+    }
+
+    /// Bind the `element` of this descriptor set
+    #[inline]
+    pub fn bind(
+        &self,
+        index: usize,
+        pipeline_layout: &B::PipelineLayout,
+        set_id: u32,
+        element: usize,
+        encoder: &mut RenderPassEncoder<'_, B>,
+    ) {
+        unsafe {
+            encoder.bind_graphics_descriptor_sets(
+                pipeline_layout,
+                set_id,
+                Some(self.per_image[index].set.raw()),
+                Some((element as u64 * self.stride) as u32),
+            );
+        }
+    }
+}
@@ -1,14 +1,18 @@
 //! Renderer system
 use crate::{
-    camera::{ActiveCamera, Camera},
+    camera::{ActiveCamera, Camera, ClipPlane},
     debug_drawing::DebugLinesComponent,
+    graph_description::RenderGraphDescription,
     light::Light,
     mtl::{Material, MaterialDefaults},
-    resources::Tint,
+    pipeline::PipelineCache,
+    resources::{ScissorRect, Tint},
     skinning::JointTransforms,
     sprite::SpriteRender,
+    stats::GpuMemoryStats,
+    transcode::{TranscodeTarget, Transcoder},
     transparent::Transparent,
-    types::{Backend, Mesh, Texture},
+    types::{Backend, Mesh, Texture, TextureLevel},
     visibility::Visibility,
 };
 use amethyst_assets::{AssetStorage, Handle, HotReloadStrategy, ProcessingState, ThreadPool};
@@ -25,7 +29,7 @@ use rendy::{
     graph::{Graph, GraphBuilder},
     texture::palette::{load_from_linear_rgba, load_from_srgba},
 };
-use std::{marker::PhantomData, sync::Arc};
+use std::{marker::PhantomData, path::PathBuf, sync::Arc};
 
 #[cfg(feature = "profiler")]
 use thread_profiler::profile_scope;
@@ -39,6 +43,12 @@ pub trait GraphCreator<B: Backend> {
 
     /// Retrieve configured complete graph builder.
     fn builder(&mut self, factory: &mut Factory<B>, world: &World) -> GraphBuilder<B, World>;
+
+    /// Take the description of the graph retrieved by the last `builder` call, published
+    /// in the [RenderGraphDescription] resource. Graphs aren't described by default.
+    fn description(&mut self) -> Option<RenderGraphDescription> {
+        None
+    }
 }
 
 /// Amethyst rendering system
@@ -51,6 +61,7 @@ where
     graph: Option<Graph<B, World>>,
     families: Option<Families<B>>,
     graph_creator: G,
+    pipeline_cache: Option<PathBuf>,
 }
 
 impl<B, G> RenderingSystem<B, G>
@@ -64,8 +75,16 @@ where
             graph: None,
             families: None,
             graph_creator,
+            pipeline_cache: None,
         }
     }
+
+    /// Load the [PipelineCache] from the file at `path` on startup,
+    /// and write it back on shutdown.
+    pub fn with_pipeline_cache(mut self, path: impl Into<PathBuf>) -> Self {
+        self.pipeline_cache = Some(path.into());
+        self
+    }
 }
 
 type SetupData<'a> = (
@@ -73,8 +92,10 @@ type SetupData<'a> = (
     ReadStorage<'a, Handle<Texture>>,
     ReadStorage<'a, Handle<Material>>,
     ReadStorage<'a, Tint>,
+    ReadStorage<'a, ScissorRect>,
     ReadStorage<'a, Light>,
     ReadStorage<'a, Camera>,
+    ReadStorage<'a, ClipPlane>,
     ReadStorage<'a, Hidden>,
     ReadStorage<'a, HiddenPropagate>,
     ReadStorage<'a, DebugLinesComponent>,
@@ -83,7 +104,9 @@ type SetupData<'a> = (
     ReadStorage<'a, SpriteRender>,
     Option<Read<'a, Visibility>>,
     Read<'a, ActiveCamera>,
+    Write<'a, GpuMemoryStats>,
     ReadStorage<'a, JointTransforms>,
+    Write<'a, RenderGraphDescription>,
 );
 
 impl<B, G> RenderingSystem<B, G>
@@ -108,6 +131,9 @@ where
             profile_scope!("run_graph_creator");
             self.graph_creator.builder(&mut factory, world)
         };
+        if let Some(description) = self.graph_creator.description() {
+            *world.fetch_mut::<RenderGraphDescription>() = description;
+        }
 
         let graph = {
             #[cfg(feature = "profiler")]
@@ -126,7 +152,18 @@ where
         self.graph
             .as_mut()
             .unwrap()
-            .run(&mut factory, self.families.as_mut().unwrap(), world)
+            .run(&mut factory, self.families.as_mut().unwrap(), world);
+
+        let (mut stats, textures, meshes) = <(
+            Write<'_, GpuMemoryStats>,
+            Option<Read<'_, AssetStorage<Texture>>>,
+            Option<Read<'_, AssetStorage<Mesh>>>,
+        )>::fetch(world);
+        stats.update(
+            &factory.memory_utilization(),
+            textures.map_or(0, |textures| textures.len()),
+            meshes.map_or(0, |meshes| meshes.len()),
+        );
     }
 }
 
@@ -153,6 +190,7 @@ where
         };
 
         self.families = Some(families);
+        world.insert(PipelineCache::load(&factory, self.pipeline_cache.take()));
         world.insert(factory);
         world.insert(queue_id);
 
@@ -169,6 +207,13 @@ where
             graph.dispose(&mut *factory, world);
         }
 
+        if let Some(cache) = world.remove::<PipelineCache<B>>() {
+            log::debug!("Dispose pipeline cache");
+            unsafe {
+                cache.dispose(&world.fetch::<Factory<B>>());
+            }
+        }
+
         log::debug!("Unload resources");
         if let Some(mut storage) = world.try_fetch_mut::<AssetStorage<Mesh>>() {
             storage.unload_all();
@@ -220,6 +265,61 @@ impl<'a, B: Backend> System<'a> for MeshProcessorSystem<B> {
     }
 }
 
+/// Uploads the pre-built mip levels of a texture, following the base level uploaded by rendy.
+fn upload_texture_levels<B: Backend>(
+    factory: &Factory<B>,
+    texture: &rendy::texture::Texture<B>,
+    levels: &[TextureLevel],
+    next_state: ImageState,
+) -> Result<(), failure::Error> {
+    let image = texture.image();
+    let info = image.info();
+    if levels.len() >= info.levels as usize {
+        failure::bail!(
+            "Texture has {} mip levels, but {} were provided",
+            info.levels,
+            levels.len() + 1
+        );
+    }
+
+    let desc = info.format.surface_desc();
+    let layers = info.kind.num_layers();
+    for (level, data) in (1..).zip(levels) {
+        let blocks =
+            (data.data_width / u32::from(desc.dim.0)) * (data.data_height / u32::from(desc.dim.1));
+        let expected = blocks as usize * usize::from(desc.bits / 8) * usize::from(layers);
+        if data.data.len() != expected {
+            failure::bail!(
+                "Mip level {} has {} bytes, expected {} for {:?}",
+                level,
+                data.data.len(),
+                expected,
+                info.format
+            );
+        }
+
+        // The image was just created by the same factory, so it isn't in use yet.
+        unsafe {
+            factory.upload_image(
+                image.clone(),
+                data.data_width,
+                data.data_height,
+                rendy::hal::image::SubresourceLayers {
+                    aspects: desc.aspects,
+                    level,
+                    layers: 0..layers,
+                },
+                rendy::hal::image::Offset::ZERO,
+                info.kind.level_extent(level),
+                &data.data,
+                rendy::hal::image::Layout::ShaderReadOnlyOptimal,
+                next_state,
+            )?;
+        }
+    }
+    Ok(())
+}
+
 /// Asset processing system for `Texture` asset type.
 #[derive(Debug, derivative::Derivative)]
 #[derivative(Default(bound = ""))]
@@ -232,33 +332,42 @@ impl<'a, B: Backend> System<'a> for TextureProcessorSystem<B> {
         ReadExpect<'a, Arc<ThreadPool>>,
         Option<Read<'a, HotReloadStrategy>>,
         WriteExpect<'a, Factory<B>>,
+        Option<Read<'a, Transcoder>>,
     );
 
     fn run(
         &mut self,
-        (mut texture_storage, queue_id, time, pool, strategy, mut factory): Self::SystemData,
+        (mut texture_storage, queue_id, time, pool, strategy, mut factory, transcoder): Self::SystemData,
     ) {
         #[cfg(feature = "profiler")]
         profile_scope!("texture_processor");
 
+        let target = TranscodeTarget::best_supported(&factory);
         texture_storage.process(
             |b| {
                 #[cfg(feature = "profiler")]
                 profile_scope!("process_texture");
 
-                b.0.build(
-                    ImageState {
-                        queue: *queue_id,
-                        stage: rendy::hal::pso::PipelineStage::VERTEX_SHADER
-                            | rendy::hal::pso::PipelineStage::FRAGMENT_SHADER,
-                        access: rendy::hal::image::Access::SHADER_READ,
-                        layout: rendy::hal::image::Layout::ShaderReadOnlyOptimal,
-                    },
-                    &mut factory,
-                )
-                .map(B::wrap_texture)
-                .map(ProcessingState::Loaded)
-                .map_err(|e| e.compat().into())
+                let b = match b.2 {
+                    Some(ref source) => source.transcode(transcoder.as_deref(), target)?,
+                    None => b,
+                };
+
+                let next_state = ImageState {
+                    queue: *queue_id,
+                    stage: rendy::hal::pso::PipelineStage::VERTEX_SHADER
+                        | rendy::hal::pso::PipelineStage::FRAGMENT_SHADER,
+                    access: rendy::hal::image::Access::SHADER_READ,
+                    layout: rendy::hal::image::Layout::ShaderReadOnlyOptimal,
+                };
+                b.0.build(next_state, &mut factory)
+                    .and_then(|texture| {
+                        upload_texture_levels(&factory, &texture, &b.1, next_state)?;
+                        Ok(texture)
+                    })
+                    .map(B::wrap_texture)
+                    .map(ProcessingState::Loaded)
+                    .map_err(|e| e.compat().into())
             },
             time.frame_number(),
             &**pool,
@@ -268,7 +377,7 @@ impl<'a, B: Backend> System<'a> for TextureProcessorSystem<B> {
 }
 
 fn create_default_mat<B: Backend>(world: &mut World) -> Material {
-    use crate::mtl::TextureOffset;
+    use crate::mtl::{TextureOffset, TextureTransforms};
 
     use amethyst_assets::Loader;
 
@@ -280,6 +389,7 @@ fn create_default_mat<B: Backend>(world: &mut World) -> Material {
     let metallic_roughness = load_from_linear_rgba(LinSrgba::new(0.0, 0.5, 0.0, 0.0));
     let ambient_occlusion = load_from_linear_rgba(LinSrgba::new(1.0, 1.0, 1.0, 1.0));
     let cavity = load_from_linear_rgba(LinSrgba::new(1.0, 1.0, 1.0, 1.0));
+    let lightmap = load_from_linear_rgba(LinSrgba::new(0.0, 0.0, 0.0, 0.0));
 
     let tex_storage = world.fetch();
 
@@ -289,6 +399,7 @@ fn create_default_mat<B: Backend>(world: &mut World) -> Material {
     let metallic_roughness = loader.load_from_data(metallic_roughness.into(), (), &tex_storage);
     let ambient_occlusion = loader.load_from_data(ambient_occlusion.into(), (), &tex_storage);
     let cavity = loader.load_from_data(cavity.into(), (), &tex_storage);
+    let lightmap = loader.load_from_data(lightmap.into(), (), &tex_storage);
 
     Material {
         alpha_cutoff: 0.01,
@@ -299,5 +410,10 @@ fn create_default_mat<B: Backend>(world: &mut World) -> Material {
         ambient_occlusion,
         cavity,
         uv_offset: TextureOffset::default(),
+        texture_transforms: TextureTransforms::default(),
+        emission_strength: 1.0,
+        unlit: false,
+        lightmap,
+        lightmap_intensity: 0.0,
     }
 }
@@ -0,0 +1,115 @@
+//! Temporal anti-aliasing configuration.
+
+use amethyst_core::{
+    ecs::{Read, SystemData, World},
+    math::Vector2,
+    timing::Time,
+};
+
+/// Number of sub-pixel camera offsets cycled through by temporal anti-aliasing.
+pub const TAA_JITTER_SAMPLES: u64 = 8;
+
+/// Resource configuring the temporal anti-aliasing applied by `RenderTaa`.
+///
+/// All fields are read every frame. While disabled, the camera is not jittered and
+/// the resolve pass copies the current frame unchanged.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TaaParams {
+    /// Jitter the camera and blend the image with its reprojected history.
+    pub enabled: bool,
+    /// Weight of the history in the blended image, between 0 and 1. Higher values
+    /// smooth more aliasing but take longer to converge after disocclusion.
+    pub history_weight: f32,
+}
+
+impl Default for TaaParams {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            history_weight: 0.9,
+        }
+    }
+}
+
+impl TaaParams {
+    /// Returns the `TaaParams` resource, or default parameters if there is none.
+    pub fn fetch(world: &World) -> Self {
+        <Option<Read<'_, TaaParams>>>::fetch(world)
+            .map(|p| *p)
+            .unwrap_or_default()
+    }
+}
+
+/// Resource holding the size of the image being anti-aliased by `RenderTaa`,
+/// which jitter offsets are computed for.
+///
+/// It is inserted and updated by `RenderTaa`; the camera is only jittered while it exists.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct TaaJitter {
+    pub(crate) extent: Option<(u32, u32)>,
+}
+
+impl TaaJitter {
+    /// Returns the offset of the camera projection this frame in normalized device coordinates,
+    /// or `None` if temporal anti-aliasing is not active.
+    ///
+    /// Offsets follow a Halton (2, 3) sequence of sub-pixel positions, advanced with
+    /// the frame number.
+    pub fn offset(world: &World) -> Option<Vector2<f32>> {
+        let (jitter, time) = <(Option<Read<'_, TaaJitter>>, Option<Read<'_, Time>>)>::fetch(world);
+        let (width, height) = jitter?.extent?;
+        if !TaaParams::fetch(world).enabled {
+            return None;
+        }
+
+        let index = time.map_or(0, |t| t.frame_number()) % TAA_JITTER_SAMPLES + 1;
+        Some(Vector2::new(
+            (halton(index, 2) - 0.5) * 2.0 / width.max(1) as f32,
+            (halton(index, 3) - 0.5) * 2.0 / height.max(1) as f32,
+        ))
+    }
+}
+
+fn halton(mut index: u64, base: u64) -> f32 {
+    let mut fraction = 1.0;
+    let mut result = 0.0;
+    while index > 0 {
+        fraction /= base as f32;
+        result += fraction * (index % base) as f32;
+        index /= base;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use amethyst_core::ecs::WorldExt;
+
+    #[test]
+    fn halton_sequence() {
+        let base_2: Vec<f32> = (1..5).map(|i| halton(i, 2)).collect();
+        assert_eq!(base_2, vec![0.5, 0.25, 0.75, 0.125]);
+        let base_3: Vec<f32> = (1..4).map(|i| halton(i, 3)).collect();
+        assert_eq!(base_3, vec![1.0 / 3.0, 2.0 / 3.0, 1.0 / 9.0]);
+    }
+
+    #[test]
+    fn no_jitter_without_taa() {
+        let mut world = World::new();
+        assert_eq!(TaaJitter::offset(&world), None);
+
+        world.insert(TaaJitter {
+            extent: Some((100, 50)),
+        });
+        let offset = TaaJitter::offset(&world).unwrap();
+        assert!(offset.x.abs() <= 1.0 / 100.0);
+        assert!(offset.y.abs() <= 1.0 / 50.0);
+
+        world.insert(TaaParams {
+            enabled: false,
+            ..Default::default()
+        });
+        assert_eq!(TaaJitter::offset(&world), None);
+    }
+}
@@ -0,0 +1,575 @@
+//! Terrain built from a heightmap, split into chunks with levels of detail.
+//!
+//! [TerrainBuilder] creates an entity for every square chunk of the heightmap, with a mesh for
+//! every level of detail. The meshes are swapped by the
+//! [LodSelectionSystem](crate::lod::LodSelectionSystem) by the distance of the chunk to the
+//! camera: every coarser level skips every other height sample of the finer one, and skirts
+//! hanging from the chunk edges hide the cracks between neighbouring chunks of different levels.
+//! Chunks have a `BoundingBox`, so chunks outside of the view are culled like any other mesh,
+//! and are drawn by the 3D passes with a `Material`, whose albedo can be splatted from layer
+//! images mixed by a blend map with [TerrainSplat].
+use crate::{
+    lod::LodGroup,
+    mtl::{Material, MaterialDefaults},
+    types::{Mesh, MeshData, Texture, TextureData},
+    visibility::BoundingBox,
+};
+use amethyst_assets::{AssetStorage, Loader};
+use amethyst_core::{
+    ecs::{Builder, Entity, World, WorldExt},
+    math::{Point3, Vector3},
+    Transform,
+};
+use amethyst_error::{format_err, Error};
+use image::RgbaImage;
+use rendy::{
+    hal::{
+        format::Format,
+        image::{Filter, Kind, SamplerInfo, ViewKind, WrapMode},
+    },
+    mesh::{MeshBuilder, Normal, Position, Tangent, TexCoord},
+    texture::{MipLevels, TextureBuilder},
+};
+
+/// Grid of heights of a terrain, in rows of samples along the x axis.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Heightmap {
+    width: u32,
+    depth: u32,
+    heights: Vec<f32>,
+}
+
+impl Heightmap {
+    /// Create a heightmap of `width` samples along the x axis by `depth` samples along the z
+    /// axis, from its heights in rows along the x axis.
+    pub fn new(width: u32, depth: u32, heights: Vec<f32>) -> Result<Self, Error> {
+        if width < 2 || depth < 2 || heights.len() != (width * depth) as usize {
+            return Err(format_err!(
+                "Heightmap of {}x{} samples needs at least 2x2 samples and {} heights, got {}",
+                width,
+                depth,
+                width * depth,
+                heights.len()
+            ));
+        }
+        Ok(Heightmap {
+            width,
+            depth,
+            heights,
+        })
+    }
+
+    /// Decode a heightmap from a grayscale image, with heights from 0 for black to 1 for white.
+    pub fn from_image(bytes: &[u8]) -> Result<Self, Error> {
+        let image = image::load_from_memory(bytes)
+            .map_err(|e| format_err!("{}", e))?
+            .to_luma();
+        let (width, depth) = image.dimensions();
+        let heights = image
+            .into_raw()
+            .into_iter()
+            .map(|height| f32::from(height) / 255.0)
+            .collect();
+        Self::new(width, depth, heights)
+    }
+
+    /// Read a heightmap from raw little endian 16 bit samples, as exported by most terrain
+    /// editors, with heights from 0 to 1.
+    pub fn from_raw_u16(width: u32, depth: u32, bytes: &[u8]) -> Result<Self, Error> {
+        let heights = bytes
+            .chunks_exact(2)
+            .map(|sample| f32::from(u16::from_le_bytes([sample[0], sample[1]])) / 65535.0)
+            .collect();
+        Self::new(width, depth, heights)
+    }
+
+    /// Number of samples along the x axis.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Number of samples along the z axis.
+    pub fn depth(&self) -> u32 {
+        self.depth
+    }
+
+    /// Returns the height of a sample, clamping its coordinates to the heightmap.
+    pub fn height(&self, x: i64, z: i64) -> f32 {
+        let x = x.max(0).min(i64::from(self.width) - 1) as usize;
+        let z = z.max(0).min(i64::from(self.depth) - 1) as usize;
+        self.heights[z * self.width as usize + x]
+    }
+
+    /// Returns the height between samples, interpolated bilinearly, e.g. to place objects on
+    /// the terrain. The coordinates are in samples.
+    pub fn sample(&self, x: f32, z: f32) -> f32 {
+        let (x0, z0) = (x.floor(), z.floor());
+        let (fx, fz) = (x - x0, z - z0);
+        let (x0, z0) = (x0 as i64, z0 as i64);
+        let top = lerp(self.height(x0, z0), self.height(x0 + 1, z0), fx);
+        let bottom = lerp(self.height(x0, z0 + 1), self.height(x0 + 1, z0 + 1), fx);
+        lerp(top, bottom, fz)
+    }
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+/// Albedo of a terrain mixed from up to four layer images by the channels of a blend map.
+///
+/// The albedo is baked into a texture per chunk when the terrain is built, so the layers can be
+/// drawn by any 3D pass.
+#[derive(Clone, Debug)]
+pub struct TerrainSplat {
+    blend_map: RgbaImage,
+    layers: Vec<(RgbaImage, f32)>,
+    resolution: u32,
+}
+
+impl TerrainSplat {
+    /// Create a splat from a blend map covering the whole terrain. Its red, green, blue and
+    /// alpha channels weigh the layers in the order they're added.
+    pub fn new(blend_map: RgbaImage) -> Self {
+        TerrainSplat {
+            blend_map,
+            layers: Vec::new(),
+            resolution: 256,
+        }
+    }
+
+    /// Add a layer image repeated `tiling` times across the terrain. Layers after the fourth
+    /// one are ignored.
+    pub fn with_layer(mut self, image: RgbaImage, tiling: f32) -> Self {
+        self.layers.push((image, tiling));
+        self
+    }
+
+    /// Set the width and height in texels of the albedo texture of each chunk, 256 by default.
+    pub fn with_resolution(mut self, resolution: u32) -> Self {
+        self.resolution = resolution.max(1);
+        self
+    }
+
+    /// Bake the albedo of the part of the terrain between the normalized coordinates `min`
+    /// and `max`.
+    fn bake(&self, min: (f32, f32), max: (f32, f32)) -> RgbaImage {
+        let size = self.resolution;
+        RgbaImage::from_fn(size, size, |x, y| {
+            let u = lerp(min.0, max.0, (x as f32 + 0.5) / size as f32);
+            let v = lerp(min.1, max.1, (y as f32 + 0.5) / size as f32);
+            let weights = sample_image(&self.blend_map, u, v, false);
+            let total: f32 = weights.iter().take(self.layers.len()).sum();
+
+            let mut color = [0.0; 4];
+            for (index, (layer, tiling)) in self.layers.iter().take(4).enumerate() {
+                let weight = if total > 0.0 {
+                    weights[index] / total
+                } else if index == 0 {
+                    1.0
+                } else {
+                    0.0
+                };
+                let texel = sample_image(layer, u * tiling, v * tiling, true);
+                for channel in 0..4 {
+                    color[channel] += texel[channel] * weight;
+                }
+            }
+            image::Rgba([
+                color[0].round() as u8,
+                color[1].round() as u8,
+                color[2].round() as u8,
+                color[3].round() as u8,
+            ])
+        })
+    }
+}
+
+/// Sample an image at normalized coordinates bilinearly, repeating it or clamping to its edges.
+fn sample_image(image: &RgbaImage, u: f32, v: f32, repeat: bool) -> [f32; 4] {
+    let (width, height) = image.dimensions();
+    let x = u * width as f32 - 0.5;
+    let y = v * height as f32 - 0.5;
+    let texel = |x: i64, y: i64| {
+        let (x, y) = if repeat {
+            (
+                x.rem_euclid(i64::from(width)),
+                y.rem_euclid(i64::from(height)),
+            )
+        } else {
+            (
+                x.max(0).min(i64::from(width) - 1),
+                y.max(0).min(i64::from(height) - 1),
+            )
+        };
+        image.get_pixel(x as u32, y as u32).0
+    };
+    let (x0, y0) = (x.floor(), y.floor());
+    let (fx, fy) = (x - x0, y - y0);
+    let (x0, y0) = (x0 as i64, y0 as i64);
+    let mut result = [0.0; 4];
+    for (channel, value) in result.iter_mut().enumerate() {
+        let top = lerp(
+            f32::from(texel(x0, y0)[channel]),
+            f32::from(texel(x0 + 1, y0)[channel]),
+            fx,
+        );
+        let bottom = lerp(
+            f32::from(texel(x0, y0 + 1)[channel]),
+            f32::from(texel(x0 + 1, y0 + 1)[channel]),
+            fx,
+        );
+        *value = lerp(top, bottom, fy);
+    }
+    result
+}
+
+/// Vertices and indices of the mesh of a chunk at a level of detail.
+#[derive(Debug)]
+struct ChunkGeometry {
+    positions: Vec<Position>,
+    normals: Vec<Normal>,
+    tangents: Vec<Tangent>,
+    tex_coords: Vec<TexCoord>,
+    indices: Vec<u32>,
+}
+
+/// Builds the chunks of a terrain from a [Heightmap].
+///
+/// The terrain spans from the origin along the positive x and z axes; add a `Parent` to the
+/// built chunks to move it.
+#[derive(Debug)]
+pub struct TerrainBuilder {
+    heightmap: Heightmap,
+    scale: Vector3<f32>,
+    chunk_size: u32,
+    lod_levels: u32,
+    lod_distance: f32,
+    skirt_depth: Option<f32>,
+    material: Option<Material>,
+    splat: Option<TerrainSplat>,
+}
+
+impl TerrainBuilder {
+    /// Create a builder of chunks of 64x64 samples with 4 levels of detail.
+    pub fn new(heightmap: Heightmap) -> Self {
+        TerrainBuilder {
+            heightmap,
+            scale: Vector3::new(1.0, 1.0, 1.0),
+            chunk_size: 64,
+            lod_levels: 4,
+            lod_distance: 50.0,
+            skirt_depth: None,
+            material: None,
+            splat: None,
+        }
+    }
+
+    /// Set the distance between samples along the x and z axes, and the height of a sample of
+    /// height 1 along the y axis.
+    pub fn with_scale(mut self, scale: Vector3<f32>) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Set the number of sample intervals along each side of a chunk, rounded up to a power
+    /// of two.
+    pub fn with_chunk_size(mut self, chunk_size: u32) -> Self {
+        self.chunk_size = chunk_size.max(1).next_power_of_two();
+        self
+    }
+
+    /// Set the number of levels of detail, each one skipping every other sample of the previous
+    /// one, and the distance from the camera at which the second level is used. Every further
+    /// level is used from twice the distance of the previous one.
+    pub fn with_lod(mut self, levels: u32, distance: f32) -> Self {
+        self.lod_levels = levels.max(1);
+        self.lod_distance = distance;
+        self
+    }
+
+    /// Set how far the skirts hang below the chunk edges, by default a tenth of the height scale.
+    pub fn with_skirt_depth(mut self, depth: f32) -> Self {
+        self.skirt_depth = Some(depth);
+        self
+    }
+
+    /// Set the material of the chunks, instead of the `MaterialDefaults`. With a splat, its
+    /// albedo is replaced by the baked one of every chunk.
+    pub fn with_material(mut self, material: Material) -> Self {
+        self.material = Some(material);
+        self
+    }
+
+    /// Splat the albedo of the chunks from layers mixed by a blend map.
+    pub fn with_splat(mut self, splat: TerrainSplat) -> Self {
+        self.splat = Some(splat);
+        self
+    }
+
+    /// Returns the heightmap of the terrain.
+    pub fn heightmap(&self) -> &Heightmap {
+        &self.heightmap
+    }
+
+    /// Create the entities of the chunks, returning them.
+    pub fn build(&self, world: &mut World) -> Vec<Entity> {
+        world.register::<LodGroup>();
+        world.register::<BoundingBox>();
+
+        let last_x = self.heightmap.width - 1;
+        let last_z = self.heightmap.depth - 1;
+        let mut chunks = Vec::new();
+        for z0 in (0..last_z).step_by(self.chunk_size as usize) {
+            for x0 in (0..last_x).step_by(self.chunk_size as usize) {
+                chunks.push((
+                    (x0, z0),
+                    (
+                        (x0 + self.chunk_size).min(last_x),
+                        (z0 + self.chunk_size).min(last_z),
+                    ),
+                ));
+            }
+        }
+
+        let material_defaults = world.read_resource::<MaterialDefaults>().0.clone();
+        let base_material = self.material.clone().unwrap_or(material_defaults);
+
+        let mut built = Vec::with_capacity(chunks.len());
+        for &(min, max) in &chunks {
+            let (levels, material) = {
+                let loader = world.read_resource::<Loader>();
+                let mesh_storage = world.read_resource::<AssetStorage<Mesh>>();
+                let texture_storage = world.read_resource::<AssetStorage<Texture>>();
+                let material_storage = world.read_resource::<AssetStorage<Material>>();
+
+                let levels = (0..self.lod_levels)
+                    .map(|level| {
+                        let distance = if level == 0 {
+                            0.0
+                        } else {
+                            self.lod_distance * 2f32.powi(level as i32 - 1)
+                        };
+                        let mesh = loader.load_from_data(
+                            self.chunk_mesh(min, max, level),
+                            (),
+                            &mesh_storage,
+                        );
+                        (distance, mesh)
+                    })
+                    .collect::<Vec<_>>();
+
+                let material = match &self.splat {
+                    Some(splat) => {
+                        let albedo = loader.load_from_data(
+                            splat_texture_data(splat.bake(
+                                (min.0 as f32 / last_x as f32, min.1 as f32 / last_z as f32),
+                                (max.0 as f32 / last_x as f32, max.1 as f32 / last_z as f32),
+                            )),
+                            (),
+                            &texture_storage,
+                        );
+                        Material {
+                            albedo,
+                            ..base_material.clone()
+                        }
+                    }
+                    None => base_material.clone(),
+                };
+                (
+                    levels,
+                    loader.load_from_data(material, (), &material_storage),
+                )
+            };
+
+            let mut transform = Transform::default();
+            transform.set_translation_xyz(
+                min.0 as f32 * self.scale.x,
+                0.0,
+                min.1 as f32 * self.scale.z,
+            );
+            let mesh = levels[0].1.clone();
+            built.push(
+                world
+                    .create_entity()
+                    .with(transform)
+                    .with(mesh)
+                    .with(material)
+                    .with(LodGroup::new(levels))
+                    .with(self.chunk_bounds(min, max))
+                    .build(),
+            );
+        }
+        built
+    }
+
+    fn chunk_mesh(&self, min: (u32, u32), max: (u32, u32), level: u32) -> MeshData {
+        let geometry = self.chunk_geometry(min, max, level);
+        MeshBuilder::new()
+            .with_indices(geometry.indices)
+            .with_vertices(geometry.positions)
+            .with_vertices(geometry.normals)
+            .with_vertices(geometry.tangents)
+            .with_vertices(geometry.tex_coords)
+            .into()
+    }
+
+    fn skirt_depth(&self) -> f32 {
+        self.skirt_depth.unwrap_or(self.scale.y * 0.1)
+    }
+
+    /// Bounds of a chunk in its local space, including the skirts.
+    fn chunk_bounds(&self, min: (u32, u32), max: (u32, u32)) -> BoundingBox {
+        let (mut low, mut high) = (std::f32::INFINITY, std::f32::NEG_INFINITY);
+        for z in min.1..=max.1 {
+            for x in min.0..=max.0 {
+                let height = self.heightmap.height(i64::from(x), i64::from(z));
+                low = low.min(height);
+                high = high.max(height);
+            }
+        }
+        BoundingBox::new(
+            Point3::new(0.0, low * self.scale.y - self.skirt_depth(), 0.0),
+            Point3::new(
+                (max.0 - min.0) as f32 * self.scale.x,
+                high * self.scale.y,
+                (max.1 - min.1) as f32 * self.scale.z,
+            ),
+        )
+    }
+
+    /// Build the grid of a chunk at a level of detail, along with the skirts around it.
+    fn chunk_geometry(&self, min: (u32, u32), max: (u32, u32), level: u32) -> ChunkGeometry {
+        let step = 1 << level;
+        let samples = |from: u32, to: u32| {
+            let mut samples = (from..to).step_by(step).collect::<Vec<_>>();
+            samples.push(to);
+            samples
+        };
+        let xs = samples(min.0, max.0);
+        let zs = samples(min.1, max.1);
+        let (columns, rows) = (xs.len(), zs.len());
+
+        let mut geometry = ChunkGeometry {
+            positions: Vec::new(),
+            normals: Vec::new(),
+            tangents: Vec::new(),
+            tex_coords: Vec::new(),
+            indices: Vec::new(),
+        };
+        let scale = self.scale;
+        let heightmap = &self.heightmap;
+        let push_vertex = |geometry: &mut ChunkGeometry, x: u32, z: u32, drop: f32| {
+            let (xi, zi) = (i64::from(x), i64::from(z));
+            let slope_x = (heightmap.height(xi + 1, zi) - heightmap.height(xi - 1, zi)) * scale.y
+                / (2.0 * scale.x);
+            let slope_z = (heightmap.height(xi, zi + 1) - heightmap.height(xi, zi - 1)) * scale.y
+                / (2.0 * scale.z);
+            let normal = Vector3::new(-slope_x, 1.0, -slope_z).normalize();
+            let tangent = Vector3::new(1.0, slope_x, 0.0).normalize();
+            geometry.positions.push(Position([
+                (x - min.0) as f32 * scale.x,
+                heightmap.height(xi, zi) * scale.y - drop,
+                (z - min.1) as f32 * scale.z,
+            ]));
+            geometry
+                .normals
+                .push(Normal([normal.x, normal.y, normal.z]));
+            geometry
+                .tangents
+                .push(Tangent([tangent.x, tangent.y, tangent.z, 1.0]));
+            geometry.tex_coords.push(TexCoord([
+                (x - min.0) as f32 / (max.0 - min.0) as f32,
+                (z - min.1) as f32 / (max.1 - min.1) as f32,
+            ]));
+        };
+
+        for &z in &zs {
+            for &x in &xs {
+                push_vertex(&mut geometry, x, z, 0.0);
+            }
+        }
+        let index = |column: usize, row: usize| (row * columns + column) as u32;
+        for row in 0..rows - 1 {
+            for column in 0..columns - 1 {
+                let (a, b) = (index(column, row), index(column, row + 1));
+                let (c, d) = (index(column + 1, row), index(column + 1, row + 1));
+                geometry.indices.extend_from_slice(&[a, b, c, c, b, d]);
+            }
+        }
+
+        // Skirts hang from every edge, facing both ways so their winding doesn't matter.
+        let edges = [
+            (0..columns).map(|column| (column, 0)).collect::<Vec<_>>(),
+            (0..columns).map(|column| (column, rows - 1)).collect(),
+            (0..rows).map(|row| (0, row)).collect(),
+            (0..rows).map(|row| (columns - 1, row)).collect(),
+        ];
+        let drop = self.skirt_depth();
+        for edge in &edges {
+            let first = geometry.positions.len() as u32;
+            for &(column, row) in edge {
+                push_vertex(&mut geometry, xs[column], zs[row], drop);
+            }
+            for (i, pair) in edge.windows(2).enumerate() {
+                let (top_a, top_b) = (index(pair[0].0, pair[0].1), index(pair[1].0, pair[1].1));
+                let (bottom_a, bottom_b) = (first + i as u32, first + i as u32 + 1);
+                geometry.indices.extend_from_slice(&[
+                    top_a, top_b, bottom_a, bottom_a, top_b, bottom_b, top_a, bottom_a, top_b,
+                    bottom_a, bottom_b, top_b,
+                ]);
+            }
+        }
+        geometry
+    }
+}
+
+fn splat_texture_data(image: RgbaImage) -> TextureData {
+    let (width, height) = image.dimensions();
+    TextureBuilder::new()
+        .with_kind(Kind::D2(width, height, 1, 1))
+        .with_view_kind(ViewKind::D2)
+        .with_data_width(width)
+        .with_data_height(height)
+        .with_mip_levels(MipLevels::GenerateAuto)
+        .with_sampler_info(SamplerInfo::new(Filter::Linear, WrapMode::Clamp))
+        .with_raw_data(image.into_raw(), Format::Rgba8Srgb)
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunk_levels_skip_samples() {
+        let heights = (0..25).map(|i| (i % 5) as f32 * 0.25).collect();
+        let builder = TerrainBuilder::new(Heightmap::new(5, 5, heights).unwrap())
+            .with_scale(Vector3::new(2.0, 4.0, 2.0))
+            .with_skirt_depth(0.5);
+
+        let fine = builder.chunk_geometry((0, 0), (4, 4), 0);
+        // 5x5 grid and 4 skirts of 5 vertices.
+        assert_eq!(fine.positions.len(), 25 + 20);
+        assert_eq!(fine.indices.len(), 16 * 6 + 4 * 4 * 12);
+        assert_eq!(fine.positions[24].0, [8.0, 4.0, 8.0]);
+        assert_eq!(fine.positions[25].0, [0.0, -0.5, 0.0]);
+        assert_eq!(fine.tex_coords[6].0, [0.25, 0.25]);
+
+        let coarse = builder.chunk_geometry((0, 0), (4, 4), 1);
+        assert_eq!(coarse.positions.len(), 9 + 12);
+        assert_eq!(coarse.positions[4].0, [4.0, 2.0, 4.0]);
+
+        let bounds = builder.chunk_bounds((0, 0), (4, 4));
+        assert_eq!(bounds.min, Point3::new(0.0, -0.5, 0.0));
+        assert_eq!(bounds.max, Point3::new(8.0, 4.0, 8.0));
+    }
+
+    #[test]
+    fn heightmap_samples_bilinearly() {
+        let map = Heightmap::new(2, 2, vec![0.0, 1.0, 2.0, 3.0]).unwrap();
+        assert_eq!(map.sample(0.5, 0.5), 1.5);
+        assert_eq!(map.height(-3, 5), 2.0);
+        assert!(Heightmap::new(2, 2, vec![0.0]).is_err());
+    }
+}
@@ -0,0 +1,443 @@
+//! Streaming of the mip levels of textures by their distance to the camera, under a memory budget.
+//!
+//! Every level of a streamed texture is a pre-built image file, named by [level_name] after
+//! the full size image and half the size of the previous level, e.g. `rock.png`,
+//! `rock.mip1.png` and `rock.mip2.png`. Textures loaded with [TextureStreaming::load] start out
+//! with only their coarsest level resident. The [TextureStreamingSystem] then loads finer levels one at a time for the textures
+//! of materials near the active camera, and drops them again for textures far away or when the
+//! resident levels would use more memory than the budget. Materials refer to the handle returned
+//! by `load`, which the material bindings of the 3D passes resolve to the finest resident level.
+use crate::{
+    camera::{ActiveCamera, Camera},
+    formats::texture::ImageFormat,
+    mtl::Material,
+    types::{Backend, Texture},
+};
+use amethyst_assets::{AssetStorage, Handle, Loader, Progress};
+use amethyst_core::{
+    ecs::prelude::{Entities, Join, Read, ReadExpect, ReadStorage, System, Write},
+    math::{distance, Point3},
+    Transform,
+};
+use fnv::FnvHashMap;
+use std::marker::PhantomData;
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+/// Name of the file of mip `level` of the image `name`, which is `name` itself for the full
+/// size, and has `.mip<level>` inserted before the extension for the other levels.
+pub fn level_name(name: &str, level: u32) -> String {
+    if level == 0 {
+        return name.to_string();
+    }
+    let file_start = name.rfind('/').map_or(0, |slash| slash + 1);
+    match name[file_start..].rfind('.') {
+        Some(dot) => {
+            let (stem, extension) = name.split_at(file_start + dot);
+            format!("{}.mip{}{}", stem, level, extension)
+        }
+        None => format!("{}.mip{}", name, level),
+    }
+}
+
+/// Resource holding the textures streamed by the [TextureStreamingSystem].
+///
+/// The 3D render plugins insert it with a budget of 512 MiB when it isn't present already.
+#[derive(Debug)]
+pub struct TextureStreaming {
+    /// Bytes of GPU memory the resident levels of all streamed textures may use.
+    pub budget: u64,
+    /// Distance from the camera within which textures are wanted at full resolution. Every
+    /// doubling of the distance beyond it drops one more level.
+    pub full_resolution_distance: f32,
+    textures: Vec<StreamedTexture>,
+    lookup: FnvHashMap<u32, usize>,
+    generation: u64,
+}
+
+#[derive(Debug)]
+struct StreamedTexture {
+    name: String,
+    format: ImageFormat,
+    /// Handle of every level, from the full size to the coarsest one, which stays loaded.
+    levels: Vec<Option<Handle<Texture>>>,
+    resident: usize,
+    /// Size of the full resolution, known once the coarsest level is loaded.
+    extent: Option<(u32, u32)>,
+}
+
+impl StreamedTexture {
+    /// Move the resident level one step towards `wanted`, returning whether it changed.
+    ///
+    /// Finer levels are loaded one after another, while a coarser level is loaded directly.
+    /// The resident level stays in use until the next one is loaded.
+    fn step(&mut self, wanted: usize, loader: &Loader, storage: &AssetStorage<Texture>) -> bool {
+        let coarsest = self.levels.len() - 1;
+        let next = if wanted < self.resident {
+            self.resident - 1
+        } else if wanted > self.resident {
+            wanted
+        } else {
+            return false;
+        };
+
+        let resident = self.resident;
+        for (level, handle) in self.levels.iter_mut().enumerate() {
+            if level != resident && level != next && level != coarsest {
+                *handle = None;
+            }
+        }
+
+        match &self.levels[next] {
+            Some(handle) if storage.get(handle).is_some() => {
+                if resident != coarsest {
+                    self.levels[resident] = None;
+                }
+                self.resident = next;
+                true
+            }
+            Some(_) => false,
+            None => {
+                self.levels[next] = Some(loader.load(
+                    level_name(&self.name, next as u32),
+                    self.format.clone(),
+                    (),
+                    storage,
+                ));
+                false
+            }
+        }
+    }
+}
+
+impl Default for TextureStreaming {
+    fn default() -> Self {
+        TextureStreaming::new(512 * 1024 * 1024)
+    }
+}
+
+impl TextureStreaming {
+    /// Create the resource with a budget of `budget` bytes.
+    pub fn new(budget: u64) -> Self {
+        TextureStreaming {
+            budget,
+            full_resolution_distance: 10.0,
+            textures: Vec::new(),
+            lookup: FnvHashMap::default(),
+            generation: 0,
+        }
+    }
+
+    /// Load the image `name` with `levels` mip levels streamed from the files named by
+    /// [level_name], returning the handle to use in materials. Only the coarsest level, with the
+    /// size of the image divided by `2 ^ (levels - 1)`, is loaded right away.
+    pub fn load<P: Progress>(
+        &mut self,
+        name: impl Into<String>,
+        format: ImageFormat,
+        levels: u32,
+        progress: P,
+        loader: &Loader,
+        storage: &AssetStorage<Texture>,
+    ) -> Handle<Texture> {
+        let name = name.into();
+        let coarsest = levels.max(1) as usize - 1;
+        let handle = loader.load(
+            level_name(&name, coarsest as u32),
+            format.clone(),
+            progress,
+            storage,
+        );
+        let mut level_handles = vec![None; coarsest + 1];
+        level_handles[coarsest] = Some(handle.clone());
+        self.lookup.insert(handle.id(), self.textures.len());
+        self.textures.push(StreamedTexture {
+            name,
+            format,
+            levels: level_handles,
+            resident: coarsest,
+            extent: None,
+        });
+        handle
+    }
+
+    /// Stop streaming a texture loaded with `load`, dropping all of its levels but the
+    /// coarsest one, which is kept alive by `handle`. Returns whether it was streamed.
+    pub fn remove(&mut self, handle: &Handle<Texture>) -> bool {
+        match self.lookup.remove(&handle.id()) {
+            Some(index) => {
+                self.textures.swap_remove(index);
+                if let Some(moved) = self.textures.get(index) {
+                    let coarsest = moved.levels.len() - 1;
+                    let id = moved.levels[coarsest].as_ref().unwrap().id();
+                    self.lookup.insert(id, index);
+                }
+                self.generation = self.generation.wrapping_add(1);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the handle of the finest resident level of a texture loaded with `load`, or
+    /// `handle` itself for other textures.
+    pub fn resolve<'a>(&'a self, handle: &'a Handle<Texture>) -> &'a Handle<Texture> {
+        self.find(handle)
+            .and_then(|texture| texture.levels[texture.resident].as_ref())
+            .unwrap_or(handle)
+    }
+
+    /// Returns the finest resident level of a texture loaded with `load`.
+    pub fn resident_level(&self, handle: &Handle<Texture>) -> Option<u32> {
+        self.find(handle).map(|texture| texture.resident as u32)
+    }
+
+    /// Bytes of GPU memory estimated to be used by the resident levels of streamed textures.
+    pub fn resident_bytes(&self) -> u64 {
+        self.textures
+            .iter()
+            .filter_map(|texture| {
+                let extent = texture.extent?;
+                let coarsest = texture.levels.len() - 1;
+                let finer = if texture.resident < coarsest {
+                    level_bytes(extent, texture.resident)
+                } else {
+                    0
+                };
+                Some(finer + level_bytes(extent, coarsest))
+            })
+            .sum()
+    }
+
+    /// Counter changing whenever the resident level of a streamed texture changes.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    fn find(&self, handle: &Handle<Texture>) -> Option<&StreamedTexture> {
+        self.lookup
+            .get(&handle.id())
+            .map(|&index| &self.textures[index])
+    }
+}
+
+/// Size of `level` of an image of the given size.
+fn level_extent((width, height): (u32, u32), level: usize) -> (u32, u32) {
+    ((width >> level).max(1), (height >> level).max(1))
+}
+
+/// Bytes used by `level` of a texture of the given size, along with its mip chain.
+fn level_bytes(extent: (u32, u32), level: usize) -> u64 {
+    let (width, height) = level_extent(extent, level);
+    u64::from(width) * u64::from(height) * 4 * 4 / 3
+}
+
+/// Level wanted for a texture at `distance` from the camera, out of `count` levels.
+fn wanted_level(distance: f32, full_resolution_distance: f32, count: usize) -> usize {
+    let coarsest = count.saturating_sub(1);
+    if distance <= full_resolution_distance || full_resolution_distance <= 0.0 {
+        return 0;
+    }
+    ((distance / full_resolution_distance).log2().ceil() as usize).min(coarsest)
+}
+
+/// Coarsen the `wanted` levels of the farthest textures until the finer levels and the always
+/// loaded coarsest ones fit in `budget`. `textures` holds the distance, level count and full size
+/// of every texture.
+fn fit_to_budget(wanted: &mut [usize], textures: &[(f32, usize, (u32, u32))], budget: u64) {
+    let bytes = |texture: usize, level: usize| {
+        let (_, count, extent) = textures[texture];
+        if level + 1 < count {
+            level_bytes(extent, level)
+        } else {
+            0
+        }
+    };
+    let mut total: u64 = textures
+        .iter()
+        .enumerate()
+        .map(|(index, &(_, count, extent))| {
+            level_bytes(extent, count - 1) + bytes(index, wanted[index])
+        })
+        .sum();
+
+    let mut farthest = (0..textures.len()).collect::<Vec<_>>();
+    farthest.sort_by(|&a, &b| {
+        textures[b]
+            .0
+            .partial_cmp(&textures[a].0)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    for index in farthest {
+        let coarsest = textures[index].1 - 1;
+        while total > budget && wanted[index] < coarsest {
+            total -= bytes(index, wanted[index]);
+            wanted[index] += 1;
+            total += bytes(index, wanted[index]);
+        }
+    }
+}
+
+/// Load and drop the mip levels of the textures in [TextureStreaming] by the distance of the
+/// entities using them from the active camera.
+///
+/// Finer levels are loaded one at a time, so the resolution of a texture coming into view
+/// increases progressively. Note that this should run after `Transform` has been updated for
+/// the current frame.
+#[derive(Debug)]
+pub struct TextureStreamingSystem<B: Backend> {
+    marker: PhantomData<B>,
+}
+
+impl<B: Backend> TextureStreamingSystem<B> {
+    /// Create new texture streaming system
+    pub fn new() -> Self {
+        TextureStreamingSystem {
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, B: Backend> System<'a> for TextureStreamingSystem<B> {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, ActiveCamera>,
+        ReadStorage<'a, Camera>,
+        ReadStorage<'a, Transform>,
+        ReadStorage<'a, Handle<Material>>,
+        Read<'a, AssetStorage<Material>>,
+        Read<'a, AssetStorage<Texture>>,
+        ReadExpect<'a, Loader>,
+        Write<'a, TextureStreaming>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            active,
+            camera,
+            transform,
+            materials,
+            material_storage,
+            texture_storage,
+            loader,
+            mut streaming,
+        ): Self::SystemData,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("texture_streaming_system");
+
+        if streaming.textures.is_empty() {
+            return;
+        }
+
+        for texture in &mut streaming.textures {
+            if texture.extent.is_none() {
+                let coarsest = texture.levels.len() - 1;
+                texture.extent = texture.levels[coarsest]
+                    .as_ref()
+                    .and_then(|handle| texture_storage.get(handle))
+                    .and_then(B::unwrap_texture)
+                    .map(|loaded| {
+                        let extent = loaded.image().kind().extent();
+                        (extent.width << coarsest, extent.height << coarsest)
+                    });
+            }
+        }
+
+        let origin = Point3::origin();
+        let mut camera_join = (&camera, &transform).join();
+        let camera_centroid = active
+            .entity
+            .and_then(|a| camera_join.get(a, &entities))
+            .or_else(|| camera_join.next())
+            .map(|(_, camera_transform)| camera_transform.global_matrix().transform_point(&origin));
+
+        let mut distances = vec![std::f32::INFINITY; streaming.textures.len()];
+        if let Some(camera_centroid) = camera_centroid {
+            for (transform, material) in (&transform, &materials).join() {
+                let material = match material_storage.get(material) {
+                    Some(material) => material,
+                    None => continue,
+                };
+                let centroid = transform.global_matrix().transform_point(&origin);
+                let dist = distance(&centroid, &camera_centroid);
+                for texture in &[
+                    &material.albedo,
+                    &material.emission,
+                    &material.normal,
+                    &material.metallic_roughness,
+                    &material.ambient_occlusion,
+                    &material.cavity,
+                ] {
+                    if let Some(&index) = streaming.lookup.get(&texture.id()) {
+                        distances[index] = distances[index].min(dist);
+                    }
+                }
+            }
+        }
+
+        // Textures of unknown size can't be budgeted yet and keep their coarsest level.
+        let sized = (0..streaming.textures.len())
+            .filter(|&index| streaming.textures[index].extent.is_some())
+            .collect::<Vec<_>>();
+        let budgeted = sized
+            .iter()
+            .map(|&index| {
+                let texture = &streaming.textures[index];
+                (
+                    distances[index],
+                    texture.levels.len(),
+                    texture.extent.unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+        let mut wanted = budgeted
+            .iter()
+            .map(|&(dist, count, _)| wanted_level(dist, streaming.full_resolution_distance, count))
+            .collect::<Vec<_>>();
+        fit_to_budget(&mut wanted, &budgeted, streaming.budget);
+
+        let mut changed = false;
+        for (&index, &wanted) in sized.iter().zip(&wanted) {
+            changed |= streaming.textures[index].step(wanted, &loader, &texture_storage);
+        }
+        if changed {
+            streaming.generation = streaming.generation.wrapping_add(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn levels_are_named_after_the_full_size_image() {
+        assert_eq!(level_name("textures/rock.png", 0), "textures/rock.png");
+        assert_eq!(level_name("textures/rock.png", 2), "textures/rock.mip2.png");
+        assert_eq!(level_name("rock", 1), "rock.mip1");
+        assert_eq!(level_name("v1.2/rock", 1), "v1.2/rock.mip1");
+    }
+
+    #[test]
+    fn levels_drop_with_distance_and_budget() {
+        assert_eq!(wanted_level(5.0, 10.0, 4), 0);
+        assert_eq!(wanted_level(15.0, 10.0, 4), 1);
+        assert_eq!(wanted_level(40.0, 10.0, 4), 2);
+        assert_eq!(wanted_level(1000.0, 10.0, 4), 3);
+        assert_eq!(wanted_level(std::f32::INFINITY, 10.0, 4), 3);
+
+        // Two 1024x1024 textures with 4 levels, the second one farther away.
+        let textures = [(1.0, 4, (1024, 1024)), (50.0, 4, (1024, 1024))];
+        let mut wanted = [0, 0];
+        fit_to_budget(&mut wanted, &textures, 8 * 1024 * 1024);
+        assert_eq!(wanted, [0, 1]);
+
+        let mut wanted = [0, 0];
+        fit_to_budget(&mut wanted, &textures, 0);
+        assert_eq!(wanted, [3, 3]);
+    }
+}
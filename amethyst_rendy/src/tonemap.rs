@@ -0,0 +1,69 @@
+//! Tonemapping and exposure configuration.
+
+use amethyst_core::ecs::{Read, SystemData, World};
+
+/// Curve mapping high dynamic range colors to the displayable range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToneMapOperator {
+    /// Filmic curve fitted to the ACES reference rendering transform.
+    Aces,
+    /// Reinhard curve `color / (1 + color)`.
+    Reinhard,
+}
+
+impl ToneMapOperator {
+    /// Index of the operator passed to tonemapping shaders.
+    pub fn index(self) -> f32 {
+        match self {
+            ToneMapOperator::Aces => 0.0,
+            ToneMapOperator::Reinhard => 1.0,
+        }
+    }
+}
+
+/// Resource configuring the tonemapping applied by `RenderToneMap`.
+///
+/// Changing `auto_exposure` rebuilds the render graph.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ToneMapParams {
+    /// Tonemapping curve.
+    pub operator: ToneMapOperator,
+    /// Exposure compensation in stops. Scales colors by `2^exposure` before tonemapping.
+    pub exposure: f32,
+    /// Adapt exposure to the average luminance of the image, computed on the GPU
+    /// from a luminance histogram every frame.
+    pub auto_exposure: bool,
+    /// Base 2 logarithm of the lowest luminance considered by auto exposure.
+    pub min_log_luminance: f32,
+    /// Base 2 logarithm of the highest luminance considered by auto exposure.
+    pub max_log_luminance: f32,
+    /// Rate at which auto exposure adapts to luminance changes, per second.
+    pub adaptation_speed: f32,
+}
+
+impl Default for ToneMapParams {
+    fn default() -> Self {
+        Self {
+            operator: ToneMapOperator::Aces,
+            exposure: 0.0,
+            auto_exposure: false,
+            min_log_luminance: -8.0,
+            max_log_luminance: 4.0,
+            adaptation_speed: 1.5,
+        }
+    }
+}
+
+impl ToneMapParams {
+    /// Returns the `ToneMapParams` resource, or default parameters if there is none.
+    pub fn fetch(world: &World) -> Self {
+        <Option<Read<'_, ToneMapParams>>>::fetch(world)
+            .map(|p| *p)
+            .unwrap_or_default()
+    }
+
+    /// Linear scale applied to colors before tonemapping.
+    pub fn exposure_scale(&self) -> f32 {
+        self.exposure.exp2()
+    }
+}
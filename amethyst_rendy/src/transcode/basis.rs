@@ -0,0 +1,230 @@
+//! Transcoder backed by the official Basis Universal transcoder.
+use super::{TextureTranscoder, TranscodeTarget, TranscodedTexture};
+use crate::formats::container::parse_ktx2_uastc;
+use amethyst_error::{format_err, Error};
+use basis_universal::{
+    BasisTextureType, DecodeFlags, LowLevelUastcTranscoder, SliceParametersUastc,
+    TranscodeParameters, Transcoder, TranscoderBlockFormat, TranscoderTextureFormat,
+};
+
+const BASIS_SIGNATURE: &[u8] = b"sB";
+
+/// `TextureTranscoder` built on the [basis-universal] crate, used when no `Transcoder` resource
+/// is inserted.
+///
+/// It transcodes `.basis` files, and KTX2 files holding UASTC data without supercompression.
+/// ETC1S data in KTX2 files, compressed with BasisLZ, isn't supported.
+///
+/// [basis-universal]: https://crates.io/crates/basis-universal
+#[derive(Debug, Clone, Copy)]
+pub struct BasisUniversalTranscoder;
+
+impl BasisUniversalTranscoder {
+    /// Initializes the transcoder tables, which only happens once.
+    pub fn new() -> Self {
+        basis_universal::transcoder_init();
+        BasisUniversalTranscoder
+    }
+}
+
+impl Default for BasisUniversalTranscoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TextureTranscoder for BasisUniversalTranscoder {
+    fn transcode(&self, file: &[u8], target: TranscodeTarget) -> Result<TranscodedTexture, Error> {
+        if file.starts_with(BASIS_SIGNATURE) {
+            transcode_basis(file, target)
+        } else {
+            transcode_ktx2_uastc(file, target)
+        }
+    }
+}
+
+fn transcode_basis(file: &[u8], target: TranscodeTarget) -> Result<TranscodedTexture, Error> {
+    let mut transcoder = Transcoder::new();
+    if !transcoder.validate_header(file) {
+        return Err(format_err!("Invalid Basis Universal file"));
+    }
+    let images = transcoder.image_count(file);
+    let (layers, cube) = match transcoder.basis_texture_type(file) {
+        BasisTextureType::TextureType2D => (1, false),
+        BasisTextureType::TextureType2DArray => (images, false),
+        BasisTextureType::TextureTypeCubemapArray => (images, true),
+        other => {
+            return Err(format_err!(
+                "Unsupported Basis Universal texture type {:?}",
+                other
+            ))
+        }
+    };
+    if layers == 0 || layers > u32::from(u16::max_value()) {
+        return Err(format_err!("Basis Universal file has {} images", layers));
+    }
+    let base = transcoder
+        .image_level_description(file, 0, 0)
+        .ok_or_else(|| format_err!("Basis Universal file has no base level"))?;
+    // Every image of an array or cube map has the same levels.
+    let level_count = transcoder.image_level_count(file, 0);
+
+    transcoder
+        .prepare_transcoding(file)
+        .map_err(|_| format_err!("Failed to prepare Basis Universal transcoding"))?;
+    let levels = (0..level_count)
+        .map(|level_index| {
+            let mut level = Vec::new();
+            for image_index in 0..layers {
+                let data = transcoder
+                    .transcode_image_level(
+                        file,
+                        texture_format(target),
+                        TranscodeParameters {
+                            image_index,
+                            level_index,
+                            ..Default::default()
+                        },
+                    )
+                    .map_err(|err| {
+                        format_err!(
+                            "Failed to transcode image {} level {}: {:?}",
+                            image_index,
+                            level_index,
+                            err
+                        )
+                    })?;
+                level.extend(data);
+            }
+            Ok(level)
+        })
+        .collect::<Result<Vec<_>, Error>>();
+    transcoder.end_transcoding();
+
+    Ok(TranscodedTexture {
+        width: base.original_width,
+        height: base.original_height,
+        layers: layers as u16,
+        cube,
+        levels: levels?,
+    })
+}
+
+fn transcode_ktx2_uastc(file: &[u8], target: TranscodeTarget) -> Result<TranscodedTexture, Error> {
+    let (texture, has_alpha) = parse_ktx2_uastc(file)?;
+    let transcoder = LowLevelUastcTranscoder::new();
+    let levels = texture
+        .levels
+        .iter()
+        .enumerate()
+        .map(|(level, data)| {
+            let original_width = (texture.width >> level).max(1);
+            let original_height = (texture.height >> level).max(1);
+            let num_blocks_x = (original_width + 3) / 4;
+            let num_blocks_y = (original_height + 3) / 4;
+            let slice_size = (num_blocks_x * num_blocks_y) as usize * 16;
+
+            let mut transcoded = Vec::new();
+            for slice in data.chunks(slice_size) {
+                let blocks = transcoder
+                    .transcode_slice(
+                        slice,
+                        SliceParametersUastc {
+                            num_blocks_x,
+                            num_blocks_y,
+                            has_alpha,
+                            original_width,
+                            original_height,
+                        },
+                        DecodeFlags::empty(),
+                        block_format(target),
+                    )
+                    .map_err(|err| {
+                        format_err!("Failed to transcode UASTC level {}: {:?}", level, err)
+                    })?;
+                transcoded.extend(blocks);
+            }
+            Ok(transcoded)
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    Ok(TranscodedTexture {
+        width: texture.width,
+        height: texture.height,
+        layers: texture.layers,
+        cube: texture.cube,
+        levels,
+    })
+}
+
+fn texture_format(target: TranscodeTarget) -> TranscoderTextureFormat {
+    match target {
+        TranscodeTarget::Astc4x4 => TranscoderTextureFormat::ASTC_4x4_RGBA,
+        TranscodeTarget::Bc7 => TranscoderTextureFormat::BC7_RGBA,
+        TranscodeTarget::Etc2Rgba => TranscoderTextureFormat::ETC2_RGBA,
+        TranscodeTarget::Rgba8 => TranscoderTextureFormat::RGBA32,
+    }
+}
+
+fn block_format(target: TranscodeTarget) -> TranscoderBlockFormat {
+    match target {
+        TranscodeTarget::Astc4x4 => TranscoderBlockFormat::ASTC_4x4,
+        TranscodeTarget::Bc7 => TranscoderBlockFormat::BC7,
+        TranscodeTarget::Etc2Rgba => TranscoderBlockFormat::ETC2_RGBA,
+        TranscodeTarget::Rgba8 => TranscoderBlockFormat::RGBA32,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use basis_universal::{BasisTextureFormat, Compressor, CompressorParams};
+
+    const COLOR: [u8; 4] = [200, 100, 50, 255];
+
+    // An 8x8 texture of one color, encoded as a UASTC `.basis` file.
+    fn uastc_basis_file() -> Vec<u8> {
+        let pixels = COLOR.repeat(8 * 8);
+        let mut params = CompressorParams::new();
+        params.set_basis_format(BasisTextureFormat::UASTC4x4);
+        params.set_generate_mipmaps(false);
+        params.set_print_status_to_stdout(false);
+        params.source_image_mut(0).init(&pixels, 8, 8, 4);
+
+        let mut compressor = Compressor::default();
+        unsafe {
+            assert!(compressor.init(&params));
+            compressor.process().unwrap();
+        }
+        compressor.basis_file().to_vec()
+    }
+
+    #[test]
+    fn uastc_basis_file_is_transcoded() {
+        let file = uastc_basis_file();
+        let transcoder = BasisUniversalTranscoder::new();
+
+        let texture = transcoder.transcode(&file, TranscodeTarget::Rgba8).unwrap();
+        assert_eq!((texture.width, texture.height), (8, 8));
+        assert_eq!((texture.layers, texture.cube), (1, false));
+        assert_eq!(texture.levels.len(), 1);
+        assert_eq!(texture.levels[0].len(), 8 * 8 * 4);
+        for pixel in texture.levels[0].chunks(4) {
+            assert_eq!(pixel, COLOR);
+        }
+
+        // Four 4x4 blocks of 16 bytes.
+        let texture = transcoder.transcode(&file, TranscodeTarget::Bc7).unwrap();
+        assert_eq!(texture.levels.len(), 1);
+        assert_eq!(texture.levels[0].len(), 4 * 16);
+    }
+
+    #[test]
+    fn corrupt_basis_file_is_rejected() {
+        let mut file = uastc_basis_file();
+        file.truncate(file.len() / 2);
+        assert!(BasisUniversalTranscoder::new()
+            .transcode(&file, TranscodeTarget::Rgba8)
+            .is_err());
+    }
+}
@@ -0,0 +1,148 @@
+//! Transcoding of Basis Universal textures when they are loaded.
+//!
+//! Basis Universal textures, stored in `.basis` files or in KTX2 files as ETC1S or UASTC data, are
+//! transcoded by `TextureProcessorSystem` to the best block format the backend supports, so one
+//! file serves every platform. The transcoder is taken from the `Transcoder` resource, wrapping an
+//! implementation of `TextureTranscoder`. Without one, the `basis-universal` feature provides
+//! `BasisUniversalTranscoder`, built on the official Basis Universal transcoder.
+#[cfg(feature = "basis-universal")]
+pub use self::basis::BasisUniversalTranscoder;
+
+use crate::{
+    formats::{container::ContainerTexture, texture::TextureCompression},
+    types::TextureData,
+};
+use amethyst_error::{format_err, Error};
+use rendy::{
+    factory::Factory,
+    hal::{format::Format, image::SamplerInfo, Backend},
+};
+
+#[cfg(feature = "basis-universal")]
+mod basis;
+
+/// Formats Basis Universal textures can be transcoded to, from the most to the least preferred.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TranscodeTarget {
+    /// ASTC with 4x4 blocks.
+    Astc4x4,
+    /// BC7.
+    Bc7,
+    /// ETC2 with an EAC alpha channel.
+    Etc2Rgba,
+    /// Uncompressed 8 bit RGBA, for backends without any supported block format.
+    Rgba8,
+}
+
+impl TranscodeTarget {
+    /// Returns the preferred target the backend of `factory` can sample.
+    pub fn best_supported<B: Backend>(factory: &Factory<B>) -> Self {
+        if TextureCompression::Astc.is_supported(factory) {
+            TranscodeTarget::Astc4x4
+        } else if TextureCompression::Bc.is_supported(factory) {
+            TranscodeTarget::Bc7
+        } else if TextureCompression::Etc2.is_supported(factory) {
+            TranscodeTarget::Etc2Rgba
+        } else {
+            TranscodeTarget::Rgba8
+        }
+    }
+
+    /// Returns the texture format of the transcoded data.
+    pub fn format(self, srgb: bool) -> Format {
+        match (self, srgb) {
+            (TranscodeTarget::Astc4x4, true) => Format::Astc4x4Srgb,
+            (TranscodeTarget::Astc4x4, false) => Format::Astc4x4Unorm,
+            (TranscodeTarget::Bc7, true) => Format::Bc7Srgb,
+            (TranscodeTarget::Bc7, false) => Format::Bc7Unorm,
+            (TranscodeTarget::Etc2Rgba, true) => Format::Etc2R8g8b8a8Srgb,
+            (TranscodeTarget::Etc2Rgba, false) => Format::Etc2R8g8b8a8Unorm,
+            (TranscodeTarget::Rgba8, true) => Format::Rgba8Srgb,
+            (TranscodeTarget::Rgba8, false) => Format::Rgba8Unorm,
+        }
+    }
+}
+
+/// Transcodes Basis Universal `.basis` and KTX2 files.
+pub trait TextureTranscoder: Send + Sync + 'static {
+    /// Transcodes every level, layer and face of `file` to `target`.
+    fn transcode(&self, file: &[u8], target: TranscodeTarget) -> Result<TranscodedTexture, Error>;
+}
+
+/// Texture data produced by a `TextureTranscoder`.
+#[derive(Debug, Clone)]
+pub struct TranscodedTexture {
+    /// Width of the base level in texels.
+    pub width: u32,
+    /// Height of the base level in texels.
+    pub height: u32,
+    /// Number of array layers, six for each cube map.
+    pub layers: u16,
+    /// Whether the layers are faces of cube maps.
+    pub cube: bool,
+    /// Blocks of each mip level, starting from the base level, with every layer one after the
+    /// other.
+    pub levels: Vec<Vec<u8>>,
+}
+
+/// Resource holding the `TextureTranscoder` used by `TextureProcessorSystem`.
+pub struct Transcoder(Box<dyn TextureTranscoder>);
+
+impl Transcoder {
+    /// Wraps a transcoder to be inserted as a resource.
+    pub fn new(transcoder: impl TextureTranscoder) -> Self {
+        Transcoder(Box::new(transcoder))
+    }
+}
+
+impl std::fmt::Debug for Transcoder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Transcoder").finish()
+    }
+}
+
+/// Basis Universal file waiting to be transcoded, as loaded by `BasisFormat` or `Ktx2Format`.
+#[derive(Debug, Clone)]
+pub struct TranscodeSource {
+    /// Contents of the `.basis` or KTX2 file.
+    pub file: Vec<u8>,
+    /// Whether the texture holds sRGB colors.
+    pub srgb: bool,
+    /// Sampler of the transcoded texture.
+    pub sampler_info: SamplerInfo,
+}
+
+impl TranscodeSource {
+    /// Transcodes the file to `target`, returning texture data ready to be uploaded.
+    pub(crate) fn transcode(
+        &self,
+        transcoder: Option<&Transcoder>,
+        target: TranscodeTarget,
+    ) -> Result<TextureData, Error> {
+        let texture = match transcoder {
+            Some(transcoder) => transcoder.0.transcode(&self.file, target)?,
+            #[cfg(feature = "basis-universal")]
+            None => BasisUniversalTranscoder::new().transcode(&self.file, target)?,
+            #[cfg(not(feature = "basis-universal"))]
+            None => {
+                return Err(format_err!(
+                    "A `Transcoder` resource or the `basis-universal` feature is needed to load \
+                     Basis Universal textures"
+                ))
+            }
+        };
+        if texture.levels.is_empty() {
+            return Err(format_err!("Transcoded texture has no levels"));
+        }
+
+        Ok(ContainerTexture {
+            format: target.format(self.srgb),
+            width: texture.width,
+            height: texture.height,
+            layers: texture.layers,
+            cube: texture.cube,
+            levels: texture.levels,
+        }
+        .into_texture_data(self.sampler_info.clone()))
+    }
+}
@@ -1,7 +1,10 @@
 //! Transparency component implementation
 use amethyst_assets::PrefabData;
-use amethyst_core::ecs::{prelude::Component, storage::NullStorage, Entity, WriteStorage};
+use amethyst_core::ecs::{
+    prelude::Component, storage::NullStorage, Entity, Read, SystemData, World, WriteStorage,
+};
 use amethyst_error::Error;
+use derivative::Derivative;
 
 /// Transparent mesh component
 #[derive(Clone, Copy, Debug, Default, serde::Serialize, serde::Deserialize)]
@@ -26,3 +29,29 @@ impl<'a> PrefabData<'a> for Transparent {
         Ok(())
     }
 }
+
+/// Resource selecting how the 3D plugins blend [Transparent] meshes into [`Target::Main`].
+///
+/// [`Target::Main`]: crate::bundle::Target::Main
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Derivative)]
+#[derivative(Default)]
+pub enum TransparencyMode {
+    /// Sort meshes back to front and blend them over the opaque meshes in the main pass.
+    #[derivative(Default)]
+    Sorted,
+    /// Accumulate meshes in any order with weighted blended order-independent transparency,
+    /// inserted by the `RenderOrderIndependentTransparency` plugin which resolves them.
+    ///
+    /// Intersecting meshes blend smoothly without sorting, but colors of overlapping layers
+    /// are averaged by weights favoring near and opaque layers rather than exactly ordered.
+    WeightedBlended,
+}
+
+impl TransparencyMode {
+    /// Returns the `TransparencyMode` resource, or `Sorted` if there is none.
+    pub fn fetch(world: &World) -> Self {
+        <Option<Read<'_, TransparencyMode>>>::fetch(world)
+            .map(|mode| *mode)
+            .unwrap_or_default()
+    }
+}
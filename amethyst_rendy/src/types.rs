@@ -137,8 +137,30 @@ pub struct MeshData(
 );
 
 /// Newtype for TextureBuilder prefab usage.
+///
+/// The second field holds pre-built mip levels following the base level, as loaded from texture
+/// containers like KTX and DDS. They are uploaded as they are, so the builder needs
+/// `MipLevels::RawLevels` covering them and a format the backend supports without conversion.
+///
+/// The third field holds a Basis Universal file, which replaces the builder once it has been
+/// transcoded to a format the backend supports.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct TextureData(pub rendy::texture::TextureBuilder<'static>);
+pub struct TextureData(
+    pub rendy::texture::TextureBuilder<'static>,
+    #[serde(skip)] pub Vec<TextureLevel>,
+    #[serde(skip)] pub Option<crate::transcode::TranscodeSource>,
+);
+
+/// Pixels of a mip level of a texture, for all of its layers.
+#[derive(Debug, Clone)]
+pub struct TextureLevel {
+    /// Width of the level in texels, rounded up to whole blocks for compressed formats.
+    pub data_width: u32,
+    /// Height of the level in texels, rounded up to whole blocks for compressed formats.
+    pub data_height: u32,
+    /// Pixels of every layer of the level, one layer after the other.
+    pub data: Vec<u8>,
+}
 
 impl From<rendy::mesh::MeshBuilder<'static>> for MeshData {
     fn from(builder: rendy::mesh::MeshBuilder<'static>) -> Self {
@@ -148,7 +170,7 @@ impl From<rendy::mesh::MeshBuilder<'static>> for MeshData {
 
 impl From<rendy::texture::TextureBuilder<'static>> for TextureData {
     fn from(builder: rendy::texture::TextureBuilder<'static>) -> Self {
-        Self(builder)
+        Self(builder, Vec::new(), None)
     }
 }
 
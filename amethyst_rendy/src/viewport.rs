@@ -0,0 +1,102 @@
+//! Rendering several cameras side by side in the window, e.g. for split-screen.
+
+use crate::{bundle::Target, camera::Camera};
+use amethyst_core::ecs::{
+    Component, DenseVecStorage, Entities, Entity, Join, ReadStorage, SystemData, World,
+};
+use rendy::hal::pso::Rect;
+
+/// Component rendering a camera into a rectangle of the window instead of the whole window.
+///
+/// The camera renders `target`, which is defined by [RenderViewport](crate::plugins::RenderViewport)
+/// and drawn by the plugins added with that target. The rectangle is given in fractions of
+/// the window size, from its top left corner. The camera projection should have the aspect
+/// ratio of the rectangle, see `aspect_ratio`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraViewport {
+    /// Render target drawn from the camera.
+    pub target: Target,
+    /// Left edge of the viewport, between 0 and 1.
+    pub x: f32,
+    /// Top edge of the viewport, between 0 and 1.
+    pub y: f32,
+    /// Width of the viewport, between 0 and 1.
+    pub width: f32,
+    /// Height of the viewport, between 0 and 1.
+    pub height: f32,
+}
+
+impl Component for CameraViewport {
+    type Storage = DenseVecStorage<Self>;
+}
+
+impl CameraViewport {
+    /// Create a `CameraViewport` rendering `target` into the given rectangle of the window.
+    pub fn new(target: Target, x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {
+            target,
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Rectangle of the viewport in pixels of a `width` by `height` window.
+    ///
+    /// Edges are rounded to whole pixels, so adjacent viewports neither overlap nor leave gaps.
+    pub fn rect(&self, width: u32, height: u32) -> Rect {
+        let edge = |fraction: f32, size: u32| (fraction.max(0.0).min(1.0) * size as f32).round();
+        let left = edge(self.x, width);
+        let top = edge(self.y, height);
+        Rect {
+            x: left as i16,
+            y: top as i16,
+            w: (edge(self.x + self.width, width) - left) as i16,
+            h: (edge(self.y + self.height, height) - top) as i16,
+        }
+    }
+
+    /// Aspect ratio of the viewport in a `width` by `height` window, for the camera projection.
+    pub fn aspect_ratio(&self, width: u32, height: u32) -> f32 {
+        let rect = self.rect(width, height);
+        f32::from(rect.w) / f32::from(rect.h.max(1))
+    }
+}
+
+/// Find the camera entity rendering `target` into a viewport, along with its `CameraViewport`.
+///
+/// When several cameras render the same target, the first one found is used.
+pub fn viewport_camera(world: &World, target: Target) -> Option<(Entity, CameraViewport)> {
+    let (entities, cameras, viewports) = <(
+        Entities<'_>,
+        ReadStorage<'_, Camera>,
+        ReadStorage<'_, CameraViewport>,
+    )>::fetch(world);
+    (&entities, &cameras, &viewports)
+        .join()
+        .find(|(_, _, viewport)| viewport.target == target)
+        .map(|(entity, _, viewport)| (entity, *viewport))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_viewports_tile_window() {
+        let left = CameraViewport::new(Target::Custom("left"), 0.0, 0.0, 0.5, 1.0);
+        let right = CameraViewport::new(Target::Custom("right"), 0.5, 0.0, 0.5, 1.0);
+        let (left, right) = (left.rect(1281, 720), right.rect(1281, 720));
+        assert_eq!((left.x, left.y, left.w, left.h), (0, 0, 641, 720));
+        assert_eq!((right.x, right.y, right.w, right.h), (641, 0, 640, 720));
+    }
+
+    #[test]
+    fn viewport_is_clamped_to_window() {
+        let viewport = CameraViewport::new(Target::Main, 0.75, 0.5, 0.5, 0.75);
+        let rect = viewport.rect(800, 600);
+        assert_eq!((rect.x, rect.y, rect.w, rect.h), (600, 300, 200, 300));
+        assert_eq!(viewport.aspect_ratio(800, 600), 200.0 / 300.0);
+    }
+}
@@ -0,0 +1,43 @@
+//! Vignette configuration.
+
+use amethyst_core::ecs::{Read, SystemData, World};
+
+/// Resource configuring the vignette applied by `RenderVignette`.
+///
+/// All fields are read every frame, so the vignette can be animated, e.g. flashing
+/// red as damage feedback. While disabled, the pass copies its input unchanged.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VignetteParams {
+    /// Tint the edges of the image.
+    pub enabled: bool,
+    /// Opacity of the vignette at the corners of the image, between 0 and 1.
+    pub intensity: f32,
+    /// Distance from the center, relative to the distance of the corners, where
+    /// the vignette starts.
+    pub radius: f32,
+    /// Distance, relative to the distance of the corners, over which the vignette fades in.
+    pub smoothness: f32,
+    /// Linear color the edges are tinted towards.
+    pub color: [f32; 3],
+}
+
+impl Default for VignetteParams {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            intensity: 0.5,
+            radius: 0.6,
+            smoothness: 0.4,
+            color: [0.0; 3],
+        }
+    }
+}
+
+impl VignetteParams {
+    /// Returns the `VignetteParams` resource, or default parameters if there is none.
+    pub fn fetch(world: &World) -> Self {
+        <Option<Read<'_, VignetteParams>>>::fetch(world)
+            .map(|p| *p)
+            .unwrap_or_default()
+    }
+}
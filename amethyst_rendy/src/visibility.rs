@@ -1,6 +1,7 @@
-//! Transparency, visibility sorting and camera centroid culling for 3D Meshes.
+//! Transparency, visibility sorting and camera frustum culling for 3D Meshes.
 use crate::{
     camera::{ActiveCamera, Camera},
+    occlusion::OcclusionCulling,
     transparent::Transparent,
 };
 use amethyst_core::{
@@ -10,7 +11,7 @@ use amethyst_core::{
             Component, DenseVecStorage, Entities, Entity, Join, Read, ReadStorage, System, Write,
         },
     },
-    math::{convert, distance_squared, Matrix4, Point3, Vector4},
+    math::{convert, Matrix4, Point3, Vector3, Vector4},
     Hidden, HiddenPropagate, Transform,
 };
 
@@ -22,16 +23,23 @@ use thread_profiler::profile_scope;
 
 /// Resource for controlling what entities should be rendered, and whether to draw them ordered or
 /// not, which is useful for transparent surfaces.
+///
+/// Opaque and transparent entities are split into separate queues: an entity is in exactly one
+/// of them, depending on whether it has the [Transparent] component.
 #[derive(Default, Debug)]
 pub struct Visibility {
-    /// Visible entities that can be drawn in any order
+    /// Visible opaque entities that can be drawn in any order
     pub visible_unordered: BitSet,
-    /// Visible entities that need to be drawn in the given order
+    /// Visible transparent entities that need to be drawn in the given order, back to front
     pub visible_ordered: Vec<Entity>,
 }
 
 /// Determine what entities are visible to the camera, and which are not. Will also sort transparent
-/// entities back to front based on distance from camera.
+/// entities back to front based on their depth in view space, the distance along the view
+/// direction of the camera rather than to its position.
+///
+/// With the [OcclusionCulling] resource, entities found occluded are culled too and the
+/// bounds of the remaining ones are submitted to be tested.
 ///
 /// Note that this should run after `Transform` has been updated for the current frame, and
 /// before rendering occurs.
@@ -78,12 +86,79 @@ impl Component for BoundingSphere {
     type Storage = DenseVecStorage<Self>;
 }
 
+/// Defines a object's axis aligned bounding box in local space, used by frustum culling.
+///
+/// Fits meshes that are longer along one axis much tighter than a `BoundingSphere`, and takes
+/// precedence over it when an entity has both.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BoundingBox {
+    /// Corner of the box with the smallest coordinates.
+    pub min: Point3<f32>,
+    /// Corner of the box with the largest coordinates.
+    pub max: Point3<f32>,
+}
+
+impl Default for BoundingBox {
+    fn default() -> Self {
+        Self {
+            min: Point3::new(-1.0, -1.0, -1.0),
+            max: Point3::new(1.0, 1.0, 1.0),
+        }
+    }
+}
+
+impl BoundingBox {
+    /// Create a new `BoundingBox` spanning the supplied corners.
+    pub fn new(min: Point3<f32>, max: Point3<f32>) -> Self {
+        Self { min, max }
+    }
+
+    /// Create the smallest `BoundingBox` containing all the given points, like the vertex
+    /// positions of a mesh. An empty set of points gives an empty box at the origin.
+    pub fn from_points<I>(points: I) -> Self
+    where
+        I: IntoIterator<Item = Point3<f32>>,
+    {
+        let mut points = points.into_iter();
+        let first = match points.next() {
+            Some(point) => point,
+            None => return Self::new(Point3::origin(), Point3::origin()),
+        };
+        points.fold(Self::new(first, first), |mut bounds, point| {
+            for i in 0..3 {
+                bounds.min[i] = bounds.min[i].min(point[i]);
+                bounds.max[i] = bounds.max[i].max(point[i]);
+            }
+            bounds
+        })
+    }
+
+    /// Returns the center of the box.
+    pub fn center(&self) -> Point3<f32> {
+        Point3::from((self.min.coords + self.max.coords) * 0.5)
+    }
+
+    /// Returns half of the size of the box along each axis.
+    pub fn half_extents(&self) -> Vector3<f32> {
+        (self.max - self.min) * 0.5
+    }
+
+    /// Returns the smallest `BoundingSphere` containing the box.
+    pub fn bounding_sphere(&self) -> BoundingSphere {
+        BoundingSphere::new(self.center(), self.half_extents().magnitude())
+    }
+}
+
+impl Component for BoundingBox {
+    type Storage = DenseVecStorage<Self>;
+}
+
 #[derive(Debug, Clone)]
 struct Internals {
     entity: Entity,
     transparent: bool,
     centroid: Point3<f32>,
-    camera_distance: f32,
+    view_depth: f32,
 }
 
 impl VisibilitySortingSystem {
@@ -104,6 +179,8 @@ impl<'a> System<'a> for VisibilitySortingSystem {
         ReadStorage<'a, Transparent>,
         ReadStorage<'a, Transform>,
         ReadStorage<'a, BoundingSphere>,
+        ReadStorage<'a, BoundingBox>,
+        Option<Write<'a, OcclusionCulling>>,
     );
 
     fn run(
@@ -118,6 +195,8 @@ impl<'a> System<'a> for VisibilitySortingSystem {
             transparent,
             transform,
             bound,
+            aabb,
+            mut occlusion,
         ): Self::SystemData,
     ) {
         #[cfg(feature = "profiler")]
@@ -134,11 +213,16 @@ impl<'a> System<'a> for VisibilitySortingSystem {
             .or_else(|| camera_join.next())
             .unwrap_or((&defcam, &identity));
 
-        let camera_centroid = camera_transform.global_matrix().transform_point(&origin);
-        let frustum = Frustum::new(
-            convert::<_, Matrix4<f32>>(camera.matrix)
-                * camera_transform.global_matrix().try_inverse().unwrap(),
-        );
+        let view = camera_transform.global_matrix().try_inverse().unwrap();
+        let frustum = Frustum::new(convert::<_, Matrix4<f32>>(camera.matrix) * view);
+
+        if let Some(occlusion) = &mut occlusion {
+            occlusion.boxes.clear();
+        }
+        let mut occlusion = occlusion
+            .as_mut()
+            .map(|occlusion| &mut **occlusion)
+            .filter(|occlusion| occlusion.enabled);
 
         self.centroids.clear();
         self.centroids.extend(
@@ -146,36 +230,57 @@ impl<'a> System<'a> for VisibilitySortingSystem {
                 &*entities,
                 &transform,
                 bound.maybe(),
+                aabb.maybe(),
                 !&hidden,
                 !&hidden_prop,
             )
                 .join()
-                .map(|(entity, transform, sphere, _, _)| {
-                    let pos = sphere.map_or(&origin, |s| &s.center);
+                .filter_map(|(entity, transform, sphere, aabb, _, _)| {
                     let matrix = transform.global_matrix();
-                    (
-                        entity,
-                        matrix.transform_point(&pos),
-                        sphere.map_or(1.0, |s| s.radius)
-                            * matrix[(0, 0)].max(matrix[(1, 1)]).max(matrix[(2, 2)]),
-                    )
+                    let (centroid, half_extents, visible) = if let Some(aabb) = aabb {
+                        let centroid = matrix.transform_point(&aabb.center());
+                        let half_extents = transform_extents(matrix, &aabb.half_extents());
+                        let visible = frustum.check_box(&centroid, &half_extents);
+                        (centroid, half_extents, visible)
+                    } else {
+                        let pos = sphere.map_or(&origin, |s| &s.center);
+                        let centroid = matrix.transform_point(pos);
+                        let radius = sphere.map_or(1.0, |s| s.radius)
+                            * matrix[(0, 0)].max(matrix[(1, 1)]).max(matrix[(2, 2)]);
+                        let visible = frustum.check_sphere(&centroid, radius);
+                        (centroid, Vector3::repeat(radius), visible)
+                    };
+                    if visible {
+                        Some((entity, centroid, half_extents))
+                    } else {
+                        None
+                    }
+                })
+                .filter(|(entity, centroid, half_extents)| match &mut occlusion {
+                    Some(occlusion) => {
+                        occlusion.submit(*entity, *centroid, *half_extents);
+                        !occlusion.is_occluded(*entity)
+                    }
+                    None => true,
                 })
-                .filter(|(_, centroid, radius)| frustum.check_sphere(centroid, *radius))
                 .map(|(entity, centroid, _)| Internals {
                     entity,
                     transparent: transparent.contains(entity),
                     centroid,
-                    camera_distance: distance_squared(&centroid, &camera_centroid),
+                    // The camera looks down its negative z axis.
+                    view_depth: -view.transform_point(&centroid).z,
                 }),
         );
         self.transparent.clear();
         self.transparent
             .extend(self.centroids.iter().filter(|c| c.transparent).cloned());
 
+        // Entities at the same depth are kept in a stable order, so they don't flicker.
         self.transparent.sort_by(|a, b| {
-            b.camera_distance
-                .partial_cmp(&a.camera_distance)
+            b.view_depth
+                .partial_cmp(&a.view_depth)
                 .unwrap_or(Ordering::Equal)
+                .then_with(|| a.entity.id().cmp(&b.entity.id()))
         });
 
         visibility.visible_unordered.clear();
@@ -193,6 +298,16 @@ impl<'a> System<'a> for VisibilitySortingSystem {
     }
 }
 
+/// Half extents of the box with the given local half extents after transforming it by `matrix`,
+/// so the axis aligned box around it still contains it when rotated.
+fn transform_extents(matrix: &Matrix4<f32>, half_extents: &Vector3<f32>) -> Vector3<f32> {
+    Vector3::from_fn(|row, _| {
+        (0..3)
+            .map(|col| matrix[(row, col)].abs() * half_extents[col])
+            .sum()
+    })
+}
+
 /// Simple view Frustum implementation
 #[derive(Debug)]
 pub struct Frustum {
@@ -232,4 +347,142 @@ impl Frustum {
         }
         true
     }
+    /// Check if the given axis aligned box is within the Frustum
+    pub fn check_box(&self, center: &Point3<f32>, half_extents: &Vector3<f32>) -> bool {
+        for plane in &self.planes {
+            let radius = plane.x.abs() * half_extents.x
+                + plane.y.abs() * half_extents.y
+                + plane.z.abs() * half_extents.z;
+            if plane.xyz().dot(&center.coords) + plane.w <= -radius {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use amethyst_core::ecs::{Builder, RunNow, World, WorldExt};
+
+    #[test]
+    fn transparent_entities_sorted_by_view_depth() {
+        let mut world = World::new();
+        let mut system = VisibilitySortingSystem::new();
+        System::setup(&mut system, &mut world);
+
+        let positioned = |x, z| {
+            let mut transform = Transform::default();
+            transform.set_translation_xyz(x, 0.0, z);
+            transform.copy_local_to_global();
+            transform
+        };
+        world
+            .create_entity()
+            .with(Camera::perspective(1.0, 2.8, 0.1))
+            .with(Transform::default())
+            .build();
+        // Further from the camera position, but nearer along its view direction.
+        let side = world
+            .create_entity()
+            .with(positioned(8.0, -4.0))
+            .with(Transparent)
+            .build();
+        let ahead = world
+            .create_entity()
+            .with(positioned(0.0, -5.0))
+            .with(Transparent)
+            .build();
+        let opaque = world.create_entity().with(positioned(0.0, -2.0)).build();
+
+        system.run_now(&world);
+        let visibility = world.read_resource::<Visibility>();
+        assert_eq!(visibility.visible_ordered, vec![ahead, side]);
+        assert!(visibility.visible_unordered.contains(opaque.id()));
+        assert!(!visibility.visible_unordered.contains(side.id()));
+    }
+
+    #[test]
+    fn bounding_box_from_points() {
+        let bounds = BoundingBox::from_points(vec![
+            Point3::new(1.0, -2.0, 0.5),
+            Point3::new(-1.0, 3.0, 0.0),
+            Point3::new(0.0, 0.0, -4.0),
+        ]);
+        assert_eq!(bounds.min, Point3::new(-1.0, -2.0, -4.0));
+        assert_eq!(bounds.max, Point3::new(1.0, 3.0, 0.5));
+        assert_eq!(bounds.center(), Point3::new(0.0, 0.5, -1.75));
+        assert_eq!(bounds.half_extents(), Vector3::new(1.0, 2.5, 2.25));
+    }
+
+    #[test]
+    fn frustum_checks_box_against_planes() {
+        // Orthographic volume spanning [-1, 1] along every axis.
+        let frustum = Frustum::new(Matrix4::identity());
+        let half_extents = Vector3::new(0.5, 0.5, 0.5);
+        assert!(frustum.check_box(&Point3::new(0.0, 0.0, 0.0), &half_extents));
+        assert!(frustum.check_box(&Point3::new(1.2, 0.0, 0.0), &half_extents));
+        assert!(!frustum.check_box(&Point3::new(1.6, 0.0, 0.0), &half_extents));
+        assert!(!frustum.check_box(&Point3::new(0.0, -2.0, 0.0), &half_extents));
+    }
+
+    #[test]
+    fn rotated_box_extents_grow() {
+        let rotation = Matrix4::from_euler_angles(0.0, 0.0, std::f32::consts::FRAC_PI_4);
+        let extents = transform_extents(&rotation, &Vector3::new(1.0, 1.0, 1.0));
+        assert!((extents.x - 2.0f32.sqrt()).abs() < 1e-5);
+        assert!((extents.y - 2.0f32.sqrt()).abs() < 1e-5);
+        assert!((extents.z - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn frustum_checks_sphere_against_planes() {
+        let frustum = Frustum::new(Matrix4::identity());
+        assert!(frustum.check_sphere(&Point3::new(0.0, 0.0, 0.0), 0.1));
+        assert!(frustum.check_sphere(&Point3::new(0.0, 1.4, 0.0), 0.5));
+        assert!(!frustum.check_sphere(&Point3::new(0.0, 1.6, 0.0), 0.5));
+        assert!(!frustum.check_sphere(&Point3::new(0.0, 0.0, -3.0), 1.0));
+    }
+
+    #[test]
+    fn entities_outside_the_view_are_culled() {
+        let mut world = World::new();
+        let mut system = VisibilitySortingSystem::new();
+        System::setup(&mut system, &mut world);
+
+        let positioned = |x, z| {
+            let mut transform = Transform::default();
+            transform.set_translation_xyz(x, 0.0, z);
+            transform.copy_local_to_global();
+            transform
+        };
+        world
+            .create_entity()
+            .with(Camera::perspective(1.0, std::f32::consts::FRAC_PI_2, 0.1))
+            .with(Transform::default())
+            .build();
+        let ahead = world.create_entity().with(positioned(0.0, -5.0)).build();
+        let behind = world.create_entity().with(positioned(0.0, 5.0)).build();
+        let aside = world.create_entity().with(positioned(20.0, -5.0)).build();
+        // Its bounds reach into the view, though its origin is outside.
+        let large = world
+            .create_entity()
+            .with(positioned(8.0, -5.0))
+            .with(BoundingSphere::origin(4.0))
+            .build();
+        let hidden = world
+            .create_entity()
+            .with(positioned(0.0, -5.0))
+            .with(Hidden)
+            .build();
+
+        system.run_now(&world);
+        let visibility = world.read_resource::<Visibility>();
+        assert!(visibility.visible_unordered.contains(ahead.id()));
+        assert!(visibility.visible_unordered.contains(large.id()));
+        assert!(!visibility.visible_unordered.contains(behind.id()));
+        assert!(!visibility.visible_unordered.contains(aside.id()));
+        assert!(!visibility.visible_unordered.contains(hidden.id()));
+    }
 }
@@ -0,0 +1,259 @@
+//! Water surfaces with animated normals, planar reflections and refraction.
+
+use crate::{
+    camera::{ActiveCamera, Camera, ClipPlane},
+    types::Texture,
+};
+use amethyst_assets::Handle;
+use amethyst_core::{
+    ecs::prelude::{
+        Component, DenseVecStorage, Entities, Entity, Join, Read, ReadStorage, System, WriteStorage,
+    },
+    math::{Matrix4, Point3, UnitQuaternion, Vector2, Vector3},
+    Transform,
+};
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+/// Component drawing a water surface on the plane of its entity, with `RenderWater`.
+///
+/// The surface is a rectangle centered on the `Transform` of the entity, spanning `size`
+/// along its local x and z axes, and facing its local y axis. Its normals are two layers of
+/// `normal_map` scrolling in different directions. The scene behind the surface is refracted
+/// from the image and depth of the main target, and fades to `deep_color` the further light
+/// travels through the water. Reflections come from the camera `reflection`, which is placed
+/// every frame by the [WaterReflectionSystem], and are blended with the refraction by the
+/// Fresnel term of the view angle.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Water {
+    /// Size of the surface along the local x and z axes.
+    pub size: Vector2<f32>,
+    /// Tangent space normal map of the waves.
+    pub normal_map: Handle<Texture>,
+    /// World units covered by one repetition of the normal map.
+    pub normal_tiling: f32,
+    /// Scrolling velocities of the two layers of the normal map, in world units per second.
+    pub wave_velocities: [Vector2<f32>; 2],
+    /// Steepness of the waves, scaling the slopes of the normal map.
+    pub wave_strength: f32,
+    /// Linear color of the water where no light from the scene behind it gets through.
+    pub deep_color: [f32; 3],
+    /// Distance light travels through the water before fading to about a third towards
+    /// `deep_color`, in world units.
+    pub visibility: f32,
+    /// Offset of the refracted image by the slopes of the waves, in screen space.
+    pub refraction_distortion: f32,
+    /// Offset of the reflected image by the slopes of the waves, in screen space.
+    pub reflection_distortion: f32,
+    /// Fraction of the light reflected when looking straight down at the surface.
+    pub reflectance: f32,
+    /// Linear color reflected while there is no reflection camera or its texture is loading.
+    pub sky_color: [f32; 3],
+    /// Camera with a [CameraTarget](crate::render_texture::CameraTarget) rendering the
+    /// reflection of the scene in the surface.
+    pub reflection: Option<Entity>,
+}
+
+impl Water {
+    /// Water surface of 10 by 10 world units with waves from `normal_map`.
+    pub fn new(normal_map: Handle<Texture>) -> Self {
+        Water {
+            size: Vector2::new(10.0, 10.0),
+            normal_map,
+            normal_tiling: 4.0,
+            wave_velocities: [Vector2::new(0.2, 0.1), Vector2::new(-0.1, 0.15)],
+            wave_strength: 0.5,
+            deep_color: [0.01, 0.05, 0.08],
+            visibility: 2.0,
+            refraction_distortion: 0.02,
+            reflection_distortion: 0.02,
+            reflectance: 0.02,
+            sky_color: [0.5, 0.6, 0.7],
+            reflection: None,
+        }
+    }
+
+    /// Set the size of the surface.
+    pub fn with_size(mut self, size: Vector2<f32>) -> Self {
+        self.size = size;
+        self
+    }
+
+    /// Set the color of deep water and the distance light travels through the water.
+    pub fn with_deep_color(mut self, deep_color: [f32; 3], visibility: f32) -> Self {
+        self.deep_color = deep_color;
+        self.visibility = visibility;
+        self
+    }
+
+    /// Reflect the image rendered by the `camera` entity, which must have a `CameraTarget`.
+    pub fn with_reflection(mut self, camera: Entity) -> Self {
+        self.reflection = Some(camera);
+        self
+    }
+
+    /// Returns the plane of the surface, facing the local y axis of `transform`.
+    pub fn plane(transform: &Transform) -> ClipPlane {
+        let matrix = transform.global_matrix();
+        ClipPlane::new(
+            matrix.transform_vector(&Vector3::y()),
+            matrix.transform_point(&Point3::origin()),
+        )
+    }
+}
+
+impl Component for Water {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Place the reflection camera of every [Water] surface at the mirror image of the active
+/// camera.
+///
+/// The reflection camera gets the projection of the active camera and a `ClipPlane` on the
+/// surface, facing the active camera, so nothing on the other side of the water is reflected.
+/// Its `Transform` is replaced, so it shouldn't have a parent.
+#[derive(Default, Debug)]
+pub struct WaterReflectionSystem;
+
+impl WaterReflectionSystem {
+    /// Create new water reflection system
+    pub fn new() -> Self {
+        WaterReflectionSystem
+    }
+}
+
+impl<'a> System<'a> for WaterReflectionSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, Water>,
+        Read<'a, ActiveCamera>,
+        WriteStorage<'a, Camera>,
+        WriteStorage<'a, Transform>,
+        WriteStorage<'a, ClipPlane>,
+    );
+
+    fn run(
+        &mut self,
+        (entities, waters, active_camera, mut cameras, mut transforms, mut clip_planes): Self::SystemData,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("water_reflection_system");
+
+        let reflections = (&entities, &waters)
+            .join()
+            .filter_map(|(entity, water)| water.reflection.map(|camera| (entity, camera)))
+            .collect::<Vec<_>>();
+        if reflections.is_empty() {
+            return;
+        }
+
+        let is_reflection = |entity| reflections.iter().any(|&(_, camera)| camera == entity);
+        let main = active_camera
+            .entity
+            .filter(|&entity| cameras.contains(entity) && transforms.contains(entity))
+            .or_else(|| {
+                (&entities, &cameras, &transforms)
+                    .join()
+                    .map(|(entity, _, _)| entity)
+                    .find(|&entity| !is_reflection(entity))
+            });
+        let (camera, view) = match main {
+            Some(main) if !is_reflection(main) => (
+                cameras.get(main).unwrap().clone(),
+                *transforms.get(main).unwrap().global_matrix(),
+            ),
+            _ => return,
+        };
+
+        for (water, reflection) in reflections {
+            if !entities.is_alive(reflection) {
+                continue;
+            }
+            let plane = match transforms.get(water) {
+                Some(transform) => facing_plane(Water::plane(transform), &view),
+                None => continue,
+            };
+            // The camera has no parent, so its global matrix is updated right away.
+            let mut transform = mirror_transform(&view, &plane);
+            transform.copy_local_to_global();
+            transforms
+                .insert(reflection, transform)
+                .expect("Reflection camera is alive");
+            cameras
+                .insert(reflection, camera.clone())
+                .expect("Reflection camera is alive");
+            clip_planes
+                .insert(reflection, plane)
+                .expect("Reflection camera is alive");
+        }
+    }
+}
+
+/// Returns `plane`, flipped if needed to face the camera with the global matrix `view`, so a
+/// camera under water reflects the underwater scene.
+fn facing_plane(plane: ClipPlane, view: &Matrix4<f32>) -> ClipPlane {
+    if plane.signed_distance(&view.transform_point(&Point3::origin())) < 0.0 {
+        ClipPlane {
+            normal: -plane.normal,
+            distance: -plane.distance,
+        }
+    } else {
+        plane
+    }
+}
+
+/// Transform of the camera seeing the mirror image through `plane` of what the camera with
+/// the global matrix `view` sees.
+///
+/// Mirroring the camera would flip the winding of triangles, so it keeps the mirrored view
+/// and up directions instead, which flips its image horizontally. The water samples it at
+/// the projection of its surface, which undoes the flip.
+fn mirror_transform(view: &Matrix4<f32>, plane: &ClipPlane) -> Transform {
+    let reflect = |v: Vector3<f32>| v - plane.normal * (2.0 * plane.normal.dot(&v));
+    let position = view.transform_point(&Point3::origin());
+    let forward = reflect(view.transform_vector(&-Vector3::z()));
+    let up = reflect(view.transform_vector(&Vector3::y()));
+
+    let mut transform = Transform::default();
+    transform
+        .set_translation(position.coords - plane.normal * (2.0 * plane.signed_distance(&position)));
+    // Cameras look along their negative z axis.
+    transform.set_rotation(UnitQuaternion::face_towards(&-forward, &up));
+    transform
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn mirrors_camera_through_plane() {
+        let plane = ClipPlane::new(Vector3::y(), Point3::new(0.0, 1.0, 0.0));
+        let mut camera = Transform::default();
+        camera.set_translation_xyz(2.0, 5.0, 3.0);
+        camera.face_towards(Vector3::new(2.0, 1.0, 0.0), Vector3::y());
+        camera.copy_local_to_global();
+
+        let mut mirrored = mirror_transform(camera.global_matrix(), &plane);
+        mirrored.copy_local_to_global();
+        let matrix = mirrored.global_matrix();
+        assert_relative_eq!(
+            matrix.transform_point(&Point3::origin()),
+            Point3::new(2.0, -3.0, 3.0),
+            epsilon = 1e-5
+        );
+        // The camera looks up at the point where the original one looks down.
+        let forward = matrix.transform_vector(&-Vector3::z());
+        assert_relative_eq!(
+            forward,
+            Vector3::new(0.0, 4.0, -3.0).normalize(),
+            epsilon = 1e-5
+        );
+
+        let below = facing_plane(plane, mirrored.global_matrix());
+        assert_relative_eq!(below.normal, -Vector3::y());
+        assert_relative_eq!(below.distance, -1.0);
+    }
+}
@@ -19,6 +19,7 @@ pub use map::{Map, MapStorage, Tile, TileMap};
 pub use morton::{MortonEncoder, MortonEncoder2D};
 pub use pass::{
     DrawTiles2D, DrawTiles2DBounds, DrawTiles2DBoundsDefault, DrawTiles2DDesc, RenderTiles2D,
+    TILE_CHUNK_SIZE,
 };
 
 use amethyst_core::math::Vector3;
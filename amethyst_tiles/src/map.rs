@@ -4,7 +4,7 @@ use crate::{CoordinateEncoder, TileOutOfBoundsError};
 use amethyst_assets::{Asset, Handle};
 use amethyst_core::{
     ecs::{Component, HashMapStorage, World},
-    math::{Matrix4, Point3, Vector3},
+    math::{Matrix4, Point3, Vector2, Vector3},
     Transform,
 };
 use amethyst_rendy::{palette::Srgba, SpriteSheet};
@@ -115,6 +115,9 @@ pub struct TileMap<T: Tile, E: CoordinateEncoder = crate::MortonEncoder2D> {
 
     pub(crate) version: u64,
 
+    #[serde(default)]
+    pub(crate) layer_parallax: Vec<Vector2<f32>>,
+
     #[serde(skip)]
     pub(crate) sprite_sheet: Option<Handle<SpriteSheet>>,
 
@@ -139,6 +142,30 @@ impl<T: Tile, E: CoordinateEncoder> TileMap<T, E> {
         self.version
     }
 
+    /// Set how much the tiles of the layer at `z` coordinate `layer` follow the camera.
+    ///
+    /// A factor of `1.0` on an axis, the default, keeps the layer in place like the rest of the
+    /// world, and `0.0` moves it with the camera so it stays still on screen. Factors in
+    /// between make background layers scroll slower than the camera, values above `1.0` make
+    /// foreground layers scroll faster.
+    pub fn set_layer_parallax(&mut self, layer: u32, parallax: Vector2<f32>) {
+        let layer = layer as usize;
+        if self.layer_parallax.len() <= layer {
+            self.layer_parallax
+                .resize(layer + 1, Vector2::new(1.0, 1.0));
+        }
+        self.layer_parallax[layer] = parallax;
+    }
+
+    /// Parallax factor of the layer at `z` coordinate `layer`, see `set_layer_parallax`.
+    #[must_use]
+    pub fn layer_parallax(&self, layer: u32) -> Vector2<f32> {
+        self.layer_parallax
+            .get(layer as usize)
+            .copied()
+            .unwrap_or_else(|| Vector2::new(1.0, 1.0))
+    }
+
     ///Create a new instance of `TileMap`.
     pub fn new(
         dimensions: Vector3<u32>,
@@ -164,6 +191,7 @@ impl<T: Tile, E: CoordinateEncoder> TileMap<T, E> {
             transform,
             encoder,
             version: 1,
+            layer_parallax: Vec::new(),
         }
     }
 }
@@ -547,4 +575,18 @@ mod tests {
             &map_transform,
         );
     }
+
+    #[test]
+    pub fn layer_parallax_defaults_to_world() {
+        let mut map = TileMap::<TestTile, FlatEncoder>::new(
+            Vector3::new(4, 4, 3),
+            Vector3::new(10, 10, 1),
+            None,
+        );
+        map.set_layer_parallax(1, Vector2::new(0.5, 0.25));
+
+        assert_eq!(map.layer_parallax(0), Vector2::new(1.0, 1.0));
+        assert_eq!(map.layer_parallax(1), Vector2::new(0.5, 0.25));
+        assert_eq!(map.layer_parallax(2), Vector2::new(1.0, 1.0));
+    }
 }
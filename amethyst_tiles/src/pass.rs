@@ -16,7 +16,7 @@ use amethyst_rendy::{
     batch::{GroupIterator, OneLevelBatch, OrderedTwoLevelBatch},
     bundle::{RenderOrder, RenderPlan, RenderPlugin, Target},
     camera::{ActiveCamera, Camera},
-    pipeline::{PipelineDescBuilder, PipelinesBuilder},
+    pipeline::{PipelineCache, PipelineDescBuilder, PipelinesBuilder},
     pod::IntoPod,
     rendy::{
         command::{QueueId, RenderPassEncoder},
@@ -36,6 +36,7 @@ use amethyst_rendy::{
     resources::Tint as TintComponent,
     sprite::{SpriteRender, SpriteSheet},
     sprite_visibility::SpriteVisibility,
+    stats::DrawCounter,
     submodules::{
         gather::CameraGatherer, DynamicUniform, DynamicVertexBuffer, FlatEnvironmentSub, TextureId,
         TextureSub,
@@ -77,6 +78,9 @@ lazy_static::lazy_static! {
 
 }
 
+/// Width and height in tiles of the chunks of a layer culled against the view of the camera.
+pub const TILE_CHUNK_SIZE: u32 = 16;
+
 /// Trait to describe how rendering tiles may be culled for the tilemap to render
 pub trait DrawTiles2DBounds: 'static + std::fmt::Debug + Send + Sync {
     /// Returns the region to render the tiles
@@ -115,7 +119,7 @@ impl<B: Backend, T: Tile, E: CoordinateEncoder, Z: DrawTiles2DBounds> RenderGrou
         _ctx: &GraphContext<B>,
         factory: &mut Factory<B>,
         _queue: QueueId,
-        _aux: &World,
+        aux: &World,
         framebuffer_width: u32,
         framebuffer_height: u32,
         subpass: hal::pass::Subpass<'_, B>,
@@ -130,8 +134,11 @@ impl<B: Backend, T: Tile, E: CoordinateEncoder, Z: DrawTiles2DBounds> RenderGrou
         let textures = TextureSub::new(factory)?;
         let vertex = DynamicVertexBuffer::new();
 
+        let pipeline_cache = aux.try_fetch::<PipelineCache<B>>();
+
         let (pipeline, pipeline_layout) = build_tiles_pipeline(
             factory,
+            pipeline_cache.as_ref().and_then(|cache| cache.raw()),
             subpass,
             framebuffer_width,
             framebuffer_height,
@@ -158,6 +165,10 @@ impl<B: Backend, T: Tile, E: CoordinateEncoder, Z: DrawTiles2DBounds> RenderGrou
 /// for  transparency to occur correctly. If viewed from "underneath", transparency ordering issues will occur.
 ///
 /// In shorter terms, this means that the camera must "Look Down" at the tiles.
+///
+/// Each layer of the region returned by the `DrawTiles2DBounds` is split into chunks of
+/// `TILE_CHUNK_SIZE` tiles per side, and only the chunks in view of the active camera are drawn.
+/// Layers are offset by their `TileMap::layer_parallax` factor relative to the camera.
 #[derive(Derivative)]
 #[derivative(Debug(bound = ""))]
 pub struct DrawTiles2D<
@@ -209,7 +220,13 @@ impl<B: Backend, T: Tile, E: CoordinateEncoder, Z: DrawTiles2DBounds> RenderGrou
 
         sprites_ref.swap_clear();
 
-        let CameraGatherer { projview, .. } = CameraGatherer::gather(world);
+        let camera = CameraGatherer::gather(world);
+        let camera_position = camera
+            .view
+            .try_inverse()
+            .map_or_else(Vector2::zeros, |camera| {
+                Vector2::new(camera[(0, 3)], camera[(1, 3)])
+            });
 
         let mut tilemap_args = vec![];
 
@@ -225,53 +242,53 @@ impl<B: Backend, T: Tile, E: CoordinateEncoder, Z: DrawTiles2DBounds> RenderGrou
                 None => continue,
             };
 
-            let tilemap_args_index = tilemap_args.len();
-            let map_coordinate_transform: [[f32; 4]; 4] = (*tile_map.transform()).into();
-            let map_transform: [[f32; 4]; 4] = if let Some(transform) = transform {
-                (*transform.global_matrix()).into()
-            } else {
-                Matrix4::identity().into()
-            };
-            tilemap_args.push(TileMapArgs {
-                proj: projview.proj,
-                view: projview.view,
-                map_coordinate_transform: map_coordinate_transform.into(),
-                map_transform: map_transform.into(),
-                sprite_dimensions: [
-                    tile_map.tile_dimensions().x as f32,
-                    tile_map.tile_dimensions().y as f32,
-                ]
-                .into(),
-            });
-
-            compute_region::<T, E, Z>(&tile_map, &world)
-                .iter()
-                .filter_map(|coord| {
-                    let tile = tile_map.get(&coord).unwrap();
-                    if let Some(sprite_number) = tile.sprite(coord, world) {
-                        let (batch_data, texture) = TileArgs::from_data(
-                            &tex_storage,
-                            &sprite_sheet,
-                            sprite_number,
-                            Some(&TintComponent(tile.tint(coord, world))),
-                            &coord,
-                        )?;
-
-                        let (tex_id, this_changed) = textures_ref.insert(
-                            factory,
-                            world,
-                            texture,
-                            hal::image::Layout::ShaderReadOnlyOptimal,
-                        )?;
-                        changed = changed || this_changed;
-
-                        return Some((tex_id, batch_data));
-                    }
-                    None
-                })
-                .for_each_group(|tex_id, batch_data| {
-                    sprites_ref.insert(tex_id, tilemap_args_index, batch_data.drain(..))
-                });
+            let map_transform =
+                transform.map_or_else(Matrix4::identity, |transform| *transform.global_matrix());
+            let region = compute_region::<T, E, Z>(&tile_map, &world);
+
+            for layer in region.min.z..=region.max.z {
+                let layer_transform =
+                    parallax_transform(camera_position, tile_map.layer_parallax(layer))
+                        * map_transform;
+                let clip = camera.projection * camera.view * layer_transform * tile_map.transform();
+
+                let tilemap_args_index = tilemap_args.len();
+                tilemap_args.push(layer_args(&camera, tile_map, &layer_transform));
+
+                let layer_region = Region::new(
+                    Point3::new(region.min.x, region.min.y, layer),
+                    Point3::new(region.max.x, region.max.y, layer),
+                );
+                layer_chunks(&layer_region)
+                    .filter(|chunk| chunk_visible(&clip, chunk))
+                    .flat_map(|chunk| chunk.iter())
+                    .filter_map(|coord| {
+                        let tile = tile_map.get(&coord).unwrap();
+                        if let Some(sprite_number) = tile.sprite(coord, world) {
+                            let (batch_data, texture) = TileArgs::from_data(
+                                &tex_storage,
+                                &sprite_sheet,
+                                sprite_number,
+                                Some(&TintComponent(tile.tint(coord, world))),
+                                &coord,
+                            )?;
+
+                            let (tex_id, this_changed) = textures_ref.insert(
+                                factory,
+                                world,
+                                texture,
+                                hal::image::Layout::ShaderReadOnlyOptimal,
+                            )?;
+                            changed = changed || this_changed;
+
+                            return Some((tex_id, batch_data));
+                        }
+                        None
+                    })
+                    .for_each_group(|tex_id, batch_data| {
+                        sprites_ref.insert(tex_id, tilemap_args_index, batch_data.drain(..))
+                    });
+            }
         }
 
         self.textures.maintain(factory, world);
@@ -307,7 +324,7 @@ impl<B: Backend, T: Tile, E: CoordinateEncoder, Z: DrawTiles2DBounds> RenderGrou
         mut encoder: RenderPassEncoder<'_, B>,
         index: usize,
         _subpass: hal::pass::Subpass<'_, B>,
-        _world: &World,
+        world: &World,
     ) {
         #[cfg(feature = "profiler")]
         profile_scope!("draw");
@@ -316,6 +333,7 @@ impl<B: Backend, T: Tile, E: CoordinateEncoder, Z: DrawTiles2DBounds> RenderGrou
         encoder.bind_graphics_pipeline(&self.pipeline);
 
         self.vertex.bind(index, 0, 0, &mut encoder);
+        let mut draws = DrawCounter::fetch(world);
         for (&tex, ranges) in self.sprites.iter() {
             for (tilemap_args_index, range) in ranges {
                 let env = self.env.get(*tilemap_args_index).unwrap();
@@ -327,6 +345,7 @@ impl<B: Backend, T: Tile, E: CoordinateEncoder, Z: DrawTiles2DBounds> RenderGrou
                     unsafe {
                         encoder.draw(0..4, range.to_owned());
                     }
+                    draws.draw_quads(range.end - range.start);
                 }
             }
         }
@@ -363,8 +382,88 @@ fn compute_region<T: Tile, E: CoordinateEncoder, Z: DrawTiles2DBounds>(
     region
 }
 
+/// Build the uniform arguments used to draw one layer of a tile map.
+#[allow(clippy::cast_precision_loss)]
+fn layer_args<T: Tile, E: CoordinateEncoder>(
+    camera: &CameraGatherer,
+    tile_map: &TileMap<T, E>,
+    layer_transform: &Matrix4<f32>,
+) -> TileMapArgs {
+    let map_coordinate_transform: [[f32; 4]; 4] = (*tile_map.transform()).into();
+    let layer_transform: [[f32; 4]; 4] = (*layer_transform).into();
+    TileMapArgs {
+        proj: camera.projview.proj,
+        view: camera.projview.view,
+        map_coordinate_transform: map_coordinate_transform.into(),
+        map_transform: layer_transform.into(),
+        sprite_dimensions: [
+            tile_map.tile_dimensions().x as f32,
+            tile_map.tile_dimensions().y as f32,
+        ]
+        .into(),
+    }
+}
+
+/// Offset a layer towards the camera so that layers with a parallax below 1 scroll slower than
+/// the world.
+fn parallax_transform(camera_position: Vector2<f32>, parallax: Vector2<f32>) -> Matrix4<f32> {
+    let offset = camera_position.component_mul(&(Vector2::new(1.0, 1.0) - parallax));
+    Matrix4::new_translation(&Vector3::new(offset.x, offset.y, 0.0))
+}
+
+/// Split a region of a single layer into chunks of at most `TILE_CHUNK_SIZE` tiles per side.
+fn layer_chunks(region: &Region) -> impl Iterator<Item = Region> {
+    let (min, max) = (region.min, region.max);
+    (min.y..=max.y)
+        .step_by(TILE_CHUNK_SIZE as usize)
+        .flat_map(move |y| {
+            (min.x..=max.x)
+                .step_by(TILE_CHUNK_SIZE as usize)
+                .map(move |x| {
+                    Region::new(
+                        Point3::new(x, y, min.z),
+                        Point3::new(
+                            (x + TILE_CHUNK_SIZE - 1).min(max.x),
+                            (y + TILE_CHUNK_SIZE - 1).min(max.y),
+                            min.z,
+                        ),
+                    )
+                })
+        })
+}
+
+/// Check whether any tile of a chunk may be visible, given the transform from the tile
+/// coordinates of the map to clip space.
+#[allow(clippy::cast_precision_loss)]
+fn chunk_visible(clip: &Matrix4<f32>, chunk: &Region) -> bool {
+    // Tiles are centered on their coordinate, and the y axis of tile coordinates points down.
+    let (min_x, max_x) = (chunk.min.x as f32 - 0.5, chunk.max.x as f32 + 0.5);
+    let (min_y, max_y) = (-(chunk.max.y as f32) - 0.5, -(chunk.min.y as f32) + 0.5);
+    let z = chunk.min.z as f32;
+
+    let mut outside = [true; 4];
+    for &(x, y) in &[
+        (min_x, min_y),
+        (max_x, min_y),
+        (min_x, max_y),
+        (max_x, max_y),
+    ] {
+        let corner = clip * Vector4::new(x, y, z, 1.0);
+        if corner.w <= 0.0 {
+            // Behind the camera of a perspective projection, don't bother clipping.
+            return true;
+        }
+        outside[0] &= corner.x < -corner.w;
+        outside[1] &= corner.x > corner.w;
+        outside[2] &= corner.y < -corner.w;
+        outside[3] &= corner.y > corner.w;
+    }
+    !outside.iter().any(|&outside| outside)
+}
+
 fn build_tiles_pipeline<B: Backend>(
     factory: &Factory<B>,
+    cache: Option<&B::PipelineCache>,
     subpass: hal::pass::Subpass<'_, B>,
     framebuffer_width: u32,
     framebuffer_height: u32,
@@ -396,7 +495,7 @@ fn build_tiles_pipeline<B: Backend>(
                     write: false,
                 }),
         )
-        .build(factory, None);
+        .build_cached(factory, cache);
 
     shaders.dispose(factory);
 
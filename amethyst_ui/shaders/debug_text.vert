@@ -0,0 +1,48 @@
+#version 450
+
+layout(std140, set = 0, binding = 0) uniform ViewArgs {
+    uniform mat4 proj;
+    uniform mat4 view;
+    uniform mat4 proj_view;
+};
+
+layout(std140, set = 2, binding = 0) uniform DebugTextViewArgs {
+    uniform vec2 inverse_window_size;
+};
+
+layout(location = 0) in vec3 position;
+layout(location = 1) in vec2 coords;
+layout(location = 2) in vec2 dimensions;
+layout(location = 3) in vec4 tex_coord_bounds;
+layout(location = 4) in vec4 color;
+
+layout(location = 0) out vec2 out_tex_coords;
+layout(location = 1) out vec4 out_color;
+layout(location = 2) out vec4 out_color_bias;
+
+const vec2 positions[4] = vec2[](
+    vec2(0.5, -0.5), // Right bottom
+    vec2(-0.5, -0.5), // Left bottom
+    vec2(0.5, 0.5), // Right top
+    vec2(-0.5, 0.5) // Left top
+);
+
+void main() {
+    vec2 pos = positions[gl_VertexIndex];
+
+    vec2 coords_base = pos + vec2(0.5);
+    out_tex_coords = mix(tex_coord_bounds.xy, tex_coord_bounds.zw, coords_base);
+    out_color = color;
+    out_color_bias = vec4(1.0, 1.0, 1.0, 0.0);
+
+    vec4 anchor = proj_view * vec4(position, 1.0);
+    if (anchor.w <= 0.0) {
+        // label behind the camera, move the quad outside of the clip volume
+        gl_Position = vec4(2.0, 2.0, 2.0, 1.0);
+        return;
+    }
+
+    // glyph offsets are in pixels with y pointing up
+    vec2 offset = (vec2(coords.x, -coords.y) + dimensions * pos) * inverse_window_size * 2.0;
+    gl_Position = anchor + vec4(offset * anchor.w, 0.0, 0.0);
+}
@@ -0,0 +1,38 @@
+#version 450
+
+layout(std140, set = 0, binding = 0) uniform ViewArgs {
+    uniform mat4 proj;
+    uniform mat4 view;
+    uniform mat4 proj_view;
+};
+
+layout(location = 0) in mat4 model;
+layout(location = 4) in vec2 coords;
+layout(location = 5) in vec2 dimensions;
+layout(location = 6) in vec4 tex_coord_bounds;
+layout(location = 7) in vec4 color;
+
+layout(location = 0) out vec2 out_tex_coords;
+layout(location = 1) out vec4 out_color;
+layout(location = 2) out vec4 out_color_bias;
+
+const vec2 positions[4] = vec2[](
+    vec2(0.5, -0.5), // Right bottom
+    vec2(-0.5, -0.5), // Left bottom
+    vec2(0.5, 0.5), // Right top
+    vec2(-0.5, 0.5) // Left top
+);
+
+void main() {
+    vec2 pos = positions[gl_VertexIndex];
+
+    // texture rows go down while glyph coords go up
+    vec2 coords_base = vec2(pos.x, -pos.y) + vec2(0.5);
+    out_tex_coords = mix(tex_coord_bounds.xy, tex_coord_bounds.zw, coords_base);
+    out_color = color;
+    out_color_bias = vec4(1.0, 1.0, 1.0, 0.0);
+
+    // glyph coords are in pixels with y pointing up, the model matrix scales them to ems
+    vec2 local = coords + dimensions * pos;
+    gl_Position = proj_view * model * vec4(local, 0.0, 1.0);
+}
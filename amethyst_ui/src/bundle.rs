@@ -2,10 +2,12 @@
 
 use crate::{
     BlinkSystem, CacheSelectionOrderSystem, DragWidgetSystemDesc, FontAsset, NoCustomUi,
-    ResizeSystemDesc, SelectionKeyboardSystemDesc, SelectionMouseSystemDesc,
-    TextEditingInputSystemDesc, TextEditingMouseSystemDesc, ToNativeWidget,
-    UiButtonActionRetriggerSystemDesc, UiButtonSystemDesc, UiLoaderSystemDesc, UiMouseSystem,
-    UiSoundRetriggerSystemDesc, UiSoundSystemDesc, UiTransformSystemDesc, WidgetId,
+    ResizeSystemDesc, ScrollAreaSystem, SelectionKeyboardSystemDesc, SelectionMouseSystemDesc,
+    TextEditingInputSystemDesc, TextEditingMouseSystemDesc, ToNativeWidget, ToggleGroupSystemDesc,
+    UiButtonActionRetriggerSystemDesc, UiButtonSystemDesc, UiCheckboxSystemDesc,
+    UiDropdownSystemDesc, UiFlexSystem, UiLoaderSystemDesc, UiMouseSystem, UiProgressBarSystem,
+    UiSliderSystemDesc, UiSoundRetriggerSystemDesc, UiSoundSystemDesc, UiThemeSystemDesc,
+    UiTooltipSystemDesc, UiTransformSystemDesc, UiTweenSystem, WidgetId,
 };
 use amethyst_assets::Processor;
 use amethyst_core::{
@@ -47,10 +49,28 @@ where
             "ui_loader",
             &[],
         );
+        builder.add(
+            UiThemeSystemDesc::default().build(world),
+            "ui_theme",
+            &["ui_loader"],
+        );
+        // Both set the `UiTransform` of elements, before `ui_transform` positions them.
+        builder.add(UiProgressBarSystem::new(), "ui_progress_bar_system", &[]);
+        builder.add(UiTweenSystem::new(), "ui_tween_system", &[]);
+        builder.add(
+            UiFlexSystem::new(),
+            "ui_flex",
+            &["transform_system", "ui_theme"],
+        );
         builder.add(
             UiTransformSystemDesc::default().build(world),
             "ui_transform",
-            &["transform_system"],
+            &[
+                "transform_system",
+                "ui_flex",
+                "ui_progress_bar_system",
+                "ui_tween_system",
+            ],
         );
         builder.add(
             UiMouseSystem::<T>::new(),
@@ -104,6 +124,36 @@ where
             "ui_drag_system",
             &["ui_mouse_system"],
         );
+        builder.add(
+            ScrollAreaSystem::<T>::new(),
+            "ui_scroll_area_system",
+            &["ui_mouse_system"],
+        );
+        builder.add(
+            UiSliderSystemDesc::<T>::default().build(world),
+            "ui_slider_system",
+            &["ui_mouse_system"],
+        );
+        builder.add(
+            UiCheckboxSystemDesc::default().build(world),
+            "ui_checkbox_system",
+            &["ui_mouse_system"],
+        );
+        builder.add(
+            UiDropdownSystemDesc::<T>::default().build(world),
+            "ui_dropdown_system",
+            &["ui_mouse_system"],
+        );
+        builder.add(
+            ToggleGroupSystemDesc::default().build(world),
+            "ui_toggle_group_system",
+            &["ui_mouse_system"],
+        );
+        builder.add(
+            UiTooltipSystemDesc::<T>::default().build(world),
+            "ui_tooltip_system",
+            &["ui_mouse_system"],
+        );
 
         builder.add(
             UiButtonActionRetriggerSystemDesc::default().build(world),
@@ -0,0 +1,121 @@
+use serde::{Deserialize, Serialize};
+
+use amethyst_core::{
+    ecs::{
+        Component, DenseVecStorage, Entities, Entity, Join, ReaderId, System, SystemData, Write,
+        WriteStorage,
+    },
+    shrev::EventChannel,
+    HiddenPropagate,
+};
+use amethyst_derive::SystemDesc;
+
+use crate::{UiEvent, UiEventType};
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+/// Component making a ui element a checkbox, toggled by clicking it. The element needs to be
+/// `Interactable`.
+///
+/// Toggling it sends a `UiEventType::ValueChange` event targeting the checkbox.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UiCheckbox {
+    /// Whether the checkbox is checked.
+    pub checked: bool,
+    /// Child shown while the checkbox is checked, and hidden with `HiddenPropagate` otherwise.
+    #[serde(skip)]
+    pub check_mark: Option<Entity>,
+}
+
+impl UiCheckbox {
+    /// Creates a checkbox, checked or not.
+    pub fn new(checked: bool) -> Self {
+        UiCheckbox {
+            checked,
+            check_mark: None,
+        }
+    }
+
+    /// Sets the check mark shown while the checkbox is checked.
+    pub fn with_check_mark(mut self, check_mark: Entity) -> Self {
+        self.check_mark = Some(check_mark);
+        self
+    }
+}
+
+impl Component for UiCheckbox {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Toggles `UiCheckbox`es when they are clicked, and shows their check marks.
+///
+/// It's automatically registered with the `UiBundle`.
+#[derive(Debug, SystemDesc)]
+#[system_desc(name(UiCheckboxSystemDesc))]
+pub struct UiCheckboxSystem {
+    #[system_desc(event_channel_reader)]
+    event_reader: ReaderId<UiEvent>,
+}
+
+impl UiCheckboxSystem {
+    /// Creates a new instance of this structure
+    pub fn new(event_reader: ReaderId<UiEvent>) -> Self {
+        Self { event_reader }
+    }
+}
+
+impl<'s> System<'s> for UiCheckboxSystem {
+    type SystemData = (
+        Entities<'s>,
+        Write<'s, EventChannel<UiEvent>>,
+        WriteStorage<'s, UiCheckbox>,
+        WriteStorage<'s, HiddenPropagate>,
+    );
+
+    fn run(&mut self, (entities, mut events, mut checkboxes, mut hiddens): Self::SystemData) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("ui_checkbox_system");
+
+        let mut toggled = Vec::new();
+        for event in events.read(&mut self.event_reader) {
+            if let UiEventType::Click = event.event_type {
+                // Clicks on the check mark toggle its checkbox too.
+                let checkbox = if checkboxes.contains(event.target) {
+                    Some(event.target)
+                } else {
+                    (&entities, &checkboxes)
+                        .join()
+                        .find(|(_, checkbox)| checkbox.check_mark == Some(event.target))
+                        .map(|(entity, _)| entity)
+                };
+                if let Some(checkbox) = checkbox {
+                    if !toggled.contains(&checkbox) {
+                        toggled.push(checkbox);
+                    }
+                }
+            }
+        }
+
+        for entity in toggled {
+            if let Some(checkbox) = checkboxes.get_mut(entity) {
+                checkbox.checked = !checkbox.checked;
+                events.single_write(UiEvent::new(UiEventType::ValueChange, entity));
+            }
+        }
+
+        for checkbox in (&checkboxes).join() {
+            let check_mark = match checkbox.check_mark {
+                Some(check_mark) if entities.is_alive(check_mark) => check_mark,
+                _ => continue,
+            };
+            if checkbox.checked && hiddens.contains(check_mark) {
+                hiddens.remove(check_mark);
+            } else if !checkbox.checked && !hiddens.contains(check_mark) {
+                hiddens
+                    .insert(check_mark, HiddenPropagate::new())
+                    .expect("Unreachable: Entity is alive");
+            }
+        }
+    }
+}
@@ -0,0 +1,260 @@
+//! World-space debug text labels rendered with the UI glyph cache.
+
+use crate::{
+    world_glyphs::{
+        DrawGlyphs, DrawGlyphsDesc, GlyphLayouter, GlyphProjection, GlyphText, TextGlyphs,
+    },
+    FontAsset, FontHandle,
+};
+use amethyst_assets::AssetStorage;
+use amethyst_core::{
+    ecs::{DispatcherBuilder, Read, System, World, Write},
+    math::Point3,
+};
+use amethyst_error::Error;
+use amethyst_rendy::{
+    bundle::{RenderOrder, RenderPlan, RenderPlugin, Target},
+    palette::Srgba,
+    rendy::{
+        command::QueueId,
+        factory::Factory,
+        graph::render::RenderGroupDesc,
+        hal::pso::{self, ShaderStageFlags},
+        mesh::Position,
+        shader::SpirvShader,
+    },
+    shader_reload::ReloadableShader,
+    types::{Backend, Texture},
+};
+use glyph_brush::{HorizontalAlign, Layout, VerticalAlign};
+use std::marker::PhantomData;
+
+/// Font size in pixels used by `DebugLabels::draw_label`.
+const DEFAULT_FONT_SIZE: f32 = 16.0;
+
+#[derive(Debug, Clone)]
+struct DebugLabel {
+    position: Point3<f32>,
+    text: String,
+    color: [f32; 4],
+    font_size: f32,
+}
+
+/// Resource that stores non-persistent text labels placed at world positions.
+/// Labels are rendered by [DrawDebugText] facing the camera with a constant on-screen size,
+/// and are automatically cleared every frame.
+#[derive(Debug)]
+pub struct DebugLabels {
+    font: FontHandle,
+    labels: Vec<DebugLabel>,
+}
+
+impl DebugLabels {
+    /// Creates an empty set of labels which will be rendered with the given font.
+    pub fn new(font: FontHandle) -> Self {
+        Self {
+            font,
+            labels: Vec::new(),
+        }
+    }
+
+    /// Submits a label centered at a world position, using the default font size.
+    pub fn draw_label(&mut self, position: Point3<f32>, text: impl Into<String>, color: Srgba) {
+        self.draw_label_with_size(position, text, color, DEFAULT_FONT_SIZE);
+    }
+
+    /// Submits a label centered at a world position, with a font size in pixels.
+    pub fn draw_label_with_size(
+        &mut self,
+        position: Point3<f32>,
+        text: impl Into<String>,
+        color: Srgba,
+        font_size: f32,
+    ) {
+        let (r, g, b, a) = color.into_components();
+        self.labels.push(DebugLabel {
+            position,
+            text: text.into(),
+            color: [r, g, b, a],
+            font_size,
+        });
+    }
+
+    /// Removes all labels submitted this frame.
+    pub fn clear(&mut self) {
+        self.labels.clear();
+    }
+}
+
+/// Places the glyphs of [DebugLabels] at their world position, facing the camera with a
+/// constant size on screen.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DebugTextProjection;
+
+impl GlyphProjection for DebugTextProjection {
+    type Placement = Position;
+
+    const PIXEL_SIZED: bool = true;
+
+    fn vertex_shader() -> &'static ReloadableShader {
+        &DEBUG_TEXT_VERTEX
+    }
+
+    fn depth_test() -> Option<pso::DepthTest> {
+        None
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref DEBUG_TEXT_VERTEX: ReloadableShader = ReloadableShader::new(
+        env!("CARGO_PKG_NAME"),
+        "compiled/debug_text.vert.spv",
+        SpirvShader::from_bytes(
+            include_bytes!("../compiled/debug_text.vert.spv"),
+            ShaderStageFlags::VERTEX,
+            "main",
+        ).unwrap(),
+    );
+}
+
+/// Glyph vertices of the labels submitted this frame, produced by `DebugTextGlyphsSystem`.
+pub type DebugTextGlyphs = TextGlyphs<DebugTextProjection>;
+
+/// Describes drawing of [DebugLabels] text at world positions.
+pub type DrawDebugTextDesc = DrawGlyphsDesc<DebugTextProjection>;
+
+/// Draws [DebugLabels] text at world positions, always facing the camera.
+pub type DrawDebugText<B> = DrawGlyphs<B, DebugTextProjection>;
+
+/// Lays out the text of [DebugLabels] and keeps its glyph texture up to date.
+#[allow(missing_debug_implementations)]
+pub struct DebugTextGlyphsSystem<B: Backend> {
+    layouter: GlyphLayouter<DebugTextProjection>,
+    marker: PhantomData<B>,
+}
+
+impl<B: Backend> Default for DebugTextGlyphsSystem<B> {
+    fn default() -> Self {
+        Self {
+            layouter: GlyphLayouter::new((256, 256)),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, B: Backend> System<'a> for DebugTextGlyphsSystem<B> {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Option<Write<'a, Factory<B>>>,
+        Option<Read<'a, QueueId>>,
+        Option<Write<'a, DebugLabels>>,
+        Write<'a, AssetStorage<Texture>>,
+        Read<'a, AssetStorage<FontAsset>>,
+        Write<'a, DebugTextGlyphs>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            mut maybe_factory,
+            maybe_queue,
+            labels,
+            mut tex_storage,
+            font_storage,
+            mut glyphs,
+        ): Self::SystemData,
+    ) {
+        glyphs.clear();
+
+        let (factory, queue, mut labels) = if let (Some(factory), Some(queue), Some(labels)) =
+            (maybe_factory.as_mut(), maybe_queue, labels)
+        {
+            (factory, queue, labels)
+        } else {
+            return;
+        };
+
+        if labels.labels.is_empty() {
+            return;
+        }
+
+        let font_id = match font_storage.get(&labels.font) {
+            Some(font) => self.layouter.font_id(&labels.font, font),
+            None => {
+                log::trace!("Debug label font not loaded yet: labels skipped");
+                labels.clear();
+                return;
+            }
+        };
+
+        let texts = labels
+            .labels
+            .iter()
+            .map(|label| GlyphText {
+                text: &label.text,
+                font_id,
+                font_size: label.font_size,
+                color: label.color,
+                placement: Position([label.position.x, label.position.y, label.position.z]),
+            })
+            .collect::<Vec<_>>();
+        let layout = Layout::default_single_line()
+            .h_align(HorizontalAlign::Center)
+            .v_align(VerticalAlign::Center);
+        self.layouter.layout(
+            factory,
+            *queue,
+            &mut tex_storage,
+            &mut glyphs,
+            layout,
+            &texts,
+        );
+
+        labels.clear();
+    }
+}
+
+/// A [RenderPlugin] for drawing [DebugLabels] text at world positions.
+#[derive(Debug, Default)]
+pub struct RenderDebugText {
+    target: Target,
+}
+
+impl RenderDebugText {
+    /// Set target to which debug text will be rendered.
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+}
+
+impl<B: Backend> RenderPlugin<B> for RenderDebugText {
+    fn on_build<'a, 'b>(
+        &mut self,
+        _world: &mut World,
+        builder: &mut DispatcherBuilder<'a, 'b>,
+    ) -> Result<(), Error> {
+        builder.add(
+            DebugTextGlyphsSystem::<B>::default(),
+            "debug_text_glyphs_system",
+            &[],
+        );
+        Ok(())
+    }
+
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        _factory: &mut Factory<B>,
+        _world: &World,
+    ) -> Result<(), Error> {
+        plan.extend_target(self.target, |ctx| {
+            ctx.add(
+                RenderOrder::AfterTransparent,
+                DrawDebugTextDesc::new().builder(),
+            )?;
+            Ok(())
+        });
+        Ok(())
+    }
+}
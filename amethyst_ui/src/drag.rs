@@ -25,6 +25,9 @@ use crate::{
 /// Component that denotes whether a given ui widget is draggable.
 /// Requires UiTransform to work, and its expected way of usage is
 /// through UiTransformData prefab.
+///
+/// Dragging starts once the cursor moved `DRAG_THRESHOLD` pixels away from where the widget was
+/// clicked, so clicking the widget doesn't move it.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Draggable;
 
@@ -32,18 +35,81 @@ impl Component for Draggable {
     type Storage = DenseVecStorage<Self>;
 }
 
+/// Distance in pixels the cursor has to move with a `Draggable` widget clicked to drag it.
+pub const DRAG_THRESHOLD: f32 = 4.0;
+
+/// Component carrying the data of a `Draggable` ui widget, sent along with its drag and drop
+/// events, e.g. the item held by an inventory slot.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct DragPayload {
+    /// Kind of the data, matched against the kinds accepted by `DropTarget`s.
+    pub kind: String,
+    /// The data.
+    pub data: String,
+}
+
+impl DragPayload {
+    /// Creates a payload of `kind` carrying `data`.
+    pub fn new<K: ToString, D: ToString>(kind: K, data: D) -> Self {
+        DragPayload {
+            kind: kind.to_string(),
+            data: data.to_string(),
+        }
+    }
+}
+
+impl Component for DragPayload {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Component making a ui widget a place `Draggable` widgets can be dropped on. Dropping on one of
+/// its descendants counts as dropping on it. As for `UiEventType::Dropped`, the element under the
+/// cursor needs to be `Interactable`.
+///
+/// Dragging a widget over it sends `UiEventType::DragOver` events, and dropping the widget on it
+/// a `UiEventType::Drop` event, targeting the drop target.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DropTarget {
+    /// Kinds of `DragPayload` accepted. If empty, all widgets are accepted, with a payload or not.
+    pub accepts: Vec<String>,
+}
+
+impl DropTarget {
+    /// Creates a drop target accepting all widgets.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Accepts payloads of `kind`, and no longer widgets of other kinds or without a payload.
+    pub fn accepting<S: ToString>(mut self, kind: S) -> Self {
+        self.accepts.push(kind.to_string());
+        self
+    }
+
+    /// Returns whether a widget with `payload` can be dropped on this target.
+    pub fn accepts(&self, payload: Option<&DragPayload>) -> bool {
+        self.accepts.is_empty()
+            || payload.map_or(false, |payload| self.accepts.contains(&payload.kind))
+    }
+}
+
+impl Component for DropTarget {
+    type Storage = DenseVecStorage<Self>;
+}
+
 #[derive(Debug, SystemDesc)]
 #[system_desc(name(DragWidgetSystemDesc))]
 pub struct DragWidgetSystem<T: BindingTypes> {
     #[system_desc(event_channel_reader)]
     ui_reader_id: ReaderId<UiEvent>,
 
-    /// hashmap whose keys are every entities being dragged,
+    /// hashmap whose keys are every entities clicked or being dragged,
     /// and whose element is a tuple whose first element is
-    /// the original mouse position when drag first started,
-    /// and second element the mouse position one frame ago
+    /// the original mouse position when the entity was clicked,
+    /// second element the mouse position one frame ago,
+    /// and third element whether the entity is being dragged
     #[system_desc(skip)]
-    record: HashMap<Entity, (Vector2<f32>, Vector2<f32>)>,
+    record: HashMap<Entity, (Vector2<f32>, Vector2<f32>, bool)>,
 
     phantom: PhantomData<T>,
 }
@@ -73,6 +139,8 @@ where
         ReadStorage<'s, Hidden>,
         ReadStorage<'s, HiddenPropagate>,
         ReadStorage<'s, Draggable>,
+        ReadStorage<'s, DragPayload>,
+        ReadStorage<'s, DropTarget>,
         ReadStorage<'s, Interactable>,
         Write<'s, EventChannel<UiEvent>>,
         WriteStorage<'s, UiTransform>,
@@ -88,6 +156,8 @@ where
             hiddens,
             hidden_props,
             draggables,
+            payloads,
+            drop_targets,
             interactables,
             mut ui_events,
             mut ui_transforms,
@@ -102,7 +172,8 @@ where
             match event.event_type {
                 UiEventType::ClickStart => {
                     if draggables.get(event.target).is_some() {
-                        self.record.insert(event.target, (mouse_pos, mouse_pos));
+                        self.record
+                            .insert(event.target, (mouse_pos, mouse_pos, false));
                     }
                 }
                 UiEventType::ClickStop => {
@@ -120,7 +191,30 @@ where
             }
         }
 
-        for (entity, (first, prev)) in self.record.iter_mut() {
+        // A widget released or hidden before being dragged was only clicked.
+        let record = &mut self.record;
+        click_stopped.retain(|entity| {
+            let dragged = record.get(entity).map_or(false, |(_, _, dragged)| *dragged);
+            if !dragged {
+                record.remove(entity);
+            }
+            dragged
+        });
+
+        for (entity, (first, prev, dragged)) in self.record.iter_mut() {
+            if !*dragged {
+                if !exceeds_drag_threshold(*first, mouse_pos) {
+                    continue;
+                }
+                *dragged = true;
+                ui_events.single_write(UiEvent::new(
+                    UiEventType::DragStart {
+                        payload: payloads.get(*entity).cloned(),
+                    },
+                    *entity,
+                ));
+            }
+
             ui_events.single_write(UiEvent::new(
                 UiEventType::Dragging {
                     offset_from_mouse: mouse_pos - *first,
@@ -144,28 +238,181 @@ where
             ui_transform.local_y += change[1] / scale_y;
 
             *prev = mouse_pos;
+
+            let below = targeted_below(
+                (mouse_pos[0], mouse_pos[1]),
+                ui_transforms.get(*entity).unwrap().global_z,
+                (
+                    &*entities,
+                    &ui_transforms,
+                    interactables.maybe(),
+                    !&hiddens,
+                    !&hidden_props,
+                )
+                    .join(),
+            );
+            let payload = payloads.get(*entity);
+            if let Some(target) = drop_target(
+                below,
+                payload,
+                |entity| hierarchy.parent(entity),
+                |entity| drop_targets.get(entity),
+            ) {
+                ui_events.single_write(UiEvent::new(
+                    UiEventType::DragOver {
+                        dragged: *entity,
+                        payload: payload.cloned(),
+                    },
+                    target,
+                ));
+            }
         }
 
         for entity in click_stopped.iter() {
+            let below = targeted_below(
+                (mouse_pos[0], mouse_pos[1]),
+                ui_transforms.get(*entity).unwrap().global_z,
+                (
+                    &*entities,
+                    &ui_transforms,
+                    interactables.maybe(),
+                    !&hiddens,
+                    !&hidden_props,
+                )
+                    .join(),
+            );
             ui_events.single_write(UiEvent::new(
-                UiEventType::Dropped {
-                    dropped_on: targeted_below(
-                        (mouse_pos[0], mouse_pos[1]),
-                        ui_transforms.get(*entity).unwrap().global_z,
-                        (
-                            &*entities,
-                            &ui_transforms,
-                            interactables.maybe(),
-                            !&hiddens,
-                            !&hidden_props,
-                        )
-                            .join(),
-                    ),
-                },
+                UiEventType::Dropped { dropped_on: below },
                 *entity,
             ));
 
+            let payload = payloads.get(*entity);
+            if let Some(target) = drop_target(
+                below,
+                payload,
+                |entity| hierarchy.parent(entity),
+                |entity| drop_targets.get(entity),
+            ) {
+                ui_events.single_write(UiEvent::new(
+                    UiEventType::Drop {
+                        dragged: *entity,
+                        payload: payload.cloned(),
+                    },
+                    target,
+                ));
+            }
+
             self.record.remove(entity);
         }
     }
 }
+
+/// Returns whether the cursor moved far enough from `first` to drag the clicked widget.
+fn exceeds_drag_threshold(first: Vector2<f32>, mouse_pos: Vector2<f32>) -> bool {
+    (mouse_pos - first).norm() >= DRAG_THRESHOLD
+}
+
+/// Finds the `DropTarget` accepting `payload` for a widget dropped on `below`, the element
+/// `targeted_below` the widget, which is either `below` or its closest ancestor with a
+/// `DropTarget`.
+fn drop_target<'a, P, D>(
+    below: Option<Entity>,
+    payload: Option<&DragPayload>,
+    parent: P,
+    drop_target: D,
+) -> Option<Entity>
+where
+    P: Fn(Entity) -> Option<Entity>,
+    D: Fn(Entity) -> Option<&'a DropTarget>,
+{
+    let mut current = below;
+    while let Some(entity) = current {
+        if let Some(target) = drop_target(entity) {
+            return if target.accepts(payload) {
+                Some(entity)
+            } else {
+                None
+            };
+        }
+        current = parent(entity);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use amethyst_core::{
+        ecs::{Builder, World, WorldExt},
+        math::Vector2,
+    };
+
+    use super::*;
+
+    #[test]
+    fn drag_starts_past_the_threshold() {
+        let first = Vector2::new(10.0, 10.0);
+        assert!(!exceeds_drag_threshold(first, first));
+        assert!(!exceeds_drag_threshold(first, Vector2::new(12.0, 12.0)));
+        assert!(exceeds_drag_threshold(
+            first,
+            Vector2::new(10.0, 10.0 - DRAG_THRESHOLD)
+        ));
+        assert!(exceeds_drag_threshold(first, Vector2::new(20.0, 10.0)));
+    }
+
+    #[test]
+    fn drop_target_accepts_payload_kinds() {
+        let item = DragPayload::new("item", "sword");
+        let spell = DragPayload::new("spell", "fireball");
+
+        assert!(DropTarget::new().accepts(None));
+        assert!(DropTarget::new().accepts(Some(&item)));
+
+        let target = DropTarget::new().accepting("item");
+        assert!(target.accepts(Some(&item)));
+        assert!(!target.accepts(Some(&spell)));
+        assert!(!target.accepts(None));
+    }
+
+    #[test]
+    fn drop_target_is_the_closest_ancestor_with_one() {
+        let mut world = World::new();
+        let slot = world.create_entity().build();
+        let frame = world.create_entity().build();
+        let icon = world.create_entity().build();
+        let parents: HashMap<Entity, Entity> =
+            vec![(icon, frame), (frame, slot)].into_iter().collect();
+        let mut targets = HashMap::new();
+        targets.insert(slot, DropTarget::new().accepting("item"));
+        let item = DragPayload::new("item", "sword");
+        let spell = DragPayload::new("spell", "fireball");
+
+        let find = |below, payload| {
+            drop_target(
+                below,
+                payload,
+                |entity| parents.get(&entity).cloned(),
+                |entity| targets.get(&entity),
+            )
+        };
+        assert_eq!(find(Some(icon), Some(&item)), Some(slot));
+        assert_eq!(find(Some(slot), Some(&item)), Some(slot));
+        assert_eq!(find(Some(icon), Some(&spell)), None);
+        assert_eq!(find(None, Some(&item)), None);
+
+        // The closest drop target decides, even when it refuses the payload.
+        targets.insert(frame, DropTarget::new().accepting("spell"));
+        let find = |below, payload| {
+            drop_target(
+                below,
+                payload,
+                |entity| parents.get(&entity).cloned(),
+                |entity| targets.get(&entity),
+            )
+        };
+        assert_eq!(find(Some(icon), Some(&item)), None);
+        assert_eq!(find(Some(icon), Some(&spell)), Some(frame));
+    }
+}
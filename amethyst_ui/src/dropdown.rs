@@ -0,0 +1,288 @@
+use std::marker::PhantomData;
+
+use amethyst_core::{
+    ecs::{
+        Component, DenseVecStorage, Entities, Entity, Join, Read, ReaderId, System, SystemData,
+        Write, WriteStorage,
+    },
+    shrev::EventChannel,
+    HiddenPropagate,
+};
+use amethyst_derive::SystemDesc;
+use amethyst_input::{BindingTypes, InputHandler};
+use winit::MouseButton;
+
+use crate::{UiEvent, UiEventType, UiText, UiTransform};
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+/// Component making a ui element a dropdown, opening a list of options when clicked.
+///
+/// The dropdown and each of its options need to be `Interactable`. The list is hidden with
+/// `HiddenPropagate` while the dropdown is closed. While open, its `local_z` is raised to
+/// `overlay_z`, so it is drawn above the ui elements around the dropdown and receives their
+/// clicks. Clicking an option selects it, sending a `UiEventType::ValueChange` event targeting
+/// the dropdown, and closes the list, which clicking anywhere else closes too.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UiDropdown {
+    /// Container of the options, usually a child of the dropdown.
+    pub list: Entity,
+    /// Entities of the options, usually children of the list.
+    pub options: Vec<Entity>,
+    /// Index of the selected option.
+    pub selected: Option<usize>,
+    /// Whether the list is open.
+    pub open: bool,
+    /// Entity whose `UiText` shows the text of the selected option, usually a child of the
+    /// dropdown.
+    pub label: Option<Entity>,
+    /// `local_z` of the list while it is open.
+    pub overlay_z: f32,
+}
+
+impl UiDropdown {
+    /// Creates a closed dropdown of `options` in `list`, with nothing selected.
+    pub fn new(list: Entity, options: Vec<Entity>) -> Self {
+        UiDropdown {
+            list,
+            options,
+            selected: None,
+            open: false,
+            label: None,
+            overlay_z: 1000.0,
+        }
+    }
+
+    /// Sets the entity showing the text of the selected option.
+    pub fn with_label(mut self, label: Entity) -> Self {
+        self.label = Some(label);
+        self
+    }
+
+    /// Selects the option at `index`.
+    pub fn with_selected(mut self, index: usize) -> Self {
+        self.selected = Some(index);
+        self
+    }
+
+    /// Returns the entity of the selected option.
+    pub fn selected_option(&self) -> Option<Entity> {
+        self.selected
+            .and_then(|index| self.options.get(index))
+            .cloned()
+    }
+
+    /// Opens, closes and selects following the clicks of a frame on the dropdown `entity`, given
+    /// the targets of the clicks started and of the clicks completed. Returns whether the
+    /// selection changed.
+    fn handle_clicks(
+        &mut self,
+        entity: Entity,
+        click_started: bool,
+        click_targets: &[Entity],
+        clicked: &[Entity],
+    ) -> bool {
+        // Clicks starting outside of the dropdown and its list close it.
+        let inside = click_targets.iter().any(|target| {
+            *target == entity || *target == self.list || self.options.contains(target)
+        });
+        if self.open && click_started && !inside {
+            self.open = false;
+        }
+
+        let mut changed = false;
+        for target in clicked {
+            if let Some(index) = self.options.iter().position(|option| option == target) {
+                if self.selected != Some(index) {
+                    self.selected = Some(index);
+                    changed = true;
+                }
+                self.open = false;
+            } else if *target == entity {
+                self.open = !self.open;
+            }
+        }
+        changed
+    }
+}
+
+impl Component for UiDropdown {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Opens and closes `UiDropdown`s and selects their options when clicked.
+///
+/// It's automatically registered with the `UiBundle`.
+#[derive(Debug, SystemDesc)]
+#[system_desc(name(UiDropdownSystemDesc))]
+pub struct UiDropdownSystem<T: BindingTypes> {
+    #[system_desc(event_channel_reader)]
+    event_reader: ReaderId<UiEvent>,
+    #[system_desc(skip)]
+    was_down: bool,
+    phantom: PhantomData<T>,
+}
+
+impl<T: BindingTypes> UiDropdownSystem<T> {
+    /// Creates a new instance of this structure
+    pub fn new(event_reader: ReaderId<UiEvent>) -> Self {
+        Self {
+            event_reader,
+            was_down: false,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<'s, T: BindingTypes> System<'s> for UiDropdownSystem<T> {
+    type SystemData = (
+        Entities<'s>,
+        Read<'s, InputHandler<T>>,
+        Write<'s, EventChannel<UiEvent>>,
+        WriteStorage<'s, UiDropdown>,
+        WriteStorage<'s, UiTransform>,
+        WriteStorage<'s, UiText>,
+        WriteStorage<'s, HiddenPropagate>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            input,
+            mut events,
+            mut dropdowns,
+            mut transforms,
+            mut texts,
+            mut hiddens,
+        ): Self::SystemData,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("ui_dropdown_system");
+
+        let down = input.mouse_button_is_down(MouseButton::Left);
+        let click_started = down && !self.was_down;
+        self.was_down = down;
+
+        let mut clicked = Vec::new();
+        let mut click_targets = Vec::new();
+        for event in events.read(&mut self.event_reader) {
+            match event.event_type {
+                UiEventType::Click => clicked.push(event.target),
+                UiEventType::ClickStart => click_targets.push(event.target),
+                _ => {}
+            }
+        }
+
+        let mut changed = Vec::new();
+        for (entity, dropdown) in (&entities, &mut dropdowns).join() {
+            if dropdown.handle_clicks(entity, click_started, &click_targets, &clicked) {
+                changed.push(entity);
+            }
+
+            if let (Some(label), Some(option)) = (dropdown.label, dropdown.selected_option()) {
+                let text = texts.get(option).map(|text| text.text.clone());
+                if let (Some(text), Some(label)) = (text, texts.get_mut(label)) {
+                    if label.text != text {
+                        label.text = text;
+                    }
+                }
+            }
+
+            if !entities.is_alive(dropdown.list) {
+                continue;
+            }
+            if dropdown.open {
+                if hiddens.contains(dropdown.list) {
+                    hiddens.remove(dropdown.list);
+                }
+                let raised = transforms.get(dropdown.list).map_or(true, |transform| {
+                    (transform.local_z - dropdown.overlay_z).abs() <= std::f32::EPSILON
+                });
+                if !raised {
+                    transforms
+                        .get_mut(dropdown.list)
+                        .expect("Checked above")
+                        .local_z = dropdown.overlay_z;
+                }
+            } else if !hiddens.contains(dropdown.list) {
+                hiddens
+                    .insert(dropdown.list, HiddenPropagate::new())
+                    .expect("Unreachable: Entity is alive");
+            }
+        }
+
+        for entity in changed {
+            events.single_write(UiEvent::new(UiEventType::ValueChange, entity));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use amethyst_core::ecs::{Builder, Entity, World, WorldExt};
+
+    use super::UiDropdown;
+
+    fn dropdown(world: &mut World) -> (Entity, UiDropdown) {
+        let entity = world.create_entity().build();
+        let list = world.create_entity().build();
+        let options = (0..3).map(|_| world.create_entity().build()).collect();
+        (entity, UiDropdown::new(list, options))
+    }
+
+    #[test]
+    fn clicking_the_dropdown_toggles_the_list() {
+        let mut world = World::new();
+        let (entity, mut dropdown) = dropdown(&mut world);
+
+        assert!(!dropdown.handle_clicks(entity, true, &[entity], &[entity]));
+        assert!(dropdown.open);
+        assert!(!dropdown.handle_clicks(entity, true, &[entity], &[entity]));
+        assert!(!dropdown.open);
+    }
+
+    #[test]
+    fn clicking_an_option_selects_it_and_closes_the_list() {
+        let mut world = World::new();
+        let (entity, mut dropdown) = dropdown(&mut world);
+        let option = dropdown.options[1];
+        dropdown.open = true;
+
+        assert!(dropdown.handle_clicks(entity, true, &[option], &[option]));
+        assert_eq!(dropdown.selected, Some(1));
+        assert_eq!(dropdown.selected_option(), Some(option));
+        assert!(!dropdown.open);
+
+        // Selecting the selected option again is no change.
+        dropdown.open = true;
+        assert!(!dropdown.handle_clicks(entity, true, &[option], &[option]));
+        assert!(!dropdown.open);
+    }
+
+    #[test]
+    fn clicking_elsewhere_closes_the_list() {
+        let mut world = World::new();
+        let (entity, dropdown) = dropdown(&mut world);
+        let mut dropdown = dropdown.with_selected(0);
+        let list = dropdown.list;
+        let elsewhere = world.create_entity().build();
+        dropdown.open = true;
+
+        // Clicks on the list itself keep it open.
+        dropdown.handle_clicks(entity, true, &[list], &[]);
+        assert!(dropdown.open);
+
+        assert!(!dropdown.handle_clicks(entity, true, &[elsewhere], &[elsewhere]));
+        assert!(!dropdown.open);
+        assert_eq!(dropdown.selected, Some(0));
+    }
+
+    #[test]
+    fn selected_option_out_of_range_is_none() {
+        let mut world = World::new();
+        let (_, dropdown) = dropdown(&mut world);
+        assert_eq!(dropdown.with_selected(5).selected_option(), None);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::transform::UiTransform;
+use crate::{transform::UiTransform, DragPayload};
 use amethyst_core::{
     ecs::{
         prelude::{
@@ -8,9 +8,10 @@ use amethyst_core::{
     },
     math::Vector2,
     shrev::EventChannel,
-    Hidden, HiddenPropagate,
+    Hidden, HiddenPropagate, Parent,
 };
 use amethyst_input::{BindingTypes, InputHandler};
+use amethyst_rendy::resources::ScissorRect;
 use amethyst_window::ScreenDimensions;
 use serde::{Deserialize, Serialize};
 use std::{collections::HashSet, marker::PhantomData};
@@ -52,9 +53,30 @@ pub enum UiEventType {
         /// The entity on which the dragged object was dropped.
         dropped_on: Option<Entity>,
     },
-    /// When the value of a UiText element has been changed by user input.
+    /// When a `Draggable` Ui element starts being dragged.
+    DragStart {
+        /// The `DragPayload` of the dragged element.
+        payload: Option<DragPayload>,
+    },
+    /// When a `Draggable` Ui element is dragged over a `DropTarget` accepting its payload. Sent
+    /// every frame, targeting the drop target.
+    DragOver {
+        /// The dragged entity.
+        dragged: Entity,
+        /// The `DragPayload` of the dragged element.
+        payload: Option<DragPayload>,
+    },
+    /// When a `Draggable` Ui element is dropped on a `DropTarget` accepting its payload. Targets
+    /// the drop target.
+    Drop {
+        /// The dropped entity.
+        dragged: Entity,
+        /// The `DragPayload` of the dropped element.
+        payload: Option<DragPayload>,
+    },
+    /// When the value of a UiText, UiSlider or UiCheckbox element has been changed by user input.
     ValueChange,
-    /// When the value of a UiText element has been committed by user action.
+    /// When the value of a UiText or UiSlider element has been committed by user action.
     ValueCommit,
     /// When an editable UiText element has gained focus.
     Focus,
@@ -123,6 +145,8 @@ impl<'a, T: BindingTypes> System<'a> for UiMouseSystem<T> {
         ReadStorage<'a, HiddenPropagate>,
         ReadStorage<'a, UiTransform>,
         ReadStorage<'a, Interactable>,
+        ReadStorage<'a, ScissorRect>,
+        ReadStorage<'a, Parent>,
         Read<'a, InputHandler<T>>,
         ReadExpect<'a, ScreenDimensions>,
         Write<'a, EventChannel<UiEvent>>,
@@ -130,7 +154,18 @@ impl<'a, T: BindingTypes> System<'a> for UiMouseSystem<T> {
 
     fn run(
         &mut self,
-        (entities, hiddens, hidden_props, transform, react, input, screen_dimensions, mut events): Self::SystemData,
+        (
+            entities,
+            hiddens,
+            hidden_props,
+            transform,
+            react,
+            scissors,
+            parents,
+            input,
+            screen_dimensions,
+            mut events,
+        ): Self::SystemData,
     ) {
         let down = input.mouse_button_is_down(MouseButton::Left);
 
@@ -151,7 +186,10 @@ impl<'a, T: BindingTypes> System<'a> for UiMouseSystem<T> {
                     !&hiddens,
                     !&hidden_props,
                 )
-                    .join(),
+                    .join()
+                    .filter(|(entity, _, _, _, _)| {
+                        !is_clipped(*entity, (pos_x, pos_y), &scissors, &parents)
+                    }),
             );
             for target in targets.difference(&self.last_targets) {
                 events.single_write(UiEvent::new(UiEventType::HoverStart, *target));
@@ -211,6 +249,30 @@ where
     entity_transforms.into_iter().map(|(e, _t)| e).collect()
 }
 
+/// Checks if the position `pos`, measured from the top left corner of the screen, is outside of
+/// the scissor rectangle of `entity` or one of its ancestors, hiding the entity there.
+fn is_clipped(
+    entity: Entity,
+    pos: (f32, f32),
+    scissors: &ReadStorage<'_, ScissorRect>,
+    parents: &ReadStorage<'_, Parent>,
+) -> bool {
+    let mut current = Some(entity);
+    while let Some(entity) = current {
+        if let Some(scissor) = scissors.get(entity) {
+            let inside = pos.0 >= scissor.x as f32
+                && pos.0 < (scissor.x + scissor.width) as f32
+                && pos.1 >= scissor.y as f32
+                && pos.1 < (scissor.y + scissor.height) as f32;
+            if !inside {
+                return true;
+            }
+        }
+        current = parents.get(entity).map(|parent| parent.entity);
+    }
+    false
+}
+
 /// Checks if an interactable entity is at the position `pos`, doesn't have anything on top blocking
 /// the check, and is below specified height.
 pub fn targeted_below<'a, I>(pos: (f32, f32), height: f32, transforms: I) -> Option<Entity>
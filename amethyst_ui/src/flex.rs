@@ -0,0 +1,520 @@
+//! Flexbox layout of the children of ui elements.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use amethyst_core::{
+    ecs::prelude::{
+        Component, DenseVecStorage, Entities, Entity, Join, ReadExpect, ReadStorage, System,
+        WriteStorage,
+    },
+    Hidden, HiddenPropagate, ParentHierarchy,
+};
+
+use crate::{Anchor, ScaleMode, Stretch, UiTransform};
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+/// Axis the children of a `UiFlex` container are placed along.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Deserialize, Serialize)]
+pub enum FlexDirection {
+    /// Left to right.
+    Row,
+    /// Top to bottom.
+    Column,
+}
+
+/// How the free space along the main axis of a line is distributed around its children.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Deserialize, Serialize)]
+pub enum FlexJustify {
+    /// Children are packed at the start of the line.
+    Start,
+    /// Children are packed at the end of the line.
+    End,
+    /// Children are packed in the middle of the line.
+    Center,
+    /// The free space is shared between the children, with none before the first and after the
+    /// last.
+    SpaceBetween,
+    /// The free space is shared around each of the children.
+    SpaceAround,
+}
+
+/// How children are placed along the cross axis of their line.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Deserialize, Serialize)]
+pub enum FlexAlign {
+    /// At the start of the line, the top of a row or the left of a column.
+    Start,
+    /// At the end of the line.
+    End,
+    /// In the middle of the line.
+    Center,
+    /// Sized to fill the line.
+    Stretch,
+}
+
+/// Space between the edges of a `UiFlex` container and its children, in pixels.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Padding {
+    /// Space on the left.
+    pub left: f32,
+    /// Space on the right.
+    pub right: f32,
+    /// Space on the top.
+    pub top: f32,
+    /// Space on the bottom.
+    pub bottom: f32,
+}
+
+impl Padding {
+    /// The same space on all edges.
+    pub fn uniform(padding: f32) -> Self {
+        Padding {
+            left: padding,
+            right: padding,
+            top: padding,
+            bottom: padding,
+        }
+    }
+}
+
+/// Component laying out the children of a ui element in rows or columns, as an alternative to
+/// placing each of them with its anchor.
+///
+/// The `UiFlexSystem` sets the anchor and pivot of every visible child with a `UiTransform` to
+/// `Anchor::TopLeft`, and its position and size in pixels. The size a child had before the layout
+/// sized it, or the basis of its `UiFlexItem`, is the size it grows or shrinks from, so a child
+/// keeps its own size until the layout changes it. Children can be nested containers.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct UiFlex {
+    /// Axis the children are placed along.
+    pub direction: FlexDirection,
+    /// Whether children which don't fit move to a new line.
+    pub wrap: bool,
+    /// Space between the edges of the element and its children.
+    pub padding: Padding,
+    /// Space between consecutive children, and between lines.
+    pub gap: f32,
+    /// How children are placed along the main axis.
+    pub justify: FlexJustify,
+    /// How children are placed along the cross axis, unless they set their own.
+    pub align: FlexAlign,
+}
+
+impl Default for UiFlex {
+    fn default() -> Self {
+        UiFlex {
+            direction: FlexDirection::Row,
+            wrap: false,
+            padding: Padding::default(),
+            gap: 0.0,
+            justify: FlexJustify::Start,
+            align: FlexAlign::Start,
+        }
+    }
+}
+
+impl UiFlex {
+    /// Creates a container placing its children along `direction`, packed at the start.
+    pub fn new(direction: FlexDirection) -> Self {
+        UiFlex {
+            direction,
+            ..Default::default()
+        }
+    }
+
+    /// Sets whether children which don't fit move to a new line.
+    pub fn with_wrap(mut self, wrap: bool) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    /// Sets the padding.
+    pub fn with_padding(mut self, padding: Padding) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Sets the space between children.
+    pub fn with_gap(mut self, gap: f32) -> Self {
+        self.gap = gap;
+        self
+    }
+
+    /// Sets how children are placed along the main axis.
+    pub fn with_justify(mut self, justify: FlexJustify) -> Self {
+        self.justify = justify;
+        self
+    }
+
+    /// Sets how children are placed along the cross axis.
+    pub fn with_align(mut self, align: FlexAlign) -> Self {
+        self.align = align;
+        self
+    }
+}
+
+impl Component for UiFlex {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Component setting how a child of a `UiFlex` container is sized and ordered. Children without
+/// one keep their size and the order of the hierarchy.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct UiFlexItem {
+    /// Share of the free space of its line the child grows by.
+    pub grow: f32,
+    /// How much the child shrinks, relative to its basis, when its line overflows.
+    pub shrink: f32,
+    /// Size along the main axis before growing or shrinking, instead of its own size.
+    pub basis: Option<f32>,
+    /// Placement along the cross axis, instead of the one of the container.
+    pub align_self: Option<FlexAlign>,
+    /// Children are placed in increasing order, and in the order of the hierarchy when equal.
+    pub order: i32,
+}
+
+impl Default for UiFlexItem {
+    fn default() -> Self {
+        UiFlexItem {
+            grow: 0.0,
+            shrink: 1.0,
+            basis: None,
+            align_self: None,
+            order: 0,
+        }
+    }
+}
+
+impl UiFlexItem {
+    /// Creates an item growing by `grow` shares of the free space.
+    pub fn new(grow: f32) -> Self {
+        UiFlexItem {
+            grow,
+            ..Default::default()
+        }
+    }
+}
+
+impl Component for UiFlexItem {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Child measured for the layout, with sizes along the main and cross axes.
+#[derive(Debug, Clone, Copy)]
+struct FlexInput {
+    basis: f32,
+    cross: f32,
+    grow: f32,
+    shrink: f32,
+    align: FlexAlign,
+}
+
+/// Lays out `items` in a container of inner size `inner` along the main and cross axes, returning
+/// the position of each item from the inner top left corner and its size, along those axes.
+fn layout(flex: &UiFlex, inner: (f32, f32), items: &[FlexInput]) -> Vec<[f32; 4]> {
+    let (inner_main, inner_cross) = inner;
+
+    // Split the items into lines.
+    let mut lines = Vec::new();
+    let mut start = 0;
+    let mut used = 0.0;
+    for (i, item) in items.iter().enumerate() {
+        let needed = if i == start {
+            item.basis
+        } else {
+            used + flex.gap + item.basis
+        };
+        if flex.wrap && i > start && needed > inner_main {
+            lines.push(start..i);
+            start = i;
+            used = item.basis;
+        } else {
+            used = needed;
+        }
+    }
+    if start < items.len() {
+        lines.push(start..items.len());
+    }
+
+    let mut placed = vec![[0.0; 4]; items.len()];
+    let mut line_pos = 0.0;
+    for line in lines {
+        let line_items = &items[line.clone()];
+        let count = line_items.len() as f32;
+        let mut sizes = line_items.iter().map(|item| item.basis).collect::<Vec<_>>();
+        let mut free = inner_main - sizes.iter().sum::<f32>() - flex.gap * (count - 1.0);
+
+        let grow = line_items.iter().map(|item| item.grow).sum::<f32>();
+        let shrink = line_items
+            .iter()
+            .map(|item| item.shrink * item.basis)
+            .sum::<f32>();
+        if free > 0.0 && grow > 0.0 {
+            for (size, item) in sizes.iter_mut().zip(line_items) {
+                *size += free * item.grow / grow;
+            }
+            free = 0.0;
+        } else if free < 0.0 && shrink > 0.0 {
+            for (size, item) in sizes.iter_mut().zip(line_items) {
+                *size = (*size + free * item.shrink * item.basis / shrink).max(0.0);
+            }
+            free = 0.0;
+        }
+
+        let free = free.max(0.0);
+        let (mut main_pos, spacing) = match flex.justify {
+            FlexJustify::Start => (0.0, flex.gap),
+            FlexJustify::End => (free, flex.gap),
+            FlexJustify::Center => (free / 2.0, flex.gap),
+            FlexJustify::SpaceBetween if count > 1.0 => (0.0, flex.gap + free / (count - 1.0)),
+            FlexJustify::SpaceBetween => (0.0, flex.gap),
+            FlexJustify::SpaceAround => (free / count / 2.0, flex.gap + free / count),
+        };
+
+        // A single line fills the container, lines of wrapping containers fit their items.
+        let line_cross = if flex.wrap {
+            line_items.iter().map(|item| item.cross).fold(0.0, f32::max)
+        } else {
+            inner_cross
+        };
+        for ((placed, item), size) in placed[line].iter_mut().zip(line_items).zip(sizes) {
+            let (cross_pos, cross) = match item.align {
+                FlexAlign::Start => (0.0, item.cross),
+                FlexAlign::End => (line_cross - item.cross, item.cross),
+                FlexAlign::Center => ((line_cross - item.cross) / 2.0, item.cross),
+                FlexAlign::Stretch => (0.0, line_cross),
+            };
+            *placed = [main_pos, line_pos + cross_pos, size, cross];
+            main_pos += size + spacing;
+        }
+        line_pos += line_cross + flex.gap;
+    }
+    placed
+}
+
+/// Places the children of every `UiFlex` container.
+///
+/// Runs before the `UiTransformSystem`, and lays out containers from the root of the hierarchy
+/// down, so nested containers are laid out with the size their parent gave them. The transforms
+/// of children are only written when the layout moves or resizes them.
+#[derive(Debug, Default)]
+pub struct UiFlexSystem {
+    /// Size of each child before the layout changed it, in pixels.
+    natural: HashMap<Entity, (f32, f32)>,
+    /// Size the layout last gave each child, in pixels.
+    laid_out: HashMap<Entity, (f32, f32)>,
+}
+
+impl UiFlexSystem {
+    /// Creates a new `UiFlexSystem`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<'a> System<'a> for UiFlexSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, ParentHierarchy>,
+        ReadStorage<'a, UiFlex>,
+        ReadStorage<'a, UiFlexItem>,
+        ReadStorage<'a, Hidden>,
+        ReadStorage<'a, HiddenPropagate>,
+        WriteStorage<'a, UiTransform>,
+    );
+
+    fn run(
+        &mut self,
+        (entities, hierarchy, flexes, items, hiddens, hidden_props, mut transforms): Self::SystemData,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("ui_flex_system");
+
+        let mut children = Vec::new();
+        let mut inputs = Vec::new();
+        let mut sizes = HashMap::new();
+        let mut placed_children = Vec::new();
+
+        // Containers without a parent come first, the hierarchy lists parents before children.
+        let order = (&entities, &flexes)
+            .join()
+            .map(|(entity, _)| entity)
+            .filter(|entity| hierarchy.parent(*entity).is_none())
+            .chain(hierarchy.all().iter().cloned())
+            .collect::<Vec<_>>();
+        for container in order {
+            let flex = match flexes.get(container) {
+                Some(flex) => flex,
+                None => continue,
+            };
+            let (width, height) = match sizes.get(&container) {
+                Some(size) => *size,
+                None => match transforms.get(container) {
+                    Some(transform) => (transform.pixel_width(), transform.pixel_height()),
+                    None => continue,
+                },
+            };
+
+            children.clear();
+            children.extend(
+                hierarchy
+                    .children(container)
+                    .iter()
+                    .filter(|child| !hiddens.contains(**child) && !hidden_props.contains(**child))
+                    .filter(|child| transforms.contains(**child)),
+            );
+            children.sort_by_key(|child| items.get(*child).map_or(0, |item| item.order));
+
+            inputs.clear();
+            for child in &children {
+                let transform = transforms.get(*child).expect("Filtered above");
+                let current = (transform.pixel_width(), transform.pixel_height());
+                // The child was resized by someone else than the layout since it last ran.
+                let natural = match (self.laid_out.get(child), self.natural.get(child)) {
+                    (Some(laid_out), Some(natural)) if *laid_out == current => *natural,
+                    _ => current,
+                };
+                self.natural.insert(*child, natural);
+
+                let item = items.get(*child).cloned().unwrap_or_default();
+                let (main, cross) = match flex.direction {
+                    FlexDirection::Row => natural,
+                    FlexDirection::Column => (natural.1, natural.0),
+                };
+                inputs.push(FlexInput {
+                    basis: item.basis.unwrap_or(main),
+                    cross,
+                    grow: item.grow,
+                    shrink: item.shrink,
+                    align: item.align_self.unwrap_or(flex.align),
+                });
+            }
+
+            let padding = flex.padding;
+            let inner = (
+                (width - padding.left - padding.right).max(0.0),
+                (height - padding.top - padding.bottom).max(0.0),
+            );
+            let inner = match flex.direction {
+                FlexDirection::Row => inner,
+                FlexDirection::Column => (inner.1, inner.0),
+            };
+
+            placed_children.clear();
+            placed_children.extend(layout(flex, inner, &inputs));
+            for (child, [main_pos, cross_pos, main, cross]) in
+                children.iter().zip(placed_children.iter().cloned())
+            {
+                let (x, y, width, height) = match flex.direction {
+                    FlexDirection::Row => (main_pos, cross_pos, main, cross),
+                    FlexDirection::Column => (cross_pos, main_pos, cross, main),
+                };
+                let (x, y) = (padding.left + x, -(padding.top + y));
+                sizes.insert(*child, (width, height));
+                self.laid_out.insert(*child, (width, height));
+
+                let transform = transforms.get(*child).expect("Filtered above");
+                let changed = transform.anchor != Anchor::TopLeft
+                    || transform.pivot != Anchor::TopLeft
+                    || transform.scale_mode != ScaleMode::Pixel
+                    || match transform.stretch {
+                        Stretch::NoStretch => false,
+                        _ => true,
+                    }
+                    || (transform.local_x - x).abs() > std::f32::EPSILON
+                    || (transform.local_y - y).abs() > std::f32::EPSILON
+                    || (transform.width - width).abs() > std::f32::EPSILON
+                    || (transform.height - height).abs() > std::f32::EPSILON;
+                if changed {
+                    let transform = transforms.get_mut(*child).expect("Filtered above");
+                    transform.anchor = Anchor::TopLeft;
+                    transform.pivot = Anchor::TopLeft;
+                    transform.scale_mode = ScaleMode::Pixel;
+                    transform.stretch = Stretch::NoStretch;
+                    transform.local_x = x;
+                    transform.local_y = y;
+                    transform.width = width;
+                    transform.height = height;
+                }
+            }
+        }
+
+        // Forget children which were removed from their container.
+        let sizes = &sizes;
+        self.natural.retain(|entity, _| sizes.contains_key(entity));
+        self.laid_out.retain(|entity, _| sizes.contains_key(entity));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(basis: f32, cross: f32, grow: f32) -> FlexInput {
+        FlexInput {
+            basis,
+            cross,
+            grow,
+            shrink: 1.0,
+            align: FlexAlign::Start,
+        }
+    }
+
+    #[test]
+    fn grows_and_justifies_items() {
+        let flex = UiFlex::new(FlexDirection::Row).with_gap(10.0);
+        let placed = layout(
+            &flex,
+            (200.0, 50.0),
+            &[item(40.0, 20.0, 1.0), item(40.0, 30.0, 3.0)],
+        );
+        // 110 pixels of free space are shared 1:3.
+        assert_eq!(
+            placed,
+            vec![[0.0, 0.0, 67.5, 20.0], [77.5, 0.0, 122.5, 30.0]]
+        );
+
+        let flex = flex.with_justify(FlexJustify::SpaceBetween);
+        let placed = layout(
+            &flex,
+            (200.0, 50.0),
+            &[item(40.0, 20.0, 0.0), item(40.0, 30.0, 0.0)],
+        );
+        assert_eq!(placed[1], [160.0, 0.0, 40.0, 30.0]);
+    }
+
+    #[test]
+    fn wraps_and_shrinks_items() {
+        let flex = UiFlex::new(FlexDirection::Row)
+            .with_wrap(true)
+            .with_gap(10.0);
+        let stretched = |basis, cross| FlexInput {
+            align: FlexAlign::Stretch,
+            ..item(basis, cross, 0.0)
+        };
+        let placed = layout(
+            &flex,
+            (100.0, 200.0),
+            &[
+                stretched(50.0, 20.0),
+                stretched(40.0, 30.0),
+                stretched(150.0, 10.0),
+            ],
+        );
+        assert_eq!(
+            placed,
+            vec![
+                [0.0, 0.0, 50.0, 30.0],
+                [60.0, 0.0, 40.0, 30.0],
+                // Alone on its line, the last item shrinks to fit.
+                [0.0, 40.0, 100.0, 10.0],
+            ]
+        );
+    }
+}
@@ -1,8 +1,8 @@
 //! Module containing the system managing glyphbrush state for visible UI Text components.
 
 use crate::{
-    pass::UiArgs, text::CachedGlyph, FontAsset, LineMode, Selected, TextEditing, UiText,
-    UiTransform,
+    markup::text_runs, pass::UiArgs, text::CachedGlyph, FontAsset, FontHandle, LineMode, Selected,
+    TextEditing, UiText, UiTransform,
 };
 use amethyst_assets::{AssetStorage, Handle};
 use amethyst_core::{
@@ -18,14 +18,15 @@ use amethyst_rendy::{
         command::QueueId,
         factory::{Factory, ImageState},
         hal,
-        texture::{pixel::R8Unorm, TextureBuilder},
+        texture::{pixel::R8Unorm, Texture as RendyTexture, TextureBuilder},
     },
     resources::Tint,
     Backend, Texture,
 };
 use glyph_brush::{
-    rusttype::Scale, BrushAction, BrushError, BuiltInLineBreaker, FontId, GlyphBrush,
-    GlyphBrushBuilder, GlyphCruncher, Layout, LineBreak, LineBreaker, SectionText, VariedSection,
+    rusttype::{Rect, Scale},
+    BrushAction, BrushError, BuiltInLineBreaker, FontId, GlyphBrush, GlyphBrushBuilder,
+    GlyphCruncher, Layout, LineBreak, LineBreaker, SectionText, VariedSection,
 };
 use std::{collections::HashMap, marker::PhantomData};
 use unicode_segmentation::UnicodeSegmentation;
@@ -185,16 +186,9 @@ impl<'a, B: Backend> System<'a> for UiGlyphsSystem<B> {
             ui_text.cached_glyphs.clear();
 
             let font_asset = font_storage.get(&ui_text.font).map(|font| font.0.clone());
-            let font_lookup = fonts_map_ref
-                .entry(ui_text.font.id())
-                .or_insert(FontState::NotFound);
-            if font_lookup.id().is_none() {
-                if let Some(font) = font_storage.get(&ui_text.font) {
-                    *font_lookup = FontState::Ready(glyph_brush_ref.add_font(font.0.clone()));
-                }
-            }
+            let font_id = font_id(fonts_map_ref, glyph_brush_ref, &font_storage, &ui_text.font);
 
-            if let (Some(font_id), Some(font_asset)) = (font_lookup.id(), font_asset) {
+            if let (Some(font_id), Some(font_asset)) = (font_id, font_asset) {
                 let tint_color = tint.map_or([1., 1., 1., 1.], |t| {
                     let (r, g, b, a) = t.0.into_components();
                     [r, g, b, a]
@@ -204,42 +198,59 @@ impl<'a, B: Backend> System<'a> for UiGlyphsSystem<B> {
                 let scale = Scale::uniform(ui_text.font_size);
 
                 let text = match (ui_text.password, editing) {
-                    (false, None) => vec![SectionText {
-                        text: &ui_text.text,
-                        scale,
-                        color: base_color,
-                        font_id,
-                    }],
-                    (false, Some(sel)) => {
-                        if let Some((start, end)) = selection_span(sel, &ui_text.text) {
-                            vec![
-                                SectionText {
-                                    text: &ui_text.text[..start],
-                                    scale,
-                                    color: base_color,
-                                    font_id,
-                                },
-                                SectionText {
-                                    text: &ui_text.text[start..end],
-                                    scale,
-                                    color: mul_blend(&sel.selected_text_color, &tint_color),
-                                    font_id,
-                                },
-                                SectionText {
-                                    text: &ui_text.text[end..],
-                                    scale,
-                                    color: base_color,
-                                    font_id,
-                                },
-                            ]
-                        } else {
-                            vec![SectionText {
-                                text: &ui_text.text,
-                                scale,
-                                color: base_color,
-                                font_id,
-                            }]
+                    (false, editing) => {
+                        let selection = editing.and_then(|sel| {
+                            selection_span(sel, &ui_text.text).map(|span| (sel, span))
+                        });
+                        let mut sections = Vec::new();
+                        for (range, span) in text_runs(&ui_text.text, &ui_text.spans) {
+                            // Spans fall back to the style of the text while their font loads.
+                            let span_font_id = span
+                                .and_then(|span| span.font.as_ref())
+                                .and_then(|font| {
+                                    self::font_id(
+                                        fonts_map_ref,
+                                        glyph_brush_ref,
+                                        &font_storage,
+                                        font,
+                                    )
+                                })
+                                .unwrap_or(font_id);
+                            let span_scale = span
+                                .and_then(|span| span.font_size)
+                                .map_or(scale, Scale::uniform);
+                            let span_color = span
+                                .and_then(|span| span.color)
+                                .map_or(base_color, |color| mul_blend(&color, &tint_color));
+
+                            // The selected part of the run is drawn in the selected color.
+                            let mut bounds = vec![range.start, range.end];
+                            if let Some((_, (start, end))) = selection {
+                                bounds.extend(
+                                    [start, end]
+                                        .iter()
+                                        .filter(|&&b| b > range.start && b < range.end),
+                                );
+                                bounds.sort();
+                            }
+                            for piece in bounds.windows(2) {
+                                let color = match selection {
+                                    Some((sel, (start, end)))
+                                        if piece[0] >= start && piece[1] <= end =>
+                                    {
+                                        mul_blend(&sel.selected_text_color, &tint_color)
+                                    }
+                                    _ => span_color,
+                                };
+                                sections.push(SectionText {
+                                    text: &ui_text.text[piece[0]..piece[1]],
+                                    scale: span_scale,
+                                    color,
+                                    font_id: span_font_id,
+                                });
+                            }
                         }
+                        sections
                     }
                     (true, None) => {
                         let string_len = ui_text.text.graphemes(true).count();
@@ -366,44 +377,7 @@ impl<'a, B: Backend> System<'a> for UiGlyphsSystem<B> {
 
         loop {
             let action = glyph_brush_ref.process_queued(
-                |rect, data| unsafe {
-                    log::trace!("Upload glyph image at {:?}", rect);
-                    factory
-                        .upload_image(
-                            tex.image().clone(),
-                            rect.width(),
-                            rect.height(),
-                            hal::image::SubresourceLayers {
-                                aspects: hal::format::Aspects::COLOR,
-                                level: 0,
-                                layers: 0..1,
-                            },
-                            hal::image::Offset {
-                                x: rect.min.x as _,
-                                y: rect.min.y as _,
-                                z: 0,
-                            },
-                            hal::image::Extent {
-                                width: rect.width(),
-                                height: rect.height(),
-                                depth: 1,
-                            },
-                            data,
-                            ImageState {
-                                queue: *queue,
-                                stage: hal::pso::PipelineStage::FRAGMENT_SHADER,
-                                access: hal::image::Access::SHADER_READ,
-                                layout: hal::image::Layout::General,
-                            },
-                            ImageState {
-                                queue: *queue,
-                                stage: hal::pso::PipelineStage::FRAGMENT_SHADER,
-                                access: hal::image::Access::SHADER_READ,
-                                layout: hal::image::Layout::General,
-                            },
-                        )
-                        .unwrap();
-                },
+                |rect, data| upload_glyph_rect(factory, *queue, tex, rect, data),
                 move |glyph| {
                     // The glyph's Z parameter smuggles entity id, so glyphs can be associated
                     // for rendering as part of specific components.
@@ -623,7 +597,54 @@ fn update_cursor_position(
     };
 }
 
-fn create_glyph_texture<B: Backend>(
+pub(crate) fn upload_glyph_rect<B: Backend>(
+    factory: &mut Factory<B>,
+    queue: QueueId,
+    tex: &RendyTexture<B>,
+    rect: Rect<u32>,
+    data: &[u8],
+) {
+    log::trace!("Upload glyph image at {:?}", rect);
+    unsafe {
+        factory
+            .upload_image(
+                tex.image().clone(),
+                rect.width(),
+                rect.height(),
+                hal::image::SubresourceLayers {
+                    aspects: hal::format::Aspects::COLOR,
+                    level: 0,
+                    layers: 0..1,
+                },
+                hal::image::Offset {
+                    x: rect.min.x as _,
+                    y: rect.min.y as _,
+                    z: 0,
+                },
+                hal::image::Extent {
+                    width: rect.width(),
+                    height: rect.height(),
+                    depth: 1,
+                },
+                data,
+                ImageState {
+                    queue,
+                    stage: hal::pso::PipelineStage::FRAGMENT_SHADER,
+                    access: hal::image::Access::SHADER_READ,
+                    layout: hal::image::Layout::General,
+                },
+                ImageState {
+                    queue,
+                    stage: hal::pso::PipelineStage::FRAGMENT_SHADER,
+                    access: hal::image::Access::SHADER_READ,
+                    layout: hal::image::Layout::General,
+                },
+            )
+            .unwrap();
+    }
+}
+
+pub(crate) fn create_glyph_texture<B: Backend>(
     factory: &mut Factory<B>,
     queue: QueueId,
     w: u32,
@@ -654,6 +675,22 @@ fn create_glyph_texture<B: Backend>(
         .expect("Failed to create glyph texture")
 }
 
+/// Returns the id of `font` in the glyph brush, adding the font once it is loaded.
+fn font_id(
+    fonts_map: &mut HashMap<u32, FontState>,
+    glyph_brush: &mut GlyphBrush<'static, (u32, UiArgs)>,
+    font_storage: &AssetStorage<FontAsset>,
+    font: &FontHandle,
+) -> Option<FontId> {
+    let font_lookup = fonts_map.entry(font.id()).or_insert(FontState::NotFound);
+    if font_lookup.id().is_none() {
+        if let Some(font) = font_storage.get(font) {
+            *font_lookup = FontState::Ready(glyph_brush.add_font(font.0.clone()));
+        }
+    }
+    font_lookup.id()
+}
+
 fn selection_span(editing: &TextEditing, string: &str) -> Option<(usize, usize)> {
     if editing.highlight_vector == 0 {
         return None;
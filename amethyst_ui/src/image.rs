@@ -1,6 +1,6 @@
 use amethyst_assets::Handle;
 use amethyst_core::ecs::{Component, DenseVecStorage};
-use amethyst_rendy::{SpriteRender, Texture};
+use amethyst_rendy::{atlas::TextureRegion, SpriteRender, Texture};
 
 /// Image used UI widgets, often as background.
 #[derive(Debug, Clone, PartialEq)]
@@ -45,6 +45,20 @@ pub enum UiImage {
         /// Dimensions of the entire texture
         texture_dimensions: [u32; 2],
     },
+    /// An image backed by a 9-sliced sprite, with the distances of the slices from the edges of
+    /// the sprite in pixels
+    NineSliceSprite {
+        /// Sprite sliced into nine parts
+        sprite: SpriteRender,
+        /// Distance from the left edge of the sprite for a slice
+        left_dist: u32,
+        /// Distance from the right edge of the sprite for a slice
+        right_dist: u32,
+        /// Distance from the top edge of the sprite for a slice
+        top_dist: u32,
+        /// Distance from the bottom edge of the sprite for a slice
+        bottom_dist: u32,
+    },
     /// An image entirely covered by single solid color
     SolidColor([f32; 4]),
 }
@@ -52,3 +66,15 @@ pub enum UiImage {
 impl Component for UiImage {
     type Storage = DenseVecStorage<Self>;
 }
+
+impl From<TextureRegion> for UiImage {
+    fn from(region: TextureRegion) -> Self {
+        UiImage::PartialTexture {
+            tex: region.texture,
+            left: region.left,
+            right: region.right,
+            bottom: region.bottom,
+            top: region.top,
+        }
+    }
+}
@@ -17,13 +17,21 @@ pub use self::{
         UiButtonActionRetriggerSystemDesc, UiButtonActionType, UiButtonBuilder,
         UiButtonBuilderResources, UiButtonSystem, UiButtonSystemDesc,
     },
-    drag::{DragWidgetSystemDesc, Draggable},
+    checkbox::{UiCheckbox, UiCheckboxSystem, UiCheckboxSystemDesc},
+    debug_text::{
+        DebugLabels, DebugTextGlyphsSystem, DrawDebugText, DrawDebugTextDesc, RenderDebugText,
+    },
+    drag::{DragPayload, DragWidgetSystemDesc, Draggable, DropTarget, DRAG_THRESHOLD},
+    dropdown::{UiDropdown, UiDropdownSystem, UiDropdownSystemDesc},
     event::{
         targeted, targeted_below, Interactable, TargetedEvent, UiEvent, UiEventType, UiMouseSystem,
     },
     event_retrigger::{
         EventReceiver, EventRetrigger, EventRetriggerSystem, EventRetriggerSystemDesc,
     },
+    flex::{
+        FlexAlign, FlexDirection, FlexJustify, Padding, UiFlex, UiFlexItem, UiFlexSystem,
+    },
     font::{
         default::get_default_font,
         systemfont::{default_system_font, get_all_font_handles, list_system_font_families},
@@ -33,26 +41,38 @@ pub use self::{
     image::UiImage,
     label::{UiLabel, UiLabelBuilder, UiLabelBuilderResources},
     layout::{Anchor, ScaleMode, Stretch, UiTransformSystem, UiTransformSystemDesc},
+    markup::{MarkupFonts, TextSpan},
     pass::{DrawUi, DrawUiDesc, RenderUi},
     prefab::{
         NoCustomUi, ToNativeWidget, UiButtonData, UiCreator, UiFormat, UiImageLoadPrefab,
         UiImagePrefab, UiLoader, UiLoaderSystem, UiLoaderSystemDesc, UiPrefab, UiTextData,
         UiTransformData, UiWidget,
     },
+    progress::{FillDirection, UiProgressBar, UiProgressBarSystem},
     resize::{ResizeSystem, ResizeSystemDesc, UiResize},
+    scroll::{ScrollArea, ScrollAreaSystem},
     selection::{
         Selectable, Selected, SelectionKeyboardSystem, SelectionKeyboardSystemDesc,
         SelectionMouseSystem, SelectionMouseSystemDesc,
     },
     selection_order_cache::{CacheSelectionOrderSystem, CachedSelectionOrder},
+    slider::{UiSlider, UiSliderSystem, UiSliderSystemDesc},
     sound::{
         UiPlaySoundAction, UiSoundRetrigger, UiSoundRetriggerSystem, UiSoundRetriggerSystemDesc,
         UiSoundSystem, UiSoundSystemDesc,
     },
     text::{LineMode, TextEditing, TextEditingMouseSystem, TextEditingMouseSystemDesc, UiText},
     text_editing::{TextEditingInputSystem, TextEditingInputSystemDesc},
+    theme::{UiStyle, UiStyled, UiTheme, UiThemeSystem, UiThemeSystemDesc},
+    toggle::{ToggleGroup, ToggleGroupSystem, ToggleGroupSystemDesc, UiToggle},
+    tooltip::{TooltipContent, UiTooltip, UiTooltipSystem, UiTooltipSystemDesc},
     transform::{get_parent_pixel_size, UiFinder, UiTransform},
+    tween::{Easing, Tween, TweenTarget, UiTween, UiTweenEvent, UiTweenSystem},
     widgets::{Widget, WidgetId, Widgets},
+    world_text::{
+        DrawWorldText, DrawWorldTextDesc, RenderWorldText, WorldText, WorldTextGlyphs,
+        WorldTextGlyphsSystem,
+    },
 };
 
 pub(crate) use amethyst_core::ecs::prelude::Entity;
@@ -60,22 +80,36 @@ pub(crate) use amethyst_core::ecs::prelude::Entity;
 mod blink;
 mod bundle;
 mod button;
+mod checkbox;
+mod debug_text;
 mod drag;
+mod dropdown;
 mod event;
 mod event_retrigger;
+mod flex;
 mod font;
 mod format;
 mod glyphs;
 mod image;
 mod label;
 mod layout;
+mod markup;
 mod pass;
 mod prefab;
+mod progress;
 mod resize;
+mod scroll;
 mod selection;
 mod selection_order_cache;
+mod slider;
 mod sound;
 mod text;
 mod text_editing;
+mod theme;
+mod toggle;
+mod tooltip;
 mod transform;
+mod tween;
 mod widgets;
+mod world_glyphs;
+mod world_text;
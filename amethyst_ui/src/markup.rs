@@ -0,0 +1,299 @@
+//! Styled spans of a `UiText`, and the markup they can be parsed from.
+
+use std::ops::Range;
+
+use crate::FontHandle;
+
+/// Style applied to a range of the text of a `UiText`, over the style of the whole text.
+///
+/// The range is in bytes of `UiText::text`, so it has to start and end on character boundaries.
+/// Spans which don't, or which overlap an earlier span, are ignored when the text is drawn.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextSpan {
+    /// Byte range of the text styled by the span.
+    pub range: Range<usize>,
+    /// Color of the range, instead of the color of the text.
+    pub color: Option<[f32; 4]>,
+    /// Font of the range, e.g. a bold or italic variant of the font of the text.
+    pub font: Option<FontHandle>,
+    /// Height of a line of the range in pixels, instead of the font size of the text.
+    pub font_size: Option<f32>,
+}
+
+impl TextSpan {
+    /// Creates a span of `range` with the style of the text.
+    pub fn new(range: Range<usize>) -> Self {
+        TextSpan {
+            range,
+            color: None,
+            font: None,
+            font_size: None,
+        }
+    }
+
+    /// Sets the color of the span.
+    pub fn with_color(mut self, color: [f32; 4]) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    /// Sets the font of the span.
+    pub fn with_font(mut self, font: FontHandle) -> Self {
+        self.font = Some(font);
+        self
+    }
+
+    /// Sets the font size of the span.
+    pub fn with_font_size(mut self, font_size: f32) -> Self {
+        self.font_size = Some(font_size);
+        self
+    }
+}
+
+/// Fonts used for the bold and italic tags of markup parsed by `UiText::set_markup`.
+///
+/// Without a font for a style, text of that style keeps the font of the text.
+#[derive(Clone, Debug, Default)]
+pub struct MarkupFonts {
+    /// Font of `[b]` text.
+    pub bold: Option<FontHandle>,
+    /// Font of `[i]` text.
+    pub italic: Option<FontHandle>,
+    /// Font of text both bold and italic. Falls back to `bold`, then `italic`.
+    pub bold_italic: Option<FontHandle>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Tag {
+    Bold,
+    Italic,
+    Color([f32; 4]),
+    Size(f32),
+}
+
+impl Tag {
+    fn parse(tag: &str) -> Option<Tag> {
+        let (name, value) = match tag.find('=') {
+            Some(i) => (&tag[..i], Some(&tag[i + 1..])),
+            None => (tag, None),
+        };
+        match (name, value) {
+            ("b", None) => Some(Tag::Bold),
+            ("i", None) => Some(Tag::Italic),
+            ("color", Some(value)) => parse_color(value).map(Tag::Color),
+            ("size", Some(value)) => value
+                .parse::<f32>()
+                .ok()
+                .filter(|size| *size > 0.0)
+                .map(Tag::Size),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Tag::Bold => "b",
+            Tag::Italic => "i",
+            Tag::Color(_) => "color",
+            Tag::Size(_) => "size",
+        }
+    }
+}
+
+/// Parses a `rrggbb` or `rrggbbaa` hex color, optionally prefixed with `#`.
+fn parse_color(value: &str) -> Option<[f32; 4]> {
+    let hex = value.trim_start_matches('#');
+    if (hex.len() != 6 && hex.len() != 8) || !hex.is_ascii() {
+        return None;
+    }
+    let mut color = [1.0; 4];
+    for (i, channel) in color.iter_mut().enumerate().take(hex.len() / 2) {
+        *channel = f32::from(u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok()?) / 255.0;
+    }
+    Some(color)
+}
+
+/// Style of the text inside of the open tags, or `None` if it is the style of the whole text.
+fn style(open: &[Tag], fonts: &MarkupFonts) -> Option<TextSpan> {
+    let bold = open.contains(&Tag::Bold);
+    let italic = open.contains(&Tag::Italic);
+    let font = match (bold, italic) {
+        (true, true) => fonts
+            .bold_italic
+            .as_ref()
+            .or(fonts.bold.as_ref())
+            .or(fonts.italic.as_ref()),
+        (true, false) => fonts.bold.as_ref(),
+        (false, true) => fonts.italic.as_ref(),
+        (false, false) => None,
+    };
+    let mut span = TextSpan::new(0..0);
+    span.font = font.cloned();
+    for tag in open {
+        match *tag {
+            Tag::Color(color) => span.color = Some(color),
+            Tag::Size(size) => span.font_size = Some(size),
+            Tag::Bold | Tag::Italic => {}
+        }
+    }
+    if span.color.is_none() && span.font.is_none() && span.font_size.is_none() {
+        None
+    } else {
+        Some(span)
+    }
+}
+
+/// Parses `markup` into its text and the spans styled by its tags.
+///
+/// Supported tags are `[b]`, `[i]`, `[color=#rrggbb]`, `[color=#rrggbbaa]` and `[size=24]`, each
+/// closed by `[/b]`, `[/i]`, `[/color]` and `[/size]`, and `[[` for a literal `[`. Tags can nest,
+/// a closing tag closes the innermost open tag of its name, and tags left open end with the
+/// text. Anything else in brackets is kept as text.
+pub(crate) fn parse_markup(markup: &str, fonts: &MarkupFonts) -> (String, Vec<TextSpan>) {
+    let mut text = String::with_capacity(markup.len());
+    let mut spans = Vec::new();
+    let mut open: Vec<Tag> = Vec::new();
+    let mut run_start = 0;
+    let mut rest = markup;
+
+    while let Some(i) = rest.find('[') {
+        text.push_str(&rest[..i]);
+        rest = &rest[i..];
+        if rest.starts_with("[[") {
+            text.push('[');
+            rest = &rest[2..];
+            continue;
+        }
+
+        let end = match rest.find(']') {
+            Some(end) => end,
+            None => break,
+        };
+        let tag = &rest[1..end];
+        let changed = if tag.starts_with('/') {
+            open.iter()
+                .rposition(|open| open.name() == &tag[1..])
+                .map(Err)
+        } else {
+            Tag::parse(tag).map(Ok)
+        };
+
+        match changed {
+            Some(change) => {
+                if text.len() > run_start {
+                    if let Some(mut span) = style(&open, fonts) {
+                        span.range = run_start..text.len();
+                        spans.push(span);
+                    }
+                }
+                run_start = text.len();
+                match change {
+                    Ok(tag) => open.push(tag),
+                    Err(i) => {
+                        open.remove(i);
+                    }
+                }
+            }
+            None => text.push_str(&rest[..=end]),
+        }
+        rest = &rest[end + 1..];
+    }
+    text.push_str(rest);
+
+    if text.len() > run_start {
+        if let Some(mut span) = style(&open, fonts) {
+            span.range = run_start..text.len();
+            spans.push(span);
+        }
+    }
+    (text, spans)
+}
+
+/// Splits `text` into consecutive runs, each styled by one of the valid `spans` or by none.
+pub(crate) fn text_runs<'a>(
+    text: &str,
+    spans: &'a [TextSpan],
+) -> Vec<(Range<usize>, Option<&'a TextSpan>)> {
+    let mut sorted = spans
+        .iter()
+        .filter(|span| {
+            span.range.start < span.range.end
+                && span.range.end <= text.len()
+                && text.is_char_boundary(span.range.start)
+                && text.is_char_boundary(span.range.end)
+        })
+        .collect::<Vec<_>>();
+    sorted.sort_by_key(|span| span.range.start);
+
+    let mut runs = Vec::with_capacity(sorted.len() * 2 + 1);
+    let mut position = 0;
+    for span in sorted {
+        if span.range.start < position {
+            continue;
+        }
+        if span.range.start > position {
+            runs.push((position..span.range.start, None));
+        }
+        runs.push((span.range.clone(), Some(span)));
+        position = span.range.end;
+    }
+    if position < text.len() || runs.is_empty() {
+        runs.push((position..text.len(), None));
+    }
+    runs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_nested_tags() {
+        let (text, spans) = parse_markup(
+            "a [color=#ff0000]red [size=20]big[/size][/color] [[b]",
+            &MarkupFonts::default(),
+        );
+        assert_eq!(text, "a red big [b]");
+        assert_eq!(
+            spans,
+            vec![
+                TextSpan::new(2..6).with_color([1.0, 0.0, 0.0, 1.0]),
+                TextSpan::new(6..9)
+                    .with_color([1.0, 0.0, 0.0, 1.0])
+                    .with_font_size(20.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_unknown_tags_and_skips_unstyled_fonts() {
+        // Bold without a bold font keeps the style of the text.
+        let (text, spans) = parse_markup("[x]a[b]b[/b][/i]", &MarkupFonts::default());
+        assert_eq!(text, "[x]ab[/i]");
+        assert!(spans.is_empty());
+    }
+
+    #[test]
+    fn splits_text_into_runs() {
+        let spans = vec![
+            TextSpan::new(4..6).with_font_size(10.0),
+            TextSpan::new(1..3).with_font_size(20.0),
+            // Overlaps the previous span.
+            TextSpan::new(2..5).with_font_size(30.0),
+        ];
+        let runs = text_runs("abcdefg", &spans)
+            .into_iter()
+            .map(|(range, span)| (range, span.and_then(|span| span.font_size)))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            runs,
+            vec![
+                (0..1, None),
+                (1..3, Some(20.0)),
+                (3..4, None),
+                (4..6, Some(10.0)),
+                (6..7, None),
+            ]
+        );
+    }
+}
@@ -8,14 +8,14 @@ use amethyst_core::{
         hibitset::BitSet, DispatcherBuilder, Entities, Entity, Join, Read, ReadExpect, ReadStorage,
         SystemData, World,
     },
-    Hidden, HiddenPropagate, SystemDesc,
+    Hidden, HiddenPropagate, Parent, SystemDesc,
 };
 use amethyst_error::Error;
 use amethyst_rendy::{
     batch::OrderedOneLevelBatch,
     bundle::{RenderOrder, RenderPlan, RenderPlugin, Target},
     palette,
-    pipeline::{PipelineDescBuilder, PipelinesBuilder},
+    pipeline::{PipelineCache, PipelineDescBuilder, PipelinesBuilder},
     rendy::{
         command::{QueueId, RenderPassEncoder},
         factory::Factory,
@@ -33,8 +33,10 @@ use amethyst_rendy::{
         shader::{Shader, SpirvShader},
         texture::palette::load_from_srgba,
     },
-    resources::Tint,
+    resources::{ScissorRect, Tint},
+    shader_reload::ReloadableShader,
     simple_shader_set,
+    stats::DrawCounter,
     submodules::{DynamicUniform, DynamicVertexBuffer, TextureId, TextureSub},
     types::{Backend, Texture},
     ChangeDetection, SpriteSheet,
@@ -117,17 +119,25 @@ struct UiViewArgs {
 }
 
 lazy_static::lazy_static! {
-    static ref UI_VERTEX: SpirvShader = SpirvShader::from_bytes(
-        include_bytes!("../compiled/ui.vert.spv"),
-        ShaderStageFlags::VERTEX,
-        "main",
-    ).unwrap();
-
-    static ref UI_FRAGMENT: SpirvShader = SpirvShader::from_bytes(
-        include_bytes!("../compiled/ui.frag.spv"),
-        ShaderStageFlags::FRAGMENT,
-        "main",
-    ).unwrap();
+    static ref UI_VERTEX: ReloadableShader = ReloadableShader::new(
+        env!("CARGO_PKG_NAME"),
+        "compiled/ui.vert.spv",
+        SpirvShader::from_bytes(
+            include_bytes!("../compiled/ui.vert.spv"),
+            ShaderStageFlags::VERTEX,
+            "main",
+        ).unwrap(),
+    );
+
+    static ref UI_FRAGMENT: ReloadableShader = ReloadableShader::new(
+        env!("CARGO_PKG_NAME"),
+        "compiled/ui.frag.spv",
+        SpirvShader::from_bytes(
+            include_bytes!("../compiled/ui.frag.spv"),
+            ShaderStageFlags::FRAGMENT,
+            "main",
+        ).unwrap(),
+    );
 }
 
 /// A UI drawing pass that draws UI elements and text in screen-space
@@ -161,8 +171,11 @@ impl<B: Backend> RenderGroupDesc<B, World> for DrawUiDesc {
         let textures = TextureSub::new(factory)?;
         let vertex = DynamicVertexBuffer::new();
 
+        let pipeline_cache = resources.try_fetch::<PipelineCache<B>>();
+
         let (pipeline, pipeline_layout) = build_ui_pipeline(
             factory,
+            pipeline_cache.as_ref().and_then(|cache| cache.raw()),
             subpass,
             framebuffer_width,
             framebuffer_height,
@@ -186,6 +199,12 @@ impl<B: Backend> RenderGroupDesc<B, World> for DrawUiDesc {
             change: Default::default(),
             cached_draw_order: Default::default(),
             batches: Default::default(),
+            framebuffer_rect: pso::Rect {
+                x: 0,
+                y: 0,
+                w: framebuffer_width as i16,
+                h: framebuffer_height as i16,
+            },
             white_tex,
         }))
     }
@@ -199,7 +218,8 @@ pub struct DrawUi<B: Backend> {
     env: DynamicUniform<B, UiViewArgs>,
     textures: TextureSub<B>,
     vertex: DynamicVertexBuffer<B, UiArgs>,
-    batches: OrderedOneLevelBatch<TextureId, UiArgs>,
+    batches: OrderedOneLevelBatch<(TextureId, Option<ScissorRect>), UiArgs>,
+    framebuffer_rect: pso::Rect,
     change: ChangeDetection,
     cached_draw_order: CachedDrawOrder,
     white_tex: Handle<Texture>,
@@ -233,6 +253,8 @@ impl<B: Backend> RenderGroup<B, World> for DrawUi<B> {
             hidden_propagates,
             selected,
             tints,
+            scissors,
+            parents,
             glyphs,
             glyphs_res,
             screen_dimesnions,
@@ -245,6 +267,8 @@ impl<B: Backend> RenderGroup<B, World> for DrawUi<B> {
             ReadStorage<'_, HiddenPropagate>,
             ReadStorage<'_, Selected>,
             ReadStorage<'_, Tint>,
+            ReadStorage<'_, ScissorRect>,
+            ReadStorage<'_, Parent>,
             ReadStorage<'_, UiGlyphs>,
             ReadExpect<'_, UiGlyphsResource>,
             ReadExpect<'_, ScreenDimensions>,
@@ -346,6 +370,18 @@ impl<B: Backend> RenderGroup<B, World> for DrawUi<B> {
                 [r, g, b, a]
             });
 
+            // Elements are clipped to the scissor rectangles of themselves and their parents.
+            let mut scissor = scissors.get(entity).copied();
+            let mut ancestor = parents.get(entity);
+            while let Some(parent) = ancestor {
+                if let Some(parent_scissor) = scissors.get(parent.entity) {
+                    scissor = Some(scissor.map_or(*parent_scissor, |scissor| {
+                        scissor.intersection(parent_scissor)
+                    }));
+                }
+                ancestor = parents.get(parent.entity);
+            }
+
             let image = images.get(entity);
             if let Some(image) = image {
                 let this_changed = render_image(
@@ -355,6 +391,7 @@ impl<B: Backend> RenderGroup<B, World> for DrawUi<B> {
                     image,
                     &tint,
                     white_tex_id,
+                    scissor,
                     &mut self.textures,
                     &mut self.batches,
                 );
@@ -363,8 +400,10 @@ impl<B: Backend> RenderGroup<B, World> for DrawUi<B> {
 
             if let Some(glyph_data) = glyphs.get(entity) {
                 if !glyph_data.sel_vertices.is_empty() {
-                    self.batches
-                        .insert(white_tex_id, glyph_data.sel_vertices.iter().cloned());
+                    self.batches.insert(
+                        (white_tex_id, scissor),
+                        glyph_data.sel_vertices.iter().cloned(),
+                    );
                 }
 
                 // blinking cursor
@@ -400,7 +439,7 @@ impl<B: Backend> RenderGroup<B, World> for DrawUi<B> {
                         let h = bottom - top;
 
                         self.batches.insert(
-                            white_tex_id,
+                            (white_tex_id, scissor),
                             Some(UiArgs {
                                 coords: [x, y].into(),
                                 dimensions: [w, h].into(),
@@ -414,7 +453,7 @@ impl<B: Backend> RenderGroup<B, World> for DrawUi<B> {
 
                 if !glyph_data.vertices.is_empty() {
                     self.batches
-                        .insert(glyph_tex_id, glyph_data.vertices.iter().cloned());
+                        .insert((glyph_tex_id, scissor), glyph_data.vertices.iter().cloned());
                 }
             }
         }
@@ -451,21 +490,30 @@ impl<B: Backend> RenderGroup<B, World> for DrawUi<B> {
         mut encoder: RenderPassEncoder<'_, B>,
         index: usize,
         _subpass: hal::pass::Subpass<'_, B>,
-        _resources: &World,
+        resources: &World,
     ) {
         #[cfg(feature = "profiler")]
         profile_scope!("draw");
 
         if self.batches.count() > 0 {
+            let mut draws = DrawCounter::fetch(resources);
             let layout = &self.pipeline_layout;
             encoder.bind_graphics_pipeline(&self.pipeline);
             self.env.bind(index, &self.pipeline_layout, 0, &mut encoder);
             self.vertex.bind(index, 0, 0, &mut encoder);
-            for (&tex, range) in self.batches.iter() {
+            for (&(tex, scissor), range) in self.batches.iter() {
+                let rect = match scissor {
+                    Some(scissor) if scissor.is_empty() => continue,
+                    Some(scissor) => scissor.to_rect(),
+                    None => self.framebuffer_rect,
+                };
                 self.textures.bind(layout, 1, tex, &mut encoder);
+                let instances = range.end - range.start;
                 unsafe {
+                    encoder.set_scissors(0, Some(&rect));
                     encoder.draw(0..4, range);
                 }
+                draws.draw_quads(instances);
             }
         }
     }
@@ -482,6 +530,7 @@ impl<B: Backend> RenderGroup<B, World> for DrawUi<B> {
 
 fn build_ui_pipeline<B: Backend>(
     factory: &Factory<B>,
+    cache: Option<&B::PipelineCache>,
     subpass: hal::pass::Subpass<'_, B>,
     framebuffer_width: u32,
     framebuffer_height: u32,
@@ -496,21 +545,36 @@ fn build_ui_pipeline<B: Backend>(
     let shader_vertex = unsafe { UI_VERTEX.module(factory).unwrap() };
     let shader_fragment = unsafe { UI_FRAGMENT.module(factory).unwrap() };
 
+    let mut pipe_desc = PipelineDescBuilder::new()
+        .with_vertex_desc(&[(UiArgs::vertex(), pso::VertexInputRate::Instance(1))])
+        .with_input_assembler(pso::InputAssemblerDesc::new(hal::Primitive::TriangleStrip))
+        .with_shaders(simple_shader_set(&shader_vertex, Some(&shader_fragment)))
+        .with_layout(&pipeline_layout)
+        .with_subpass(subpass)
+        .with_framebuffer_size(framebuffer_width, framebuffer_height)
+        .with_blend_targets(vec![pso::ColorBlendDesc {
+            mask: pso::ColorMask::ALL,
+            blend: Some(pso::BlendState::ALPHA),
+        }]);
+    // Scissor is set per batch while drawing.
+    pipe_desc.set_baked_states(pso::BakedStates {
+        viewport: Some(pso::Viewport {
+            rect: pso::Rect {
+                x: 0,
+                y: 0,
+                w: framebuffer_width as i16,
+                h: framebuffer_height as i16,
+            },
+            depth: 0.0..1.0,
+        }),
+        scissor: None,
+        blend_color: None,
+        depth_bounds: None,
+    });
+
     let pipes = PipelinesBuilder::new()
-        .with_pipeline(
-            PipelineDescBuilder::new()
-                .with_vertex_desc(&[(UiArgs::vertex(), pso::VertexInputRate::Instance(1))])
-                .with_input_assembler(pso::InputAssemblerDesc::new(hal::Primitive::TriangleStrip))
-                .with_shaders(simple_shader_set(&shader_vertex, Some(&shader_fragment)))
-                .with_layout(&pipeline_layout)
-                .with_subpass(subpass)
-                .with_framebuffer_size(framebuffer_width, framebuffer_height)
-                .with_blend_targets(vec![pso::ColorBlendDesc {
-                    mask: pso::ColorMask::ALL,
-                    blend: Some(pso::BlendState::ALPHA),
-                }]),
-        )
-        .build(factory, None);
+        .with_pipeline(pipe_desc)
+        .build_cached(factory, cache);
 
     unsafe {
         factory.destroy_shader_module(shader_vertex);
@@ -539,8 +603,9 @@ fn render_image<B: Backend>(
     raw_image: &UiImage,
     tint: &Option<[f32; 4]>,
     white_tex_id: TextureId,
+    scissor: Option<ScissorRect>,
     textures: &mut TextureSub<B>,
-    batches: &mut OrderedOneLevelBatch<TextureId, UiArgs>,
+    batches: &mut OrderedOneLevelBatch<(TextureId, Option<ScissorRect>), UiArgs>,
 ) -> bool {
     let color = match (raw_image, tint.as_ref()) {
         (UiImage::SolidColor(color), Some(t)) => mul_blend(color, t),
@@ -590,7 +655,7 @@ fn render_image<B: Backend>(
                 tex,
                 hal::image::Layout::ShaderReadOnlyOptimal,
             ) {
-                batches.insert(tex_id, Some(args));
+                batches.insert((tex_id, scissor), Some(args));
                 this_changed
             } else {
                 false
@@ -603,7 +668,7 @@ fn render_image<B: Backend>(
                 tex,
                 hal::image::Layout::ShaderReadOnlyOptimal,
             ) {
-                batches.insert(tex_id, Some(args));
+                batches.insert((tex_id, scissor), Some(args));
                 this_changed
             } else {
                 false
@@ -618,7 +683,7 @@ fn render_image<B: Backend>(
                     &sprite_sheet.texture,
                     hal::image::Layout::ShaderReadOnlyOptimal,
                 ) {
-                    batches.insert(tex_id, Some(args));
+                    batches.insert((tex_id, scissor), Some(args));
                     this_changed
                 } else {
                     false
@@ -659,45 +724,69 @@ fn render_image<B: Backend>(
                     (*y_start + *height) as f32 / texture_dimensions[1] as f32,
                 ];
 
-                //The dimensions of each slice
-                let x_dimensions = [
-                    *left_dist as f32,
-                    transform.pixel_width - (*right_dist + *left_dist) as f32,
-                    *right_dist as f32,
-                ];
-                let y_dimensions = [
-                    *top_dist as f32,
-                    transform.pixel_height - (*top_dist + *bottom_dist) as f32,
-                    *bottom_dist as f32,
-                ];
+                for slice in nine_slices(
+                    args,
+                    transform,
+                    x_tex_coord_bound,
+                    y_tex_coord_bound,
+                    [*left_dist as f32, *right_dist as f32],
+                    [*top_dist as f32, *bottom_dist as f32],
+                ) {
+                    batches.insert((tex_id, scissor), Some(slice));
+                }
 
-                //The center location of each slice on the screen
-                let x_coords = [
-                    transform.pixel_x() - ((transform.pixel_width - *left_dist as f32) / 2.0),
-                    transform.pixel_x() + (*left_dist as f32 - *right_dist as f32) / 2.0,
-                    transform.pixel_x() + ((transform.pixel_width - *right_dist as f32) / 2.0),
+                this_changed
+            } else {
+                false
+            }
+        }
+        UiImage::NineSliceSprite {
+            sprite,
+            left_dist,
+            right_dist,
+            top_dist,
+            bottom_dist,
+        } => {
+            let sprite_sheets = resources.fetch::<AssetStorage<SpriteSheet>>();
+            let sprite_sheet = match sprite_sheets.get(&sprite.sprite_sheet) {
+                Some(sprite_sheet) => sprite_sheet,
+                None => return false,
+            };
+            if let Some((tex_id, this_changed)) = textures.insert(
+                factory,
+                resources,
+                &sprite_sheet.texture,
+                hal::image::Layout::ShaderReadOnlyOptimal,
+            ) {
+                let sprite = &sprite_sheet.sprites[sprite.sprite_number];
+                let tex = &sprite.tex_coords;
+                // Slices are cut at the same fractions of the sprite in pixels and in
+                // texture coordinates.
+                let cut = |from: f32, to: f32, dist: u32, size: f32| {
+                    from + (to - from) * dist as f32 / size
+                };
+                let x_tex_coord_bound = [
+                    tex.left,
+                    cut(tex.left, tex.right, *left_dist, sprite.width),
+                    cut(tex.right, tex.left, *right_dist, sprite.width),
+                    tex.right,
                 ];
-                let y_coords = [
-                    transform.pixel_y() + ((transform.pixel_height - *top_dist as f32) / 2.0),
-                    transform.pixel_y() + (*bottom_dist as f32 - *top_dist as f32) / 2.0,
-                    transform.pixel_y() - ((transform.pixel_height - *bottom_dist as f32) / 2.0),
+                let y_tex_coord_bound = [
+                    tex.top,
+                    cut(tex.top, tex.bottom, *top_dist, sprite.height),
+                    cut(tex.bottom, tex.top, *bottom_dist, sprite.height),
+                    tex.bottom,
                 ];
 
-                // loop through left to right, the top to bottom and batch each slice to render
-                for x in 0..3 {
-                    for y in 0..3 {
-                        let mut temp_args = args;
-                        temp_args.tex_coord_bounds = [
-                            x_tex_coord_bound[x],
-                            y_tex_coord_bound[y],
-                            x_tex_coord_bound[x + 1],
-                            y_tex_coord_bound[y + 1],
-                        ]
-                        .into();
-                        temp_args.dimensions = [x_dimensions[x], y_dimensions[y]].into();
-                        temp_args.coords = [x_coords[x], y_coords[y]].into();
-                        batches.insert(tex_id, Some(temp_args));
-                    }
+                for slice in nine_slices(
+                    args,
+                    transform,
+                    x_tex_coord_bound,
+                    y_tex_coord_bound,
+                    [*left_dist as f32, *right_dist as f32],
+                    [*top_dist as f32, *bottom_dist as f32],
+                ) {
+                    batches.insert((tex_id, scissor), Some(slice));
                 }
 
                 this_changed
@@ -706,8 +795,66 @@ fn render_image<B: Backend>(
             }
         }
         _ => {
-            batches.insert(white_tex_id, Some(args));
+            batches.insert((white_tex_id, scissor), Some(args));
             false
         }
     }
 }
+
+/// Splits `args` stretched over `transform` into nine slices, from left to right, then top to
+/// bottom. The slice edges are at `x_tex_coord_bound` and `y_tex_coord_bound` on the texture.
+///
+/// The borders keep their size in pixels while the center stretches. When the element is
+/// smaller than the two borders of an axis, they shrink to fit and the center disappears.
+fn nine_slices(
+    args: UiArgs,
+    transform: &UiTransform,
+    x_tex_coord_bound: [f32; 4],
+    y_tex_coord_bound: [f32; 4],
+    x_borders: [f32; 2],
+    y_borders: [f32; 2],
+) -> impl Iterator<Item = UiArgs> {
+    let (x_dimensions, x_coords) =
+        slice_axis(transform.pixel_x(), transform.pixel_width, x_borders, 1.0);
+    // Pixel coordinates go up, while slices go from the top.
+    let (y_dimensions, y_coords) =
+        slice_axis(transform.pixel_y(), transform.pixel_height, y_borders, -1.0);
+
+    (0..3).flat_map(move |x| {
+        (0..3).map(move |y| {
+            let mut slice = args;
+            slice.tex_coord_bounds = [
+                x_tex_coord_bound[x],
+                y_tex_coord_bound[y],
+                x_tex_coord_bound[x + 1],
+                y_tex_coord_bound[y + 1],
+            ]
+            .into();
+            slice.dimensions = [x_dimensions[x], y_dimensions[y]].into();
+            slice.coords = [x_coords[x], y_coords[y]].into();
+            slice
+        })
+    })
+}
+
+/// Returns the sizes and the centers of the three slices of an axis, starting at the side
+/// `direction` points away from.
+fn slice_axis(center: f32, size: f32, borders: [f32; 2], direction: f32) -> ([f32; 3], [f32; 3]) {
+    let total = borders[0] + borders[1];
+    let scale = if total > size && total > 0.0 {
+        size.max(0.0) / total
+    } else {
+        1.0
+    };
+    let (first, last) = (borders[0] * scale, borders[1] * scale);
+    let middle = (size - first - last).max(0.0);
+    let start = center - direction * size / 2.0;
+    (
+        [first, middle, last],
+        [
+            start + direction * first / 2.0,
+            start + direction * (first + middle / 2.0),
+            start + direction * (size - last / 2.0),
+        ],
+    )
+}
@@ -23,9 +23,10 @@ use amethyst_rendy::TexturePrefab;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    get_default_font, Anchor, Draggable, FontAsset, Interactable, LineMode, Selectable, Stretch,
-    TextEditing, UiButton, UiButtonAction, UiButtonActionRetrigger, UiButtonActionType, UiImage,
-    UiPlaySoundAction, UiSoundRetrigger, UiText, UiTransform, WidgetId, Widgets,
+    get_default_font, Anchor, DragPayload, Draggable, DropTarget, FontAsset, Interactable,
+    LineMode, MarkupFonts, Selectable, Stretch, TextEditing, UiButton, UiButtonAction,
+    UiButtonActionRetrigger, UiButtonActionType, UiImage, UiPlaySoundAction, UiSoundRetrigger,
+    UiStyled, UiText, UiTransform, WidgetId, Widgets,
 };
 
 /// Loadable `UiTransform` data.
@@ -81,6 +82,12 @@ pub struct UiTransformData<G> {
     pub selectable: Option<u32>,
     /// Makes the UiTransform draggable through mouse inputs.
     pub draggable: bool,
+    /// Data sent along with the drag and drop events of a draggable UiTransform.
+    pub drag_payload: Option<DragPayload>,
+    /// Makes the UiTransform a place draggable UiTransforms can be dropped on.
+    pub drop_target: Option<DropTarget>,
+    /// Name of the `UiTheme` style applied to the element, see `UiStyled`.
+    pub style: Option<String>,
     #[serde(skip)]
     _phantom: PhantomData<G>,
 }
@@ -139,6 +146,15 @@ impl<G> UiTransformData<G> {
         self.stretch = Some(stretch);
         self
     }
+
+    /// Set the name of the theme style
+    pub fn with_style<S>(mut self, style: S) -> Self
+    where
+        S: ToString,
+    {
+        self.style = Some(style.to_string());
+        self
+    }
 }
 
 impl<'a, G> PrefabData<'a> for UiTransformData<G>
@@ -151,6 +167,9 @@ where
         WriteStorage<'a, HiddenPropagate>,
         WriteStorage<'a, Selectable<G>>,
         WriteStorage<'a, Draggable>,
+        WriteStorage<'a, UiStyled>,
+        WriteStorage<'a, DragPayload>,
+        WriteStorage<'a, DropTarget>,
     );
     type Result = ();
 
@@ -198,6 +217,18 @@ where
             system_data.4.insert(entity, Draggable)?;
         }
 
+        if let Some(ref style) = self.style {
+            system_data.5.insert(entity, UiStyled::new(style))?;
+        }
+
+        if let Some(ref payload) = self.drag_payload {
+            system_data.6.insert(entity, payload.clone())?;
+        }
+
+        if let Some(ref drop_target) = self.drop_target {
+            system_data.7.insert(entity, drop_target.clone())?;
+        }
+
         Ok(())
     }
 }
@@ -228,6 +259,15 @@ pub struct UiTextData {
     /// Optionally make the text editable
     #[serde(default)]
     pub editable: Option<TextEditingPrefab>,
+    /// Parse the text as markup with styled spans, see `UiText::set_markup`.
+    #[serde(default)]
+    pub markup: bool,
+    /// Font of bold markup text.
+    #[serde(default)]
+    pub bold_font: Option<AssetPrefab<FontAsset>>,
+    /// Font of italic markup text.
+    #[serde(default)]
+    pub italic_font: Option<AssetPrefab<FontAsset>>,
 }
 impl Debug for UiTextData {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
@@ -248,6 +288,7 @@ impl Debug for UiTextData {
             .field("line_mode", &self.line_mode)
             .field("align", &self.align)
             .field("editable", &self.editable)
+            .field("markup", &self.markup)
             .finish()
     }
 }
@@ -317,6 +358,20 @@ impl<'a> PrefabData<'a> for UiTextData {
             ui_text_align,
         );
         ui_text.password = self.password;
+        if self.markup {
+            let markup_fonts = MarkupFonts {
+                bold: match self.bold_font {
+                    Some(ref font) => Some(font.add_to_entity(entity, fonts, &[], &[])?),
+                    None => None,
+                },
+                italic: match self.italic_font {
+                    Some(ref font) => Some(font.add_to_entity(entity, fonts, &[], &[])?),
+                    None => None,
+                },
+                bold_italic: None,
+            };
+            ui_text.set_markup(&self.text, &markup_fonts);
+        }
 
         texts.insert(entity, ui_text)?;
         if let Some(ref editing) = self.editable {
@@ -340,12 +395,17 @@ impl<'a> PrefabData<'a> for UiTextData {
     ) -> Result<bool, Error> {
         let (_, _, ref mut fonts) = system_data;
 
-        self.font
+        let mut loading = self
+            .font
             .get_or_insert_with(|| {
                 let (ref loader, _, ref storage) = fonts;
                 AssetPrefab::Handle(get_default_font(loader, storage))
             })
-            .load_sub_assets(progress, fonts)
+            .load_sub_assets(progress, fonts)?;
+        for font in self.bold_font.iter_mut().chain(self.italic_font.iter_mut()) {
+            loading |= font.load_sub_assets(progress, fonts)?;
+        }
+        Ok(loading)
     }
 }
 
@@ -973,6 +1033,9 @@ fn walk_ui_tree<C, W>(
                 line_mode: None,
                 text: button.text.clone(),
                 font_size: button.font_size,
+                markup: false,
+                bold_font: None,
+                italic_font: None,
             };
 
             prefab
@@ -0,0 +1,225 @@
+use serde::{Deserialize, Serialize};
+
+use amethyst_core::{
+    ecs::{Component, DenseVecStorage, Entity, Join, Read, System, WriteStorage},
+    timing::Time,
+};
+
+use crate::{Anchor, ScaleMode, Stretch, UiTransform};
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+/// Edge of a `UiProgressBar` its fill grows from.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Deserialize, Serialize)]
+pub enum FillDirection {
+    /// Grows from the left edge to the right.
+    LeftToRight,
+    /// Grows from the right edge to the left.
+    RightToLeft,
+    /// Grows from the bottom edge up.
+    BottomToTop,
+    /// Grows from the top edge down.
+    TopToBottom,
+}
+
+impl FillDirection {
+    fn anchor(self) -> Anchor {
+        match self {
+            FillDirection::LeftToRight => Anchor::MiddleLeft,
+            FillDirection::RightToLeft => Anchor::MiddleRight,
+            FillDirection::BottomToTop => Anchor::BottomMiddle,
+            FillDirection::TopToBottom => Anchor::TopMiddle,
+        }
+    }
+}
+
+/// Component making a ui element a progress bar, e.g. a health or loading bar, whose `fill` child
+/// covers the fraction of the bar `value` is at between `min` and `max`.
+///
+/// The `UiProgressBarSystem` sets the anchor, pivot, position and size of the fill, in
+/// `ScaleMode::Percent`, so setting `value` is enough to update the bar.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UiProgressBar {
+    /// Value of an empty bar.
+    pub min: f32,
+    /// Value of a full bar.
+    pub max: f32,
+    /// Current value.
+    pub value: f32,
+    /// Edge the fill grows from.
+    pub direction: FillDirection,
+    /// Fraction of the bar the fill moves by per second towards the value, or `None` for it to
+    /// follow the value immediately.
+    pub fill_speed: Option<f32>,
+    /// Child covering the filled part of the bar.
+    #[serde(skip)]
+    pub fill: Option<Entity>,
+    /// Fraction of the bar currently covered by the fill.
+    #[serde(skip)]
+    displayed: Option<f32>,
+}
+
+impl UiProgressBar {
+    /// Creates a bar filling from left to right between `min` and `max`, at `value`.
+    pub fn new(min: f32, max: f32, value: f32) -> Self {
+        UiProgressBar {
+            min,
+            max,
+            value,
+            direction: FillDirection::LeftToRight,
+            fill_speed: None,
+            fill: None,
+            displayed: None,
+        }
+    }
+
+    /// Sets the edge the fill grows from.
+    pub fn with_direction(mut self, direction: FillDirection) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Animates the fill towards the value by `fill_speed` of the bar per second.
+    pub fn with_fill_speed(mut self, fill_speed: f32) -> Self {
+        self.fill_speed = Some(fill_speed);
+        self
+    }
+
+    /// Sets the child covering the filled part of the bar.
+    pub fn with_fill(mut self, fill: Entity) -> Self {
+        self.fill = Some(fill);
+        self
+    }
+
+    /// Returns the fraction of the bar the value is at.
+    pub fn fraction(&self) -> f32 {
+        if (self.max - self.min).abs() > std::f32::EPSILON {
+            ((self.value - self.min) / (self.max - self.min))
+                .max(0.0)
+                .min(1.0)
+        } else {
+            0.0
+        }
+    }
+
+    /// Returns the fraction of the bar currently covered by the fill, which lags behind the value
+    /// while the fill is animated.
+    pub fn displayed_fraction(&self) -> f32 {
+        self.displayed.unwrap_or_else(|| self.fraction())
+    }
+}
+
+impl Component for UiProgressBar {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Animates and sizes the fill of `UiProgressBar`s.
+///
+/// It's automatically registered with the `UiBundle`.
+#[derive(Debug, Default)]
+pub struct UiProgressBarSystem;
+
+impl UiProgressBarSystem {
+    /// Creates a new `UiProgressBarSystem`.
+    pub fn new() -> Self {
+        UiProgressBarSystem
+    }
+}
+
+impl<'s> System<'s> for UiProgressBarSystem {
+    type SystemData = (
+        Read<'s, Time>,
+        WriteStorage<'s, UiProgressBar>,
+        WriteStorage<'s, UiTransform>,
+    );
+
+    fn run(&mut self, (time, mut bars, mut transforms): Self::SystemData) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("ui_progress_bar_system");
+
+        for bar in (&mut bars).join() {
+            let target = bar.fraction();
+            let displayed = match (bar.displayed, bar.fill_speed) {
+                (Some(displayed), Some(speed)) => {
+                    let step = speed * time.delta_seconds();
+                    if (target - displayed).abs() <= step {
+                        target
+                    } else if target > displayed {
+                        displayed + step
+                    } else {
+                        displayed - step
+                    }
+                }
+                _ => target,
+            };
+            bar.displayed = Some(displayed);
+
+            let fill = match bar.fill {
+                Some(fill) => fill,
+                None => continue,
+            };
+            let transform = match transforms.get(fill) {
+                Some(transform) => transform,
+                None => continue,
+            };
+            let anchor = bar.direction.anchor();
+            let (width, height) = match bar.direction {
+                FillDirection::LeftToRight | FillDirection::RightToLeft => (displayed, 1.0),
+                FillDirection::BottomToTop | FillDirection::TopToBottom => (1.0, displayed),
+            };
+            let changed = transform.anchor != anchor
+                || transform.pivot != anchor
+                || transform.scale_mode != ScaleMode::Percent
+                || match transform.stretch {
+                    Stretch::NoStretch => false,
+                    _ => true,
+                }
+                || transform.local_x.abs() > std::f32::EPSILON
+                || transform.local_y.abs() > std::f32::EPSILON
+                || (transform.width - width).abs() > std::f32::EPSILON
+                || (transform.height - height).abs() > std::f32::EPSILON;
+            if changed {
+                let transform = transforms.get_mut(fill).expect("Checked above");
+                transform.anchor = anchor;
+                transform.pivot = anchor;
+                transform.scale_mode = ScaleMode::Percent;
+                transform.stretch = Stretch::NoStretch;
+                transform.local_x = 0.0;
+                transform.local_y = 0.0;
+                transform.width = width;
+                transform.height = height;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::UiProgressBar;
+
+    #[test]
+    fn fraction_is_the_position_of_the_value_between_min_and_max() {
+        assert!((UiProgressBar::new(0.0, 100.0, 25.0).fraction() - 0.25).abs() < 1e-6);
+        assert!((UiProgressBar::new(-1.0, 1.0, 0.5).fraction() - 0.75).abs() < 1e-6);
+        assert!((UiProgressBar::new(10.0, 0.0, 2.5).fraction() - 0.75).abs() < 1e-6);
+    }
+
+    #[test]
+    fn fraction_is_clamped() {
+        assert!(UiProgressBar::new(0.0, 100.0, -10.0).fraction().abs() < 1e-6);
+        assert!((UiProgressBar::new(0.0, 100.0, 150.0).fraction() - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn fraction_is_zero_when_min_equals_max() {
+        assert!(UiProgressBar::new(5.0, 5.0, 5.0).fraction().abs() < 1e-6);
+        assert!(UiProgressBar::new(5.0, 5.0, 10.0).fraction().abs() < 1e-6);
+    }
+
+    #[test]
+    fn displayed_fraction_follows_the_value_until_animated() {
+        let bar = UiProgressBar::new(0.0, 4.0, 1.0).with_fill_speed(0.5);
+        assert!((bar.displayed_fraction() - 0.25).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,360 @@
+use std::marker::PhantomData;
+
+use amethyst_core::{
+    ecs::{
+        Component, DenseVecStorage, Entities, Entity, Join, Read, ReadExpect, ReadStorage, System,
+        WriteStorage,
+    },
+    math::Vector2,
+    Hidden, HiddenPropagate,
+};
+use amethyst_input::{BindingTypes, InputHandler};
+use amethyst_rendy::resources::ScissorRect;
+use amethyst_window::ScreenDimensions;
+use winit::MouseButton;
+
+use crate::{Anchor, ScaleMode, UiTransform};
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+/// Component scrolling the `content` child of a ui element, which is clipped to the element.
+///
+/// The content is placed with its top left corner at the top left corner of the element, moved
+/// by `offset`, so its anchor and pivot are set to `Anchor::TopLeft`. The mouse wheel scrolls
+/// the innermost area under the cursor, and dragging with the left mouse button moves the
+/// content when `drag` is set. Scroll bars are optional children of the element, whose length
+/// and position along their edge are set to show the visible part of the content.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScrollArea {
+    /// Child entity scrolled, usually larger than the element.
+    pub content: Entity,
+    /// Pixels the content is scrolled by, to the left and up.
+    pub offset: Vector2<f32>,
+    /// Pixels scrolled by a step of the mouse wheel.
+    pub scroll_speed: f32,
+    /// Whether the content scrolls horizontally.
+    pub horizontal: bool,
+    /// Whether the content scrolls vertically.
+    pub vertical: bool,
+    /// Whether the content can be dragged with the left mouse button.
+    pub drag: bool,
+    /// Thumb of the horizontal scroll bar, anchored to the bottom edge of the element.
+    pub horizontal_bar: Option<Entity>,
+    /// Thumb of the vertical scroll bar, anchored to the right edge of the element.
+    pub vertical_bar: Option<Entity>,
+}
+
+impl ScrollArea {
+    /// Creates a vertically scrolling area of `content`, which can be dragged.
+    pub fn new(content: Entity) -> Self {
+        ScrollArea {
+            content,
+            offset: Vector2::zeros(),
+            scroll_speed: 40.0,
+            horizontal: false,
+            vertical: true,
+            drag: true,
+            horizontal_bar: None,
+            vertical_bar: None,
+        }
+    }
+
+    /// Sets the directions the content scrolls in.
+    pub fn with_directions(mut self, horizontal: bool, vertical: bool) -> Self {
+        self.horizontal = horizontal;
+        self.vertical = vertical;
+        self
+    }
+
+    /// Sets the thumbs of the scroll bars.
+    pub fn with_bars(mut self, horizontal: Option<Entity>, vertical: Option<Entity>) -> Self {
+        self.horizontal_bar = horizontal;
+        self.vertical_bar = vertical;
+        self
+    }
+
+    /// Keeps the offset between zero and the part of content of `content_size` which doesn't fit
+    /// in an area of `size`, along the directions the area scrolls in.
+    fn clamp_offset(&mut self, size: Vector2<f32>, content_size: Vector2<f32>) {
+        let max_offset = Vector2::new(
+            if self.horizontal {
+                (content_size.x - size.x).max(0.0)
+            } else {
+                0.0
+            },
+            if self.vertical {
+                (content_size.y - size.y).max(0.0)
+            } else {
+                0.0
+            },
+        );
+        self.offset = Vector2::new(
+            self.offset.x.max(0.0).min(max_offset.x),
+            self.offset.y.max(0.0).min(max_offset.y),
+        );
+    }
+}
+
+/// Returns the fraction of content of `content_size` visible in an area of `size`.
+fn visible_fraction(size: Vector2<f32>, content_size: Vector2<f32>) -> Vector2<f32> {
+    Vector2::new(
+        (size.x / content_size.x.max(size.x)).min(1.0),
+        (size.y / content_size.y.max(size.y)).min(1.0),
+    )
+}
+
+impl Component for ScrollArea {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Scrolls the content of every `ScrollArea` with the mouse, and clips it to the area with a
+/// `ScissorRect`.
+#[derive(Debug)]
+pub struct ScrollAreaSystem<T: BindingTypes> {
+    /// Area being dragged, with the mouse position and offset when the drag started.
+    dragging: Option<(Entity, Vector2<f32>, Vector2<f32>)>,
+    was_down: bool,
+    _marker: PhantomData<T>,
+}
+
+impl<T: BindingTypes> ScrollAreaSystem<T> {
+    /// Creates a new `ScrollAreaSystem`.
+    pub fn new() -> Self {
+        ScrollAreaSystem {
+            dragging: None,
+            was_down: false,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<'s, T: BindingTypes> System<'s> for ScrollAreaSystem<T> {
+    type SystemData = (
+        Entities<'s>,
+        Read<'s, InputHandler<T>>,
+        ReadExpect<'s, ScreenDimensions>,
+        ReadStorage<'s, Hidden>,
+        ReadStorage<'s, HiddenPropagate>,
+        WriteStorage<'s, ScrollArea>,
+        WriteStorage<'s, UiTransform>,
+        WriteStorage<'s, ScissorRect>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            input,
+            screen_dimensions,
+            hiddens,
+            hidden_props,
+            mut areas,
+            mut transforms,
+            mut scissors,
+        ): Self::SystemData,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("scroll_area_system");
+
+        let screen_height = screen_dimensions.height();
+        let down = input.mouse_button_is_down(MouseButton::Left);
+        let click_started = down && !self.was_down;
+        self.was_down = down;
+        // Mouse position from the top left corner, the direction the offset grows in.
+        let mouse = input.mouse_position().map(|(x, y)| Vector2::new(x, y));
+
+        // The innermost visible area under the cursor receives the mouse.
+        let hovered = mouse.and_then(|mouse| {
+            (&entities, &areas, &transforms, !&hiddens, !&hidden_props)
+                .join()
+                .filter(|(_, _, transform, _, _)| {
+                    transform.position_inside(mouse.x, screen_height - mouse.y)
+                })
+                .max_by(|(_, _, t1, _, _), (_, _, t2, _, _)| {
+                    t1.global_z()
+                        .partial_cmp(&t2.global_z())
+                        .expect("Unexpected NaN")
+                })
+                .map(|(entity, _, _, _, _)| entity)
+        });
+
+        if !down {
+            self.dragging = None;
+        } else if click_started {
+            self.dragging = hovered
+                .filter(|&entity| areas.get(entity).map_or(false, |area| area.drag))
+                .and_then(|entity| Some((entity, mouse?, areas.get(entity)?.offset)));
+        }
+
+        if let Some(area) = hovered.and_then(|entity| areas.get_mut(entity)) {
+            let wheel = Vector2::new(
+                input.mouse_wheel_value(true),
+                input.mouse_wheel_value(false),
+            );
+            // A vertical wheel scrolls areas which only scroll horizontally.
+            let wheel = if area.horizontal && !area.vertical {
+                Vector2::new(wheel.x + wheel.y, 0.0)
+            } else {
+                wheel
+            };
+            area.offset -= wheel * area.scroll_speed;
+        }
+        if let (Some((entity, start, offset)), Some(mouse)) = (self.dragging, mouse) {
+            if let Some(area) = areas.get_mut(entity) {
+                area.offset = offset - (mouse - start);
+            }
+        }
+
+        for (entity, area) in (&entities, &mut areas).join() {
+            let (width, height) = match transforms.get(entity) {
+                Some(transform) => {
+                    let scissor = transform.scissor_rect(screen_height);
+                    if scissors.get(entity) != Some(&scissor) {
+                        scissors
+                            .insert(entity, scissor)
+                            .expect("Unreachable: Entity is alive");
+                    }
+                    (transform.pixel_width(), transform.pixel_height())
+                }
+                None => continue,
+            };
+            let content_size = match transforms.get(area.content) {
+                Some(content) => Vector2::new(content.pixel_width(), content.pixel_height()),
+                None => continue,
+            };
+            let size = Vector2::new(width, height);
+            area.clamp_offset(size, content_size);
+
+            place(
+                &mut transforms,
+                area.content,
+                Anchor::TopLeft,
+                Vector2::new(-area.offset.x, area.offset.y),
+                None,
+                size,
+            );
+
+            // Thumbs cover the visible fraction of the content along their edge.
+            let visible = visible_fraction(size, content_size);
+            if let Some(bar) = area.horizontal_bar {
+                place(
+                    &mut transforms,
+                    bar,
+                    Anchor::BottomLeft,
+                    Vector2::new(area.offset.x * visible.x, 0.0),
+                    Some((size.x * visible.x, true)),
+                    size,
+                );
+            }
+            if let Some(bar) = area.vertical_bar {
+                place(
+                    &mut transforms,
+                    bar,
+                    Anchor::TopRight,
+                    Vector2::new(0.0, -area.offset.y * visible.y),
+                    Some((size.y * visible.y, false)),
+                    size,
+                );
+            }
+        }
+    }
+}
+
+/// Moves the `UiTransform` of `entity` to `position` in pixels from `anchor` of a parent of
+/// `parent_size`, and sets its `length` along the horizontal or vertical axis. The transform is
+/// only written when it changes, so the layout isn't recomputed every frame.
+fn place(
+    transforms: &mut WriteStorage<'_, UiTransform>,
+    entity: Entity,
+    anchor: Anchor,
+    position: Vector2<f32>,
+    length: Option<(f32, bool)>,
+    parent_size: Vector2<f32>,
+) {
+    let transform = match transforms.get(entity) {
+        Some(transform) => transform,
+        None => return,
+    };
+    let scale = match transform.scale_mode {
+        ScaleMode::Pixel => Vector2::new(1.0, 1.0),
+        ScaleMode::Percent => parent_size.map(|size| size.max(1.0)),
+    };
+    let (x, y) = (position.x / scale.x, position.y / scale.y);
+    let (width, height) = match length {
+        Some((length, true)) => (length / scale.x, transform.height),
+        Some((length, false)) => (transform.width, length / scale.y),
+        None => (transform.width, transform.height),
+    };
+
+    let changed = transform.anchor != anchor
+        || transform.pivot != anchor
+        || (transform.local_x - x).abs() > std::f32::EPSILON
+        || (transform.local_y - y).abs() > std::f32::EPSILON
+        || (transform.width - width).abs() > std::f32::EPSILON
+        || (transform.height - height).abs() > std::f32::EPSILON;
+    if changed {
+        let transform = transforms.get_mut(entity).expect("Checked above");
+        transform.anchor = anchor;
+        transform.pivot = anchor;
+        transform.local_x = x;
+        transform.local_y = y;
+        transform.width = width;
+        transform.height = height;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use amethyst_core::{
+        ecs::{Builder, World, WorldExt},
+        math::Vector2,
+    };
+
+    use super::{visible_fraction, ScrollArea};
+
+    fn area() -> ScrollArea {
+        let mut world = World::new();
+        ScrollArea::new(world.create_entity().build())
+    }
+
+    #[test]
+    fn offset_is_clamped_to_the_content() {
+        let mut area = area().with_directions(true, true);
+        let size = Vector2::new(100.0, 50.0);
+        let content_size = Vector2::new(300.0, 80.0);
+
+        area.offset = Vector2::new(500.0, 500.0);
+        area.clamp_offset(size, content_size);
+        assert_eq!(area.offset, Vector2::new(200.0, 30.0));
+
+        area.offset = Vector2::new(-10.0, 10.0);
+        area.clamp_offset(size, content_size);
+        assert_eq!(area.offset, Vector2::new(0.0, 10.0));
+    }
+
+    #[test]
+    fn content_smaller_than_the_area_does_not_scroll() {
+        let mut area = area().with_directions(true, true);
+        area.offset = Vector2::new(20.0, 20.0);
+        area.clamp_offset(Vector2::new(100.0, 100.0), Vector2::new(50.0, 150.0));
+        assert_eq!(area.offset, Vector2::new(0.0, 20.0));
+    }
+
+    #[test]
+    fn offset_is_zero_along_fixed_directions() {
+        let mut area = area();
+        area.offset = Vector2::new(20.0, 20.0);
+        area.clamp_offset(Vector2::new(100.0, 100.0), Vector2::new(300.0, 300.0));
+        assert_eq!(area.offset, Vector2::new(0.0, 20.0));
+    }
+
+    #[test]
+    fn thumbs_cover_the_visible_fraction() {
+        let size = Vector2::new(100.0, 100.0);
+        assert_eq!(
+            visible_fraction(size, Vector2::new(400.0, 50.0)),
+            Vector2::new(0.25, 1.0)
+        );
+    }
+}
@@ -0,0 +1,284 @@
+use serde::{Deserialize, Serialize};
+use std::marker::PhantomData;
+
+use amethyst_core::{
+    ecs::{
+        Component, DenseVecStorage, Entities, Entity, Join, Read, ReadExpect, ReadStorage,
+        ReaderId, System, SystemData, Write, WriteStorage,
+    },
+    shrev::EventChannel,
+    Hidden, HiddenPropagate,
+};
+use amethyst_derive::SystemDesc;
+use amethyst_input::{BindingTypes, InputHandler};
+use amethyst_window::ScreenDimensions;
+use winit::MouseButton;
+
+use crate::{Anchor, ScaleMode, UiEvent, UiEventType, UiTransform};
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+/// Component making a ui element the track of a slider, whose value is set by clicking or dragging
+/// along it. The element needs to be `Interactable`.
+///
+/// Clicks on the handle drag the slider as well. While the value is dragged, a
+/// `UiEventType::ValueChange` event targeting the slider is sent every time it changes, and a
+/// `UiEventType::ValueCommit` event when the mouse is released.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UiSlider {
+    /// Value at the left or bottom end of the track.
+    pub min: f32,
+    /// Value at the right or top end of the track.
+    pub max: f32,
+    /// Current value, between `min` and `max`.
+    pub value: f32,
+    /// Values are rounded to multiples of `step` from `min`, unless it is zero.
+    pub step: f32,
+    /// Whether the track is vertical, with `min` at the bottom.
+    pub vertical: bool,
+    /// Child placed at the position of the value along the track, with its pivot in its middle.
+    #[serde(skip)]
+    pub handle: Option<Entity>,
+}
+
+impl UiSlider {
+    /// Creates a horizontal slider between `min` and `max`, set to `value`.
+    pub fn new(min: f32, max: f32, value: f32) -> Self {
+        UiSlider {
+            min,
+            max,
+            value,
+            step: 0.0,
+            vertical: false,
+            handle: None,
+        }
+    }
+
+    /// Sets the step values are rounded to.
+    pub fn with_step(mut self, step: f32) -> Self {
+        self.step = step;
+        self
+    }
+
+    /// Makes the slider vertical.
+    pub fn into_vertical(mut self) -> Self {
+        self.vertical = true;
+        self
+    }
+
+    /// Sets the handle moved along the track.
+    pub fn with_handle(mut self, handle: Entity) -> Self {
+        self.handle = Some(handle);
+        self
+    }
+
+    /// Returns the value at the fraction `position` of the track, rounded to the step.
+    pub fn value_at(&self, position: f32) -> f32 {
+        let value = self.min + position.max(0.0).min(1.0) * (self.max - self.min);
+        let value = if self.step > 0.0 {
+            self.min + ((value - self.min) / self.step).round() * self.step
+        } else {
+            value
+        };
+        value
+            .max(self.min.min(self.max))
+            .min(self.max.max(self.min))
+    }
+
+    /// Returns the fraction of the track at which the value is.
+    pub fn position(&self) -> f32 {
+        if (self.max - self.min).abs() > std::f32::EPSILON {
+            ((self.value - self.min) / (self.max - self.min))
+                .max(0.0)
+                .min(1.0)
+        } else {
+            0.0
+        }
+    }
+}
+
+impl Component for UiSlider {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Sets the value of `UiSlider`s dragged with the mouse, and places their handles.
+///
+/// It's automatically registered with the `UiBundle`.
+#[derive(Debug, SystemDesc)]
+#[system_desc(name(UiSliderSystemDesc))]
+pub struct UiSliderSystem<T: BindingTypes> {
+    #[system_desc(event_channel_reader)]
+    event_reader: ReaderId<UiEvent>,
+    #[system_desc(skip)]
+    dragging: Option<Entity>,
+    phantom: PhantomData<T>,
+}
+
+impl<T: BindingTypes> UiSliderSystem<T> {
+    /// Creates a new instance of this structure
+    pub fn new(event_reader: ReaderId<UiEvent>) -> Self {
+        Self {
+            event_reader,
+            dragging: None,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<'s, T: BindingTypes> System<'s> for UiSliderSystem<T> {
+    type SystemData = (
+        Entities<'s>,
+        Read<'s, InputHandler<T>>,
+        ReadExpect<'s, ScreenDimensions>,
+        ReadStorage<'s, Hidden>,
+        ReadStorage<'s, HiddenPropagate>,
+        Write<'s, EventChannel<UiEvent>>,
+        WriteStorage<'s, UiSlider>,
+        WriteStorage<'s, UiTransform>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            input,
+            screen_dimensions,
+            hiddens,
+            hidden_props,
+            mut events,
+            mut sliders,
+            mut transforms,
+        ): Self::SystemData,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("ui_slider_system");
+
+        for event in events.read(&mut self.event_reader) {
+            if let UiEventType::ClickStart = event.event_type {
+                // Clicks on the handle start dragging its slider too.
+                let slider = if sliders.contains(event.target) {
+                    Some(event.target)
+                } else {
+                    (&entities, &sliders)
+                        .join()
+                        .find(|(_, slider)| slider.handle == Some(event.target))
+                        .map(|(entity, _)| entity)
+                };
+                if slider.is_some() {
+                    self.dragging = slider;
+                }
+            }
+        }
+
+        if let Some(entity) = self.dragging {
+            let released = !input.mouse_button_is_down(MouseButton::Left)
+                || hiddens.contains(entity)
+                || hidden_props.contains(entity);
+            let mouse = input.mouse_position();
+            if let (Some(slider), Some(transform), Some((x, y))) =
+                (sliders.get_mut(entity), transforms.get(entity), mouse)
+            {
+                let y = screen_dimensions.height() - y;
+                let position = if slider.vertical {
+                    (y - transform.pixel_y() + transform.pixel_height() / 2.0)
+                        / transform.pixel_height()
+                } else {
+                    (x - transform.pixel_x() + transform.pixel_width() / 2.0)
+                        / transform.pixel_width()
+                };
+                let value = slider.value_at(position);
+                if (value - slider.value).abs() > std::f32::EPSILON {
+                    slider.value = value;
+                    events.single_write(UiEvent::new(UiEventType::ValueChange, entity));
+                }
+            }
+            if released {
+                events.single_write(UiEvent::new(UiEventType::ValueCommit, entity));
+                self.dragging = None;
+            }
+        }
+
+        for (entity, slider) in (&entities, &sliders).join() {
+            let handle = match slider.handle {
+                Some(handle) => handle,
+                None => continue,
+            };
+            let (width, height) = match transforms.get(entity) {
+                Some(transform) => (transform.pixel_width(), transform.pixel_height()),
+                None => continue,
+            };
+            let handle_transform = match transforms.get(handle) {
+                Some(transform) => transform,
+                None => continue,
+            };
+            let position = slider.position();
+            let (anchor, x, y) = match (slider.vertical, &handle_transform.scale_mode) {
+                (false, ScaleMode::Pixel) => (Anchor::MiddleLeft, position * width, 0.0),
+                (false, ScaleMode::Percent) => (Anchor::MiddleLeft, position, 0.0),
+                (true, ScaleMode::Pixel) => (Anchor::BottomMiddle, 0.0, position * height),
+                (true, ScaleMode::Percent) => (Anchor::BottomMiddle, 0.0, position),
+            };
+            let changed = handle_transform.anchor != anchor
+                || handle_transform.pivot != Anchor::Middle
+                || (handle_transform.local_x - x).abs() > std::f32::EPSILON
+                || (handle_transform.local_y - y).abs() > std::f32::EPSILON;
+            if changed {
+                let handle_transform = transforms.get_mut(handle).expect("Checked above");
+                handle_transform.anchor = anchor;
+                handle_transform.pivot = Anchor::Middle;
+                handle_transform.local_x = x;
+                handle_transform.local_y = y;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::UiSlider;
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-5, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn value_maps_linearly_along_the_track() {
+        let slider = UiSlider::new(10.0, 20.0, 10.0);
+        assert_close(slider.value_at(0.0), 10.0);
+        assert_close(slider.value_at(0.25), 12.5);
+        assert_close(slider.value_at(1.0), 20.0);
+    }
+
+    #[test]
+    fn value_is_clamped_to_the_track() {
+        let slider = UiSlider::new(10.0, 20.0, 10.0);
+        assert_close(slider.value_at(-0.5), 10.0);
+        assert_close(slider.value_at(1.5), 20.0);
+    }
+
+    #[test]
+    fn value_is_rounded_to_the_step() {
+        let slider = UiSlider::new(1.0, 10.0, 1.0).with_step(2.0);
+        assert_close(slider.value_at(0.1), 1.0);
+        assert_close(slider.value_at(0.2), 3.0);
+        assert_close(slider.value_at(0.95), 9.0);
+        // Rounding past the end of the track stops at `max`.
+        assert_close(slider.value_at(1.0), 10.0);
+    }
+
+    #[test]
+    fn reversed_range_maps_min_to_the_start() {
+        let slider = UiSlider::new(1.0, -1.0, 0.5);
+        assert_close(slider.value_at(0.0), 1.0);
+        assert_close(slider.value_at(0.75), -0.5);
+        assert_close(slider.position(), 0.25);
+    }
+
+    #[test]
+    fn position_is_the_fraction_of_the_value() {
+        assert_close(UiSlider::new(0.0, 4.0, 1.0).position(), 0.25);
+        assert_close(UiSlider::new(0.0, 4.0, 8.0).position(), 1.0);
+        assert_close(UiSlider::new(2.0, 2.0, 2.0).position(), 0.0);
+    }
+}
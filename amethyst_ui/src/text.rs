@@ -1,6 +1,9 @@
 //! Module holding the components related to text and text editing.
 
-use crate::Anchor;
+use crate::{
+    markup::{parse_markup, MarkupFonts, TextSpan},
+    Anchor,
+};
 
 use derivative::Derivative;
 use serde::{Deserialize, Serialize};
@@ -48,6 +51,10 @@ pub struct UiText {
     pub line_mode: LineMode,
     /// How to align the text within its `UiTransform`.
     pub align: Anchor,
+    /// Ranges of the text drawn with their own color, font or size. They aren't moved when the
+    /// text is edited.
+    #[serde(skip)]
+    pub spans: Vec<TextSpan>,
     /// Cached glyph positions including invisible characters, used to process mouse highlighting.
     #[serde(skip)]
     pub(crate) cached_glyphs: Vec<CachedGlyph>,
@@ -87,9 +94,28 @@ impl UiText {
             password: false,
             line_mode,
             align,
+            spans: Vec::new(),
             cached_glyphs: Vec::new(),
         }
     }
+
+    /// Sets the styled spans of the text.
+    pub fn with_spans(mut self, spans: Vec<TextSpan>) -> Self {
+        self.spans = spans;
+        self
+    }
+
+    /// Replaces the text and its spans with the ones parsed from `markup`, using `fonts` for
+    /// bold and italic text.
+    ///
+    /// The markup supports `[b]`, `[i]`, `[color=#rrggbb]`, `[color=#rrggbbaa]` and
+    /// `[size=24]` tags, closed by `[/b]`, `[/i]`, `[/color]` and `[/size]`, and `[[` for a
+    /// literal `[`, e.g. `"You found [color=#ffd700][b]the golden key[/b][/color]!"`.
+    pub fn set_markup(&mut self, markup: &str, fonts: &MarkupFonts) {
+        let (text, spans) = parse_markup(markup, fonts);
+        self.text = text;
+        self.spans = spans;
+    }
 }
 
 impl Component for UiText {
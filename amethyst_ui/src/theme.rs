@@ -0,0 +1,282 @@
+use std::collections::HashMap;
+
+use amethyst_core::{
+    ecs::prelude::{
+        BitSet, Component, ComponentEvent, DenseVecStorage, Entities, Entity, FlaggedStorage, Join,
+        ReadStorage, System, SystemData, Write, WriteStorage,
+    },
+    shrev::ReaderId,
+};
+use amethyst_derive::SystemDesc;
+
+use crate::{FontHandle, Padding, UiFlex, UiImage, UiText};
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+/// Named set of properties applied to the ui elements referencing it with `UiStyled`. Properties
+/// left to `None` are not touched.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UiStyle {
+    /// Font of the `UiText`.
+    pub font: Option<FontHandle>,
+    /// Font size of the `UiText`.
+    pub font_size: Option<f32>,
+    /// Color of the `UiText`.
+    pub text_color: Option<[f32; 4]>,
+    /// `UiImage` of the element, e.g. its background. Use `UiImage::SolidColor` for a plain color.
+    pub image: Option<UiImage>,
+    /// Padding of the `UiFlex` container.
+    pub padding: Option<Padding>,
+}
+
+impl UiStyle {
+    /// Sets the font of texts.
+    pub fn with_font(mut self, font: FontHandle) -> Self {
+        self.font = Some(font);
+        self
+    }
+
+    /// Sets the font size of texts.
+    pub fn with_font_size(mut self, font_size: f32) -> Self {
+        self.font_size = Some(font_size);
+        self
+    }
+
+    /// Sets the color of texts.
+    pub fn with_text_color(mut self, text_color: [f32; 4]) -> Self {
+        self.text_color = Some(text_color);
+        self
+    }
+
+    /// Sets the image of elements.
+    pub fn with_image(mut self, image: UiImage) -> Self {
+        self.image = Some(image);
+        self
+    }
+
+    /// Sets the padding of flex containers.
+    pub fn with_padding(mut self, padding: Padding) -> Self {
+        self.padding = Some(padding);
+        self
+    }
+}
+
+/// Resource holding the `UiStyle`s referenced by name with `UiStyled`.
+///
+/// The `UiThemeSystem` applies the styles to an element when its `UiStyled` is inserted or
+/// modified, and to every element when the theme is replaced or one of its styles is changed.
+/// In between, other systems are free to change the styled properties, e.g. the `UiButton`
+/// hover and press actions.
+#[derive(Debug, Clone)]
+pub struct UiTheme {
+    styles: HashMap<String, UiStyle>,
+    changed: bool,
+}
+
+impl Default for UiTheme {
+    fn default() -> Self {
+        UiTheme {
+            styles: HashMap::new(),
+            changed: true,
+        }
+    }
+}
+
+impl UiTheme {
+    /// Creates an empty theme.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the style `name`, replacing any style of the same name.
+    pub fn with_style<S: ToString>(mut self, name: S, style: UiStyle) -> Self {
+        self.insert(name, style);
+        self
+    }
+
+    /// Adds the style `name`, returning the style it replaced.
+    pub fn insert<S: ToString>(&mut self, name: S, style: UiStyle) -> Option<UiStyle> {
+        self.changed = true;
+        self.styles.insert(name.to_string(), style)
+    }
+
+    /// Removes the style `name`. Elements keep the properties it gave them.
+    pub fn remove(&mut self, name: &str) -> Option<UiStyle> {
+        self.changed = true;
+        self.styles.remove(name)
+    }
+
+    /// Returns the style `name`.
+    pub fn get(&self, name: &str) -> Option<&UiStyle> {
+        self.styles.get(name)
+    }
+
+    /// Returns the style `name` for modification.
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut UiStyle> {
+        self.changed = true;
+        self.styles.get_mut(name)
+    }
+
+    /// Returns whether the theme changed since the last call.
+    fn take_changed(&mut self) -> bool {
+        std::mem::replace(&mut self.changed, false)
+    }
+}
+
+/// Component giving a ui element the style of the `UiTheme` with this name. The element keeps its
+/// own properties where the style has none, or if the theme has no such style.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct UiStyled(pub String);
+
+impl UiStyled {
+    /// Gives an element the style `name`.
+    pub fn new<S: ToString>(name: S) -> Self {
+        UiStyled(name.to_string())
+    }
+}
+
+impl Component for UiStyled {
+    type Storage = FlaggedStorage<Self, DenseVecStorage<Self>>;
+}
+
+/// Applies the styles of the `UiTheme` to the ui elements with `UiStyled`, when either changes.
+///
+/// It's automatically registered with the `UiBundle`.
+#[derive(Debug, SystemDesc)]
+#[system_desc(name(UiThemeSystemDesc))]
+pub struct UiThemeSystem {
+    #[system_desc(flagged_storage_reader(UiStyled))]
+    styled_events: ReaderId<ComponentEvent>,
+    #[system_desc(skip)]
+    modified: BitSet,
+}
+
+impl UiThemeSystem {
+    /// Creates a new `UiThemeSystem` reading the changes of `UiStyled` with the given reader id.
+    pub fn new(styled_events: ReaderId<ComponentEvent>) -> Self {
+        UiThemeSystem {
+            styled_events,
+            modified: BitSet::default(),
+        }
+    }
+}
+
+impl<'s> System<'s> for UiThemeSystem {
+    type SystemData = (
+        Entities<'s>,
+        Write<'s, UiTheme>,
+        ReadStorage<'s, UiStyled>,
+        WriteStorage<'s, UiText>,
+        WriteStorage<'s, UiImage>,
+        WriteStorage<'s, UiFlex>,
+    );
+
+    fn run(
+        &mut self,
+        (entities, mut theme, styled, mut texts, mut images, mut flexes): Self::SystemData,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("ui_theme_system");
+
+        self.modified.clear();
+        for event in styled.channel().read(&mut self.styled_events) {
+            match event {
+                ComponentEvent::Inserted(id) | ComponentEvent::Modified(id) => {
+                    self.modified.add(*id);
+                }
+                ComponentEvent::Removed(_id) => {}
+            }
+        }
+
+        if theme.take_changed() {
+            for (entity, styled) in (&entities, &styled).join() {
+                if let Some(style) = theme.get(&styled.0) {
+                    apply_style(entity, style, &mut texts, &mut images, &mut flexes);
+                }
+            }
+        } else {
+            for (entity, styled, _) in (&entities, &styled, &self.modified).join() {
+                if let Some(style) = theme.get(&styled.0) {
+                    apply_style(entity, style, &mut texts, &mut images, &mut flexes);
+                }
+            }
+        }
+    }
+}
+
+fn apply_style(
+    entity: Entity,
+    style: &UiStyle,
+    texts: &mut WriteStorage<'_, UiText>,
+    images: &mut WriteStorage<'_, UiImage>,
+    flexes: &mut WriteStorage<'_, UiFlex>,
+) {
+    if let Some(text) = texts.get(entity) {
+        let changed = style.font.as_ref().map_or(false, |font| *font != text.font)
+            || style.font_size.map_or(false, |size| {
+                (size - text.font_size).abs() > std::f32::EPSILON
+            })
+            || style.text_color.map_or(false, |color| color != text.color);
+        if changed {
+            let text = texts.get_mut(entity).expect("Checked above");
+            if let Some(ref font) = style.font {
+                text.font = font.clone();
+            }
+            if let Some(font_size) = style.font_size {
+                text.font_size = font_size;
+            }
+            if let Some(color) = style.text_color {
+                text.color = color;
+            }
+        }
+    }
+
+    if let Some(ref image) = style.image {
+        if images.get(entity) != Some(image) {
+            images
+                .insert(entity, image.clone())
+                .expect("Unreachable: Entity is alive");
+        }
+    }
+
+    if let Some(padding) = style.padding {
+        let changed = flexes
+            .get(entity)
+            .map_or(false, |flex| flex.padding != padding);
+        if changed {
+            flexes.get_mut(entity).expect("Checked above").padding = padding;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{UiStyle, UiTheme};
+
+    #[test]
+    fn new_theme_is_applied() {
+        let mut theme = UiTheme::new();
+        assert!(theme.take_changed());
+        assert!(!theme.take_changed());
+    }
+
+    #[test]
+    fn changing_styles_marks_the_theme_changed() {
+        let mut theme = UiTheme::new().with_style("title", UiStyle::default());
+        theme.take_changed();
+
+        assert!(theme.get("title").is_some());
+        assert!(!theme.take_changed());
+
+        theme.get_mut("title").unwrap().font_size = Some(30.0);
+        assert!(theme.take_changed());
+
+        theme.insert("body", UiStyle::default().with_font_size(12.0));
+        assert!(theme.take_changed());
+
+        theme.remove("title");
+        assert!(theme.take_changed());
+        assert!(theme.get("title").is_none());
+    }
+}
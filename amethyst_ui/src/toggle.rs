@@ -0,0 +1,201 @@
+use amethyst_core::{
+    ecs::{
+        Component, DenseVecStorage, Entities, Entity, Join, ReadStorage, ReaderId, System,
+        SystemData, Write, WriteStorage,
+    },
+    shrev::EventChannel,
+    HiddenPropagate,
+};
+use amethyst_derive::SystemDesc;
+
+use crate::{UiEvent, UiEventType};
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+/// Component making an entity a group of `UiToggle`s, of which only one is active at a time,
+/// like radio buttons or the tabs of a tab bar.
+///
+/// Changing the active member sends a `UiEventType::ValueChange` event targeting the group.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ToggleGroup {
+    /// The active member.
+    pub active: Option<Entity>,
+    /// Whether clicking the active member deactivates it, leaving none active.
+    pub allow_none: bool,
+}
+
+impl ToggleGroup {
+    /// Creates a group with `active` as its active member.
+    pub fn new(active: Option<Entity>) -> Self {
+        ToggleGroup {
+            active,
+            allow_none: false,
+        }
+    }
+
+    /// Lets clicking the active member deactivate it.
+    pub fn allowing_none(mut self) -> Self {
+        self.allow_none = true;
+        self
+    }
+
+    /// Updates the active member after `member` was clicked. Returns whether it changed.
+    fn click(&mut self, member: Entity) -> bool {
+        if self.active != Some(member) {
+            self.active = Some(member);
+            true
+        } else if self.allow_none {
+            self.active = None;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl Component for ToggleGroup {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Component making a ui element a member of a `ToggleGroup`, activated by clicking it. The
+/// element needs to be `Interactable`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UiToggle {
+    /// Entity of the group.
+    pub group: Entity,
+    /// Entity shown while the member is active, and hidden with `HiddenPropagate` otherwise,
+    /// e.g. the dot of a radio button or the page of a tab.
+    pub content: Option<Entity>,
+}
+
+impl UiToggle {
+    /// Creates a member of `group`.
+    pub fn new(group: Entity) -> Self {
+        UiToggle {
+            group,
+            content: None,
+        }
+    }
+
+    /// Sets the entity shown while the member is active.
+    pub fn with_content(mut self, content: Entity) -> Self {
+        self.content = Some(content);
+        self
+    }
+}
+
+impl Component for UiToggle {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Activates `UiToggle`s when they are clicked, and shows the content of the active members.
+///
+/// It's automatically registered with the `UiBundle`.
+#[derive(Debug, SystemDesc)]
+#[system_desc(name(ToggleGroupSystemDesc))]
+pub struct ToggleGroupSystem {
+    #[system_desc(event_channel_reader)]
+    event_reader: ReaderId<UiEvent>,
+}
+
+impl ToggleGroupSystem {
+    /// Creates a new instance of this structure
+    pub fn new(event_reader: ReaderId<UiEvent>) -> Self {
+        Self { event_reader }
+    }
+}
+
+impl<'s> System<'s> for ToggleGroupSystem {
+    type SystemData = (
+        Entities<'s>,
+        Write<'s, EventChannel<UiEvent>>,
+        ReadStorage<'s, UiToggle>,
+        WriteStorage<'s, ToggleGroup>,
+        WriteStorage<'s, HiddenPropagate>,
+    );
+
+    fn run(&mut self, (entities, mut events, toggles, mut groups, mut hiddens): Self::SystemData) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("toggle_group_system");
+
+        let mut changed = Vec::new();
+        for event in events.read(&mut self.event_reader) {
+            if let UiEventType::Click = event.event_type {
+                let toggle = match toggles.get(event.target) {
+                    Some(toggle) => toggle,
+                    None => continue,
+                };
+                let clicked = groups
+                    .get_mut(toggle.group)
+                    .map_or(false, |group| group.click(event.target));
+                if clicked && !changed.contains(&toggle.group) {
+                    changed.push(toggle.group);
+                }
+            }
+        }
+        for group in changed {
+            events.single_write(UiEvent::new(UiEventType::ValueChange, group));
+        }
+
+        for (entity, toggle) in (&entities, &toggles).join() {
+            let content = match toggle.content {
+                Some(content) if entities.is_alive(content) => content,
+                _ => continue,
+            };
+            let active = groups
+                .get(toggle.group)
+                .map_or(false, |group| group.active == Some(entity));
+            if active && hiddens.contains(content) {
+                hiddens.remove(content);
+            } else if !active && !hiddens.contains(content) {
+                hiddens
+                    .insert(content, HiddenPropagate::new())
+                    .expect("Unreachable: Entity is alive");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use amethyst_core::ecs::{Builder, World, WorldExt};
+
+    use super::ToggleGroup;
+
+    #[test]
+    fn clicking_a_member_activates_it() {
+        let mut world = World::new();
+        let first = world.create_entity().build();
+        let second = world.create_entity().build();
+        let mut group = ToggleGroup::new(Some(first));
+
+        assert!(group.click(second));
+        assert_eq!(group.active, Some(second));
+        assert!(group.click(first));
+        assert_eq!(group.active, Some(first));
+    }
+
+    #[test]
+    fn clicking_the_active_member_keeps_it_active() {
+        let mut world = World::new();
+        let member = world.create_entity().build();
+        let mut group = ToggleGroup::new(None);
+
+        assert!(group.click(member));
+        assert!(!group.click(member));
+        assert_eq!(group.active, Some(member));
+    }
+
+    #[test]
+    fn clicking_the_active_member_can_deactivate_it() {
+        let mut world = World::new();
+        let member = world.create_entity().build();
+        let mut group = ToggleGroup::new(Some(member)).allowing_none();
+
+        assert!(group.click(member));
+        assert_eq!(group.active, None);
+        assert!(group.click(member));
+        assert_eq!(group.active, Some(member));
+    }
+}
@@ -0,0 +1,412 @@
+use std::marker::PhantomData;
+
+use amethyst_assets::{AssetStorage, Handle, Loader};
+use amethyst_core::{
+    ecs::{
+        Component, DenseVecStorage, Entities, Entity, Read, ReadExpect, ReadStorage, ReaderId,
+        System, SystemData, WriteStorage,
+    },
+    shrev::EventChannel,
+    timing::Time,
+    Hidden, HiddenPropagate, Parent, ParentHierarchy,
+};
+use amethyst_derive::SystemDesc;
+use amethyst_input::{BindingTypes, InputHandler};
+use amethyst_window::ScreenDimensions;
+
+use crate::{
+    get_default_font, Anchor, FontAsset, FontHandle, LineMode, UiEvent, UiEventType, UiImage,
+    UiPrefab, UiText, UiTransform,
+};
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+/// What a `UiTooltip` shows.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TooltipContent {
+    /// A text.
+    Text(String),
+    /// A ui prefab, instantiated as a child of the tooltip.
+    ///
+    /// The prefab is loaded by the `UiLoaderSystem` of the `UiBundle`, so this needs the bundle's
+    /// default custom ui and widget id types.
+    Prefab(Handle<UiPrefab>),
+}
+
+/// Component showing a tooltip next to the cursor once it has hovered the entity for `delay`
+/// seconds. The entity needs to be `Interactable`.
+///
+/// The tooltip is a new ui element of `size` following the cursor at `offset` from it, kept on
+/// the screen. It is deleted, with its children, as soon as the cursor leaves the entity.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UiTooltip {
+    /// What the tooltip shows.
+    pub content: TooltipContent,
+    /// Seconds the cursor has to stay over the entity before the tooltip appears.
+    pub delay: f32,
+    /// Position of the top left corner of the tooltip relative to the cursor, in pixels.
+    pub offset: (f32, f32),
+    /// Width and height of the tooltip, in pixels.
+    pub size: (f32, f32),
+    /// Background of the tooltip.
+    pub background: Option<UiImage>,
+    /// Font of a text tooltip, or the default font if `None`.
+    pub font: Option<FontHandle>,
+    /// Font size of a text tooltip.
+    pub font_size: f32,
+    /// Color of a text tooltip.
+    pub text_color: [f32; 4],
+}
+
+impl UiTooltip {
+    /// Creates a tooltip showing `text`.
+    pub fn text<S: ToString>(text: S) -> Self {
+        Self::new(TooltipContent::Text(text.to_string()))
+    }
+
+    /// Creates a tooltip showing an instance of `prefab`.
+    pub fn prefab(prefab: Handle<UiPrefab>) -> Self {
+        Self::new(TooltipContent::Prefab(prefab))
+    }
+
+    fn new(content: TooltipContent) -> Self {
+        UiTooltip {
+            content,
+            delay: 0.5,
+            offset: (12.0, -12.0),
+            size: (200.0, 30.0),
+            background: None,
+            font: None,
+            font_size: 16.0,
+            text_color: [1.0, 1.0, 1.0, 1.0],
+        }
+    }
+
+    /// Sets the seconds before the tooltip appears.
+    pub fn with_delay(mut self, delay: f32) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Sets the position of the tooltip relative to the cursor.
+    pub fn with_offset(mut self, x: f32, y: f32) -> Self {
+        self.offset = (x, y);
+        self
+    }
+
+    /// Sets the size of the tooltip.
+    pub fn with_size(mut self, width: f32, height: f32) -> Self {
+        self.size = (width, height);
+        self
+    }
+
+    /// Sets the background of the tooltip.
+    pub fn with_background(mut self, background: UiImage) -> Self {
+        self.background = Some(background);
+        self
+    }
+
+    /// Sets the font of a text tooltip.
+    pub fn with_font(mut self, font: FontHandle) -> Self {
+        self.font = Some(font);
+        self
+    }
+
+    /// Sets the font size of a text tooltip.
+    pub fn with_font_size(mut self, font_size: f32) -> Self {
+        self.font_size = font_size;
+        self
+    }
+
+    /// Sets the color of a text tooltip.
+    pub fn with_text_color(mut self, text_color: [f32; 4]) -> Self {
+        self.text_color = text_color;
+        self
+    }
+
+    /// Returns the position of the top left corner of the tooltip from the bottom left corner of
+    /// a screen of `screen` size, for the cursor at `mouse` from its top left corner.
+    fn position(&self, mouse: (f32, f32), screen: (f32, f32)) -> (f32, f32) {
+        let (width, height) = self.size;
+        let x = (mouse.0 + self.offset.0).min(screen.0 - width).max(0.0);
+        let y = (screen.1 - mouse.1 + self.offset.1)
+            .max(height)
+            .min(screen.1);
+        (x, y)
+    }
+}
+
+impl Component for UiTooltip {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Shows, moves and deletes the tooltips of the entities with a `UiTooltip`.
+///
+/// It's automatically registered with the `UiBundle`.
+#[derive(Debug, SystemDesc)]
+#[system_desc(name(UiTooltipSystemDesc))]
+pub struct UiTooltipSystem<T: BindingTypes> {
+    #[system_desc(event_channel_reader)]
+    event_reader: ReaderId<UiEvent>,
+    #[system_desc(skip)]
+    hover: Hover,
+    /// Entity whose tooltip is shown, and the tooltip.
+    #[system_desc(skip)]
+    shown: Option<(Entity, Entity)>,
+    phantom: PhantomData<T>,
+}
+
+/// Entity hovered and the seconds it has been.
+#[derive(Debug, Default)]
+struct Hover(Option<(Entity, f32)>);
+
+impl Hover {
+    fn start(&mut self, entity: Entity) {
+        self.0 = Some((entity, 0.0));
+    }
+
+    fn stop(&mut self, entity: Entity) {
+        if self.entity() == Some(entity) {
+            self.0 = None;
+        }
+    }
+
+    fn entity(&self) -> Option<Entity> {
+        self.0.map(|(entity, _)| entity)
+    }
+
+    /// Adds `delta` seconds to the hover, returning the entity and the seconds it's hovered.
+    fn tick(&mut self, delta: f32) -> Option<(Entity, f32)> {
+        self.0.as_mut().map(|(entity, seconds)| {
+            *seconds += delta;
+            (*entity, *seconds)
+        })
+    }
+}
+
+impl<T: BindingTypes> UiTooltipSystem<T> {
+    /// Creates a new instance of this structure
+    pub fn new(event_reader: ReaderId<UiEvent>) -> Self {
+        Self {
+            event_reader,
+            hover: Hover::default(),
+            shown: None,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<'s, T: BindingTypes> System<'s> for UiTooltipSystem<T> {
+    type SystemData = (
+        Entities<'s>,
+        Read<'s, Time>,
+        Read<'s, InputHandler<T>>,
+        ReadExpect<'s, ScreenDimensions>,
+        ReadExpect<'s, ParentHierarchy>,
+        ReadExpect<'s, Loader>,
+        Read<'s, AssetStorage<FontAsset>>,
+        Read<'s, EventChannel<UiEvent>>,
+        ReadStorage<'s, UiTooltip>,
+        ReadStorage<'s, Hidden>,
+        ReadStorage<'s, HiddenPropagate>,
+        WriteStorage<'s, UiTransform>,
+        WriteStorage<'s, UiText>,
+        WriteStorage<'s, UiImage>,
+        WriteStorage<'s, Parent>,
+        WriteStorage<'s, Handle<UiPrefab>>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            time,
+            input,
+            screen_dimensions,
+            hierarchy,
+            loader,
+            font_storage,
+            events,
+            tooltips,
+            hiddens,
+            hidden_props,
+            mut transforms,
+            mut texts,
+            mut images,
+            mut parents,
+            mut prefabs,
+        ): Self::SystemData,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("ui_tooltip_system");
+
+        for event in events.read(&mut self.event_reader) {
+            match event.event_type {
+                UiEventType::HoverStart if tooltips.contains(event.target) => {
+                    self.hover.start(event.target);
+                }
+                UiEventType::HoverStop => self.hover.stop(event.target),
+                _ => {}
+            }
+        }
+
+        if let Some(entity) = self.hover.entity() {
+            if !entities.is_alive(entity)
+                || !tooltips.contains(entity)
+                || hiddens.contains(entity)
+                || hidden_props.contains(entity)
+            {
+                self.hover.stop(entity);
+            }
+        }
+
+        // Deletes the tooltip of an entity which is no longer hovered.
+        if let Some((owner, tooltip)) = self.shown {
+            if self.hover.entity() != Some(owner) {
+                for child in hierarchy.all_children_iter(tooltip) {
+                    entities
+                        .delete(child)
+                        .expect("Unreachable: Children are alive");
+                }
+                if entities.is_alive(tooltip) {
+                    entities
+                        .delete(tooltip)
+                        .expect("Unreachable: Entity is alive");
+                }
+                self.shown = None;
+            }
+        }
+
+        let (owner, hovered_for) = match self.hover.tick(time.delta_seconds()) {
+            Some(hover) => hover,
+            None => return,
+        };
+        let tooltip = tooltips.get(owner).expect("Checked above");
+        if self.shown.is_none() && hovered_for >= tooltip.delay {
+            let entity = entities.create();
+            transforms
+                .insert(
+                    entity,
+                    UiTransform::new(
+                        "tooltip".to_string(),
+                        Anchor::BottomLeft,
+                        Anchor::TopLeft,
+                        0.0,
+                        0.0,
+                        1000.0,
+                        tooltip.size.0,
+                        tooltip.size.1,
+                    )
+                    .into_transparent(),
+                )
+                .expect("Unreachable: Inserting newly created entity");
+            if let Some(ref background) = tooltip.background {
+                images
+                    .insert(entity, background.clone())
+                    .expect("Unreachable: Inserting newly created entity");
+            }
+            match tooltip.content {
+                TooltipContent::Text(ref text) => {
+                    let font = tooltip
+                        .font
+                        .clone()
+                        .unwrap_or_else(|| get_default_font(&loader, &font_storage));
+                    texts
+                        .insert(
+                            entity,
+                            UiText::new(
+                                font,
+                                text.clone(),
+                                tooltip.text_color,
+                                tooltip.font_size,
+                                LineMode::Wrap,
+                                Anchor::Middle,
+                            ),
+                        )
+                        .expect("Unreachable: Inserting newly created entity");
+                }
+                TooltipContent::Prefab(ref prefab) => {
+                    let child = entities.create();
+                    parents
+                        .insert(child, Parent { entity })
+                        .expect("Unreachable: Inserting newly created entity");
+                    prefabs
+                        .insert(child, prefab.clone())
+                        .expect("Unreachable: Inserting newly created entity");
+                }
+            }
+            self.shown = Some((owner, entity));
+        }
+
+        // Follows the cursor, keeping the tooltip on the screen.
+        if let (Some((_, entity)), Some(mouse)) = (self.shown, input.mouse_position()) {
+            let screen = (screen_dimensions.width(), screen_dimensions.height());
+            let (x, y) = tooltip.position(mouse, screen);
+            let moved = transforms.get(entity).map_or(false, |transform| {
+                (transform.local_x - x).abs() > std::f32::EPSILON
+                    || (transform.local_y - y).abs() > std::f32::EPSILON
+            });
+            if moved {
+                let transform = transforms.get_mut(entity).expect("Checked above");
+                transform.local_x = x;
+                transform.local_y = y;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use amethyst_core::ecs::{Builder, World, WorldExt};
+
+    use super::{Hover, UiTooltip};
+
+    #[test]
+    fn hover_time_accumulates_until_stopped() {
+        let mut world = World::new();
+        let entity = world.create_entity().build();
+        let tooltip = UiTooltip::text("Hint").with_delay(0.5);
+        let mut hover = Hover::default();
+        assert_eq!(hover.tick(0.1), None);
+
+        hover.start(entity);
+        let (_, seconds) = hover.tick(0.3).unwrap();
+        assert!(seconds < tooltip.delay);
+        let (hovered, seconds) = hover.tick(0.3).unwrap();
+        assert_eq!(hovered, entity);
+        assert!(seconds >= tooltip.delay);
+
+        hover.stop(entity);
+        assert_eq!(hover.tick(0.1), None);
+    }
+
+    #[test]
+    fn hovering_another_entity_restarts_the_delay() {
+        let mut world = World::new();
+        let first = world.create_entity().build();
+        let second = world.create_entity().build();
+        let mut hover = Hover::default();
+
+        hover.start(first);
+        hover.tick(1.0);
+        hover.start(second);
+        // The end of the first hover, received after the start of the second, is ignored.
+        hover.stop(first);
+        assert_eq!(hover.tick(0.25), Some((second, 0.25)));
+    }
+
+    #[test]
+    fn tooltip_follows_the_cursor_on_the_screen() {
+        let tooltip = UiTooltip::text("Hint")
+            .with_offset(10.0, -10.0)
+            .with_size(100.0, 20.0);
+        let screen = (800.0, 600.0);
+
+        assert_eq!(tooltip.position((100.0, 100.0), screen), (110.0, 490.0));
+        // Kept inside the right and bottom edges.
+        assert_eq!(tooltip.position((750.0, 595.0), screen), (700.0, 20.0));
+        // And inside the top edge.
+        assert_eq!(tooltip.position((0.0, -50.0), screen), (10.0, 600.0));
+    }
+}
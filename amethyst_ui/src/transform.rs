@@ -10,6 +10,7 @@ use amethyst_core::{
     },
     ParentHierarchy,
 };
+use amethyst_rendy::resources::ScissorRect;
 use amethyst_window::ScreenDimensions;
 
 use serde::{Deserialize, Serialize};
@@ -181,6 +182,22 @@ impl UiTransform {
     pub fn pixel_height(&self) -> f32 {
         self.pixel_height
     }
+
+    /// Returns the area of this UiTransform as a `ScissorRect`, for clipping the element and its
+    /// children to it. `screen_height` is the height of the screen in pixels, as the scissor
+    /// rectangle is measured from the top of the screen.
+    pub fn scissor_rect(&self, screen_height: f32) -> ScissorRect {
+        let left = (self.pixel_x - self.pixel_width * 0.5).max(0.0);
+        let right = (self.pixel_x + self.pixel_width * 0.5).max(left);
+        let top = (screen_height - self.pixel_y - self.pixel_height * 0.5).max(0.0);
+        let bottom = (screen_height - self.pixel_y + self.pixel_height * 0.5).max(top);
+        ScissorRect::new(
+            left.round() as u32,
+            top.round() as u32,
+            (right.round() - left.round()) as u32,
+            (bottom.round() - top.round()) as u32,
+        )
+    }
 }
 
 impl Component for UiTransform {
@@ -245,4 +262,21 @@ mod tests {
         let pos = (-1.49, 1.20);
         assert!(!tr.position_inside(pos.0, pos.1));
     }
+
+    #[test]
+    fn scissor_rect_from_top_left() {
+        let mut tr = UiTransform::new(
+            "".to_string(),
+            Anchor::BottomLeft,
+            Anchor::BottomLeft,
+            0.0,
+            0.0,
+            0.0,
+            100.0,
+            50.0,
+        );
+        tr.pixel_x = 60.0;
+        tr.pixel_y = 25.0;
+        assert_eq!(tr.scissor_rect(600.0), ScissorRect::new(10, 550, 100, 50));
+    }
 }
@@ -0,0 +1,544 @@
+use serde::{Deserialize, Serialize};
+use std::f32::consts::PI;
+
+use amethyst_core::{
+    ecs::{Component, DenseVecStorage, Entities, Entity, Join, Read, System, Write, WriteStorage},
+    shrev::EventChannel,
+    timing::Time,
+};
+use amethyst_rendy::{palette::Srgba, resources::Tint};
+
+use crate::{TargetedEvent, UiTransform};
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+/// Curve mapping the elapsed fraction of a `Tween` to the fraction of the way its property has
+/// moved.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Deserialize, Serialize)]
+pub enum Easing {
+    /// Constant speed.
+    Linear,
+    /// Starts slow and speeds up.
+    QuadIn,
+    /// Starts fast and slows down.
+    QuadOut,
+    /// Speeds up, then slows down.
+    QuadInOut,
+    /// Like `QuadIn`, more pronounced.
+    CubicIn,
+    /// Like `QuadOut`, more pronounced.
+    CubicOut,
+    /// Like `QuadInOut`, more pronounced.
+    CubicInOut,
+    /// Follows a quarter sine wave, speeding up.
+    SineIn,
+    /// Follows a quarter sine wave, slowing down.
+    SineOut,
+    /// Follows a half sine wave.
+    SineInOut,
+    /// Overshoots the target slightly before settling on it.
+    BackOut,
+    /// Bounces off the target like a dropped ball.
+    BounceOut,
+}
+
+impl Easing {
+    /// Returns the fraction of the way moved at the elapsed fraction `t`, between 0 and 1.
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.max(0.0).min(1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::QuadIn => t * t,
+            Easing::QuadOut => t * (2.0 - t),
+            Easing::QuadInOut => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    -1.0 + (4.0 - 2.0 * t) * t
+                }
+            }
+            Easing::CubicIn => t * t * t,
+            Easing::CubicOut => {
+                let t = t - 1.0;
+                t * t * t + 1.0
+            }
+            Easing::CubicInOut => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    let t = 2.0 * t - 2.0;
+                    0.5 * t * t * t + 1.0
+                }
+            }
+            Easing::SineIn => 1.0 - (t * PI / 2.0).cos(),
+            Easing::SineOut => (t * PI / 2.0).sin(),
+            Easing::SineInOut => 0.5 * (1.0 - (t * PI).cos()),
+            Easing::BackOut => {
+                const C: f32 = 1.701_58;
+                let t = t - 1.0;
+                t * t * ((C + 1.0) * t + C) + 1.0
+            }
+            Easing::BounceOut => {
+                const N: f32 = 7.5625;
+                const D: f32 = 2.75;
+                if t < 1.0 / D {
+                    N * t * t
+                } else if t < 2.0 / D {
+                    let t = t - 1.5 / D;
+                    N * t * t + 0.75
+                } else if t < 2.5 / D {
+                    let t = t - 2.25 / D;
+                    N * t * t + 0.9375
+                } else {
+                    let t = t - 2.625 / D;
+                    N * t * t + 0.984_375
+                }
+            }
+        }
+    }
+}
+
+impl Default for Easing {
+    fn default() -> Self {
+        Easing::Linear
+    }
+}
+
+/// Property of a ui element animated by a `Tween`, with the value it is animated to. It is
+/// animated from the value it has when the tween starts.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub enum TweenTarget {
+    /// `local_x` and `local_y` of the `UiTransform`.
+    Position(f32, f32),
+    /// `width` and `height` of the `UiTransform`.
+    Size(f32, f32),
+    /// `width` and `height` of the `UiTransform`, scaled by this factor from the size they have
+    /// when the tween starts.
+    Scale(f32),
+    /// Alpha of the `Tint`, which is added if missing.
+    Alpha(f32),
+    /// Color of the `Tint`, which is added if missing.
+    Color([f32; 4]),
+}
+
+impl TweenTarget {
+    fn read(self, transform: Option<&UiTransform>, tint: Option<&Tint>) -> [f32; 4] {
+        match self {
+            TweenTarget::Position(..) => transform.map_or([0.0; 4], |transform| {
+                [transform.local_x, transform.local_y, 0.0, 0.0]
+            }),
+            TweenTarget::Size(..) | TweenTarget::Scale(..) => transform
+                .map_or([0.0; 4], |transform| {
+                    [transform.width, transform.height, 0.0, 0.0]
+                }),
+            TweenTarget::Alpha(..) | TweenTarget::Color(..) => {
+                let (r, g, b, a) =
+                    tint.map_or((1.0, 1.0, 1.0, 1.0), |tint| tint.0.into_components());
+                [r, g, b, a]
+            }
+        }
+    }
+
+    fn end(self, from: [f32; 4]) -> [f32; 4] {
+        match self {
+            TweenTarget::Position(x, y) | TweenTarget::Size(x, y) => [x, y, 0.0, 0.0],
+            TweenTarget::Scale(scale) => [from[0] * scale, from[1] * scale, 0.0, 0.0],
+            TweenTarget::Alpha(alpha) => [from[0], from[1], from[2], alpha],
+            TweenTarget::Color(color) => color,
+        }
+    }
+
+    fn write(
+        self,
+        entity: Entity,
+        value: [f32; 4],
+        transforms: &mut WriteStorage<'_, UiTransform>,
+        tints: &mut WriteStorage<'_, Tint>,
+    ) {
+        match self {
+            TweenTarget::Position(..) => {
+                if let Some(transform) = transforms.get_mut(entity) {
+                    transform.local_x = value[0];
+                    transform.local_y = value[1];
+                }
+            }
+            TweenTarget::Size(..) | TweenTarget::Scale(..) => {
+                if let Some(transform) = transforms.get_mut(entity) {
+                    transform.width = value[0];
+                    transform.height = value[1];
+                }
+            }
+            TweenTarget::Alpha(..) | TweenTarget::Color(..) => {
+                let tint = Tint(Srgba::new(value[0], value[1], value[2], value[3]));
+                tints
+                    .insert(entity, tint)
+                    .expect("Unreachable: Entity is alive");
+            }
+        }
+    }
+}
+
+/// Animation of one property of a ui element, part of a `UiTween`.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct Tween {
+    /// Property animated and its final value.
+    pub target: TweenTarget,
+    /// Duration of the animation in seconds.
+    pub duration: f32,
+    /// Curve the property follows.
+    pub easing: Easing,
+    /// Seconds to wait from the start of the step before animating.
+    pub delay: f32,
+}
+
+impl Tween {
+    /// Creates a tween animating `target` linearly over `duration` seconds.
+    pub fn new(target: TweenTarget, duration: f32) -> Self {
+        Tween {
+            target,
+            duration,
+            easing: Easing::Linear,
+            delay: 0.0,
+        }
+    }
+
+    /// Sets the curve the property follows.
+    pub fn with_easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    /// Sets the seconds to wait before animating.
+    pub fn with_delay(mut self, delay: f32) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    fn end_time(&self) -> f32 {
+        self.delay + self.duration
+    }
+
+    fn progress(&self, elapsed: f32) -> f32 {
+        if self.duration > 0.0 {
+            self.easing.apply((elapsed - self.delay) / self.duration)
+        } else {
+            1.0
+        }
+    }
+}
+
+/// Component animating properties of a ui element through a sequence of steps, each made of
+/// `Tween`s running at the same time. A step starts when all tweens of the previous one are done.
+///
+/// The `UiTweenSystem` sends a `UiTweenEvent` targeting the element when a step ends, and removes
+/// the component after the last one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UiTween {
+    steps: Vec<Vec<Tween>>,
+    step: usize,
+    elapsed: f32,
+    from: Option<Vec<[f32; 4]>>,
+}
+
+impl UiTween {
+    /// Creates a tween whose first step runs `tween`.
+    pub fn new(tween: Tween) -> Self {
+        UiTween {
+            steps: vec![vec![tween]],
+            step: 0,
+            elapsed: 0.0,
+            from: None,
+        }
+    }
+
+    /// Runs `tween` at the same time as the tweens of the last step.
+    pub fn with(mut self, tween: Tween) -> Self {
+        self.steps
+            .last_mut()
+            .expect("Unreachable: A `UiTween` has at least one step")
+            .push(tween);
+        self
+    }
+
+    /// Adds a step running `tween` once the last step is done.
+    pub fn then(mut self, tween: Tween) -> Self {
+        self.steps.push(vec![tween]);
+        self
+    }
+
+    /// Returns the index of the running step.
+    pub fn step(&self) -> usize {
+        self.step
+    }
+
+    /// Animates the properties by `delta` seconds, returning the indices of the steps which ended.
+    fn advance<P: TweenedProperties>(&mut self, delta: f32, properties: &mut P) -> Vec<usize> {
+        let mut ended = Vec::new();
+        self.elapsed += delta;
+        // Several short steps may end within one frame.
+        while let Some(step) = self.steps.get(self.step) {
+            let from = match self.from.take() {
+                Some(from) => from,
+                None => step.iter().map(|t| properties.read(t.target)).collect(),
+            };
+            for (t, from) in step.iter().zip(&from) {
+                if self.elapsed < t.delay {
+                    continue;
+                }
+                let progress = t.progress(self.elapsed);
+                let end = t.target.end(*from);
+                let lerp = |i: usize| from[i] + (end[i] - from[i]) * progress;
+                properties.write(t.target, [lerp(0), lerp(1), lerp(2), lerp(3)]);
+            }
+
+            let duration = step.iter().map(Tween::end_time).fold(0.0, f32::max);
+            if self.elapsed < duration {
+                self.from = Some(from);
+                break;
+            }
+            self.elapsed -= duration;
+            ended.push(self.step);
+            self.step += 1;
+        }
+        ended
+    }
+}
+
+/// Properties of a ui element animated by a `UiTween`.
+trait TweenedProperties {
+    fn read(&self, target: TweenTarget) -> [f32; 4];
+    fn write(&mut self, target: TweenTarget, value: [f32; 4]);
+}
+
+/// The `UiTransform` and `Tint` of an entity.
+struct Element<'a, 's> {
+    entity: Entity,
+    transforms: &'a mut WriteStorage<'s, UiTransform>,
+    tints: &'a mut WriteStorage<'s, Tint>,
+}
+
+impl<'a, 's> TweenedProperties for Element<'a, 's> {
+    fn read(&self, target: TweenTarget) -> [f32; 4] {
+        target.read(
+            self.transforms.get(self.entity),
+            self.tints.get(self.entity),
+        )
+    }
+
+    fn write(&mut self, target: TweenTarget, value: [f32; 4]) {
+        target.write(self.entity, value, self.transforms, self.tints)
+    }
+}
+
+impl Component for UiTween {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Event sent when a step of a `UiTween` ends.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UiTweenEvent {
+    /// The entity whose `UiTween` ended a step.
+    pub target: Entity,
+    /// Index of the step which ended.
+    pub step: usize,
+    /// Whether it was the last step, after which the `UiTween` was removed.
+    pub finished: bool,
+}
+
+impl TargetedEvent for UiTweenEvent {
+    fn get_target(&self) -> Entity {
+        self.target
+    }
+}
+
+/// Animates the properties of ui elements with a `UiTween`.
+///
+/// It's automatically registered with the `UiBundle`.
+#[derive(Debug, Default)]
+pub struct UiTweenSystem;
+
+impl UiTweenSystem {
+    /// Creates a new `UiTweenSystem`.
+    pub fn new() -> Self {
+        UiTweenSystem
+    }
+}
+
+impl<'s> System<'s> for UiTweenSystem {
+    type SystemData = (
+        Entities<'s>,
+        Read<'s, Time>,
+        Write<'s, EventChannel<UiTweenEvent>>,
+        WriteStorage<'s, UiTween>,
+        WriteStorage<'s, UiTransform>,
+        WriteStorage<'s, Tint>,
+    );
+
+    fn run(
+        &mut self,
+        (entities, time, mut events, mut tweens, mut transforms, mut tints): Self::SystemData,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("ui_tween_system");
+
+        let mut finished = Vec::new();
+        for (entity, tween) in (&entities, &mut tweens).join() {
+            let mut element = Element {
+                entity,
+                transforms: &mut transforms,
+                tints: &mut tints,
+            };
+            for step in tween.advance(time.delta_seconds(), &mut element) {
+                let last = step + 1 == tween.steps.len();
+                events.single_write(UiTweenEvent {
+                    target: entity,
+                    step,
+                    finished: last,
+                });
+                if last {
+                    finished.push(entity);
+                }
+            }
+        }
+        for entity in finished {
+            tweens.remove(entity);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn easings_start_and_end_at_the_bounds() {
+        let easings = [
+            Easing::Linear,
+            Easing::QuadIn,
+            Easing::QuadOut,
+            Easing::QuadInOut,
+            Easing::CubicIn,
+            Easing::CubicOut,
+            Easing::CubicInOut,
+            Easing::SineIn,
+            Easing::SineOut,
+            Easing::SineInOut,
+            Easing::BackOut,
+            Easing::BounceOut,
+        ];
+        for easing in &easings {
+            assert!(easing.apply(0.0).abs() < 1e-5, "{:?}", easing);
+            assert!((easing.apply(1.0) - 1.0).abs() < 1e-5, "{:?}", easing);
+        }
+    }
+
+    #[test]
+    fn easings_follow_their_curves() {
+        assert!((Easing::Linear.apply(0.25) - 0.25).abs() < 1e-5);
+        assert!((Easing::QuadIn.apply(0.5) - 0.25).abs() < 1e-5);
+        assert!((Easing::QuadOut.apply(0.5) - 0.75).abs() < 1e-5);
+        assert!((Easing::CubicIn.apply(0.5) - 0.125).abs() < 1e-5);
+        assert!((Easing::CubicOut.apply(0.5) - 0.875).abs() < 1e-5);
+        for easing in &[Easing::QuadInOut, Easing::CubicInOut, Easing::SineInOut] {
+            assert!((easing.apply(0.5) - 0.5).abs() < 1e-5, "{:?}", easing);
+        }
+        assert!(Easing::BackOut.apply(0.8) > 1.0);
+    }
+
+    #[test]
+    fn easings_clamp_the_elapsed_fraction() {
+        assert!(Easing::CubicOut.apply(-1.0).abs() < 1e-5);
+        assert!((Easing::BounceOut.apply(2.0) - 1.0).abs() < 1e-5);
+    }
+
+    #[derive(Default)]
+    struct Properties {
+        transform: [f32; 4],
+        tint: [f32; 4],
+    }
+
+    impl TweenedProperties for Properties {
+        fn read(&self, target: TweenTarget) -> [f32; 4] {
+            match target {
+                TweenTarget::Alpha(..) | TweenTarget::Color(..) => self.tint,
+                _ => self.transform,
+            }
+        }
+
+        fn write(&mut self, target: TweenTarget, value: [f32; 4]) {
+            match target {
+                TweenTarget::Alpha(..) | TweenTarget::Color(..) => self.tint = value,
+                _ => self.transform = value,
+            }
+        }
+    }
+
+    fn assert_close(a: [f32; 4], b: [f32; 4]) {
+        assert!(
+            a.iter().zip(&b).all(|(a, b)| (a - b).abs() < 1e-5),
+            "{:?} != {:?}",
+            a,
+            b
+        );
+    }
+
+    #[test]
+    fn steps_run_one_after_the_other() {
+        let mut properties = Properties::default();
+        let mut tween = UiTween::new(Tween::new(TweenTarget::Position(10.0, 0.0), 1.0))
+            .then(Tween::new(TweenTarget::Position(10.0, 20.0), 1.0));
+
+        assert!(tween.advance(0.5, &mut properties).is_empty());
+        assert_close(properties.transform, [5.0, 0.0, 0.0, 0.0]);
+
+        // The second step starts from where the first ended.
+        assert_eq!(tween.advance(0.75, &mut properties), vec![0]);
+        assert_eq!(tween.step(), 1);
+        assert_close(properties.transform, [10.0, 5.0, 0.0, 0.0]);
+
+        assert_eq!(tween.advance(1.0, &mut properties), vec![1]);
+        assert_eq!(tween.step(), 2);
+        assert_close(properties.transform, [10.0, 20.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn short_steps_end_within_one_frame() {
+        let mut properties = Properties::default();
+        let mut tween = UiTween::new(Tween::new(TweenTarget::Size(1.0, 1.0), 0.1))
+            .then(Tween::new(TweenTarget::Scale(2.0), 0.1))
+            .then(Tween::new(TweenTarget::Scale(3.0), 0.1));
+
+        assert_eq!(tween.advance(1.0, &mut properties), vec![0, 1, 2]);
+        assert_close(properties.transform, [6.0, 6.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn tweens_of_a_step_run_together_until_the_longest_ends() {
+        let mut properties = Properties {
+            transform: [0.0; 4],
+            tint: [1.0; 4],
+        };
+        let mut tween = UiTween::new(Tween::new(TweenTarget::Position(4.0, 0.0), 1.0))
+            .with(Tween::new(TweenTarget::Alpha(0.0), 1.0).with_delay(1.0));
+
+        assert!(tween.advance(0.5, &mut properties).is_empty());
+        assert_close(properties.transform, [2.0, 0.0, 0.0, 0.0]);
+        assert_close(properties.tint, [1.0; 4]);
+
+        assert!(tween.advance(1.0, &mut properties).is_empty());
+        assert_close(properties.transform, [4.0, 0.0, 0.0, 0.0]);
+        assert_close(properties.tint, [1.0, 1.0, 1.0, 0.5]);
+
+        assert_eq!(tween.advance(0.5, &mut properties), vec![0]);
+        assert_close(properties.tint, [1.0, 1.0, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn scale_and_alpha_keep_the_other_components() {
+        let from = [10.0, 20.0, 0.0, 0.0];
+        assert_eq!(TweenTarget::Scale(2.0).end(from), [20.0, 40.0, 0.0, 0.0]);
+        let from = [0.5, 0.25, 1.0, 1.0];
+        assert_eq!(TweenTarget::Alpha(0.0).end(from), [0.5, 0.25, 1.0, 0.0]);
+    }
+}
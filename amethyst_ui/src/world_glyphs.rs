@@ -0,0 +1,537 @@
+//! Glyph quads placed in the world, laid out and drawn with the UI glyph cache.
+//!
+//! Shared by [WorldText](crate::WorldText) and [DebugLabels](crate::DebugLabels), which only
+//! differ in how a [GlyphProjection] places their glyphs.
+
+use crate::{
+    glyphs::{create_glyph_texture, upload_glyph_rect},
+    FontAsset, FontHandle,
+};
+use amethyst_assets::{AssetStorage, Handle};
+use amethyst_core::ecs::{Read, SystemData, World};
+use amethyst_rendy::{
+    pipeline::{PipelineCache, PipelineDescBuilder, PipelinesBuilder},
+    pod::ViewArgs,
+    rendy::{
+        command::{QueueId, RenderPassEncoder},
+        factory::Factory,
+        graph::{
+            render::{PrepareResult, RenderGroup, RenderGroupDesc},
+            GraphContext, NodeBuffer, NodeImage,
+        },
+        hal::{
+            self,
+            device::Device,
+            format::Format,
+            pso::{self, ShaderStageFlags},
+        },
+        mesh::{AsVertex, VertexFormat},
+        shader::{Shader, SpirvShader},
+    },
+    shader_reload::ReloadableShader,
+    simple_shader_set,
+    stats::DrawCounter,
+    submodules::{
+        gather::CameraGatherer, DynamicUniform, DynamicVertexBuffer, TextureId, TextureSub,
+    },
+    types::{Backend, Texture},
+    ChangeDetection,
+};
+use glsl_layout::{vec2, vec4, AsStd140};
+use glyph_brush::{
+    rusttype::Scale, BrushAction, BrushError, BuiltInLineBreaker, FontId, GlyphBrush,
+    GlyphBrushBuilder, Layout, SectionText, VariedSection,
+};
+use std::{collections::HashMap, fmt::Debug, marker::PhantomData};
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+/// Places the glyphs of world texts, each text with its own placement.
+pub trait GlyphProjection: Debug + Send + Sync + 'static {
+    /// Instance attributes placing all glyphs of a text, preceding the attributes of the glyph.
+    type Placement: AsVertex;
+
+    /// Whether glyphs keep their size in pixels on screen, for which the inverse window size
+    /// is bound in set 2.
+    const PIXEL_SIZED: bool;
+
+    /// Vertex shader placing the glyph quads of a text.
+    fn vertex_shader() -> &'static ReloadableShader;
+
+    /// Depth test the glyphs are drawn with, if any.
+    fn depth_test() -> Option<pso::DepthTest>;
+}
+
+/// Glyph quad in pixels relative to the center of its text, with y pointing up.
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, AsStd140)]
+#[repr(C, align(4))]
+pub(crate) struct GlyphQuad {
+    coords: vec2,
+    dimensions: vec2,
+    tex_coord_bounds: vec4,
+    color: vec4,
+}
+
+impl AsVertex for GlyphQuad {
+    fn vertex() -> VertexFormat {
+        VertexFormat::new((
+            (Format::Rg32Sfloat, "coords"),
+            (Format::Rg32Sfloat, "dimensions"),
+            (Format::Rgba32Sfloat, "tex_coord_bounds"),
+            (Format::Rgba32Sfloat, "color"),
+        ))
+    }
+}
+
+/// Instance drawing a glyph quad with the placement of its text.
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+#[repr(C)]
+pub struct GlyphVertex<T> {
+    placement: T,
+    glyph: GlyphQuad,
+}
+
+impl<T: AsVertex> AsVertex for GlyphVertex<T> {
+    fn vertex() -> VertexFormat {
+        VertexFormat::new((T::vertex(), GlyphQuad::vertex()))
+    }
+}
+
+/// Glyph vertices of the world texts placed by `P`, laid out by a [GlyphLayouter].
+#[derive(Debug)]
+pub struct TextGlyphs<P: GlyphProjection> {
+    glyph_tex: Option<Handle<Texture>>,
+    vertices: Vec<GlyphVertex<P::Placement>>,
+}
+
+impl<P: GlyphProjection> Default for TextGlyphs<P> {
+    fn default() -> Self {
+        Self {
+            glyph_tex: None,
+            vertices: Vec::new(),
+        }
+    }
+}
+
+/// Text laid out by a [GlyphLayouter].
+#[derive(Debug)]
+pub(crate) struct GlyphText<'a, T> {
+    pub(crate) text: &'a str,
+    pub(crate) font_id: FontId,
+    pub(crate) font_size: f32,
+    pub(crate) color: [f32; 4],
+    pub(crate) placement: T,
+}
+
+/// Lays out texts centered on their placement, and keeps their glyph texture up to date.
+#[allow(missing_debug_implementations)]
+pub(crate) struct GlyphLayouter<P: GlyphProjection> {
+    glyph_brush: GlyphBrush<'static, (u32, GlyphQuad)>,
+    fonts_map: HashMap<u32, FontId>,
+    /// Glyphs from the last layout, tagged with the index of their text.
+    cached: Vec<(u32, GlyphQuad)>,
+    marker: PhantomData<P>,
+}
+
+impl<P: GlyphProjection> GlyphLayouter<P> {
+    /// Create a layouter with a glyph texture of `cache_size` pixels, grown when it's full.
+    pub(crate) fn new(cache_size: (u32, u32)) -> Self {
+        Self {
+            glyph_brush: GlyphBrushBuilder::using_fonts(vec![])
+                .initial_cache_size(cache_size)
+                .build(),
+            fonts_map: Default::default(),
+            cached: Vec::new(),
+            marker: PhantomData,
+        }
+    }
+
+    /// Id of the loaded font of `handle`, added to the glyph brush on first use.
+    pub(crate) fn font_id(&mut self, handle: &FontHandle, font: &FontAsset) -> FontId {
+        let glyph_brush = &mut self.glyph_brush;
+        *self
+            .fonts_map
+            .entry(handle.id())
+            .or_insert_with(|| glyph_brush.add_font(font.0.clone()))
+    }
+
+    /// Lay out `texts` and add their glyphs to `glyphs`, uploading new glyphs to its texture.
+    pub(crate) fn layout<B: Backend>(
+        &mut self,
+        factory: &mut Factory<B>,
+        queue: QueueId,
+        tex_storage: &mut AssetStorage<Texture>,
+        glyphs: &mut TextGlyphs<P>,
+        layout: Layout<BuiltInLineBreaker>,
+        texts: &[GlyphText<'_, P::Placement>],
+    ) {
+        if texts.is_empty() {
+            return;
+        }
+
+        let glyph_brush = &mut self.glyph_brush;
+        let glyph_tex = glyphs.glyph_tex.get_or_insert_with(|| {
+            let (w, h) = glyph_brush.texture_dimensions();
+            tex_storage.insert(create_glyph_texture(factory, queue, w, h))
+        });
+        let glyph_tex = glyph_tex.clone();
+
+        let mut tex = tex_storage
+            .get(&glyph_tex)
+            .and_then(B::unwrap_texture)
+            .expect("Glyph texture is created synchronously");
+
+        for (i, text) in texts.iter().enumerate() {
+            glyph_brush.queue(VariedSection {
+                screen_position: (0.0, 0.0),
+                bounds: (std::f32::INFINITY, std::f32::INFINITY),
+                // Like in `UiGlyphsSystem`, Z is used to tag glyphs with their owner.
+                z: f32::from_bits(i as u32),
+                layout,
+                text: vec![SectionText {
+                    text: text.text,
+                    scale: Scale::uniform(text.font_size),
+                    color: text.color,
+                    font_id: text.font_id,
+                }],
+            });
+        }
+
+        loop {
+            let action = glyph_brush.process_queued(
+                |rect, data| upload_glyph_rect(factory, queue, tex, rect, data),
+                |glyph| {
+                    let uv = glyph.tex_coords;
+                    let coords = glyph.pixel_coords;
+                    (
+                        glyph.z.to_bits(),
+                        GlyphQuad {
+                            // Glyph centers in pixels with y pointing up.
+                            coords: [
+                                (coords.max.x + coords.min.x) as f32 * 0.5,
+                                -(coords.max.y + coords.min.y) as f32 * 0.5,
+                            ]
+                            .into(),
+                            dimensions: [
+                                (coords.max.x - coords.min.x) as f32,
+                                (coords.max.y - coords.min.y) as f32,
+                            ]
+                            .into(),
+                            tex_coord_bounds: [uv.min.x, uv.min.y, uv.max.x, uv.max.y].into(),
+                            color: glyph.color.into(),
+                        },
+                    )
+                },
+            );
+
+            match action {
+                Ok(BrushAction::Draw(vertices)) => {
+                    self.cached = vertices;
+                    break;
+                }
+                // Sections are unchanged, so the cached glyphs still match text indices.
+                Ok(BrushAction::ReDraw) => break,
+                Err(BrushError::TextureTooSmall { suggested: (w, h) }) => {
+                    tex_storage.replace(&glyph_tex, create_glyph_texture(factory, queue, w, h));
+                    tex = tex_storage
+                        .get(&glyph_tex)
+                        .and_then(B::unwrap_texture)
+                        .unwrap();
+                    glyph_brush.resize_texture(w, h);
+                }
+            }
+        }
+
+        // Placements are not part of the layout, attach them every frame.
+        glyphs
+            .vertices
+            .extend(self.cached.iter().map(|&(text, glyph)| GlyphVertex {
+                placement: texts[text as usize].placement,
+                glyph,
+            }));
+    }
+}
+
+impl<P: GlyphProjection> TextGlyphs<P> {
+    /// Remove the glyphs of the last layout.
+    pub(crate) fn clear(&mut self) {
+        self.vertices.clear();
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, AsStd140)]
+struct GlyphViewArgs {
+    inverse_window_size: vec2,
+}
+
+lazy_static::lazy_static! {
+    static ref GLYPH_FRAGMENT: ReloadableShader = ReloadableShader::new(
+        env!("CARGO_PKG_NAME"),
+        "compiled/ui.frag.spv",
+        SpirvShader::from_bytes(
+            include_bytes!("../compiled/ui.frag.spv"),
+            ShaderStageFlags::FRAGMENT,
+            "main",
+        ).unwrap(),
+    );
+}
+
+/// Describes drawing of the [TextGlyphs] placed by `P`.
+#[derive(Debug)]
+pub struct DrawGlyphsDesc<P> {
+    marker: PhantomData<P>,
+}
+
+impl<P> Default for DrawGlyphsDesc<P> {
+    fn default() -> Self {
+        Self {
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<P> Clone for DrawGlyphsDesc<P> {
+    fn clone(&self) -> Self {
+        Default::default()
+    }
+}
+
+impl<P> PartialEq for DrawGlyphsDesc<P> {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl<P> DrawGlyphsDesc<P> {
+    /// Create instance of the render group drawing the glyphs.
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl<B: Backend, P: GlyphProjection> RenderGroupDesc<B, World> for DrawGlyphsDesc<P> {
+    fn build(
+        self,
+        _ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        _queue: QueueId,
+        resources: &World,
+        framebuffer_width: u32,
+        framebuffer_height: u32,
+        subpass: hal::pass::Subpass<'_, B>,
+        _buffers: Vec<NodeBuffer>,
+        _images: Vec<NodeImage>,
+    ) -> Result<Box<dyn RenderGroup<B, World>>, failure::Error> {
+        #[cfg(feature = "profiler")]
+        profile_scope!("build");
+
+        let env = DynamicUniform::new(factory, pso::ShaderStageFlags::VERTEX)?;
+        let textures = TextureSub::new(factory)?;
+        let args = if P::PIXEL_SIZED {
+            Some(DynamicUniform::new(factory, pso::ShaderStageFlags::VERTEX)?)
+        } else {
+            None
+        };
+        let vertex = DynamicVertexBuffer::new();
+
+        let pipeline_cache = resources.try_fetch::<PipelineCache<B>>();
+
+        let mut layouts = vec![env.raw_layout(), textures.raw_layout()];
+        layouts.extend(args.as_ref().map(DynamicUniform::raw_layout));
+        let (pipeline, pipeline_layout) = build_glyph_pipeline::<B, P>(
+            factory,
+            pipeline_cache.as_ref().and_then(|cache| cache.raw()),
+            subpass,
+            framebuffer_width,
+            framebuffer_height,
+            layouts,
+        )?;
+
+        Ok(Box::new(DrawGlyphs::<B, P> {
+            pipeline,
+            pipeline_layout,
+            env,
+            textures,
+            args,
+            vertex,
+            framebuffer_width: framebuffer_width as f32,
+            framebuffer_height: framebuffer_height as f32,
+            tex_id: None,
+            glyph_count: 0,
+            change: Default::default(),
+        }))
+    }
+}
+
+/// Draws the [TextGlyphs] placed by `P`.
+#[derive(Debug)]
+pub struct DrawGlyphs<B: Backend, P: GlyphProjection> {
+    pipeline: B::GraphicsPipeline,
+    pipeline_layout: B::PipelineLayout,
+    env: DynamicUniform<B, ViewArgs>,
+    textures: TextureSub<B>,
+    args: Option<DynamicUniform<B, GlyphViewArgs>>,
+    vertex: DynamicVertexBuffer<B, GlyphVertex<P::Placement>>,
+    framebuffer_width: f32,
+    framebuffer_height: f32,
+    tex_id: Option<TextureId>,
+    glyph_count: usize,
+    change: ChangeDetection,
+}
+
+impl<B: Backend, P: GlyphProjection> RenderGroup<B, World> for DrawGlyphs<B, P> {
+    fn prepare(
+        &mut self,
+        factory: &Factory<B>,
+        _queue: QueueId,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        resources: &World,
+    ) -> PrepareResult {
+        #[cfg(feature = "profiler")]
+        profile_scope!("prepare");
+
+        let glyphs = <Read<'_, TextGlyphs<P>>>::fetch(resources);
+
+        let old_count = self.glyph_count;
+        self.glyph_count = 0;
+        self.tex_id = None;
+
+        let mut changed = false;
+        if let Some(glyph_tex) = glyphs.glyph_tex.as_ref() {
+            if let Some((tex_id, tex_changed)) =
+                self.textures
+                    .insert(factory, resources, glyph_tex, hal::image::Layout::General)
+            {
+                changed = tex_changed;
+                self.tex_id = Some(tex_id);
+                self.glyph_count = glyphs.vertices.len();
+            }
+        }
+        self.textures.maintain(factory, resources);
+
+        let cam = CameraGatherer::gather(resources);
+        self.env.write(factory, index, cam.projview);
+        if let Some(args) = self.args.as_mut() {
+            args.write(
+                factory,
+                index,
+                GlyphViewArgs {
+                    inverse_window_size: [
+                        1.0 / self.framebuffer_width,
+                        1.0 / self.framebuffer_height,
+                    ]
+                    .into(),
+                }
+                .std140(),
+            );
+        }
+
+        {
+            #[cfg(feature = "profiler")]
+            profile_scope!("write");
+            self.vertex.write(
+                factory,
+                index,
+                self.glyph_count as u64,
+                Some(&glyphs.vertices[..self.glyph_count]),
+            );
+        }
+
+        changed = changed || old_count != self.glyph_count;
+        self.change.prepare_result(index, changed)
+    }
+
+    fn draw_inline(
+        &mut self,
+        mut encoder: RenderPassEncoder<'_, B>,
+        index: usize,
+        _subpass: hal::pass::Subpass<'_, B>,
+        resources: &World,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("draw");
+
+        let tex_id = match self.tex_id {
+            Some(tex_id) if self.glyph_count > 0 => tex_id,
+            _ => return,
+        };
+
+        let layout = &self.pipeline_layout;
+        encoder.bind_graphics_pipeline(&self.pipeline);
+        self.env.bind(index, layout, 0, &mut encoder);
+        self.textures.bind(layout, 1, tex_id, &mut encoder);
+        if let Some(args) = self.args.as_ref() {
+            args.bind(index, layout, 2, &mut encoder);
+        }
+        self.vertex.bind(index, 0, 0, &mut encoder);
+        unsafe {
+            encoder.draw(0..4, 0..self.glyph_count as u32);
+        }
+        DrawCounter::fetch(resources).draw_quads(self.glyph_count as u32);
+    }
+
+    fn dispose(self: Box<Self>, factory: &mut Factory<B>, _aux: &World) {
+        unsafe {
+            factory.device().destroy_graphics_pipeline(self.pipeline);
+            factory
+                .device()
+                .destroy_pipeline_layout(self.pipeline_layout);
+        }
+    }
+}
+
+fn build_glyph_pipeline<B: Backend, P: GlyphProjection>(
+    factory: &Factory<B>,
+    cache: Option<&B::PipelineCache>,
+    subpass: hal::pass::Subpass<'_, B>,
+    framebuffer_width: u32,
+    framebuffer_height: u32,
+    layouts: Vec<&B::DescriptorSetLayout>,
+) -> Result<(B::GraphicsPipeline, B::PipelineLayout), failure::Error> {
+    let pipeline_layout = unsafe {
+        factory
+            .device()
+            .create_pipeline_layout(layouts, None as Option<(_, _)>)
+    }?;
+
+    let shader_vertex = unsafe { P::vertex_shader().module(factory).unwrap() };
+    let shader_fragment = unsafe { GLYPH_FRAGMENT.module(factory).unwrap() };
+
+    let mut pipeline = PipelineDescBuilder::new()
+        .with_vertex_desc(&[(
+            GlyphVertex::<P::Placement>::vertex(),
+            pso::VertexInputRate::Instance(1),
+        )])
+        .with_input_assembler(pso::InputAssemblerDesc::new(hal::Primitive::TriangleStrip))
+        .with_shaders(simple_shader_set(&shader_vertex, Some(&shader_fragment)))
+        .with_layout(&pipeline_layout)
+        .with_subpass(subpass)
+        .with_framebuffer_size(framebuffer_width, framebuffer_height)
+        .with_blend_targets(vec![pso::ColorBlendDesc {
+            mask: pso::ColorMask::ALL,
+            blend: Some(pso::BlendState::ALPHA),
+        }]);
+    if let Some(depth_test) = P::depth_test() {
+        pipeline = pipeline.with_depth_test(depth_test);
+    }
+    let pipes = PipelinesBuilder::new()
+        .with_pipeline(pipeline)
+        .build_cached(factory, cache);
+
+    unsafe {
+        factory.destroy_shader_module(shader_vertex);
+        factory.destroy_shader_module(shader_fragment);
+    }
+
+    match pipes {
+        Err(e) => {
+            unsafe {
+                factory.device().destroy_pipeline_layout(pipeline_layout);
+            }
+            Err(e)
+        }
+        Ok(mut pipes) => Ok((pipes.remove(0), pipeline_layout)),
+    }
+}
@@ -0,0 +1,250 @@
+//! Text placed in the world with a `Transform`, rendered with the UI glyph cache.
+
+use crate::{
+    world_glyphs::{
+        DrawGlyphs, DrawGlyphsDesc, GlyphLayouter, GlyphProjection, GlyphText, TextGlyphs,
+    },
+    FontAsset, FontHandle,
+};
+use amethyst_assets::AssetStorage;
+use amethyst_core::{
+    ecs::{
+        Component, DenseVecStorage, DispatcherBuilder, Join, Read, ReadStorage, System, World,
+        WorldExt, Write,
+    },
+    math::{convert, Matrix4},
+    Hidden, HiddenPropagate, Transform,
+};
+use amethyst_error::Error;
+use amethyst_rendy::{
+    bundle::{RenderOrder, RenderPlan, RenderPlugin, Target},
+    palette::Srgba,
+    rendy::{
+        command::QueueId,
+        factory::Factory,
+        graph::render::RenderGroupDesc,
+        hal::pso::{self, ShaderStageFlags},
+        mesh::Model,
+        shader::SpirvShader,
+    },
+    shader_reload::ReloadableShader,
+    types::{Backend, Texture},
+};
+use glyph_brush::{HorizontalAlign, Layout, VerticalAlign};
+use std::marker::PhantomData;
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+/// Component drawing text in the local XY plane of the `Transform` of its entity, for signs,
+/// damage numbers and name plates.
+///
+/// Text is centered on the origin and laid out one unit per em, so its world size is set by
+/// scaling the transform. `font_size` only sets the resolution the glyphs are rasterized at.
+/// Lines are split at `\n`. Text is drawn by the `RenderWorldText` plugin, depth tested against
+/// the opaque objects of its target.
+#[derive(Clone, Debug)]
+pub struct WorldText {
+    /// The string rendered.
+    pub text: String,
+    /// The font used for rendering.
+    pub font: FontHandle,
+    /// Size in pixels the glyphs are rasterized at.
+    pub font_size: f32,
+    /// The normalized RGBA color of the text.
+    pub color: [f32; 4],
+}
+
+impl WorldText {
+    /// Creates world text rasterized at 32 pixels per em.
+    pub fn new(font: FontHandle, text: impl Into<String>, color: Srgba) -> Self {
+        let (r, g, b, a) = color.into_components();
+        WorldText {
+            text: text.into(),
+            font,
+            font_size: 32.0,
+            color: [r, g, b, a],
+        }
+    }
+}
+
+impl Component for WorldText {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Places the glyphs of [WorldText] in the local XY plane of its transform, depth tested
+/// against the opaque objects.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct WorldTextProjection;
+
+impl GlyphProjection for WorldTextProjection {
+    type Placement = Model;
+
+    const PIXEL_SIZED: bool = false;
+
+    fn vertex_shader() -> &'static ReloadableShader {
+        &WORLD_TEXT_VERTEX
+    }
+
+    fn depth_test() -> Option<pso::DepthTest> {
+        Some(pso::DepthTest {
+            fun: pso::Comparison::Greater,
+            write: false,
+        })
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref WORLD_TEXT_VERTEX: ReloadableShader = ReloadableShader::new(
+        env!("CARGO_PKG_NAME"),
+        "compiled/world_text.vert.spv",
+        SpirvShader::from_bytes(
+            include_bytes!("../compiled/world_text.vert.spv"),
+            ShaderStageFlags::VERTEX,
+            "main",
+        ).unwrap(),
+    );
+}
+
+/// Glyph vertices of the visible [WorldText]s, produced by `WorldTextGlyphsSystem`.
+pub type WorldTextGlyphs = TextGlyphs<WorldTextProjection>;
+
+/// Describes drawing of [WorldText] components.
+pub type DrawWorldTextDesc = DrawGlyphsDesc<WorldTextProjection>;
+
+/// Draws [WorldText] components in the local plane of their transforms.
+pub type DrawWorldText<B> = DrawGlyphs<B, WorldTextProjection>;
+
+/// Lays out the text of [WorldText] components and keeps its glyph texture up to date.
+#[allow(missing_debug_implementations)]
+pub struct WorldTextGlyphsSystem<B: Backend> {
+    layouter: GlyphLayouter<WorldTextProjection>,
+    marker: PhantomData<B>,
+}
+
+impl<B: Backend> Default for WorldTextGlyphsSystem<B> {
+    fn default() -> Self {
+        Self {
+            layouter: GlyphLayouter::new((512, 512)),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, B: Backend> System<'a> for WorldTextGlyphsSystem<B> {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Option<Write<'a, Factory<B>>>,
+        Option<Read<'a, QueueId>>,
+        ReadStorage<'a, WorldText>,
+        ReadStorage<'a, Transform>,
+        ReadStorage<'a, Hidden>,
+        ReadStorage<'a, HiddenPropagate>,
+        Write<'a, AssetStorage<Texture>>,
+        Read<'a, AssetStorage<FontAsset>>,
+        Write<'a, WorldTextGlyphs>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            mut maybe_factory,
+            maybe_queue,
+            texts,
+            transforms,
+            hiddens,
+            hidden_props,
+            mut tex_storage,
+            font_storage,
+            mut glyphs,
+        ): Self::SystemData,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("world_text_glyphs_system");
+
+        glyphs.clear();
+
+        let (factory, queue) =
+            if let (Some(factory), Some(queue)) = (maybe_factory.as_mut(), maybe_queue) {
+                (factory, queue)
+            } else {
+                return;
+            };
+
+        let layouter = &mut self.layouter;
+        // Text is laid out in pixels, scaled to ems so the size of the text doesn't depend on
+        // its resolution.
+        let visible = (&texts, &transforms, !&hiddens, !&hidden_props)
+            .join()
+            .filter_map(|(text, transform, _, _)| {
+                let font = font_storage.get(&text.font)?;
+                let model = convert::<_, Matrix4<f32>>(*transform.global_matrix())
+                    * Matrix4::new_scaling(1.0 / text.font_size);
+                let model: [[f32; 4]; 4] = model.into();
+                Some(GlyphText {
+                    text: &text.text,
+                    font_id: layouter.font_id(&text.font, font),
+                    font_size: text.font_size,
+                    color: text.color,
+                    placement: model.into(),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let layout = Layout::default()
+            .h_align(HorizontalAlign::Center)
+            .v_align(VerticalAlign::Center);
+        layouter.layout(
+            factory,
+            *queue,
+            &mut tex_storage,
+            &mut glyphs,
+            layout,
+            &visible,
+        );
+    }
+}
+
+/// A [RenderPlugin] for drawing [WorldText] components with the transparent objects of its
+/// target.
+#[derive(Debug, Default)]
+pub struct RenderWorldText {
+    target: Target,
+}
+
+impl RenderWorldText {
+    /// Set target to which world text will be rendered.
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+}
+
+impl<B: Backend> RenderPlugin<B> for RenderWorldText {
+    fn on_build<'a, 'b>(
+        &mut self,
+        world: &mut World,
+        builder: &mut DispatcherBuilder<'a, 'b>,
+    ) -> Result<(), Error> {
+        world.register::<WorldText>();
+        builder.add(
+            WorldTextGlyphsSystem::<B>::default(),
+            "world_text_glyphs_system",
+            &[],
+        );
+        Ok(())
+    }
+
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        _factory: &mut Factory<B>,
+        _world: &World,
+    ) -> Result<(), Error> {
+        plan.extend_target(self.target, |ctx| {
+            ctx.add(RenderOrder::Transparent, DrawWorldTextDesc::new().builder())?;
+            Ok(())
+        });
+        Ok(())
+    }
+}
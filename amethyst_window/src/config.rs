@@ -52,6 +52,10 @@ pub struct DisplayConfig {
     /// window.
     #[serde(default)]
     pub transparent: bool,
+    /// How rendered frames are presented to the window, which sets whether rendering waits
+    /// for vertical sync.
+    #[serde(default)]
+    pub present_mode: PresentMode,
 
     /// A programmatically loaded window icon; not present in serialization.
     /// Takes precedence over `icon`.
@@ -97,11 +101,32 @@ impl Default for DisplayConfig {
             multitouch: false,
             resizable: default_resizable(),
             transparent: false,
+            present_mode: PresentMode::default(),
             loaded_icon: None,
         }
     }
 }
 
+/// How rendered frames are presented to a window.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum PresentMode {
+    /// Frames are presented in order at vertical sync, so rendering waits for the display.
+    /// Avoids tearing and is supported everywhere.
+    Fifo,
+    /// Frames are presented at vertical sync, replacing frames waiting to be presented, so
+    /// rendering doesn't wait for the display. Avoids tearing at the cost of extra latency.
+    Mailbox,
+    /// Frames are presented immediately, turning vertical sync off. Has the lowest latency,
+    /// but shows tearing.
+    Immediate,
+}
+
+impl Default for PresentMode {
+    fn default() -> Self {
+        PresentMode::Fifo
+    }
+}
+
 fn default_title() -> String {
     "Amethyst game".to_string()
 }
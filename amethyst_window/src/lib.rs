@@ -15,14 +15,16 @@ mod config;
 mod monitor;
 mod resources;
 mod system;
+mod windows;
 
 #[cfg(feature = "test-support")]
 pub use crate::bundle::{SCREEN_HEIGHT, SCREEN_WIDTH};
 pub use crate::{
     bundle::WindowBundle,
-    config::DisplayConfig,
+    config::{DisplayConfig, PresentMode},
     monitor::{MonitorIdent, MonitorsAccess},
     resources::ScreenDimensions,
     system::{EventsLoopSystem, WindowSystem},
+    windows::{AdditionalWindowEvent, Windows},
 };
-pub use winit::{Icon, Window};
+pub use winit::{Icon, Window, WindowId};
@@ -1,4 +1,5 @@
 use amethyst_core::math::Vector2;
+use winit::Window;
 
 /// World resource that stores screen dimensions.
 #[derive(Debug, PartialEq, Clone)]
@@ -27,6 +28,17 @@ impl ScreenDimensions {
         }
     }
 
+    /// Creates the screen dimensions of the inner size of `window`.
+    pub(crate) fn of_window(window: &Window) -> Self {
+        let hidpi = window.get_hidpi_factor();
+        let (width, height) = window
+            .get_inner_size()
+            .expect("Window closed during initialization!")
+            .to_physical(hidpi)
+            .into();
+        Self::new(width, height, hidpi)
+    }
+
     /// Returns the current logical size of window as diagonal vector.
     pub fn diagonal(&self) -> Vector2<f32> {
         Vector2::new(self.width(), self.height())
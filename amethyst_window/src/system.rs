@@ -1,4 +1,8 @@
-use crate::{config::DisplayConfig, resources::ScreenDimensions};
+use crate::{
+    config::DisplayConfig,
+    resources::ScreenDimensions,
+    windows::{AdditionalWindowEvent, Windows},
+};
 use amethyst_config::{Config, ConfigError};
 use amethyst_core::{
     ecs::{ReadExpect, RunNow, System, SystemData, World, Write, WriteExpect},
@@ -38,13 +42,7 @@ impl WindowSystem {
 
     /// Create a new `WindowSystem` wrapping the provided `Window`
     pub fn new(world: &mut World, window: Window) -> Self {
-        let hidpi = window.get_hidpi_factor();
-        let (width, height) = window
-            .get_inner_size()
-            .expect("Window closed during initialization!")
-            .to_physical(hidpi)
-            .into();
-        world.insert(ScreenDimensions::new(width, height, hidpi));
+        world.insert(ScreenDimensions::of_window(&window));
         world.insert(window);
         Self
     }
@@ -78,13 +76,20 @@ impl WindowSystem {
 }
 
 impl<'a> System<'a> for WindowSystem {
-    type SystemData = (WriteExpect<'a, ScreenDimensions>, ReadExpect<'a, Window>);
+    type SystemData = (
+        WriteExpect<'a, ScreenDimensions>,
+        ReadExpect<'a, Window>,
+        Write<'a, Windows>,
+    );
 
-    fn run(&mut self, (mut screen_dimensions, window): Self::SystemData) {
+    fn run(&mut self, (mut screen_dimensions, window, mut windows): Self::SystemData) {
         #[cfg(feature = "profiler")]
         profile_scope!("window_system");
 
         self.manage_dimensions(&mut screen_dimensions, &window);
+        for (window, dimensions) in windows.iter_mut() {
+            self.manage_dimensions(dimensions, window);
+        }
     }
 }
 
@@ -92,10 +97,13 @@ impl<'a> System<'a> for WindowSystem {
 ///
 /// This system must be active for any `GameState` to receive
 /// any `StateEvent::Window` event into it's `handle_event` method.
+/// It also opens the windows requested from the `Windows` resource, and sends their events
+/// to the `EventChannel<AdditionalWindowEvent>` instead.
 #[derive(Debug)]
 pub struct EventsLoopSystem {
     events_loop: EventsLoop,
     events: Vec<Event>,
+    window_events: Vec<AdditionalWindowEvent>,
 }
 
 impl EventsLoopSystem {
@@ -104,22 +112,32 @@ impl EventsLoopSystem {
         Self {
             events_loop,
             events: Vec::with_capacity(128),
+            window_events: Vec::new(),
         }
     }
 }
 
 impl<'a> RunNow<'a> for EventsLoopSystem {
     fn run_now(&mut self, world: &'a World) {
-        let mut event_handler = <Write<'a, EventChannel<Event>>>::fetch(world);
-
-        let events = &mut self.events;
+        let (mut windows, mut event_handler, mut window_event_handler) = <(
+            Write<'a, Windows>,
+            Write<'a, EventChannel<Event>>,
+            Write<'a, EventChannel<AdditionalWindowEvent>>,
+        )>::fetch(world);
+        windows.destroy_closed();
+        windows.open_pending(&self.events_loop);
+
+        let (events, window_events) = (&mut self.events, &mut self.window_events);
         self.events_loop.poll_events(|event| {
-            events.push(event);
+            events.extend(windows.route(event, window_events));
         });
         event_handler.drain_vec_write(events);
+        window_event_handler.drain_vec_write(window_events);
     }
 
     fn setup(&mut self, world: &mut World) {
         <Write<'a, EventChannel<Event>>>::setup(world);
+        <Write<'a, EventChannel<AdditionalWindowEvent>>>::setup(world);
+        <Write<'a, Windows>>::setup(world);
     }
 }
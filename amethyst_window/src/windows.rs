@@ -0,0 +1,163 @@
+use crate::{config::DisplayConfig, resources::ScreenDimensions};
+use std::fmt;
+use winit::{Event, EventsLoop, Window, WindowEvent, WindowId};
+
+/// Number of `EventsLoopSystem` runs a closed window is kept alive for, so that every
+/// renderer had a frame to release its surface before the window is destroyed.
+const CLOSED_WINDOW_FRAMES: u32 = 2;
+
+/// Event of one of the windows of the `Windows` resource, sent to the
+/// `EventChannel<AdditionalWindowEvent>` instead of the `EventChannel<Event>` of the main window.
+#[derive(Debug, Clone)]
+pub struct AdditionalWindowEvent {
+    /// Name of the window the event belongs to.
+    pub window: String,
+    /// The window event.
+    pub event: WindowEvent,
+}
+
+/// World resource holding the windows opened besides the main `Window`, by name, e.g. for
+/// tool windows of editors or a second screen.
+///
+/// Windows requested with `open` are created by the `EventsLoopSystem` at the start of the next
+/// frame, and the `WindowSystem` keeps their `ScreenDimensions` up to date like those of the
+/// main window. Their events are sent to the `EventChannel<AdditionalWindowEvent>`, so the
+/// `EventChannel<Event>` only has the events of the main window and of devices.
+///
+/// A window stays open until it's closed with `close`, even when the user requests to close it.
+#[derive(Default)]
+pub struct Windows {
+    windows: Vec<(String, Window, ScreenDimensions)>,
+    pending: Vec<(String, DisplayConfig)>,
+    closed: Vec<(Window, u32)>,
+}
+
+impl Windows {
+    /// Request a window named `name` to be opened with the given config, replacing the window
+    /// of the same name if there is one.
+    pub fn open(&mut self, name: impl Into<String>, config: DisplayConfig) {
+        self.pending.push((name.into(), config));
+    }
+
+    /// Close the window named `name`. Returns whether there was such a window.
+    ///
+    /// The window is destroyed a frame later, after the renderer dropped its surface.
+    pub fn close(&mut self, name: &str) -> bool {
+        let (closed, open): (Vec<_>, Vec<_>) = std::mem::take(&mut self.windows)
+            .into_iter()
+            .partition(|(window_name, _, _)| window_name == name);
+        self.windows = open;
+        self.pending.retain(|(window_name, _)| window_name != name);
+        let was_open = !closed.is_empty();
+        self.closed.extend(
+            closed
+                .into_iter()
+                .map(|(_, window, _)| (window, CLOSED_WINDOW_FRAMES)),
+        );
+        was_open
+    }
+
+    /// Returns the window named `name`, if it's open.
+    pub fn get(&self, name: &str) -> Option<&Window> {
+        self.find(name).map(|(_, window, _)| window)
+    }
+
+    /// Returns the dimensions of the window named `name`, if it's open.
+    pub fn dimensions(&self, name: &str) -> Option<&ScreenDimensions> {
+        self.find(name).map(|(_, _, dimensions)| dimensions)
+    }
+
+    /// Returns the dimensions of the window named `name` for resizing it, if it's open.
+    pub fn dimensions_mut(&mut self, name: &str) -> Option<&mut ScreenDimensions> {
+        self.windows
+            .iter_mut()
+            .find(|(window_name, _, _)| window_name == name)
+            .map(|(_, _, dimensions)| dimensions)
+    }
+
+    /// Returns the name of the window with the given id, or `None` for the main window and
+    /// closed windows.
+    pub fn name_of(&self, id: WindowId) -> Option<&str> {
+        self.windows
+            .iter()
+            .find(|(_, window, _)| window.id() == id)
+            .map(|(name, _, _)| name.as_str())
+    }
+
+    /// Returns the names of the open windows.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.windows.iter().map(|(name, _, _)| name.as_str())
+    }
+
+    pub(crate) fn iter_mut(&mut self) -> impl Iterator<Item = (&Window, &mut ScreenDimensions)> {
+        self.windows
+            .iter_mut()
+            .map(|(_, window, dimensions)| (&*window, dimensions))
+    }
+
+    /// Sends the events of the windows to `window_events` and returns the others, dropping
+    /// the events of closed windows.
+    pub(crate) fn route(
+        &self,
+        event: Event,
+        window_events: &mut Vec<AdditionalWindowEvent>,
+    ) -> Option<Event> {
+        match event {
+            Event::WindowEvent { window_id, event } => {
+                if let Some(name) = self.name_of(window_id) {
+                    window_events.push(AdditionalWindowEvent {
+                        window: name.to_string(),
+                        event,
+                    });
+                    None
+                } else if self
+                    .closed
+                    .iter()
+                    .any(|(window, _)| window.id() == window_id)
+                {
+                    None
+                } else {
+                    Some(Event::WindowEvent { window_id, event })
+                }
+            }
+            event => Some(event),
+        }
+    }
+
+    /// Destroys the windows closed for long enough.
+    pub(crate) fn destroy_closed(&mut self) {
+        for (_, frames) in &mut self.closed {
+            *frames -= 1;
+        }
+        self.closed.retain(|(_, frames)| *frames > 0);
+    }
+
+    pub(crate) fn open_pending(&mut self, events_loop: &EventsLoop) {
+        for (name, config) in std::mem::take(&mut self.pending) {
+            match config.into_window_builder(events_loop).build(events_loop) {
+                Ok(window) => {
+                    self.close(&name);
+                    let dimensions = ScreenDimensions::of_window(&window);
+                    self.windows.push((name, window, dimensions));
+                }
+                Err(err) => log::error!("Failed to open window {:?}: {}", name, err),
+            }
+        }
+    }
+
+    fn find(&self, name: &str) -> Option<&(String, Window, ScreenDimensions)> {
+        self.windows
+            .iter()
+            .find(|(window_name, _, _)| window_name == name)
+    }
+}
+
+impl fmt::Debug for Windows {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Windows")
+            .field("windows", &self.names().collect::<Vec<_>>())
+            .field("pending", &self.pending)
+            .field("closed", &self.closed.len())
+            .finish()
+    }
+}
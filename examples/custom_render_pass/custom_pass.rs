@@ -5,7 +5,7 @@ use amethyst::{
     prelude::*,
     renderer::{
         bundle::{RenderOrder, RenderPlan, RenderPlugin, Target},
-        pipeline::{PipelineDescBuilder, PipelinesBuilder},
+        pipeline::{PipelineCache, PipelineDescBuilder, PipelinesBuilder},
         rendy::{
             command::{QueueId, RenderPassEncoder},
             factory::Factory,
@@ -86,7 +86,7 @@ impl<B: Backend> RenderGroupDesc<B, World> for DrawCustomDesc {
         _ctx: &GraphContext<B>,
         factory: &mut Factory<B>,
         _queue: QueueId,
-        _world: &World,
+        world: &World,
         framebuffer_width: u32,
         framebuffer_height: u32,
         subpass: hal::pass::Subpass<'_, B>,
@@ -96,8 +96,11 @@ impl<B: Backend> RenderGroupDesc<B, World> for DrawCustomDesc {
         let env = DynamicUniform::new(factory, pso::ShaderStageFlags::VERTEX)?;
         let vertex = DynamicVertexBuffer::new();
 
+        let pipeline_cache = world.try_fetch::<PipelineCache<B>>();
+
         let (pipeline, pipeline_layout) = build_custom_pipeline(
             factory,
+            pipeline_cache.as_ref().and_then(|cache| cache.raw()),
             subpass,
             framebuffer_width,
             framebuffer_height,
@@ -202,6 +205,7 @@ impl<B: Backend> RenderGroup<B, World> for DrawCustom<B> {
 
 fn build_custom_pipeline<B: Backend>(
     factory: &Factory<B>,
+    cache: Option<&B::PipelineCache>,
     subpass: hal::pass::Subpass<'_, B>,
     framebuffer_width: u32,
     framebuffer_height: u32,
@@ -238,7 +242,7 @@ fn build_custom_pipeline<B: Backend>(
                     blend: Some(pso::BlendState::ALPHA),
                 }]),
         )
-        .build(factory, None);
+        .build_cached(factory, cache);
 
     // Destoy the shaders once loaded
     unsafe {
@@ -39,8 +39,8 @@ use amethyst::{
         types::{DefaultBackend, Mesh, Texture},
         visibility::BoundingSphere,
         ActiveCamera, Camera, Factory, ImageFormat, Material, MaterialDefaults, RenderDebugLines,
-        RenderFlat2D, RenderFlat3D, RenderPbr3D, RenderShaded3D, RenderSkybox, RenderToWindow,
-        RenderingBundle, SpriteRender, SpriteSheet, SpriteSheetFormat, Transparent,
+        RenderDeferredPbr3D, RenderFlat2D, RenderFlat3D, RenderPbr3D, RenderShaded3D, RenderSkybox,
+        RenderToWindow, RenderingBundle, SpriteRender, SpriteSheet, SpriteSheetFormat, Transparent,
     },
     utils::{
         application_root_dir,
@@ -118,6 +118,7 @@ enum RenderMode {
     Flat,
     Shaded,
     Pbr,
+    Deferred,
 }
 
 impl Default for RenderMode {
@@ -361,7 +362,8 @@ impl SimpleState for Example {
                 *mode = match *mode {
                     RenderMode::Flat => RenderMode::Shaded,
                     RenderMode::Shaded => RenderMode::Pbr,
-                    RenderMode::Pbr => RenderMode::Flat,
+                    RenderMode::Pbr => RenderMode::Deferred,
+                    RenderMode::Deferred => RenderMode::Flat,
                 };
                 Trans::None
             } else {
@@ -675,6 +677,7 @@ fn main() -> amethyst::Result<()> {
 #[derive(Default, Debug)]
 struct RenderSwitchable3D {
     pbr: RenderPbr3D,
+    deferred: RenderDeferredPbr3D,
     shaded: RenderShaded3D,
     flat: RenderFlat3D,
     last_mode: RenderMode,
@@ -704,6 +707,7 @@ impl RenderPlugin<DefaultBackend> for RenderSwitchable3D {
         self.last_mode = mode;
         match mode {
             RenderMode::Pbr => self.pbr.on_plan(plan, factory, world),
+            RenderMode::Deferred => self.deferred.on_plan(plan, factory, world),
             RenderMode::Shaded => self.shaded.on_plan(plan, factory, world),
             RenderMode::Flat => self.flat.on_plan(plan, factory, world),
         }